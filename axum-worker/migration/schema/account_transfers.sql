@@ -0,0 +1,15 @@
+-- Create account transfers table (deposits and withdrawals recorded against each user)
+CREATE TABLE IF NOT EXISTS account_transfers (
+    id UUID PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    exchange VARCHAR(32) NOT NULL,
+    asset VARCHAR(20) NOT NULL,
+    kind VARCHAR(16) NOT NULL, -- DEPOSIT or WITHDRAWAL
+    amount NUMERIC(36, 18) NOT NULL, -- Always positive; kind gives the direction
+    note TEXT,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL -- When it reaches the user's balances and snapshot flows
+);
+
+-- Index for building a user's balances on one exchange
+CREATE INDEX idx_account_transfers_user_exchange_created_at ON account_transfers(user_id, exchange, created_at);
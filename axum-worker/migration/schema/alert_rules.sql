@@ -0,0 +1,27 @@
+-- Create alert rules table (price, spread and fill alerts users set up)
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL,
+    name VARCHAR(100),
+    condition VARCHAR(20) NOT NULL, -- PRICE_ABOVE, PRICE_BELOW, PERCENT_CHANGE, SPREAD_ABOVE or ORDER_FILLED
+    exchange VARCHAR(32), -- Optional filter for ORDER_FILLED rules
+    base VARCHAR(20),
+    quote VARCHAR(20),
+    threshold NUMERIC(36, 18), -- Price, or a percentage for PERCENT_CHANGE and SPREAD_ABOVE
+    window_minutes INTEGER, -- PERCENT_CHANGE only
+    repeat BOOLEAN NOT NULL DEFAULT FALSE, -- Fire again each time the condition recurs
+    status VARCHAR(16) NOT NULL, -- ACTIVE, TRIGGERED or DISABLED
+    armed BOOLEAN NOT NULL DEFAULT TRUE, -- Cleared when the rule fires, set again once the condition clears
+    last_value NUMERIC(36, 18), -- Value last observed for the condition
+    last_checked_at TIMESTAMPTZ,
+    triggered_at TIMESTAMPTZ,
+    trigger_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for listing a user's alerts
+CREATE INDEX idx_alert_rules_user_id ON alert_rules(user_id);
+
+-- Index for the evaluator's scan of active rules
+CREATE INDEX idx_alert_rules_status ON alert_rules(status);
@@ -0,0 +1,19 @@
+-- Create candles table (OHLCV bars per instrument and interval)
+CREATE TABLE IF NOT EXISTS candles (
+    instrument_id VARCHAR(100) NOT NULL, -- exchange:base:quote, lowercase
+    exchange VARCHAR(32) NOT NULL,
+    base VARCHAR(20) NOT NULL,
+    quote VARCHAR(20) NOT NULL,
+    period VARCHAR(4) NOT NULL, -- 1m, 5m, 15m, 1h, 4h or 1d
+    open_time TIMESTAMPTZ NOT NULL,
+    open NUMERIC(36, 18) NOT NULL,
+    high NUMERIC(36, 18) NOT NULL,
+    low NUMERIC(36, 18) NOT NULL,
+    close NUMERIC(36, 18) NOT NULL,
+    volume NUMERIC(36, 18) NOT NULL,
+    trade_count INTEGER, -- NULL when the exchange's klines don't report it
+    source VARCHAR(16) NOT NULL, -- TRADES or KLINES; klines replace trade-built candles
+    last_trade_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL, -- A candle updated after its bucket closed is final
+    PRIMARY KEY (instrument_id, period, open_time)
+);
@@ -0,0 +1,14 @@
+-- Create notifications table (each user's in-app notification feed)
+CREATE TABLE IF NOT EXISTS notifications (
+    id VARCHAR(36) PRIMARY KEY, -- Also the id of the webhook delivery
+    user_id VARCHAR(36) NOT NULL,
+    alert_id VARCHAR(36), -- Alert rule that raised it
+    title VARCHAR(200) NOT NULL,
+    message TEXT NOT NULL,
+    data TEXT NOT NULL, -- JSON event details, as sent to the webhook
+    read_at TIMESTAMPTZ, -- NULL until the user reads it
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for reading a user's feed newest first
+CREATE INDEX idx_notifications_user_id_created_at ON notifications(user_id, created_at);
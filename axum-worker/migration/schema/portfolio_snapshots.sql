@@ -0,0 +1,14 @@
+-- Create portfolio snapshots table (periodic valuations for the equity curve)
+CREATE TABLE IF NOT EXISTS portfolio_snapshots (
+    id UUID PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    exchange VARCHAR(32) NOT NULL, -- 'all' for the aggregate across exchanges
+    total_value_usd NUMERIC(36, 18) NOT NULL,
+    realized_pnl_usd NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    unrealized_pnl_usd NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    net_flow_usd NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    taken_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for loading a user's curve on one exchange over a time range
+CREATE INDEX idx_portfolio_snapshots_user_exchange_taken_at ON portfolio_snapshots(user_id, exchange, taken_at);
@@ -0,0 +1,21 @@
+-- Create scheduled job runs table (history of cron and manual runs)
+CREATE TABLE IF NOT EXISTS scheduled_job_runs (
+    id UUID PRIMARY KEY,
+    job_name VARCHAR(64) NOT NULL,
+    trigger VARCHAR(16) NOT NULL, -- CRON or MANUAL
+    triggered_by VARCHAR(100), -- Admin username for manual runs
+    status VARCHAR(16) NOT NULL, -- RUNNING, SUCCEEDED, FAILED or SKIPPED
+    message TEXT,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ
+);
+
+-- Index for the latest runs of each job
+CREATE INDEX idx_scheduled_job_runs_job_name_started_at ON scheduled_job_runs(job_name, started_at);
+
+-- Create scheduled job locks table (one lease per running job)
+CREATE TABLE IF NOT EXISTS scheduled_job_locks (
+    job_name VARCHAR(64) PRIMARY KEY,
+    run_id UUID NOT NULL,
+    locked_until TIMESTAMPTZ NOT NULL
+);
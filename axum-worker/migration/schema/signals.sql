@@ -0,0 +1,31 @@
+-- Create signal hooks table (one inbound signal webhook per user)
+CREATE TABLE IF NOT EXISTS signal_hooks (
+    id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL UNIQUE,
+    token VARCHAR(80) NOT NULL UNIQUE, -- Secret part of the hook URL
+    risk_limits TEXT NOT NULL, -- JSON: max_order_notional, max_position, max_open_orders
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Create trading signals table (the log of signals received and the orders they placed)
+CREATE TABLE IF NOT EXISTS trading_signals (
+    id VARCHAR(36) PRIMARY KEY,
+    hook_id VARCHAR(36) NOT NULL,
+    user_id VARCHAR(36) NOT NULL,
+    payload TEXT NOT NULL, -- Body as received
+    status VARCHAR(16) NOT NULL, -- PLACED, REJECTED or FAILED
+    error TEXT, -- Why the signal was rejected or its order failed
+    exchange VARCHAR(32),
+    symbol VARCHAR(32),
+    side VARCHAR(8),
+    order_type VARCHAR(32),
+    quantity NUMERIC(36, 18), -- After sizing a percent of balance signal
+    order_id VARCHAR(36), -- Order placed for the signal
+    order_status VARCHAR(32),
+    received_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for reading a user's signal log newest first
+CREATE INDEX idx_trading_signals_user_id_received_at ON trading_signals(user_id, received_at);
@@ -0,0 +1,17 @@
+-- Create strategy instances table (strategies users run live or on paper)
+CREATE TABLE IF NOT EXISTS strategy_instances (
+    id VARCHAR(36) PRIMARY KEY, -- Also names the instance's StrategyRunner Durable Object
+    user_id VARCHAR(36) NOT NULL,
+    strategy VARCHAR(50) NOT NULL, -- e.g. sma_crossover
+    parameters TEXT NOT NULL, -- JSON, including the defaults filled in
+    exchange VARCHAR(32) NOT NULL,
+    base VARCHAR(20) NOT NULL,
+    quote VARCHAR(20) NOT NULL,
+    mode VARCHAR(8) NOT NULL, -- PAPER or LIVE
+    status VARCHAR(16) NOT NULL, -- RUNNING, PAUSED, STOPPED or HALTED
+    status_reason TEXT, -- Why a halted strategy was stopped
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_strategy_instances_user_id ON strategy_instances(user_id);
@@ -0,0 +1,24 @@
+-- Create trade executions table (individual fills of trading orders)
+CREATE TABLE IF NOT EXISTS trade_executions (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL,
+    user_id VARCHAR(100) NOT NULL,
+    exchange VARCHAR(32) NOT NULL,
+    symbol VARCHAR(32) NOT NULL,
+    base_asset VARCHAR(16) NOT NULL,
+    quote_asset VARCHAR(16) NOT NULL,
+    exchange_trade_id VARCHAR(100) NOT NULL,
+    side VARCHAR(8) NOT NULL,
+    quantity NUMERIC(36, 18) NOT NULL,
+    price NUMERIC(36, 18) NOT NULL,
+    commission NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    commission_asset VARCHAR(16) NOT NULL,
+    is_maker BOOLEAN NOT NULL DEFAULT FALSE,
+    executed_at TIMESTAMPTZ NOT NULL
+);
+
+-- Each exchange trade is recorded once
+CREATE UNIQUE INDEX idx_trade_executions_exchange_trade_id ON trade_executions(exchange, exchange_trade_id);
+
+-- Index for replaying a user's executions in order
+CREATE INDEX idx_trade_executions_user_exchange_executed_at ON trade_executions(user_id, exchange, executed_at);
@@ -0,0 +1,18 @@
+-- Create order groups table (OCO pairs and brackets)
+CREATE TABLE IF NOT EXISTS trading_order_groups (
+    id UUID PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    exchange VARCHAR(32) NOT NULL,
+    symbol VARCHAR(32) NOT NULL,
+    group_type VARCHAR(16) NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    quantity NUMERIC(36, 18) NOT NULL,
+    parent_order_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_trading_order_groups_user_id ON trading_order_groups(user_id);
+
+CREATE TRIGGER update_trading_order_groups_updated_at BEFORE UPDATE
+    ON trading_order_groups FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,36 @@
+-- Create trading orders table
+CREATE TABLE IF NOT EXISTS trading_orders (
+    id UUID PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    exchange VARCHAR(32) NOT NULL,
+    symbol VARCHAR(32) NOT NULL,
+    base_asset VARCHAR(16) NOT NULL,
+    quote_asset VARCHAR(16) NOT NULL,
+    exchange_order_id VARCHAR(100),
+    side VARCHAR(8) NOT NULL,
+    order_type VARCHAR(32) NOT NULL,
+    status VARCHAR(32) NOT NULL,
+    quantity NUMERIC(36, 18) NOT NULL,
+    price NUMERIC(36, 18),
+    stop_price NUMERIC(36, 18),
+    trigger_by VARCHAR(8) NOT NULL DEFAULT 'LAST',
+    filled_quantity NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    average_price NUMERIC(36, 18),
+    commission NUMERIC(36, 18) NOT NULL DEFAULT 0,
+    commission_asset VARCHAR(16) NOT NULL,
+    group_id UUID,
+    parent_order_id UUID,
+    client_order_id VARCHAR(64),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    executed_at TIMESTAMPTZ
+);
+
+-- Index for the trigger monitor's pending-order lookups
+CREATE INDEX idx_trading_orders_exchange_symbol_status ON trading_orders(exchange, symbol, status);
+CREATE INDEX idx_trading_orders_user_id ON trading_orders(user_id);
+CREATE INDEX idx_trading_orders_group_id ON trading_orders(group_id);
+CREATE UNIQUE INDEX idx_trading_orders_user_client_order_id ON trading_orders(user_id, client_order_id);
+
+CREATE TRIGGER update_trading_orders_updated_at BEFORE UPDATE
+    ON trading_orders FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,38 @@
+-- Create webhook endpoints table (URLs users registered for order and account events)
+CREATE TABLE IF NOT EXISTS webhook_endpoints (
+    id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL,
+    url TEXT NOT NULL, -- https only
+    secret VARCHAR(80) NOT NULL, -- Signs every delivery with HMAC-SHA256
+    events TEXT NOT NULL, -- Comma-separated, e.g. order.filled,balance.changed
+    description VARCHAR(200),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for finding a user's endpoints when an event happens
+CREATE INDEX idx_webhook_endpoints_user_id ON webhook_endpoints(user_id);
+
+-- Create webhook deliveries table (the delivery log)
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id VARCHAR(36) PRIMARY KEY, -- Sent as X-Webhook-Id, the same on every attempt
+    endpoint_id VARCHAR(36) NOT NULL,
+    user_id VARCHAR(36) NOT NULL,
+    event VARCHAR(32) NOT NULL, -- e.g. order.filled, or webhook.test
+    payload TEXT NOT NULL, -- JSON body sent on every attempt
+    status VARCHAR(16) NOT NULL, -- PENDING, RETRYING, SUCCEEDED or FAILED
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER, -- HTTP status of the last answer
+    error TEXT, -- Why the last attempt failed
+    next_attempt_at TIMESTAMPTZ, -- When a retrying delivery is tried again
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Index for reading a user's delivery log newest first
+CREATE INDEX idx_webhook_deliveries_user_id_created_at ON webhook_deliveries(user_id, created_at);
+
+-- Index for one endpoint's deliveries
+CREATE INDEX idx_webhook_deliveries_endpoint_id ON webhook_deliveries(endpoint_id);
@@ -1,6 +1,18 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20250729_194734_create_users_table;
+mod m20261018_090000_create_trading_orders_table;
+mod m20261018_100000_create_trading_order_groups_table;
+mod m20261018_110000_add_client_order_id_to_trading_orders;
+mod m20261018_120000_create_trade_executions_table;
+mod m20261018_130000_create_portfolio_snapshots_table;
+mod m20261018_140000_create_scheduled_job_tables;
+mod m20261018_150000_create_candles_table;
+mod m20261018_160000_create_strategy_instances_table;
+mod m20261018_170000_create_alert_tables;
+mod m20261018_180000_create_webhook_tables;
+mod m20261018_190000_create_signal_tables;
+mod m20261018_200000_create_account_transfers_table;
 
 pub struct Migrator;
 
@@ -9,6 +21,18 @@ impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
             Box::new(m20250729_194734_create_users_table::Migration),
+            Box::new(m20261018_090000_create_trading_orders_table::Migration),
+            Box::new(m20261018_100000_create_trading_order_groups_table::Migration),
+            Box::new(m20261018_110000_add_client_order_id_to_trading_orders::Migration),
+            Box::new(m20261018_120000_create_trade_executions_table::Migration),
+            Box::new(m20261018_130000_create_portfolio_snapshots_table::Migration),
+            Box::new(m20261018_140000_create_scheduled_job_tables::Migration),
+            Box::new(m20261018_150000_create_candles_table::Migration),
+            Box::new(m20261018_160000_create_strategy_instances_table::Migration),
+            Box::new(m20261018_170000_create_alert_tables::Migration),
+            Box::new(m20261018_180000_create_webhook_tables::Migration),
+            Box::new(m20261018_190000_create_signal_tables::Migration),
+            Box::new(m20261018_200000_create_account_transfers_table::Migration),
         ]
     }
 }
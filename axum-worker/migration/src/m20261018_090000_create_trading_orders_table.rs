@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create trading orders table
+        manager
+            .create_table(
+                Table::create()
+                    .table(TradingOrders::Table)
+                    .if_not_exists()
+                    .col(uuid(TradingOrders::Id).primary_key())
+                    .col(string_len(TradingOrders::UserId, 100).not_null())
+                    .col(string_len(TradingOrders::Exchange, 32).not_null())
+                    .col(string_len(TradingOrders::Symbol, 32).not_null())
+                    .col(string_len(TradingOrders::BaseAsset, 16).not_null())
+                    .col(string_len(TradingOrders::QuoteAsset, 16).not_null())
+                    .col(string_len_null(TradingOrders::ExchangeOrderId, 100))
+                    .col(string_len(TradingOrders::Side, 8).not_null())
+                    .col(string_len(TradingOrders::OrderType, 32).not_null())
+                    .col(string_len(TradingOrders::Status, 32).not_null())
+                    .col(decimal_len(TradingOrders::Quantity, 36, 18).not_null())
+                    .col(decimal_len_null(TradingOrders::Price, 36, 18))
+                    .col(decimal_len_null(TradingOrders::StopPrice, 36, 18))
+                    .col(string_len(TradingOrders::TriggerBy, 8).not_null().default("LAST"))
+                    .col(decimal_len(TradingOrders::FilledQuantity, 36, 18).not_null().default(0))
+                    .col(decimal_len_null(TradingOrders::AveragePrice, 36, 18))
+                    .col(decimal_len(TradingOrders::Commission, 36, 18).not_null().default(0))
+                    .col(string_len(TradingOrders::CommissionAsset, 16).not_null())
+                    .col(timestamp_with_time_zone(TradingOrders::CreatedAt).not_null().default(Expr::current_timestamp()))
+                    .col(timestamp_with_time_zone(TradingOrders::UpdatedAt).not_null().default(Expr::current_timestamp()))
+                    .col(timestamp_with_time_zone_null(TradingOrders::ExecutedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for the trigger monitor's pending-order lookups
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trading_orders_exchange_symbol_status")
+                    .table(TradingOrders::Table)
+                    .col(TradingOrders::Exchange)
+                    .col(TradingOrders::Symbol)
+                    .col(TradingOrders::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trading_orders_user_id")
+                    .table(TradingOrders::Table)
+                    .col(TradingOrders::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the trading orders table
+        manager
+            .drop_table(Table::drop().table(TradingOrders::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TradingOrders {
+    Table,
+    Id,
+    UserId,
+    Exchange,
+    Symbol,
+    BaseAsset,
+    QuoteAsset,
+    ExchangeOrderId,
+    Side,
+    OrderType,
+    Status,
+    Quantity,
+    Price,
+    StopPrice,
+    TriggerBy,
+    FilledQuantity,
+    AveragePrice,
+    Commission,
+    CommissionAsset,
+    CreatedAt,
+    UpdatedAt,
+    ExecutedAt,
+}
@@ -0,0 +1,105 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create order groups table (OCO pairs and brackets)
+        manager
+            .create_table(
+                Table::create()
+                    .table(TradingOrderGroups::Table)
+                    .if_not_exists()
+                    .col(uuid(TradingOrderGroups::Id).primary_key())
+                    .col(string_len(TradingOrderGroups::UserId, 100).not_null())
+                    .col(string_len(TradingOrderGroups::Exchange, 32).not_null())
+                    .col(string_len(TradingOrderGroups::Symbol, 32).not_null())
+                    .col(string_len(TradingOrderGroups::GroupType, 16).not_null())
+                    .col(string_len(TradingOrderGroups::Status, 16).not_null())
+                    .col(decimal_len(TradingOrderGroups::Quantity, 36, 18).not_null())
+                    .col(uuid_null(TradingOrderGroups::ParentOrderId))
+                    .col(timestamp_with_time_zone(TradingOrderGroups::CreatedAt).not_null().default(Expr::current_timestamp()))
+                    .col(timestamp_with_time_zone(TradingOrderGroups::UpdatedAt).not_null().default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Link orders to their group and, for bracket exits, their entry order
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TradingOrders::Table)
+                    .add_column(uuid_null(TradingOrders::GroupId))
+                    .add_column(uuid_null(TradingOrders::ParentOrderId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trading_orders_group_id")
+                    .table(TradingOrders::Table)
+                    .col(TradingOrders::GroupId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trading_order_groups_user_id")
+                    .table(TradingOrderGroups::Table)
+                    .col(TradingOrderGroups::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_trading_orders_group_id").table(TradingOrders::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TradingOrders::Table)
+                    .drop_column(TradingOrders::GroupId)
+                    .drop_column(TradingOrders::ParentOrderId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Drop the order groups table
+        manager
+            .drop_table(Table::drop().table(TradingOrderGroups::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TradingOrderGroups {
+    Table,
+    Id,
+    UserId,
+    Exchange,
+    Symbol,
+    GroupType,
+    Status,
+    Quantity,
+    ParentOrderId,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TradingOrders {
+    Table,
+    GroupId,
+    ParentOrderId,
+}
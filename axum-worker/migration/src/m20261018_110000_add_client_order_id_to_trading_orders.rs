@@ -0,0 +1,56 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Client-supplied idempotency key for order placement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TradingOrders::Table)
+                    .add_column(string_len_null(TradingOrders::ClientOrderId, 64))
+                    .to_owned(),
+            )
+            .await?;
+
+        // One order per client order id per user; NULLs are not deduplicated
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trading_orders_user_client_order_id")
+                    .table(TradingOrders::Table)
+                    .col(TradingOrders::UserId)
+                    .col(TradingOrders::ClientOrderId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_trading_orders_user_client_order_id").table(TradingOrders::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TradingOrders::Table)
+                    .drop_column(TradingOrders::ClientOrderId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TradingOrders {
+    Table,
+    UserId,
+    ClientOrderId,
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create trade executions table (individual fills of trading orders)
+        manager
+            .create_table(
+                Table::create()
+                    .table(TradeExecutions::Table)
+                    .if_not_exists()
+                    .col(uuid(TradeExecutions::Id).primary_key())
+                    .col(uuid(TradeExecutions::OrderId).not_null())
+                    .col(string_len(TradeExecutions::UserId, 100).not_null())
+                    .col(string_len(TradeExecutions::Exchange, 32).not_null())
+                    .col(string_len(TradeExecutions::Symbol, 32).not_null())
+                    .col(string_len(TradeExecutions::BaseAsset, 16).not_null())
+                    .col(string_len(TradeExecutions::QuoteAsset, 16).not_null())
+                    .col(string_len(TradeExecutions::ExchangeTradeId, 100).not_null())
+                    .col(string_len(TradeExecutions::Side, 8).not_null())
+                    .col(decimal_len(TradeExecutions::Quantity, 36, 18).not_null())
+                    .col(decimal_len(TradeExecutions::Price, 36, 18).not_null())
+                    .col(decimal_len(TradeExecutions::Commission, 36, 18).not_null().default(0))
+                    .col(string_len(TradeExecutions::CommissionAsset, 16).not_null())
+                    .col(boolean(TradeExecutions::IsMaker).not_null().default(false))
+                    .col(timestamp_with_time_zone(TradeExecutions::ExecutedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Each exchange trade is recorded once
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trade_executions_exchange_trade_id")
+                    .table(TradeExecutions::Table)
+                    .col(TradeExecutions::Exchange)
+                    .col(TradeExecutions::ExchangeTradeId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for replaying a user's executions in order
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trade_executions_user_exchange_executed_at")
+                    .table(TradeExecutions::Table)
+                    .col(TradeExecutions::UserId)
+                    .col(TradeExecutions::Exchange)
+                    .col(TradeExecutions::ExecutedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the trade executions table
+        manager
+            .drop_table(Table::drop().table(TradeExecutions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TradeExecutions {
+    Table,
+    Id,
+    OrderId,
+    UserId,
+    Exchange,
+    Symbol,
+    BaseAsset,
+    QuoteAsset,
+    ExchangeTradeId,
+    Side,
+    Quantity,
+    Price,
+    Commission,
+    CommissionAsset,
+    IsMaker,
+    ExecutedAt,
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create portfolio snapshots table (periodic valuations for the equity curve)
+        manager
+            .create_table(
+                Table::create()
+                    .table(PortfolioSnapshots::Table)
+                    .if_not_exists()
+                    .col(uuid(PortfolioSnapshots::Id).primary_key())
+                    .col(string_len(PortfolioSnapshots::UserId, 100).not_null())
+                    .col(string_len(PortfolioSnapshots::Exchange, 32).not_null())
+                    .col(decimal_len(PortfolioSnapshots::TotalValueUsd, 36, 18).not_null())
+                    .col(decimal_len(PortfolioSnapshots::RealizedPnlUsd, 36, 18).not_null().default(0))
+                    .col(decimal_len(PortfolioSnapshots::UnrealizedPnlUsd, 36, 18).not_null().default(0))
+                    .col(decimal_len(PortfolioSnapshots::NetFlowUsd, 36, 18).not_null().default(0))
+                    .col(timestamp_with_time_zone(PortfolioSnapshots::TakenAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for loading a user's curve on one exchange over a time range
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_portfolio_snapshots_user_exchange_taken_at")
+                    .table(PortfolioSnapshots::Table)
+                    .col(PortfolioSnapshots::UserId)
+                    .col(PortfolioSnapshots::Exchange)
+                    .col(PortfolioSnapshots::TakenAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the portfolio snapshots table
+        manager
+            .drop_table(Table::drop().table(PortfolioSnapshots::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PortfolioSnapshots {
+    Table,
+    Id,
+    UserId,
+    Exchange,
+    TotalValueUsd,
+    RealizedPnlUsd,
+    UnrealizedPnlUsd,
+    NetFlowUsd,
+    TakenAt,
+}
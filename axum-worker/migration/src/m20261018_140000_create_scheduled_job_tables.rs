@@ -0,0 +1,106 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create scheduled job runs table (history of cron and manual runs)
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledJobRuns::Table)
+                    .if_not_exists()
+                    .col(uuid(ScheduledJobRuns::Id).primary_key())
+                    .col(string_len(ScheduledJobRuns::JobName, 64).not_null())
+                    .col(string_len(ScheduledJobRuns::Trigger, 16).not_null())
+                    .col(string_len_null(ScheduledJobRuns::TriggeredBy, 100))
+                    .col(string_len(ScheduledJobRuns::Status, 16).not_null())
+                    .col(text_null(ScheduledJobRuns::Message))
+                    .col(timestamp_with_time_zone(ScheduledJobRuns::StartedAt).not_null())
+                    .col(timestamp_with_time_zone_null(ScheduledJobRuns::FinishedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for the latest runs of each job
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_scheduled_job_runs_job_name_started_at")
+                    .table(ScheduledJobRuns::Table)
+                    .col(ScheduledJobRuns::JobName)
+                    .col(ScheduledJobRuns::StartedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create scheduled job locks table (one lease per running job)
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledJobLocks::Table)
+                    .if_not_exists()
+                    .col(string_len(ScheduledJobLocks::JobName, 64).primary_key())
+                    .col(uuid(ScheduledJobLocks::RunId).not_null())
+                    .col(timestamp_with_time_zone(ScheduledJobLocks::LockedUntil).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Role of each user, as Role::to_i32 (1 admin, 2 user, 3 system)
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(small_integer(Users::Role).not_null().default(2))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(Table::alter().table(Users::Table).drop_column(Users::Role).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ScheduledJobLocks::Table).to_owned())
+            .await?;
+
+        // Drop the scheduled job runs table
+        manager
+            .drop_table(Table::drop().table(ScheduledJobRuns::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledJobRuns {
+    Table,
+    Id,
+    JobName,
+    Trigger,
+    TriggeredBy,
+    Status,
+    Message,
+    StartedAt,
+    FinishedAt,
+}
+
+#[derive(DeriveIden)]
+enum ScheduledJobLocks {
+    Table,
+    JobName,
+    RunId,
+    LockedUntil,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Role,
+}
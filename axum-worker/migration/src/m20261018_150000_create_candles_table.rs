@@ -0,0 +1,69 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create candles table (OHLCV bars per instrument and interval)
+        manager
+            .create_table(
+                Table::create()
+                    .table(Candles::Table)
+                    .if_not_exists()
+                    .col(string_len(Candles::InstrumentId, 100).not_null())
+                    .col(string_len(Candles::Exchange, 32).not_null())
+                    .col(string_len(Candles::Base, 20).not_null())
+                    .col(string_len(Candles::Quote, 20).not_null())
+                    .col(string_len(Candles::Period, 4).not_null())
+                    .col(timestamp_with_time_zone(Candles::OpenTime).not_null())
+                    .col(decimal_len(Candles::Open, 36, 18).not_null())
+                    .col(decimal_len(Candles::High, 36, 18).not_null())
+                    .col(decimal_len(Candles::Low, 36, 18).not_null())
+                    .col(decimal_len(Candles::Close, 36, 18).not_null())
+                    .col(decimal_len(Candles::Volume, 36, 18).not_null())
+                    .col(integer_null(Candles::TradeCount))
+                    .col(string_len(Candles::Source, 16).not_null())
+                    .col(timestamp_with_time_zone(Candles::LastTradeAt).not_null())
+                    .col(timestamp_with_time_zone(Candles::UpdatedAt).not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(Candles::InstrumentId)
+                            .col(Candles::Period)
+                            .col(Candles::OpenTime),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the candles table
+        manager
+            .drop_table(Table::drop().table(Candles::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Candles {
+    Table,
+    InstrumentId,
+    Exchange,
+    Base,
+    Quote,
+    Period,
+    OpenTime,
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    TradeCount,
+    Source,
+    LastTradeAt,
+    UpdatedAt,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create strategy instances table (strategies users run live or on paper)
+        manager
+            .create_table(
+                Table::create()
+                    .table(StrategyInstances::Table)
+                    .if_not_exists()
+                    .col(string_len(StrategyInstances::Id, 36).primary_key())
+                    .col(string_len(StrategyInstances::UserId, 36).not_null())
+                    .col(string_len(StrategyInstances::Strategy, 50).not_null())
+                    .col(text(StrategyInstances::Parameters).not_null())
+                    .col(string_len(StrategyInstances::Exchange, 32).not_null())
+                    .col(string_len(StrategyInstances::Base, 20).not_null())
+                    .col(string_len(StrategyInstances::Quote, 20).not_null())
+                    .col(string_len(StrategyInstances::Mode, 8).not_null())
+                    .col(string_len(StrategyInstances::Status, 16).not_null())
+                    .col(text_null(StrategyInstances::StatusReason))
+                    .col(timestamp_with_time_zone(StrategyInstances::CreatedAt).not_null())
+                    .col(timestamp_with_time_zone(StrategyInstances::UpdatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for listing a user's strategies
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_strategy_instances_user_id")
+                    .table(StrategyInstances::Table)
+                    .col(StrategyInstances::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the strategy instances table
+        manager
+            .drop_table(Table::drop().table(StrategyInstances::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StrategyInstances {
+    Table,
+    Id,
+    UserId,
+    Strategy,
+    Parameters,
+    Exchange,
+    Base,
+    Quote,
+    Mode,
+    Status,
+    StatusReason,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,138 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create alert rules table (price, spread and fill alerts users set up)
+        manager
+            .create_table(
+                Table::create()
+                    .table(AlertRules::Table)
+                    .if_not_exists()
+                    .col(string_len(AlertRules::Id, 36).primary_key())
+                    .col(string_len(AlertRules::UserId, 36).not_null())
+                    .col(string_len_null(AlertRules::Name, 100))
+                    .col(string_len(AlertRules::Condition, 20).not_null())
+                    .col(string_len_null(AlertRules::Exchange, 32))
+                    .col(string_len_null(AlertRules::Base, 20))
+                    .col(string_len_null(AlertRules::Quote, 20))
+                    .col(decimal_len_null(AlertRules::Threshold, 36, 18))
+                    .col(integer_null(AlertRules::WindowMinutes))
+                    .col(boolean(AlertRules::Repeat).not_null().default(false))
+                    .col(string_len(AlertRules::Status, 16).not_null())
+                    .col(boolean(AlertRules::Armed).not_null().default(true))
+                    .col(decimal_len_null(AlertRules::LastValue, 36, 18))
+                    .col(timestamp_with_time_zone_null(AlertRules::LastCheckedAt))
+                    .col(timestamp_with_time_zone_null(AlertRules::TriggeredAt))
+                    .col(integer(AlertRules::TriggerCount).not_null().default(0))
+                    .col(timestamp_with_time_zone(AlertRules::CreatedAt).not_null())
+                    .col(timestamp_with_time_zone(AlertRules::UpdatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for listing a user's alerts
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_alert_rules_user_id")
+                    .table(AlertRules::Table)
+                    .col(AlertRules::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for the evaluator's scan of active rules
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_alert_rules_status")
+                    .table(AlertRules::Table)
+                    .col(AlertRules::Status)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create notifications table (each user's in-app notification feed)
+        manager
+            .create_table(
+                Table::create()
+                    .table(Notifications::Table)
+                    .if_not_exists()
+                    .col(string_len(Notifications::Id, 36).primary_key())
+                    .col(string_len(Notifications::UserId, 36).not_null())
+                    .col(string_len_null(Notifications::AlertId, 36))
+                    .col(string_len(Notifications::Title, 200).not_null())
+                    .col(text(Notifications::Message).not_null())
+                    .col(text(Notifications::Data).not_null())
+                    .col(timestamp_with_time_zone_null(Notifications::ReadAt))
+                    .col(timestamp_with_time_zone(Notifications::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for reading a user's feed newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_notifications_user_id_created_at")
+                    .table(Notifications::Table)
+                    .col(Notifications::UserId)
+                    .col(Notifications::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Notifications::Table).to_owned())
+            .await?;
+
+        // Drop the alert rules table
+        manager
+            .drop_table(Table::drop().table(AlertRules::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AlertRules {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Condition,
+    Exchange,
+    Base,
+    Quote,
+    Threshold,
+    WindowMinutes,
+    Repeat,
+    Status,
+    Armed,
+    LastValue,
+    LastCheckedAt,
+    TriggeredAt,
+    TriggerCount,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Notifications {
+    Table,
+    Id,
+    UserId,
+    AlertId,
+    Title,
+    Message,
+    Data,
+    ReadAt,
+    CreatedAt,
+}
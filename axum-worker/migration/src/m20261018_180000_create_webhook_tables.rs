@@ -0,0 +1,130 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create webhook endpoints table (URLs users registered for order and account events)
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookEndpoints::Table)
+                    .if_not_exists()
+                    .col(string_len(WebhookEndpoints::Id, 36).primary_key())
+                    .col(string_len(WebhookEndpoints::UserId, 36).not_null())
+                    .col(text(WebhookEndpoints::Url).not_null())
+                    .col(string_len(WebhookEndpoints::Secret, 80).not_null())
+                    .col(text(WebhookEndpoints::Events).not_null())
+                    .col(string_len_null(WebhookEndpoints::Description, 200))
+                    .col(boolean(WebhookEndpoints::IsActive).not_null().default(true))
+                    .col(timestamp_with_time_zone(WebhookEndpoints::CreatedAt).not_null())
+                    .col(timestamp_with_time_zone(WebhookEndpoints::UpdatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for finding a user's endpoints when an event happens
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_endpoints_user_id")
+                    .table(WebhookEndpoints::Table)
+                    .col(WebhookEndpoints::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create webhook deliveries table (the delivery log)
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDeliveries::Table)
+                    .if_not_exists()
+                    .col(string_len(WebhookDeliveries::Id, 36).primary_key())
+                    .col(string_len(WebhookDeliveries::EndpointId, 36).not_null())
+                    .col(string_len(WebhookDeliveries::UserId, 36).not_null())
+                    .col(string_len(WebhookDeliveries::Event, 32).not_null())
+                    .col(text(WebhookDeliveries::Payload).not_null())
+                    .col(string_len(WebhookDeliveries::Status, 16).not_null())
+                    .col(integer(WebhookDeliveries::Attempts).not_null().default(0))
+                    .col(integer_null(WebhookDeliveries::ResponseStatus))
+                    .col(text_null(WebhookDeliveries::Error))
+                    .col(timestamp_with_time_zone_null(WebhookDeliveries::NextAttemptAt))
+                    .col(timestamp_with_time_zone_null(WebhookDeliveries::DeliveredAt))
+                    .col(timestamp_with_time_zone(WebhookDeliveries::CreatedAt).not_null())
+                    .col(timestamp_with_time_zone(WebhookDeliveries::UpdatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for reading a user's delivery log newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_user_id_created_at")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::UserId)
+                    .col(WebhookDeliveries::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for one endpoint's deliveries
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_endpoint_id")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::EndpointId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookDeliveries::Table).to_owned())
+            .await?;
+
+        // Drop the webhook endpoints table
+        manager
+            .drop_table(Table::drop().table(WebhookEndpoints::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WebhookEndpoints {
+    Table,
+    Id,
+    UserId,
+    Url,
+    Secret,
+    Events,
+    Description,
+    IsActive,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WebhookDeliveries {
+    Table,
+    Id,
+    EndpointId,
+    UserId,
+    Event,
+    Payload,
+    Status,
+    Attempts,
+    ResponseStatus,
+    Error,
+    NextAttemptAt,
+    DeliveredAt,
+    CreatedAt,
+    UpdatedAt,
+}
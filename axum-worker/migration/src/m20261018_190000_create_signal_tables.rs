@@ -0,0 +1,106 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create signal hooks table (one inbound signal webhook per user)
+        manager
+            .create_table(
+                Table::create()
+                    .table(SignalHooks::Table)
+                    .if_not_exists()
+                    .col(string_len(SignalHooks::Id, 36).primary_key())
+                    .col(string_len(SignalHooks::UserId, 36).not_null().unique_key())
+                    .col(string_len(SignalHooks::Token, 80).not_null().unique_key())
+                    .col(text(SignalHooks::RiskLimits).not_null())
+                    .col(boolean(SignalHooks::IsActive).not_null().default(true))
+                    .col(timestamp_with_time_zone(SignalHooks::CreatedAt).not_null())
+                    .col(timestamp_with_time_zone(SignalHooks::UpdatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create trading signals table (the log of signals received and the orders they placed)
+        manager
+            .create_table(
+                Table::create()
+                    .table(TradingSignals::Table)
+                    .if_not_exists()
+                    .col(string_len(TradingSignals::Id, 36).primary_key())
+                    .col(string_len(TradingSignals::HookId, 36).not_null())
+                    .col(string_len(TradingSignals::UserId, 36).not_null())
+                    .col(text(TradingSignals::Payload).not_null())
+                    .col(string_len(TradingSignals::Status, 16).not_null())
+                    .col(text_null(TradingSignals::Error))
+                    .col(string_len_null(TradingSignals::Exchange, 32))
+                    .col(string_len_null(TradingSignals::Symbol, 32))
+                    .col(string_len_null(TradingSignals::Side, 8))
+                    .col(string_len_null(TradingSignals::OrderType, 32))
+                    .col(decimal_len_null(TradingSignals::Quantity, 36, 18))
+                    .col(string_len_null(TradingSignals::OrderId, 36))
+                    .col(string_len_null(TradingSignals::OrderStatus, 32))
+                    .col(timestamp_with_time_zone(TradingSignals::ReceivedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for reading a user's signal log newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_trading_signals_user_id_received_at")
+                    .table(TradingSignals::Table)
+                    .col(TradingSignals::UserId)
+                    .col(TradingSignals::ReceivedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TradingSignals::Table).to_owned())
+            .await?;
+
+        // Drop the signal hooks table
+        manager
+            .drop_table(Table::drop().table(SignalHooks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SignalHooks {
+    Table,
+    Id,
+    UserId,
+    Token,
+    RiskLimits,
+    IsActive,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TradingSignals {
+    Table,
+    Id,
+    HookId,
+    UserId,
+    Payload,
+    Status,
+    Error,
+    Exchange,
+    Symbol,
+    Side,
+    OrderType,
+    Quantity,
+    OrderId,
+    OrderStatus,
+    ReceivedAt,
+}
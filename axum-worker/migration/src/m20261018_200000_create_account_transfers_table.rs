@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create account transfers table (deposits and withdrawals recorded against each user)
+        manager
+            .create_table(
+                Table::create()
+                    .table(AccountTransfers::Table)
+                    .if_not_exists()
+                    .col(uuid(AccountTransfers::Id).primary_key())
+                    .col(string_len(AccountTransfers::UserId, 100).not_null())
+                    .col(string_len(AccountTransfers::Exchange, 32).not_null())
+                    .col(string_len(AccountTransfers::Asset, 20).not_null())
+                    .col(string_len(AccountTransfers::Kind, 16).not_null())
+                    .col(decimal_len(AccountTransfers::Amount, 36, 18).not_null())
+                    .col(text_null(AccountTransfers::Note))
+                    .col(timestamp_with_time_zone(AccountTransfers::OccurredAt).not_null())
+                    .col(timestamp_with_time_zone(AccountTransfers::CreatedAt).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Index for building a user's balances on one exchange
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_account_transfers_user_exchange_created_at")
+                    .table(AccountTransfers::Table)
+                    .col(AccountTransfers::UserId)
+                    .col(AccountTransfers::Exchange)
+                    .col(AccountTransfers::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop the account transfers table
+        manager
+            .drop_table(Table::drop().table(AccountTransfers::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AccountTransfers {
+    Table,
+    Id,
+    UserId,
+    Exchange,
+    Asset,
+    Kind,
+    Amount,
+    Note,
+    OccurredAt,
+    CreatedAt,
+}
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use worker::{console_log, Delay, Method, ObjectNamespace, Request, RequestInit};
+
+use crate::clients::trading::Exchange;
+
+/// Binding of the `ExchangeGovernor` namespace in wrangler.toml
+pub const EXCHANGE_GOVERNOR_BINDING: &str = "EXCHANGE_GOVERNOR";
+
+/// Longest a call waits for room in a window before it's rejected
+pub(crate) const MAX_QUEUE_WAIT_MS: i64 = 2_000;
+
+/// Cool-down after a 429 or 418 that came without a Retry-After header
+const DEFAULT_BAN_SECONDS: i64 = 60;
+
+/// What an exchange limit counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LimitType {
+    /// Sum of the weights of the calls made
+    RequestWeight,
+    /// New orders placed
+    Orders,
+    /// Calls made, whatever they are
+    RawRequests,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LimitInterval {
+    Second,
+    Minute,
+    Day,
+}
+
+/// A limit an exchange publishes, e.g. 6000 request weight per minute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeLimit {
+    pub rate_limit_type: LimitType,
+    pub interval: LimitInterval,
+    pub interval_num: u32,
+    pub limit: u32,
+}
+
+/// An exchange limit with what's been used of it in the current window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitUsage {
+    pub limit: ExchangeLimit,
+    pub count: u32,
+}
+
+/// Kind of exchange call, which decides its weight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeCall {
+    Quote,
+    OrderBook { depth: u32 },
+    RecentTrades,
+    Klines,
+    MarkPrice,
+    Balances,
+    PlaceOrder,
+    /// Both orders of an exchange-held OCO pair
+    PlaceOco,
+    CancelOrder,
+    OrderStatus,
+    Fills,
+}
+
+/// What a call counts against each kind of limit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CallCost {
+    pub weight: u32,
+    pub orders: u32,
+}
+
+/// Answer to a request for room to make a call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "admission", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Admission {
+    /// Make the call now; it has been counted
+    Granted,
+    /// A window is full; ask again after this long
+    Wait { wait_ms: i64 },
+    /// The exchange told us to back off; nothing may be sent until then
+    Banned { wait_ms: i64 },
+}
+
+/// Usage and limit information an exchange returned with a response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseReport {
+    pub status: u16,
+    pub used: Vec<(LimitType, LimitInterval, u32, u32)>, // Type, interval, interval_num, count
+    pub retry_after_seconds: Option<u32>,
+}
+
+/// Usage of one limit in its current window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WindowUsage {
+    limit: ExchangeLimit,
+    window_start_ms: i64,
+    count: u32,
+}
+
+/// Usage of every limit of one exchange and API key, kept by the governor object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GovernorState {
+    windows: Vec<WindowUsage>,
+    banned_until_ms: Option<i64>,
+}
+
+/// Body of a request to the governor object for room to make a call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcquireRequest {
+    pub exchange: String,
+    pub cost: CallCost,
+}
+
+/// Body of a request to the governor object reporting a response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObserveRequest {
+    pub exchange: String,
+    pub report: ResponseReport,
+}
+
+impl LimitType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitType::RequestWeight => "REQUEST_WEIGHT",
+            LimitType::Orders => "ORDERS",
+            LimitType::RawRequests => "RAW_REQUESTS",
+        }
+    }
+}
+
+impl LimitInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitInterval::Second => "SECOND",
+            LimitInterval::Minute => "MINUTE",
+            LimitInterval::Day => "DAY",
+        }
+    }
+
+    fn millis(&self) -> i64 {
+        match self {
+            LimitInterval::Second => 1_000,
+            LimitInterval::Minute => 60_000,
+            LimitInterval::Day => 86_400_000,
+        }
+    }
+
+    /// Parse the suffix of a usage header, e.g. "1m" or "10s"
+    fn parse_suffix(suffix: &str) -> Option<(Self, u32)> {
+        let (num, unit) = suffix.split_at(suffix.len().checked_sub(1)?);
+        let interval = match unit.to_ascii_lowercase().as_str() {
+            "s" => LimitInterval::Second,
+            "m" => LimitInterval::Minute,
+            "d" => LimitInterval::Day,
+            _ => return None,
+        };
+        Some((interval, num.parse().ok()?))
+    }
+}
+
+impl ExchangeLimit {
+    const fn new(rate_limit_type: LimitType, interval: LimitInterval, interval_num: u32, limit: u32) -> Self {
+        Self { rate_limit_type, interval, interval_num, limit }
+    }
+
+    fn window_ms(&self) -> i64 {
+        self.interval.millis() * self.interval_num.max(1) as i64
+    }
+
+    /// Most we let ourselves use, a tenth short of the limit to leave room for
+    /// calls the governor doesn't see
+    fn usable(&self) -> u32 {
+        (self.limit - self.limit / 10).max(1)
+    }
+
+    fn cost(&self, cost: CallCost) -> u32 {
+        match self.rate_limit_type {
+            LimitType::RequestWeight => cost.weight,
+            LimitType::Orders => cost.orders,
+            LimitType::RawRequests => 1,
+        }
+    }
+
+    fn matches(&self, rate_limit_type: LimitType, interval: LimitInterval, interval_num: u32) -> bool {
+        self.rate_limit_type == rate_limit_type && self.interval == interval && self.interval_num == interval_num
+    }
+}
+
+/// Published REST limits of an exchange
+pub fn exchange_limits(exchange: Exchange) -> Vec<ExchangeLimit> {
+    use LimitInterval::*;
+    use LimitType::*;
+    match exchange {
+        Exchange::Binance => vec![
+            ExchangeLimit::new(RequestWeight, Minute, 1, 6000),
+            ExchangeLimit::new(Orders, Second, 10, 100),
+            ExchangeLimit::new(Orders, Day, 1, 200_000),
+        ],
+        Exchange::BinanceFuturesUsd => vec![
+            ExchangeLimit::new(RequestWeight, Minute, 1, 2400),
+            ExchangeLimit::new(Orders, Second, 10, 300),
+            ExchangeLimit::new(Orders, Minute, 1, 1200),
+        ],
+        Exchange::Coinbase => vec![
+            ExchangeLimit::new(RawRequests, Second, 1, 10),
+        ],
+        // Kraken's call counter holds 15 and decays by about one a second
+        Exchange::Kraken => vec![
+            ExchangeLimit::new(RawRequests, Second, 15, 15),
+            ExchangeLimit::new(Orders, Minute, 1, 60),
+        ],
+        Exchange::Okx => vec![
+            ExchangeLimit::new(RawRequests, Second, 2, 20),
+            ExchangeLimit::new(Orders, Second, 2, 60),
+        ],
+        Exchange::Bybit => vec![
+            ExchangeLimit::new(RawRequests, Second, 5, 600),
+            ExchangeLimit::new(Orders, Second, 1, 10),
+        ],
+    }
+}
+
+impl ExchangeCall {
+    /// Whether the call only reads, so sending it again does no harm
+    pub fn is_read(&self) -> bool {
+        !matches!(self, ExchangeCall::PlaceOrder | ExchangeCall::PlaceOco | ExchangeCall::CancelOrder)
+    }
+
+    /// Orders the call places, which count against order limits
+    pub fn order_count(&self) -> u32 {
+        match self {
+            ExchangeCall::PlaceOrder => 1,
+            ExchangeCall::PlaceOco => 2,
+            _ => 0,
+        }
+    }
+
+    /// Weight and order count of the call on an exchange. Weights are
+    /// Binance's; other exchanges only count calls.
+    pub fn cost(&self, exchange: Exchange) -> CallCost {
+        let weight = match (exchange, self) {
+            (Exchange::Binance, ExchangeCall::Quote) => 2,
+            (Exchange::Binance, ExchangeCall::OrderBook { depth }) => match depth {
+                0..=100 => 5,
+                101..=500 => 25,
+                501..=1000 => 50,
+                _ => 250,
+            },
+            (Exchange::Binance, ExchangeCall::RecentTrades) => 25,
+            (Exchange::Binance, ExchangeCall::Klines) => 2,
+            (Exchange::Binance, ExchangeCall::Balances | ExchangeCall::Fills) => 20,
+            (Exchange::Binance, ExchangeCall::OrderStatus) => 4,
+            (Exchange::BinanceFuturesUsd, ExchangeCall::OrderBook { depth }) => match depth {
+                0..=50 => 2,
+                51..=100 => 5,
+                101..=500 => 10,
+                _ => 20,
+            },
+            (Exchange::BinanceFuturesUsd, ExchangeCall::Klines) => 5,
+            (Exchange::BinanceFuturesUsd, ExchangeCall::Balances | ExchangeCall::Fills) => 5,
+            _ => 1,
+        };
+        CallCost { weight, orders: self.order_count() }
+    }
+}
+
+impl ResponseReport {
+    /// Read the usage headers an exchange sends, e.g. Binance's
+    /// `X-MBX-USED-WEIGHT-1M` and `X-MBX-ORDER-COUNT-10S`
+    pub fn from_headers(exchange: Exchange, call: ExchangeCall, status: u16, headers: &HashMap<String, String>) -> Self {
+        let header = |name: &str| headers.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.trim().parse::<u32>().ok());
+
+        let mut used = Vec::new();
+        match exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => {
+                for (name, value) in headers {
+                    let name = name.to_ascii_lowercase();
+                    let (rate_limit_type, suffix) = if let Some(suffix) = name.strip_prefix("x-mbx-used-weight-") {
+                        (LimitType::RequestWeight, suffix)
+                    } else if let Some(suffix) = name.strip_prefix("x-mbx-order-count-") {
+                        (LimitType::Orders, suffix)
+                    } else {
+                        continue;
+                    };
+                    if let (Some((interval, interval_num)), Ok(count)) = (LimitInterval::parse_suffix(suffix), value.trim().parse()) {
+                        used.push((rate_limit_type, interval, interval_num, count));
+                    }
+                }
+            }
+            // Bybit reports the limit and what's left of it for the endpoint called
+            Exchange::Bybit if call.order_count() > 0 => {
+                if let (Some(limit), Some(remaining)) = (header("X-Bapi-Limit"), header("X-Bapi-Limit-Status")) {
+                    used.push((LimitType::Orders, LimitInterval::Second, 1, limit.saturating_sub(remaining)));
+                }
+            }
+            _ => {}
+        }
+
+        Self { status, used, retry_after_seconds: header("Retry-After") }
+    }
+
+    /// Whether the response tells the governor anything it doesn't know
+    pub fn is_informative(&self) -> bool {
+        !self.used.is_empty() || self.is_ban()
+    }
+
+    /// 429 is a warning to back off; Binance answers 418 once it has banned the IP
+    fn is_ban(&self) -> bool {
+        matches!(self.status, 418 | 429)
+    }
+}
+
+impl GovernorState {
+    pub fn new(limits: Vec<ExchangeLimit>) -> Self {
+        Self {
+            windows: limits.into_iter()
+                .map(|limit| WindowUsage { limit, window_start_ms: 0, count: 0 })
+                .collect(),
+            banned_until_ms: None,
+        }
+    }
+
+    /// Limits the counts are kept against
+    pub fn limits(&self) -> Vec<ExchangeLimit> {
+        self.windows.iter().map(|window| window.limit).collect()
+    }
+
+    /// Start new windows for those that have ended. Windows are aligned to
+    /// the epoch, as the exchanges' own are.
+    fn roll(&mut self, now_ms: i64) {
+        for window in &mut self.windows {
+            let start = now_ms - now_ms.rem_euclid(window.limit.window_ms());
+            if window.window_start_ms != start {
+                window.window_start_ms = start;
+                window.count = 0;
+            }
+        }
+    }
+
+    /// Count a call if every limit has room for it, or say how long until it would
+    pub fn acquire(&mut self, cost: CallCost, now_ms: i64) -> Admission {
+        if let Some(until) = self.banned_until_ms.filter(|until| *until > now_ms) {
+            return Admission::Banned { wait_ms: until - now_ms };
+        }
+        self.roll(now_ms);
+
+        let wait_ms = self.windows.iter()
+            .filter(|window| {
+                let cost = window.limit.cost(cost);
+                // A call too heavy for an empty window still goes, alone
+                cost > 0 && window.count > 0 && window.count + cost > window.limit.usable()
+            })
+            .map(|window| window.window_start_ms + window.limit.window_ms() - now_ms)
+            .max();
+        if let Some(wait_ms) = wait_ms {
+            return Admission::Wait { wait_ms };
+        }
+
+        for window in &mut self.windows {
+            window.count += window.limit.cost(cost);
+        }
+        Admission::Granted
+    }
+
+    /// Take in what an exchange reported. Its counts include calls made from
+    /// elsewhere with the same key or address, so they only ever raise ours.
+    pub fn observe(&mut self, report: &ResponseReport, now_ms: i64) {
+        self.roll(now_ms);
+        for (rate_limit_type, interval, interval_num, count) in &report.used {
+            for window in &mut self.windows {
+                if window.limit.matches(*rate_limit_type, *interval, *interval_num) {
+                    window.count = window.count.max(*count);
+                }
+            }
+        }
+        if report.is_ban() {
+            let seconds = report.retry_after_seconds.map(i64::from).unwrap_or(DEFAULT_BAN_SECONDS);
+            self.banned_until_ms = Some(now_ms + seconds * 1000);
+        }
+    }
+
+    /// Usage of every limit in its current window
+    pub fn usage(&mut self, now_ms: i64) -> Vec<LimitUsage> {
+        self.roll(now_ms);
+        self.windows.iter()
+            .map(|window| LimitUsage { limit: window.limit, count: window.count })
+            .collect()
+    }
+}
+
+/// Client of the `ExchangeGovernor` Durable Objects, which count calls
+/// against each exchange's limits.
+///
+/// There is one object per exchange and API key, so every worker instance
+/// shares its counts. Without the binding, calls aren't governed. Calls are
+/// let through when the object can't be reached.
+#[derive(Clone)]
+pub struct GovernorClient {
+    namespace: Option<ObjectNamespace>,
+}
+
+impl GovernorClient {
+    pub fn new(namespace: Option<ObjectNamespace>) -> Self {
+        Self { namespace }
+    }
+
+    /// Wait for room to make a call, for up to two seconds; past that, or
+    /// while the exchange has us backing off, the call is refused
+    pub async fn acquire(&self, exchange: Exchange, name: &str, call: ExchangeCall) -> Result<(), String> {
+        let request = AcquireRequest { exchange: exchange_name(exchange), cost: call.cost(exchange) };
+        let mut waited_ms = 0;
+        loop {
+            let Some(admission) = self.call::<_, Admission>(name, Method::Post, "/acquire", Some(&request)).await else {
+                return Ok(());
+            };
+            match admission {
+                Admission::Granted => return Ok(()),
+                Admission::Wait { wait_ms } if waited_ms + wait_ms <= MAX_QUEUE_WAIT_MS => {
+                    console_log!("EXCHANGE GOVERNOR: Holding {:?} call on {} for {}ms", call, name, wait_ms);
+                    Delay::from(Duration::from_millis(wait_ms.max(1) as u64)).await;
+                    waited_ms += wait_ms;
+                }
+                Admission::Wait { wait_ms } => {
+                    return Err(format!("Rate limit of {} reached, retry in {}s", request.exchange, (wait_ms + 999) / 1000));
+                }
+                Admission::Banned { wait_ms } => {
+                    return Err(format!("{} asked us to back off, retry in {}s", request.exchange, (wait_ms + 999) / 1000));
+                }
+            }
+        }
+    }
+
+    /// Pass on the usage an exchange reported with a response
+    pub async fn observe(&self, exchange: Exchange, name: &str, report: ResponseReport) {
+        if !report.is_informative() {
+            return;
+        }
+        if report.is_ban() {
+            console_log!("EXCHANGE GOVERNOR: {} answered {}, backing off", name, report.status);
+        }
+        let request = ObserveRequest { exchange: exchange_name(exchange), report };
+        let _: Option<Vec<LimitUsage>> = self.call(name, Method::Post, "/observe", Some(&request)).await;
+    }
+
+    /// Live usage of each of the exchange's limits; counts are zero when
+    /// calls aren't governed
+    pub async fn usage(&self, exchange: Exchange, name: &str) -> Vec<LimitUsage> {
+        let path = format!("/usage?exchange={}", exchange_name(exchange));
+        match self.call(name, Method::Get, &path, None::<&()>).await {
+            Some(usage) => usage,
+            None => exchange_limits(exchange).into_iter().map(|limit| LimitUsage { limit, count: 0 }).collect(),
+        }
+    }
+
+    async fn call<B: Serialize, T: for<'de> Deserialize<'de>>(&self, name: &str, method: Method, path: &str, body: Option<&B>) -> Option<T> {
+        let namespace = self.namespace.as_ref()?;
+        let result = async {
+            let body = body.map(serde_json::to_string).transpose().map_err(|e| worker::Error::RustError(e.to_string()))?;
+            let mut init = RequestInit::new();
+            init.with_method(method).with_body(body.map(|body| JsValue::from_str(&body)));
+            let request = Request::new_with_init(&format!("https://exchange-governor{}", path), &init)?;
+
+            let stub = namespace.id_from_name(name)?.get_stub()?;
+            let mut response = stub.fetch_with_request(request).await?;
+            response.json::<T>().await
+        }.await;
+
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                console_log!("EXCHANGE GOVERNOR: Governor unavailable for {}, letting the call through: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+/// Name of an exchange as used in API requests
+pub fn exchange_name(exchange: Exchange) -> String {
+    format!("{:?}", exchange).to_lowercase()
+}
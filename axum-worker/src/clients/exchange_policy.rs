@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use worker::{console_log, Method, ObjectNamespace, Request, RequestInit};
+
+use crate::clients::exchange_governor::exchange_name;
+use crate::clients::trading::Exchange;
+
+/// Binding of the `CircuitBreaker` namespace in wrangler.toml
+pub const CIRCUIT_BREAKER_BINDING: &str = "CIRCUIT_BREAKER";
+
+/// Variable holding JSON overrides of the default call policy
+pub const EXCHANGE_CALL_POLICY_VAR: &str = "EXCHANGE_CALL_POLICY";
+
+/// Timeouts, retries and circuit breaking of exchange calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CallPolicy {
+    pub timeout_ms: u64,
+    /// Timeouts of particular exchanges, e.g. `{"kraken": 15000}`
+    pub exchange_timeout_ms: HashMap<String, u64>,
+    /// Retries of a read that timed out or found the exchange unavailable
+    pub read_retries: u32,
+    /// First retry delay, doubled for each retry after it
+    pub retry_base_ms: u64,
+    pub retry_max_ms: u64,
+    /// Failed calls in a row that open an exchange's circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit refuses calls before letting a trial through
+    pub open_seconds: u32,
+}
+
+/// State of an exchange's circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// Calls are refused until the cool-down ends
+    Open,
+    /// The cool-down has ended; one trial call decides whether to close
+    HalfOpen,
+}
+
+/// Failures of one exchange, kept by the circuit breaker object
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BreakerState {
+    consecutive_failures: u32,
+    opened_at_ms: Option<i64>,
+    open_until_ms: Option<i64>,
+    trial_started_ms: Option<i64>,
+    last_failure: Option<String>,
+}
+
+/// Circuit breaker state as reported by the circuit breaker object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitStatus {
+    pub state: CircuitState,
+    /// Whether the call asked about may be made
+    pub allowed: bool,
+    pub consecutive_failures: u32,
+    pub retry_after_seconds: u32,
+    pub last_failure: Option<String>,
+    pub opened_at: Option<DateTime<Utc>>,
+}
+
+/// Request to the circuit breaker object for leave to make a call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitCheckRequest {
+    /// How long a half-open trial may run before another is let through
+    pub trial_timeout_ms: i64,
+}
+
+/// Request to the circuit breaker object to record a failed call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitFailureRequest {
+    pub error: String,
+    pub failure_threshold: u32,
+    pub open_seconds: u32,
+}
+
+/// Why an exchange call failed
+#[derive(Debug, Clone)]
+pub enum CallError {
+    /// No answer within the timeout
+    Timeout(String),
+    /// Network failure or a 5xx answer
+    Unavailable(String),
+    /// The exchange refused the request, e.g. a 4xx or a malformed answer
+    Rejected(String),
+    /// Refused by the rate-limit governor before it was sent
+    RateLimited(String),
+    /// Refused because the exchange's circuit is open
+    CircuitOpen(String),
+    /// The exchange has no order matching an order lookup
+    NotFound(String),
+}
+
+impl Default for CallPolicy {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 10_000,
+            exchange_timeout_ms: HashMap::new(),
+            read_retries: 2,
+            retry_base_ms: 250,
+            retry_max_ms: 2_000,
+            failure_threshold: 5,
+            open_seconds: 30,
+        }
+    }
+}
+
+impl CallPolicy {
+    /// Parse and check overrides
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let policy: CallPolicy = serde_json::from_str(json).map_err(|e| format!("Invalid {}: {}", EXCHANGE_CALL_POLICY_VAR, e))?;
+        if policy.timeout_ms == 0 || policy.exchange_timeout_ms.values().any(|timeout| *timeout == 0) {
+            return Err("Timeouts must be at least 1ms".to_string());
+        }
+        if policy.failure_threshold == 0 || policy.open_seconds == 0 {
+            return Err("failure_threshold and open_seconds must be at least 1".to_string());
+        }
+        Ok(policy)
+    }
+
+    pub fn timeout_for(&self, exchange: Exchange) -> Duration {
+        let timeout_ms = self.exchange_timeout_ms.get(&exchange_name(exchange)).copied().unwrap_or(self.timeout_ms);
+        Duration::from_millis(timeout_ms)
+    }
+
+    /// Delay before a retry, with jitter so callers that failed together
+    /// don't retry together: between half and all of the doubled delay
+    pub fn retry_delay(&self, retry: u32) -> Duration {
+        self.retry_delay_with(retry, js_sys::Math::random())
+    }
+
+    /// Retry delay with the jitter drawn as `random`, from 0 up to 1
+    fn retry_delay_with(&self, retry: u32, random: f64) -> Duration {
+        let delay = self.retry_base_ms.saturating_mul(1 << retry.min(16)).min(self.retry_max_ms);
+        let jitter = (random * (delay / 2) as f64) as u64;
+        Duration::from_millis(delay / 2 + jitter)
+    }
+}
+
+impl CircuitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "CLOSED",
+            CircuitState::Open => "OPEN",
+            CircuitState::HalfOpen => "HALF_OPEN",
+        }
+    }
+}
+
+impl BreakerState {
+    fn state(&self, now_ms: i64) -> CircuitState {
+        match self.open_until_ms {
+            None => CircuitState::Closed,
+            Some(until) if until > now_ms => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    pub fn status(&self, now_ms: i64) -> CircuitStatus {
+        let state = self.state(now_ms);
+        CircuitStatus {
+            state,
+            allowed: state == CircuitState::Closed,
+            consecutive_failures: self.consecutive_failures,
+            retry_after_seconds: self.open_until_ms
+                .map(|until| ((until - now_ms).max(0) as f64 / 1000.0).ceil() as u32)
+                .unwrap_or(0),
+            last_failure: self.last_failure.clone(),
+            opened_at: self.opened_at_ms.and_then(|opened_at| Utc.timestamp_millis_opt(opened_at).single()),
+        }
+    }
+
+    /// Whether a call may be made. Once the cool-down ends, one call at a
+    /// time is let through as a trial.
+    pub fn check(&mut self, trial_timeout_ms: i64, now_ms: i64) -> CircuitStatus {
+        let mut status = self.status(now_ms);
+        if status.state == CircuitState::HalfOpen {
+            let trial_running = self.trial_started_ms.is_some_and(|started| started + trial_timeout_ms > now_ms);
+            if !trial_running {
+                self.trial_started_ms = Some(now_ms);
+                status.allowed = true;
+            }
+        }
+        status
+    }
+
+    pub fn record_success(&mut self, now_ms: i64) -> CircuitStatus {
+        *self = BreakerState::default();
+        self.status(now_ms)
+    }
+
+    /// Count a failed call, opening the circuit once enough fail in a row or
+    /// when a trial fails
+    pub fn record_failure(&mut self, request: &CircuitFailureRequest, now_ms: i64) -> CircuitStatus {
+        let state = self.state(now_ms);
+        self.consecutive_failures += 1;
+        self.last_failure = Some(request.error.clone());
+        if state == CircuitState::HalfOpen || (state == CircuitState::Closed && self.consecutive_failures >= request.failure_threshold) {
+            self.opened_at_ms = Some(now_ms);
+            self.open_until_ms = Some(now_ms + request.open_seconds as i64 * 1000);
+            self.trial_started_ms = None;
+        }
+        self.status(now_ms)
+    }
+}
+
+impl CallError {
+    /// Whether a read may be tried again
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, CallError::Timeout(_) | CallError::Unavailable(_))
+    }
+
+    /// Whether the failure says the exchange is in trouble, rather than the
+    /// request or us
+    pub fn counts_against_circuit(&self) -> bool {
+        matches!(self, CallError::Timeout(_) | CallError::Unavailable(_))
+    }
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Timeout(message)
+            | CallError::Unavailable(message)
+            | CallError::Rejected(message)
+            | CallError::RateLimited(message)
+            | CallError::CircuitOpen(message)
+            | CallError::NotFound(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Call policy of the exchange clients, with the client of the
+/// `CircuitBreaker` Durable Objects that track each exchange's failures.
+///
+/// There is one breaker object per exchange, so every worker instance sees
+/// an outage once any of them has. Without the binding, circuits are never
+/// opened; timeouts and retries still apply.
+#[derive(Clone)]
+pub struct ExchangePolicy {
+    policy: CallPolicy,
+    namespace: Option<ObjectNamespace>,
+}
+
+impl ExchangePolicy {
+    pub fn new(namespace: Option<ObjectNamespace>, config_json: Option<String>) -> Self {
+        let policy = match config_json.as_deref().map(CallPolicy::parse) {
+            Some(Ok(policy)) => policy,
+            Some(Err(e)) => {
+                console_log!("EXCHANGE POLICY: Using the default call policy, {}", e);
+                CallPolicy::default()
+            }
+            None => CallPolicy::default(),
+        };
+        Self { policy, namespace }
+    }
+
+    pub fn policy(&self) -> &CallPolicy {
+        &self.policy
+    }
+
+    /// Ask the exchange's breaker whether a call may be made. None means the
+    /// breaker couldn't be asked, and the call goes ahead.
+    pub async fn check(&self, exchange: Exchange) -> Result<Option<CircuitStatus>, CallError> {
+        let request = CircuitCheckRequest { trial_timeout_ms: self.policy.timeout_for(exchange).as_millis() as i64 };
+        let status: Option<CircuitStatus> = self.call(exchange, Method::Post, "/check", Some(&request)).await;
+        match status {
+            Some(status) if !status.allowed => Err(CallError::CircuitOpen(format!(
+                "{} is unavailable after {} failed calls in a row, retry in {}s",
+                exchange_name(exchange), status.consecutive_failures, status.retry_after_seconds.max(1),
+            ))),
+            status => Ok(status),
+        }
+    }
+
+    /// Record a call that got an answer. Only sent when the breaker had
+    /// failures to forget.
+    pub async fn record_success(&self, exchange: Exchange, status: Option<&CircuitStatus>) {
+        let Some(status) = status.filter(|status| status.state != CircuitState::Closed || status.consecutive_failures > 0) else {
+            return;
+        };
+        if status.state != CircuitState::Closed {
+            console_log!("EXCHANGE POLICY: Closing the circuit of {}", exchange_name(exchange));
+        }
+        let _: Option<CircuitStatus> = self.call(exchange, Method::Post, "/success", None::<&()>).await;
+    }
+
+    pub async fn record_failure(&self, exchange: Exchange, error: &CallError) {
+        let request = CircuitFailureRequest {
+            error: error.to_string(),
+            failure_threshold: self.policy.failure_threshold,
+            open_seconds: self.policy.open_seconds,
+        };
+        let status: Option<CircuitStatus> = self.call(exchange, Method::Post, "/failure", Some(&request)).await;
+        if let Some(status) = status.filter(|status| status.state == CircuitState::Open) {
+            console_log!("EXCHANGE POLICY: Circuit of {} open for {}s after: {}", exchange_name(exchange), status.retry_after_seconds, error);
+        }
+    }
+
+    /// The exchange's circuit, if it's tracked
+    pub async fn status(&self, exchange: Exchange) -> Option<CircuitStatus> {
+        self.call(exchange, Method::Get, "/status", None::<&()>).await
+    }
+
+    async fn call<B: Serialize, T: for<'de> Deserialize<'de>>(&self, exchange: Exchange, method: Method, path: &str, body: Option<&B>) -> Option<T> {
+        let namespace = self.namespace.as_ref()?;
+        let name = exchange_name(exchange);
+        let result = async {
+            let body = body.map(serde_json::to_string).transpose().map_err(|e| worker::Error::RustError(e.to_string()))?;
+            let mut init = RequestInit::new();
+            init.with_method(method).with_body(body.map(|body| JsValue::from_str(&body)));
+            let request = Request::new_with_init(&format!("https://circuit-breaker{}", path), &init)?;
+
+            let stub = namespace.id_from_name(&name)?.get_stub()?;
+            let mut response = stub.fetch_with_request(request).await?;
+            response.json::<T>().await
+        }.await;
+
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                console_log!("EXCHANGE POLICY: Circuit breaker unavailable for {}: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(failure_threshold: u32) -> CircuitFailureRequest {
+        CircuitFailureRequest { error: "timed out".to_string(), failure_threshold, open_seconds: 30 }
+    }
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures() {
+        let mut breaker = BreakerState::default();
+        assert_eq!(breaker.record_failure(&failure(2), 0).state, CircuitState::Closed);
+        let opened = breaker.record_failure(&failure(2), 1_000);
+        assert_eq!(opened.state, CircuitState::Open);
+        assert_eq!(opened.retry_after_seconds, 30);
+        assert_eq!(opened.last_failure.as_deref(), Some("timed out"));
+
+        let refused = breaker.check(5_000, 10_000);
+        assert!(!refused.allowed);
+        assert_eq!(refused.retry_after_seconds, 21);
+    }
+
+    #[test]
+    fn half_open_circuit_lets_one_trial_through() {
+        let mut breaker = BreakerState::default();
+        breaker.record_failure(&failure(1), 0);
+
+        let trial = breaker.check(5_000, 30_000);
+        assert_eq!(trial.state, CircuitState::HalfOpen);
+        assert!(trial.allowed);
+        assert!(!breaker.check(5_000, 31_000).allowed);
+        // A trial that never reported back is replaced after its timeout
+        assert!(breaker.check(5_000, 35_000).allowed);
+
+        // A failed trial opens the circuit again at once
+        assert_eq!(breaker.record_failure(&failure(5), 36_000).state, CircuitState::Open);
+
+        let closed = breaker.record_success(70_000);
+        assert_eq!(closed.state, CircuitState::Closed);
+        assert_eq!(closed.consecutive_failures, 0);
+        assert!(breaker.check(5_000, 70_000).allowed);
+    }
+
+    #[test]
+    fn retry_delays_double_up_to_the_maximum_with_jitter() {
+        let policy = CallPolicy::default();
+        assert_eq!(policy.retry_delay_with(0, 0.0), Duration::from_millis(125));
+        assert_eq!(policy.retry_delay_with(0, 0.999), Duration::from_millis(249));
+        assert_eq!(policy.retry_delay_with(2, 0.0), Duration::from_millis(500));
+        assert_eq!(policy.retry_delay_with(10, 0.0), Duration::from_millis(1_000));
+        assert_eq!(policy.retry_delay_with(u32::MAX, 0.5), Duration::from_millis(1_500));
+    }
+
+    #[test]
+    fn policy_overrides_are_checked() {
+        let policy = CallPolicy::parse(r#"{"timeout_ms": 5000, "exchange_timeout_ms": {"kraken": 15000}}"#).unwrap();
+        assert_eq!(policy.timeout_for(Exchange::Kraken), Duration::from_millis(15_000));
+        assert_eq!(policy.timeout_for(Exchange::Binance), Duration::from_millis(5_000));
+
+        assert!(CallPolicy::parse(r#"{"timeout_ms": 0}"#).is_err());
+        assert!(CallPolicy::parse(r#"{"failure_threshold": 0}"#).is_err());
+        assert!(CallPolicy::parse(r#"{"retries": 3}"#).is_err());
+    }
+
+    #[test]
+    fn only_exchange_trouble_is_retried_or_counted() {
+        for error in [CallError::Timeout(String::new()), CallError::Unavailable(String::new())] {
+            assert!(error.is_retryable() && error.counts_against_circuit());
+        }
+        for error in [CallError::Rejected(String::new()), CallError::RateLimited(String::new()), CallError::NotFound(String::new())] {
+            assert!(!error.is_retryable() && !error.counts_against_circuit());
+        }
+    }
+}
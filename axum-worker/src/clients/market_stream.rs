@@ -0,0 +1,436 @@
+use serde_json::{json, Value};
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+use crate::clients::trading::Exchange;
+use crate::entity::market_data::{BookSnapshot, BookUpdate, Instrument, MarketDataEvent, OrderBookLevel, Trade, TradeSide};
+use crate::service::order_book::{ChecksumFormat, SequenceRule};
+use crate::util::sql::row_decimal;
+
+/// Levels per side Kraken's book subscription keeps
+const KRAKEN_BOOK_DEPTH: usize = 10;
+
+/// One decoded feed message
+#[derive(Debug, Clone)]
+pub enum FeedMessage {
+    Event(MarketDataEvent),
+    /// Full order book to rebuild the local book from
+    Snapshot(BookSnapshot),
+    /// Incremental order book change to apply to the local book
+    Update(BookUpdate),
+}
+
+/// Public market data WebSocket feed of one instrument on one exchange.
+///
+/// Knows where to connect, which frames subscribe to trades and the order
+/// book, and how to turn the exchange's messages into `FeedMessage`s. The
+/// book arrives as a snapshot followed by incremental updates, numbered by
+/// the exchange's `SequenceRule`; Binance sends only updates, so its
+/// snapshot is fetched from `snapshot_url`. Kraken's checksum is written at
+/// the pair's precision, which is fetched from `precision_url` before its
+/// checksums are passed on. Coinbase only sends the top of the book, each
+/// message a new snapshot: its `level2` channels carry no sequence numbers,
+/// so its book can't be checked for missed updates.
+#[derive(Debug, Clone)]
+pub struct MarketFeed {
+    exchange: Exchange,
+    instrument: Instrument,
+    checksum_format: Option<ChecksumFormat>,
+}
+
+impl MarketFeed {
+    pub fn new(instrument: Instrument) -> Result<Self, String> {
+        let exchange = Exchange::parse(&instrument.exchange)
+            .ok_or_else(|| format!("Unsupported exchange: {}", instrument.exchange))?;
+        let checksum_format = match exchange {
+            Exchange::Okx => Some(ChecksumFormat::Interleaved),
+            _ => None,
+        };
+        Ok(Self { exchange, instrument, checksum_format })
+    }
+
+    pub fn instrument(&self) -> &Instrument {
+        &self.instrument
+    }
+
+    /// WebSocket URL of the exchange's public feed
+    pub fn url(&self) -> String {
+        let base = &self.instrument.base;
+        let quote = &self.instrument.quote;
+        match self.exchange {
+            Exchange::Binance => {
+                let symbol = format!("{}{}", base, quote).to_lowercase();
+                format!("wss://stream.binance.com:9443/stream?streams={0}@trade/{0}@depth@100ms", symbol)
+            }
+            Exchange::BinanceFuturesUsd => {
+                let symbol = format!("{}{}", base, quote).to_lowercase();
+                format!("wss://fstream.binance.com/stream?streams={0}@aggTrade/{0}@depth@100ms", symbol)
+            }
+            Exchange::Coinbase => "wss://ws-feed.exchange.coinbase.com".to_string(),
+            Exchange::Kraken => "wss://ws.kraken.com/v2".to_string(),
+            Exchange::Okx => "wss://ws.okx.com:8443/ws/v5/public".to_string(),
+            Exchange::Bybit => "wss://stream.bybit.com/v5/public/spot".to_string(),
+        }
+    }
+
+    /// How the exchange numbers its book updates
+    pub fn sequence_rule(&self) -> SequenceRule {
+        match self.exchange {
+            Exchange::Binance => SequenceRule::UpdateRange,
+            Exchange::BinanceFuturesUsd | Exchange::Okx => SequenceRule::PreviousId,
+            Exchange::Bybit => SequenceRule::Contiguous,
+            Exchange::Coinbase | Exchange::Kraken => SequenceRule::Unsequenced,
+        }
+    }
+
+    /// Levels per side the book subscription covers, when the exchange
+    /// doesn't send removals for levels that fall out of it
+    pub fn book_depth(&self) -> Option<usize> {
+        match self.exchange {
+            Exchange::Kraken => Some(KRAKEN_BOOK_DEPTH),
+            _ => None,
+        }
+    }
+
+    /// How the book's checksums are computed; None when the feed doesn't send
+    /// them or their precision isn't known yet
+    pub fn checksum_format(&self) -> Option<ChecksumFormat> {
+        self.checksum_format
+    }
+
+    /// REST URL of the pair's price and quantity precision, while it is
+    /// still needed to verify the book's checksums
+    pub fn precision_url(&self) -> Option<String> {
+        match self.exchange {
+            Exchange::Kraken if self.checksum_format.is_none() => Some(format!(
+                "https://api.kraken.com/0/public/AssetPairs?pair={}{}", self.instrument.base, self.instrument.quote,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Take the pair's precision from the response of `precision_url`;
+    /// returns whether it was found
+    pub fn apply_precision(&mut self, text: &str) -> bool {
+        let Ok(response) = serde_json::from_str::<Value>(text) else { return false };
+        let Some(pair) = response["result"].as_object().and_then(|pairs| pairs.values().next()) else { return false };
+        let (Some(price_precision), Some(quantity_precision)) = (pair["pair_decimals"].as_u64(), pair["lot_decimals"].as_u64()) else {
+            return false;
+        };
+        self.checksum_format = Some(ChecksumFormat::Concatenated {
+            price_precision: price_precision as u32,
+            quantity_precision: quantity_precision as u32,
+        });
+        true
+    }
+
+    /// REST URL of the book snapshot, for feeds that only stream updates
+    pub fn snapshot_url(&self) -> Option<String> {
+        let symbol = format!("{}{}", self.instrument.base, self.instrument.quote).to_uppercase();
+        match self.exchange {
+            Exchange::Binance => Some(format!("https://api.binance.com/api/v3/depth?symbol={}&limit=1000", symbol)),
+            Exchange::BinanceFuturesUsd => Some(format!("https://fapi.binance.com/fapi/v1/depth?symbol={}&limit=1000", symbol)),
+            _ => None,
+        }
+    }
+
+    /// Parse the response from `snapshot_url`
+    pub fn parse_snapshot(&self, text: &str) -> Option<BookSnapshot> {
+        let snapshot = serde_json::from_str::<Value>(text).ok()?;
+        Some(BookSnapshot {
+            last_update_id: snapshot["lastUpdateId"].as_u64()?,
+            bids: levels(&snapshot["bids"]),
+            asks: levels(&snapshot["asks"]),
+            checksum: None,
+            exchange_timestamp: millis(&snapshot["E"]).unwrap_or_else(Utc::now),
+        })
+    }
+
+    /// Frames to send after connecting; Binance subscribes through the URL
+    pub fn subscribe_frames(&self) -> Vec<String> {
+        let base = &self.instrument.base;
+        let quote = &self.instrument.quote;
+        let frames = match self.exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => vec![],
+            Exchange::Coinbase => vec![json!({
+                "type": "subscribe",
+                "product_ids": [format!("{}-{}", base, quote)],
+                "channels": ["matches", "ticker"],
+            })],
+            Exchange::Kraken => vec![
+                json!({
+                    "method": "subscribe",
+                    "params": { "channel": "trade", "symbol": [format!("{}/{}", base, quote)] },
+                }),
+                json!({
+                    "method": "subscribe",
+                    "params": { "channel": "book", "symbol": [format!("{}/{}", base, quote)], "depth": KRAKEN_BOOK_DEPTH },
+                }),
+            ],
+            Exchange::Okx => {
+                let inst_id = format!("{}-{}", base, quote);
+                vec![json!({
+                    "op": "subscribe",
+                    "args": [
+                        { "channel": "trades", "instId": inst_id },
+                        { "channel": "books", "instId": inst_id },
+                    ],
+                })]
+            }
+            Exchange::Bybit => {
+                let symbol = format!("{}{}", base, quote);
+                vec![json!({
+                    "op": "subscribe",
+                    "args": [format!("publicTrade.{}", symbol), format!("orderbook.50.{}", symbol)],
+                })]
+            }
+        };
+        frames.iter().map(|frame| frame.to_string()).collect()
+    }
+
+    /// Events and book changes carried by one feed message; control messages
+    /// and anything unrecognized yield none
+    pub fn parse_message(&self, text: &str) -> Vec<FeedMessage> {
+        let Ok(message) = serde_json::from_str::<Value>(text) else { return Vec::new() };
+
+        match self.exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => self.parse_binance(&message["data"]),
+            Exchange::Coinbase => self.parse_coinbase(&message),
+            Exchange::Kraken => self.parse_kraken(&message),
+            Exchange::Okx => self.parse_okx(&message),
+            Exchange::Bybit => self.parse_bybit(&message),
+        }
+    }
+
+    fn parse_binance(&self, data: &Value) -> Vec<FeedMessage> {
+        match data["e"].as_str() {
+            Some("trade") | Some("aggTrade") => {
+                // "m" is true when the buyer was the maker, i.e. the taker sold
+                let side = if data["m"].as_bool().unwrap_or(false) { TradeSide::Sell } else { TradeSide::Buy };
+                // Aggregate trades are numbered separately from raw trades
+                let id = if data["e"].as_str() == Some("aggTrade") { &data["a"] } else { &data["t"] };
+                self.trade(id, &data["p"], &data["q"], side, millis(&data["T"])).into_iter().collect()
+            }
+            Some("depthUpdate") => {
+                let (Some(first_update_id), Some(last_update_id)) = (data["U"].as_u64(), data["u"].as_u64()) else {
+                    return Vec::new();
+                };
+                vec![FeedMessage::Update(BookUpdate {
+                    first_update_id,
+                    last_update_id,
+                    // Only the futures stream links each update to the previous one
+                    previous_update_id: data["pu"].as_u64(),
+                    bids: level_changes(&data["b"]),
+                    asks: level_changes(&data["a"]),
+                    checksum: None,
+                    exchange_timestamp: millis(&data["E"]).unwrap_or_else(Utc::now),
+                })]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse_coinbase(&self, message: &Value) -> Vec<FeedMessage> {
+        let timestamp = rfc3339(&message["time"]);
+        match message["type"].as_str() {
+            Some("match") | Some("last_match") => {
+                // Coinbase reports the maker's side; the taker traded the other way
+                let side = match message["side"].as_str() {
+                    Some("sell") => TradeSide::Buy,
+                    _ => TradeSide::Sell,
+                };
+                self.trade(&message["trade_id"], &message["price"], &message["size"], side, timestamp).into_iter().collect()
+            }
+            // The ticker only carries the best bid and ask, so each one replaces the book
+            Some("ticker") => vec![FeedMessage::Snapshot(BookSnapshot {
+                last_update_id: 0,
+                bids: level(&message["best_bid"], &message["best_bid_size"]).into_iter().collect(),
+                asks: level(&message["best_ask"], &message["best_ask_size"]).into_iter().collect(),
+                checksum: None,
+                exchange_timestamp: timestamp.unwrap_or_else(Utc::now),
+            })],
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse_kraken(&self, message: &Value) -> Vec<FeedMessage> {
+        let Some(data) = message["data"].as_array() else { return Vec::new() };
+        match message["channel"].as_str() {
+            Some("trade") => data.iter()
+                .filter_map(|trade| self.trade(&trade["trade_id"], &trade["price"], &trade["qty"], side(&trade["side"]), rfc3339(&trade["timestamp"])))
+                .collect(),
+            Some("book") => data.iter()
+                .map(|book| {
+                    let bids = object_level_changes(&book["bids"]);
+                    let asks = object_level_changes(&book["asks"]);
+                    // Kraken's checksum is an unsigned 32-bit CRC; it can only be
+                    // checked once the pair's precision is known
+                    let checksum = book["checksum"].as_u64()
+                        .filter(|_| self.checksum_format.is_some())
+                        .map(|checksum| checksum as u32 as i32);
+                    let exchange_timestamp = rfc3339(&book["timestamp"]).unwrap_or_else(Utc::now);
+                    match message["type"].as_str() {
+                        Some("snapshot") => FeedMessage::Snapshot(BookSnapshot {
+                            last_update_id: 0,
+                            bids,
+                            asks,
+                            checksum,
+                            exchange_timestamp,
+                        }),
+                        _ => FeedMessage::Update(BookUpdate {
+                            first_update_id: 0,
+                            last_update_id: 0,
+                            previous_update_id: None,
+                            bids,
+                            asks,
+                            checksum,
+                            exchange_timestamp,
+                        }),
+                    }
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse_okx(&self, message: &Value) -> Vec<FeedMessage> {
+        let Some(data) = message["data"].as_array() else { return Vec::new() };
+        match message["arg"]["channel"].as_str() {
+            Some("trades") => data.iter()
+                .filter_map(|trade| self.trade(&trade["tradeId"], &trade["px"], &trade["sz"], side(&trade["side"]), millis(&trade["ts"])))
+                .collect(),
+            Some("books") => data.iter()
+                .filter_map(|book| {
+                    let last_update_id = book["seqId"].as_u64()?;
+                    let bids = level_changes(&book["bids"]);
+                    let asks = level_changes(&book["asks"]);
+                    // OKX's checksum is a signed 32-bit CRC
+                    let checksum = book["checksum"].as_i64().map(|checksum| checksum as i32);
+                    let exchange_timestamp = millis(&book["ts"]).unwrap_or_else(Utc::now);
+                    Some(match message["action"].as_str() {
+                        Some("snapshot") => FeedMessage::Snapshot(BookSnapshot { last_update_id, bids, asks, checksum, exchange_timestamp }),
+                        _ => {
+                            let previous_update_id = book["prevSeqId"].as_u64()?;
+                            FeedMessage::Update(BookUpdate {
+                                first_update_id: previous_update_id + 1,
+                                last_update_id,
+                                previous_update_id: Some(previous_update_id),
+                                bids,
+                                asks,
+                                checksum,
+                                exchange_timestamp,
+                            })
+                        }
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse_bybit(&self, message: &Value) -> Vec<FeedMessage> {
+        let topic = message["topic"].as_str().unwrap_or_default();
+        if topic.starts_with("publicTrade.") {
+            let Some(data) = message["data"].as_array() else { return Vec::new() };
+            return data.iter()
+                .filter_map(|trade| self.trade(&trade["i"], &trade["p"], &trade["v"], side(&trade["S"]), millis(&trade["T"])))
+                .collect();
+        }
+        if topic.starts_with("orderbook.") {
+            let data = &message["data"];
+            let Some(update_id) = data["u"].as_u64() else { return Vec::new() };
+            let bids = level_changes(&data["b"]);
+            let asks = level_changes(&data["a"]);
+            let exchange_timestamp = millis(&message["ts"]).unwrap_or_else(Utc::now);
+            // Bybit also resends a snapshot, with an update id of 1, when its service restarts
+            return vec![match message["type"].as_str() {
+                Some("snapshot") => FeedMessage::Snapshot(BookSnapshot {
+                    last_update_id: update_id,
+                    bids,
+                    asks,
+                    checksum: None,
+                    exchange_timestamp,
+                }),
+                _ => FeedMessage::Update(BookUpdate {
+                    first_update_id: update_id,
+                    last_update_id: update_id,
+                    previous_update_id: None,
+                    bids,
+                    asks,
+                    checksum: None,
+                    exchange_timestamp,
+                }),
+            }];
+        }
+        Vec::new()
+    }
+
+    fn trade(&self, id: &Value, price: &Value, quantity: &Value, side: TradeSide, timestamp: Option<DateTime<Utc>>) -> Option<FeedMessage> {
+        let price = row_decimal(price)?;
+        let quantity = row_decimal(quantity)?;
+        let timestamp = timestamp.unwrap_or_else(Utc::now);
+        let trade = match id {
+            Value::String(id) => Trade::from_exchange(id.clone(), self.instrument.clone(), price, quantity, side, timestamp),
+            Value::Number(id) => Trade::from_exchange(id.to_string(), self.instrument.clone(), price, quantity, side, timestamp),
+            _ => Trade::new(self.instrument.clone(), price, quantity, side, timestamp),
+        };
+        Some(FeedMessage::Event(MarketDataEvent::Trade(trade)))
+    }
+}
+
+/// Price levels from `[[price, quantity, ...], ...]`, skipping empty ones
+fn levels(value: &Value) -> Vec<OrderBookLevel> {
+    value.as_array()
+        .map(|levels| levels.iter().filter_map(|entry| level(&entry[0], &entry[1])).collect())
+        .unwrap_or_default()
+}
+
+/// Level changes from `[[price, quantity, ...], ...]`, keeping zero
+/// quantities, which remove the level
+fn level_changes(value: &Value) -> Vec<OrderBookLevel> {
+    value.as_array()
+        .map(|levels| levels.iter()
+            .filter_map(|entry| Some(OrderBookLevel {
+                price: row_decimal(&entry[0])?,
+                quantity: row_decimal(&entry[1])?,
+            }))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Level changes from `[{"price": ..., "qty": ...}, ...]`
+fn object_level_changes(value: &Value) -> Vec<OrderBookLevel> {
+    value.as_array()
+        .map(|levels| levels.iter()
+            .filter_map(|entry| Some(OrderBookLevel {
+                price: row_decimal(&entry["price"])?,
+                quantity: row_decimal(&entry["qty"])?,
+            }))
+            .collect())
+        .unwrap_or_default()
+}
+
+fn level(price: &Value, quantity: &Value) -> Option<OrderBookLevel> {
+    let quantity = row_decimal(quantity)?;
+    (quantity > Decimal::ZERO).then_some(OrderBookLevel {
+        price: row_decimal(price)?,
+        quantity,
+    })
+}
+
+fn side(value: &Value) -> TradeSide {
+    match value.as_str().map(|s| s.to_lowercase()).as_deref() {
+        Some("sell") => TradeSide::Sell,
+        _ => TradeSide::Buy,
+    }
+}
+
+/// Millisecond epoch timestamp, given as a number or a string
+fn millis(value: &Value) -> Option<DateTime<Utc>> {
+    let millis = value.as_i64().or_else(|| value.as_str()?.parse().ok())?;
+    DateTime::from_timestamp_millis(millis)
+}
+
+fn rfc3339(value: &Value) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.as_str()?).ok().map(|dt| dt.with_timezone(&Utc))
+}
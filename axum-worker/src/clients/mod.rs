@@ -1,2 +1,6 @@
 pub mod neon;
 pub mod trading;
+pub mod market_stream;
+pub mod webhook;
+pub mod exchange_governor;
+pub mod exchange_policy;
@@ -1,9 +1,16 @@
+use std::collections::HashMap;
 use serde_json::{json, Value};
-use worker::console_log;
+use futures::future::{select, Either};
+use sha2::{Digest, Sha256};
+use worker::{console_log, AbortController, AbortSignal, Delay};
 
 use rust_decimal::Decimal;
 use chrono::{DateTime, Utc};
 
+use crate::clients::exchange_governor::{exchange_name, ExchangeCall, GovernorClient, LimitUsage, ResponseReport, MAX_QUEUE_WAIT_MS};
+use crate::clients::exchange_policy::{CallError, CircuitStatus, ExchangePolicy};
+use crate::entity::market_data::CandleInterval;
+
 /// Trading side enumeration
 #[derive(Debug, Clone, Copy)]
 pub enum Side {
@@ -22,6 +29,21 @@ pub enum Exchange {
     Bybit,
 }
 
+impl Exchange {
+    /// Parse an exchange name as used in API requests ("binance", "coinbase", ...)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "binance" => Some(Exchange::Binance),
+            "binancefuturesusd" => Some(Exchange::BinanceFuturesUsd),
+            "coinbase" => Some(Exchange::Coinbase),
+            "kraken" => Some(Exchange::Kraken),
+            "okx" => Some(Exchange::Okx),
+            "bybit" => Some(Exchange::Bybit),
+            _ => None,
+        }
+    }
+}
+
 /// Trading Client for interacting with cryptocurrency exchanges using barter-rs
 #[derive(Clone)]
 pub struct TradingClient {
@@ -30,6 +52,15 @@ pub struct TradingClient {
     pub api_secret: Option<String>,
     pub sandbox_mode: bool,
     pub base_url: String,
+    governor: GovernorClient,
+    policy: ExchangePolicy,
+}
+
+/// Answer from an exchange, whatever its status
+struct RawResponse {
+    status: u16,
+    headers: HashMap<String, String>,
+    body: Value,
 }
 
 /// Simple instrument structure for trading
@@ -47,6 +78,9 @@ pub struct OrderRequest {
     pub quantity: Decimal,
     pub price: Option<Decimal>, // None for market orders
     pub order_type: OrderType,
+    pub stop_price: Option<Decimal>, // Trigger price for stop/take-profit orders
+    pub trigger_by: TriggerBy,
+    pub client_order_id: Option<String>, // Lets the exchange deduplicate retries and us look the order up later
 }
 
 /// Order type enumeration
@@ -55,7 +89,60 @@ pub enum OrderType {
     Market,
     Limit,
     StopLoss,
+    StopLossLimit,
     TakeProfit,
+    TakeProfitLimit,
+}
+
+/// Reference price an exchange uses to fire a trigger order
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TriggerBy {
+    #[default]
+    LastPrice,
+    MarkPrice,
+}
+
+/// Exchange-reported state of an order
+#[derive(Debug, Clone)]
+pub struct OrderStatusReport {
+    pub exchange_order_id: String,
+    pub status: String,
+    pub filled_quantity: Decimal,
+    pub average_price: Option<Decimal>,
+}
+
+/// Single fill of an order as reported by the exchange
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub exchange_trade_id: String,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub commission: Decimal,
+    pub commission_asset: String,
+    pub is_maker: bool,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Public trade printed on an exchange's tape
+#[derive(Debug, Clone)]
+pub struct PublicTrade {
+    pub exchange_trade_id: String,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub side: Side, // Taker side
+    pub executed_at: DateTime<Utc>,
+}
+
+/// OHLCV bar from an exchange's kline API
+#[derive(Debug, Clone)]
+pub struct Kline {
+    pub open_time: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal, // In the base asset
+    pub trade_count: Option<u32>,
 }
 
 /// Portfolio balance information
@@ -112,9 +199,40 @@ impl TradingClient {
             api_secret,
             sandbox_mode: true, // Start in sandbox mode for safety
             base_url,
+            governor: GovernorClient::new(None),
+            policy: ExchangePolicy::new(None, None),
         }
     }
 
+    /// Apply timeouts, retries and circuit breaking to this client's calls
+    pub fn with_policy(mut self, policy: ExchangePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Longest an order placement can take from the moment it is made: the
+    /// governor's wait for room, then the call's timeout. Writes aren't
+    /// retried, so past this an attempt has either landed or given up.
+    pub fn submit_deadline(&self) -> std::time::Duration {
+        self.policy.policy().timeout_for(self.exchange) + std::time::Duration::from_millis(MAX_QUEUE_WAIT_MS as u64)
+    }
+
+    /// State of the exchange's circuit breaker, if it's tracked
+    pub async fn circuit_status(&self) -> Option<CircuitStatus> {
+        self.policy.status(self.exchange).await
+    }
+
+    /// Count this client's calls against the exchange's limits
+    pub fn with_governor(mut self, governor: GovernorClient) -> Self {
+        self.governor = governor;
+        self
+    }
+
+    /// Live usage of the exchange's rate limits by this client's API key
+    pub async fn rate_limit_usage(&self) -> Vec<LimitUsage> {
+        self.governor.usage(self.exchange, &self.governor_name()).await
+    }
+
     /// Create trading client from environment variables
     pub fn from_env(exchange: Exchange) -> Result<Self, String> {
         console_log!("TRADING CLIENT: Creating client from environment variables for {:?}", exchange);
@@ -159,7 +277,7 @@ impl TradingClient {
         let endpoint = self.build_quote_endpoint(instrument)?;
         
         // Make HTTP request to exchange API
-        match self.make_request(&endpoint, "GET", None).await {
+        match self.make_request(ExchangeCall::Quote, &endpoint, "GET", None).await {
             Ok(response) => {
                 console_log!("TRADING CLIENT: Successfully fetched quote data");
                 self.parse_quote_response(instrument, &response)
@@ -177,7 +295,7 @@ impl TradingClient {
         
         let endpoint = self.build_order_book_endpoint(instrument, depth)?;
         
-        match self.make_request(&endpoint, "GET", None).await {
+        match self.make_request(ExchangeCall::OrderBook { depth }, &endpoint, "GET", None).await {
             Ok(response) => {
                 console_log!("TRADING CLIENT: Successfully fetched order book data");
                 self.parse_order_book_response(instrument, &response)
@@ -189,6 +307,155 @@ impl TradingClient {
         }
     }
 
+    /// Get the most recent public trades for an instrument, newest first.
+    ///
+    /// `limit` is capped at the most the exchange returns in one request.
+    pub async fn get_recent_trades(&self, instrument: &SimpleInstrument, limit: u32) -> Result<Vec<PublicTrade>, String> {
+        console_log!("TRADING CLIENT: Fetching recent trades for instrument: {:?} with limit: {}", instrument, limit);
+
+        let limit = limit.clamp(1, self.max_recent_trades());
+        let endpoint = self.build_recent_trades_endpoint(instrument, limit)?;
+
+        match self.make_request(ExchangeCall::RecentTrades, &endpoint, "GET", None).await {
+            Ok(response) => {
+                console_log!("TRADING CLIENT: Successfully fetched recent trades");
+                let mut trades = self.parse_recent_trades_response(&response)?;
+                trades.sort_by_key(|trade| std::cmp::Reverse(trade.executed_at));
+                trades.truncate(limit as usize);
+                Ok(trades)
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to fetch recent trades: {}", e);
+                Err(format!("Failed to fetch recent trades: {}", e))
+            }
+        }
+    }
+
+    /// Get klines with an open time in `[start, end)`, oldest first.
+    ///
+    /// Returns at most `max_klines()` bars; callers page through longer
+    /// ranges. Buckets without trades may be missing.
+    pub async fn get_klines(
+        &self,
+        instrument: &SimpleInstrument,
+        interval: CandleInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Kline>, String> {
+        console_log!("TRADING CLIENT: Fetching {} klines for instrument: {:?} from {} to {}",
+            interval.as_str(), instrument, start, end);
+
+        let endpoint = self.build_klines_endpoint(instrument, interval, start, end)?;
+
+        match self.make_request(ExchangeCall::Klines, &endpoint, "GET", None).await {
+            Ok(response) => {
+                console_log!("TRADING CLIENT: Successfully fetched klines");
+                let mut klines = self.parse_klines_response(&response)?;
+                klines.retain(|kline| kline.open_time >= start && kline.open_time < end);
+                klines.sort_by_key(|kline| kline.open_time);
+                klines.truncate(self.max_klines() as usize);
+                Ok(klines)
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to fetch klines: {}", e);
+                Err(format!("Failed to fetch klines: {}", e))
+            }
+        }
+    }
+
+    /// Whether the exchange's kline API offers this interval
+    pub fn supports_kline_interval(&self, interval: CandleInterval) -> bool {
+        self.kline_interval(interval).is_some()
+    }
+
+    /// Most klines the exchange returns per request
+    pub fn max_klines(&self) -> u32 {
+        match self.exchange {
+            Exchange::Coinbase => 300,
+            Exchange::Kraken => 720,
+            Exchange::Okx => 100,
+            _ => 1000,
+        }
+    }
+
+    /// Taker fee rate at the exchange's entry volume tier, e.g. 0.001 for 0.1%
+    pub fn taker_fee_rate(&self) -> Decimal {
+        match self.exchange {
+            Exchange::Binance => Decimal::new(10, 4),
+            Exchange::BinanceFuturesUsd => Decimal::new(5, 4),
+            Exchange::Coinbase => Decimal::new(60, 4),
+            Exchange::Kraken => Decimal::new(40, 4),
+            Exchange::Okx => Decimal::new(10, 4),
+            Exchange::Bybit => Decimal::new(10, 4),
+        }
+    }
+
+    /// Get the mark price for an instrument.
+    ///
+    /// Only derivatives venues publish a mark price; spot venues fall back to the
+    /// mid of the current quote.
+    pub async fn get_mark_price(&self, instrument: &SimpleInstrument) -> Result<Decimal, String> {
+        console_log!("TRADING CLIENT: Fetching mark price for instrument: {:?}", instrument);
+
+        let endpoint = match self.build_mark_price_endpoint(instrument)? {
+            Some(endpoint) => endpoint,
+            None => {
+                let quote = self.get_quote(instrument).await?;
+                return Ok((quote.bid + quote.ask) / Decimal::new(2, 0));
+            }
+        };
+
+        match self.make_request(ExchangeCall::MarkPrice, &endpoint, "GET", None).await {
+            Ok(response) => {
+                console_log!("TRADING CLIENT: Successfully fetched mark price");
+                self.parse_mark_price_response(&response)
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to fetch mark price: {}", e);
+                Err(format!("Failed to fetch mark price: {}", e))
+            }
+        }
+    }
+
+    /// Whether the exchange accepts this trigger order natively.
+    ///
+    /// Orders that return false here have to be held and fired by the server-side
+    /// trigger monitor instead.
+    pub fn supports_native_trigger(&self, order_type: &OrderType, trigger_by: TriggerBy) -> bool {
+        let is_limit = matches!(order_type, OrderType::StopLossLimit | OrderType::TakeProfitLimit);
+
+        match order_type {
+            OrderType::Market | OrderType::Limit => true,
+            _ => match self.exchange {
+                // Spot stop orders on Binance always trigger on the last traded price
+                Exchange::Binance => trigger_by == TriggerBy::LastPrice,
+                // USD-M futures expose workingType=MARK_PRICE|CONTRACT_PRICE
+                Exchange::BinanceFuturesUsd => true,
+                // Coinbase only offers stop-limit orders, triggered by last trade
+                Exchange::Coinbase => is_limit && trigger_by == TriggerBy::LastPrice,
+                // Kraken supports trigger=last|index for all four stop types
+                Exchange::Kraken => true,
+                // Bybit supports triggerBy=LastPrice|MarkPrice
+                Exchange::Bybit => true,
+                // OKX trigger orders live on the separate algo-order API
+                Exchange::Okx => false,
+            },
+        }
+    }
+
+    /// Whether the exchange can hold a take-profit limit and a stop-loss as one
+    /// OCO pair, where either order filling cancels the other.
+    ///
+    /// Otherwise both legs resting at once would each lock the full size, so
+    /// the stop has to be held by the server-side trigger monitor instead.
+    pub fn supports_native_oco(&self, trigger_by: TriggerBy) -> bool {
+        match self.exchange {
+            // Spot order lists, whose stops trigger on the last traded price
+            Exchange::Binance => trigger_by == TriggerBy::LastPrice,
+            _ => false,
+        }
+    }
+
     /// Get account balances
     pub async fn get_balances(&self) -> Result<Vec<Balance>, String> {
         console_log!("TRADING CLIENT: Fetching account balances");
@@ -199,7 +466,7 @@ impl TradingClient {
 
         let endpoint = self.build_balance_endpoint()?;
         
-        match self.make_authenticated_request(&endpoint, "GET", None).await {
+        match self.make_authenticated_request(ExchangeCall::Balances, &endpoint, "GET", None).await {
             Ok(response) => {
                 console_log!("TRADING CLIENT: Successfully fetched balance data");
                 self.parse_balance_response(&response)
@@ -223,7 +490,7 @@ impl TradingClient {
         let endpoint = self.build_order_endpoint()?;
         let payload = self.build_order_payload(order)?;
         
-        match self.make_authenticated_request(&endpoint, "POST", Some(payload)).await {
+        match self.make_authenticated_request(ExchangeCall::PlaceOrder, &endpoint, "POST", Some(payload)).await {
             Ok(response) => {
                 console_log!("TRADING CLIENT: Successfully placed order");
                 self.parse_order_response(&response)
@@ -235,26 +502,233 @@ impl TradingClient {
         }
     }
 
+    /// Place a take-profit limit and a stop-loss as one exchange-held OCO
+    /// pair. Returns the exchange ids of the take-profit and the stop-loss.
+    pub async fn place_oco_order(&self, take_profit: &OrderRequest, stop_loss: &OrderRequest) -> Result<(String, String), String> {
+        console_log!("TRADING CLIENT: Placing OCO: {:?} {} {:?} (TP: {:?}, SL: {:?})",
+            take_profit.side, take_profit.quantity, take_profit.instrument, take_profit.price, stop_loss.stop_price);
+
+        if !self.supports_native_oco(stop_loss.trigger_by) {
+            return Err(format!("{:?} does not support OCO orders", self.exchange));
+        }
+        if self.api_key.is_none() || self.api_secret.is_none() {
+            return Err("API credentials required for order placement".to_string());
+        }
+
+        let endpoint = format!("{}/api/v3/orderList/oco", self.base_url);
+        let symbol = self.format_symbol(&take_profit.instrument)?;
+        let payload = binance_oco_payload(&symbol, take_profit, stop_loss)?;
+
+        match self.make_authenticated_request(ExchangeCall::PlaceOco, &endpoint, "POST", Some(payload)).await {
+            Ok(response) => {
+                console_log!("TRADING CLIENT: Successfully placed OCO");
+                parse_oco_response(&response, take_profit, stop_loss)
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to place OCO: {}", e);
+                Err(format!("Failed to place OCO: {}", e))
+            }
+        }
+    }
+
+    /// Cancel an open order
+    pub async fn cancel_order(&self, instrument: &SimpleInstrument, exchange_order_id: &str) -> Result<(), String> {
+        console_log!("TRADING CLIENT: Cancelling order {} for {:?}", exchange_order_id, instrument);
+
+        if self.api_key.is_none() || self.api_secret.is_none() {
+            return Err("API credentials required for order cancellation".to_string());
+        }
+
+        let endpoint = self.build_order_status_endpoint(instrument, exchange_order_id)?;
+
+        match self.make_authenticated_request(ExchangeCall::CancelOrder, &endpoint, "DELETE", None).await {
+            Ok(_) => {
+                console_log!("TRADING CLIENT: Successfully cancelled order {}", exchange_order_id);
+                Ok(())
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to cancel order: {}", e);
+                Err(format!("Failed to cancel order: {}", e))
+            }
+        }
+    }
+
+    /// Get the current state of an order
+    pub async fn get_order_status(&self, instrument: &SimpleInstrument, exchange_order_id: &str) -> Result<OrderStatusReport, String> {
+        console_log!("TRADING CLIENT: Fetching status of order {} for {:?}", exchange_order_id, instrument);
+
+        if self.api_key.is_none() || self.api_secret.is_none() {
+            return Err("API credentials required for order queries".to_string());
+        }
+
+        let endpoint = self.build_order_status_endpoint(instrument, exchange_order_id)?;
+
+        match self.make_authenticated_request(ExchangeCall::OrderStatus, &endpoint, "GET", None).await {
+            Ok(response) => {
+                console_log!("TRADING CLIENT: Successfully fetched order status");
+                self.parse_order_status_response(exchange_order_id, &response)
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to fetch order status: {}", e);
+                Err(format!("Failed to fetch order status: {}", e))
+            }
+        }
+    }
+
+    /// Look up an order by the client order id it was submitted with.
+    ///
+    /// Used to find out what happened to an order whose placement request
+    /// failed without a definite answer from the exchange. `Ok(None)` means
+    /// the exchange answered that it has no such order.
+    pub async fn get_order_by_client_id(&self, instrument: &SimpleInstrument, client_order_id: &str) -> Result<Option<OrderStatusReport>, String> {
+        console_log!("TRADING CLIENT: Looking up order by client id {} for {:?}", client_order_id, instrument);
+
+        if self.api_key.is_none() || self.api_secret.is_none() {
+            return Err("API credentials required for order queries".to_string());
+        }
+
+        let endpoint = self.build_client_order_endpoint(instrument, client_order_id)?;
+
+        match self.execute_request(ExchangeCall::OrderStatus, &endpoint, "GET", None).await {
+            Ok(response) => {
+                console_log!("TRADING CLIENT: Found order for client id {}", client_order_id);
+                let exchange_order_id = self.parse_order_response(&response)?;
+                self.parse_order_status_response(&exchange_order_id, &response).map(Some)
+            }
+            Err(CallError::NotFound(e)) => {
+                console_log!("TRADING CLIENT: No order with client id {}: {}", client_order_id, e);
+                Ok(None)
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to look up order by client id: {}", e);
+                Err(format!("Failed to look up order by client id: {}", e))
+            }
+        }
+    }
+
+    /// Get the individual fills of an order
+    pub async fn get_order_fills(&self, instrument: &SimpleInstrument, exchange_order_id: &str) -> Result<Vec<Fill>, String> {
+        console_log!("TRADING CLIENT: Fetching fills of order {} for {:?}", exchange_order_id, instrument);
+
+        if self.api_key.is_none() || self.api_secret.is_none() {
+            return Err("API credentials required for trade queries".to_string());
+        }
+
+        let endpoint = self.build_fills_endpoint(instrument, exchange_order_id)?;
+
+        match self.make_authenticated_request(ExchangeCall::Fills, &endpoint, "GET", None).await {
+            Ok(response) => {
+                console_log!("TRADING CLIENT: Successfully fetched order fills");
+                self.parse_fills_response(&response)
+            }
+            Err(e) => {
+                console_log!("TRADING CLIENT: Failed to fetch order fills: {}", e);
+                Err(format!("Failed to fetch order fills: {}", e))
+            }
+        }
+    }
+
     /// Make HTTP request to exchange API
-    async fn make_request(&self, endpoint: &str, method: &str, payload: Option<Value>) -> Result<Value, String> {
+    async fn make_request(&self, call: ExchangeCall, endpoint: &str, method: &str, payload: Option<Value>) -> Result<Value, String> {
         console_log!("TRADING CLIENT: Making {} request to: {}", method, endpoint);
         
         // For now, simulate API responses based on the endpoint
         // In production, this would make actual HTTP requests using gloo-net
-        self.simulate_api_response(endpoint, method, payload).await
+        self.execute_request(call, endpoint, method, payload).await.map_err(|e| e.to_string())
     }
 
     /// Make authenticated HTTP request to exchange API
-    async fn make_authenticated_request(&self, endpoint: &str, method: &str, payload: Option<Value>) -> Result<Value, String> {
+    async fn make_authenticated_request(&self, call: ExchangeCall, endpoint: &str, method: &str, payload: Option<Value>) -> Result<Value, String> {
         console_log!("TRADING CLIENT: Making authenticated {} request to: {}", method, endpoint);
         
         // Add authentication headers and signature
         // For now, simulate authenticated responses
-        self.simulate_api_response(endpoint, method, payload).await
+        self.execute_request(call, endpoint, method, payload).await.map_err(|e| e.to_string())
+    }
+
+    /// Send a request under the call policy: refused while the exchange's
+    /// circuit is open, and retried with backoff if it only reads and timed
+    /// out or found the exchange unavailable
+    async fn execute_request(&self, call: ExchangeCall, endpoint: &str, method: &str, payload: Option<Value>) -> Result<Value, CallError> {
+        let policy = self.policy.policy();
+        let circuit = self.policy.check(self.exchange).await?;
+        let attempts = if call.is_read() { policy.read_retries + 1 } else { 1 };
+
+        let mut attempt = 1;
+        loop {
+            match self.governed_request(call, endpoint, method, payload.clone()).await {
+                Ok(response) => {
+                    self.policy.record_success(self.exchange, circuit.as_ref()).await;
+                    return Ok(response);
+                }
+                Err(e) if e.is_retryable() && attempt < attempts => {
+                    let delay = policy.retry_delay(attempt - 1);
+                    console_log!("TRADING CLIENT: Retrying {:?} call in {}ms after: {}", call, delay.as_millis(), e);
+                    Delay::from(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    if e.counts_against_circuit() {
+                        self.policy.record_failure(self.exchange, &e).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Send a request once the governor has room for it, giving up after the
+    /// timeout, then pass on the usage the exchange reported with the response
+    async fn governed_request(&self, call: ExchangeCall, endpoint: &str, method: &str, payload: Option<Value>) -> Result<Value, CallError> {
+        let exchange = exchange_name(self.exchange);
+        let governor_name = self.governor_name();
+        self.governor.acquire(self.exchange, &governor_name, call).await.map_err(CallError::RateLimited)?;
+
+        let timeout = self.policy.policy().timeout_for(self.exchange);
+        let controller = AbortController::default();
+        let request = self.send_request(endpoint, method, payload, controller.signal());
+        let response = match select(Box::pin(request), Box::pin(Delay::from(timeout))).await {
+            Either::Left((response, _)) => response?,
+            Either::Right(_) => {
+                controller.abort();
+                return Err(CallError::Timeout(format!("{} didn't answer within {}ms", exchange, timeout.as_millis())));
+            }
+        };
+
+        let report = ResponseReport::from_headers(self.exchange, call, response.status, &response.headers);
+        self.governor.observe(self.exchange, &governor_name, report).await;
+
+        if call == ExchangeCall::OrderStatus && is_unknown_order(self.exchange, response.status, &response.body) {
+            return Err(CallError::NotFound(format!("{} has no such order: {}", exchange, response.body)));
+        }
+        match response.status {
+            200..=299 => Ok(response.body),
+            418 | 429 => Err(CallError::RateLimited(format!("{} is rate limiting us ({})", exchange, response.status))),
+            500..=599 => Err(CallError::Unavailable(format!("{} answered {}: {}", exchange, response.status, response.body))),
+            status => Err(CallError::Rejected(format!("{} answered {}: {}", exchange, status, response.body))),
+        }
+    }
+
+    /// Send a request to the exchange. Real requests hand the signal to fetch,
+    /// so a timeout cancels them; simulated ones answer at once, with no
+    /// rate limit headers.
+    async fn send_request(&self, endpoint: &str, method: &str, payload: Option<Value>, _signal: AbortSignal) -> Result<RawResponse, CallError> {
+        let body = self.simulate_api_response(endpoint, method, payload).await.map_err(CallError::Rejected)?;
+        Ok(RawResponse { status: 200, headers: HashMap::new(), body })
+    }
+
+    /// Name of the governor object counting this client's calls: one per
+    /// exchange and API key, with keyless clients sharing one
+    fn governor_name(&self) -> String {
+        let key = match &self.api_key {
+            Some(api_key) => format!("{:x}", Sha256::digest(api_key.as_bytes()))[..16].to_string(),
+            None => "public".to_string(),
+        };
+        format!("{:?}:{}", self.exchange, key).to_lowercase()
     }
 
     /// Simulate API responses for development/testing
-    async fn simulate_api_response(&self, endpoint: &str, _method: &str, _payload: Option<Value>) -> Result<Value, String> {
+    async fn simulate_api_response(&self, endpoint: &str, _method: &str, payload: Option<Value>) -> Result<Value, String> {
         console_log!("TRADING CLIENT: Simulating API response for endpoint: {}", endpoint);
         
         // Generate realistic mock responses based on endpoint
@@ -279,6 +753,16 @@ impl TradingClient {
                 ],
                 "timestamp": Utc::now().timestamp_millis()
             }))
+        } else if endpoint.contains("klines?") || endpoint.contains("kline?") || endpoint.contains("candles?") || endpoint.contains("OHLC?") {
+            Ok(self.simulate_klines(endpoint))
+        } else if endpoint.contains("/trades?") || endpoint.contains("/Trades?") || endpoint.contains("recent-trade") {
+            Ok(self.simulate_recent_trades())
+        } else if endpoint.contains("premiumIndex") || endpoint.contains("tickers?category=linear") {
+            Ok(json!({
+                "symbol": "BTCUSDT",
+                "markPrice": "45000.80",
+                "time": Utc::now().timestamp_millis()
+            }))
         } else if endpoint.contains("account") || endpoint.contains("balance") {
             Ok(json!({
                 "balances": [
@@ -294,6 +778,31 @@ impl TradingClient {
                     }
                 ]
             }))
+        } else if endpoint.contains("myTrades") || endpoint.contains("userTrades") || endpoint.contains("fills") {
+            Ok(json!([
+                {
+                    "id": format!("trade_{}", Utc::now().timestamp_millis()),
+                    "orderId": format!("order_{}", Utc::now().timestamp()),
+                    "price": "45000.50",
+                    "qty": "0.001",
+                    "commission": "0.045",
+                    "commissionAsset": "USDT",
+                    "isMaker": false,
+                    "time": Utc::now().timestamp_millis()
+                }
+            ]))
+        } else if endpoint.contains("orderList") {
+            let payload = payload.unwrap_or_default();
+            let now = Utc::now().timestamp();
+            Ok(json!({
+                "orderListId": now,
+                "listStatusType": "EXEC_STARTED",
+                "symbol": payload["symbol"],
+                "orders": [
+                    {"symbol": payload["symbol"], "orderId": format!("order_{}_1", now), "clientOrderId": payload["aboveClientOrderId"]},
+                    {"symbol": payload["symbol"], "orderId": format!("order_{}_2", now), "clientOrderId": payload["belowClientOrderId"]}
+                ]
+            }))
         } else if endpoint.contains("order") {
             Ok(json!({
                 "orderId": format!("order_{}", Utc::now().timestamp()),
@@ -302,13 +811,170 @@ impl TradingClient {
                 "side": "BUY",
                 "type": "LIMIT",
                 "quantity": "0.001",
-                "price": "45000.00"
+                "price": "45000.00",
+                "executedQty": "0.000"
             }))
         } else {
             Err("Unknown endpoint".to_string())
         }
     }
 
+    /// Simulated public trades in the exchange's own response format
+    fn simulate_recent_trades(&self) -> Value {
+        let now = Utc::now().timestamp_millis();
+        let trades = [("45000.50", "0.012", true), ("45001.25", "0.250", false), ("45000.75", "0.003", false)];
+
+        match self.exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => Value::Array(trades.iter().enumerate()
+                .map(|(i, (price, qty, buyer_maker))| json!({
+                    "id": 3_500_000_000i64 - i as i64,
+                    "price": price,
+                    "qty": qty,
+                    "time": now - i as i64 * 1000,
+                    "isBuyerMaker": buyer_maker,
+                }))
+                .collect()),
+            Exchange::Coinbase => Value::Array(trades.iter().enumerate()
+                .map(|(i, (price, size, buyer_maker))| json!({
+                    "trade_id": 650_000_000i64 - i as i64,
+                    "price": price,
+                    "size": size,
+                    "time": DateTime::from_timestamp_millis(now - i as i64 * 1000).unwrap_or_default().to_rfc3339(),
+                    "side": if *buyer_maker { "buy" } else { "sell" },
+                }))
+                .collect()),
+            Exchange::Kraken => json!({
+                "error": [],
+                "result": {
+                    "XXBTZUSD": trades.iter().enumerate()
+                        .map(|(i, (price, volume, buyer_maker))| json!([
+                            price,
+                            volume,
+                            (now - i as i64 * 1000) as f64 / 1000.0,
+                            if *buyer_maker { "s" } else { "b" },
+                            "m",
+                            "",
+                            80_000_000i64 - i as i64,
+                        ]))
+                        .collect::<Vec<_>>(),
+                    "last": format!("{}", now * 1_000_000),
+                }
+            }),
+            Exchange::Okx => json!({
+                "code": "0",
+                "data": trades.iter().enumerate()
+                    .map(|(i, (price, size, buyer_maker))| json!({
+                        "tradeId": format!("{}", 600_000_000i64 - i as i64),
+                        "px": price,
+                        "sz": size,
+                        "side": if *buyer_maker { "sell" } else { "buy" },
+                        "ts": format!("{}", now - i as i64 * 1000),
+                    }))
+                    .collect::<Vec<_>>(),
+            }),
+            Exchange::Bybit => json!({
+                "retCode": 0,
+                "result": {
+                    "category": "spot",
+                    "list": trades.iter().enumerate()
+                        .map(|(i, (price, size, buyer_maker))| json!({
+                            "execId": format!("{}", 2_200_000_000i64 - i as i64),
+                            "price": price,
+                            "size": size,
+                            "side": if *buyer_maker { "Sell" } else { "Buy" },
+                            "time": format!("{}", now - i as i64 * 1000),
+                        }))
+                        .collect::<Vec<_>>(),
+                }
+            }),
+        }
+    }
+
+    /// Simulated klines for the requested interval and start, in the
+    /// exchange's own response format
+    fn simulate_klines(&self, endpoint: &str) -> Value {
+        let params: HashMap<String, String> = worker::Url::parse(endpoint)
+            .map(|url| url.query_pairs().into_owned().collect())
+            .unwrap_or_default();
+        let param = |name: &str| params.get(name).cloned().unwrap_or_default();
+
+        let (interval_param, start_param, start_scale) = match self.exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => ("interval", "startTime", 1),
+            Exchange::Coinbase => ("granularity", "start", 1000),
+            Exchange::Kraken => ("interval", "since", 1000),
+            Exchange::Okx => ("bar", "before", 1),
+            Exchange::Bybit => ("interval", "start", 1),
+        };
+        let Some(interval) = CandleInterval::ALL.into_iter()
+            .find(|interval| self.kline_interval(*interval) == Some(param(interval_param))) else {
+            return json!([]);
+        };
+        let start = param(start_param).parse::<i64>().unwrap_or_default() * start_scale;
+        let step = interval.seconds() * 1000;
+        let first = (start + step - 1).div_euclid(step) * step;
+        let now = Utc::now().timestamp_millis();
+
+        let bars: Vec<(i64, Decimal)> = (0..5)
+            .map(|i| (first + i * step, Decimal::new(4_500_000 + i * 125, 2)))
+            .filter(|(open_time, _)| *open_time <= now)
+            .collect();
+        let row = |open_time: i64, price: Decimal| -> [String; 5] {
+            let high = price + Decimal::new(2_50, 2);
+            let low = price - Decimal::new(1_75, 2);
+            let close = price + Decimal::ONE;
+            [price.to_string(), high.to_string(), low.to_string(), close.to_string(), format!("{}", 10 + open_time % 7)]
+        };
+
+        match self.exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => Value::Array(bars.iter()
+                .map(|(open_time, price)| {
+                    let [o, h, l, c, v] = row(*open_time, *price);
+                    json!([open_time, o, h, l, c, v, open_time + step - 1, "0", 42])
+                })
+                .collect()),
+            Exchange::Coinbase => Value::Array(bars.iter().rev()
+                .map(|(open_time, price)| {
+                    let [o, h, l, c, v] = row(*open_time, *price);
+                    json!([open_time / 1000, l.parse::<f64>().unwrap_or_default(), h.parse::<f64>().unwrap_or_default(),
+                        o.parse::<f64>().unwrap_or_default(), c.parse::<f64>().unwrap_or_default(), v.parse::<f64>().unwrap_or_default()])
+                })
+                .collect()),
+            Exchange::Kraken => json!({
+                "error": [],
+                "result": {
+                    "XXBTZUSD": bars.iter()
+                        .map(|(open_time, price)| {
+                            let [o, h, l, c, v] = row(*open_time, *price);
+                            json!([open_time / 1000, o, h, l, c, o, v, 42])
+                        })
+                        .collect::<Vec<_>>(),
+                    "last": now / 1000,
+                }
+            }),
+            Exchange::Okx => json!({
+                "code": "0",
+                "data": bars.iter().rev()
+                    .map(|(open_time, price)| {
+                        let [o, h, l, c, v] = row(*open_time, *price);
+                        json!([open_time.to_string(), o, h, l, c, v, "0", "0", "1"])
+                    })
+                    .collect::<Vec<_>>(),
+            }),
+            Exchange::Bybit => json!({
+                "retCode": 0,
+                "result": {
+                    "category": "spot",
+                    "list": bars.iter().rev()
+                        .map(|(open_time, price)| {
+                            let [o, h, l, c, v] = row(*open_time, *price);
+                            json!([open_time.to_string(), o, h, l, c, v, "0"])
+                        })
+                        .collect::<Vec<_>>(),
+                }
+            }),
+        }
+    }
+
     /// Build quote endpoint URL for the exchange
     fn build_quote_endpoint(&self, instrument: &SimpleInstrument) -> Result<String, String> {
         let symbol = self.format_symbol(instrument)?;
@@ -337,6 +1003,97 @@ impl TradingClient {
         Ok(endpoint)
     }
 
+    /// Most trades the exchange's public trades endpoint returns per request
+    fn max_recent_trades(&self) -> u32 {
+        match self.exchange {
+            Exchange::Okx => 500,
+            Exchange::Bybit => 60,
+            _ => 1000,
+        }
+    }
+
+    /// Build public recent trades endpoint URL for the exchange
+    fn build_recent_trades_endpoint(&self, instrument: &SimpleInstrument, limit: u32) -> Result<String, String> {
+        let symbol = self.format_symbol(instrument)?;
+
+        let endpoint = match self.exchange {
+            Exchange::Binance => format!("{}/api/v3/trades?symbol={}&limit={}", self.base_url, symbol, limit),
+            Exchange::BinanceFuturesUsd => format!("{}/fapi/v1/trades?symbol={}&limit={}", self.base_url, symbol, limit),
+            Exchange::Coinbase => format!("{}/products/{}/trades?limit={}", self.base_url, symbol, limit),
+            Exchange::Kraken => format!("{}/0/public/Trades?pair={}&count={}", self.base_url, symbol, limit),
+            Exchange::Okx => format!("{}/api/v5/market/trades?instId={}-{}&limit={}", self.base_url, instrument.base, instrument.quote, limit),
+            Exchange::Bybit => format!("{}/v5/market/recent-trade?category=spot&symbol={}&limit={}", self.base_url, symbol, limit),
+        };
+
+        Ok(endpoint)
+    }
+
+    /// The exchange's code for a kline interval
+    fn kline_interval(&self, interval: CandleInterval) -> Option<String> {
+        let code = match (self.exchange, interval) {
+            (Exchange::Binance | Exchange::BinanceFuturesUsd, _) => interval.as_str().to_string(),
+            // Coinbase has 6h buckets but no 4h ones
+            (Exchange::Coinbase, CandleInterval::FourHours) => return None,
+            (Exchange::Coinbase, _) => interval.seconds().to_string(),
+            (Exchange::Kraken, _) => (interval.seconds() / 60).to_string(),
+            (Exchange::Okx, CandleInterval::OneHour) => "1H".to_string(),
+            (Exchange::Okx, CandleInterval::FourHours) => "4H".to_string(),
+            (Exchange::Okx, CandleInterval::OneDay) => "1Dutc".to_string(),
+            (Exchange::Okx, _) => interval.as_str().to_string(),
+            (Exchange::Bybit, CandleInterval::OneDay) => "D".to_string(),
+            (Exchange::Bybit, _) => (interval.seconds() / 60).to_string(),
+        };
+        Some(code)
+    }
+
+    /// Build kline endpoint URL for the exchange
+    fn build_klines_endpoint(
+        &self,
+        instrument: &SimpleInstrument,
+        interval: CandleInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<String, String> {
+        let symbol = self.format_symbol(instrument)?;
+        let code = self.kline_interval(interval)
+            .ok_or_else(|| format!("{:?} has no {} klines", self.exchange, interval.as_str()))?;
+        let start_ms = start.timestamp_millis();
+        let end_ms = end.timestamp_millis();
+        let limit = self.max_klines();
+
+        let endpoint = match self.exchange {
+            Exchange::Binance => format!("{}/api/v3/klines?symbol={}&interval={}&startTime={}&endTime={}&limit={}",
+                self.base_url, symbol, code, start_ms, end_ms - 1, limit),
+            Exchange::BinanceFuturesUsd => format!("{}/fapi/v1/klines?symbol={}&interval={}&startTime={}&endTime={}&limit={}",
+                self.base_url, symbol, code, start_ms, end_ms - 1, limit),
+            Exchange::Coinbase => format!("{}/products/{}/candles?granularity={}&start={}&end={}",
+                self.base_url, symbol, code, start.timestamp(), end.timestamp() - 1),
+            // Kraken only takes a start and returns up to 720 bars after it
+            Exchange::Kraken => format!("{}/0/public/OHLC?pair={}&interval={}&since={}",
+                self.base_url, symbol, code, start.timestamp() - 1),
+            // OKX pages backwards: after/before are exclusive bounds on the open time
+            Exchange::Okx => format!("{}/api/v5/market/history-candles?instId={}-{}&bar={}&after={}&before={}&limit={}",
+                self.base_url, instrument.base, instrument.quote, code, end_ms, start_ms - 1, limit),
+            Exchange::Bybit => format!("{}/v5/market/kline?category=spot&symbol={}&interval={}&start={}&end={}&limit={}",
+                self.base_url, symbol, code, start_ms, end_ms - 1, limit),
+        };
+
+        Ok(endpoint)
+    }
+
+    /// Build mark price endpoint URL for the exchange, if the venue publishes one
+    fn build_mark_price_endpoint(&self, instrument: &SimpleInstrument) -> Result<Option<String>, String> {
+        let symbol = self.format_symbol(instrument)?;
+
+        let endpoint = match self.exchange {
+            Exchange::BinanceFuturesUsd => Some(format!("{}/fapi/v1/premiumIndex?symbol={}", self.base_url, symbol)),
+            Exchange::Bybit => Some(format!("{}/v5/market/tickers?category=linear&symbol={}", self.base_url, symbol)),
+            _ => None,
+        };
+
+        Ok(endpoint)
+    }
+
     /// Build balance endpoint URL for the exchange
     fn build_balance_endpoint(&self) -> Result<String, String> {
         let endpoint = match self.exchange {
@@ -361,6 +1118,84 @@ impl TradingClient {
         Ok(endpoint)
     }
 
+    /// Build the endpoint URL addressing a single order (query and cancel)
+    fn build_order_status_endpoint(&self, instrument: &SimpleInstrument, exchange_order_id: &str) -> Result<String, String> {
+        let symbol = self.format_symbol(instrument)?;
+
+        let endpoint = match self.exchange {
+            Exchange::Binance => format!("{}/api/v3/order?symbol={}&orderId={}", self.base_url, symbol, exchange_order_id),
+            Exchange::BinanceFuturesUsd => format!("{}/fapi/v1/order?symbol={}&orderId={}", self.base_url, symbol, exchange_order_id),
+            Exchange::Coinbase => format!("{}/orders/{}", self.base_url, exchange_order_id),
+            Exchange::Kraken => format!("{}/0/private/QueryOrders?txid={}", self.base_url, exchange_order_id),
+            _ => format!("{}/api/v3/order?symbol={}&orderId={}", self.base_url, symbol, exchange_order_id),
+        };
+
+        Ok(endpoint)
+    }
+
+    /// Build the endpoint URL listing the fills of an order
+    fn build_fills_endpoint(&self, instrument: &SimpleInstrument, exchange_order_id: &str) -> Result<String, String> {
+        let symbol = self.format_symbol(instrument)?;
+
+        let endpoint = match self.exchange {
+            Exchange::Binance => format!("{}/api/v3/myTrades?symbol={}&orderId={}", self.base_url, symbol, exchange_order_id),
+            Exchange::BinanceFuturesUsd => format!("{}/fapi/v1/userTrades?symbol={}&orderId={}", self.base_url, symbol, exchange_order_id),
+            Exchange::Coinbase => format!("{}/fills?order_id={}", self.base_url, exchange_order_id),
+            _ => format!("{}/api/v3/myTrades?symbol={}&orderId={}", self.base_url, symbol, exchange_order_id),
+        };
+
+        Ok(endpoint)
+    }
+
+    /// Build the endpoint URL looking up an order by client order id
+    fn build_client_order_endpoint(&self, instrument: &SimpleInstrument, client_order_id: &str) -> Result<String, String> {
+        let symbol = self.format_symbol(instrument)?;
+        let client_order_id = self.format_client_order_id(client_order_id);
+
+        let endpoint = match self.exchange {
+            Exchange::Binance => format!("{}/api/v3/order?symbol={}&origClientOrderId={}", self.base_url, symbol, client_order_id),
+            Exchange::BinanceFuturesUsd => format!("{}/fapi/v1/order?symbol={}&origClientOrderId={}", self.base_url, symbol, client_order_id),
+            Exchange::Coinbase => format!("{}/orders/client:{}", self.base_url, client_order_id),
+            Exchange::Kraken => format!("{}/0/private/QueryOrders?cl_ord_id={}", self.base_url, client_order_id),
+            Exchange::Okx => format!("{}/api/v5/trade/order?instId={}&clOrdId={}", self.base_url, symbol, client_order_id),
+            Exchange::Bybit => format!("{}/v5/order/realtime?category=spot&symbol={}&orderLinkId={}", self.base_url, symbol, client_order_id),
+        };
+
+        Ok(endpoint)
+    }
+
+    /// Map an exchange-specific asset code to the common ticker, e.g. Kraken's
+    /// XXBT and ZUSD to BTC and USD. Balances and fill commissions come out
+    /// of the client already mapped, so nothing past it sees exchange codes.
+    pub fn normalize_asset(&self, asset: &str) -> String {
+        let asset = asset.trim().to_uppercase();
+        match self.exchange {
+            Exchange::Kraken => {
+                // Staked and opt-in rewards balances carry a suffix, e.g. ETH2.S or DOT.F
+                let code = asset.split('.').next().unwrap_or_default();
+                let code = match code.len() {
+                    4 if code.starts_with('X') || code.starts_with('Z') => &code[1..],
+                    _ => code,
+                };
+                match code {
+                    "XBT" => "BTC".to_string(),
+                    "XDG" => "DOGE".to_string(),
+                    "ETH2" => "ETH".to_string(),
+                    other => other.to_string(),
+                }
+            }
+            _ => asset,
+        }
+    }
+
+    /// Client order ids are UUIDs; OKX only accepts plain alphanumerics
+    fn format_client_order_id(&self, client_order_id: &str) -> String {
+        match self.exchange {
+            Exchange::Okx => client_order_id.replace('-', ""),
+            _ => client_order_id.to_string(),
+        }
+    }
+
     /// Format instrument symbol for the specific exchange
     fn format_symbol(&self, instrument: &SimpleInstrument) -> Result<String, String> {
         match self.exchange {
@@ -443,6 +1278,19 @@ impl TradingClient {
         })
     }
 
+    /// Parse mark price response from exchange API
+    fn parse_mark_price_response(&self, response: &Value) -> Result<Decimal, String> {
+        console_log!("TRADING CLIENT: Parsing mark price response");
+
+        // Bybit nests ticker data under result.list
+        let mark_str = response["markPrice"].as_str()
+            .or_else(|| response["result"]["list"][0]["markPrice"].as_str())
+            .ok_or("Missing mark price in response")?;
+
+        Decimal::from_str_exact(mark_str)
+            .map_err(|e| format!("Invalid mark price format: {}", e))
+    }
+
     /// Parse balance response from exchange API
     fn parse_balance_response(&self, response: &Value) -> Result<Vec<Balance>, String> {
         console_log!("TRADING CLIENT: Parsing balance response");
@@ -450,7 +1298,7 @@ impl TradingClient {
         let balances_array = response["balances"].as_array()
             .ok_or("Missing balances in response")?;
 
-        let mut balances = Vec::new();
+        let mut balances: Vec<Balance> = Vec::new();
         for balance in balances_array {
             let asset = balance["asset"].as_str()
                 .ok_or("Missing asset in balance")?;
@@ -469,14 +1317,18 @@ impl TradingClient {
 
             let total = free + locked;
 
-            // Only include balances with non-zero amounts
+            // Only include balances with non-zero amounts. Several exchange
+            // codes can map to one asset, e.g. Kraken's XETH and ETH2.S
             if total > Decimal::ZERO {
-                balances.push(Balance {
-                    asset: asset.to_string(),
-                    free,
-                    locked,
-                    total,
-                });
+                let asset = self.normalize_asset(asset);
+                match balances.iter_mut().find(|existing| existing.asset == asset) {
+                    Some(existing) => {
+                        existing.free += free;
+                        existing.locked += locked;
+                        existing.total += total;
+                    }
+                    None => balances.push(Balance { asset, free, locked, total }),
+                }
             }
         }
 
@@ -495,6 +1347,249 @@ impl TradingClient {
         Ok(order_id.to_string())
     }
 
+    /// Parse order fills response from exchange API
+    fn parse_fills_response(&self, response: &Value) -> Result<Vec<Fill>, String> {
+        console_log!("TRADING CLIENT: Parsing order fills response");
+
+        let fills = response.as_array().ok_or("Invalid fills response format")?;
+        let mut parsed = Vec::new();
+
+        for fill in fills {
+            // Binance uses id/qty/commissionAsset/time, Coinbase trade_id/size/fee/created_at
+            let exchange_trade_id = match (&fill["id"], &fill["trade_id"]) {
+                (Value::String(id), _) | (_, Value::String(id)) => id.clone(),
+                (Value::Number(id), _) | (_, Value::Number(id)) => id.to_string(),
+                _ => return Err("Missing trade id in fill".to_string()),
+            };
+
+            let quantity = fill["qty"].as_str().or_else(|| fill["size"].as_str())
+                .ok_or("Missing quantity in fill")?;
+            let price = fill["price"].as_str().ok_or("Missing price in fill")?;
+            let commission = fill["commission"].as_str().or_else(|| fill["fee"].as_str()).unwrap_or("0");
+
+            let executed_at = fill["time"].as_i64()
+                .and_then(DateTime::from_timestamp_millis)
+                .or_else(|| fill["created_at"].as_str()
+                    .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                    .map(|ts| ts.with_timezone(&Utc)))
+                .unwrap_or_else(Utc::now);
+
+            parsed.push(Fill {
+                exchange_trade_id,
+                quantity: Decimal::from_str_exact(quantity)
+                    .map_err(|e| format!("Invalid fill quantity: {}", e))?,
+                price: Decimal::from_str_exact(price)
+                    .map_err(|e| format!("Invalid fill price: {}", e))?,
+                commission: Decimal::from_str_exact(commission)
+                    .map_err(|e| format!("Invalid fill commission: {}", e))?,
+                commission_asset: fill["commissionAsset"].as_str()
+                    .map(|asset| self.normalize_asset(asset))
+                    .unwrap_or_default(),
+                is_maker: fill["isMaker"].as_bool()
+                    .or_else(|| fill["liquidity"].as_str().map(|l| l == "M"))
+                    .unwrap_or(false),
+                executed_at,
+            });
+        }
+
+        Ok(parsed)
+    }
+
+    /// Parse public recent trades response from exchange API
+    fn parse_recent_trades_response(&self, response: &Value) -> Result<Vec<PublicTrade>, String> {
+        console_log!("TRADING CLIENT: Parsing recent trades response");
+
+        let decimal = |value: &Value, field: &str| -> Result<Decimal, String> {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                Value::Number(number) => number.to_string(),
+                _ => return Err(format!("Missing {} in trade", field)),
+            };
+            Decimal::from_str_exact(&text).map_err(|e| format!("Invalid trade {}: {}", field, e))
+        };
+        let trade_id = |value: &Value| -> Result<String, String> {
+            match value {
+                Value::String(id) => Ok(id.clone()),
+                Value::Number(id) => Ok(id.to_string()),
+                _ => Err("Missing trade id in trade".to_string()),
+            }
+        };
+        let millis = |value: &Value| value.as_i64()
+            .or_else(|| value.as_str().and_then(|ms| ms.parse().ok()))
+            .and_then(DateTime::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+        let side = |value: &Value| match value.as_str().map(str::to_lowercase).as_deref() {
+            Some("sell") | Some("s") => Side::Sell,
+            _ => Side::Buy,
+        };
+
+        let mut trades = Vec::new();
+        match self.exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => {
+                for trade in response.as_array().ok_or("Invalid trades response format")? {
+                    trades.push(PublicTrade {
+                        exchange_trade_id: trade_id(&trade["id"])?,
+                        price: decimal(&trade["price"], "price")?,
+                        quantity: decimal(&trade["qty"], "quantity")?,
+                        // isBuyerMaker means the taker sold
+                        side: if trade["isBuyerMaker"].as_bool().unwrap_or(false) { Side::Sell } else { Side::Buy },
+                        executed_at: millis(&trade["time"]),
+                    });
+                }
+            }
+            Exchange::Coinbase => {
+                for trade in response.as_array().ok_or("Invalid trades response format")? {
+                    trades.push(PublicTrade {
+                        exchange_trade_id: trade_id(&trade["trade_id"])?,
+                        price: decimal(&trade["price"], "price")?,
+                        quantity: decimal(&trade["size"], "quantity")?,
+                        // Coinbase reports the maker's side; the taker traded the other way
+                        side: match side(&trade["side"]) {
+                            Side::Sell => Side::Buy,
+                            Side::Buy => Side::Sell,
+                        },
+                        executed_at: trade["time"].as_str()
+                            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                            .map(|ts| ts.with_timezone(&Utc))
+                            .unwrap_or_else(Utc::now),
+                    });
+                }
+            }
+            Exchange::Kraken => {
+                if let Some(error) = response["error"].as_array().and_then(|errors| errors.first()) {
+                    return Err(format!("Kraken error: {}", error));
+                }
+                // result holds one array per pair next to a "last" cursor:
+                // [price, volume, time, side, type, misc, trade_id]
+                let result = response["result"].as_object().ok_or("Missing result in trades response")?;
+                let rows = result.iter()
+                    .find(|(key, _)| key.as_str() != "last")
+                    .and_then(|(_, rows)| rows.as_array())
+                    .ok_or("Missing trades in response")?;
+                for row in rows {
+                    let executed_at = row[2].as_f64()
+                        .and_then(|seconds| DateTime::from_timestamp_millis((seconds * 1000.0) as i64))
+                        .unwrap_or_else(Utc::now);
+                    trades.push(PublicTrade {
+                        exchange_trade_id: trade_id(&row[6])?,
+                        price: decimal(&row[0], "price")?,
+                        quantity: decimal(&row[1], "quantity")?,
+                        side: side(&row[3]),
+                        executed_at,
+                    });
+                }
+            }
+            Exchange::Okx => {
+                for trade in response["data"].as_array().ok_or("Missing data in trades response")? {
+                    trades.push(PublicTrade {
+                        exchange_trade_id: trade_id(&trade["tradeId"])?,
+                        price: decimal(&trade["px"], "price")?,
+                        quantity: decimal(&trade["sz"], "quantity")?,
+                        side: side(&trade["side"]),
+                        executed_at: millis(&trade["ts"]),
+                    });
+                }
+            }
+            Exchange::Bybit => {
+                for trade in response["result"]["list"].as_array().ok_or("Missing result list in trades response")? {
+                    trades.push(PublicTrade {
+                        exchange_trade_id: trade_id(&trade["execId"])?,
+                        price: decimal(&trade["price"], "price")?,
+                        quantity: decimal(&trade["size"], "quantity")?,
+                        side: side(&trade["side"]),
+                        executed_at: millis(&trade["time"]),
+                    });
+                }
+            }
+        }
+
+        Ok(trades)
+    }
+
+    /// Parse kline response from exchange API
+    fn parse_klines_response(&self, response: &Value) -> Result<Vec<Kline>, String> {
+        console_log!("TRADING CLIENT: Parsing klines response");
+
+        let decimal = |value: &Value, field: &str| -> Result<Decimal, String> {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                Value::Number(number) => number.to_string(),
+                _ => return Err(format!("Missing {} in kline", field)),
+            };
+            Decimal::from_str_exact(&text).map_err(|e| format!("Invalid kline {}: {}", field, e))
+        };
+        let time = |value: &Value, scale: i64| -> Result<DateTime<Utc>, String> {
+            value.as_i64()
+                .or_else(|| value.as_str().and_then(|ts| ts.parse().ok()))
+                .and_then(|ts| DateTime::from_timestamp_millis(ts * scale))
+                .ok_or_else(|| "Missing open time in kline".to_string())
+        };
+
+        // Each exchange returns rows of positional fields:
+        // (rows, time scale to millis, open, high, low, close, volume, trade count)
+        let (rows, scale, fields, count) = match self.exchange {
+            Exchange::Binance | Exchange::BinanceFuturesUsd => {
+                (response.as_array().ok_or("Invalid klines response format")?, 1, [1, 2, 3, 4, 5], Some(8))
+            }
+            // [time, low, high, open, close, volume]
+            Exchange::Coinbase => (response.as_array().ok_or("Invalid klines response format")?, 1000, [3, 2, 1, 4, 5], None),
+            Exchange::Kraken => {
+                if let Some(error) = response["error"].as_array().and_then(|errors| errors.first()) {
+                    return Err(format!("Kraken error: {}", error));
+                }
+                // [time, open, high, low, close, vwap, volume, count]
+                let rows = response["result"].as_object()
+                    .and_then(|result| result.iter().find(|(key, _)| key.as_str() != "last"))
+                    .and_then(|(_, rows)| rows.as_array())
+                    .ok_or("Missing klines in response")?;
+                (rows, 1000, [1, 2, 3, 4, 6], Some(7))
+            }
+            Exchange::Okx => (response["data"].as_array().ok_or("Missing data in klines response")?, 1, [1, 2, 3, 4, 5], None),
+            Exchange::Bybit => (response["result"]["list"].as_array().ok_or("Missing result list in klines response")?, 1, [1, 2, 3, 4, 5], None),
+        };
+
+        let [open, high, low, close, volume] = fields;
+        rows.iter()
+            .map(|row| Ok(Kline {
+                open_time: time(&row[0], scale)?,
+                open: decimal(&row[open], "open")?,
+                high: decimal(&row[high], "high")?,
+                low: decimal(&row[low], "low")?,
+                close: decimal(&row[close], "close")?,
+                volume: decimal(&row[volume], "volume")?,
+                trade_count: count.and_then(|index| row[index].as_u64()).map(|count| count as u32),
+            }))
+            .collect()
+    }
+
+    /// Parse order status response from exchange API
+    fn parse_order_status_response(&self, exchange_order_id: &str, response: &Value) -> Result<OrderStatusReport, String> {
+        console_log!("TRADING CLIENT: Parsing order status response");
+
+        let status = response["status"].as_str()
+            .ok_or("Missing status in order response")?
+            .to_uppercase();
+
+        // Binance reports executedQty, Coinbase filled_size
+        let filled_quantity = match response["executedQty"].as_str().or_else(|| response["filled_size"].as_str()) {
+            Some(filled) => Decimal::from_str_exact(filled)
+                .map_err(|e| format!("Invalid filled quantity: {}", e))?,
+            None => Decimal::ZERO,
+        };
+
+        let average_price = match response["avgPrice"].as_str().or_else(|| response["executed_value"].as_str()) {
+            Some(price) => Decimal::from_str_exact(price).ok().filter(|p| *p > Decimal::ZERO),
+            None => None,
+        };
+
+        Ok(OrderStatusReport {
+            exchange_order_id: exchange_order_id.to_string(),
+            status,
+            filled_quantity,
+            average_price,
+        })
+    }
+
     /// Build order payload for the exchange API
     fn build_order_payload(&self, order: &OrderRequest) -> Result<Value, String> {
         console_log!("TRADING CLIENT: Building order payload");
@@ -505,12 +1600,7 @@ impl TradingClient {
             Side::Sell => "SELL",
         };
 
-        let order_type_str = match order.order_type {
-            OrderType::Market => "MARKET",
-            OrderType::Limit => "LIMIT",
-            OrderType::StopLoss => "STOP_LOSS",
-            OrderType::TakeProfit => "TAKE_PROFIT",
-        };
+        let order_type_str = self.format_order_type(&order.order_type);
 
         let mut payload = json!({
             "symbol": symbol,
@@ -526,6 +1616,257 @@ impl TradingClient {
             payload["timeInForce"] = json!("GTC"); // Good Till Cancelled
         }
 
+        if let Some(stop_price) = order.stop_price {
+            self.add_trigger_fields(&mut payload, order, stop_price);
+        }
+
+        if let Some(client_order_id) = &order.client_order_id {
+            let field = match self.exchange {
+                Exchange::Coinbase => "client_oid",
+                Exchange::Kraken => "cl_ord_id",
+                Exchange::Okx => "clOrdId",
+                Exchange::Bybit => "orderLinkId",
+                _ => "newClientOrderId",
+            };
+            payload[field] = json!(self.format_client_order_id(client_order_id));
+        }
+
         Ok(payload)
     }
+
+    /// Exchange-specific name for an order type
+    fn format_order_type(&self, order_type: &OrderType) -> &'static str {
+        match self.exchange {
+            Exchange::BinanceFuturesUsd => match order_type {
+                OrderType::Market => "MARKET",
+                OrderType::Limit => "LIMIT",
+                OrderType::StopLoss => "STOP_MARKET",
+                OrderType::StopLossLimit => "STOP",
+                OrderType::TakeProfit => "TAKE_PROFIT_MARKET",
+                OrderType::TakeProfitLimit => "TAKE_PROFIT",
+            },
+            Exchange::Kraken => match order_type {
+                OrderType::Market => "market",
+                OrderType::Limit => "limit",
+                OrderType::StopLoss => "stop-loss",
+                OrderType::StopLossLimit => "stop-loss-limit",
+                OrderType::TakeProfit => "take-profit",
+                OrderType::TakeProfitLimit => "take-profit-limit",
+            },
+            Exchange::Coinbase | Exchange::Bybit => match order_type {
+                // Trigger behaviour is carried by separate fields on these venues
+                OrderType::Market | OrderType::StopLoss | OrderType::TakeProfit => "MARKET",
+                OrderType::Limit | OrderType::StopLossLimit | OrderType::TakeProfitLimit => "LIMIT",
+            },
+            _ => match order_type {
+                OrderType::Market => "MARKET",
+                OrderType::Limit => "LIMIT",
+                OrderType::StopLoss => "STOP_LOSS",
+                OrderType::StopLossLimit => "STOP_LOSS_LIMIT",
+                OrderType::TakeProfit => "TAKE_PROFIT",
+                OrderType::TakeProfitLimit => "TAKE_PROFIT_LIMIT",
+            },
+        }
+    }
+
+    /// Add the exchange-specific trigger price fields to an order payload
+    fn add_trigger_fields(&self, payload: &mut Value, order: &OrderRequest, stop_price: Decimal) {
+        let is_stop_loss = matches!(order.order_type, OrderType::StopLoss | OrderType::StopLossLimit);
+
+        match self.exchange {
+            Exchange::BinanceFuturesUsd => {
+                payload["stopPrice"] = json!(stop_price.to_string());
+                payload["workingType"] = json!(match order.trigger_by {
+                    TriggerBy::LastPrice => "CONTRACT_PRICE",
+                    TriggerBy::MarkPrice => "MARK_PRICE",
+                });
+            }
+            Exchange::Coinbase => {
+                // A sell stop-loss and a buy take-profit both fire on a falling price
+                let falling = is_stop_loss == matches!(order.side, Side::Sell);
+                payload["stop"] = json!(if falling { "loss" } else { "entry" });
+                payload["stop_price"] = json!(stop_price.to_string());
+            }
+            Exchange::Kraken => {
+                // Kraken puts the trigger in `price` and the limit price in `price2`
+                if let Some(price) = order.price {
+                    payload["price2"] = json!(price.to_string());
+                }
+                payload["price"] = json!(stop_price.to_string());
+                payload["trigger"] = json!(match order.trigger_by {
+                    TriggerBy::LastPrice => "last",
+                    TriggerBy::MarkPrice => "index",
+                });
+            }
+            Exchange::Bybit => {
+                let falling = is_stop_loss == matches!(order.side, Side::Sell);
+                payload["triggerPrice"] = json!(stop_price.to_string());
+                payload["triggerDirection"] = json!(if falling { 2 } else { 1 });
+                payload["triggerBy"] = json!(match order.trigger_by {
+                    TriggerBy::LastPrice => "LastPrice",
+                    TriggerBy::MarkPrice => "MarkPrice",
+                });
+            }
+            _ => {
+                payload["stopPrice"] = json!(stop_price.to_string());
+            }
+        }
+    }
+}
+
+/// Binance order list payload for an OCO pair. The order above the market is
+/// the take-profit when selling and the stop when buying.
+fn binance_oco_payload(symbol: &str, take_profit: &OrderRequest, stop_loss: &OrderRequest) -> Result<Value, String> {
+    let limit_price = take_profit.price.ok_or("OCO take-profit requires a limit price")?;
+    let stop_price = stop_loss.stop_price.ok_or("OCO stop-loss requires a stop price")?;
+    let side = match take_profit.side {
+        Side::Buy => "BUY",
+        Side::Sell => "SELL",
+    };
+    let (take_profit_at, stop_at) = match take_profit.side {
+        Side::Sell => ("above", "below"),
+        Side::Buy => ("below", "above"),
+    };
+
+    let mut payload = json!({
+        "symbol": symbol,
+        "side": side,
+        "quantity": take_profit.quantity.to_string(),
+        "timestamp": Utc::now().timestamp_millis()
+    });
+    payload[format!("{}Type", take_profit_at)] = json!("LIMIT_MAKER");
+    payload[format!("{}Price", take_profit_at)] = json!(limit_price.to_string());
+    payload[format!("{}StopPrice", stop_at)] = json!(stop_price.to_string());
+    match stop_loss.price {
+        Some(price) => {
+            payload[format!("{}Type", stop_at)] = json!("STOP_LOSS_LIMIT");
+            payload[format!("{}Price", stop_at)] = json!(price.to_string());
+            payload[format!("{}TimeInForce", stop_at)] = json!("GTC");
+        }
+        None => payload[format!("{}Type", stop_at)] = json!("STOP_LOSS"),
+    }
+    if let Some(client_order_id) = &take_profit.client_order_id {
+        payload[format!("{}ClientOrderId", take_profit_at)] = json!(client_order_id);
+    }
+    if let Some(client_order_id) = &stop_loss.client_order_id {
+        payload[format!("{}ClientOrderId", stop_at)] = json!(client_order_id);
+    }
+    Ok(payload)
+}
+
+/// Exchange ids of the take-profit and stop-loss in an order list response,
+/// told apart by their client order ids
+fn parse_oco_response(response: &Value, take_profit: &OrderRequest, stop_loss: &OrderRequest) -> Result<(String, String), String> {
+    let orders = response["orders"].as_array().ok_or("Missing orders in OCO response")?;
+    let order_id = |client_order_id: &Option<String>| {
+        let order = orders.iter().find(|order| order["clientOrderId"].as_str() == client_order_id.as_deref())?;
+        match &order["orderId"] {
+            Value::String(id) => Some(id.clone()),
+            Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        }
+    };
+
+    match (order_id(&take_profit.client_order_id), order_id(&stop_loss.client_order_id)) {
+        (Some(take_profit_id), Some(stop_loss_id)) => Ok((take_profit_id, stop_loss_id)),
+        _ => Err("OCO response is missing one of its orders".to_string()),
+    }
+}
+
+/// Whether an exchange's answer to an order lookup says it has no such order.
+///
+/// Most exchanges answer with an error code rather than a 404, and some with
+/// an empty result.
+fn is_unknown_order(exchange: Exchange, status: u16, body: &Value) -> bool {
+    if status == 404 {
+        return true;
+    }
+    match exchange {
+        Exchange::Binance | Exchange::BinanceFuturesUsd => body["code"].as_i64() == Some(-2013),
+        Exchange::Coinbase => false,
+        Exchange::Kraken => {
+            let errors = body["error"].as_array();
+            errors.is_some_and(|errors| errors.iter().any(|e| e.as_str() == Some("EOrder:Unknown order")))
+                || (errors.is_none_or(|errors| errors.is_empty()) && body["result"].as_object().is_some_and(|result| result.is_empty()))
+        }
+        Exchange::Okx => body["code"].as_str() == Some("51603"),
+        Exchange::Bybit => {
+            body["retCode"].as_i64() == Some(110001)
+                || (body["retCode"].as_i64() == Some(0) && body["result"]["list"].as_array().is_some_and(|list| list.is_empty()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_unknown_order_answers() {
+        assert!(is_unknown_order(Exchange::Coinbase, 404, &json!({})));
+        assert!(is_unknown_order(Exchange::Binance, 400, &json!({"code": -2013, "msg": "Order does not exist."})));
+        assert!(is_unknown_order(Exchange::Okx, 200, &json!({"code": "51603", "msg": "Order does not exist", "data": []})));
+        assert!(is_unknown_order(Exchange::Kraken, 200, &json!({"error": [], "result": {}})));
+        assert!(is_unknown_order(Exchange::Kraken, 200, &json!({"error": ["EOrder:Unknown order"]})));
+        assert!(is_unknown_order(Exchange::Bybit, 200, &json!({"retCode": 0, "result": {"list": []}})));
+    }
+
+    fn oco_legs(side: Side, stop_limit: Option<Decimal>) -> (OrderRequest, OrderRequest) {
+        let take_profit = OrderRequest {
+            instrument: SimpleInstrument { base: "BTC".to_string(), quote: "USDT".to_string() },
+            side,
+            quantity: Decimal::ONE,
+            price: Some(Decimal::from(50_000)),
+            order_type: OrderType::Limit,
+            stop_price: None,
+            trigger_by: TriggerBy::LastPrice,
+            client_order_id: Some("tp".to_string()),
+        };
+        let stop_loss = OrderRequest {
+            order_type: if stop_limit.is_some() { OrderType::StopLossLimit } else { OrderType::StopLoss },
+            price: stop_limit,
+            stop_price: Some(Decimal::from(40_000)),
+            client_order_id: Some("sl".to_string()),
+            ..take_profit.clone()
+        };
+        (take_profit, stop_loss)
+    }
+
+    #[test]
+    fn oco_payload_puts_each_leg_on_its_side_of_the_market() {
+        let (take_profit, stop_loss) = oco_legs(Side::Sell, None);
+        let payload = binance_oco_payload("BTCUSDT", &take_profit, &stop_loss).unwrap();
+        assert_eq!(payload["aboveType"], "LIMIT_MAKER");
+        assert_eq!(payload["abovePrice"], "50000");
+        assert_eq!(payload["aboveClientOrderId"], "tp");
+        assert_eq!(payload["belowType"], "STOP_LOSS");
+        assert_eq!(payload["belowStopPrice"], "40000");
+        assert_eq!(payload["belowClientOrderId"], "sl");
+
+        let (take_profit, stop_loss) = oco_legs(Side::Buy, Some(Decimal::from(40_100)));
+        let payload = binance_oco_payload("BTCUSDT", &take_profit, &stop_loss).unwrap();
+        assert_eq!(payload["belowType"], "LIMIT_MAKER");
+        assert_eq!(payload["aboveType"], "STOP_LOSS_LIMIT");
+        assert_eq!(payload["abovePrice"], "40100");
+        assert_eq!(payload["aboveTimeInForce"], "GTC");
+    }
+
+    #[test]
+    fn oco_response_matches_orders_by_client_order_id() {
+        let (take_profit, stop_loss) = oco_legs(Side::Sell, None);
+        let response = json!({"orders": [
+            {"orderId": 12, "clientOrderId": "sl"},
+            {"orderId": 13, "clientOrderId": "tp"}
+        ]});
+        assert_eq!(parse_oco_response(&response, &take_profit, &stop_loss).unwrap(), ("13".to_string(), "12".to_string()));
+        assert!(parse_oco_response(&json!({"orders": [{"orderId": 13, "clientOrderId": "tp"}]}), &take_profit, &stop_loss).is_err());
+    }
+
+    #[test]
+    fn other_failures_are_not_unknown_orders() {
+        assert!(!is_unknown_order(Exchange::Binance, 400, &json!({"code": -1021, "msg": "Timestamp outside recvWindow"})));
+        assert!(!is_unknown_order(Exchange::Binance, 503, &json!({})));
+        assert!(!is_unknown_order(Exchange::Kraken, 200, &json!({"error": [], "result": {"OABC": {"status": "open"}}})));
+        assert!(!is_unknown_order(Exchange::Bybit, 200, &json!({"retCode": 0, "result": {"list": [{"orderId": "1"}]}})));
+    }
 }
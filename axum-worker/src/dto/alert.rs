@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+/// Request to set up an alert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAlertRequest {
+    pub name: Option<String>,
+    pub condition: String, // "PRICE_ABOVE", "PRICE_BELOW", "PERCENT_CHANGE", "SPREAD_ABOVE" or "ORDER_FILLED"
+    pub exchange: Option<String>, // Required except for ORDER_FILLED, where it narrows the orders watched
+    pub base: Option<String>,
+    pub quote: Option<String>,
+    pub threshold: Option<Decimal>, // Price, or a percentage for PERCENT_CHANGE and SPREAD_ABOVE
+    pub window_minutes: Option<i64>, // PERCENT_CHANGE only, defaults to 60
+    pub repeat: Option<bool>, // Fire each time the condition recurs, defaults to false (fire once)
+}
+
+/// Request naming one of the user's alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRequest {
+    pub alert_id: String,
+}
+
+/// Alert rule DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertDto {
+    pub alert_id: String,
+    pub name: Option<String>,
+    pub condition: String,
+    pub description: String, // e.g. "BTC/USDT on binance price above 70000"
+    pub exchange: Option<String>,
+    pub base: Option<String>,
+    pub quote: Option<String>,
+    pub threshold: Option<Decimal>,
+    pub window_minutes: Option<i64>,
+    pub repeat: bool,
+    pub status: String, // "ACTIVE", "TRIGGERED" or "DISABLED"
+    pub armed: bool, // False while a repeating alert waits for its condition to clear
+    pub last_value: Option<Decimal>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub triggered_at: Option<DateTime<Utc>>,
+    pub trigger_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Response listing the user's alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAlertsResponse {
+    pub alerts: Vec<AlertDto>,
+}
+
+/// Request to read the user's notification feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetNotificationsRequest {
+    pub unread_only: Option<bool>, // Defaults to false
+    pub limit: Option<u32>, // Defaults to 50, at most 200
+}
+
+/// Response containing the user's notifications, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetNotificationsResponse {
+    pub notifications: Vec<NotificationDto>,
+    pub unread: u64,
+}
+
+/// Notification DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationDto {
+    pub notification_id: String,
+    pub alert_id: Option<String>,
+    pub title: String,
+    pub message: String,
+    pub data: Value, // Event details, as sent in the alert.triggered webhook event
+    pub read: bool,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request to mark notifications read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkNotificationsReadRequest {
+    pub notification_ids: Option<Vec<String>>, // Omit to mark every notification read
+}
+
+/// Response giving how many notifications are left unread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkNotificationsReadResponse {
+    pub unread: u64,
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+use crate::dto::market_data::InstrumentDto;
+
+/// Request to backtest a strategy over stored market data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunBacktestRequest {
+    pub exchange: String,
+    pub base: String,
+    pub quote: String,
+    pub strategy: String, // "buy_and_hold" or "sma_crossover"
+    pub parameters: Option<Value>, // Strategy settings; omitted ones take their defaults
+    pub source: Option<String>, // "candles" (default) or "trades" recorded from the live stream
+    pub interval: Option<String>, // Candle interval and equity sampling interval, defaults to 1h
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>, // Defaults to now
+    pub initial_cash: Option<Decimal>, // In the quote asset, defaults to 10000
+    pub fee_rate: Option<Decimal>, // Defaults to the exchange's taker fee
+    pub slippage_bps: Option<Decimal>, // Applied to market orders, defaults to 5
+}
+
+/// Backtest report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunBacktestResponse {
+    pub instrument: InstrumentDto,
+    pub strategy: String,
+    pub parameters: Value, // Including the defaults filled in
+    pub source: String,
+    pub interval: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub events: u32, // Candles or trades replayed
+    pub fee_rate: Decimal,
+    pub slippage_bps: Decimal,
+    pub summary: BacktestSummaryDto,
+    pub trades: Vec<BacktestTradeDto>,
+    pub fills: Vec<BacktestFillDto>,
+    pub equity_curve: Vec<BacktestEquityPointDto>,
+}
+
+/// Headline results of a backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestSummaryDto {
+    pub initial_equity: Decimal,
+    pub final_equity: Decimal, // Open position marked at the last price
+    pub net_pnl: Decimal,
+    pub return_percentage: Decimal,
+    pub fees_paid: Decimal,
+    pub sharpe_ratio: Option<Decimal>, // Annualized; None without enough variation to measure
+    pub max_drawdown_percentage: Decimal,
+    pub win_rate_percentage: Option<Decimal>, // Of closed trades; None if none closed
+    pub total_trades: u32,
+    pub winning_trades: u32,
+    pub losing_trades: u32,
+}
+
+/// Round trip from flat to flat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestTradeDto {
+    pub entry_time: DateTime<Utc>,
+    pub exit_time: Option<DateTime<Utc>>, // None while still open at the end
+    pub quantity: Decimal,
+    pub entry_price: Decimal, // Average of the buys
+    pub exit_price: Option<Decimal>, // Average of the sells
+    pub fees: Decimal,
+    pub pnl: Decimal, // Net of fees
+    pub return_percentage: Decimal,
+}
+
+/// Simulated fill DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestFillDto {
+    pub timestamp: DateTime<Utc>,
+    pub side: String, // "BUY" or "SELL"
+    pub quantity: Decimal,
+    pub price: Decimal, // Including slippage
+    pub fee: Decimal,
+}
+
+/// Backtest equity curve point DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestEquityPointDto {
+    pub timestamp: DateTime<Utc>,
+    pub equity: Decimal,
+    pub drawdown_percentage: Decimal, // Below the running peak
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::entity::job::{JobRun, ScheduledJob};
+
+/// Request to run a job now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunJobRequest {
+    pub job: String, // e.g. "PORTFOLIO_SNAPSHOTS"
+}
+
+/// Request to get job run history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetJobHistoryRequest {
+    pub job: Option<String>, // Omit for every job
+    pub limit: Option<u32>, // Defaults to 50, at most 500
+}
+
+/// Response listing the registered jobs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListJobsResponse {
+    pub jobs: Vec<JobDto>,
+}
+
+/// Registered job with its schedule and latest run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobDto {
+    pub job: String,
+    pub cron: String,
+    pub description: String,
+    pub last_run: Option<JobRunDto>,
+}
+
+/// Response containing job run history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetJobHistoryResponse {
+    pub runs: Vec<JobRunDto>,
+}
+
+/// Job run DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRunDto {
+    pub run_id: String,
+    pub job: String,
+    pub trigger: String, // "CRON" or "MANUAL"
+    pub triggered_by: Option<String>,
+    pub status: String, // "RUNNING", "SUCCEEDED", "FAILED" or "SKIPPED"
+    pub message: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl JobDto {
+    pub fn new(job: ScheduledJob, last_run: Option<JobRunDto>) -> Self {
+        Self {
+            job: job.as_str().to_string(),
+            cron: job.cron().to_string(),
+            description: job.description().to_string(),
+            last_run,
+        }
+    }
+}
+
+impl From<JobRun> for JobRunDto {
+    fn from(run: JobRun) -> Self {
+        Self {
+            run_id: run.id,
+            job: run.job.as_str().to_string(),
+            trigger: run.trigger.as_str().to_string(),
+            triggered_by: run.triggered_by,
+            status: run.status.as_str().to_string(),
+            message: run.message,
+            started_at: run.started_at,
+            finished_at: run.finished_at,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
 use chrono::{DateTime, Utc};
-use crate::entity::market_data::{MarketDataEvent, Trade, Instrument, TradeSide, InstrumentKind};
+use crate::entity::market_data::{Candle, CandleInterval, MarketDataEvent, OrderBook, OrderBookLevel, Trade, Instrument, TradeSide, InstrumentKind};
 
 /// Request to subscribe to market data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +26,23 @@ pub struct MarketDataSubscriptionResponse {
     pub success: bool,
     pub message: String,
     pub subscription_id: Option<String>,
+    pub stream_url: Option<String>, // WebSocket path that streams this instrument's events
+}
+
+/// Frame sent by a WebSocket client to change what it receives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStreamFrame {
+    pub action: String, // "subscribe", "unsubscribe" or "ping"
+    pub channels: Option<Vec<String>>, // "trade" and/or "orderbook"; omit for both
+}
+
+/// Reply to a client frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStreamAck {
+    pub event_type: String, // "subscribed", "unsubscribed", "pong" or "error"
+    pub channels: Vec<String>, // Channels the client now receives
+    pub message: Option<String>,
+    pub timestamp: DateTime<Utc>,
 }
 
 /// Market data stream event for API responses
@@ -55,6 +72,8 @@ pub enum MarketDataEventData {
     },
     Candle {
         instrument: InstrumentDto,
+        interval: String,
+        open_time: DateTime<Utc>,
         open: Decimal,
         high: Decimal,
         low: Decimal,
@@ -108,6 +127,97 @@ pub struct GetTradesResponse {
     pub trades: Vec<TradeDto>,
 }
 
+/// Request for OHLCV candles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetCandlesRequest {
+    pub exchange: String,
+    pub base: String,
+    pub quote: String,
+    pub interval: String, // 1m, 5m, 15m, 1h, 4h or 1d
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>, // Defaults to now
+    pub limit: Option<u32>, // Candles before end_time when start_time is omitted
+    pub fill_gaps: Option<bool>, // Defaults to true
+}
+
+/// Response with OHLCV candles, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetCandlesResponse {
+    pub instrument: InstrumentDto,
+    pub interval: String,
+    pub candles: Vec<CandleDto>,
+    pub backfilled: u32, // Candles fetched from the exchange for this request
+}
+
+/// Request for technical indicators over an instrument's candles. The range
+/// is given as for candles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetIndicatorsRequest {
+    pub exchange: String,
+    pub base: String,
+    pub quote: String,
+    pub interval: String,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub limit: Option<u32>,
+    pub indicators: Vec<IndicatorRequest>,
+}
+
+/// One indicator to compute; settings an indicator doesn't use are ignored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorRequest {
+    pub name: String, // sma, ema, rsi, macd, bollinger, atr or vwap
+    pub period: Option<usize>, // Defaults to 20 for sma, ema and bollinger, 14 for rsi and atr
+    pub fast: Option<usize>, // MACD, defaults to 12
+    pub slow: Option<usize>, // MACD, defaults to 26
+    pub signal: Option<usize>, // MACD, defaults to 9
+    pub multiplier: Option<Decimal>, // Bollinger band width in standard deviations, defaults to 2
+}
+
+/// Response with candles and indicator values, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetIndicatorsResponse {
+    pub instrument: InstrumentDto,
+    pub interval: String,
+    pub candles: Vec<CandleDto>,
+    pub indicators: Vec<IndicatorSeriesDto>,
+    pub backfilled: u32,
+}
+
+/// An indicator's values, one per candle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorSeriesDto {
+    pub name: String,
+    pub label: String, // Name with settings, e.g. "macd(12,26,9)"
+    pub values: Vec<IndicatorValueDto>,
+}
+
+/// Indicator value as of one candle. Every field but open_time is null
+/// while the indicator warms up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorValueDto {
+    pub open_time: DateTime<Utc>,
+    pub value: Option<Decimal>, // The MACD line, or the middle Bollinger band
+    pub signal: Option<Decimal>, // MACD only
+    pub histogram: Option<Decimal>, // MACD only
+    pub upper: Option<Decimal>, // Bollinger only
+    pub lower: Option<Decimal>, // Bollinger only
+}
+
+/// OHLCV candle DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleDto {
+    pub open_time: DateTime<Utc>,
+    pub close_time: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    pub trade_count: Option<u32>,
+    pub is_complete: bool, // False for the bucket still in progress
+}
+
 /// Trade DTO for API responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeDto {
@@ -117,6 +227,7 @@ pub struct TradeDto {
     pub quantity: Decimal,
     pub side: String,
     pub timestamp: DateTime<Utc>,
+    pub exchange_timestamp: DateTime<Utc>, // When the exchange executed the trade
 }
 
 // Conversion implementations
@@ -137,6 +248,22 @@ impl From<&Instrument> for InstrumentDto {
     }
 }
 
+impl From<&Candle> for CandleDto {
+    fn from(candle: &Candle) -> Self {
+        Self {
+            open_time: candle.open_time,
+            close_time: candle.close_time(),
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume,
+            trade_count: candle.trade_count,
+            is_complete: candle.is_complete(),
+        }
+    }
+}
+
 impl From<&Trade> for TradeDto {
     fn from(trade: &Trade) -> Self {
         Self {
@@ -149,6 +276,7 @@ impl From<&Trade> for TradeDto {
                 TradeSide::Sell => "sell".to_string(),
             },
             timestamp: trade.timestamp,
+            exchange_timestamp: trade.exchange_timestamp,
         }
     }
 }
@@ -188,6 +316,8 @@ impl From<MarketDataEvent> for MarketDataEventResponse {
                 "candle".to_string(),
                 MarketDataEventData::Candle {
                     instrument: InstrumentDto::from(&candle.instrument),
+                    interval: candle.interval.as_str().to_string(),
+                    open_time: candle.open_time,
                     open: candle.open,
                     high: candle.high,
                     low: candle.low,
@@ -205,3 +335,52 @@ impl From<MarketDataEvent> for MarketDataEventResponse {
         }
     }
 }
+
+impl MarketDataEventResponse {
+    /// Rebuild the event from a stream frame, e.g. one recorded to the
+    /// archive or read from a market stream. Exchange trade ids aren't in the
+    /// frame, so trades come back without one.
+    pub fn to_event(&self) -> Option<MarketDataEvent> {
+        let instrument_of = |dto: &InstrumentDto| {
+            Instrument::new(dto.base.clone(), dto.quote.clone(), dto.exchange.clone(), InstrumentKind::Spot)
+        };
+        let levels = |levels: &[OrderBookLevelDto]| -> Vec<OrderBookLevel> {
+            levels.iter().map(|level| OrderBookLevel { price: level.price, quantity: level.quantity }).collect()
+        };
+
+        let event = match &self.data {
+            MarketDataEventData::Trade { instrument, price, quantity, side, exchange_timestamp } => MarketDataEvent::Trade(Trade {
+                id: String::new(),
+                instrument: instrument_of(instrument),
+                price: *price,
+                quantity: *quantity,
+                side: if side.eq_ignore_ascii_case("sell") { TradeSide::Sell } else { TradeSide::Buy },
+                timestamp: self.timestamp,
+                exchange_timestamp: *exchange_timestamp,
+            }),
+            MarketDataEventData::OrderBook { instrument, bids, asks, exchange_timestamp } => MarketDataEvent::OrderBook(OrderBook {
+                instrument: instrument_of(instrument),
+                bids: levels(bids),
+                asks: levels(asks),
+                timestamp: self.timestamp,
+                exchange_timestamp: *exchange_timestamp,
+            }),
+            MarketDataEventData::Candle { instrument, interval, open_time, open, high, low, close, volume, exchange_timestamp } => {
+                MarketDataEvent::Candle(Candle {
+                    instrument: instrument_of(instrument),
+                    interval: CandleInterval::parse(interval)?,
+                    open_time: *open_time,
+                    open: *open,
+                    high: *high,
+                    low: *low,
+                    close: *close,
+                    volume: *volume,
+                    trade_count: None,
+                    timestamp: self.timestamp,
+                    exchange_timestamp: *exchange_timestamp,
+                })
+            }
+        };
+        Some(event)
+    }
+}
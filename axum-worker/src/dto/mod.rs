@@ -1,3 +1,9 @@
 pub mod user;
 pub mod market_data;
 pub mod trading;
+pub mod job;
+pub mod backtest;
+pub mod strategy;
+pub mod alert;
+pub mod webhook;
+pub mod signal;
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+/// Signal posted to a user's hook by an external alerting tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingSignalRequest {
+    pub exchange: Option<String>, // Required unless the symbol names it
+    pub symbol: String, // "BTCUSDT", "BTC/USDT", or TradingView's "BINANCE:BTCUSDT"
+    pub side: String, // "BUY" or "SELL", any case
+    pub quantity: Option<Decimal>, // In the base asset; give this or percent_of_balance
+    pub percent_of_balance: Option<Decimal>, // Of the free quote balance for buys, the free base balance for sells
+    pub order_type: Option<String>, // "MARKET" (default) or "LIMIT"
+    pub price: Option<Decimal>, // Required for LIMIT
+    pub signal_id: Option<String>, // Used as the client order id, so a resent signal places one order
+}
+
+/// Request to set up or change the user's signal hook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigureSignalHookRequest {
+    pub risk_limits: Option<SignalRiskLimitsDto>, // Replaces the current limits
+    pub enabled: Option<bool>,
+    pub rotate_token: Option<bool>, // Issue a new token, so the old URL stops working
+}
+
+/// Pre-trade limits on signal orders; omitted limits aren't checked
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalRiskLimitsDto {
+    pub max_order_notional: Option<Decimal>, // In the quote asset
+    pub max_position: Option<Decimal>, // Most of the base asset a buy may take the balance to
+    pub max_open_orders: Option<usize>, // Checked for limit orders, across the exchange
+}
+
+/// Signal hook DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalHookDto {
+    pub hook_id: String,
+    pub token: String,
+    pub path: String, // Where to post signals, e.g. "/api/signals/hook/<token>"
+    pub risk_limits: SignalRiskLimitsDto,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to read the signal log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetSignalsRequest {
+    pub limit: Option<u32>, // Defaults to 50, at most 200
+}
+
+/// Response containing the user's signals, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetSignalsResponse {
+    pub signals: Vec<TradingSignalDto>,
+}
+
+/// Received signal DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingSignalDto {
+    pub signal_id: String,
+    pub status: String, // "PLACED", "REJECTED" or "FAILED"
+    pub error: Option<String>,
+    pub payload: Value, // Body as received; a string if it wasn't JSON
+    pub exchange: Option<String>,
+    pub symbol: Option<String>,
+    pub side: Option<String>,
+    pub order_type: Option<String>,
+    pub quantity: Option<Decimal>,
+    pub order_id: Option<String>,
+    pub order_status: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
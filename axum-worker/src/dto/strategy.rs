@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+use crate::dto::market_data::InstrumentDto;
+
+/// Request to start running a strategy on one instrument
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartStrategyRequest {
+    pub exchange: String,
+    pub base: String,
+    pub quote: String,
+    pub strategy: String, // As for backtests, e.g. "sma_crossover"
+    pub parameters: Option<Value>, // Strategy settings; omitted ones take their defaults
+    pub mode: Option<String>, // "paper" (default) or "live"
+    pub interval: Option<String>, // Interval of the candles the strategy sees, defaults to 1h
+    pub initial_cash: Option<Decimal>, // Quote asset the strategy trades with; required in live mode, defaults to 10000 on paper
+    pub fee_rate: Option<Decimal>, // Defaults to the exchange's taker fee
+    pub slippage_bps: Option<Decimal>, // Applied to paper market orders, defaults to 5
+    pub risk_limits: Option<RiskLimitsDto>,
+}
+
+/// Pre-trade limits on a running strategy; omitted limits aren't checked
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskLimitsDto {
+    pub max_order_notional: Option<Decimal>, // In the quote asset
+    pub max_position: Option<Decimal>, // In the base asset
+    pub max_open_orders: Option<usize>,
+    pub max_drawdown_percentage: Option<Decimal>, // Loss from the starting equity that halts the strategy
+}
+
+/// Request naming one of the user's strategies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyRequest {
+    pub strategy_id: String,
+}
+
+/// Strategy instance DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyInstanceDto {
+    pub strategy_id: String,
+    pub strategy: String,
+    pub parameters: Value,
+    pub instrument: InstrumentDto,
+    pub mode: String, // "PAPER" or "LIVE"
+    pub status: String, // "RUNNING", "PAUSED", "STOPPED" or "HALTED"
+    pub status_reason: Option<String>, // Why the risk engine halted it
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Response listing the user's strategies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListStrategiesResponse {
+    pub strategies: Vec<StrategyInstanceDto>,
+}
+
+/// State of a strategy instance as its runtime sees it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyStatusResponse {
+    pub strategy_id: String,
+    pub strategy: String,
+    pub parameters: Value,
+    pub instrument: InstrumentDto,
+    pub mode: String,
+    pub status: String,
+    pub status_reason: Option<String>,
+    pub interval: String,
+    pub fee_rate: Decimal,
+    pub slippage_bps: Decimal,
+    pub risk_limits: RiskLimitsDto,
+    pub account: StrategyAccountDto,
+    pub performance: StrategyPerformanceDto,
+    pub strategy_state: Value, // What the strategy keeps track of, e.g. a grid's levels or the next DCA buy
+    pub open_orders: Vec<StrategyOrderDto>, // Resting paper orders or working live orders
+    pub events: u64, // Market data events received
+    pub orders_sent: u64,
+    pub orders_rejected: u64,
+    pub fills: u64,
+    pub fees_paid: Decimal,
+    pub recent_fills: Vec<StrategyFillDto>, // Newest last
+    pub recent_rejections: Vec<RejectedOrderDto>, // Newest last
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Cash and position the strategy trades with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyAccountDto {
+    pub initial_cash: Decimal,
+    pub cash: Decimal,
+    pub position: Decimal,
+    pub last_price: Option<Decimal>,
+    pub equity: Decimal, // Position marked at the last price
+    pub pnl: Decimal,
+    pub return_percentage: Decimal,
+}
+
+/// Performance summary of a strategy's fills
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyPerformanceDto {
+    pub buys: u64,
+    pub sells: u64,
+    pub bought_quantity: Decimal,
+    pub average_buy_price: Option<Decimal>,
+    pub sold_quantity: Decimal,
+    pub average_sell_price: Option<Decimal>,
+    pub average_entry_price: Option<Decimal>, // Average cost of the position held, fees included
+    pub realized_pnl: Decimal,
+    pub unrealized_pnl: Option<Decimal>, // Position held marked at the last price
+}
+
+/// Open strategy order DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyOrderDto {
+    pub order_id: Option<String>, // Trading order id in live mode
+    pub side: String,
+    pub quantity: Decimal,
+    pub filled_quantity: Decimal,
+    pub price: Option<Decimal>, // None for a market order
+}
+
+/// Strategy fill DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyFillDto {
+    pub timestamp: DateTime<Utc>,
+    pub side: String,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub fee: Decimal, // Estimated with the fee rate in live mode
+}
+
+/// Order the risk engine or the exchange turned down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedOrderDto {
+    pub timestamp: DateTime<Utc>,
+    pub order_type: String, // "MARKET", "LIMIT" or "CANCEL_ALL"
+    pub side: Option<String>,
+    pub quantity: Option<Decimal>,
+    pub price: Option<Decimal>,
+    pub reason: String,
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
 use chrono::{DateTime, Utc};
@@ -48,22 +49,136 @@ pub struct OrderBookLevelDto {
     pub quantity: Decimal,
 }
 
+/// Request to merge an instrument's order books across exchanges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetConsolidatedBookRequest {
+    pub symbol: String,
+    pub exchanges: Option<Vec<String>>, // Defaults to every configured exchange
+    pub depth: Option<u32>, // Levels fetched from each exchange
+    pub fee_rates: Option<HashMap<String, Decimal>>, // Taker fee rates by exchange, overriding the entry tier
+}
+
+/// Response containing every exchange's levels ranked by fee-adjusted price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetConsolidatedBookResponse {
+    pub symbol: String,
+    pub exchanges: Vec<String>, // Exchanges whose books were merged
+    pub bids: Vec<ConsolidatedLevelDto>, // Highest effective price first
+    pub asks: Vec<ConsolidatedLevelDto>, // Lowest effective price first
+    pub exchange_errors: Vec<ExchangeErrorDto>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// One exchange's price level in a consolidated book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidatedLevelDto {
+    pub exchange: String,
+    pub price: Decimal,
+    pub effective_price: Decimal, // Price including the taker fee
+    pub quantity: Decimal,
+}
+
+/// Request for the best bid and offer across exchanges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetBestBidOfferRequest {
+    pub symbol: String,
+    pub exchanges: Option<Vec<String>>, // Defaults to every configured exchange
+    pub fee_rates: Option<HashMap<String, Decimal>>, // Taker fee rates by exchange, overriding the entry tier
+}
+
+/// Response containing the best fee-adjusted bid and offer and each exchange's top of book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetBestBidOfferResponse {
+    pub symbol: String,
+    pub best_bid: Option<ConsolidatedLevelDto>,
+    pub best_ask: Option<ConsolidatedLevelDto>,
+    pub spread: Option<Decimal>, // Between the effective best ask and best bid
+    pub venues: Vec<VenueBidOfferDto>,
+    pub exchange_errors: Vec<ExchangeErrorDto>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// One exchange's top of book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueBidOfferDto {
+    pub exchange: String,
+    pub taker_fee_rate: Decimal,
+    pub bid: Option<ConsolidatedLevelDto>,
+    pub ask: Option<ConsolidatedLevelDto>,
+}
+
+/// Request to estimate filling a market order on each exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFillQuoteRequest {
+    pub symbol: String,
+    pub side: String, // "BUY" or "SELL"
+    pub quantity: Decimal,
+    pub exchanges: Option<Vec<String>>, // Defaults to every configured exchange
+    pub depth: Option<u32>, // Levels fetched from each exchange
+    pub fee_rates: Option<HashMap<String, Decimal>>, // Taker fee rates by exchange, overriding the entry tier
+}
+
+/// Response containing the expected fill on each exchange and split across all of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFillQuoteResponse {
+    pub symbol: String,
+    pub side: String,
+    pub quantity: Decimal,
+    pub best_exchange: Option<String>, // Cheapest single exchange that fills the whole quantity
+    pub venues: Vec<VenueFillDto>, // Full fills first, then by effective average price
+    pub routed: Option<FillEstimateDto>, // Filled from the consolidated book across exchanges
+    pub exchange_errors: Vec<ExchangeErrorDto>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Expected fill on one exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueFillDto {
+    pub exchange: String,
+    pub taker_fee_rate: Decimal,
+    pub estimate: FillEstimateDto,
+}
+
+/// Expected result of a market order walking an order book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillEstimateDto {
+    pub filled_quantity: Decimal,
+    pub fully_filled: bool, // False when the fetched depth runs out first
+    pub average_price: Decimal,
+    pub effective_average_price: Decimal, // Average price including taker fees
+    pub best_price: Decimal, // Effective price of the first level
+    pub slippage_bps: Decimal, // Effective average price versus the best price
+    pub total_cost: Decimal, // Quote asset paid for a buy, or received for a sell, after fees
+    pub allocations: Vec<FillAllocationDto>,
+}
+
+/// Quantity taken from one exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillAllocationDto {
+    pub exchange: String,
+    pub quantity: Decimal,
+}
+
 /// Request to place a trading order
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaceOrderRequest {
     pub exchange: String,
     pub symbol: String,
     pub side: String, // "BUY" or "SELL"
-    pub order_type: String, // "MARKET", "LIMIT", etc.
+    pub order_type: String, // "MARKET", "LIMIT", "STOP_LOSS", "STOP_LOSS_LIMIT", "TAKE_PROFIT", "TAKE_PROFIT_LIMIT"
     pub quantity: Decimal,
     pub price: Option<Decimal>,
     pub time_in_force: Option<String>, // "GTC", "IOC", "FOK"
+    pub stop_price: Option<Decimal>, // Trigger price for stop-loss/take-profit orders
+    pub trigger_by: Option<String>, // "LAST" (default) or "MARK"
+    pub client_order_id: Option<String>, // Idempotency key; also accepted as the Idempotency-Key header
 }
 
 /// Response after placing an order
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaceOrderResponse {
     pub order_id: String,
+    pub client_order_id: Option<String>,
     pub exchange_order_id: String,
     pub symbol: String,
     pub side: String,
@@ -71,10 +186,62 @@ pub struct PlaceOrderResponse {
     pub status: String,
     pub quantity: Decimal,
     pub price: Option<Decimal>,
+    pub stop_price: Option<Decimal>,
+    pub trigger_by: Option<String>,
+    pub trigger_mode: String, // "NONE", "NATIVE" (held by exchange) or "SERVER" (held by trigger monitor)
     pub filled_quantity: Decimal,
     pub created_at: DateTime<Utc>,
 }
 
+/// Request to place an OCO pair of exit orders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaceOcoOrderRequest {
+    pub exchange: String,
+    pub symbol: String,
+    pub side: String, // Side of both exit legs: "SELL" closes a long, "BUY" closes a short
+    pub quantity: Decimal,
+    pub take_profit_price: Decimal, // Limit price of the take-profit leg
+    pub stop_loss_price: Decimal, // Trigger price of the stop-loss leg
+    pub stop_limit_price: Option<Decimal>, // Makes the stop-loss leg a stop-limit order
+    pub trigger_by: Option<String>, // "LAST" (default) or "MARK"
+}
+
+/// Request to place an entry order with attached take-profit and stop-loss
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaceBracketOrderRequest {
+    pub exchange: String,
+    pub symbol: String,
+    pub side: String, // Side of the entry order; exits use the opposite side
+    pub order_type: String, // Entry order type: "MARKET" or "LIMIT"
+    pub quantity: Decimal,
+    pub price: Option<Decimal>,
+    pub take_profit_price: Decimal,
+    pub stop_loss_price: Decimal,
+    pub stop_limit_price: Option<Decimal>,
+    pub trigger_by: Option<String>,
+}
+
+/// Request addressing an order group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderGroupRequest {
+    pub group_id: String,
+}
+
+/// Response describing an order group and its orders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderGroupResponse {
+    pub group_id: String,
+    pub group_type: String,
+    pub status: String,
+    pub exchange: String,
+    pub symbol: String,
+    pub quantity: Decimal,
+    pub parent_order: Option<OrderDto>,
+    pub legs: Vec<OrderDto>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// Request to get account balances
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetBalancesRequest {
@@ -152,6 +319,7 @@ pub struct GetOrderHistoryResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderDto {
     pub order_id: String,
+    pub client_order_id: Option<String>,
     pub exchange_order_id: Option<String>,
     pub symbol: String,
     pub side: String,
@@ -159,14 +327,23 @@ pub struct OrderDto {
     pub status: String,
     pub quantity: Decimal,
     pub price: Option<Decimal>,
+    pub stop_price: Option<Decimal>,
     pub filled_quantity: Decimal,
     pub average_price: Option<Decimal>,
     pub commission: Decimal,
     pub commission_asset: String,
+    pub group_id: Option<String>,
+    pub parent_order_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Request addressing one of the user's orders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetOrderRequest {
+    pub order_id: String,
+}
+
 /// Request to cancel an order
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CancelOrderRequest {
@@ -201,6 +378,30 @@ pub struct GetTradingStatusResponse {
     pub server_time: DateTime<Utc>,
 }
 
+/// Response of the trading health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingHealthResponse {
+    pub status: String, // "healthy", "degraded" or "unhealthy"
+    pub service: String,
+    pub barter_integration: String,
+    pub supported_exchanges: Vec<String>,
+    pub exchanges: Vec<ExchangeHealthDto>,
+    pub timestamp: DateTime<Utc>,
+    pub version: String,
+}
+
+/// Health of one exchange's connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeHealthDto {
+    pub exchange: String,
+    pub status: String, // "healthy", "recovering" or "unavailable"
+    pub circuit_state: String, // "CLOSED", "OPEN", "HALF_OPEN", or "UNKNOWN" when not tracked
+    pub consecutive_failures: u32,
+    pub last_failure: Option<String>,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub retry_after_seconds: Option<u32>, // Until a trial call is let through, while open
+}
+
 /// Rate limit DTO
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitDto {
@@ -215,6 +416,8 @@ pub struct RateLimitDto {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetPortfolioRequest {
     pub exchange: String,
+    pub cost_basis_method: Option<String>, // "FIFO" (default), "LIFO" or "AVERAGE_COST"
+    pub session_start: Option<DateTime<Utc>>, // Defaults to the start of the current UTC day
 }
 
 /// Response containing portfolio summary
@@ -224,11 +427,59 @@ pub struct GetPortfolioResponse {
     pub total_value_usd: Decimal,
     pub total_pnl_usd: Decimal,
     pub total_pnl_percentage: Decimal,
+    pub cost_basis_method: String,
     pub balances: Vec<BalanceDto>,
     pub top_holdings: Vec<HoldingDto>,
+    pub asset_pnl: Vec<AssetPnlDto>,
+    pub session: SessionPnlDto,
+    pub unpriced_assets: Vec<String>, // Non-zero balances with no quote path to USD, valued at zero
     pub updated_at: DateTime<Utc>,
 }
 
+/// Request to get one portfolio across several exchanges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetAggregatePortfolioRequest {
+    pub exchanges: Option<Vec<String>>, // Defaults to every configured exchange
+}
+
+/// Response containing holdings summed across exchanges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetAggregatePortfolioResponse {
+    pub exchanges: Vec<String>, // Exchanges that returned balances
+    pub total_value_usd: Decimal,
+    pub balances: Vec<AggregateBalanceDto>,
+    pub exchange_errors: Vec<ExchangeErrorDto>, // Exchanges left out of the totals
+    pub unpriced_assets: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Balance of one asset summed across exchanges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateBalanceDto {
+    pub asset: String, // Normalized asset code, e.g. Kraken's XXBT is reported as BTC
+    pub free: Decimal,
+    pub locked: Decimal,
+    pub total: Decimal,
+    pub usd_value: Decimal,
+    pub percentage: Decimal,
+    pub exchanges: Vec<ExchangeBalanceDto>,
+}
+
+/// One exchange's share of an aggregated balance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeBalanceDto {
+    pub exchange: String,
+    pub total: Decimal,
+    pub usd_value: Decimal,
+}
+
+/// Error from one exchange in a multi-exchange request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeErrorDto {
+    pub exchange: String,
+    pub error: String,
+}
+
 /// Holding DTO for portfolio summary
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoldingDto {
@@ -240,6 +491,114 @@ pub struct HoldingDto {
     pub pnl_percentage: Decimal,
 }
 
+/// Lot-accounting PnL for one asset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetPnlDto {
+    pub asset: String,
+    pub quantity: Decimal, // Open quantity held in lots
+    pub average_cost_usd: Option<Decimal>,
+    pub cost_basis_usd: Decimal, // Cost of the open lots
+    pub total_cost_usd: Decimal, // Cost of everything ever acquired
+    pub market_value_usd: Decimal,
+    pub realized_pnl_usd: Decimal,
+    pub unrealized_pnl_usd: Decimal,
+    pub pnl_percentage: Decimal, // Realized plus unrealized PnL as a percentage of total cost
+    pub fees_usd: Decimal,
+    pub fees: HashMap<String, Decimal>, // Commission paid, keyed by commission asset
+    pub unmatched_quantity: Decimal, // Sold without a matching lot; excluded from PnL
+}
+
+/// PnL for the executions inside a trading session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionPnlDto {
+    pub session_id: String,
+    pub started_at: DateTime<Utc>,
+    pub total_trades: u32,
+    pub total_volume_usd: Decimal,
+    pub realized_pnl_usd: Decimal,
+    pub unrealized_pnl_usd: Decimal, // On lots opened during the session
+    pub fees_usd: Decimal,
+}
+
+/// Request to get the equity curve built from portfolio snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetEquityCurveRequest {
+    pub exchange: Option<String>, // Omit for the aggregate across all exchanges
+    pub start_time: Option<DateTime<Utc>>, // Defaults to 30 days ago
+    pub end_time: Option<DateTime<Utc>>, // Defaults to now
+}
+
+/// Response containing the equity curve and its return statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetEquityCurveResponse {
+    pub exchange: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub points: Vec<EquityPointDto>,
+    pub daily_returns: Vec<DailyReturnDto>,
+    pub start_value_usd: Decimal,
+    pub end_value_usd: Decimal,
+    pub max_drawdown_percentage: Decimal,
+    pub time_weighted_return_percentage: Decimal, // Flow-adjusted, so deposits and withdrawals are not counted as growth
+}
+
+/// Equity curve point DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityPointDto {
+    pub timestamp: DateTime<Utc>,
+    pub total_value_usd: Decimal,
+    pub realized_pnl_usd: Decimal,
+    pub unrealized_pnl_usd: Decimal,
+    pub net_flow_usd: Decimal, // Deposits minus withdrawals recorded since the previous point
+    pub drawdown_percentage: Decimal, // Below the running peak
+}
+
+/// Daily return DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyReturnDto {
+    pub date: String, // UTC day, "YYYY-MM-DD"
+    pub close_value_usd: Decimal,
+    pub return_percentage: Decimal,
+}
+
+/// Request to record a deposit or withdrawal against a user's account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordTransferRequest {
+    pub user_id: String,
+    pub exchange: String,
+    pub asset: String,
+    pub kind: String, // "DEPOSIT" or "WITHDRAWAL"
+    pub amount: Decimal, // Positive; kind gives the direction
+    pub occurred_at: Option<DateTime<Utc>>, // Defaults to now
+    pub note: Option<String>,
+}
+
+/// Request to list the user's deposits and withdrawals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTransfersRequest {
+    pub exchange: Option<String>, // Omit for every exchange
+}
+
+/// Response containing the user's transfers, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetTransfersResponse {
+    pub transfers: Vec<TransferDto>,
+}
+
+/// Account transfer DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferDto {
+    pub transfer_id: String,
+    pub user_id: String,
+    pub exchange: String,
+    pub asset: String,
+    pub kind: String,
+    pub amount: Decimal,
+    pub note: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>, // When it reached the user's balances
+}
+
 /// Request to get trade history
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetTradeHistoryRequest {
@@ -278,6 +637,8 @@ pub struct TradingErrorResponse {
     pub error: String,
     pub error_code: Option<String>,
     pub details: Option<String>,
+    /// How long to wait before retrying, when waiting is what it takes
+    pub retry_after_seconds: Option<u64>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -287,6 +648,7 @@ impl TradingErrorResponse {
             error,
             error_code: None,
             details: None,
+            retry_after_seconds: None,
             timestamp: Utc::now(),
         }
     }
@@ -296,6 +658,7 @@ impl TradingErrorResponse {
             error,
             error_code: Some(error_code),
             details: None,
+            retry_after_seconds: None,
             timestamp: Utc::now(),
         }
     }
@@ -305,6 +668,7 @@ impl TradingErrorResponse {
             error,
             error_code: None,
             details: Some(details),
+            retry_after_seconds: None,
             timestamp: Utc::now(),
         }
     }
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use chrono::{DateTime, Utc};
+
+/// Request to register a webhook endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String, // https URL to POST events to
+    pub events: Option<Vec<String>>, // e.g. ["order.filled", "balance.changed"], defaults to every event
+    pub description: Option<String>,
+}
+
+/// Request naming one of the user's webhook endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookRequest {
+    pub webhook_id: String,
+}
+
+/// Webhook endpoint DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpointDto {
+    pub webhook_id: String,
+    pub url: String,
+    pub secret: String, // Key of the HMAC-SHA256 signature on deliveries
+    pub events: Vec<String>,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Response listing the user's webhook endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListWebhooksResponse {
+    pub webhooks: Vec<WebhookEndpointDto>,
+}
+
+/// Request to read the delivery log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetDeliveriesRequest {
+    pub webhook_id: Option<String>, // Omit for every endpoint's deliveries
+    pub limit: Option<u32>, // Defaults to 50, at most 200
+}
+
+/// Response containing the user's deliveries, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetDeliveriesResponse {
+    pub deliveries: Vec<WebhookDeliveryDto>,
+}
+
+/// Webhook delivery DTO
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDeliveryDto {
+    pub delivery_id: String, // Sent as X-Webhook-Id on every attempt
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: Value,
+    pub status: String, // "PENDING", "RETRYING", "SUCCEEDED" or "FAILED"
+    pub attempts: i64,
+    pub response_status: Option<i64>,
+    pub error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
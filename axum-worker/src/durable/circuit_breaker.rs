@@ -0,0 +1,79 @@
+use chrono::Utc;
+use worker::*;
+
+use crate::clients::exchange_policy::{BreakerState, CircuitCheckRequest, CircuitFailureRequest};
+
+/// Storage key of the breaker state
+const STATE_KEY: &str = "state";
+
+/// Circuit breaker of one exchange, named after it, e.g. "binance".
+///
+/// A Durable Object handles one request at a time, so only one caller gets
+/// the trial call of a half-open circuit. State is kept in storage as well
+/// as memory, so an evicted object doesn't close an open circuit.
+#[durable_object]
+pub struct CircuitBreaker {
+    state: State,
+    breaker: Option<BreakerState>,
+}
+
+#[durable_object]
+impl DurableObject for CircuitBreaker {
+    fn new(state: State, _env: Env) -> Self {
+        Self {
+            state,
+            breaker: None,
+        }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let now_ms = Utc::now().timestamp_millis();
+        let path = req.path();
+        match (req.method(), path.as_str()) {
+            (Method::Post, "/check") => {
+                let request: CircuitCheckRequest = match req.json().await {
+                    Ok(request) => request,
+                    Err(e) => return Response::error(format!("Invalid check request: {}", e), 400),
+                };
+                let status = self.load().await.check(request.trial_timeout_ms, now_ms);
+                self.save().await?;
+                Response::from_json(&status)
+            }
+            (Method::Post, "/success") => {
+                let status = self.load().await.record_success(now_ms);
+                self.save().await?;
+                Response::from_json(&status)
+            }
+            (Method::Post, "/failure") => {
+                let request: CircuitFailureRequest = match req.json().await {
+                    Ok(request) => request,
+                    Err(e) => return Response::error(format!("Invalid failure request: {}", e), 400),
+                };
+                let status = self.load().await.record_failure(&request, now_ms);
+                self.save().await?;
+                Response::from_json(&status)
+            }
+            (Method::Get, "/status") => {
+                let status = self.load().await.status(now_ms);
+                Response::from_json(&status)
+            }
+            _ => Response::error("Not found", 404),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    async fn load(&mut self) -> &mut BreakerState {
+        if self.breaker.is_none() {
+            self.breaker = self.state.storage().get(STATE_KEY).await.ok();
+        }
+        self.breaker.get_or_insert_with(BreakerState::default)
+    }
+
+    async fn save(&self) -> Result<()> {
+        if let Some(breaker) = &self.breaker {
+            self.state.storage().put(STATE_KEY, breaker).await?;
+        }
+        Ok(())
+    }
+}
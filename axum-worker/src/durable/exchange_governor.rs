@@ -0,0 +1,96 @@
+use chrono::Utc;
+use worker::*;
+
+use crate::clients::exchange_governor::{exchange_limits, AcquireRequest, GovernorState, ObserveRequest};
+use crate::clients::trading::Exchange;
+
+/// Storage key of the usage counts
+const STATE_KEY: &str = "state";
+
+/// Call counts against one exchange's limits for one API key, named by the
+/// governor client after the pair, e.g. "binance:public".
+///
+/// A Durable Object handles one request at a time, so two calls can't both
+/// take the last of a window. Counts are kept in storage as well as memory,
+/// so an evicted object doesn't forget a window it filled.
+#[durable_object]
+pub struct ExchangeGovernor {
+    state: State,
+    governor: Option<GovernorState>,
+}
+
+#[durable_object]
+impl DurableObject for ExchangeGovernor {
+    fn new(state: State, _env: Env) -> Self {
+        Self {
+            state,
+            governor: None,
+        }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let now_ms = Utc::now().timestamp_millis();
+        let path = req.path();
+        match (req.method(), path.as_str()) {
+            (Method::Post, "/acquire") => {
+                let request: AcquireRequest = match req.json().await {
+                    Ok(request) => request,
+                    Err(e) => return Response::error(format!("Invalid acquire request: {}", e), 400),
+                };
+                let Some(exchange) = Exchange::parse(&request.exchange) else {
+                    return Response::error(format!("Unsupported exchange: {}", request.exchange), 400);
+                };
+                let governor = self.load(exchange).await;
+                let admission = governor.acquire(request.cost, now_ms);
+                self.save().await?;
+                Response::from_json(&admission)
+            }
+            (Method::Post, "/observe") => {
+                let request: ObserveRequest = match req.json().await {
+                    Ok(request) => request,
+                    Err(e) => return Response::error(format!("Invalid observe request: {}", e), 400),
+                };
+                let Some(exchange) = Exchange::parse(&request.exchange) else {
+                    return Response::error(format!("Unsupported exchange: {}", request.exchange), 400);
+                };
+                let governor = self.load(exchange).await;
+                governor.observe(&request.report, now_ms);
+                let usage = governor.usage(now_ms);
+                self.save().await?;
+                Response::from_json(&usage)
+            }
+            (Method::Get, "/usage") => {
+                let url = req.url()?;
+                let exchange = url.query_pairs()
+                    .find(|(key, _)| key == "exchange")
+                    .and_then(|(_, value)| Exchange::parse(&value));
+                let Some(exchange) = exchange else {
+                    return Response::error("Missing or unsupported exchange", 400);
+                };
+                let usage = self.load(exchange).await.usage(now_ms);
+                Response::from_json(&usage)
+            }
+            _ => Response::error("Not found", 404),
+        }
+    }
+}
+
+impl ExchangeGovernor {
+    /// Counts from memory or storage, or fresh ones if the exchange's limits
+    /// have changed since they were stored
+    async fn load(&mut self, exchange: Exchange) -> &mut GovernorState {
+        let limits = exchange_limits(exchange);
+        if self.governor.is_none() {
+            let stored: Option<GovernorState> = self.state.storage().get(STATE_KEY).await.ok();
+            self.governor = stored.filter(|governor| governor.limits() == limits);
+        }
+        self.governor.get_or_insert_with(|| GovernorState::new(limits))
+    }
+
+    async fn save(&self) -> Result<()> {
+        if let Some(governor) = &self.governor {
+            self.state.storage().put(STATE_KEY, governor).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,809 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use worker::*;
+use worker::ws_events::WebsocketEvent;
+
+use crate::clients::market_stream::{FeedMessage, MarketFeed};
+use crate::dto::market_data::{MarketDataEventResponse, MarketStreamAck, MarketStreamFrame};
+use crate::entity::market_data::{Candle, CandleInterval, CandleSource, Instrument, InstrumentKind, MarketDataEvent, RecordedChunk, Trade};
+use crate::entity::trading::TradingOrder;
+use crate::repo::candle::CandleRepository;
+use crate::repo::market_data_archive::{MarketDataArchive, MARKET_DATA_ARCHIVE_BINDING};
+use crate::service::candle_aggregator::CandleAggregator;
+use crate::service::market_data_recorder::MarketDataRecorder;
+use crate::service::order_book::{ApplyOutcome, BookSyncError, LocalOrderBook};
+use crate::service::trading::TradingService;
+use crate::service::trigger_monitor::{any_triggered, PriceUpdate};
+use crate::state::init_trading_service;
+
+/// Binding of the `MarketStream` namespace in wrangler.toml
+pub const MARKET_STREAM_BINDING: &str = "MARKET_STREAM";
+
+/// Channels a client can receive, named after `MarketDataEventResponse::event_type`
+pub const CHANNELS: [&str; 3] = ["trade", "orderbook", "candle"];
+
+/// How long a one-minute candle waits for late trades before it is closed
+const CANDLE_LATENESS_SECONDS: i64 = 2;
+
+/// Delay before reconnecting to the exchange after the feed drops
+const RECONNECT_DELAY_MS: i64 = 5_000;
+
+/// How often the object wakes while recording or watching trigger orders
+/// without clients, to keep the feed connected and close candles through
+/// quiet minutes
+const KEEPALIVE_MS: i64 = 30_000;
+
+/// Longest the watched trigger orders are used before being read again, so
+/// new and cancelled orders are picked up
+const TRIGGER_REFRESH_SECONDS: i64 = 5;
+
+/// Levels per side sent in each order book event
+const BOOK_EVENT_DEPTH: usize = 10;
+
+/// Least time between order book snapshot requests while resyncing
+const RESYNC_DELAY_SECONDS: i64 = 5;
+
+/// Most events in one recorded chunk
+const RECORD_CHUNK_EVENTS: usize = 5_000;
+
+/// Longest a recorded chunk stays open, bounding what is lost if the object is evicted
+const RECORD_CHUNK_SECONDS: i64 = 60;
+
+/// Storage key of the instrument this object streams
+const INSTRUMENT_KEY: &str = "instrument";
+
+/// Events kept for `Last-Event-ID` resume
+const HISTORY_LIMIT: usize = 500;
+
+/// Per-client state, kept in the WebSocket attachment so it survives hibernation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StreamClient {
+    client_id: String,
+    channels: Vec<String>,
+}
+
+/// Event with the sequence number used as its SSE id
+#[derive(Debug, Clone)]
+struct StreamEvent {
+    sequence: u64,
+    event_type: String,
+    data: String,
+}
+
+/// Trigger orders held server-side for this instrument, checked against
+/// every trade
+struct TriggerWatch {
+    trading_service: TradingService,
+    pending: RefCell<Vec<TradingOrder>>,
+    loaded_at: Cell<Option<DateTime<Utc>>>,
+    /// Set while orders are loaded or evaluated; trades arriving meanwhile
+    /// are not checked
+    evaluating: Cell<bool>,
+}
+
+/// Open SSE response fed through a channel
+struct EventListener {
+    channels: Vec<String>,
+    sender: UnboundedSender<String>,
+}
+
+/// Clients of the stream and the recent events they can resume from
+struct StreamHub {
+    state: State,
+    sequence: Cell<u64>,
+    history: RefCell<VecDeque<StreamEvent>>,
+    listeners: RefCell<Vec<EventListener>>,
+    /// One-minute candles built from the trades passing through
+    candles: RefCell<Option<CandleAggregator>>,
+    candle_repository: Option<CandleRepository>,
+    /// Local copy of the exchange's order book, rebuilt on each connection
+    book: RefCell<Option<LocalOrderBook>>,
+    /// Buffers events for the archive, when one is bound
+    recorder: RefCell<Option<MarketDataRecorder>>,
+    archive: Option<MarketDataArchive>,
+    /// Fires trigger orders, when the database is configured
+    triggers: Option<Rc<TriggerWatch>>,
+}
+
+/// Durable Object streaming one instrument's market data.
+///
+/// The worker routes every client of an instrument to the same object (named
+/// after `Instrument::id`). The object holds a single upstream connection to
+/// the exchange's public feed and fans its normalized events out to
+/// WebSocket clients and Server-Sent Events listeners subscribed to each
+/// channel. WebSocket clients are accepted through the hibernation API; once
+/// the last client leaves the upstream connection is closed so the object can
+/// hibernate until someone connects again, unless it is recording.
+///
+/// The exchange's order book is kept locally from a snapshot plus
+/// incremental updates, and its top levels are sent on the `orderbook`
+/// channel after every change. When an update is missed the book is resynced:
+/// from the REST snapshot for exchanges that only stream updates, otherwise by
+/// reconnecting so the exchange sends a fresh snapshot.
+///
+/// Trades are also folded into one-minute candles, which are sent on the
+/// `candle` channel and saved to the database as each minute closes.
+///
+/// When the `MARKET_DATA_ARCHIVE` bucket is bound, every event sent is also
+/// recorded there in chunks for replay. Once an instrument has been streamed,
+/// its object then keeps the upstream connection open without clients, and
+/// an alarm wakes it regularly to reconnect if it was evicted.
+///
+/// The trading service sends a `/watch` request when it holds a stop-loss or
+/// take-profit order for the instrument server-side. The object then loads
+/// the instrument's pending trigger orders, checks each trade's price
+/// against them and submits those that fire, keeping the feed open the same
+/// way as recording until none are left. The scheduled trigger evaluation
+/// remains as a backstop.
+#[durable_object]
+pub struct MarketStream {
+    hub: Rc<StreamHub>,
+    feed: Rc<RefCell<Option<MarketFeed>>>,
+    upstream: Rc<RefCell<Option<WebSocket>>>,
+}
+
+#[durable_object]
+impl DurableObject for MarketStream {
+    fn new(state: State, env: Env) -> Self {
+        // Sequences start from the clock so ids keep increasing across restarts
+        let sequence = Utc::now().timestamp_millis().max(0) as u64 * 1_000;
+        let connection_string = env.secret("DB_CONNECTION_STRING").ok().map(|secret| secret.to_string());
+
+        Self {
+            hub: Rc::new(StreamHub {
+                state,
+                sequence: Cell::new(sequence),
+                history: RefCell::new(VecDeque::new()),
+                listeners: RefCell::new(Vec::new()),
+                candles: RefCell::new(None),
+                candle_repository: connection_string.clone().map(CandleRepository::new),
+                book: RefCell::new(None),
+                recorder: RefCell::new(None),
+                archive: env.bucket(MARKET_DATA_ARCHIVE_BINDING).ok().map(MarketDataArchive::new),
+                triggers: connection_string.map(|connection_string| Rc::new(TriggerWatch {
+                    trading_service: init_trading_service(&env, connection_string),
+                    pending: RefCell::new(Vec::new()),
+                    loaded_at: Cell::new(None),
+                    evaluating: Cell::new(false),
+                })),
+            }),
+            feed: Rc::new(RefCell::new(None)),
+            upstream: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    async fn fetch(&mut self, req: Request) -> Result<Response> {
+        let url = req.url()?;
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        let (Some(exchange), Some(base), Some(quote)) = (params.get("exchange"), params.get("base"), params.get("quote")) else {
+            return Response::error("exchange, base and quote are required", 400);
+        };
+        let channels = match params.get("channels") {
+            Some(channels) => match parse_channels(channels.split(',').map(|c| c.trim().to_string()).collect()) {
+                Ok(channels) => channels,
+                Err(e) => return Response::error(e, 400),
+            },
+            None => CHANNELS.iter().map(|c| c.to_string()).collect(),
+        };
+
+        let instrument = Instrument::new(base.clone(), quote.clone(), exchange.clone(), InstrumentKind::Spot);
+        let feed = match MarketFeed::new(instrument.clone()) {
+            Ok(feed) => feed,
+            Err(e) => return Response::error(e, 400),
+        };
+        if self.feed.borrow().is_none() {
+            self.hub.state.storage().put(INSTRUMENT_KEY, &instrument).await?;
+            *self.feed.borrow_mut() = Some(feed);
+        }
+
+        let is_websocket = req.headers().get("Upgrade")?.is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+        let response = if url.path() == "/watch" {
+            let Some(watch) = self.hub.triggers.clone() else {
+                return Response::error("Trigger orders need DB_CONNECTION_STRING", 503);
+            };
+            watch.reload(&instrument).await;
+            console_log!("MARKET STREAM: Watching {} trigger orders on {}", watch.pending.borrow().len(), instrument.id);
+            Response::ok("")
+        } else if is_websocket {
+            self.accept_websocket(&instrument, channels)
+        } else if url.path() == "/events" {
+            let last_event_id = params.get("last_event_id").and_then(|id| id.parse().ok());
+            self.open_event_stream(&instrument, channels, last_event_id)
+        } else {
+            return Response::error("Expected a WebSocket upgrade", 426);
+        };
+
+        self.ensure_upstream().await;
+        self.keep_alive(false).await;
+        response
+    }
+
+    async fn websocket_message(&mut self, ws: WebSocket, message: WebSocketIncomingMessage) -> Result<()> {
+        // The upstream may have been dropped while the object hibernated
+        self.ensure_upstream().await;
+
+        let mut client = ws.deserialize_attachment::<StreamClient>()?.unwrap_or_else(|| StreamClient {
+            client_id: uuid::Uuid::new_v4().to_string(),
+            channels: Vec::new(),
+        });
+
+        let frame = match message {
+            WebSocketIncomingMessage::String(text) => serde_json::from_str::<MarketStreamFrame>(&text)
+                .map_err(|e| format!("Invalid frame: {}", e)),
+            WebSocketIncomingMessage::Binary(_) => Err("Binary frames are not supported".to_string()),
+        };
+        let requested = frame.and_then(|frame| {
+            let channels = match frame.channels {
+                Some(channels) => parse_channels(channels)?,
+                None => CHANNELS.iter().map(|c| c.to_string()).collect(),
+            };
+            Ok((frame.action, channels))
+        });
+
+        let (event_type, message) = match requested {
+            Ok((action, channels)) => match action.as_str() {
+                "subscribe" => {
+                    for channel in channels {
+                        if !client.channels.contains(&channel) {
+                            client.channels.push(channel);
+                        }
+                    }
+                    ("subscribed", None)
+                }
+                "unsubscribe" => {
+                    client.channels.retain(|channel| !channels.contains(channel));
+                    ("unsubscribed", None)
+                }
+                "ping" => ("pong", None),
+                other => ("error", Some(format!("Unknown action: {}", other))),
+            },
+            Err(e) => ("error", Some(e)),
+        };
+
+        ws.serialize_attachment(&client)?;
+        ws.send(&MarketStreamAck {
+            event_type: event_type.to_string(),
+            channels: client.channels,
+            message,
+            timestamp: Utc::now(),
+        })
+    }
+
+    async fn websocket_close(&mut self, ws: WebSocket, _code: usize, _reason: String, _was_clean: bool) -> Result<()> {
+        self.disconnect(&ws);
+        Ok(())
+    }
+
+    async fn websocket_error(&mut self, ws: WebSocket, error: Error) -> Result<()> {
+        console_log!("MARKET STREAM: Client socket error: {}", error);
+        self.disconnect(&ws);
+        Ok(())
+    }
+
+    async fn alarm(&mut self) -> Result<Response> {
+        // Without trades the watched orders aren't refreshed, and the feed would
+        // be kept open for orders cancelled meanwhile
+        let instrument = self.feed.borrow().as_ref().map(|feed| feed.instrument().clone());
+        if let (Some(watch), Some(instrument)) = (&self.hub.triggers, instrument) {
+            if watch.is_watching() && watch.is_stale() && !watch.evaluating.get() {
+                watch.reload(&instrument).await;
+            }
+        }
+
+        if self.hub.keeps_streaming() {
+            if self.upstream.borrow().is_none() {
+                console_log!("MARKET STREAM: Reconnecting upstream feed");
+            }
+            self.ensure_upstream().await;
+            // Minutes without trades are closed here rather than by the next trade
+            self.hub.close_candles();
+        }
+        self.keep_alive(true).await;
+        Response::ok("")
+    }
+}
+
+impl MarketStream {
+    fn accept_websocket(&self, instrument: &Instrument, channels: Vec<String>) -> Result<Response> {
+        let pair = WebSocketPair::new()?;
+        self.hub.state.accept_web_socket(&pair.server);
+        let client = StreamClient {
+            client_id: uuid::Uuid::new_v4().to_string(),
+            channels,
+        };
+        pair.server.serialize_attachment(&client)?;
+        console_log!("MARKET STREAM: Client {} joined {} on {:?}", client.client_id, instrument.id, client.channels);
+
+        Response::from_websocket(pair.client)
+    }
+
+    /// Server-Sent Events body of the stream, starting with any retained
+    /// events newer than `last_event_id`
+    fn open_event_stream(&self, instrument: &Instrument, channels: Vec<String>, last_event_id: Option<u64>) -> Result<Response> {
+        let (sender, receiver) = unbounded();
+
+        if let Some(last_event_id) = last_event_id {
+            let replayed: Vec<String> = self.hub.history.borrow().iter()
+                .filter(|event| event.sequence > last_event_id && channels.contains(&event.event_type))
+                .map(StreamEvent::to_sse)
+                .collect();
+            console_log!("MARKET STREAM: Replaying {} events after {} for {}", replayed.len(), last_event_id, instrument.id);
+            for frame in replayed {
+                let _ = sender.unbounded_send(frame);
+            }
+        }
+
+        self.hub.listeners.borrow_mut().push(EventListener { channels, sender });
+        console_log!("MARKET STREAM: Event listener joined {}", instrument.id);
+
+        let body = receiver.map(|frame| Ok::<Vec<u8>, Error>(frame.into_bytes()));
+        let mut response = Response::from_stream(body)?;
+        response.headers_mut().set("Content-Type", "text/event-stream")?;
+        Ok(response)
+    }
+
+    /// Connect to the exchange feed unless already connected. On failure a
+    /// retry is scheduled with an alarm.
+    async fn ensure_upstream(&self) {
+        if self.upstream.borrow().is_some() {
+            return;
+        }
+
+        if let Err(e) = self.connect_upstream().await {
+            console_log!("MARKET STREAM: Failed to connect upstream feed: {}", e);
+            if let Err(e) = self.hub.state.storage().set_alarm(RECONNECT_DELAY_MS).await {
+                console_log!("MARKET STREAM: Failed to schedule reconnect: {}", e);
+            }
+        }
+    }
+
+    async fn connect_upstream(&self) -> Result<()> {
+        let cached = self.feed.borrow().clone();
+        let mut feed = match cached {
+            Some(feed) => feed,
+            None => {
+                let instrument: Instrument = self.hub.state.storage().get(INSTRUMENT_KEY).await?;
+                MarketFeed::new(instrument)?
+            }
+        };
+        if let Some(url) = feed.precision_url() {
+            load_precision(&mut feed, &url).await;
+        }
+        *self.feed.borrow_mut() = Some(feed.clone());
+
+        let socket = WebSocket::connect(feed.url().parse()?).await?;
+        socket.accept()?;
+        for frame in feed.subscribe_frames() {
+            socket.send_with_str(frame)?;
+        }
+        *self.upstream.borrow_mut() = Some(socket.clone());
+        let mut book = LocalOrderBook::new(feed.instrument().clone(), feed.sequence_rule());
+        if let Some(levels) = feed.book_depth() {
+            book = book.with_max_levels(levels);
+        }
+        if let Some(format) = feed.checksum_format() {
+            book = book.with_checksum_format(format);
+        }
+        *self.hub.book.borrow_mut() = Some(book);
+        if self.hub.archive.is_some() && self.hub.recorder.borrow().is_none() {
+            *self.hub.recorder.borrow_mut() = Some(MarketDataRecorder::new(
+                feed.instrument().id.clone(),
+                RECORD_CHUNK_EVENTS,
+                chrono::Duration::seconds(RECORD_CHUNK_SECONDS),
+            ));
+        }
+        console_log!("MARKET STREAM: Connected upstream feed for {}", feed.instrument().id);
+
+        let hub = self.hub.clone();
+        let upstream = self.upstream.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            match socket.events() {
+                Ok(mut events) => {
+                    let mut last_snapshot_request = None;
+                    'feed: while let Some(event) = events.next().await {
+                        // Updates arriving while the snapshot loads wait in the event stream
+                        if let Some(url) = feed.snapshot_url() {
+                            let due = last_snapshot_request
+                                .is_none_or(|requested| Utc::now() - requested >= chrono::Duration::seconds(RESYNC_DELAY_SECONDS));
+                            if due && !hub.book_synced() {
+                                last_snapshot_request = Some(Utc::now());
+                                hub.load_snapshot(&feed, &url).await;
+                            }
+                        }
+
+                        match event {
+                            Ok(WebsocketEvent::Message(message)) => {
+                                if let Some(text) = message.text() {
+                                    for message in feed.parse_message(&text) {
+                                        if let Err(e) = hub.receive(message) {
+                                            console_log!("MARKET STREAM: Order book for {} out of sync: {}", feed.instrument().id, e);
+                                            // Without a snapshot URL, resubscribing is what gets a fresh snapshot
+                                            if feed.snapshot_url().is_none() {
+                                                let _ = socket.close(Some(1000), Some("Resync"));
+                                                break 'feed;
+                                            }
+                                        }
+                                    }
+                                }
+                                // Event listeners only notice they are gone when a send fails
+                                if !hub.keeps_streaming() {
+                                    console_log!("MARKET STREAM: No clients left, closing upstream feed");
+                                    let _ = socket.close(Some(1000), Some("No subscribers"));
+                                    break;
+                                }
+                            }
+                            Ok(WebsocketEvent::Close(close)) => {
+                                console_log!("MARKET STREAM: Upstream closed ({}): {}", close.code(), close.reason());
+                                break;
+                            }
+                            Err(e) => {
+                                console_log!("MARKET STREAM: Upstream error: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => console_log!("MARKET STREAM: Failed to read upstream events: {}", e),
+            }
+
+            hub.flush_recording();
+
+            // Only reconnect if this is still the live connection and someone is
+            // listening or it is being recorded
+            let is_current = upstream.borrow().as_ref() == Some(&socket);
+            if is_current {
+                upstream.borrow_mut().take();
+                if hub.keeps_streaming() {
+                    if let Err(e) = hub.state.storage().set_alarm(RECONNECT_DELAY_MS).await {
+                        console_log!("MARKET STREAM: Failed to schedule reconnect: {}", e);
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Wake the object again while it records or watches trigger orders with
+    /// the upstream connected. A failed connection has already scheduled its
+    /// own reconnect instead, and unless `rearm`, an alarm that is already
+    /// set is left alone.
+    async fn keep_alive(&self, rearm: bool) {
+        if !self.hub.streams_unattended() || self.upstream.borrow().is_none() {
+            return;
+        }
+        let storage = self.hub.state.storage();
+        if !rearm && storage.get_alarm().await.ok().flatten().is_some() {
+            return;
+        }
+        if let Err(e) = storage.set_alarm(KEEPALIVE_MS).await {
+            console_log!("MARKET STREAM: Failed to schedule keepalive: {}", e);
+        }
+    }
+
+    /// Drop a WebSocket client, closing the upstream feed when it was the last
+    /// client and nothing is being recorded or watched
+    fn disconnect(&self, ws: &WebSocket) {
+        let client_id = ws.deserialize_attachment::<StreamClient>().ok().flatten().map(|client| client.client_id);
+        let _ = ws.close(Some(1000), Some("Closed"));
+
+        let remaining = self.hub.websocket_clients().into_iter()
+            .filter(|(_, client)| Some(&client.client_id) != client_id.as_ref())
+            .count()
+            + self.hub.listeners.borrow().len();
+        console_log!("MARKET STREAM: Client {:?} left, {} remaining", client_id, remaining);
+
+        if remaining == 0 && !self.hub.streams_unattended() {
+            if let Some(upstream) = self.upstream.borrow_mut().take() {
+                console_log!("MARKET STREAM: No clients left, closing upstream feed");
+                let _ = upstream.close(Some(1000), Some("No subscribers"));
+            }
+        }
+    }
+}
+
+impl StreamHub {
+    /// Handle a feed message: publish events and keep the order book in sync
+    fn receive(&self, message: FeedMessage) -> std::result::Result<(), BookSyncError> {
+        let changed = {
+            let mut book = self.book.borrow_mut();
+            match (message, book.as_mut()) {
+                (FeedMessage::Event(event), _) => {
+                    drop(book);
+                    self.publish(event);
+                    return Ok(());
+                }
+                (_, None) => false,
+                (FeedMessage::Snapshot(snapshot), Some(book)) => book.apply_snapshot(snapshot).map(|_| true)?,
+                (FeedMessage::Update(update), Some(book)) => book.apply_update(update)? == ApplyOutcome::Applied,
+            }
+        };
+
+        let top = self.book.borrow().as_ref()
+            .filter(|book| changed && book.is_synced())
+            .map(|book| book.depth(BOOK_EVENT_DEPTH));
+        if let Some(top) = top {
+            self.publish(MarketDataEvent::OrderBook(top));
+        }
+        Ok(())
+    }
+
+    fn book_synced(&self) -> bool {
+        self.book.borrow().as_ref().is_some_and(LocalOrderBook::is_synced)
+    }
+
+    /// Fetch the exchange's REST snapshot and rebuild the book from it
+    async fn load_snapshot(&self, feed: &MarketFeed, url: &str) {
+        console_log!("MARKET STREAM: Loading order book snapshot for {}", feed.instrument().id);
+        let text = match Url::parse(url) {
+            Ok(url) => match Fetch::Url(url).send().await {
+                Ok(mut response) => response.text().await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(Error::from(e.to_string())),
+        };
+        let snapshot = match text {
+            Ok(text) => feed.parse_snapshot(&text),
+            Err(e) => {
+                console_log!("MARKET STREAM: Failed to load order book snapshot: {}", e);
+                return;
+            }
+        };
+        let Some(snapshot) = snapshot else {
+            console_log!("MARKET STREAM: Unrecognized order book snapshot for {}", feed.instrument().id);
+            return;
+        };
+        if let Err(e) = self.receive(FeedMessage::Snapshot(snapshot)) {
+            console_log!("MARKET STREAM: Order book snapshot for {} out of sync: {}", feed.instrument().id, e);
+        }
+    }
+
+    /// Send an event to clients, along with the candles its trade closed
+    fn publish(&self, event: MarketDataEvent) {
+        let closed = match &event {
+            MarketDataEvent::Trade(trade) => {
+                if let Some(watch) = &self.triggers {
+                    watch.on_trade(trade);
+                }
+                let mut candles = self.candles.borrow_mut();
+                let aggregator = candles.get_or_insert_with(|| CandleAggregator::new(
+                    trade.instrument.clone(),
+                    CandleInterval::OneMinute,
+                    chrono::Duration::seconds(CANDLE_LATENESS_SECONDS),
+                ));
+                if !aggregator.push(trade) {
+                    console_log!("MARKET STREAM: Dropped late trade {} for {}", trade.id, trade.instrument.id);
+                }
+                aggregator.close_until(Utc::now())
+            }
+            _ => Vec::new(),
+        };
+
+        self.broadcast(event);
+        self.publish_candles(closed);
+    }
+
+    /// Close the candles whose minute is over, without waiting for a trade
+    fn close_candles(&self) {
+        let closed = self.candles.borrow_mut().as_mut()
+            .map(|aggregator| aggregator.close_until(Utc::now()))
+            .unwrap_or_default();
+        self.publish_candles(closed);
+    }
+
+    /// Save closed candles and send them on the `candle` channel
+    fn publish_candles(&self, closed: Vec<Candle>) {
+        if closed.is_empty() {
+            return;
+        }
+        if let Some(repository) = self.candle_repository.clone() {
+            let candles = closed.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(e) = repository.save_candles(&candles, CandleSource::Trades).await {
+                    console_log!("MARKET STREAM: Failed to save candles: {}", e);
+                }
+            });
+        }
+        for candle in closed {
+            self.broadcast(MarketDataEvent::Candle(candle));
+        }
+    }
+
+    /// Number an event, retain it for resume and send it to every client
+    /// subscribed to its channel
+    fn broadcast(&self, event: MarketDataEvent) {
+        let response = MarketDataEventResponse::from(event);
+        let Ok(data) = serde_json::to_string(&response) else { return };
+
+        let finished = self.recorder.borrow_mut().as_mut()
+            .map(|recorder| recorder.record(data.clone(), response.timestamp))
+            .unwrap_or_default();
+        self.save_chunks(finished);
+
+        let sequence = self.sequence.get() + 1;
+        self.sequence.set(sequence);
+        let event = StreamEvent {
+            sequence,
+            event_type: response.event_type,
+            data,
+        };
+
+        for (ws, client) in self.websocket_clients() {
+            if client.channels.contains(&event.event_type) {
+                if let Err(e) = ws.send_with_str(&event.data) {
+                    console_log!("MARKET STREAM: Failed to send to client {}: {}", client.client_id, e);
+                }
+            }
+        }
+
+        let frame = event.to_sse();
+        self.listeners.borrow_mut().retain(|listener| {
+            if listener.channels.contains(&event.event_type) {
+                listener.sender.unbounded_send(frame.clone()).is_ok()
+            } else {
+                !listener.sender.is_closed()
+            }
+        });
+
+        let mut history = self.history.borrow_mut();
+        history.push_back(event);
+        if history.len() > HISTORY_LIMIT {
+            history.pop_front();
+        }
+    }
+
+    /// Save the open recorded chunk, e.g. when the upstream feed closes
+    fn flush_recording(&self) {
+        let chunk = self.recorder.borrow_mut().as_mut().and_then(MarketDataRecorder::flush);
+        self.save_chunks(chunk.into_iter().collect());
+    }
+
+    fn save_chunks(&self, chunks: Vec<RecordedChunk>) {
+        let Some(archive) = self.archive.clone() else { return };
+        if chunks.is_empty() {
+            return;
+        }
+        wasm_bindgen_futures::spawn_local(async move {
+            for chunk in chunks {
+                if let Err(e) = archive.save_chunk(&chunk).await {
+                    console_log!("MARKET STREAM: Failed to record chunk: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Whether the upstream feed should stay open: someone is listening, or
+    /// the events are being recorded or watched
+    fn keeps_streaming(&self) -> bool {
+        self.streams_unattended() || self.has_clients()
+    }
+
+    /// Whether the upstream feed stays open without clients
+    fn streams_unattended(&self) -> bool {
+        self.archive.is_some() || self.triggers.as_ref().is_some_and(|watch| watch.is_watching())
+    }
+
+    fn has_clients(&self) -> bool {
+        !self.listeners.borrow().is_empty() || !self.websocket_clients().is_empty()
+    }
+
+    fn websocket_clients(&self) -> Vec<(WebSocket, StreamClient)> {
+        self.state.get_websockets().into_iter()
+            .filter_map(|ws| {
+                let client = ws.deserialize_attachment::<StreamClient>().ok().flatten()?;
+                Some((ws, client))
+            })
+            .collect()
+    }
+}
+
+impl TriggerWatch {
+    fn is_watching(&self) -> bool {
+        !self.pending.borrow().is_empty()
+    }
+
+    fn is_stale(&self) -> bool {
+        self.loaded_at.get()
+            .is_none_or(|loaded_at| Utc::now() - loaded_at >= chrono::Duration::seconds(TRIGGER_REFRESH_SECONDS))
+    }
+
+    /// Read the instrument's pending orders again. On failure the previous
+    /// ones are kept and the next trade tries again.
+    async fn reload(&self, instrument: &Instrument) {
+        match self.trading_service.pending_triggers_for(&instrument.exchange, &instrument.base, &instrument.quote).await {
+            Ok(orders) => {
+                *self.pending.borrow_mut() = orders;
+                self.loaded_at.set(Some(Utc::now()));
+            }
+            Err(e) => console_log!("MARKET STREAM: Failed to load trigger orders for {}: {}", instrument.id, e),
+        }
+    }
+
+    /// Check a trade against the pending orders, submitting those it fires.
+    /// The work runs in the background so the feed isn't held up.
+    fn on_trade(self: &Rc<Self>, trade: &Trade) {
+        if self.evaluating.get() {
+            return;
+        }
+        let stale = self.is_stale();
+        // Nothing is watched until the next `/watch` request
+        let Some(update) = self.price_update(trade.price) else { return };
+        if !stale && !any_triggered(&self.pending.borrow(), &update) {
+            return;
+        }
+
+        self.evaluating.set(true);
+        let watch = self.clone();
+        let instrument = trade.instrument.clone();
+        let last_price = trade.price;
+        wasm_bindgen_futures::spawn_local(async move {
+            if stale {
+                watch.reload(&instrument).await;
+            }
+            let pending = watch.pending.borrow().clone();
+            let update = watch.price_update(last_price).filter(|update| any_triggered(&pending, update));
+            if let Some(update) = update {
+                match watch.trading_service.process_price_update(update, pending).await {
+                    Ok(responses) if !responses.is_empty() => {
+                        console_log!("MARKET STREAM: {} trigger order(s) fired on {} at {}", responses.len(), instrument.id, last_price);
+                    }
+                    Ok(_) => {}
+                    Err(e) => console_log!("MARKET STREAM: Failed to evaluate trigger orders for {}: {}", instrument.id, e),
+                }
+                // Fired orders and their groups' siblings have changed
+                watch.loaded_at.set(None);
+            }
+            watch.evaluating.set(false);
+        });
+    }
+
+    /// A trade price as an update for the watched orders. The feed carries
+    /// no mark price, so mark-price orders are left to the scheduled
+    /// evaluation.
+    fn price_update(&self, last_price: Decimal) -> Option<PriceUpdate> {
+        self.pending.borrow().first().map(|order| PriceUpdate {
+            exchange: order.instrument.exchange.clone(),
+            symbol: order.instrument.symbol.clone(),
+            last_price,
+            mark_price: None,
+        })
+    }
+}
+
+impl StreamEvent {
+    fn to_sse(&self) -> String {
+        format!("id: {}\nevent: {}\ndata: {}\n\n", self.sequence, self.event_type, self.data)
+    }
+}
+
+/// Fetch the pair's precision so the feed's checksums can be verified; on
+/// failure the book is kept without them until the next connection
+async fn load_precision(feed: &mut MarketFeed, url: &str) {
+    let text = match Url::parse(url) {
+        Ok(url) => match Fetch::Url(url).send().await {
+            Ok(mut response) => response.text().await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(Error::from(e.to_string())),
+    };
+    match text {
+        Ok(text) if feed.apply_precision(&text) => {}
+        Ok(_) => console_log!("MARKET STREAM: Unrecognized precision for {}, checksums not verified", feed.instrument().id),
+        Err(e) => console_log!("MARKET STREAM: Failed to load precision for {}, checksums not verified: {}", feed.instrument().id, e),
+    }
+}
+
+/// Validate requested channel names
+pub fn parse_channels(channels: Vec<String>) -> std::result::Result<Vec<String>, String> {
+    let channels: Vec<String> = channels.into_iter().map(|c| c.to_lowercase()).collect();
+    match channels.iter().find(|channel| !CHANNELS.contains(&channel.as_str())) {
+        Some(unknown) => Err(format!("Unknown channel: {} (expected trade, orderbook or candle)", unknown)),
+        None => Ok(channels),
+    }
+}
@@ -0,0 +1,6 @@
+// Durable Objects exported by the worker
+pub mod circuit_breaker;
+pub mod exchange_governor;
+pub mod market_stream;
+pub mod rate_limiter;
+pub mod strategy_runner;
@@ -0,0 +1,92 @@
+use chrono::Utc;
+use worker::*;
+
+use crate::service::rate_limit::{LoginFailureRequest, LoginFailures, RateLimit, TokenBucket};
+
+/// Binding of the `RateLimiter` namespace in wrangler.toml
+pub const RATE_LIMITER_BINDING: &str = "RATE_LIMITER";
+
+/// Storage key of the token bucket
+const BUCKET_KEY: &str = "bucket";
+
+/// Storage key of the failed login record
+const FAILURES_KEY: &str = "failures";
+
+/// Token bucket or failed login count of one principal, named by the
+/// rate limit service after what it counts, e.g. "orders:user:alice" or
+/// "lockout:alice".
+///
+/// A Durable Object handles one request at a time, so taking a token is
+/// never raced. State is kept in storage as well as memory, so an evicted
+/// object doesn't hand out a fresh bucket.
+#[durable_object]
+pub struct RateLimiter {
+    state: State,
+    bucket: Option<TokenBucket>,
+    failures: Option<LoginFailures>,
+}
+
+#[durable_object]
+impl DurableObject for RateLimiter {
+    fn new(state: State, _env: Env) -> Self {
+        Self {
+            state,
+            bucket: None,
+            failures: None,
+        }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let now_ms = Utc::now().timestamp_millis();
+        let path = req.path();
+        match (req.method(), path.as_str()) {
+            (Method::Post, "/take") => {
+                let limit: RateLimit = match req.json().await {
+                    Ok(limit) => limit,
+                    Err(e) => return Response::error(format!("Invalid rate limit: {}", e), 400),
+                };
+                if self.bucket.is_none() {
+                    self.bucket = self.state.storage().get(BUCKET_KEY).await.ok();
+                }
+                let bucket = self.bucket.get_or_insert_with(|| TokenBucket::full(limit, now_ms));
+                let decision = bucket.take(limit, now_ms);
+                self.state.storage().put(BUCKET_KEY, &*bucket).await?;
+                Response::from_json(&decision)
+            }
+            (Method::Get, "/lockout") => {
+                let failures = self.load_failures().await;
+                Response::from_json(&failures.status(now_ms))
+            }
+            (Method::Post, "/failure") => {
+                let request: LoginFailureRequest = match req.json().await {
+                    Ok(request) => request,
+                    Err(e) => return Response::error(format!("Invalid lockout policy: {}", e), 400),
+                };
+                let mut failures = self.load_failures().await;
+                let status = failures.record_failure(request.policy, now_ms);
+                self.state.storage().put(FAILURES_KEY, &failures).await?;
+                self.failures = Some(failures);
+                Response::from_json(&status)
+            }
+            (Method::Post, "/success") => {
+                let failures = LoginFailures::default();
+                let status = failures.status(now_ms);
+                self.state.storage().delete(FAILURES_KEY).await?;
+                self.failures = Some(failures);
+                Response::from_json(&status)
+            }
+            _ => Response::error("Not found", 404),
+        }
+    }
+}
+
+impl RateLimiter {
+    async fn load_failures(&mut self) -> LoginFailures {
+        if let Some(failures) = &self.failures {
+            return failures.clone();
+        }
+        let failures: LoginFailures = self.state.storage().get(FAILURES_KEY).await.unwrap_or_default();
+        self.failures = Some(failures.clone());
+        failures
+    }
+}
@@ -0,0 +1,510 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+use futures::StreamExt;
+use chrono::{DateTime, Utc};
+use worker::*;
+use worker::ws_events::WebsocketEvent;
+
+use crate::dto::market_data::MarketDataEventResponse;
+use crate::durable::market_stream::MARKET_STREAM_BINDING;
+use crate::dto::trading::CancelOrderRequest;
+use crate::entity::strategy::StrategyStatus;
+use crate::entity::trading::OrderSide;
+use crate::repo::strategy::StrategyRepository;
+use crate::service::strategy_runtime::{RuntimeAction, RuntimeState, StrategyConfig, StrategyRuntime};
+use crate::service::strategy_service::convert_state_to_status;
+use crate::service::trading::TradingService;
+use crate::state::init_trading_service;
+
+/// Binding of the `StrategyRunner` namespace in wrangler.toml
+pub const STRATEGY_RUNNER_BINDING: &str = "STRATEGY_RUNNER";
+
+/// How often the strategy's timer fires and live orders are checked for fills
+const TIMER_INTERVAL_MS: i64 = 60_000;
+
+/// Delay before reconnecting to the market stream after it drops
+const RECONNECT_DELAY_MS: i64 = 5_000;
+
+/// Least time between saves of the runtime state while events stream in
+const SAVE_INTERVAL_SECONDS: i64 = 10;
+
+/// Storage key of the runtime state
+const STATE_KEY: &str = "runtime";
+
+/// Storage keys of when the strategy's timer and a market stream reconnect
+/// fall due. Both share the object's one alarm, set for the earlier.
+const TIMER_DUE_KEY: &str = "timer_due";
+const RECONNECT_DUE_KEY: &str = "reconnect_due";
+
+/// Market data channels the strategy is fed
+const STRATEGY_CHANNELS: &str = "trade,orderbook";
+
+/// The runtime and what it talks to
+struct Runner {
+    state: State,
+    env: Env,
+    runtime: RefCell<Option<StrategyRuntime>>,
+    /// Connection to the instrument's `MarketStream`
+    upstream: RefCell<Option<WebSocket>>,
+    trading_service: Option<TradingService>,
+    strategy_repository: Option<StrategyRepository>,
+    last_saved: Cell<Option<DateTime<Utc>>>,
+}
+
+/// Durable Object running one strategy instance continuously.
+///
+/// The worker routes each instance to its own object (named after the
+/// instance id). The object subscribes to the instrument's `MarketStream`
+/// object over a WebSocket and feeds every trade and order book event to a
+/// `StrategyRuntime`. In live mode the orders the runtime approves are placed
+/// through the trading service under the instance owner's account, and an
+/// alarm polls them for fills once a minute; the same alarm drives the
+/// strategy's timer and reconnects the stream after it drops. The object has
+/// a single alarm, so both deadlines are kept in storage and the alarm is set
+/// for whichever comes first.
+///
+/// The runtime state is saved to the object's storage every few seconds
+/// while events arrive and after every order, so an evicted object picks up
+/// where it left off at its next alarm.
+#[durable_object]
+pub struct StrategyRunner {
+    runner: Rc<Runner>,
+}
+
+#[durable_object]
+impl DurableObject for StrategyRunner {
+    fn new(state: State, env: Env) -> Self {
+        let connection_string = env.secret("DB_CONNECTION_STRING").ok().map(|secret| secret.to_string());
+
+        Self {
+            runner: Rc::new(Runner {
+                state,
+                runtime: RefCell::new(None),
+                upstream: RefCell::new(None),
+                trading_service: connection_string.clone().map(|connection_string| {
+                    init_trading_service(&env, connection_string)
+                        .with_market_streams(env.durable_object(MARKET_STREAM_BINDING).ok())
+                }),
+                strategy_repository: connection_string.map(StrategyRepository::new),
+                last_saved: Cell::new(None),
+                env,
+            }),
+        }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let path = req.path();
+        match (req.method(), path.as_str()) {
+            (Method::Post, "/start") => {
+                let config: StrategyConfig = match req.json().await {
+                    Ok(config) => config,
+                    Err(e) => return Response::error(format!("Invalid strategy config: {}", e), 400),
+                };
+                self.start(config).await
+            }
+            (Method::Get, "/status") => match self.runner.load().await {
+                Some(state) => Response::from_json(&convert_state_to_status(&state)),
+                None => Response::error("Strategy not found", 404),
+            },
+            (Method::Post, "/stop") => self.stop().await,
+            (Method::Post, "/pause") => self.pause().await,
+            (Method::Post, "/resume") => self.resume().await,
+            _ => Response::error("Not found", 404),
+        }
+    }
+
+    async fn alarm(&mut self) -> Result<Response> {
+        let Some(state) = self.runner.load().await else {
+            return Response::ok("");
+        };
+
+        let now = Utc::now();
+        let timer_due: Option<DateTime<Utc>> = self.runner.state.storage().get(TIMER_DUE_KEY).await.ok();
+        if let Err(e) = self.runner.state.storage().delete(RECONNECT_DUE_KEY).await {
+            console_log!("STRATEGY RUNNER: Failed to clear reconnect: {}", e);
+        }
+
+        if state.status == StrategyStatus::Running {
+            self.runner.ensure_upstream().await;
+        }
+
+        // An alarm that only reconnected leaves the timer where it was
+        if timer_due.is_some_and(|due| due > now) {
+            self.runner.arm().await;
+            return Response::ok("");
+        }
+
+        if state.status == StrategyStatus::Running {
+            let actions = self.runner.with_runtime(|runtime| runtime.on_timer(Utc::now()));
+            self.runner.execute(actions).await;
+        }
+        self.runner.sync_live_orders().await;
+        self.runner.save(true).await;
+
+        if self.runner.is_running() {
+            if let Err(e) = self.runner.schedule(TIMER_DUE_KEY, TIMER_INTERVAL_MS).await {
+                console_log!("STRATEGY RUNNER: Failed to schedule timer: {}", e);
+            }
+        } else {
+            self.runner.finish().await;
+        }
+        Response::ok("")
+    }
+}
+
+impl StrategyRunner {
+    async fn start(&self, config: StrategyConfig) -> Result<Response> {
+        if self.runner.load().await.is_some_and(|state| state.status == StrategyStatus::Running) {
+            return Response::error("Strategy is already running", 409);
+        }
+
+        let strategy_id = config.strategy_id.clone();
+        let runtime = match StrategyRuntime::start(config, Utc::now()) {
+            Ok(runtime) => runtime,
+            Err(e) => return Response::error(e, 400),
+        };
+        console_log!("STRATEGY RUNNER: Starting {} as {} in {} mode on {}", runtime.config().strategy,
+            strategy_id, runtime.config().mode.as_str(), runtime.config().instrument.id);
+        *self.runner.runtime.borrow_mut() = Some(runtime);
+
+        self.runner.save(true).await;
+        self.runner.ensure_upstream().await;
+        self.runner.schedule(TIMER_DUE_KEY, TIMER_INTERVAL_MS).await?;
+
+        match self.runner.snapshot() {
+            Some(state) => Response::from_json(&convert_state_to_status(&state)),
+            None => Response::error("Strategy failed to start", 500),
+        }
+    }
+
+    async fn stop(&self) -> Result<Response> {
+        if self.runner.load().await.is_none() {
+            return Response::error("Strategy not found", 404);
+        }
+
+        let actions = self.runner.with_runtime(|runtime| runtime.stop(StrategyStatus::Stopped, None, Utc::now()));
+        self.runner.execute(actions).await;
+        self.runner.sync_live_orders().await;
+        self.runner.finish().await;
+
+        match self.runner.snapshot() {
+            Some(state) => Response::from_json(&convert_state_to_status(&state)),
+            None => Response::error("Strategy not found", 404),
+        }
+    }
+
+    async fn pause(&self) -> Result<Response> {
+        match self.runner.load().await {
+            Some(state) if state.status == StrategyStatus::Running => {}
+            Some(_) => return Response::error("Strategy is not running", 409),
+            None => return Response::error("Strategy not found", 404),
+        }
+
+        let actions = self.runner.with_runtime(|runtime| runtime.pause(Utc::now()));
+        self.runner.execute(actions).await;
+        self.runner.sync_live_orders().await;
+        self.runner.finish().await;
+
+        match self.runner.snapshot() {
+            Some(state) => Response::from_json(&convert_state_to_status(&state)),
+            None => Response::error("Strategy not found", 404),
+        }
+    }
+
+    async fn resume(&self) -> Result<Response> {
+        match self.runner.load().await {
+            Some(state) if state.status == StrategyStatus::Paused => {}
+            Some(_) => return Response::error("Strategy is not paused", 409),
+            None => return Response::error("Strategy not found", 404),
+        }
+
+        let actions = self.runner.with_runtime(|runtime| runtime.resume(Utc::now()));
+        self.runner.execute(actions).await;
+        self.runner.save(true).await;
+        self.runner.ensure_upstream().await;
+        self.runner.schedule(TIMER_DUE_KEY, TIMER_INTERVAL_MS).await?;
+        self.runner.record_status().await;
+
+        match self.runner.snapshot() {
+            Some(state) => Response::from_json(&convert_state_to_status(&state)),
+            None => Response::error("Strategy not found", 404),
+        }
+    }
+}
+
+impl Runner {
+    /// The runtime's state, restoring the runtime from storage if the object
+    /// was evicted. None if no strategy was ever started here.
+    async fn load(&self) -> Option<RuntimeState> {
+        if let Some(state) = self.snapshot() {
+            return Some(state);
+        }
+
+        let saved: RuntimeState = self.state.storage().get(STATE_KEY).await.ok()?;
+        match StrategyRuntime::restore(saved) {
+            Ok(runtime) => {
+                console_log!("STRATEGY RUNNER: Restored {}", runtime.config().strategy_id);
+                *self.runtime.borrow_mut() = Some(runtime);
+                self.snapshot()
+            }
+            Err(e) => {
+                console_log!("STRATEGY RUNNER: Failed to restore saved state: {}", e);
+                None
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Option<RuntimeState> {
+        self.runtime.borrow().as_ref().map(StrategyRuntime::snapshot)
+    }
+
+    fn with_runtime<T: Default>(&self, action: impl FnOnce(&mut StrategyRuntime) -> T) -> T {
+        self.runtime.borrow_mut().as_mut().map(action).unwrap_or_default()
+    }
+
+    fn is_running(&self) -> bool {
+        self.runtime.borrow().as_ref().is_some_and(StrategyRuntime::is_running)
+    }
+
+    /// Save the runtime state, at most every few seconds unless `force`d
+    async fn save(&self, force: bool) {
+        let now = Utc::now();
+        let due = self.last_saved.get()
+            .is_none_or(|saved| now - saved >= chrono::Duration::seconds(SAVE_INTERVAL_SECONDS));
+        if !force && !due {
+            return;
+        }
+        let Some(state) = self.snapshot() else { return };
+
+        self.last_saved.set(Some(now));
+        if let Err(e) = self.state.storage().put(STATE_KEY, &state).await {
+            console_log!("STRATEGY RUNNER: Failed to save state of {}: {}", state.config.strategy_id, e);
+        }
+    }
+
+    /// Wind down a strategy that is paused or no longer running: record why,
+    /// and drop the market stream and the timer
+    async fn finish(&self) {
+        self.save(true).await;
+        let Some(state) = self.snapshot() else { return };
+        console_log!("STRATEGY RUNNER: {} is {}", state.config.strategy_id, state.status.as_str());
+
+        if let Some(upstream) = self.upstream.borrow_mut().take() {
+            let _ = upstream.close(Some(1000), Some("Strategy stopped"));
+        }
+        // Live orders still working are polled by the timer until they finish
+        let mut storage = self.state.storage();
+        let timer = if state.working_orders.is_empty() {
+            match (storage.delete(RECONNECT_DUE_KEY).await, storage.delete(TIMER_DUE_KEY).await) {
+                (Ok(_), Ok(_)) => storage.delete_alarm().await,
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            }
+        } else {
+            match storage.delete(RECONNECT_DUE_KEY).await {
+                Ok(_) => self.schedule(TIMER_DUE_KEY, TIMER_INTERVAL_MS).await,
+                Err(e) => Err(e),
+            }
+        };
+        if let Err(e) = timer {
+            console_log!("STRATEGY RUNNER: Failed to update timer: {}", e);
+        }
+        self.record_status().await;
+    }
+
+    /// Record that `key`'s deadline falls `delay_ms` from now and set the
+    /// alarm for the earliest deadline
+    async fn schedule(&self, key: &str, delay_ms: i64) -> Result<()> {
+        let due = Utc::now() + chrono::Duration::milliseconds(delay_ms);
+        self.state.storage().put(key, due).await?;
+        self.arm().await;
+        Ok(())
+    }
+
+    /// Set the alarm for the earlier of the timer and reconnect deadlines
+    async fn arm(&self) {
+        let storage = self.state.storage();
+        let timer_due: Option<DateTime<Utc>> = storage.get(TIMER_DUE_KEY).await.ok();
+        let reconnect_due: Option<DateTime<Utc>> = storage.get(RECONNECT_DUE_KEY).await.ok();
+
+        let Some(due) = timer_due.into_iter().chain(reconnect_due).min() else { return };
+        if let Err(e) = storage.set_alarm(due.max(Utc::now())).await {
+            console_log!("STRATEGY RUNNER: Failed to set alarm: {}", e);
+        }
+    }
+
+    /// Copy the status to the instance's record, which strategies are listed from
+    async fn record_status(&self) {
+        let Some(repository) = &self.strategy_repository else { return };
+        let Some((strategy_id, status, reason)) = self.runtime.borrow().as_ref().map(|runtime| {
+            let state = runtime.state();
+            (state.config.strategy_id.clone(), state.status, state.status_reason.clone())
+        }) else { return };
+
+        if let Err(e) = repository.update_status(&strategy_id, status, reason.as_deref()).await {
+            console_log!("STRATEGY RUNNER: Failed to record status of {}: {}", strategy_id, e);
+        }
+    }
+
+    /// Carry out the runtime's live orders, along with any orders their fills lead to
+    async fn execute(&self, actions: Vec<RuntimeAction>) {
+        if actions.is_empty() {
+            return;
+        }
+        let Some(config) = self.runtime.borrow().as_ref().map(|runtime| runtime.config().clone()) else { return };
+        let Some(trading_service) = &self.trading_service else {
+            console_log!("STRATEGY RUNNER: No database connection, dropping {} live actions", actions.len());
+            return;
+        };
+
+        let mut queue: VecDeque<RuntimeAction> = actions.into();
+        while let Some(action) = queue.pop_front() {
+            match action {
+                RuntimeAction::Place { intent, request } => {
+                    let (side, quantity, price) = (request.side.clone(), request.quantity, request.price);
+                    match trading_service.place_order(&config.user_id, *request).await {
+                        Ok(response) => {
+                            console_log!("STRATEGY RUNNER: {} placed {} order {}", config.strategy_id, side, response.order_id);
+                            if let Some(order_side) = OrderSide::parse(&side) {
+                                self.with_runtime(|runtime| runtime.order_placed(response.order_id.clone(), order_side, quantity, price));
+                            }
+                            // Market orders usually fill at once
+                            queue.extend(self.sync_order(trading_service, &config.user_id, &response.order_id).await);
+                        }
+                        Err(e) => {
+                            console_log!("STRATEGY RUNNER: {} failed to place order: {}", config.strategy_id, e.error);
+                            self.with_runtime(|runtime| runtime.order_failed(intent, e.error, Utc::now()));
+                        }
+                    }
+                }
+                RuntimeAction::CancelAll => {
+                    let order_ids = self.runtime.borrow().as_ref().map(StrategyRuntime::working_order_ids).unwrap_or_default();
+                    for order_id in order_ids {
+                        let request = CancelOrderRequest {
+                            exchange: config.instrument.exchange.clone(),
+                            order_id: order_id.clone(),
+                            symbol: format!("{}{}", config.instrument.base, config.instrument.quote),
+                        };
+                        if let Err(e) = trading_service.cancel_order(&config.user_id, request).await {
+                            console_log!("STRATEGY RUNNER: {} failed to cancel order {}: {}", config.strategy_id, order_id, e.error);
+                        }
+                        queue.extend(self.sync_order(trading_service, &config.user_id, &order_id).await);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check every working live order for fills
+    async fn sync_live_orders(&self) {
+        let Some(trading_service) = &self.trading_service else { return };
+        let Some((user_id, order_ids)) = self.runtime.borrow().as_ref()
+            .map(|runtime| (runtime.config().user_id.clone(), runtime.working_order_ids())) else { return };
+
+        let mut actions = Vec::new();
+        for order_id in order_ids {
+            actions.extend(self.sync_order(trading_service, &user_id, &order_id).await);
+        }
+        self.execute(actions).await;
+    }
+
+    async fn sync_order(&self, trading_service: &TradingService, user_id: &str, order_id: &str) -> Vec<RuntimeAction> {
+        match trading_service.get_order(user_id, order_id).await {
+            Ok(order) => self.with_runtime(|runtime| runtime.sync_order(&order, Utc::now())),
+            Err(e) => {
+                console_log!("STRATEGY RUNNER: Failed to check order {}: {}", order_id, e.error);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Subscribe to the market stream unless already subscribed. On failure a
+    /// retry is scheduled with an alarm.
+    async fn ensure_upstream(self: &Rc<Self>) {
+        if self.upstream.borrow().is_some() || !self.is_running() {
+            return;
+        }
+
+        if let Err(e) = self.connect_upstream().await {
+            console_log!("STRATEGY RUNNER: Failed to connect to the market stream: {}", e);
+            if let Err(e) = self.schedule(RECONNECT_DUE_KEY, RECONNECT_DELAY_MS).await {
+                console_log!("STRATEGY RUNNER: Failed to schedule reconnect: {}", e);
+            }
+        }
+    }
+
+    async fn connect_upstream(self: &Rc<Self>) -> Result<()> {
+        let Some(instrument) = self.runtime.borrow().as_ref().map(|runtime| runtime.config().instrument.clone()) else {
+            return Ok(());
+        };
+
+        let mut url = Url::parse("https://market-stream/stream")?;
+        url.query_pairs_mut()
+            .append_pair("exchange", &instrument.exchange)
+            .append_pair("base", &instrument.base)
+            .append_pair("quote", &instrument.quote)
+            .append_pair("channels", STRATEGY_CHANNELS);
+        let mut headers = Headers::new();
+        headers.set("Upgrade", "websocket")?;
+        let request = Request::new_with_init(url.as_str(), RequestInit::new().with_headers(headers))?;
+
+        let stub = self.env.durable_object(MARKET_STREAM_BINDING)?
+            .id_from_name(&instrument.id)?
+            .get_stub()?;
+        let response = stub.fetch_with_request(request).await?;
+        let socket = response.websocket().ok_or_else(|| Error::from("Market stream refused the WebSocket"))?;
+        socket.accept()?;
+        *self.upstream.borrow_mut() = Some(socket.clone());
+        console_log!("STRATEGY RUNNER: Subscribed to the market stream of {}", instrument.id);
+
+        let runner = self.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            match socket.events() {
+                Ok(mut events) => {
+                    while let Some(event) = events.next().await {
+                        match event {
+                            Ok(WebsocketEvent::Message(message)) => {
+                                // Subscription acks don't parse as events and are skipped
+                                let event = message.text()
+                                    .and_then(|text| serde_json::from_str::<MarketDataEventResponse>(&text).ok())
+                                    .and_then(|response| response.to_event());
+                                let Some(event) = event else { continue };
+
+                                let actions = runner.with_runtime(|runtime| runtime.on_event(&event, Utc::now()));
+                                let acted = !actions.is_empty();
+                                runner.execute(actions).await;
+                                runner.save(acted).await;
+                                if !runner.is_running() {
+                                    runner.finish().await;
+                                    break;
+                                }
+                            }
+                            Ok(WebsocketEvent::Close(close)) => {
+                                console_log!("STRATEGY RUNNER: Market stream closed ({}): {}", close.code(), close.reason());
+                                break;
+                            }
+                            Err(e) => {
+                                console_log!("STRATEGY RUNNER: Market stream error: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => console_log!("STRATEGY RUNNER: Failed to read market stream events: {}", e),
+            }
+
+            // Only reconnect if this is still the live connection and the strategy still runs
+            let is_current = runner.upstream.borrow().as_ref() == Some(&socket);
+            if is_current {
+                runner.upstream.borrow_mut().take();
+                if runner.is_running() {
+                    if let Err(e) = runner.schedule(RECONNECT_DUE_KEY, RECONNECT_DELAY_MS).await {
+                        console_log!("STRATEGY RUNNER: Failed to schedule reconnect: {}", e);
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+/// What an alert rule watches for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertCondition {
+    /// Mid price at or above the threshold
+    PriceAbove,
+    /// Mid price at or below the threshold
+    PriceBelow,
+    /// Mid price moved by at least the threshold percentage, either way,
+    /// since the start of the window
+    PercentChange,
+    /// Bid-ask spread at or above the threshold percentage of the bid
+    SpreadAbove,
+    /// One of the user's orders filled
+    OrderFilled,
+}
+
+/// Alert rule status enumeration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertStatus {
+    Active,
+    /// Fired once and not set to repeat; enabling it again re-arms it
+    Triggered,
+    /// Switched off by its owner
+    Disabled,
+}
+
+/// A user's alert rule. Market conditions watch one instrument; an order
+/// filled rule watches every order, or only those on the instrument parts given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    pub user_id: String,
+    pub name: Option<String>,
+    pub condition: AlertCondition,
+    pub exchange: Option<String>,
+    pub base: Option<String>,
+    pub quote: Option<String>,
+    pub threshold: Option<Decimal>,
+    pub window_minutes: Option<i64>,
+    /// Fire every time the condition is met again after it cleared, rather than once
+    pub repeat: bool,
+    pub status: AlertStatus,
+    /// Whether the rule can fire; cleared when it fires and set again once the condition clears
+    pub armed: bool,
+    /// Value last observed for the condition, e.g. the price or spread percentage
+    pub last_value: Option<Decimal>,
+    /// When the rule was last evaluated; order filled rules pick up fills from here
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub triggered_at: Option<DateTime<Utc>>,
+    pub trigger_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Entry in a user's in-app notification feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: String,
+    pub user_id: String,
+    pub alert_id: Option<String>,
+    pub title: String,
+    pub message: String,
+    /// Event details, as sent in the alert.triggered webhook event
+    pub data: Value,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AlertCondition {
+    pub const ALL: [AlertCondition; 5] = [
+        AlertCondition::PriceAbove,
+        AlertCondition::PriceBelow,
+        AlertCondition::PercentChange,
+        AlertCondition::SpreadAbove,
+        AlertCondition::OrderFilled,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertCondition::PriceAbove => "PRICE_ABOVE",
+            AlertCondition::PriceBelow => "PRICE_BELOW",
+            AlertCondition::PercentChange => "PERCENT_CHANGE",
+            AlertCondition::SpreadAbove => "SPREAD_ABOVE",
+            AlertCondition::OrderFilled => "ORDER_FILLED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|condition| condition.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// Whether the condition watches an instrument's quotes
+    pub fn is_market(&self) -> bool {
+        !matches!(self, AlertCondition::OrderFilled)
+    }
+}
+
+impl AlertStatus {
+    pub const ALL: [AlertStatus; 3] = [AlertStatus::Active, AlertStatus::Triggered, AlertStatus::Disabled];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertStatus::Active => "ACTIVE",
+            AlertStatus::Triggered => "TRIGGERED",
+            AlertStatus::Disabled => "DISABLED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str().eq_ignore_ascii_case(value))
+    }
+}
+
+impl AlertRule {
+    /// Short description of the rule, e.g. "BTC/USDT on binance price above 70000"
+    pub fn describe(&self) -> String {
+        let instrument = match (&self.base, &self.quote, &self.exchange) {
+            (Some(base), Some(quote), Some(exchange)) => format!("{}/{} on {}", base, quote, exchange),
+            (_, _, Some(exchange)) => format!("orders on {}", exchange),
+            _ => "any order".to_string(),
+        };
+        let threshold = self.threshold.map(|t| t.normalize().to_string()).unwrap_or_default();
+        match self.condition {
+            AlertCondition::PriceAbove => format!("{} price above {}", instrument, threshold),
+            AlertCondition::PriceBelow => format!("{} price below {}", instrument, threshold),
+            AlertCondition::PercentChange => format!("{} moves {}% in {} minutes",
+                instrument, threshold, self.window_minutes.unwrap_or_default()),
+            AlertCondition::SpreadAbove => format!("{} spread above {}%", instrument, threshold),
+            AlertCondition::OrderFilled => format!("{} filled", instrument),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Background job run by the cron trigger or by an admin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScheduledJob {
+    InstrumentCatalogRefresh,
+    OrderReconciliation,
+    PortfolioSnapshots,
+    CandleAggregation,
+    AlertEvaluation,
+    TriggerEvaluation,
+    LedgerReconciliation,
+}
+
+/// What started a job run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum JobTrigger {
+    Cron,
+    Manual,
+}
+
+/// Job run status enumeration
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum JobRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    /// Not run, because another run held the lock or the job had nothing to do
+    Skipped,
+}
+
+/// One execution of a scheduled job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRun {
+    pub id: String,
+    pub job: ScheduledJob,
+    pub trigger: JobTrigger,
+    pub triggered_by: Option<String>,
+    pub status: JobRunStatus,
+    pub message: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduledJob {
+    pub const ALL: [ScheduledJob; 7] = [
+        ScheduledJob::InstrumentCatalogRefresh,
+        ScheduledJob::OrderReconciliation,
+        ScheduledJob::PortfolioSnapshots,
+        ScheduledJob::CandleAggregation,
+        ScheduledJob::AlertEvaluation,
+        ScheduledJob::TriggerEvaluation,
+        ScheduledJob::LedgerReconciliation,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduledJob::InstrumentCatalogRefresh => "INSTRUMENT_CATALOG_REFRESH",
+            ScheduledJob::OrderReconciliation => "ORDER_RECONCILIATION",
+            ScheduledJob::PortfolioSnapshots => "PORTFOLIO_SNAPSHOTS",
+            ScheduledJob::CandleAggregation => "CANDLE_AGGREGATION",
+            ScheduledJob::AlertEvaluation => "ALERT_EVALUATION",
+            ScheduledJob::TriggerEvaluation => "TRIGGER_EVALUATION",
+            ScheduledJob::LedgerReconciliation => "LEDGER_RECONCILIATION",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|job| job.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// Cron expression the job runs on; each must also be listed under
+    /// `[triggers]` in wrangler.toml
+    pub fn cron(&self) -> &'static str {
+        match self {
+            ScheduledJob::InstrumentCatalogRefresh => "0 */6 * * *",
+            ScheduledJob::OrderReconciliation => "*/5 * * * *",
+            ScheduledJob::PortfolioSnapshots => "0 * * * *",
+            ScheduledJob::CandleAggregation => "*/15 * * * *",
+            ScheduledJob::AlertEvaluation => "* * * * *",
+            ScheduledJob::TriggerEvaluation => "* * * * *",
+            ScheduledJob::LedgerReconciliation => "0 * * * *",
+        }
+    }
+
+    /// How long a run may hold the job's lock before another run can take it over
+    pub fn lock_seconds(&self) -> i64 {
+        match self {
+            ScheduledJob::PortfolioSnapshots => 30 * 60,
+            // Runs every minute, so a crashed run mustn't block many after it
+            ScheduledJob::AlertEvaluation | ScheduledJob::TriggerEvaluation => 2 * 60,
+            _ => 10 * 60,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ScheduledJob::InstrumentCatalogRefresh => "Reload the tradable instruments of every exchange",
+            ScheduledJob::OrderReconciliation => "Refresh working orders from the exchanges and record their fills",
+            ScheduledJob::PortfolioSnapshots => "Snapshot every trading user's portfolio for the equity curve",
+            ScheduledJob::CandleAggregation => "Roll trades up into OHLCV candles",
+            ScheduledJob::AlertEvaluation => "Check users' alerts and send the notifications of those that fire",
+            ScheduledJob::TriggerEvaluation => "Submit server-side stop and take-profit orders whose trigger price was crossed",
+            ScheduledJob::LedgerReconciliation => "Compare each exchange account's balances with the sum of its users' ledgers",
+        }
+    }
+}
+
+impl JobTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobTrigger::Cron => "CRON",
+            JobTrigger::Manual => "MANUAL",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "CRON" => Some(JobTrigger::Cron),
+            "MANUAL" => Some(JobTrigger::Manual),
+            _ => None,
+        }
+    }
+}
+
+impl JobRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobRunStatus::Running => "RUNNING",
+            JobRunStatus::Succeeded => "SUCCEEDED",
+            JobRunStatus::Failed => "FAILED",
+            JobRunStatus::Skipped => "SKIPPED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "RUNNING" => Some(JobRunStatus::Running),
+            "SUCCEEDED" => Some(JobRunStatus::Succeeded),
+            "FAILED" => Some(JobRunStatus::Failed),
+            "SKIPPED" => Some(JobRunStatus::Skipped),
+            _ => None,
+        }
+    }
+}
+
+impl JobRun {
+    pub fn new(job: ScheduledJob, trigger: JobTrigger, triggered_by: Option<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            job,
+            trigger,
+            triggered_by,
+            status: JobRunStatus::Running,
+            message: None,
+            started_at: Utc::now(),
+            finished_at: None,
+        }
+    }
+
+    pub fn finish(&mut self, status: JobRunStatus, message: String) {
+        self.status = status;
+        self.message = Some(message);
+        self.finished_at = Some(Utc::now());
+    }
+}
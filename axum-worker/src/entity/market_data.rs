@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 
 /// Represents a trading instrument (e.g., BTC/USDT)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,17 +58,72 @@ pub struct OrderBook {
     pub exchange_timestamp: DateTime<Utc>,
 }
 
+/// Full order book sent by an exchange, the base that updates apply to
+#[derive(Debug, Clone)]
+pub struct BookSnapshot {
+    pub last_update_id: u64, // Zero when the exchange doesn't number its updates
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+    pub checksum: Option<i32>, // Exchange checksum of the resulting book
+    pub exchange_timestamp: DateTime<Utc>,
+}
+
+/// Incremental order book change; a level with zero quantity is removed
+#[derive(Debug, Clone)]
+pub struct BookUpdate {
+    pub first_update_id: u64,
+    pub last_update_id: u64,
+    pub previous_update_id: Option<u64>, // Id of the update this one follows, where the exchange sends it
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+    pub checksum: Option<i32>, // Exchange checksum of the resulting book
+    pub exchange_timestamp: DateTime<Utc>,
+}
+
+/// Recorded stream events of one instrument, stored together. A chunk never
+/// spans more than one hour, so chunks can be looked up by hour.
+#[derive(Debug, Clone)]
+pub struct RecordedChunk {
+    pub instrument_id: String,
+    pub start: DateTime<Utc>, // Time of the first event
+    pub end: DateTime<Utc>, // Time of the last event
+    pub lines: Vec<String>, // One `MarketDataEventResponse` JSON per line
+}
+
 /// Candlestick/OHLCV data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Candle {
     pub instrument: Instrument,
+    pub interval: CandleInterval,
+    pub open_time: DateTime<Utc>, // Start of the bucket
     pub open: Decimal,
     pub high: Decimal,
     pub low: Decimal,
     pub close: Decimal,
     pub volume: Decimal,
-    pub timestamp: DateTime<Utc>,
-    pub exchange_timestamp: DateTime<Utc>,
+    pub trade_count: Option<u32>, // Not every exchange's kline API reports it
+    pub timestamp: DateTime<Utc>, // When the candle was built
+    pub exchange_timestamp: DateTime<Utc>, // Last trade in the bucket
+}
+
+/// Length of a candle's bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CandleInterval {
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+    OneHour,
+    FourHours,
+    OneDay,
+}
+
+/// Where a stored candle came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandleSource {
+    /// Aggregated from the live trade stream
+    Trades,
+    /// Backfilled from the exchange's kline API, which takes precedence
+    Klines,
 }
 
 /// Market data event wrapper
@@ -118,6 +173,21 @@ impl Trade {
             exchange_timestamp,
         }
     }
+
+    /// Trade reported by an exchange, keeping the exchange's own trade id
+    pub fn from_exchange(
+        exchange_trade_id: String,
+        instrument: Instrument,
+        price: Decimal,
+        quantity: Decimal,
+        side: TradeSide,
+        exchange_timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: exchange_trade_id,
+            ..Self::new(instrument, price, quantity, side, exchange_timestamp)
+        }
+    }
 }
 
 impl OrderBook {
@@ -138,24 +208,104 @@ impl OrderBook {
 }
 
 impl Candle {
-    pub fn new(
+    /// Candle with no volume whose open, high, low and close are all `price`
+    pub fn flat(
         instrument: Instrument,
-        open: Decimal,
-        high: Decimal,
-        low: Decimal,
-        close: Decimal,
-        volume: Decimal,
+        interval: CandleInterval,
+        open_time: DateTime<Utc>,
+        price: Decimal,
         exchange_timestamp: DateTime<Utc>,
     ) -> Self {
         Self {
             instrument,
-            open,
-            high,
-            low,
-            close,
-            volume,
+            interval,
+            open_time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Decimal::ZERO,
+            trade_count: Some(0),
             timestamp: Utc::now(),
             exchange_timestamp,
         }
     }
+
+    /// End of the bucket (exclusive)
+    pub fn close_time(&self) -> DateTime<Utc> {
+        self.open_time + self.interval.duration()
+    }
+
+    /// Whether the candle was built after its bucket ended, so no more
+    /// trades can change it
+    pub fn is_complete(&self) -> bool {
+        self.timestamp >= self.close_time()
+    }
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 6] = [
+        CandleInterval::OneMinute,
+        CandleInterval::FiveMinutes,
+        CandleInterval::FifteenMinutes,
+        CandleInterval::OneHour,
+        CandleInterval::FourHours,
+        CandleInterval::OneDay,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::FifteenMinutes => "15m",
+            CandleInterval::OneHour => "1h",
+            CandleInterval::FourHours => "4h",
+            CandleInterval::OneDay => "1d",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|interval| interval.as_str().eq_ignore_ascii_case(value))
+    }
+
+    pub fn seconds(&self) -> i64 {
+        match self {
+            CandleInterval::OneMinute => 60,
+            CandleInterval::FiveMinutes => 5 * 60,
+            CandleInterval::FifteenMinutes => 15 * 60,
+            CandleInterval::OneHour => 60 * 60,
+            CandleInterval::FourHours => 4 * 60 * 60,
+            CandleInterval::OneDay => 24 * 60 * 60,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::seconds(self.seconds())
+    }
+
+    /// Start of the bucket holding `timestamp`. Buckets are aligned to the
+    /// Unix epoch, so days start at midnight UTC and 4h buckets at 00:00,
+    /// 04:00, ... UTC, matching the exchanges' klines.
+    pub fn bucket_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let millis = self.seconds() * 1000;
+        let start = timestamp.timestamp_millis().div_euclid(millis) * millis;
+        DateTime::from_timestamp_millis(start).unwrap_or(timestamp)
+    }
+}
+
+impl CandleSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleSource::Trades => "TRADES",
+            CandleSource::Klines => "KLINES",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_uppercase().as_str() {
+            "TRADES" => Some(CandleSource::Trades),
+            "KLINES" => Some(CandleSource::Klines),
+            _ => None,
+        }
+    }
 }
@@ -2,3 +2,8 @@ pub mod user;
 pub mod role_type;
 pub mod market_data;
 pub mod trading;
+pub mod job;
+pub mod strategy;
+pub mod alert;
+pub mod webhook;
+pub mod signal;
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+/// What became of an inbound trading signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignalStatus {
+    /// Mapped to an order, which the exchange accepted
+    Placed,
+    /// Malformed, or stopped by a risk check; nothing was sent
+    Rejected,
+    /// Passed the checks, but placing the order failed
+    Failed,
+}
+
+/// A user's inbound signal webhook. External alerting tools post signals to
+/// a URL holding the secret token, which stands in for the user's login.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalHook {
+    pub id: String,
+    pub user_id: String,
+    pub token: String,
+    /// Risk limits every signal's order is checked against
+    pub risk_limits: Value,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A signal received on a user's hook, and the order it led to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingSignal {
+    pub id: String,
+    pub hook_id: String,
+    pub user_id: String,
+    /// Body as received
+    pub payload: String,
+    pub status: SignalStatus,
+    pub error: Option<String>,
+    pub exchange: Option<String>,
+    pub symbol: Option<String>,
+    pub side: Option<String>,
+    pub order_type: Option<String>,
+    /// Order quantity, after sizing a percent of balance signal
+    pub quantity: Option<Decimal>,
+    pub order_id: Option<String>,
+    pub order_status: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+impl SignalStatus {
+    pub const ALL: [SignalStatus; 3] = [SignalStatus::Placed, SignalStatus::Rejected, SignalStatus::Failed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalStatus::Placed => "PLACED",
+            SignalStatus::Rejected => "REJECTED",
+            SignalStatus::Failed => "FAILED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str().eq_ignore_ascii_case(value))
+    }
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use chrono::{DateTime, Utc};
+
+/// Where a running strategy's orders go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrategyMode {
+    /// Filled against the live market by the simulator, nothing is sent
+    Paper,
+    /// Placed on the exchange through the trading service
+    Live,
+}
+
+/// Strategy instance status enumeration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrategyStatus {
+    Running,
+    /// Paused by its owner: working orders are cancelled and no market data
+    /// is delivered until it resumes
+    Paused,
+    /// Stopped by its owner
+    Stopped,
+    /// Stopped by the risk engine after breaching a loss limit
+    Halted,
+}
+
+/// A strategy started by a user on one instrument. The runtime state lives
+/// in the instance's `StrategyRunner` Durable Object; this is the record the
+/// user's strategies are listed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyInstance {
+    pub id: String,
+    pub user_id: String,
+    pub strategy: String,
+    pub parameters: Value,
+    pub exchange: String,
+    pub base: String,
+    pub quote: String,
+    pub mode: StrategyMode,
+    pub status: StrategyStatus,
+    pub status_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StrategyMode {
+    pub const ALL: [StrategyMode; 2] = [StrategyMode::Paper, StrategyMode::Live];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StrategyMode::Paper => "PAPER",
+            StrategyMode::Live => "LIVE",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str().eq_ignore_ascii_case(value))
+    }
+}
+
+impl StrategyStatus {
+    pub const ALL: [StrategyStatus; 4] = [
+        StrategyStatus::Running,
+        StrategyStatus::Paused,
+        StrategyStatus::Stopped,
+        StrategyStatus::Halted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StrategyStatus::Running => "RUNNING",
+            StrategyStatus::Paused => "PAUSED",
+            StrategyStatus::Stopped => "STOPPED",
+            StrategyStatus::Halted => "HALTED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// Whether the strategy can still run, i.e. it hasn't been stopped or halted
+    pub fn is_active(&self) -> bool {
+        matches!(self, StrategyStatus::Running | StrategyStatus::Paused)
+    }
+}
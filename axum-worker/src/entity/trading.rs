@@ -44,6 +44,7 @@ pub enum InstrumentStatus {
 pub struct TradingOrder {
     pub id: String,
     pub user_id: String,
+    pub client_order_id: Option<String>,
     pub exchange_order_id: Option<String>,
     pub instrument: TradingInstrument,
     pub side: OrderSide,
@@ -55,6 +56,10 @@ pub struct TradingOrder {
     pub average_price: Option<Decimal>,
     pub commission: Decimal,
     pub commission_asset: String,
+    pub stop_price: Option<Decimal>,
+    pub trigger_by: TriggerPriceType,
+    pub group_id: Option<String>,
+    pub parent_order_id: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub executed_at: Option<DateTime<Utc>>,
@@ -87,6 +92,51 @@ pub enum OrderStatus {
     Cancelled,
     Rejected,
     Expired,
+    PendingTrigger,
+    AwaitingParent,
+    PendingSubmit,
+    /// Claimed by one request, which is sending it to the exchange
+    Submitting,
+}
+
+/// Price source used to evaluate stop and take-profit triggers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum TriggerPriceType {
+    #[default]
+    LastPrice,
+    MarkPrice,
+}
+
+/// Linked group of orders (OCO or bracket) managed server-side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderGroup {
+    pub id: String,
+    pub user_id: String,
+    pub exchange: String,
+    pub symbol: String,
+    pub group_type: OrderGroupType,
+    pub status: OrderGroupStatus,
+    pub quantity: Decimal,
+    pub parent_order_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Order group type enumeration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderGroupType {
+    /// Two exit legs where a fill on one cancels or shrinks the other
+    Oco,
+    /// Entry order whose fills activate an OCO pair of exit legs
+    Bracket,
+}
+
+/// Order group status enumeration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderGroupStatus {
+    Active,
+    Completed,
+    Cancelled,
 }
 
 /// Portfolio balance entity
@@ -111,6 +161,48 @@ pub struct AssetBalance {
     pub usd_value: Decimal,
 }
 
+/// Point-in-time valuation of a user's portfolio on one exchange, or across
+/// all exchanges when `exchange` is `AGGREGATE_EXCHANGE`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    pub id: String,
+    pub user_id: String,
+    pub exchange: String,
+    pub total_value_usd: Decimal,
+    pub realized_pnl_usd: Decimal,
+    pub unrealized_pnl_usd: Decimal,
+    /// USD value of the deposits minus withdrawals recorded since the
+    /// previous snapshot, priced when the snapshot is taken
+    pub net_flow_usd: Decimal,
+    pub taken_at: DateTime<Utc>,
+}
+
+/// Money a user moved into or out of their account on an exchange. The
+/// exchange account is shared, so deposits and withdrawals are recorded
+/// against each user to tell their balances apart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountTransfer {
+    pub id: String,
+    pub user_id: String,
+    pub exchange: String,
+    pub asset: String,
+    pub kind: TransferKind,
+    /// Always positive; the kind gives the direction
+    pub amount: Decimal,
+    pub note: Option<String>,
+    /// When the money moved, as reported by whoever recorded it
+    pub occurred_at: DateTime<Utc>,
+    /// When the transfer was recorded, which is when it reaches the user's balances
+    pub created_at: DateTime<Utc>,
+}
+
+/// Direction of an account transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferKind {
+    Deposit,
+    Withdrawal,
+}
+
 /// Market quote entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketQuote {
@@ -181,6 +273,15 @@ pub enum SessionStatus {
     Ended,
 }
 
+/// Method used to match disposals against acquired lots
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CostBasisMethod {
+    #[default]
+    Fifo,
+    Lifo,
+    AverageCost,
+}
+
 impl TradingInstrument {
     pub fn new(
         symbol: String,
@@ -229,6 +330,7 @@ impl TradingOrder {
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             user_id,
+            client_order_id: None,
             exchange_order_id: None,
             instrument,
             side,
@@ -240,6 +342,10 @@ impl TradingOrder {
             average_price: None,
             commission: Decimal::ZERO,
             commission_asset: "USDT".to_string(),
+            stop_price: None,
+            trigger_by: TriggerPriceType::LastPrice,
+            group_id: None,
+            parent_order_id: None,
             created_at: now,
             updated_at: now,
             executed_at: None,
@@ -251,12 +357,284 @@ impl TradingOrder {
     }
 
     pub fn is_active(&self) -> bool {
-        matches!(self.status, OrderStatus::New | OrderStatus::PartiallyFilled)
+        matches!(self.status, OrderStatus::New | OrderStatus::PartiallyFilled | OrderStatus::PendingTrigger)
     }
 
     pub fn remaining_quantity(&self) -> Decimal {
         self.quantity - self.filled_quantity
     }
+
+    /// Whether the order has reached a final state
+    pub fn is_terminal(&self) -> bool {
+        matches!(self.status, OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Rejected | OrderStatus::Expired)
+    }
+
+    /// Attach a trigger price to a stop-loss or take-profit order
+    pub fn with_trigger(mut self, stop_price: Decimal, trigger_by: TriggerPriceType) -> Self {
+        self.stop_price = Some(stop_price);
+        self.trigger_by = trigger_by;
+        self
+    }
+
+    /// Check whether the given reference price crosses this order's trigger price.
+    ///
+    /// Stop-losses fire when price moves against the position (a sell stop when price
+    /// falls to the stop, a buy stop when it rises to it); take-profits fire on the
+    /// opposite move.
+    pub fn is_triggered_by(&self, price: Decimal) -> bool {
+        let stop_price = match self.stop_price {
+            Some(stop_price) if self.order_type.is_trigger_order() => stop_price,
+            _ => return false,
+        };
+
+        match (&self.order_type, &self.side) {
+            (OrderType::StopLoss | OrderType::StopLossLimit, OrderSide::Sell) => price <= stop_price,
+            (OrderType::StopLoss | OrderType::StopLossLimit, OrderSide::Buy) => price >= stop_price,
+            (OrderType::TakeProfit | OrderType::TakeProfitLimit, OrderSide::Sell) => price >= stop_price,
+            (OrderType::TakeProfit | OrderType::TakeProfitLimit, OrderSide::Buy) => price <= stop_price,
+            _ => false,
+        }
+    }
+}
+
+impl OrderSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "BUY",
+            OrderSide::Sell => "SELL",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<OrderSide> {
+        match value.to_uppercase().as_str() {
+            "BUY" => Some(OrderSide::Buy),
+            "SELL" => Some(OrderSide::Sell),
+            _ => None,
+        }
+    }
+
+    /// The side that closes a position opened on this side
+    pub fn opposite(&self) -> OrderSide {
+        match self {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+}
+
+impl OrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderType::Market => "MARKET",
+            OrderType::Limit => "LIMIT",
+            OrderType::StopLoss => "STOP_LOSS",
+            OrderType::StopLossLimit => "STOP_LOSS_LIMIT",
+            OrderType::TakeProfit => "TAKE_PROFIT",
+            OrderType::TakeProfitLimit => "TAKE_PROFIT_LIMIT",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<OrderType> {
+        match value.to_uppercase().as_str() {
+            "MARKET" => Some(OrderType::Market),
+            "LIMIT" => Some(OrderType::Limit),
+            "STOP_LOSS" => Some(OrderType::StopLoss),
+            "STOP_LOSS_LIMIT" => Some(OrderType::StopLossLimit),
+            "TAKE_PROFIT" => Some(OrderType::TakeProfit),
+            "TAKE_PROFIT_LIMIT" => Some(OrderType::TakeProfitLimit),
+            _ => None,
+        }
+    }
+
+    /// Whether the order waits for a trigger price before it becomes live
+    pub fn is_trigger_order(&self) -> bool {
+        !matches!(self, OrderType::Market | OrderType::Limit)
+    }
+
+    /// Whether the order needs a limit price once it is live
+    pub fn requires_limit_price(&self) -> bool {
+        matches!(self, OrderType::Limit | OrderType::StopLossLimit | OrderType::TakeProfitLimit)
+    }
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::New => "NEW",
+            OrderStatus::PartiallyFilled => "PARTIALLY_FILLED",
+            OrderStatus::Filled => "FILLED",
+            OrderStatus::Cancelled => "CANCELLED",
+            OrderStatus::Rejected => "REJECTED",
+            OrderStatus::Expired => "EXPIRED",
+            OrderStatus::PendingTrigger => "PENDING_TRIGGER",
+            OrderStatus::AwaitingParent => "AWAITING_PARENT",
+            OrderStatus::PendingSubmit => "PENDING_SUBMIT",
+            OrderStatus::Submitting => "SUBMITTING",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<OrderStatus> {
+        match value.to_uppercase().as_str() {
+            "NEW" => Some(OrderStatus::New),
+            "PARTIALLY_FILLED" => Some(OrderStatus::PartiallyFilled),
+            "FILLED" => Some(OrderStatus::Filled),
+            "CANCELLED" | "CANCELED" => Some(OrderStatus::Cancelled),
+            "REJECTED" => Some(OrderStatus::Rejected),
+            "EXPIRED" => Some(OrderStatus::Expired),
+            "PENDING_TRIGGER" => Some(OrderStatus::PendingTrigger),
+            "AWAITING_PARENT" => Some(OrderStatus::AwaitingParent),
+            "PENDING_SUBMIT" => Some(OrderStatus::PendingSubmit),
+            "SUBMITTING" => Some(OrderStatus::Submitting),
+            _ => None,
+        }
+    }
+}
+
+impl TradingSession {
+    pub fn new(user_id: String, exchange: String, started_at: DateTime<Utc>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id,
+            exchange,
+            status: SessionStatus::Active,
+            total_trades: 0,
+            total_volume: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+            unrealized_pnl: Decimal::ZERO,
+            started_at,
+            ended_at: None,
+        }
+    }
+
+    /// Whether an execution falls inside the session
+    pub fn covers(&self, executed_at: DateTime<Utc>) -> bool {
+        executed_at >= self.started_at && self.ended_at.is_none_or(|ended_at| executed_at <= ended_at)
+    }
+}
+
+impl CostBasisMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostBasisMethod::Fifo => "FIFO",
+            CostBasisMethod::Lifo => "LIFO",
+            CostBasisMethod::AverageCost => "AVERAGE_COST",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<CostBasisMethod> {
+        match value.to_uppercase().as_str() {
+            "FIFO" => Some(CostBasisMethod::Fifo),
+            "LIFO" => Some(CostBasisMethod::Lifo),
+            "AVERAGE_COST" | "AVERAGE" => Some(CostBasisMethod::AverageCost),
+            _ => None,
+        }
+    }
+}
+
+impl TransferKind {
+    pub const ALL: [TransferKind; 2] = [TransferKind::Deposit, TransferKind::Withdrawal];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransferKind::Deposit => "DEPOSIT",
+            TransferKind::Withdrawal => "WITHDRAWAL",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str().eq_ignore_ascii_case(value))
+    }
+}
+
+impl AccountTransfer {
+    /// The amount as it moves the balance: positive in, negative out
+    pub fn signed_amount(&self) -> Decimal {
+        match self.kind {
+            TransferKind::Deposit => self.amount,
+            TransferKind::Withdrawal => -self.amount,
+        }
+    }
+}
+
+impl OrderGroup {
+    pub fn new(
+        user_id: String,
+        exchange: String,
+        symbol: String,
+        group_type: OrderGroupType,
+        quantity: Decimal,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id,
+            exchange,
+            symbol,
+            group_type,
+            status: OrderGroupStatus::Active,
+            quantity,
+            parent_order_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, OrderGroupStatus::Active)
+    }
+}
+
+impl OrderGroupType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderGroupType::Oco => "OCO",
+            OrderGroupType::Bracket => "BRACKET",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<OrderGroupType> {
+        match value.to_uppercase().as_str() {
+            "OCO" => Some(OrderGroupType::Oco),
+            "BRACKET" => Some(OrderGroupType::Bracket),
+            _ => None,
+        }
+    }
+}
+
+impl OrderGroupStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderGroupStatus::Active => "ACTIVE",
+            OrderGroupStatus::Completed => "COMPLETED",
+            OrderGroupStatus::Cancelled => "CANCELLED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<OrderGroupStatus> {
+        match value.to_uppercase().as_str() {
+            "ACTIVE" => Some(OrderGroupStatus::Active),
+            "COMPLETED" => Some(OrderGroupStatus::Completed),
+            "CANCELLED" | "CANCELED" => Some(OrderGroupStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+impl TriggerPriceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerPriceType::LastPrice => "LAST",
+            TriggerPriceType::MarkPrice => "MARK",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<TriggerPriceType> {
+        match value.to_uppercase().as_str() {
+            "LAST" | "LAST_PRICE" => Some(TriggerPriceType::LastPrice),
+            "MARK" | "MARK_PRICE" => Some(TriggerPriceType::MarkPrice),
+            _ => None,
+        }
+    }
 }
 
 impl Portfolio {
@@ -298,6 +676,53 @@ impl Portfolio {
         self.total_value_usd = self.balances.iter().map(|b| b.usd_value).sum();
         self.updated_at = Utc::now();
     }
+
+    /// Add to an asset's balance, for combining holdings from several exchanges
+    pub fn add_balance(&mut self, asset: String, free: Decimal, locked: Decimal, usd_value: Decimal) {
+        let (free, locked, usd_value) = match self.get_balance(&asset) {
+            Some(existing) => (existing.free + free, existing.locked + locked, existing.usd_value + usd_value),
+            None => (free, locked, usd_value),
+        };
+        self.update_balance(asset, free, locked, usd_value);
+    }
+}
+
+impl PortfolioSnapshot {
+    /// Exchange name used for snapshots summed across every exchange
+    pub const AGGREGATE_EXCHANGE: &'static str = "all";
+
+    pub fn new(user_id: String, exchange: String, taken_at: DateTime<Utc>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id,
+            exchange,
+            total_value_usd: Decimal::ZERO,
+            realized_pnl_usd: Decimal::ZERO,
+            unrealized_pnl_usd: Decimal::ZERO,
+            net_flow_usd: Decimal::ZERO,
+            taken_at,
+        }
+    }
+
+    /// Add another snapshot's values into this one
+    pub fn accumulate(&mut self, other: &PortfolioSnapshot) {
+        self.total_value_usd += other.total_value_usd;
+        self.realized_pnl_usd += other.realized_pnl_usd;
+        self.unrealized_pnl_usd += other.unrealized_pnl_usd;
+        self.net_flow_usd += other.net_flow_usd;
+    }
+}
+
+impl TradingOrderBook {
+    pub fn new(instrument: TradingInstrument, bids: Vec<OrderBookLevel>, asks: Vec<OrderBookLevel>, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            instrument,
+            bids,
+            asks,
+            timestamp,
+        }
+    }
 }
 
 impl MarketQuote {
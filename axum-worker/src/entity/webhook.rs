@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Order, account and alert event a webhook endpoint can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    /// Accepted by the exchange, or by the trigger monitor for server-side stops
+    OrderCreated,
+    OrderFilled,
+    /// Cancelled by the user, a group, or the exchange
+    OrderCancelled,
+    /// Turned down by the exchange, or failed to go live
+    OrderRejected,
+    /// A fill moved the user's base and quote balances
+    BalanceChanged,
+    /// One of the user's alert rules fired
+    AlertTriggered,
+}
+
+/// Webhook delivery status enumeration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    /// Queued for its first attempt
+    Pending,
+    /// Failed at least once and queued to be tried again
+    Retrying,
+    Succeeded,
+    /// Gave up after the last attempt
+    Failed,
+}
+
+/// URL a user registered to receive signed events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub id: String,
+    pub user_id: String,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One event sent, or to be sent, to one endpoint. Retries reuse the
+/// delivery, so its id lets receivers drop duplicates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub endpoint_id: String,
+    pub user_id: String,
+    /// Event name, e.g. "order.filled" or "webhook.test"
+    pub event: String,
+    /// JSON body, signed and sent as is on every attempt
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    /// HTTP status of the last answer
+    pub response_status: Option<i64>,
+    pub error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 6] = [
+        WebhookEvent::OrderCreated,
+        WebhookEvent::OrderFilled,
+        WebhookEvent::OrderCancelled,
+        WebhookEvent::OrderRejected,
+        WebhookEvent::BalanceChanged,
+        WebhookEvent::AlertTriggered,
+    ];
+
+    /// Event name as sent in payloads and the event header
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::OrderCreated => "order.created",
+            WebhookEvent::OrderFilled => "order.filled",
+            WebhookEvent::OrderCancelled => "order.cancelled",
+            WebhookEvent::OrderRejected => "order.rejected",
+            WebhookEvent::BalanceChanged => "balance.changed",
+            WebhookEvent::AlertTriggered => "alert.triggered",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str().eq_ignore_ascii_case(value))
+    }
+}
+
+impl DeliveryStatus {
+    pub const ALL: [DeliveryStatus; 4] = [
+        DeliveryStatus::Pending,
+        DeliveryStatus::Retrying,
+        DeliveryStatus::Succeeded,
+        DeliveryStatus::Failed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "PENDING",
+            DeliveryStatus::Retrying => "RETRYING",
+            DeliveryStatus::Succeeded => "SUCCEEDED",
+            DeliveryStatus::Failed => "FAILED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// Whether no more attempts will be made
+    pub fn is_final(&self) -> bool {
+        matches!(self, DeliveryStatus::Succeeded | DeliveryStatus::Failed)
+    }
+}
+
+impl WebhookEndpoint {
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.is_active && self.events.contains(&event)
+    }
+}
@@ -0,0 +1,159 @@
+use worker::{Request, Response, RouteContext, Result};
+use worker::console_log;
+
+use crate::state::AppState;
+use crate::dto::alert::{AlertRequest, CreateAlertRequest, GetNotificationsRequest, MarkNotificationsReadRequest};
+use crate::entity::alert::AlertStatus;
+use crate::handler::auth::authenticated_user;
+
+/// Handle requests to set up an alert
+pub async fn handle_create_alert(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("ALERTS: Handling create request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: CreateAlertRequest = match req.json::<CreateAlertRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("ALERTS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.alert_service.create_alert(&user_id, request).await {
+        Ok(alert) => Response::from_json(&alert),
+        Err(e) => {
+            console_log!("ALERTS: Failed to create alert: {}", e);
+            Response::error(format!("Failed to create alert: {}", e), 400)
+        }
+    }
+}
+
+/// Handle requests to list the user's alerts
+pub async fn handle_list_alerts(req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("ALERTS: Handling list request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.alert_service.list_alerts(&user_id).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("ALERTS: Failed to list alerts: {}", e);
+            Response::error(format!("Failed to list alerts: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests to switch an alert back on, re-arming it
+pub async fn handle_enable_alert(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    set_alert_status(&mut req, &ctx, AlertStatus::Active).await
+}
+
+/// Handle requests to switch an alert off
+pub async fn handle_disable_alert(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    set_alert_status(&mut req, &ctx, AlertStatus::Disabled).await
+}
+
+/// Handle requests to delete an alert
+pub async fn handle_delete_alert(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("ALERTS: Handling delete request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: AlertRequest = match req.json::<AlertRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("ALERTS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.alert_service.delete_alert(&user_id, &request.alert_id).await {
+        Ok(true) => Response::from_json(&request),
+        Ok(false) => Response::error(format!("Alert not found: {}", request.alert_id), 404),
+        Err(e) => {
+            console_log!("ALERTS: Failed to delete alert {}: {}", request.alert_id, e);
+            Response::error(format!("Failed to delete alert: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests for the user's notification feed
+pub async fn handle_get_notifications(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("ALERTS: Handling notifications request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: GetNotificationsRequest = match req.json::<GetNotificationsRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("ALERTS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.alert_service.get_notifications(&user_id, request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("ALERTS: Failed to load notifications: {}", e);
+            Response::error(format!("Failed to load notifications: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests to mark notifications read
+pub async fn handle_mark_notifications_read(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("ALERTS: Handling mark read request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: MarkNotificationsReadRequest = match req.json::<MarkNotificationsReadRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("ALERTS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.alert_service.mark_notifications_read(&user_id, request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("ALERTS: Failed to mark notifications read: {}", e);
+            Response::error(format!("Failed to mark notifications read: {}", e), 400)
+        }
+    }
+}
+
+async fn set_alert_status(req: &mut Request, ctx: &RouteContext<AppState>, status: AlertStatus) -> Result<Response> {
+    let Some(user_id) = authenticated_user(req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: AlertRequest = match req.json::<AlertRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("ALERTS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+    console_log!("ALERTS: Setting alert {} to {}", request.alert_id, status.as_str());
+
+    match ctx.data.alert_service.set_alert_status(&user_id, &request.alert_id, status).await {
+        Ok(Some(alert)) => Response::from_json(&alert),
+        Ok(None) => Response::error(format!("Alert not found: {}", request.alert_id), 404),
+        Err(e) => {
+            console_log!("ALERTS: Failed to update alert {}: {}", request.alert_id, e);
+            Response::error(format!("Failed to update alert: {}", e), 500)
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crate::dto::user::{RegisterCommand, LoginCommand, UserDTO, LoginResponse};
 use crate::service::auth::AuthenticationService;
 use crate::repo::user::UserRepository;
 use crate::state::AppState;
+use crate::handler::rate_limit::login_locked_response;
 use uuid::Uuid;
 use worker::*;
 
@@ -128,16 +129,27 @@ pub async fn handle_login(mut req: Request, ctx: RouteContext<AppState>) -> Resu
         }
     };
 
+    // Locked accounts are turned away before the password is checked
+    let rate_limit_service = &ctx.data.rate_limit_service;
+    if let Some(retry_after) = rate_limit_service.login_locked(&command.username).await {
+        console_log!("LIVE DATABASE: Login refused, {} is locked for {}s", command.username, retry_after);
+        return login_locked_response(retry_after);
+    }
+
     match command
         .handle(&ctx.data.user_repository, &ctx.data.auth_service)
         .await
     {
         Ok(resp) => {
             console_log!("LIVE DATABASE: Login successful, returning token");
+            rate_limit_service.record_login_success(&command.username).await;
             Response::from_json(&resp)
         }
         Err(e) => {
             console_log!("LIVE DATABASE: Login failed with error: {:?}", e);
+            if let Some(retry_after) = rate_limit_service.record_login_failure(&command.username).await {
+                return login_locked_response(retry_after);
+            }
             match e {
                 UserErrors::InvalidPassword => Response::error("Unauthorized", 401),
                 UserErrors::Exists => Response::error("User exists", 400),
@@ -146,3 +158,34 @@ pub async fn handle_login(mut req: Request, ctx: RouteContext<AppState>) -> Resu
         }
     }
 }
+
+/// Resolve the authenticated username from a `Bearer` token, if present and valid
+pub fn authenticated_user(req: &Request, auth_service: &AuthenticationService) -> Option<String> {
+    let header = req.headers().get("Authorization").ok()??;
+    let token = header.strip_prefix("Bearer ")?;
+
+    match auth_service.verify_token(token) {
+        Ok(username) => Some(username),
+        Err(e) => {
+            console_log!("AUTH: Rejected bearer token: {}", e);
+            None
+        }
+    }
+}
+
+/// Resolve the authenticated username only if that user has the admin role
+pub async fn authenticated_admin(req: &Request, state: &AppState) -> Option<String> {
+    let username = authenticated_user(req, &state.auth_service)?;
+
+    match state.user_repository.get_user(&username).await {
+        Ok(Some(user)) if matches!(user.role, Role::Admin) => Some(username),
+        Ok(_) => {
+            console_log!("AUTH: {} is not an admin", username);
+            None
+        }
+        Err(e) => {
+            console_log!("AUTH: Failed to load {} for admin check: {}", username, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use worker::{Request, Response, RouteContext, Result};
+use worker::console_log;
+
+use crate::state::AppState;
+use crate::dto::backtest::RunBacktestRequest;
+use crate::repo::market_data_archive::{MarketDataArchive, MARKET_DATA_ARCHIVE_BINDING};
+
+/// Handle requests to backtest a strategy over stored market data
+pub async fn handle_run_backtest(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("BACKTEST: Handling backtest request");
+
+    let request: RunBacktestRequest = match req.json::<RunBacktestRequest>().await {
+        Ok(req) => {
+            console_log!("BACKTEST: Backtesting {} on {}/{} on {}",
+                req.strategy, req.base, req.quote, req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("BACKTEST: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    // Recorded trades are only available when the archive bucket is bound
+    let archive = ctx.env.bucket(MARKET_DATA_ARCHIVE_BINDING).ok().map(MarketDataArchive::new);
+
+    match ctx.data.backtest_service.run_backtest(request, archive).await {
+        Ok(response) => {
+            console_log!("BACKTEST: Returning report with {} trades", response.trades.len());
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("BACKTEST: Backtest failed: {}", e);
+            Response::error(format!("Backtest failed: {}", e), 400)
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use worker::{Request, Response, RouteContext, Result};
+use worker::console_log;
+
+use crate::state::AppState;
+use crate::handler::auth::authenticated_admin;
+use crate::dto::job::{GetJobHistoryRequest, GetJobHistoryResponse, JobDto, JobRunDto, ListJobsResponse, RunJobRequest};
+use crate::entity::job::{JobTrigger, ScheduledJob};
+
+/// Default and maximum number of runs returned by the history endpoint
+const DEFAULT_HISTORY_LIMIT: u32 = 50;
+const MAX_HISTORY_LIMIT: u32 = 500;
+
+/// Handle requests to list the registered jobs with their latest run
+pub async fn handle_list_jobs(req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("JOB HANDLER: Handling list jobs request");
+
+    if authenticated_admin(&req, &ctx.data).await.is_none() {
+        return Response::error("Forbidden", 403);
+    }
+
+    let latest_runs = match ctx.data.scheduler_service.latest_runs().await {
+        Ok(runs) => runs,
+        Err(e) => {
+            console_log!("JOB HANDLER: Failed to load latest runs: {}", e);
+            return Response::error(format!("Failed to load job runs: {}", e), 500);
+        }
+    };
+
+    let jobs = ScheduledJob::ALL.into_iter()
+        .map(|job| {
+            let last_run = latest_runs.iter().find(|run| run.job == job).cloned().map(JobRunDto::from);
+            JobDto::new(job, last_run)
+        })
+        .collect();
+
+    Response::from_json(&ListJobsResponse { jobs })
+}
+
+/// Handle requests to run a job immediately
+pub async fn handle_run_job(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("JOB HANDLER: Handling run job request");
+
+    let request: RunJobRequest = match req.json::<RunJobRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("JOB HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(admin) = authenticated_admin(&req, &ctx.data).await else {
+        return Response::error("Forbidden", 403);
+    };
+    let Some(job) = ScheduledJob::parse(&request.job) else {
+        return Response::error(format!("Unknown job: {}", request.job), 404);
+    };
+
+    console_log!("JOB HANDLER: {} triggered {}", admin, job.as_str());
+    let run = ctx.data.scheduler_service.run_job(job, JobTrigger::Manual, Some(admin)).await;
+    Response::from_json(&JobRunDto::from(run))
+}
+
+/// Handle requests for job run history
+pub async fn handle_get_job_history(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("JOB HANDLER: Handling job history request");
+
+    let request: GetJobHistoryRequest = match req.json::<GetJobHistoryRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("JOB HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    if authenticated_admin(&req, &ctx.data).await.is_none() {
+        return Response::error("Forbidden", 403);
+    }
+
+    let job = match request.job.as_deref() {
+        None => None,
+        Some(name) => match ScheduledJob::parse(name) {
+            Some(job) => Some(job),
+            None => return Response::error(format!("Unknown job: {}", name), 404),
+        },
+    };
+    let limit = request.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+
+    match ctx.data.scheduler_service.run_history(job, limit).await {
+        Ok(runs) => Response::from_json(&GetJobHistoryResponse {
+            runs: runs.into_iter().map(JobRunDto::from).collect(),
+        }),
+        Err(e) => {
+            console_log!("JOB HANDLER: Failed to load job history: {}", e);
+            Response::error(format!("Failed to load job runs: {}", e), 500)
+        }
+    }
+}
@@ -1,9 +1,20 @@
-use worker::{Request, Response, RouteContext, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use futures::future::{select, Either};
+use futures::stream::{select_all, Stream, StreamExt};
+use worker::{ByteStream, Delay, Request, Response, RouteContext, Result, Url, WebSocketPair};
 use worker::console_log;
 
 use crate::state::AppState;
+use crate::clients::trading::Exchange;
+use crate::durable::market_stream::{parse_channels, CHANNELS, MARKET_STREAM_BINDING};
+use crate::entity::market_data::{Instrument, InstrumentKind};
+use crate::repo::market_data_archive::{MarketDataArchive, MARKET_DATA_ARCHIVE_BINDING};
+use crate::service::market_replay::{MarketReplayService, ReplayOptions};
 use crate::dto::market_data::{
-    MarketDataSubscriptionRequest, GetInstrumentsRequest, GetTradesRequest, GetTradesResponse
+    MarketDataSubscriptionRequest, GetInstrumentsRequest, GetTradesRequest, GetCandlesRequest,
+    GetIndicatorsRequest
 };
 
 /// Handle market data subscription requests (barter-rs inspired)
@@ -29,7 +40,7 @@ pub async fn handle_subscribe_market_data(mut req: Request, ctx: RouteContext<Ap
         }
         Err(e) => {
             console_log!("MARKET DATA: Subscription failed: {}", e);
-            Response::error(&format!("Subscription failed: {}", e), 500)
+            Response::error(format!("Subscription failed: {}", e), 500)
         }
     }
 }
@@ -53,18 +64,359 @@ pub async fn handle_get_instruments(mut req: Request, ctx: RouteContext<AppState
         }
         Err(e) => {
             console_log!("MARKET DATA: Failed to get instruments: {}", e);
-            Response::error(&format!("Failed to get instruments: {}", e), 500)
+            Response::error(format!("Failed to get instruments: {}", e), 500)
         }
     }
 }
 
-/// Handle requests to get recent trades (mock implementation)
-pub async fn handle_get_trades(mut req: Request, _ctx: RouteContext<AppState>) -> Result<Response> {
+/// Handle live market data stream requests.
+///
+/// WebSocket upgrades are forwarded to the instrument's `MarketStream` Durable
+/// Object. Any other request is served as Server-Sent Events, merging the
+/// event streams of up to `MAX_STREAM_INSTRUMENTS` instruments.
+pub async fn handle_market_data_stream(req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("MARKET DATA: Handling stream request");
+
+    let params: HashMap<String, String> = req.url()?.query_pairs().into_owned().collect();
+    let is_websocket = req.headers().get("Upgrade")?.is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+    if !is_websocket {
+        return handle_market_data_events(&req, &params, &ctx).await;
+    }
+
+    let (Some(exchange), Some(base), Some(quote)) = (params.get("exchange"), params.get("base"), params.get("quote")) else {
+        return Response::error("exchange, base and quote are required", 400);
+    };
+    if Exchange::parse(exchange).is_none() {
+        return Response::error(format!("Unsupported exchange: {}", exchange), 400);
+    }
+
+    let instrument = Instrument::new(base.clone(), quote.clone(), exchange.clone(), InstrumentKind::Spot);
+    console_log!("MARKET DATA: Routing stream for {} to its Durable Object", instrument.id);
+
+    let stub = ctx.env.durable_object(MARKET_STREAM_BINDING)?
+        .id_from_name(&instrument.id)?
+        .get_stub()?;
+    stub.fetch_with_request(req).await
+}
+
+/// Most instruments a single Server-Sent Events connection may follow
+const MAX_STREAM_INSTRUMENTS: usize = 10;
+
+/// Interval of the comment lines that keep idle SSE connections open
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Reconnect delay suggested to SSE clients, in milliseconds
+const SSE_RETRY_MS: u32 = 3_000;
+
+/// Event read back from a Durable Object's SSE stream
+struct SseEvent {
+    instrument_id: String,
+    sequence: u64,
+    event_type: String,
+    data: String,
+}
+
+/// Serve market data as Server-Sent Events.
+///
+/// Each event id lists the last sequence seen per instrument
+/// (`binance:btc:usdt=42,kraken:eth:usd=17`), so a reconnecting client's
+/// `Last-Event-ID` resumes every instrument where it stopped.
+async fn handle_market_data_events(req: &Request, params: &HashMap<String, String>, ctx: &RouteContext<AppState>) -> Result<Response> {
+    let instruments = match stream_instruments(params) {
+        Ok(instruments) => instruments,
+        Err(e) => return Response::error(e, 400),
+    };
+    let channels = match params.get("channels") {
+        Some(channels) => match parse_channels(channels.split(',').map(|c| c.trim().to_string()).collect()) {
+            Ok(channels) => channels.join(","),
+            Err(e) => return Response::error(e, 400),
+        },
+        None => CHANNELS.join(","),
+    };
+
+    let last_event_id = match req.headers().get("Last-Event-ID")? {
+        Some(id) => Some(id),
+        None => params.get("last_event_id").cloned(),
+    };
+    let mut last_ids = last_event_id.as_deref().map(parse_last_event_id).unwrap_or_default();
+    // A bare sequence number resumes a single-instrument stream
+    if let (Some(sequence), [instrument]) = (last_event_id.as_deref().and_then(|id| id.trim().parse::<u64>().ok()), instruments.as_slice()) {
+        last_ids.insert(instrument.id.clone(), sequence);
+    }
+
+    console_log!("MARKET DATA: Opening event stream for {} instruments", instruments.len());
+
+    let namespace = ctx.env.durable_object(MARKET_STREAM_BINDING)?;
+    let mut streams = Vec::with_capacity(instruments.len());
+    for instrument in &instruments {
+        let mut url = Url::parse("https://market-stream/events")?;
+        url.query_pairs_mut()
+            .append_pair("exchange", &instrument.exchange)
+            .append_pair("base", &instrument.base)
+            .append_pair("quote", &instrument.quote)
+            .append_pair("channels", &channels);
+        if let Some(sequence) = last_ids.get(&instrument.id) {
+            url.query_pairs_mut().append_pair("last_event_id", &sequence.to_string());
+        }
+
+        let stub = namespace.id_from_name(&instrument.id)?.get_stub()?;
+        let mut response = stub.fetch_with_str(url.as_str()).await?;
+        if response.status_code() != 200 {
+            let message = response.text().await.unwrap_or_default();
+            console_log!("MARKET DATA: Event stream for {} failed: {}", instrument.id, message);
+            return Response::error(format!("Failed to stream {}: {}", instrument.id, message), response.status_code());
+        }
+        streams.push(Box::pin(sse_events(instrument.id.clone(), response.stream()?)));
+    }
+
+    let events = futures::stream::unfold((select_all(streams), last_ids), |(mut events, mut last_ids)| async move {
+        let next = match select(events.next(), Box::pin(Delay::from(HEARTBEAT_INTERVAL))).await {
+            Either::Left((Some(event), _)) => Some(event),
+            Either::Left((None, _)) => return None,
+            Either::Right(_) => None,
+        };
+
+        let frame = match next {
+            Some(event) => {
+                last_ids.insert(event.instrument_id.clone(), event.sequence);
+                format!("id: {}\nevent: {}\ndata: {}\n\n", format_last_event_id(&last_ids), event.event_type, event.data)
+            }
+            None => ": heartbeat\n\n".to_string(),
+        };
+        Some((frame, (events, last_ids)))
+    });
+
+    let body = futures::stream::once(async { format!("retry: {}\n\n", SSE_RETRY_MS) })
+        .chain(events)
+        .map(|frame| Ok::<Vec<u8>, worker::Error>(frame.into_bytes()));
+
+    let mut response = Response::from_stream(body)?;
+    let headers = response.headers_mut();
+    headers.set("Content-Type", "text/event-stream")?;
+    headers.set("Cache-Control", "no-cache")?;
+    headers.set("X-Accel-Buffering", "no")?;
+    Ok(response)
+}
+
+/// Instruments requested either as `instruments=exchange:BASE:QUOTE,...` or
+/// with the single-instrument `exchange`, `base` and `quote` parameters
+fn stream_instruments(params: &HashMap<String, String>) -> std::result::Result<Vec<Instrument>, String> {
+    let specs: Vec<(String, String, String)> = match params.get("instruments") {
+        Some(list) => list.split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+            .map(|spec| match spec.split(':').collect::<Vec<_>>().as_slice() {
+                [exchange, base, quote] => Ok((exchange.to_string(), base.to_string(), quote.to_string())),
+                _ => Err(format!("Invalid instrument: {} (expected exchange:BASE:QUOTE)", spec)),
+            })
+            .collect::<std::result::Result<_, _>>()?,
+        None => match (params.get("exchange"), params.get("base"), params.get("quote")) {
+            (Some(exchange), Some(base), Some(quote)) => vec![(exchange.clone(), base.clone(), quote.clone())],
+            _ => return Err("exchange, base and quote or instruments are required".to_string()),
+        },
+    };
+
+    if specs.is_empty() {
+        return Err("At least one instrument is required".to_string());
+    }
+    if specs.len() > MAX_STREAM_INSTRUMENTS {
+        return Err(format!("At most {} instruments can be streamed at once", MAX_STREAM_INSTRUMENTS));
+    }
+
+    let mut instruments: Vec<Instrument> = Vec::with_capacity(specs.len());
+    for (exchange, base, quote) in specs {
+        if Exchange::parse(&exchange).is_none() {
+            return Err(format!("Unsupported exchange: {}", exchange));
+        }
+        let instrument = Instrument::new(base, quote, exchange.to_lowercase(), InstrumentKind::Spot);
+        if !instruments.iter().any(|existing| existing.id == instrument.id) {
+            instruments.push(instrument);
+        }
+    }
+    Ok(instruments)
+}
+
+/// Parse a composite `instrument_id=sequence,...` event id
+fn parse_last_event_id(value: &str) -> HashMap<String, u64> {
+    value.split(',')
+        .filter_map(|part| {
+            let (instrument_id, sequence) = part.rsplit_once('=')?;
+            Some((instrument_id.trim().to_lowercase(), sequence.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn format_last_event_id(last_ids: &HashMap<String, u64>) -> String {
+    let mut parts: Vec<String> = last_ids.iter()
+        .map(|(instrument_id, sequence)| format!("{}={}", instrument_id, sequence))
+        .collect();
+    parts.sort();
+    parts.join(",")
+}
+
+/// Split a Durable Object's SSE body back into events
+fn sse_events(instrument_id: String, body: ByteStream) -> impl Stream<Item = SseEvent> {
+    futures::stream::unfold((body, Vec::<u8>::new()), move |(mut body, mut buffer)| {
+        let instrument_id = instrument_id.clone();
+        async move {
+            loop {
+                if let Some(end) = buffer.windows(2).position(|window| window == b"\n\n") {
+                    let block: Vec<u8> = buffer.drain(..end + 2).collect();
+                    if let Some(event) = parse_sse_block(&instrument_id, &String::from_utf8_lossy(&block)) {
+                        return Some((event, (body, buffer)));
+                    }
+                    continue;
+                }
+
+                match body.next().await {
+                    Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                    Some(Err(e)) => {
+                        console_log!("MARKET DATA: Event stream for {} failed: {}", instrument_id, e);
+                        return None;
+                    }
+                    None => return None,
+                }
+            }
+        }
+    })
+}
+
+fn parse_sse_block(instrument_id: &str, block: &str) -> Option<SseEvent> {
+    let mut sequence = None;
+    let mut event_type = None;
+    let mut data = None;
+    for line in block.lines() {
+        match line.split_once(": ") {
+            Some(("id", value)) => sequence = value.parse().ok(),
+            Some(("event", value)) => event_type = Some(value.to_string()),
+            Some(("data", value)) => data = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    Some(SseEvent {
+        instrument_id: instrument_id.to_string(),
+        sequence: sequence?,
+        event_type: event_type?,
+        data: data?,
+    })
+}
+
+/// Default length of a replay when no end time is given
+const DEFAULT_REPLAY_MINUTES: i64 = 60;
+
+/// Longest span a single replay may cover
+const MAX_REPLAY_HOURS: i64 = 24;
+
+/// Fastest replay speed, as a multiple of real time
+const MAX_REPLAY_SPEED: f64 = 1_000.0;
+
+/// Replay recorded market data of one instrument.
+///
+/// Takes `exchange`, `base`, `quote`, `start` (RFC 3339), and optionally
+/// `end`, `speed` and `channels`. Events are sent as the same
+/// `MarketDataEventResponse` JSON as the live stream, over a WebSocket when
+/// the request is an upgrade and as Server-Sent Events otherwise.
+pub async fn handle_market_data_replay(req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("MARKET DATA: Handling replay request");
+
+    let params: HashMap<String, String> = req.url()?.query_pairs().into_owned().collect();
+    let (Some(exchange), Some(base), Some(quote)) = (params.get("exchange"), params.get("base"), params.get("quote")) else {
+        return Response::error("exchange, base and quote are required", 400);
+    };
+    if Exchange::parse(exchange).is_none() {
+        return Response::error(format!("Unsupported exchange: {}", exchange), 400);
+    }
+    let instrument = Instrument::new(base.clone(), quote.clone(), exchange.to_lowercase(), InstrumentKind::Spot);
+
+    let Some(start) = params.get("start").and_then(|start| parse_time(start)) else {
+        return Response::error("start is required as an RFC 3339 time", 400);
+    };
+    let end = match params.get("end") {
+        Some(end) => match parse_time(end) {
+            Some(end) => end,
+            None => return Response::error("end must be an RFC 3339 time", 400),
+        },
+        None => start + chrono::Duration::minutes(DEFAULT_REPLAY_MINUTES),
+    };
+    if end <= start {
+        return Response::error("end must be after start", 400);
+    }
+    if end - start > chrono::Duration::hours(MAX_REPLAY_HOURS) {
+        return Response::error(format!("A replay may span at most {} hours", MAX_REPLAY_HOURS), 400);
+    }
+    let speed = match params.get("speed").map(|speed| speed.parse::<f64>()) {
+        Some(Ok(speed)) if (0.0..=MAX_REPLAY_SPEED).contains(&speed) => speed,
+        Some(_) => return Response::error(format!("speed must be between 0 and {}", MAX_REPLAY_SPEED), 400),
+        None => 1.0,
+    };
+    let channels = match params.get("channels") {
+        Some(channels) => match parse_channels(channels.split(',').map(|c| c.trim().to_string()).collect()) {
+            Ok(channels) => channels,
+            Err(e) => return Response::error(e, 400),
+        },
+        None => CHANNELS.iter().map(|c| c.to_string()).collect(),
+    };
+
+    let Ok(bucket) = ctx.env.bucket(MARKET_DATA_ARCHIVE_BINDING) else {
+        return Response::error("Market data recording is not configured", 503);
+    };
+    let replay_service = MarketReplayService::new(MarketDataArchive::new(bucket));
+    let events = match replay_service.replay(&instrument, ReplayOptions { start, end, speed, channels }).await {
+        Ok(Some(events)) => events,
+        Ok(None) => return Response::error(format!("No market data recorded for {} in that range", instrument.id), 404),
+        Err(e) => {
+            console_log!("MARKET DATA: Failed to start replay: {}", e);
+            return Response::error(format!("Failed to start replay: {}", e), 500);
+        }
+    };
+    let messages = events.filter_map(|event| async move {
+        Some((event.event_type.clone(), serde_json::to_string(&event).ok()?))
+    });
+
+    let is_websocket = req.headers().get("Upgrade")?.is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+    if is_websocket {
+        let pair = WebSocketPair::new()?;
+        let server = pair.server;
+        server.accept()?;
+        wasm_bindgen_futures::spawn_local(async move {
+            let mut messages = Box::pin(messages);
+            while let Some((_, data)) = messages.next().await {
+                if server.send_with_str(&data).is_err() {
+                    return;
+                }
+            }
+            let _ = server.close(Some(1000), Some("Replay complete"));
+        });
+        return Response::from_websocket(pair.client);
+    }
+
+    // Ids count the events sent; a replay is restarted with a new start time rather than resumed
+    let frames = messages
+        .enumerate()
+        .map(|(index, (event_type, data))| format!("id: {}\nevent: {}\ndata: {}\n\n", index + 1, event_type, data))
+        .chain(futures::stream::once(async { "event: end\ndata: {}\n\n".to_string() }))
+        .map(|frame| Ok::<Vec<u8>, worker::Error>(frame.into_bytes()));
+
+    let mut response = Response::from_stream(frames)?;
+    let headers = response.headers_mut();
+    headers.set("Content-Type", "text/event-stream")?;
+    headers.set("Cache-Control", "no-cache")?;
+    headers.set("X-Accel-Buffering", "no")?;
+    Ok(response)
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|time| time.with_timezone(&Utc))
+}
+
+/// Handle requests to get recent public trades from the exchange
+pub async fn handle_get_trades(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
     console_log!("MARKET DATA: Handling get trades request");
-    
-    let _request: GetTradesRequest = match req.json::<GetTradesRequest>().await {
+
+    let request: GetTradesRequest = match req.json::<GetTradesRequest>().await {
         Ok(req) => {
-            console_log!("MARKET DATA: Getting trades for {}/{} on {}", 
+            console_log!("MARKET DATA: Getting trades for {}/{} on {}",
                 req.base, req.quote, req.exchange);
             req
         }
@@ -73,14 +425,76 @@ pub async fn handle_get_trades(mut req: Request, _ctx: RouteContext<AppState>) -
             return Response::error("Invalid JSON request", 400);
         }
     };
-    
-    // For now, return empty trades list as this is a basic integration
-    let response = GetTradesResponse {
-        trades: vec![],
+    if Exchange::parse(&request.exchange).is_none() {
+        return Response::error(format!("Unsupported exchange: {}", request.exchange), 400);
+    }
+
+    match ctx.data.market_data_service.get_recent_trades(request).await {
+        Ok(response) => {
+            console_log!("MARKET DATA: Returning {} trades", response.trades.len());
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("MARKET DATA: Failed to get trades: {}", e);
+            Response::error(format!("Failed to get trades: {}", e), 502)
+        }
+    }
+}
+
+/// Handle requests for OHLCV candles
+pub async fn handle_get_candles(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("MARKET DATA: Handling get candles request");
+
+    let request: GetCandlesRequest = match req.json::<GetCandlesRequest>().await {
+        Ok(req) => {
+            console_log!("MARKET DATA: Getting {} candles for {}/{} on {}",
+                req.interval, req.base, req.quote, req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("MARKET DATA: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
     };
-    
-    console_log!("MARKET DATA: Returning {} trades", response.trades.len());
-    Response::from_json(&response)
+
+    match ctx.data.candle_service.get_candles(request).await {
+        Ok(response) => {
+            console_log!("MARKET DATA: Returning {} candles", response.candles.len());
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("MARKET DATA: Failed to get candles: {}", e);
+            Response::error(format!("Failed to get candles: {}", e), 400)
+        }
+    }
+}
+
+/// Handle requests for technical indicators over an instrument's candles
+pub async fn handle_get_indicators(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("MARKET DATA: Handling get indicators request");
+
+    let request: GetIndicatorsRequest = match req.json::<GetIndicatorsRequest>().await {
+        Ok(req) => {
+            console_log!("MARKET DATA: Getting {} indicators over {} candles for {}/{} on {}",
+                req.indicators.len(), req.interval, req.base, req.quote, req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("MARKET DATA: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.candle_service.get_indicators(request).await {
+        Ok(response) => {
+            console_log!("MARKET DATA: Returning {} indicators over {} candles", response.indicators.len(), response.candles.len());
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("MARKET DATA: Failed to get indicators: {}", e);
+            Response::error(format!("Failed to get indicators: {}", e), 400)
+        }
+    }
 }
 
 /// Handle market data service status requests
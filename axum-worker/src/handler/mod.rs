@@ -1,3 +1,10 @@
 pub mod auth;
 pub mod market_data;
 pub mod trading;
+pub mod job;
+pub mod backtest;
+pub mod strategy;
+pub mod alert;
+pub mod webhook;
+pub mod signal;
+pub mod rate_limit;
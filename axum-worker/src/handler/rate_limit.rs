@@ -0,0 +1,32 @@
+use worker::{Headers, Response, Result};
+
+use crate::service::rate_limit::RateLimitDecision;
+
+/// Response to a request over its rate limit
+pub fn rate_limited_response(decision: &RateLimitDecision) -> Result<Response> {
+    let response = Response::error("Too Many Requests", 429)?;
+    Ok(with_rate_limit_headers(response, decision))
+}
+
+/// Response to a login attempt on a locked account
+pub fn login_locked_response(retry_after_seconds: u32) -> Result<Response> {
+    let mut headers = Headers::new();
+    headers.set("Retry-After", &retry_after_seconds.to_string())?;
+    Ok(Response::error("Too many failed logins, try again later", 429)?.with_headers(headers))
+}
+
+/// Tell the client how much of its limit is left
+pub fn with_rate_limit_headers(response: Response, decision: &RateLimitDecision) -> Response {
+    // Headers of a response fetched from elsewhere can't be changed, so copy them
+    let mut headers = Headers::new();
+    for (name, value) in response.headers().entries() {
+        let _ = headers.append(&name, &value);
+    }
+    let _ = headers.set("X-RateLimit-Limit", &decision.limit.to_string());
+    let _ = headers.set("X-RateLimit-Remaining", &decision.remaining.to_string());
+    let _ = headers.set("X-RateLimit-Reset", &decision.reset_seconds.to_string());
+    if !decision.allowed {
+        let _ = headers.set("Retry-After", &decision.retry_after_seconds.to_string());
+    }
+    response.with_headers(headers)
+}
@@ -0,0 +1,109 @@
+use worker::{Request, Response, RouteContext, Result};
+use worker::console_log;
+
+use crate::state::AppState;
+use crate::dto::signal::{ConfigureSignalHookRequest, GetSignalsRequest};
+use crate::handler::auth::authenticated_user;
+
+/// Handle signals posted by external alerting tools. The token in the path
+/// identifies the user, so no login is needed.
+pub async fn handle_receive_signal(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("SIGNALS: Handling inbound signal");
+
+    let Some(token) = ctx.param("token").cloned() else {
+        return Response::error("Not Found", 404);
+    };
+
+    // Read the raw body so signals that aren't valid JSON are still logged
+    let body = match req.text().await {
+        Ok(body) => body,
+        Err(e) => {
+            console_log!("SIGNALS: Failed to read signal body: {}", e);
+            return Response::error("Invalid request body", 400);
+        }
+    };
+
+    match ctx.data.signal_service.receive_signal(&token, &body).await {
+        Ok(Some(signal)) => {
+            let status = match signal.status.as_str() {
+                "PLACED" => 200,
+                "REJECTED" => 422,
+                _ => 502,
+            };
+            Ok(Response::from_json(&signal)?.with_status(status))
+        }
+        Ok(None) => Response::error("Unknown signal token", 404),
+        Err(e) => {
+            console_log!("SIGNALS: Failed to process signal: {}", e);
+            Response::error(format!("Failed to process signal: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests for the user's signal hook
+pub async fn handle_get_signal_hook(req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("SIGNALS: Handling get hook request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.signal_service.get_hook(&user_id).await {
+        Ok(Some(hook)) => Response::from_json(&hook),
+        Ok(None) => Response::error("No signal hook set up", 404),
+        Err(e) => {
+            console_log!("SIGNALS: Failed to load signal hook: {}", e);
+            Response::error(format!("Failed to load signal hook: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests to set up or change the user's signal hook
+pub async fn handle_configure_signal_hook(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("SIGNALS: Handling configure hook request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: ConfigureSignalHookRequest = match req.json::<ConfigureSignalHookRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("SIGNALS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.signal_service.configure_hook(&user_id, request).await {
+        Ok(hook) => Response::from_json(&hook),
+        Err(e) => {
+            console_log!("SIGNALS: Failed to configure signal hook: {}", e);
+            Response::error(format!("Failed to configure signal hook: {}", e), 400)
+        }
+    }
+}
+
+/// Handle requests for the user's signal log
+pub async fn handle_get_signals(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("SIGNALS: Handling signal log request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: GetSignalsRequest = match req.json::<GetSignalsRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("SIGNALS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.signal_service.get_signals(&user_id, request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("SIGNALS: Failed to load signals: {}", e);
+            Response::error(format!("Failed to load signals: {}", e), 500)
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use worker::{Method, Request, RequestInit, Response, RouteContext, Result};
+use worker::console_log;
+use wasm_bindgen::JsValue;
+
+use crate::state::AppState;
+use crate::dto::strategy::{StartStrategyRequest, StrategyRequest};
+use crate::durable::strategy_runner::STRATEGY_RUNNER_BINDING;
+use crate::entity::strategy::StrategyStatus;
+use crate::handler::auth::authenticated_user;
+
+/// Handle requests to start running a strategy, live or on paper
+pub async fn handle_start_strategy(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("STRATEGY: Handling start request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: StartStrategyRequest = match req.json::<StartStrategyRequest>().await {
+        Ok(req) => {
+            console_log!("STRATEGY: Starting {} on {}/{} on {}", req.strategy, req.base, req.quote, req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("STRATEGY: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let (instance, config) = match ctx.data.strategy_service.create_instance(&user_id, request).await {
+        Ok(created) => created,
+        Err(e) => {
+            console_log!("STRATEGY: Failed to create strategy: {}", e);
+            return Response::error(format!("Failed to start strategy: {}", e), 400);
+        }
+    };
+
+    let body = serde_json::to_string(&config).map_err(|e| worker::Error::from(e.to_string()))?;
+    let mut response = call_runner(&ctx, &instance.id, Method::Post, "/start", Some(body)).await?;
+    if response.status_code() != 200 {
+        let message = response.text().await.unwrap_or_default();
+        console_log!("STRATEGY: Runner for {} failed to start: {}", instance.id, message);
+        if let Err(e) = ctx.data.strategy_service.update_status(&instance.id, StrategyStatus::Stopped, Some(&message)).await {
+            console_log!("STRATEGY: Failed to record status of {}: {}", instance.id, e);
+        }
+        return Response::error(format!("Failed to start strategy: {}", message), response.status_code());
+    }
+
+    console_log!("STRATEGY: Started {} as {}", instance.strategy, instance.id);
+    Ok(response)
+}
+
+/// Handle requests for a running strategy's account, orders and fills
+pub async fn handle_get_strategy(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    forward_to_runner(&mut req, &ctx, Method::Get, "/status").await
+}
+
+/// Handle requests to stop a strategy, cancelling its working orders
+pub async fn handle_stop_strategy(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    forward_to_runner(&mut req, &ctx, Method::Post, "/stop").await
+}
+
+/// Handle requests to pause a strategy, cancelling its working orders until it resumes
+pub async fn handle_pause_strategy(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    forward_to_runner(&mut req, &ctx, Method::Post, "/pause").await
+}
+
+/// Handle requests to resume a paused strategy
+pub async fn handle_resume_strategy(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    forward_to_runner(&mut req, &ctx, Method::Post, "/resume").await
+}
+
+/// Handle requests to list the user's strategies
+pub async fn handle_list_strategies(req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("STRATEGY: Handling list request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.strategy_service.list_instances(&user_id).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("STRATEGY: Failed to list strategies: {}", e);
+            Response::error(format!("Failed to list strategies: {}", e), 500)
+        }
+    }
+}
+
+/// Check the user owns the strategy named in the request, then pass the
+/// request on to its runner
+async fn forward_to_runner(req: &mut Request, ctx: &RouteContext<AppState>, method: Method, path: &str) -> Result<Response> {
+    let Some(user_id) = authenticated_user(req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: StrategyRequest = match req.json::<StrategyRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("STRATEGY: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+    console_log!("STRATEGY: Forwarding {} for {}", path, request.strategy_id);
+
+    match ctx.data.strategy_service.find_instance(&user_id, &request.strategy_id).await {
+        Ok(Some(_)) => call_runner(ctx, &request.strategy_id, method, path, None).await,
+        Ok(None) => Response::error(format!("Strategy not found: {}", request.strategy_id), 404),
+        Err(e) => {
+            console_log!("STRATEGY: Failed to load strategy {}: {}", request.strategy_id, e);
+            Response::error(format!("Failed to load strategy: {}", e), 500)
+        }
+    }
+}
+
+async fn call_runner(
+    ctx: &RouteContext<AppState>,
+    strategy_id: &str,
+    method: Method,
+    path: &str,
+    body: Option<String>,
+) -> Result<Response> {
+    let mut init = RequestInit::new();
+    init.with_method(method).with_body(body.map(|body| JsValue::from_str(&body)));
+    let request = Request::new_with_init(&format!("https://strategy-runner{}", path), &init)?;
+
+    let stub = ctx.env.durable_object(STRATEGY_RUNNER_BINDING)?
+        .id_from_name(strategy_id)?
+        .get_stub()?;
+    stub.fetch_with_request(request).await
+}
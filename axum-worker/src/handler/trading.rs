@@ -2,19 +2,45 @@ use worker::{Request, Response, RouteContext, Result};
 use worker::console_log;
 
 use crate::state::AppState;
+use crate::handler::auth::{authenticated_admin, authenticated_user};
+use crate::clients::exchange_governor::exchange_limits;
+use crate::clients::trading::Exchange;
 use crate::dto::trading::{
-    GetQuoteRequest, GetOrderBookRequest, PlaceOrderRequest, GetBalancesRequest,
-    GetInstrumentsRequest, GetTradingStatusRequest, TradingErrorResponse
+    GetQuoteRequest, GetOrderBookRequest, GetConsolidatedBookRequest, GetBestBidOfferRequest, GetFillQuoteRequest, PlaceOrderRequest, GetBalancesRequest,
+    GetOrderRequest, CancelOrderRequest,
+    GetInstrumentsRequest, GetTradingStatusRequest, PlaceOcoOrderRequest,
+    PlaceBracketOrderRequest, OrderGroupRequest, GetPortfolioRequest, GetEquityCurveRequest, GetAggregatePortfolioRequest,
+    RecordTransferRequest, GetTransfersRequest,
+    TradingErrorResponse
 };
 
 /// Helper function to create error responses
 fn create_error_response(error: &TradingErrorResponse) -> Result<Response> {
+    let status = error_status(error);
     match Response::from_json(error) {
-        Ok(resp) => Ok(resp.with_status(500)),
+        Ok(mut resp) => {
+            if let Some(retry_after) = error.retry_after_seconds {
+                resp.headers_mut().set("Retry-After", &retry_after.to_string())?;
+            }
+            Ok(resp.with_status(status))
+        }
         Err(_) => Response::error("Internal server error", 500),
     }
 }
 
+/// HTTP status of a trading error, from its code
+fn error_status(error: &TradingErrorResponse) -> u16 {
+    match error.error_code.as_deref() {
+        Some("CLIENT_ORDER_ID_REUSED" | "ORDER_NOT_OPEN") => 409,
+        // The order may still be settled; the client should retry after the delay
+        Some("ORDER_STATE_UNKNOWN") => 503,
+        Some("ALL_EXCHANGES_FAILED") => 502,
+        Some(code) if code.ends_with("_NOT_FOUND") => 404,
+        Some(code) if ["INVALID_", "MISSING_", "UNSUPPORTED_"].iter().any(|prefix| code.starts_with(prefix)) => 400,
+        _ => 500,
+    }
+}
+
 /// Handle market quote requests
 pub async fn handle_get_quote(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
     console_log!("TRADING HANDLER: Handling get quote request");
@@ -71,11 +97,74 @@ pub async fn handle_get_order_book(mut req: Request, ctx: RouteContext<AppState>
     }
 }
 
+/// Handle consolidated order book requests
+pub async fn handle_get_consolidated_book(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get consolidated order book request");
+
+    let request: GetConsolidatedBookRequest = match req.json::<GetConsolidatedBookRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.trading_service.get_consolidated_book(request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get consolidated order book: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle best bid and offer requests
+pub async fn handle_get_best_bid_offer(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get best bid and offer request");
+
+    let request: GetBestBidOfferRequest = match req.json::<GetBestBidOfferRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.trading_service.get_best_bid_offer(request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get best bid and offer: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle fill quote requests
+pub async fn handle_get_fill_quote(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get fill quote request");
+
+    let request: GetFillQuoteRequest = match req.json::<GetFillQuoteRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.trading_service.get_fill_quote(request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get fill quote: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
 /// Handle order placement requests
 pub async fn handle_place_order(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
     console_log!("TRADING HANDLER: Handling place order request");
     
-    let request: PlaceOrderRequest = match req.json::<PlaceOrderRequest>().await {
+    let mut request: PlaceOrderRequest = match req.json::<PlaceOrderRequest>().await {
         Ok(req) => {
             console_log!("TRADING HANDLER: Successfully parsed place order request for {} {} {} on {}", 
                 req.side, req.quantity, req.symbol, req.exchange);
@@ -86,8 +175,23 @@ pub async fn handle_place_order(mut req: Request, ctx: RouteContext<AppState>) -
             return Response::error("Invalid JSON request", 400);
         }
     };
+
+    // The Idempotency-Key header is an alternative way to supply client_order_id
+    if let Ok(Some(idempotency_key)) = req.headers().get("Idempotency-Key") {
+        match &request.client_order_id {
+            Some(client_order_id) if *client_order_id != idempotency_key => {
+                console_log!("TRADING HANDLER: Idempotency-Key header does not match client_order_id");
+                return Response::error("Idempotency-Key header does not match client_order_id", 400);
+            }
+            _ => request.client_order_id = Some(idempotency_key),
+        }
+    }
     
-    match ctx.data.trading_service.place_order(request).await {
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.place_order(&user_id, request).await {
         Ok(response) => {
             console_log!("TRADING HANDLER: Successfully placed order");
             Response::from_json(&response)
@@ -99,6 +203,192 @@ pub async fn handle_place_order(mut req: Request, ctx: RouteContext<AppState>) -
     }
 }
 
+/// Handle order lookups; a working order is refreshed from the exchange first
+pub async fn handle_get_order(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get order request");
+
+    let request: GetOrderRequest = match req.json::<GetOrderRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.get_order(&user_id, &request.order_id).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Order {} is {}", response.order_id, response.status);
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get order: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle order cancellation requests, for orders on the exchange or held
+/// by the trigger monitor
+pub async fn handle_cancel_order(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling cancel order request");
+
+    let request: CancelOrderRequest = match req.json::<CancelOrderRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed cancel order request for {}", req.order_id);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.cancel_order(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Successfully cancelled order {}", response.order_id);
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to cancel order: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle OCO order placement requests
+pub async fn handle_place_oco_order(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling place OCO order request");
+
+    let request: PlaceOcoOrderRequest = match req.json::<PlaceOcoOrderRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed OCO order request for {} {} {} on {}",
+                req.side, req.quantity, req.symbol, req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.place_oco_order(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Successfully placed OCO group {}", response.group_id);
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to place OCO order: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle bracket order placement requests
+pub async fn handle_place_bracket_order(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling place bracket order request");
+
+    let request: PlaceBracketOrderRequest = match req.json::<PlaceBracketOrderRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed bracket order request for {} {} {} on {}",
+                req.side, req.quantity, req.symbol, req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.place_bracket_order(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Successfully placed bracket group {}", response.group_id);
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to place bracket order: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle order group lookups; also applies any leg changes implied by new fills
+pub async fn handle_get_order_group(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get order group request");
+
+    let request: OrderGroupRequest = match req.json::<OrderGroupRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed order group request for {}", req.group_id);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.get_order_group(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Order group {} is {}", response.group_id, response.status);
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get order group: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle order group cancellation requests
+pub async fn handle_cancel_order_group(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling cancel order group request");
+
+    let request: OrderGroupRequest = match req.json::<OrderGroupRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed cancel order group request for {}", req.group_id);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.cancel_order_group(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Successfully cancelled order group {}", response.group_id);
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to cancel order group: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
 /// Handle balance requests
 pub async fn handle_get_balances(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
     console_log!("TRADING HANDLER: Handling get balances request");
@@ -126,6 +416,151 @@ pub async fn handle_get_balances(mut req: Request, ctx: RouteContext<AppState>)
     }
 }
 
+/// Handle portfolio summary requests
+pub async fn handle_get_portfolio(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get portfolio request");
+
+    let request: GetPortfolioRequest = match req.json::<GetPortfolioRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed portfolio request for {}", req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.get_portfolio(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Portfolio valued at {} USD", response.total_value_usd);
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get portfolio: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle aggregate portfolio requests
+pub async fn handle_get_aggregate_portfolio(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get aggregate portfolio request");
+
+    let request: GetAggregatePortfolioRequest = match req.json::<GetAggregatePortfolioRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed aggregate portfolio request for {:?}", req.exchanges);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.get_aggregate_portfolio(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Aggregate portfolio valued at {} USD ({} exchanges failed)",
+                response.total_value_usd, response.exchange_errors.len());
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get aggregate portfolio: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle requests to list the user's deposits and withdrawals
+pub async fn handle_get_transfers(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get transfers request");
+
+    let request: GetTransfersRequest = match req.json::<GetTransfersRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.trading_service.get_transfers(&user_id, request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get transfers: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle admin requests to record a deposit or withdrawal against a user's account
+pub async fn handle_record_transfer(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling record transfer request");
+
+    let request: RecordTransferRequest = match req.json::<RecordTransferRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(admin) = authenticated_admin(&req, &ctx.data).await else {
+        return Response::error("Forbidden", 403);
+    };
+
+    console_log!("TRADING HANDLER: {} is recording a {} for {}", admin, request.kind, request.user_id);
+    match ctx.data.trading_service.record_transfer(request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to record transfer: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
+/// Handle portfolio history requests
+pub async fn handle_get_portfolio_history(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("TRADING HANDLER: Handling get portfolio history request");
+
+    let request: GetEquityCurveRequest = match req.json::<GetEquityCurveRequest>().await {
+        Ok(req) => {
+            console_log!("TRADING HANDLER: Successfully parsed portfolio history request for {:?}", req.exchange);
+            req
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.snapshot_service.get_equity_curve(&user_id, request).await {
+        Ok(response) => {
+            console_log!("TRADING HANDLER: Equity curve has {} points", response.points.len());
+            Response::from_json(&response)
+        }
+        Err(e) => {
+            console_log!("TRADING HANDLER: Failed to get portfolio history: {}", e.error);
+            create_error_response(&e)
+        }
+    }
+}
+
 /// Handle instruments requests
 pub async fn handle_get_instruments(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
     console_log!("TRADING HANDLER: Handling get instruments request");
@@ -181,20 +616,14 @@ pub async fn handle_get_trading_status(mut req: Request, ctx: RouteContext<AppSt
 }
 
 /// Handle trading service health check
-pub async fn handle_trading_health(_req: Request, _ctx: RouteContext<AppState>) -> Result<Response> {
+pub async fn handle_trading_health(_req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
     console_log!("TRADING HANDLER: Handling trading health check");
     
-    let health_status = serde_json::json!({
-        "status": "healthy",
-        "service": "trading",
-        "barter_integration": "active",
-        "supported_exchanges": ["binance", "coinbase", "kraken", "okx", "bybit"],
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-        "version": "1.0.0"
-    });
+    let health = ctx.data.trading_service.get_health().await;
     
-    console_log!("TRADING HANDLER: Trading service health check completed");
-    Response::from_json(&health_status)
+    console_log!("TRADING HANDLER: Trading service health check completed: {}", health.status);
+    let status = if health.status == "unhealthy" { 503 } else { 200 };
+    Ok(Response::from_json(&health)?.with_status(status))
 }
 
 /// Handle trading service configuration
@@ -207,31 +636,24 @@ pub async fn handle_trading_config(_req: Request, _ctx: RouteContext<AppState>)
                 "name": "binance",
                 "display_name": "Binance",
                 "supported_features": ["spot", "futures", "margin"],
-                "rate_limits": {
-                    "requests_per_second": 10,
-                    "orders_per_second": 5
-                }
+                "rate_limits": exchange_limits(Exchange::Binance)
             },
             {
                 "name": "coinbase",
                 "display_name": "Coinbase Pro",
                 "supported_features": ["spot"],
-                "rate_limits": {
-                    "requests_per_second": 10,
-                    "orders_per_second": 5
-                }
+                "rate_limits": exchange_limits(Exchange::Coinbase)
             },
             {
                 "name": "kraken",
                 "display_name": "Kraken",
                 "supported_features": ["spot", "futures"],
-                "rate_limits": {
-                    "requests_per_second": 1,
-                    "orders_per_second": 1
-                }
+                "rate_limits": exchange_limits(Exchange::Kraken)
             }
         ],
-        "order_types": ["MARKET", "LIMIT", "STOP_LOSS", "TAKE_PROFIT"],
+        "order_types": ["MARKET", "LIMIT", "STOP_LOSS", "STOP_LOSS_LIMIT", "TAKE_PROFIT", "TAKE_PROFIT_LIMIT"],
+        "trigger_price_types": ["LAST", "MARK"],
+        "order_group_types": ["OCO", "BRACKET"],
         "time_in_force": ["GTC", "IOC", "FOK"],
         "default_precision": {
             "price": 8,
@@ -242,3 +664,23 @@ pub async fn handle_trading_config(_req: Request, _ctx: RouteContext<AppState>)
     console_log!("TRADING HANDLER: Trading configuration retrieved");
     Response::from_json(&config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coded(code: &str) -> TradingErrorResponse {
+        TradingErrorResponse::with_code("error".to_string(), code.to_string())
+    }
+
+    #[test]
+    fn error_codes_map_to_statuses() {
+        assert_eq!(error_status(&coded("CLIENT_ORDER_ID_REUSED")), 409);
+        assert_eq!(error_status(&coded("ORDER_STATE_UNKNOWN")), 503);
+        assert_eq!(error_status(&coded("ORDER_NOT_FOUND")), 404);
+        assert_eq!(error_status(&coded("INVALID_SIDE")), 400);
+        assert_eq!(error_status(&coded("MISSING_PRICE")), 400);
+        assert_eq!(error_status(&coded("UNSUPPORTED_EXCHANGE")), 400);
+        assert_eq!(error_status(&TradingErrorResponse::new("Failed to place order".to_string())), 500);
+    }
+}
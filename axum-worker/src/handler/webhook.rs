@@ -0,0 +1,125 @@
+use worker::{Request, Response, RouteContext, Result};
+use worker::console_log;
+
+use crate::state::AppState;
+use crate::dto::webhook::{CreateWebhookRequest, GetDeliveriesRequest, WebhookRequest};
+use crate::handler::auth::authenticated_user;
+
+/// Handle requests to register a webhook endpoint
+pub async fn handle_create_webhook(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("WEBHOOKS: Handling create request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: CreateWebhookRequest = match req.json::<CreateWebhookRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.webhook_service.create_endpoint(&user_id, request).await {
+        Ok(webhook) => Response::from_json(&webhook),
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to create webhook: {}", e);
+            Response::error(format!("Failed to create webhook: {}", e), 400)
+        }
+    }
+}
+
+/// Handle requests to list the user's webhook endpoints
+pub async fn handle_list_webhooks(req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("WEBHOOKS: Handling list request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    match ctx.data.webhook_service.list_endpoints(&user_id).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to list webhooks: {}", e);
+            Response::error(format!("Failed to list webhooks: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests to delete a webhook endpoint
+pub async fn handle_delete_webhook(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("WEBHOOKS: Handling delete request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: WebhookRequest = match req.json::<WebhookRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.webhook_service.delete_endpoint(&user_id, &request.webhook_id).await {
+        Ok(true) => Response::from_json(&request),
+        Ok(false) => Response::error(format!("Webhook not found: {}", request.webhook_id), 404),
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to delete webhook {}: {}", request.webhook_id, e);
+            Response::error(format!("Failed to delete webhook: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests to send a test event to a webhook endpoint
+pub async fn handle_test_webhook(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("WEBHOOKS: Handling test request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: WebhookRequest = match req.json::<WebhookRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.webhook_service.send_test(&user_id, &request.webhook_id).await {
+        Ok(Some(delivery)) => Response::from_json(&delivery),
+        Ok(None) => Response::error(format!("Webhook not found: {}", request.webhook_id), 404),
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to test webhook {}: {}", request.webhook_id, e);
+            Response::error(format!("Failed to test webhook: {}", e), 500)
+        }
+    }
+}
+
+/// Handle requests for the webhook delivery log
+pub async fn handle_get_webhook_deliveries(mut req: Request, ctx: RouteContext<AppState>) -> Result<Response> {
+    console_log!("WEBHOOKS: Handling deliveries request");
+
+    let Some(user_id) = authenticated_user(&req, &ctx.data.auth_service) else {
+        return Response::error("Unauthorized", 401);
+    };
+
+    let request: GetDeliveriesRequest = match req.json::<GetDeliveriesRequest>().await {
+        Ok(req) => req,
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to parse JSON request: {}", e);
+            return Response::error("Invalid JSON request", 400);
+        }
+    };
+
+    match ctx.data.webhook_service.get_deliveries(&user_id, request).await {
+        Ok(response) => Response::from_json(&response),
+        Err(e) => {
+            console_log!("WEBHOOKS: Failed to load deliveries: {}", e);
+            Response::error(format!("Failed to load deliveries: {}", e), 400)
+        }
+    }
+}
@@ -8,9 +8,9 @@ pub mod service;
 pub mod router;
 pub mod state;
 pub mod clients;
+pub mod durable;
 
 use worker::{Router, *};
-use crate::state::AppState;
 
 #[event(start)]
 fn start() {
@@ -21,32 +21,76 @@ fn start() {
 async fn fetch(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     console_error_panic_hook::set_once();
 
-    // Get environment variables
-    let jwt_secret = env.secret("JWT_SECRET")?.to_string();
-    let db_connection_string = env.secret("DB_CONNECTION_STRING")?.to_string();
-
-    // Initialize app state with LIVE Neon database connection
     console_log!("Initializing rusty-worker with LIVE Neon database");
+    let app_state = match crate::state::init_app_state(&env).await {
+        Ok(app_state) => app_state,
+        Err(e) => {
+            console_log!("Failed to initialize state: {}", e);
+            return Response::error("Internal server error", 500);
+        }
+    };
+    let rate_limit_service = app_state.rate_limit_service.clone();
 
-    // Create repository with LIVE database connection
-    let user_repository = crate::repo::user::UserRepository::new(db_connection_string);
-    let auth_service = crate::service::auth::AuthenticationService::new(jwt_secret);
-    let market_data_service = crate::service::market_data::MarketDataService::new();
-    let trading_service = crate::service::trading::TradingService::new();
-
-    console_log!("Initializing services with barter-rs trading integration");
-
-    let app_state = AppState {
-        user_repository,
-        auth_service,
-        market_data_service,
-        trading_service,
+    // Take a token from the caller's bucket before doing any work
+    let user_id = crate::handler::auth::authenticated_user(&req, &app_state.auth_service);
+    let decision = match rate_limit_service.classify(&req, user_id.as_deref()) {
+        Some((group, principals)) => rate_limit_service.check_all(group, &principals).await,
+        None => None,
     };
+    if let Some(decision) = decision.as_ref().filter(|decision| !decision.allowed) {
+        return crate::handler::rate_limit::rate_limited_response(decision);
+    }
 
     // Create router with app state
     let router = Router::with_data(app_state);
     let configured_router = crate::router::configure_routes(router);
 
     // Run the request through the router
-    configured_router.run(req, env).await
-}
\ No newline at end of file
+    let response = configured_router.run(req, env).await?;
+    Ok(match decision {
+        Some(decision) => crate::handler::rate_limit::with_rate_limit_headers(response, &decision),
+        None => response,
+    })
+}
+
+#[event(scheduled)]
+async fn scheduled(event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+    console_error_panic_hook::set_once();
+    let cron = event.cron();
+    console_log!("Running scheduled jobs for cron {}", cron);
+
+    match crate::state::init_app_state(&env).await {
+        Ok(app_state) => {
+            let runs = app_state.scheduler_service.run_scheduled(&cron).await;
+            console_log!("Finished {} scheduled jobs for cron {}", runs.len(), cron);
+        }
+        Err(e) => console_log!("Scheduled jobs skipped, failed to initialize state: {}", e),
+    }
+}
+
+#[event(queue)]
+async fn queue(batch: MessageBatch<crate::service::webhook::WebhookDeliveryMessage>, env: Env, _ctx: Context) -> Result<()> {
+    console_error_panic_hook::set_once();
+    console_log!("Processing {} webhook deliveries from queue {}", batch.messages()?.len(), batch.queue());
+
+    let db_connection_string = env.secret("DB_CONNECTION_STRING")?.to_string();
+    let webhook_repository = crate::repo::webhook::WebhookRepository::new(db_connection_string);
+    let webhook_queue = env.queue(crate::service::webhook::WEBHOOK_QUEUE_BINDING).ok();
+    let webhook_service = crate::service::webhook::WebhookService::new(webhook_repository, webhook_queue);
+
+    for message in batch.messages()? {
+        let delivery_id = &message.body().delivery_id;
+        match webhook_service.process_delivery(delivery_id).await {
+            Ok(crate::service::webhook::DeliveryOutcome::Done) => message.ack(),
+            Ok(crate::service::webhook::DeliveryOutcome::RetryAfter(delay)) => {
+                message.retry_with_options(&QueueRetryOptionsBuilder::new().with_delay_seconds(delay).build());
+            }
+            Err(e) => {
+                // The delivery couldn't be loaded or recorded; let the queue try it again
+                console_log!("Failed to process webhook delivery {}: {}", delivery_id, e);
+                message.retry();
+            }
+        }
+    }
+    Ok(())
+}
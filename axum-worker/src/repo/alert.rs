@@ -0,0 +1,139 @@
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::alert::{AlertCondition, AlertRule, AlertStatus};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_decimal, row_timestamp, sql_optional_decimal, sql_optional_text, sql_optional_timestamp};
+
+/// Alert rule repository with Neon database integration
+#[derive(Clone)]
+pub struct AlertRepository {
+    neon_client: NeonClient,
+}
+
+impl AlertRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert a new alert rule
+    pub async fn save_rule(&self, rule: &AlertRule) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} alert {} for user {}", rule.condition.as_str(), rule.id, rule.user_id);
+
+        let sql = format!(
+            "INSERT INTO alert_rules (id, user_id, name, condition, exchange, base, quote, threshold, window_minutes, \
+             repeat, status, armed, last_value, last_checked_at, triggered_at, \
+             trigger_count, created_at, updated_at) \
+             VALUES ('{}', '{}', {}, '{}', {}, {}, {}, {}, {}, {}, '{}', {}, {}, {}, {}, {}, '{}', '{}')",
+            NeonClient::escape(&rule.id),
+            NeonClient::escape(&rule.user_id),
+            sql_optional_text(rule.name.as_deref()),
+            rule.condition.as_str(),
+            sql_optional_text(rule.exchange.as_deref()),
+            sql_optional_text(rule.base.as_deref()),
+            sql_optional_text(rule.quote.as_deref()),
+            sql_optional_decimal(rule.threshold),
+            rule.window_minutes.map_or("NULL".to_string(), |minutes| minutes.to_string()),
+            rule.repeat,
+            rule.status.as_str(),
+            rule.armed,
+            sql_optional_decimal(rule.last_value),
+            sql_optional_timestamp(rule.last_checked_at),
+            sql_optional_timestamp(rule.triggered_at),
+            rule.trigger_count,
+            rule.created_at.to_rfc3339(),
+            rule.updated_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Record a rule's status, whether it is armed, and when it last
+    /// checked and fired
+    pub async fn update_state(&self, rule: &AlertRule) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Updating alert {} ({}, armed {})", rule.id, rule.status.as_str(), rule.armed);
+
+        let sql = format!(
+            "UPDATE alert_rules SET status = '{}', armed = {}, last_value = {}, last_checked_at = {}, \
+             triggered_at = {}, trigger_count = {}, updated_at = '{}' WHERE id = '{}'",
+            rule.status.as_str(),
+            rule.armed,
+            sql_optional_decimal(rule.last_value),
+            sql_optional_timestamp(rule.last_checked_at),
+            sql_optional_timestamp(rule.triggered_at),
+            rule.trigger_count,
+            rule.updated_at.to_rfc3339(),
+            NeonClient::escape(&rule.id),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    pub async fn delete_rule(&self, id: &str) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Deleting alert {}", id);
+
+        let sql = format!("DELETE FROM alert_rules WHERE id = '{}'", NeonClient::escape(id));
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    pub async fn find_rule(&self, id: &str) -> Result<Option<AlertRule>, String> {
+        console_log!("LIVE DATABASE: Loading alert {}", id);
+
+        let sql = format!("SELECT * FROM alert_rules WHERE id = '{}'", NeonClient::escape(id));
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first().and_then(row_to_rule))
+    }
+
+    /// A user's alert rules, newest first
+    pub async fn find_user_rules(&self, user_id: &str) -> Result<Vec<AlertRule>, String> {
+        console_log!("LIVE DATABASE: Loading alerts for user {}", user_id);
+
+        let sql = format!(
+            "SELECT * FROM alert_rules WHERE user_id = '{}' ORDER BY created_at DESC",
+            NeonClient::escape(user_id),
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_rule).collect())
+    }
+
+    /// Every user's active rules, for the evaluator
+    pub async fn find_active_rules(&self) -> Result<Vec<AlertRule>, String> {
+        console_log!("LIVE DATABASE: Loading active alerts");
+
+        let sql = format!(
+            "SELECT * FROM alert_rules WHERE status = '{}' ORDER BY created_at",
+            AlertStatus::Active.as_str(),
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_rule).collect())
+    }
+}
+
+/// Convert a database row into an AlertRule, skipping malformed rows
+pub(crate) fn row_to_rule(row: &Value) -> Option<AlertRule> {
+    Some(AlertRule {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        name: row["name"].as_str().map(|s| s.to_string()),
+        condition: AlertCondition::parse(row["condition"].as_str()?)?,
+        exchange: row["exchange"].as_str().map(|s| s.to_string()),
+        base: row["base"].as_str().map(|s| s.to_string()),
+        quote: row["quote"].as_str().map(|s| s.to_string()),
+        threshold: row_decimal(&row["threshold"]),
+        window_minutes: row["window_minutes"].as_i64(),
+        repeat: row["repeat"].as_bool().unwrap_or(false),
+        status: AlertStatus::parse(row["status"].as_str()?)?,
+        armed: row["armed"].as_bool().unwrap_or(true),
+        last_value: row_decimal(&row["last_value"]),
+        last_checked_at: row_timestamp(&row["last_checked_at"]),
+        triggered_at: row_timestamp(&row["triggered_at"]),
+        trigger_count: row["trigger_count"].as_i64().unwrap_or(0),
+        created_at: row_timestamp(&row["created_at"])?,
+        updated_at: row_timestamp(&row["updated_at"])?,
+    })
+}
@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::market_data::{Candle, CandleInterval, CandleSource, Instrument, InstrumentKind};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_decimal, row_timestamp};
+
+/// OHLCV candle repository with Neon database integration
+#[derive(Clone)]
+pub struct CandleRepository {
+    neon_client: NeonClient,
+}
+
+impl CandleRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert or replace candles. Candles from the exchange's kline API replace
+    /// anything stored; candles aggregated from trades never replace klines.
+    pub async fn save_candles(&self, candles: &[Candle], source: CandleSource) -> Result<(), String> {
+        if candles.is_empty() {
+            return Ok(());
+        }
+        console_log!("LIVE DATABASE: Saving {} {} candles from {}",
+            candles.len(), candles[0].interval.as_str(), source.as_str());
+
+        let values: Vec<String> = candles.iter()
+            .map(|candle| format!(
+                "('{}', '{}', '{}', '{}', '{}', '{}', {}, {}, {}, {}, {}, {}, '{}', '{}', '{}')",
+                NeonClient::escape(&candle.instrument.id),
+                NeonClient::escape(&candle.instrument.exchange),
+                NeonClient::escape(&candle.instrument.base),
+                NeonClient::escape(&candle.instrument.quote),
+                candle.interval.as_str(),
+                candle.open_time.to_rfc3339(),
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume,
+                candle.trade_count.map_or("NULL".to_string(), |count| count.to_string()),
+                source.as_str(),
+                candle.exchange_timestamp.to_rfc3339(),
+                candle.timestamp.to_rfc3339(),
+            ))
+            .collect();
+
+        let sql = format!(
+            "INSERT INTO candles (instrument_id, exchange, base, quote, period, open_time, open, high, low, \
+             close, volume, trade_count, source, last_trade_at, updated_at) VALUES {} \
+             ON CONFLICT (instrument_id, period, open_time) DO UPDATE SET \
+             open = EXCLUDED.open, high = EXCLUDED.high, low = EXCLUDED.low, close = EXCLUDED.close, \
+             volume = EXCLUDED.volume, trade_count = EXCLUDED.trade_count, source = EXCLUDED.source, \
+             last_trade_at = EXCLUDED.last_trade_at, updated_at = EXCLUDED.updated_at \
+             WHERE candles.source = '{}' OR EXCLUDED.source = '{}'",
+            values.join(", "),
+            CandleSource::Trades.as_str(),
+            CandleSource::Klines.as_str(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Find an instrument's candles opening within `[start_time, end_time)`, oldest first
+    pub async fn find_candles(
+        &self,
+        instrument_id: &str,
+        interval: CandleInterval,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Candle>, String> {
+        console_log!("LIVE DATABASE: Loading {} candles for {}", interval.as_str(), instrument_id);
+
+        let sql = format!(
+            "SELECT * FROM candles WHERE instrument_id = '{}' AND period = '{}' \
+             AND open_time >= '{}' AND open_time < '{}' ORDER BY open_time",
+            NeonClient::escape(instrument_id),
+            interval.as_str(),
+            start_time.to_rfc3339(),
+            end_time.to_rfc3339(),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_candle).collect())
+    }
+
+    /// Instruments that have candles stored, which the aggregation job keeps current
+    pub async fn find_tracked_instruments(&self) -> Result<Vec<Instrument>, String> {
+        console_log!("LIVE DATABASE: Loading instruments with candles");
+
+        let sql = "SELECT DISTINCT exchange, base, quote FROM candles ORDER BY exchange, base, quote";
+        let rows = self.neon_client.query_rows(sql).await?;
+
+        Ok(rows.iter()
+            .filter_map(|row| Some(Instrument::new(
+                row["base"].as_str()?.to_string(),
+                row["quote"].as_str()?.to_string(),
+                row["exchange"].as_str()?.to_string(),
+                InstrumentKind::Spot,
+            )))
+            .collect())
+    }
+}
+
+/// Convert a database row into a Candle, skipping malformed rows
+pub(crate) fn row_to_candle(row: &Value) -> Option<Candle> {
+    Some(Candle {
+        instrument: Instrument::new(
+            row["base"].as_str()?.to_string(),
+            row["quote"].as_str()?.to_string(),
+            row["exchange"].as_str()?.to_string(),
+            InstrumentKind::Spot,
+        ),
+        interval: CandleInterval::parse(row["period"].as_str()?)?,
+        open_time: row_timestamp(&row["open_time"])?,
+        open: row_decimal(&row["open"])?,
+        high: row_decimal(&row["high"])?,
+        low: row_decimal(&row["low"])?,
+        close: row_decimal(&row["close"])?,
+        volume: row_decimal(&row["volume"])?,
+        trade_count: row["trade_count"].as_u64().map(|count| count as u32),
+        timestamp: row_timestamp(&row["updated_at"])?,
+        exchange_timestamp: row_timestamp(&row["last_trade_at"])?,
+    })
+}
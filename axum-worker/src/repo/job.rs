@@ -0,0 +1,120 @@
+use chrono::Duration;
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::job::{JobRun, JobRunStatus, JobTrigger, ScheduledJob};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_timestamp, sql_optional_text, sql_optional_timestamp};
+
+/// Scheduled job repository with Neon database integration: run history and
+/// the per-job locks that keep runs from overlapping
+#[derive(Clone)]
+pub struct JobRepository {
+    neon_client: NeonClient,
+}
+
+impl JobRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Take the job's lock for a run. Succeeds when the job is unlocked or the
+    /// previous holder's lease has expired.
+    pub async fn acquire_lock(&self, run: &JobRun) -> Result<bool, String> {
+        console_log!("LIVE DATABASE: Acquiring lock on {} for run {}", run.job.as_str(), run.id);
+
+        let locked_until = run.started_at + Duration::seconds(run.job.lock_seconds());
+        let sql = format!(
+            "INSERT INTO scheduled_job_locks (job_name, run_id, locked_until) VALUES ('{}', '{}', '{}') \
+             ON CONFLICT (job_name) DO UPDATE SET run_id = EXCLUDED.run_id, locked_until = EXCLUDED.locked_until \
+             WHERE scheduled_job_locks.locked_until < '{}'",
+            run.job.as_str(),
+            NeonClient::escape(&run.id),
+            locked_until.to_rfc3339(),
+            run.started_at.to_rfc3339(),
+        );
+
+        let result = self.neon_client.execute_sql(&sql).await?;
+        Ok(result["rows_affected"].as_u64().unwrap_or(0) > 0)
+    }
+
+    /// Release the job's lock if this run still holds it
+    pub async fn release_lock(&self, run: &JobRun) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Releasing lock on {} for run {}", run.job.as_str(), run.id);
+
+        let sql = format!(
+            "DELETE FROM scheduled_job_locks WHERE job_name = '{}' AND run_id = '{}'",
+            run.job.as_str(),
+            NeonClient::escape(&run.id),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Insert or update a run in the history
+    pub async fn save_run(&self, run: &JobRun) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} run {} as {}", run.job.as_str(), run.id, run.status.as_str());
+
+        let sql = format!(
+            "INSERT INTO scheduled_job_runs (id, job_name, trigger, triggered_by, status, message, started_at, finished_at) \
+             VALUES ('{}', '{}', '{}', {}, '{}', {}, '{}', {}) \
+             ON CONFLICT (id) DO UPDATE SET status = EXCLUDED.status, message = EXCLUDED.message, \
+             finished_at = EXCLUDED.finished_at",
+            NeonClient::escape(&run.id),
+            run.job.as_str(),
+            run.trigger.as_str(),
+            sql_optional_text(run.triggered_by.as_deref()),
+            run.status.as_str(),
+            sql_optional_text(run.message.as_deref()),
+            run.started_at.to_rfc3339(),
+            sql_optional_timestamp(run.finished_at),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Most recent runs, newest first, optionally for one job
+    pub async fn find_runs(&self, job: Option<ScheduledJob>, limit: u32) -> Result<Vec<JobRun>, String> {
+        console_log!("LIVE DATABASE: Loading job runs for {:?}", job);
+
+        let filter = job
+            .map(|job| format!("WHERE job_name = '{}' ", job.as_str()))
+            .unwrap_or_default();
+        let sql = format!(
+            "SELECT * FROM scheduled_job_runs {}ORDER BY started_at DESC LIMIT {}",
+            filter, limit,
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_run).collect())
+    }
+
+    /// Latest run of each job
+    pub async fn find_latest_runs(&self) -> Result<Vec<JobRun>, String> {
+        console_log!("LIVE DATABASE: Loading latest run of each job");
+
+        let rows = self.neon_client
+            .query_rows("SELECT DISTINCT ON (job_name) * FROM scheduled_job_runs ORDER BY job_name, started_at DESC")
+            .await?;
+        Ok(rows.iter().filter_map(row_to_run).collect())
+    }
+}
+
+/// Convert a database row into a JobRun, skipping malformed rows
+pub(crate) fn row_to_run(row: &Value) -> Option<JobRun> {
+    Some(JobRun {
+        id: row["id"].as_str()?.to_string(),
+        job: ScheduledJob::parse(row["job_name"].as_str()?)?,
+        trigger: JobTrigger::parse(row["trigger"].as_str()?)?,
+        triggered_by: row["triggered_by"].as_str().map(|s| s.to_string()),
+        status: JobRunStatus::parse(row["status"].as_str()?)?,
+        message: row["message"].as_str().map(|s| s.to_string()),
+        started_at: row_timestamp(&row["started_at"])?,
+        finished_at: row_timestamp(&row["finished_at"]),
+    })
+}
@@ -0,0 +1,124 @@
+use std::io::{Read, Write};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use worker::{console_log, Bucket, HttpMetadata};
+
+use crate::dto::market_data::MarketDataEventResponse;
+use crate::entity::market_data::{CandleInterval, RecordedChunk};
+
+/// R2 bucket binding holding recorded market data
+pub const MARKET_DATA_ARCHIVE_BINDING: &str = "MARKET_DATA_ARCHIVE";
+
+/// Top-level folder of the recordings within the bucket
+const ARCHIVE_PREFIX: &str = "market-data";
+
+/// Recorded market data in R2, as gzip'd NDJSON chunks.
+///
+/// Chunks are keyed by instrument and hour, then by the millisecond time of
+/// their first event:
+/// `market-data/binance/btc-usdt/2026-10-18/14/1792332000123.ndjson.gz`.
+/// Millisecond times have the same number of digits for centuries, so the
+/// keys of an hour list in time order.
+#[derive(Clone)]
+pub struct MarketDataArchive {
+    bucket: Bucket,
+}
+
+impl MarketDataArchive {
+    pub fn new(bucket: Bucket) -> Self {
+        Self { bucket }
+    }
+
+    /// Compress and store a chunk, returning its key
+    pub async fn save_chunk(&self, chunk: &RecordedChunk) -> Result<String, String> {
+        let key = format!("{}/{}.ndjson.gz", hour_prefix(&chunk.instrument_id, chunk.start), chunk.start.timestamp_millis());
+        console_log!("MARKET DATA ARCHIVE: Saving {} events to {}", chunk.lines.len(), key);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for line in &chunk.lines {
+            encoder.write_all(line.as_bytes()).map_err(|e| format!("Failed to compress chunk: {}", e))?;
+            encoder.write_all(b"\n").map_err(|e| format!("Failed to compress chunk: {}", e))?;
+        }
+        let body = encoder.finish().map_err(|e| format!("Failed to compress chunk: {}", e))?;
+
+        self.bucket.put(key.clone(), body)
+            .http_metadata(HttpMetadata {
+                content_type: Some("application/x-ndjson".to_string()),
+                content_encoding: Some("gzip".to_string()),
+                ..HttpMetadata::default()
+            })
+            .execute()
+            .await
+            .map_err(|e| format!("Failed to save chunk {}: {}", key, e))?;
+        Ok(key)
+    }
+
+    /// Keys of an instrument's chunks that may hold events in `[start, end)`, oldest first
+    pub async fn list_chunks(&self, instrument_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut hour = CandleInterval::OneHour.bucket_start(start);
+
+        while hour < end {
+            let prefix = format!("{}/", hour_prefix(instrument_id, hour));
+            let mut cursor: Option<String> = None;
+            loop {
+                let mut list = self.bucket.list().prefix(prefix.clone());
+                if let Some(cursor) = cursor.take() {
+                    list = list.cursor(cursor);
+                }
+                let objects = list.execute().await
+                    .map_err(|e| format!("Failed to list {}: {}", prefix, e))?;
+
+                keys.extend(objects.objects().into_iter()
+                    .map(|object| object.key())
+                    .filter(|key| chunk_start(key).is_some_and(|chunk_start| chunk_start < end)));
+
+                match objects.cursor() {
+                    Some(next) if objects.truncated() => cursor = Some(next),
+                    _ => break,
+                }
+            }
+            hour += CandleInterval::OneHour.duration();
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Load and decompress a chunk's events, skipping lines that don't parse
+    pub async fn load_chunk(&self, key: &str) -> Result<Vec<MarketDataEventResponse>, String> {
+        let object = self.bucket.get(key).execute().await
+            .map_err(|e| format!("Failed to load {}: {}", key, e))?
+            .ok_or_else(|| format!("Chunk not found: {}", key))?;
+        let body = object.body().ok_or_else(|| format!("Chunk has no body: {}", key))?;
+        let bytes = body.bytes().await.map_err(|e| format!("Failed to read {}: {}", key, e))?;
+
+        let mut text = String::new();
+        GzDecoder::new(bytes.as_slice()).read_to_string(&mut text)
+            .map_err(|e| format!("Failed to decompress {}: {}", key, e))?;
+
+        Ok(text.lines()
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+/// `market-data/{exchange}/{base}-{quote}/{date}/{hour}` for an instrument id
+/// like `binance:btc:usdt`
+fn hour_prefix(instrument_id: &str, time: DateTime<Utc>) -> String {
+    let instrument = match instrument_id.splitn(3, ':').collect::<Vec<_>>().as_slice() {
+        [exchange, base, quote] => format!("{}/{}-{}", exchange, base, quote),
+        _ => instrument_id.replace(':', "-"),
+    };
+    format!("{}/{}/{}", ARCHIVE_PREFIX, instrument, time.format("%Y-%m-%d/%H"))
+}
+
+/// Time of a chunk's first event, from its key
+fn chunk_start(key: &str) -> Option<DateTime<Utc>> {
+    let name = key.rsplit('/').next()?;
+    let millis = name.strip_suffix(".ndjson.gz")?.parse().ok()?;
+    DateTime::from_timestamp_millis(millis)
+}
@@ -1 +1,13 @@
-pub mod user;
\ No newline at end of file
+pub mod user;
+pub mod order;
+pub mod trade;
+pub mod transfer;
+pub mod snapshot;
+pub mod job;
+pub mod candle;
+pub mod market_data_archive;
+pub mod strategy;
+pub mod alert;
+pub mod notification;
+pub mod webhook;
+pub mod signal;
@@ -0,0 +1,103 @@
+use chrono::Utc;
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::alert::Notification;
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_timestamp, sql_optional_text, sql_optional_timestamp};
+
+/// In-app notification repository with Neon database integration
+#[derive(Clone)]
+pub struct NotificationRepository {
+    neon_client: NeonClient,
+}
+
+impl NotificationRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    pub async fn save_notification(&self, notification: &Notification) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving notification {} for user {}", notification.id, notification.user_id);
+
+        let sql = format!(
+            "INSERT INTO notifications (id, user_id, alert_id, title, message, data, read_at, created_at) \
+             VALUES ('{}', '{}', {}, '{}', '{}', '{}', {}, '{}')",
+            NeonClient::escape(&notification.id),
+            NeonClient::escape(&notification.user_id),
+            sql_optional_text(notification.alert_id.as_deref()),
+            NeonClient::escape(&notification.title),
+            NeonClient::escape(&notification.message),
+            NeonClient::escape(&notification.data.to_string()),
+            sql_optional_timestamp(notification.read_at),
+            notification.created_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// A user's notifications, newest first
+    pub async fn find_notifications(&self, user_id: &str, unread_only: bool, limit: u32) -> Result<Vec<Notification>, String> {
+        console_log!("LIVE DATABASE: Loading notifications for user {}", user_id);
+
+        let sql = format!(
+            "SELECT * FROM notifications WHERE user_id = '{}' {}ORDER BY created_at DESC LIMIT {}",
+            NeonClient::escape(user_id),
+            if unread_only { "AND read_at IS NULL " } else { "" },
+            limit,
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_notification).collect())
+    }
+
+    pub async fn count_unread(&self, user_id: &str) -> Result<u64, String> {
+        let sql = format!(
+            "SELECT COUNT(*) AS unread FROM notifications WHERE user_id = '{}' AND read_at IS NULL",
+            NeonClient::escape(user_id),
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first()
+            .and_then(|row| row["unread"].as_u64().or_else(|| row["unread"].as_str()?.parse().ok()))
+            .unwrap_or(0))
+    }
+
+    /// Mark a user's notifications read: the ones given, or every unread one
+    pub async fn mark_read(&self, user_id: &str, ids: Option<&[String]>) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Marking notifications read for user {}", user_id);
+
+        let filter = match ids {
+            Some(ids) => format!(
+                " AND id IN ({})",
+                ids.iter().map(|id| format!("'{}'", NeonClient::escape(id))).collect::<Vec<_>>().join(", "),
+            ),
+            None => String::new(),
+        };
+        let sql = format!(
+            "UPDATE notifications SET read_at = '{}' WHERE user_id = '{}' AND read_at IS NULL{}",
+            Utc::now().to_rfc3339(),
+            NeonClient::escape(user_id),
+            filter,
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+}
+
+/// Convert a database row into a Notification, skipping malformed rows
+pub(crate) fn row_to_notification(row: &Value) -> Option<Notification> {
+    Some(Notification {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        alert_id: row["alert_id"].as_str().map(|s| s.to_string()),
+        title: row["title"].as_str()?.to_string(),
+        message: row["message"].as_str()?.to_string(),
+        data: row["data"].as_str().and_then(|data| serde_json::from_str(data).ok()).unwrap_or(Value::Null),
+        read_at: row_timestamp(&row["read_at"]),
+        created_at: row_timestamp(&row["created_at"])?,
+    })
+}
@@ -0,0 +1,344 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::trading::{
+    InstrumentType, OrderGroup, OrderGroupStatus, OrderGroupType, OrderSide, OrderStatus, OrderType,
+    TradingInstrument, TradingOrder, TriggerPriceType,
+};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{
+    row_decimal, row_timestamp, sql_optional_decimal, sql_optional_text, sql_optional_timestamp,
+};
+
+/// Trading order repository with Neon database integration
+#[derive(Clone)]
+pub struct OrderRepository {
+    neon_client: NeonClient,
+}
+
+impl OrderRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert a new order
+    pub async fn save_order(&self, order: &TradingOrder) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving order {} for user {}", order.id, order.user_id);
+
+        let sql = format!(
+            "INSERT INTO trading_orders (id, user_id, exchange, symbol, base_asset, quote_asset, exchange_order_id, \
+             side, order_type, status, quantity, price, stop_price, trigger_by, filled_quantity, average_price, \
+             commission, commission_asset, group_id, parent_order_id, client_order_id, created_at, updated_at, executed_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', '{}', {}, '{}', '{}', '{}', {}, {}, {}, '{}', {}, {}, {}, '{}', {}, {}, {}, '{}', '{}', {})",
+            NeonClient::escape(&order.id),
+            NeonClient::escape(&order.user_id),
+            NeonClient::escape(&order.instrument.exchange),
+            NeonClient::escape(&order.instrument.symbol),
+            NeonClient::escape(&order.instrument.base_asset),
+            NeonClient::escape(&order.instrument.quote_asset),
+            sql_optional_text(order.exchange_order_id.as_deref()),
+            order.side.as_str(),
+            order.order_type.as_str(),
+            order.status.as_str(),
+            order.quantity,
+            sql_optional_decimal(order.price),
+            sql_optional_decimal(order.stop_price),
+            order.trigger_by.as_str(),
+            order.filled_quantity,
+            sql_optional_decimal(order.average_price),
+            order.commission,
+            NeonClient::escape(&order.commission_asset),
+            sql_optional_text(order.group_id.as_deref()),
+            sql_optional_text(order.parent_order_id.as_deref()),
+            sql_optional_text(order.client_order_id.as_deref()),
+            order.created_at.to_rfc3339(),
+            order.updated_at.to_rfc3339(),
+            sql_optional_timestamp(order.executed_at),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Update an order's status, exchange id and fill progress
+    pub async fn update_order(&self, order: &TradingOrder) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Updating order {} to status {}", order.id, order.status.as_str());
+
+        let sql = format!(
+            "UPDATE trading_orders SET exchange_order_id = {}, status = '{}', quantity = {}, price = {}, \
+             filled_quantity = {}, average_price = {}, commission = {}, commission_asset = '{}', \
+             updated_at = '{}', executed_at = {} WHERE id = '{}'",
+            sql_optional_text(order.exchange_order_id.as_deref()),
+            order.status.as_str(),
+            order.quantity,
+            sql_optional_decimal(order.price),
+            order.filled_quantity,
+            sql_optional_decimal(order.average_price),
+            order.commission,
+            NeonClient::escape(&order.commission_asset),
+            Utc::now().to_rfc3339(),
+            sql_optional_timestamp(order.executed_at),
+            NeonClient::escape(&order.id),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Move an order from one status to another only if it still has the first.
+    /// Returns whether this call made the change, so concurrent callers can
+    /// race for an order and exactly one wins.
+    pub async fn transition_status(&self, order_id: &str, from: &OrderStatus, to: &OrderStatus) -> Result<bool, String> {
+        console_log!("LIVE DATABASE: Moving order {} from {} to {}", order_id, from.as_str(), to.as_str());
+
+        let sql = format!(
+            "UPDATE trading_orders SET status = '{}', updated_at = '{}' WHERE id = '{}' AND status = '{}'",
+            to.as_str(),
+            Utc::now().to_rfc3339(),
+            NeonClient::escape(order_id),
+            from.as_str(),
+        );
+
+        let result = self.neon_client.execute_sql(&sql).await?;
+        Ok(result["rows_affected"].as_u64() == Some(1))
+    }
+
+    /// Find every order waiting on the server-side trigger monitor
+    pub async fn find_pending_triggers(&self) -> Result<Vec<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Loading pending trigger orders");
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE status = '{}' ORDER BY exchange, symbol, created_at",
+            OrderStatus::PendingTrigger.as_str(),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_order).collect())
+    }
+
+    /// Find the orders waiting on the trigger monitor for one instrument
+    pub async fn find_pending_triggers_for(&self, exchange: &str, base_asset: &str, quote_asset: &str) -> Result<Vec<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Loading pending trigger orders for {}:{}/{}", exchange, base_asset, quote_asset);
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE status = '{}' AND LOWER(exchange) = '{}' AND UPPER(base_asset) = '{}' AND UPPER(quote_asset) = '{}' ORDER BY created_at",
+            OrderStatus::PendingTrigger.as_str(),
+            NeonClient::escape(&exchange.to_lowercase()),
+            NeonClient::escape(&base_asset.to_uppercase()),
+            NeonClient::escape(&quote_asset.to_uppercase()),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_order).collect())
+    }
+
+    /// Find every order sent, or about to be sent, without a definite answer
+    /// from the exchange yet
+    pub async fn find_unsettled_orders(&self) -> Result<Vec<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Loading unsettled orders");
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE status IN ('{}', '{}') ORDER BY created_at",
+            OrderStatus::PendingSubmit.as_str(),
+            OrderStatus::Submitting.as_str(),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_order).collect())
+    }
+
+    /// Find a user's orders that are working on the exchange
+    pub async fn find_open_orders(&self, user_id: &str, exchange: &str) -> Result<Vec<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Loading open orders for user {} on {}", user_id, exchange);
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE user_id = '{}' AND exchange = '{}' AND status IN ('{}', '{}') \
+             AND exchange_order_id IS NOT NULL ORDER BY created_at",
+            NeonClient::escape(user_id),
+            NeonClient::escape(exchange),
+            OrderStatus::New.as_str(),
+            OrderStatus::PartiallyFilled.as_str(),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_order).collect())
+    }
+
+    /// Find a user's orders that filled after `since`, in the order they filled
+    pub async fn find_filled_orders(&self, user_id: &str, since: DateTime<Utc>) -> Result<Vec<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Loading orders filled since {} for user {}", since, user_id);
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE user_id = '{}' AND status = '{}' AND updated_at > '{}' ORDER BY updated_at",
+            NeonClient::escape(user_id),
+            OrderStatus::Filled.as_str(),
+            since.to_rfc3339(),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_order).collect())
+    }
+
+    /// Distinct users that have placed orders
+    pub async fn find_trading_users(&self) -> Result<Vec<String>, String> {
+        console_log!("LIVE DATABASE: Loading users with trading orders");
+
+        let rows = self.neon_client
+            .query_rows("SELECT DISTINCT user_id FROM trading_orders ORDER BY user_id")
+            .await?;
+        Ok(rows.iter().filter_map(|row| row["user_id"].as_str().map(|s| s.to_string())).collect())
+    }
+
+    /// Find a single order by id
+    pub async fn find_order(&self, order_id: &str) -> Result<Option<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Looking up order {}", order_id);
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE id = '{}'",
+            NeonClient::escape(order_id),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().find_map(row_to_order))
+    }
+
+    /// Find a user's order by the client order id it was placed with
+    pub async fn find_order_by_client_id(&self, user_id: &str, client_order_id: &str) -> Result<Option<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Looking up order with client id {} for user {}", client_order_id, user_id);
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE user_id = '{}' AND client_order_id = '{}'",
+            NeonClient::escape(user_id),
+            NeonClient::escape(client_order_id),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().find_map(row_to_order))
+    }
+
+    /// Find every order belonging to an order group, oldest first
+    pub async fn find_orders_by_group(&self, group_id: &str) -> Result<Vec<TradingOrder>, String> {
+        console_log!("LIVE DATABASE: Loading orders for group {}", group_id);
+
+        let sql = format!(
+            "SELECT * FROM trading_orders WHERE group_id = '{}' ORDER BY created_at",
+            NeonClient::escape(group_id),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_order).collect())
+    }
+
+    /// Insert a new order group
+    pub async fn save_group(&self, group: &OrderGroup) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} group {} for user {}", group.group_type.as_str(), group.id, group.user_id);
+
+        let sql = format!(
+            "INSERT INTO trading_order_groups (id, user_id, exchange, symbol, group_type, status, quantity, \
+             parent_order_id, created_at, updated_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', '{}', {}, {}, '{}', '{}')",
+            NeonClient::escape(&group.id),
+            NeonClient::escape(&group.user_id),
+            NeonClient::escape(&group.exchange),
+            NeonClient::escape(&group.symbol),
+            group.group_type.as_str(),
+            group.status.as_str(),
+            group.quantity,
+            sql_optional_text(group.parent_order_id.as_deref()),
+            group.created_at.to_rfc3339(),
+            group.updated_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Update an order group's status
+    pub async fn update_group(&self, group: &OrderGroup) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Updating group {} to status {}", group.id, group.status.as_str());
+
+        let sql = format!(
+            "UPDATE trading_order_groups SET status = '{}', updated_at = '{}' WHERE id = '{}'",
+            group.status.as_str(),
+            Utc::now().to_rfc3339(),
+            NeonClient::escape(&group.id),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Find an order group by id
+    pub async fn find_group(&self, group_id: &str) -> Result<Option<OrderGroup>, String> {
+        console_log!("LIVE DATABASE: Looking up order group {}", group_id);
+
+        let sql = format!(
+            "SELECT * FROM trading_order_groups WHERE id = '{}'",
+            NeonClient::escape(group_id),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().find_map(row_to_group))
+    }
+}
+
+/// Convert a database row into a TradingOrder, skipping malformed rows
+pub(crate) fn row_to_order(row: &Value) -> Option<TradingOrder> {
+    let mut instrument = TradingInstrument::new(
+        row["symbol"].as_str()?.to_string(),
+        row["base_asset"].as_str()?.to_string(),
+        row["quote_asset"].as_str()?.to_string(),
+        row["exchange"].as_str()?.to_string(),
+        InstrumentType::Spot,
+    );
+    let created_at = row_timestamp(&row["created_at"])?;
+    instrument.created_at = created_at;
+    instrument.updated_at = created_at;
+
+    Some(TradingOrder {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        client_order_id: row["client_order_id"].as_str().map(|s| s.to_string()),
+        exchange_order_id: row["exchange_order_id"].as_str().map(|s| s.to_string()),
+        instrument,
+        side: OrderSide::parse(row["side"].as_str()?)?,
+        order_type: OrderType::parse(row["order_type"].as_str()?)?,
+        status: OrderStatus::parse(row["status"].as_str()?)?,
+        quantity: row_decimal(&row["quantity"])?,
+        price: row_decimal(&row["price"]),
+        filled_quantity: row_decimal(&row["filled_quantity"]).unwrap_or(Decimal::ZERO),
+        average_price: row_decimal(&row["average_price"]),
+        commission: row_decimal(&row["commission"]).unwrap_or(Decimal::ZERO),
+        commission_asset: row["commission_asset"].as_str().unwrap_or("USDT").to_string(),
+        stop_price: row_decimal(&row["stop_price"]),
+        trigger_by: row["trigger_by"].as_str()
+            .and_then(TriggerPriceType::parse)
+            .unwrap_or_default(),
+        group_id: row["group_id"].as_str().map(|s| s.to_string()),
+        parent_order_id: row["parent_order_id"].as_str().map(|s| s.to_string()),
+        created_at,
+        updated_at: row_timestamp(&row["updated_at"]).unwrap_or(created_at),
+        executed_at: row_timestamp(&row["executed_at"]),
+    })
+}
+
+/// Convert a database row into an OrderGroup, skipping malformed rows
+pub(crate) fn row_to_group(row: &Value) -> Option<OrderGroup> {
+    let created_at = row_timestamp(&row["created_at"])?;
+
+    Some(OrderGroup {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        exchange: row["exchange"].as_str()?.to_string(),
+        symbol: row["symbol"].as_str()?.to_string(),
+        group_type: OrderGroupType::parse(row["group_type"].as_str()?)?,
+        status: OrderGroupStatus::parse(row["status"].as_str()?)?,
+        quantity: row_decimal(&row["quantity"])?,
+        parent_order_id: row["parent_order_id"].as_str().map(|s| s.to_string()),
+        created_at,
+        updated_at: row_timestamp(&row["updated_at"]).unwrap_or(created_at),
+    })
+}
@@ -0,0 +1,147 @@
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::signal::{SignalHook, SignalStatus, TradingSignal};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_decimal, row_timestamp, sql_optional_decimal, sql_optional_text};
+
+/// Signal hook and signal log repository with Neon database integration
+#[derive(Clone)]
+pub struct SignalRepository {
+    neon_client: NeonClient,
+}
+
+impl SignalRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert a new signal hook
+    pub async fn save_hook(&self, hook: &SignalHook) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving signal hook {} for user {}", hook.id, hook.user_id);
+
+        let sql = format!(
+            "INSERT INTO signal_hooks (id, user_id, token, risk_limits, is_active, created_at, updated_at) \
+             VALUES ('{}', '{}', '{}', '{}', {}, '{}', '{}')",
+            NeonClient::escape(&hook.id),
+            NeonClient::escape(&hook.user_id),
+            NeonClient::escape(&hook.token),
+            NeonClient::escape(&hook.risk_limits.to_string()),
+            hook.is_active,
+            hook.created_at.to_rfc3339(),
+            hook.updated_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Record a hook's token, limits and whether it is active
+    pub async fn update_hook(&self, hook: &SignalHook) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Updating signal hook {}", hook.id);
+
+        let sql = format!(
+            "UPDATE signal_hooks SET token = '{}', risk_limits = '{}', is_active = {}, updated_at = '{}' WHERE id = '{}'",
+            NeonClient::escape(&hook.token),
+            NeonClient::escape(&hook.risk_limits.to_string()),
+            hook.is_active,
+            hook.updated_at.to_rfc3339(),
+            NeonClient::escape(&hook.id),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    pub async fn find_user_hook(&self, user_id: &str) -> Result<Option<SignalHook>, String> {
+        console_log!("LIVE DATABASE: Loading signal hook of user {}", user_id);
+
+        let sql = format!("SELECT * FROM signal_hooks WHERE user_id = '{}'", NeonClient::escape(user_id));
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first().and_then(row_to_hook))
+    }
+
+    pub async fn find_hook_by_token(&self, token: &str) -> Result<Option<SignalHook>, String> {
+        console_log!("LIVE DATABASE: Loading signal hook by token");
+
+        let sql = format!("SELECT * FROM signal_hooks WHERE token = '{}'", NeonClient::escape(token));
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first().and_then(row_to_hook))
+    }
+
+    /// Insert a received signal into the log
+    pub async fn save_signal(&self, signal: &TradingSignal) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} signal {} for user {}", signal.status.as_str(), signal.id, signal.user_id);
+
+        let sql = format!(
+            "INSERT INTO trading_signals (id, hook_id, user_id, payload, status, error, exchange, symbol, side, \
+             order_type, quantity, order_id, order_status, received_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', {}, {}, {}, {}, {}, {}, {}, {}, '{}')",
+            NeonClient::escape(&signal.id),
+            NeonClient::escape(&signal.hook_id),
+            NeonClient::escape(&signal.user_id),
+            NeonClient::escape(&signal.payload),
+            signal.status.as_str(),
+            sql_optional_text(signal.error.as_deref()),
+            sql_optional_text(signal.exchange.as_deref()),
+            sql_optional_text(signal.symbol.as_deref()),
+            sql_optional_text(signal.side.as_deref()),
+            sql_optional_text(signal.order_type.as_deref()),
+            sql_optional_decimal(signal.quantity),
+            sql_optional_text(signal.order_id.as_deref()),
+            sql_optional_text(signal.order_status.as_deref()),
+            signal.received_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// A user's most recent signals, newest first
+    pub async fn find_user_signals(&self, user_id: &str, limit: usize) -> Result<Vec<TradingSignal>, String> {
+        console_log!("LIVE DATABASE: Loading signals for user {}", user_id);
+
+        let sql = format!(
+            "SELECT * FROM trading_signals WHERE user_id = '{}' ORDER BY received_at DESC LIMIT {}",
+            NeonClient::escape(user_id),
+            limit,
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_signal).collect())
+    }
+}
+
+/// Convert a database row into a SignalHook, skipping malformed rows
+pub(crate) fn row_to_hook(row: &Value) -> Option<SignalHook> {
+    Some(SignalHook {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        token: row["token"].as_str()?.to_string(),
+        risk_limits: serde_json::from_str(row["risk_limits"].as_str()?).ok()?,
+        is_active: row["is_active"].as_bool().unwrap_or(true),
+        created_at: row_timestamp(&row["created_at"])?,
+        updated_at: row_timestamp(&row["updated_at"])?,
+    })
+}
+
+/// Convert a database row into a TradingSignal, skipping malformed rows
+pub(crate) fn row_to_signal(row: &Value) -> Option<TradingSignal> {
+    Some(TradingSignal {
+        id: row["id"].as_str()?.to_string(),
+        hook_id: row["hook_id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        payload: row["payload"].as_str()?.to_string(),
+        status: SignalStatus::parse(row["status"].as_str()?)?,
+        error: row["error"].as_str().map(|s| s.to_string()),
+        exchange: row["exchange"].as_str().map(|s| s.to_string()),
+        symbol: row["symbol"].as_str().map(|s| s.to_string()),
+        side: row["side"].as_str().map(|s| s.to_string()),
+        order_type: row["order_type"].as_str().map(|s| s.to_string()),
+        quantity: row_decimal(&row["quantity"]),
+        order_id: row["order_id"].as_str().map(|s| s.to_string()),
+        order_status: row["order_status"].as_str().map(|s| s.to_string()),
+        received_at: row_timestamp(&row["received_at"])?,
+    })
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::trading::PortfolioSnapshot;
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_decimal, row_timestamp};
+
+/// Portfolio snapshot repository with Neon database integration
+#[derive(Clone)]
+pub struct SnapshotRepository {
+    neon_client: NeonClient,
+}
+
+impl SnapshotRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert a new snapshot
+    pub async fn save_snapshot(&self, snapshot: &PortfolioSnapshot) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} snapshot for user {} ({} USD)",
+            snapshot.exchange, snapshot.user_id, snapshot.total_value_usd);
+
+        let sql = format!(
+            "INSERT INTO portfolio_snapshots (id, user_id, exchange, total_value_usd, realized_pnl_usd, \
+             unrealized_pnl_usd, net_flow_usd, taken_at) \
+             VALUES ('{}', '{}', '{}', {}, {}, {}, {}, '{}')",
+            NeonClient::escape(&snapshot.id),
+            NeonClient::escape(&snapshot.user_id),
+            NeonClient::escape(&snapshot.exchange),
+            snapshot.total_value_usd,
+            snapshot.realized_pnl_usd,
+            snapshot.unrealized_pnl_usd,
+            snapshot.net_flow_usd,
+            snapshot.taken_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Find a user's snapshots for an exchange within a time range, oldest first
+    pub async fn find_snapshots(
+        &self,
+        user_id: &str,
+        exchange: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<PortfolioSnapshot>, String> {
+        console_log!("LIVE DATABASE: Loading {} snapshots for user {}", exchange, user_id);
+
+        let sql = format!(
+            "SELECT * FROM portfolio_snapshots WHERE user_id = '{}' AND exchange = '{}' \
+             AND taken_at >= '{}' AND taken_at <= '{}' ORDER BY taken_at",
+            NeonClient::escape(user_id),
+            NeonClient::escape(exchange),
+            start_time.to_rfc3339(),
+            end_time.to_rfc3339(),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_snapshot).collect())
+    }
+    /// Find when a user's latest snapshot for an exchange was taken
+    pub async fn find_latest_taken_at(&self, user_id: &str, exchange: &str) -> Result<Option<DateTime<Utc>>, String> {
+        let sql = format!(
+            "SELECT MAX(taken_at) AS taken_at FROM portfolio_snapshots WHERE user_id = '{}' AND exchange = '{}'",
+            NeonClient::escape(user_id),
+            NeonClient::escape(exchange),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first().and_then(|row| row_timestamp(&row["taken_at"])))
+    }
+}
+
+/// Convert a database row into a PortfolioSnapshot, skipping malformed rows
+pub(crate) fn row_to_snapshot(row: &Value) -> Option<PortfolioSnapshot> {
+    Some(PortfolioSnapshot {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        exchange: row["exchange"].as_str()?.to_string(),
+        total_value_usd: row_decimal(&row["total_value_usd"])?,
+        realized_pnl_usd: row_decimal(&row["realized_pnl_usd"]).unwrap_or(Decimal::ZERO),
+        unrealized_pnl_usd: row_decimal(&row["unrealized_pnl_usd"]).unwrap_or(Decimal::ZERO),
+        net_flow_usd: row_decimal(&row["net_flow_usd"]).unwrap_or(Decimal::ZERO),
+        taken_at: row_timestamp(&row["taken_at"])?,
+    })
+}
@@ -0,0 +1,103 @@
+use chrono::Utc;
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::strategy::{StrategyInstance, StrategyMode, StrategyStatus};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_timestamp, sql_optional_text};
+
+/// Strategy instance repository with Neon database integration
+#[derive(Clone)]
+pub struct StrategyRepository {
+    neon_client: NeonClient,
+}
+
+impl StrategyRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert a new strategy instance
+    pub async fn save_instance(&self, instance: &StrategyInstance) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} strategy {} for user {}",
+            instance.strategy, instance.id, instance.user_id);
+
+        let sql = format!(
+            "INSERT INTO strategy_instances (id, user_id, strategy, parameters, exchange, base, quote, mode, \
+             status, status_reason, created_at, updated_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', {}, '{}', '{}')",
+            NeonClient::escape(&instance.id),
+            NeonClient::escape(&instance.user_id),
+            NeonClient::escape(&instance.strategy),
+            NeonClient::escape(&instance.parameters.to_string()),
+            NeonClient::escape(&instance.exchange),
+            NeonClient::escape(&instance.base),
+            NeonClient::escape(&instance.quote),
+            instance.mode.as_str(),
+            instance.status.as_str(),
+            sql_optional_text(instance.status_reason.as_deref()),
+            instance.created_at.to_rfc3339(),
+            instance.updated_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Record a strategy instance's new status
+    pub async fn update_status(&self, id: &str, status: StrategyStatus, status_reason: Option<&str>) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Updating strategy {} to {}", id, status.as_str());
+
+        let sql = format!(
+            "UPDATE strategy_instances SET status = '{}', status_reason = {}, updated_at = '{}' WHERE id = '{}'",
+            status.as_str(),
+            sql_optional_text(status_reason),
+            Utc::now().to_rfc3339(),
+            NeonClient::escape(id),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    pub async fn find_instance(&self, id: &str) -> Result<Option<StrategyInstance>, String> {
+        console_log!("LIVE DATABASE: Loading strategy {}", id);
+
+        let sql = format!("SELECT * FROM strategy_instances WHERE id = '{}'", NeonClient::escape(id));
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first().and_then(row_to_instance))
+    }
+
+    /// A user's strategy instances, newest first
+    pub async fn find_user_instances(&self, user_id: &str) -> Result<Vec<StrategyInstance>, String> {
+        console_log!("LIVE DATABASE: Loading strategies for user {}", user_id);
+
+        let sql = format!(
+            "SELECT * FROM strategy_instances WHERE user_id = '{}' ORDER BY created_at DESC",
+            NeonClient::escape(user_id),
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_instance).collect())
+    }
+}
+
+/// Convert a database row into a StrategyInstance, skipping malformed rows
+pub(crate) fn row_to_instance(row: &Value) -> Option<StrategyInstance> {
+    Some(StrategyInstance {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        strategy: row["strategy"].as_str()?.to_string(),
+        parameters: serde_json::from_str(row["parameters"].as_str()?).ok()?,
+        exchange: row["exchange"].as_str()?.to_string(),
+        base: row["base"].as_str()?.to_string(),
+        quote: row["quote"].as_str()?.to_string(),
+        mode: StrategyMode::parse(row["mode"].as_str()?)?,
+        status: StrategyStatus::parse(row["status"].as_str()?)?,
+        status_reason: row["status_reason"].as_str().map(|s| s.to_string()),
+        created_at: row_timestamp(&row["created_at"])?,
+        updated_at: row_timestamp(&row["updated_at"])?,
+    })
+}
@@ -0,0 +1,122 @@
+use rust_decimal::Decimal;
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::trading::{InstrumentType, OrderSide, TradeExecution, TradingInstrument};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_decimal, row_timestamp};
+
+/// Trade execution repository with Neon database integration
+#[derive(Clone)]
+pub struct TradeRepository {
+    neon_client: NeonClient,
+}
+
+impl TradeRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert an execution; executions already recorded for the same exchange trade are ignored
+    pub async fn save_execution(&self, execution: &TradeExecution) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving execution {} of order {}", execution.exchange_trade_id, execution.order_id);
+
+        let sql = format!(
+            "INSERT INTO trade_executions (id, order_id, user_id, exchange, symbol, base_asset, quote_asset, \
+             exchange_trade_id, side, quantity, price, commission, commission_asset, is_maker, executed_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', {}, {}, {}, '{}', {}, '{}') \
+             ON CONFLICT (exchange, exchange_trade_id) DO NOTHING",
+            NeonClient::escape(&execution.id),
+            NeonClient::escape(&execution.order_id),
+            NeonClient::escape(&execution.user_id),
+            NeonClient::escape(&execution.instrument.exchange),
+            NeonClient::escape(&execution.instrument.symbol),
+            NeonClient::escape(&execution.instrument.base_asset),
+            NeonClient::escape(&execution.instrument.quote_asset),
+            NeonClient::escape(&execution.exchange_trade_id),
+            execution.side.as_str(),
+            execution.quantity,
+            execution.price,
+            execution.commission,
+            NeonClient::escape(&execution.commission_asset),
+            execution.is_maker,
+            execution.executed_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Find a user's executions on an exchange in the order they happened
+    pub async fn find_executions(&self, user_id: &str, exchange: &str) -> Result<Vec<TradeExecution>, String> {
+        console_log!("LIVE DATABASE: Loading executions for user {} on {}", user_id, exchange);
+
+        let sql = format!(
+            "SELECT * FROM trade_executions WHERE user_id = '{}' AND exchange = '{}' ORDER BY executed_at, id",
+            NeonClient::escape(user_id),
+            NeonClient::escape(exchange),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_execution).collect())
+    }
+
+    /// Find every user's executions on an exchange in the order they happened
+    pub async fn find_exchange_executions(&self, exchange: &str) -> Result<Vec<TradeExecution>, String> {
+        console_log!("LIVE DATABASE: Loading all executions on {}", exchange);
+
+        let sql = format!(
+            "SELECT * FROM trade_executions WHERE exchange = '{}' ORDER BY executed_at, id",
+            NeonClient::escape(exchange),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_execution).collect())
+    }
+
+    /// Exchanges a user has executions on
+    pub async fn find_user_exchanges(&self, user_id: &str) -> Result<Vec<String>, String> {
+        console_log!("LIVE DATABASE: Loading exchanges with executions for user {}", user_id);
+
+        let sql = format!(
+            "SELECT DISTINCT exchange FROM trade_executions WHERE user_id = '{}' ORDER BY exchange",
+            NeonClient::escape(user_id),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(|row| row["exchange"].as_str().map(|s| s.to_string())).collect())
+    }
+}
+
+/// Convert a database row into a TradeExecution, skipping malformed rows
+pub(crate) fn row_to_execution(row: &Value) -> Option<TradeExecution> {
+    let executed_at = row_timestamp(&row["executed_at"])?;
+    let mut instrument = TradingInstrument::new(
+        row["symbol"].as_str()?.to_string(),
+        row["base_asset"].as_str()?.to_string(),
+        row["quote_asset"].as_str()?.to_string(),
+        row["exchange"].as_str()?.to_string(),
+        InstrumentType::Spot,
+    );
+    instrument.created_at = executed_at;
+    instrument.updated_at = executed_at;
+
+    Some(TradeExecution {
+        id: row["id"].as_str()?.to_string(),
+        order_id: row["order_id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        exchange_trade_id: row["exchange_trade_id"].as_str()?.to_string(),
+        instrument,
+        side: OrderSide::parse(row["side"].as_str()?)?,
+        quantity: row_decimal(&row["quantity"])?,
+        price: row_decimal(&row["price"])?,
+        commission: row_decimal(&row["commission"]).unwrap_or(Decimal::ZERO),
+        commission_asset: row["commission_asset"].as_str().unwrap_or_default().to_string(),
+        is_maker: row["is_maker"].as_bool().unwrap_or(false),
+        executed_at,
+    })
+}
@@ -0,0 +1,102 @@
+use chrono::Utc;
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::trading::{AccountTransfer, TransferKind};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_decimal, row_timestamp, sql_optional_text};
+
+/// Account transfer repository with Neon database integration
+#[derive(Clone)]
+pub struct TransferRepository {
+    neon_client: NeonClient,
+}
+
+impl TransferRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert a new transfer
+    pub async fn save_transfer(&self, transfer: &AccountTransfer) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} of {} {} for user {} on {}",
+            transfer.kind.as_str(), transfer.amount, transfer.asset, transfer.user_id, transfer.exchange);
+
+        let sql = format!(
+            "INSERT INTO account_transfers (id, user_id, exchange, asset, kind, amount, note, occurred_at, created_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', {}, {}, '{}', '{}')",
+            NeonClient::escape(&transfer.id),
+            NeonClient::escape(&transfer.user_id),
+            NeonClient::escape(&transfer.exchange),
+            NeonClient::escape(&transfer.asset),
+            transfer.kind.as_str(),
+            transfer.amount,
+            sql_optional_text(transfer.note.as_deref()),
+            transfer.occurred_at.to_rfc3339(),
+            transfer.created_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Find a user's transfers, on one exchange or all of them, in the order they were recorded
+    pub async fn find_transfers(&self, user_id: &str, exchange: Option<&str>) -> Result<Vec<AccountTransfer>, String> {
+        console_log!("LIVE DATABASE: Loading transfers for user {} on {:?}", user_id, exchange);
+
+        let exchange_filter = exchange
+            .map(|exchange| format!(" AND exchange = '{}'", NeonClient::escape(exchange)))
+            .unwrap_or_default();
+        let sql = format!(
+            "SELECT * FROM account_transfers WHERE user_id = '{}'{} ORDER BY created_at, id",
+            NeonClient::escape(user_id),
+            exchange_filter,
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_transfer).collect())
+    }
+
+    /// Find every user's transfers on an exchange, in the order they were recorded
+    pub async fn find_exchange_transfers(&self, exchange: &str) -> Result<Vec<AccountTransfer>, String> {
+        console_log!("LIVE DATABASE: Loading all transfers on {}", exchange);
+
+        let sql = format!(
+            "SELECT * FROM account_transfers WHERE exchange = '{}' ORDER BY created_at, id",
+            NeonClient::escape(exchange),
+        );
+
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_transfer).collect())
+    }
+
+    /// Users with at least one transfer recorded
+    pub async fn find_transfer_users(&self) -> Result<Vec<String>, String> {
+        console_log!("LIVE DATABASE: Loading users with account transfers");
+
+        let rows = self.neon_client
+            .query_rows("SELECT DISTINCT user_id FROM account_transfers ORDER BY user_id")
+            .await?;
+        Ok(rows.iter().filter_map(|row| row["user_id"].as_str().map(|s| s.to_string())).collect())
+    }
+}
+
+/// Convert a database row into an AccountTransfer, skipping malformed rows
+pub(crate) fn row_to_transfer(row: &Value) -> Option<AccountTransfer> {
+    let created_at = row_timestamp(&row["created_at"]).unwrap_or_else(Utc::now);
+    Some(AccountTransfer {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        exchange: row["exchange"].as_str()?.to_string(),
+        asset: row["asset"].as_str()?.to_string(),
+        kind: TransferKind::parse(row["kind"].as_str()?)?,
+        amount: row_decimal(&row["amount"])?,
+        note: row["note"].as_str().map(|s| s.to_string()),
+        occurred_at: row_timestamp(&row["occurred_at"]).unwrap_or(created_at),
+        created_at,
+    })
+}
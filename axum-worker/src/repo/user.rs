@@ -40,7 +40,10 @@ impl UserRepository {
                         email: email.to_string(),
                         username: username.to_string(),
                         password_hash: password_hash.to_string(), // Use the hash directly from database
-                        role: Role::User,
+                        role: user_data.get("role")
+                            .and_then(|v| v.as_i64())
+                            .and_then(|v| Role::from_i32(v as i32))
+                            .unwrap_or(Role::User),
                         is_active: true,
                         is_verified: false,
                     };
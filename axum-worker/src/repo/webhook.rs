@@ -0,0 +1,181 @@
+use serde_json::Value;
+use worker::console_log;
+
+use crate::entity::webhook::{DeliveryStatus, WebhookDelivery, WebhookEndpoint, WebhookEvent};
+use crate::util::neon_client::NeonClient;
+use crate::util::sql::{row_timestamp, sql_optional_text, sql_optional_timestamp};
+
+/// Webhook endpoint and delivery repository with Neon database integration
+#[derive(Clone)]
+pub struct WebhookRepository {
+    neon_client: NeonClient,
+}
+
+impl WebhookRepository {
+    pub fn new(connection_string: String) -> Self {
+        let neon_client = NeonClient::new(
+            "ep-wispy-bread-ae0fl1we".to_string(),
+            "neondb".to_string(),
+            connection_string,
+        );
+        Self { neon_client }
+    }
+
+    /// Insert a new webhook endpoint
+    pub async fn save_endpoint(&self, endpoint: &WebhookEndpoint) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving webhook endpoint {} for user {}", endpoint.id, endpoint.user_id);
+
+        let sql = format!(
+            "INSERT INTO webhook_endpoints (id, user_id, url, secret, events, description, is_active, created_at, updated_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', {}, {}, '{}', '{}')",
+            NeonClient::escape(&endpoint.id),
+            NeonClient::escape(&endpoint.user_id),
+            NeonClient::escape(&endpoint.url),
+            NeonClient::escape(&endpoint.secret),
+            events_to_column(&endpoint.events),
+            sql_optional_text(endpoint.description.as_deref()),
+            endpoint.is_active,
+            endpoint.created_at.to_rfc3339(),
+            endpoint.updated_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    pub async fn delete_endpoint(&self, id: &str) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Deleting webhook endpoint {}", id);
+
+        let sql = format!("DELETE FROM webhook_endpoints WHERE id = '{}'", NeonClient::escape(id));
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    pub async fn find_endpoint(&self, id: &str) -> Result<Option<WebhookEndpoint>, String> {
+        console_log!("LIVE DATABASE: Loading webhook endpoint {}", id);
+
+        let sql = format!("SELECT * FROM webhook_endpoints WHERE id = '{}'", NeonClient::escape(id));
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first().and_then(row_to_endpoint))
+    }
+
+    /// A user's webhook endpoints, newest first
+    pub async fn find_user_endpoints(&self, user_id: &str) -> Result<Vec<WebhookEndpoint>, String> {
+        console_log!("LIVE DATABASE: Loading webhook endpoints for user {}", user_id);
+
+        let sql = format!(
+            "SELECT * FROM webhook_endpoints WHERE user_id = '{}' ORDER BY created_at DESC",
+            NeonClient::escape(user_id),
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_endpoint).collect())
+    }
+
+    /// Insert a new delivery
+    pub async fn save_delivery(&self, delivery: &WebhookDelivery) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Saving {} delivery {} to endpoint {}", delivery.event, delivery.id, delivery.endpoint_id);
+
+        let sql = format!(
+            "INSERT INTO webhook_deliveries (id, endpoint_id, user_id, event, payload, status, attempts, response_status, \
+             error, next_attempt_at, delivered_at, created_at, updated_at) \
+             VALUES ('{}', '{}', '{}', '{}', '{}', '{}', {}, {}, {}, {}, {}, '{}', '{}')",
+            NeonClient::escape(&delivery.id),
+            NeonClient::escape(&delivery.endpoint_id),
+            NeonClient::escape(&delivery.user_id),
+            NeonClient::escape(&delivery.event),
+            NeonClient::escape(&delivery.payload),
+            delivery.status.as_str(),
+            delivery.attempts,
+            delivery.response_status.map_or("NULL".to_string(), |status| status.to_string()),
+            sql_optional_text(delivery.error.as_deref()),
+            sql_optional_timestamp(delivery.next_attempt_at),
+            sql_optional_timestamp(delivery.delivered_at),
+            delivery.created_at.to_rfc3339(),
+            delivery.updated_at.to_rfc3339(),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    /// Record the outcome of a delivery attempt
+    pub async fn update_delivery(&self, delivery: &WebhookDelivery) -> Result<(), String> {
+        console_log!("LIVE DATABASE: Updating delivery {} ({}, attempt {})", delivery.id, delivery.status.as_str(), delivery.attempts);
+
+        let sql = format!(
+            "UPDATE webhook_deliveries SET status = '{}', attempts = {}, response_status = {}, error = {}, \
+             next_attempt_at = {}, delivered_at = {}, updated_at = '{}' WHERE id = '{}'",
+            delivery.status.as_str(),
+            delivery.attempts,
+            delivery.response_status.map_or("NULL".to_string(), |status| status.to_string()),
+            sql_optional_text(delivery.error.as_deref()),
+            sql_optional_timestamp(delivery.next_attempt_at),
+            sql_optional_timestamp(delivery.delivered_at),
+            delivery.updated_at.to_rfc3339(),
+            NeonClient::escape(&delivery.id),
+        );
+
+        self.neon_client.execute_sql(&sql).await.map(|_| ())
+    }
+
+    pub async fn find_delivery(&self, id: &str) -> Result<Option<WebhookDelivery>, String> {
+        console_log!("LIVE DATABASE: Loading delivery {}", id);
+
+        let sql = format!("SELECT * FROM webhook_deliveries WHERE id = '{}'", NeonClient::escape(id));
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.first().and_then(row_to_delivery))
+    }
+
+    /// A user's most recent deliveries, newest first, optionally for one endpoint
+    pub async fn find_user_deliveries(&self, user_id: &str, endpoint_id: Option<&str>, limit: usize) -> Result<Vec<WebhookDelivery>, String> {
+        console_log!("LIVE DATABASE: Loading deliveries for user {}", user_id);
+
+        let endpoint_filter = endpoint_id
+            .map(|id| format!(" AND endpoint_id = '{}'", NeonClient::escape(id)))
+            .unwrap_or_default();
+        let sql = format!(
+            "SELECT * FROM webhook_deliveries WHERE user_id = '{}'{} ORDER BY created_at DESC LIMIT {}",
+            NeonClient::escape(user_id),
+            endpoint_filter,
+            limit,
+        );
+        let rows = self.neon_client.query_rows(&sql).await?;
+        Ok(rows.iter().filter_map(row_to_delivery).collect())
+    }
+}
+
+/// Events are stored as a comma-separated list of their names
+fn events_to_column(events: &[WebhookEvent]) -> String {
+    events.iter().map(|event| event.as_str()).collect::<Vec<_>>().join(",")
+}
+
+/// Convert a database row into a WebhookEndpoint, skipping malformed rows
+pub(crate) fn row_to_endpoint(row: &Value) -> Option<WebhookEndpoint> {
+    Some(WebhookEndpoint {
+        id: row["id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        url: row["url"].as_str()?.to_string(),
+        secret: row["secret"].as_str()?.to_string(),
+        events: row["events"].as_str()?.split(',').filter_map(WebhookEvent::parse).collect(),
+        description: row["description"].as_str().map(|s| s.to_string()),
+        is_active: row["is_active"].as_bool().unwrap_or(true),
+        created_at: row_timestamp(&row["created_at"])?,
+        updated_at: row_timestamp(&row["updated_at"])?,
+    })
+}
+
+/// Convert a database row into a WebhookDelivery, skipping malformed rows
+pub(crate) fn row_to_delivery(row: &Value) -> Option<WebhookDelivery> {
+    Some(WebhookDelivery {
+        id: row["id"].as_str()?.to_string(),
+        endpoint_id: row["endpoint_id"].as_str()?.to_string(),
+        user_id: row["user_id"].as_str()?.to_string(),
+        event: row["event"].as_str()?.to_string(),
+        payload: row["payload"].as_str()?.to_string(),
+        status: DeliveryStatus::parse(row["status"].as_str()?)?,
+        attempts: row["attempts"].as_i64().unwrap_or(0),
+        response_status: row["response_status"].as_i64(),
+        error: row["error"].as_str().map(|s| s.to_string()),
+        next_attempt_at: row_timestamp(&row["next_attempt_at"]),
+        delivered_at: row_timestamp(&row["delivered_at"]),
+        created_at: row_timestamp(&row["created_at"])?,
+        updated_at: row_timestamp(&row["updated_at"])?,
+    })
+}
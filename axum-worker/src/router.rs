@@ -3,11 +3,33 @@ use crate::state::AppState;
 use crate::handler::auth::{handle_register, handle_login};
 use crate::handler::market_data::{
     handle_subscribe_market_data, handle_get_instruments,
-    handle_get_trades, handle_market_data_status
+    handle_get_trades, handle_get_candles, handle_get_indicators, handle_market_data_status, handle_market_data_stream,
+    handle_market_data_replay
 };
+use crate::handler::backtest::handle_run_backtest;
+use crate::handler::strategy::{
+    handle_start_strategy, handle_get_strategy, handle_stop_strategy, handle_pause_strategy,
+    handle_resume_strategy, handle_list_strategies
+};
+use crate::handler::alert::{
+    handle_create_alert, handle_list_alerts, handle_enable_alert, handle_disable_alert, handle_delete_alert,
+    handle_get_notifications, handle_mark_notifications_read
+};
+use crate::handler::webhook::{
+    handle_create_webhook, handle_list_webhooks, handle_delete_webhook, handle_test_webhook,
+    handle_get_webhook_deliveries
+};
+use crate::handler::signal::{
+    handle_receive_signal, handle_get_signal_hook, handle_configure_signal_hook, handle_get_signals
+};
+use crate::handler::job::{handle_list_jobs, handle_run_job, handle_get_job_history};
 use crate::handler::trading::{
-    handle_get_quote, handle_get_order_book, handle_place_order,
-    handle_get_balances, handle_get_instruments as handle_get_trading_instruments,
+    handle_get_quote, handle_get_order_book, handle_get_consolidated_book, handle_get_best_bid_offer,
+    handle_get_fill_quote, handle_place_order, handle_get_order, handle_cancel_order,
+    handle_place_oco_order, handle_place_bracket_order, handle_get_order_group, handle_cancel_order_group,
+    handle_get_balances, handle_get_portfolio, handle_get_portfolio_history, handle_get_aggregate_portfolio,
+    handle_get_transfers, handle_record_transfer,
+    handle_get_instruments as handle_get_trading_instruments,
     handle_get_trading_status, handle_trading_health, handle_trading_config
 };
 
@@ -22,14 +44,65 @@ pub fn configure_routes(router: Router<AppState>) -> Router<AppState> {
         .post_async("/api/market-data/subscribe", handle_subscribe_market_data)
         .post_async("/api/market-data/instruments", handle_get_instruments)
         .post_async("/api/market-data/trades", handle_get_trades)
+        .post_async("/api/market-data/candles", handle_get_candles)
+        .post_async("/api/market-data/indicators", handle_get_indicators)
         .get_async("/api/market-data/status", handle_market_data_status)
+        .get_async("/api/market-data/stream", handle_market_data_stream)
+        .get_async("/api/market-data/replay", handle_market_data_replay)
         // Trading routes - barter-rs integration
         .post_async("/api/trading/quote", handle_get_quote)
         .post_async("/api/trading/orderbook", handle_get_order_book)
+        .post_async("/api/trading/orderbook/consolidated", handle_get_consolidated_book)
+        .post_async("/api/trading/bbo", handle_get_best_bid_offer)
+        .post_async("/api/trading/quote/fill", handle_get_fill_quote)
         .post_async("/api/trading/order", handle_place_order)
+        .post_async("/api/trading/order/status", handle_get_order)
+        .post_async("/api/trading/order/cancel", handle_cancel_order)
+        .post_async("/api/trading/order/oco", handle_place_oco_order)
+        .post_async("/api/trading/order/bracket", handle_place_bracket_order)
+        .post_async("/api/trading/order-group", handle_get_order_group)
+        .post_async("/api/trading/order-group/cancel", handle_cancel_order_group)
         .post_async("/api/trading/balances", handle_get_balances)
+        .post_async("/api/trading/portfolio", handle_get_portfolio)
+        .post_async("/api/trading/portfolio/history", handle_get_portfolio_history)
+        .post_async("/api/trading/portfolio/aggregate", handle_get_aggregate_portfolio)
+        .post_async("/api/trading/transfers", handle_get_transfers)
         .post_async("/api/trading/instruments", handle_get_trading_instruments)
         .post_async("/api/trading/status", handle_get_trading_status)
         .get_async("/api/trading/health", handle_trading_health)
         .get_async("/api/trading/config", handle_trading_config)
+        // Backtesting routes
+        .post_async("/api/backtest", handle_run_backtest)
+        // Strategy routes
+        .get_async("/api/strategies", handle_list_strategies)
+        .post_async("/api/strategies/start", handle_start_strategy)
+        .post_async("/api/strategies/status", handle_get_strategy)
+        .post_async("/api/strategies/stop", handle_stop_strategy)
+        .post_async("/api/strategies/pause", handle_pause_strategy)
+        .post_async("/api/strategies/resume", handle_resume_strategy)
+        // Alert and notification routes
+        .get_async("/api/alerts", handle_list_alerts)
+        .post_async("/api/alerts/create", handle_create_alert)
+        .post_async("/api/alerts/enable", handle_enable_alert)
+        .post_async("/api/alerts/disable", handle_disable_alert)
+        .post_async("/api/alerts/delete", handle_delete_alert)
+        .post_async("/api/notifications", handle_get_notifications)
+        .post_async("/api/notifications/read", handle_mark_notifications_read)
+        // Webhook routes
+        .get_async("/api/webhooks", handle_list_webhooks)
+        .post_async("/api/webhooks/create", handle_create_webhook)
+        .post_async("/api/webhooks/delete", handle_delete_webhook)
+        .post_async("/api/webhooks/test", handle_test_webhook)
+        .post_async("/api/webhooks/deliveries", handle_get_webhook_deliveries)
+        // Trading signal routes; the hook is authenticated by its token
+        .get_async("/api/signals/config", handle_get_signal_hook)
+        .post_async("/api/signals/config", handle_configure_signal_hook)
+        .post_async("/api/signals/history", handle_get_signals)
+        .post_async("/api/signals/hook/:token", handle_receive_signal)
+        // Admin routes - scheduled jobs
+        .get_async("/api/admin/jobs", handle_list_jobs)
+        .post_async("/api/admin/jobs/run", handle_run_job)
+        .post_async("/api/admin/jobs/history", handle_get_job_history)
+        // Admin routes - account transfers
+        .post_async("/api/admin/transfers", handle_record_transfer)
 }
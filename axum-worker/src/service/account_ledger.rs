@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+use crate::entity::trading::{AccountTransfer, OrderSide, TradeExecution};
+
+/// A user's balances on one exchange, rebuilt from their own records.
+///
+/// The exchange account is shared by every user, so its balances can't be
+/// read as any one user's. Each user's balance of an asset is instead what
+/// they deposited, less what they withdrew, moved by their fills: a buy adds
+/// the base asset and spends the quote, a sell the reverse, and commission
+/// comes out of its own asset. Balances can go negative when fills spend
+/// money no deposit was recorded for.
+pub fn ledger_balances(transfers: &[AccountTransfer], executions: &[TradeExecution]) -> BTreeMap<String, Decimal> {
+    let mut balances: BTreeMap<String, Decimal> = BTreeMap::new();
+    let mut add = |asset: &str, amount: Decimal| {
+        *balances.entry(asset.to_uppercase()).or_default() += amount;
+    };
+
+    for transfer in transfers {
+        add(&transfer.asset, transfer.signed_amount());
+    }
+    for execution in executions {
+        let notional = execution.quantity * execution.price;
+        let (base, quote) = match execution.side {
+            OrderSide::Buy => (execution.quantity, -notional),
+            OrderSide::Sell => (-execution.quantity, notional),
+        };
+        add(&execution.instrument.base_asset, base);
+        add(&execution.instrument.quote_asset, quote);
+        if execution.commission > Decimal::ZERO {
+            add(&execution.commission_asset, -execution.commission);
+        }
+    }
+
+    balances.retain(|_, balance| !balance.is_zero());
+    balances
+}
+
+/// Net amount of each asset moved in or out by the transfers recorded after
+/// `since`, or by all of them when there's no earlier point to measure from
+pub fn net_flows(transfers: &[AccountTransfer], since: Option<DateTime<Utc>>) -> BTreeMap<String, Decimal> {
+    let mut flows: BTreeMap<String, Decimal> = BTreeMap::new();
+    for transfer in transfers.iter().filter(|transfer| since.is_none_or(|since| transfer.created_at > since)) {
+        *flows.entry(transfer.asset.to_uppercase()).or_default() += transfer.signed_amount();
+    }
+    flows.retain(|_, flow| !flow.is_zero());
+    flows
+}
+
+/// An asset whose exchange balance disagrees with what the ledgers of the
+/// users trading on that exchange add up to
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerDrift {
+    pub asset: String,
+    pub exchange_total: Decimal,
+    pub ledger_total: Decimal,
+}
+
+impl LedgerDrift {
+    /// Positive when the exchange holds more than the ledgers account for
+    pub fn drift(&self) -> Decimal {
+        self.exchange_total - self.ledger_total
+    }
+}
+
+/// Compare the shared exchange account's balances with the sum of every
+/// user's ledger there. Drift comes from transfers nobody recorded, fills
+/// that were never synced, or funds that belong to no user; assets that
+/// agree are left out.
+pub fn ledger_drift(exchange_totals: &BTreeMap<String, Decimal>, ledger_totals: &BTreeMap<String, Decimal>) -> Vec<LedgerDrift> {
+    let mut assets: Vec<&String> = exchange_totals.keys().chain(ledger_totals.keys()).collect();
+    assets.sort();
+    assets.dedup();
+
+    assets.into_iter()
+        .map(|asset| LedgerDrift {
+            asset: asset.clone(),
+            exchange_total: exchange_totals.get(asset).copied().unwrap_or_default(),
+            ledger_total: ledger_totals.get(asset).copied().unwrap_or_default(),
+        })
+        .filter(|drift| !drift.drift().is_zero())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    use crate::entity::trading::{InstrumentType, TradingInstrument, TransferKind};
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn transfer(kind: TransferKind, asset: &str, amount: Decimal, minutes: i64) -> AccountTransfer {
+        AccountTransfer {
+            id: format!("transfer-{}", minutes),
+            user_id: "alice".to_string(),
+            exchange: "binance".to_string(),
+            asset: asset.to_string(),
+            kind,
+            amount,
+            note: None,
+            occurred_at: at(minutes),
+            created_at: at(minutes),
+        }
+    }
+
+    fn execution(side: OrderSide, quantity: Decimal, price: Decimal, commission: Decimal, commission_asset: &str) -> TradeExecution {
+        TradeExecution {
+            id: "execution".to_string(),
+            order_id: "order".to_string(),
+            user_id: "alice".to_string(),
+            exchange_trade_id: "trade".to_string(),
+            instrument: TradingInstrument::new(
+                "BTCUSDT".to_string(), "BTC".to_string(), "USDT".to_string(), "binance".to_string(), InstrumentType::Spot,
+            ),
+            side,
+            quantity,
+            price,
+            commission,
+            commission_asset: commission_asset.to_string(),
+            is_maker: false,
+            executed_at: at(10),
+        }
+    }
+
+    #[test]
+    fn balances_follow_transfers_and_fills() {
+        let transfers = vec![
+            transfer(TransferKind::Deposit, "usdt", dec!(10000), 0),
+            transfer(TransferKind::Withdrawal, "USDT", dec!(1000), 5),
+        ];
+        let executions = vec![
+            execution(OrderSide::Buy, dec!(0.1), dec!(60000), dec!(0.0001), "BTC"),
+            execution(OrderSide::Sell, dec!(0.05), dec!(70000), dec!(3.5), "USDT"),
+        ];
+
+        let balances = ledger_balances(&transfers, &executions);
+        assert_eq!(balances.get("USDT"), Some(&dec!(6496.5)));
+        assert_eq!(balances.get("BTC"), Some(&dec!(0.0499)));
+    }
+
+    #[test]
+    fn flows_count_only_transfers_recorded_after_the_previous_snapshot() {
+        let transfers = vec![
+            transfer(TransferKind::Deposit, "USDT", dec!(500), 0),
+            transfer(TransferKind::Deposit, "BTC", dec!(1), 20),
+            transfer(TransferKind::Withdrawal, "USDT", dec!(200), 30),
+        ];
+
+        assert_eq!(net_flows(&transfers, None).get("USDT"), Some(&dec!(300)));
+        let flows = net_flows(&transfers, Some(at(15)));
+        assert_eq!(flows.get("USDT"), Some(&dec!(-200)));
+        assert_eq!(flows.get("BTC"), Some(&dec!(1)));
+        assert!(net_flows(&transfers, Some(at(30))).is_empty());
+    }
+
+    #[test]
+    fn drift_lists_only_assets_the_ledgers_do_not_account_for() {
+        let exchange_totals = BTreeMap::from([
+            ("USDT".to_string(), dec!(6496.5)),
+            ("BTC".to_string(), dec!(0.0499)),
+            ("ETH".to_string(), dec!(2)),
+        ]);
+        let ledger_totals = BTreeMap::from([
+            ("USDT".to_string(), dec!(6500)),
+            ("BTC".to_string(), dec!(0.0499)),
+            ("SOL".to_string(), dec!(10)),
+        ]);
+
+        let drift = ledger_drift(&exchange_totals, &ledger_totals);
+        assert_eq!(drift.iter().map(|drift| (drift.asset.as_str(), drift.drift())).collect::<Vec<_>>(), vec![
+            ("ETH", dec!(2)),
+            ("SOL", dec!(-10)),
+            ("USDT", dec!(-3.5)),
+        ]);
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use worker::console_log;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+use crate::clients::trading::Exchange;
+use crate::dto::alert::{
+    AlertDto, CreateAlertRequest, GetNotificationsRequest, GetNotificationsResponse, ListAlertsResponse,
+    MarkNotificationsReadRequest, MarkNotificationsReadResponse, NotificationDto,
+};
+use crate::entity::alert::{AlertCondition, AlertRule, AlertStatus, Notification};
+use crate::entity::market_data::{CandleInterval, Instrument, InstrumentKind};
+use crate::entity::trading::{MarketQuote, TradingOrder};
+use crate::entity::webhook::WebhookEvent;
+use crate::repo::alert::AlertRepository;
+use crate::repo::notification::NotificationRepository;
+use crate::repo::order::OrderRepository;
+use crate::service::candle::CandleService;
+use crate::service::trading::TradingService;
+use crate::service::webhook::WebhookService;
+
+/// Window a percent change alert measures over when a request doesn't give one
+const DEFAULT_WINDOW_MINUTES: i64 = 60;
+
+/// Longest window a percent change alert may measure over
+const MAX_WINDOW_MINUTES: i64 = 24 * 60;
+
+/// Most alerts one user may keep
+const MAX_ALERTS_PER_USER: usize = 50;
+
+/// Notifications returned when a request doesn't give a limit
+const DEFAULT_NOTIFICATIONS_LIMIT: u32 = 50;
+
+/// Most notifications a single request may return
+const MAX_NOTIFICATIONS_LIMIT: u32 = 200;
+
+/// What one evaluation of a rule found
+struct Check {
+    /// Value observed for the condition, e.g. the price or spread percentage
+    value: Option<Decimal>,
+    /// Whether the condition holds
+    met: bool,
+    /// What to report if the rule fires: the market condition, or each order that filled
+    events: Vec<AlertEvent>,
+}
+
+/// One thing to tell the user about
+struct AlertEvent {
+    message: String,
+    data: Value,
+}
+
+/// Users' alert rules and their in-app notification feed.
+///
+/// The alert evaluation job checks every active rule each minute against
+/// the exchanges' quotes, the one-minute candles and the user's orders. A
+/// rule that fires posts to the user's feed and publishes an alert.triggered
+/// event to the user's webhook endpoints. Market rules fire when their
+/// condition starts to hold: a one-off rule is then done, and a repeating
+/// one re-arms once the condition clears.
+#[derive(Clone)]
+pub struct AlertService {
+    alert_repository: AlertRepository,
+    notification_repository: NotificationRepository,
+    order_repository: OrderRepository,
+    candle_service: CandleService,
+    webhook_service: WebhookService,
+}
+
+impl AlertService {
+    pub fn new(
+        alert_repository: AlertRepository,
+        notification_repository: NotificationRepository,
+        order_repository: OrderRepository,
+        candle_service: CandleService,
+        webhook_service: WebhookService,
+    ) -> Self {
+        Self {
+            alert_repository,
+            notification_repository,
+            order_repository,
+            candle_service,
+            webhook_service,
+        }
+    }
+
+    /// Validate and record a new alert rule
+    pub async fn create_alert(&self, user_id: &str, request: CreateAlertRequest) -> Result<AlertDto, String> {
+        console_log!("ALERT SERVICE: Creating {} alert for {}", request.condition, user_id);
+
+        let condition = AlertCondition::parse(&request.condition).ok_or_else(|| format!(
+            "Unsupported condition: {} (expected PRICE_ABOVE, PRICE_BELOW, PERCENT_CHANGE, SPREAD_ABOVE or ORDER_FILLED)",
+            request.condition,
+        ))?;
+        if let Some(exchange) = &request.exchange {
+            if Exchange::parse(exchange).is_none() {
+                return Err(format!("Unsupported exchange: {}", exchange));
+            }
+        }
+
+        let (threshold, window_minutes) = if condition.is_market() {
+            if request.exchange.is_none() || request.base.is_none() || request.quote.is_none() {
+                return Err(format!("exchange, base and quote are required for {} alerts", condition.as_str()));
+            }
+            let threshold = request.threshold
+                .ok_or_else(|| format!("threshold is required for {} alerts", condition.as_str()))?;
+            if threshold <= Decimal::ZERO {
+                return Err("threshold must be positive".to_string());
+            }
+            let window_minutes = match condition {
+                AlertCondition::PercentChange => {
+                    let window = request.window_minutes.unwrap_or(DEFAULT_WINDOW_MINUTES);
+                    if !(1..=MAX_WINDOW_MINUTES).contains(&window) {
+                        return Err(format!("window_minutes must be between 1 and {}", MAX_WINDOW_MINUTES));
+                    }
+                    Some(window)
+                }
+                _ => None,
+            };
+            (Some(threshold), window_minutes)
+        } else {
+            (None, None)
+        };
+
+        let existing = self.alert_repository.find_user_rules(user_id).await?;
+        if existing.len() >= MAX_ALERTS_PER_USER {
+            return Err(format!("At most {} alerts may be set up", MAX_ALERTS_PER_USER));
+        }
+
+        let now = Utc::now();
+        let rule = AlertRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: request.name,
+            condition,
+            exchange: request.exchange.map(|exchange| exchange.to_lowercase()),
+            base: request.base.map(|base| base.to_uppercase()),
+            quote: request.quote.map(|quote| quote.to_uppercase()),
+            threshold,
+            window_minutes,
+            repeat: request.repeat.unwrap_or(false),
+            status: AlertStatus::Active,
+            armed: true,
+            last_value: None,
+            last_checked_at: None,
+            triggered_at: None,
+            trigger_count: 0,
+            created_at: now,
+            updated_at: now,
+        };
+        self.alert_repository.save_rule(&rule).await?;
+
+        console_log!("ALERT SERVICE: Created alert {}: {}", rule.id, rule.describe());
+        Ok(convert_rule_to_dto(&rule))
+    }
+
+    pub async fn list_alerts(&self, user_id: &str) -> Result<ListAlertsResponse, String> {
+        let rules = self.alert_repository.find_user_rules(user_id).await?;
+        console_log!("ALERT SERVICE: Found {} alerts for {}", rules.len(), user_id);
+        Ok(ListAlertsResponse {
+            alerts: rules.iter().map(convert_rule_to_dto).collect(),
+        })
+    }
+
+    /// Switch one of the user's alerts on or off; switching it on re-arms it.
+    /// Returns None if the user has no such alert.
+    pub async fn set_alert_status(&self, user_id: &str, alert_id: &str, status: AlertStatus) -> Result<Option<AlertDto>, String> {
+        let Some(mut rule) = self.find_alert(user_id, alert_id).await? else {
+            return Ok(None);
+        };
+
+        let now = Utc::now();
+        rule.status = status;
+        rule.armed = status == AlertStatus::Active;
+        if status == AlertStatus::Active {
+            // Fills from while the alert was off aren't reported
+            rule.last_checked_at = Some(now);
+        }
+        rule.updated_at = now;
+        self.alert_repository.update_state(&rule).await?;
+
+        console_log!("ALERT SERVICE: Alert {} is now {}", alert_id, status.as_str());
+        Ok(Some(convert_rule_to_dto(&rule)))
+    }
+
+    /// Delete one of the user's alerts. Returns false if the user has no such alert.
+    pub async fn delete_alert(&self, user_id: &str, alert_id: &str) -> Result<bool, String> {
+        if self.find_alert(user_id, alert_id).await?.is_none() {
+            return Ok(false);
+        }
+        self.alert_repository.delete_rule(alert_id).await?;
+        console_log!("ALERT SERVICE: Deleted alert {}", alert_id);
+        Ok(true)
+    }
+
+    pub async fn get_notifications(&self, user_id: &str, request: GetNotificationsRequest) -> Result<GetNotificationsResponse, String> {
+        let limit = request.limit.unwrap_or(DEFAULT_NOTIFICATIONS_LIMIT).clamp(1, MAX_NOTIFICATIONS_LIMIT);
+        let notifications = self.notification_repository
+            .find_notifications(user_id, request.unread_only.unwrap_or(false), limit)
+            .await?;
+        let unread = self.notification_repository.count_unread(user_id).await?;
+
+        Ok(GetNotificationsResponse {
+            notifications: notifications.iter().map(convert_notification_to_dto).collect(),
+            unread,
+        })
+    }
+
+    pub async fn mark_notifications_read(&self, user_id: &str, request: MarkNotificationsReadRequest) -> Result<MarkNotificationsReadResponse, String> {
+        if request.notification_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+            return Err("notification_ids must not be empty; omit it to mark every notification read".to_string());
+        }
+        self.notification_repository.mark_read(user_id, request.notification_ids.as_deref()).await?;
+        Ok(MarkNotificationsReadResponse {
+            unread: self.notification_repository.count_unread(user_id).await?,
+        })
+    }
+
+    /// Evaluate every active alert rule, notifying the owners of those that
+    /// fire. Returns the number of rules checked and the number that fired.
+    pub async fn evaluate_alerts(&self, trading_service: &TradingService) -> Result<(usize, usize), String> {
+        let rules = self.alert_repository.find_active_rules().await?;
+        console_log!("ALERT SERVICE: Evaluating {} alerts", rules.len());
+
+        // Rules on the same instrument share one quote and reference price per run
+        let mut quotes: HashMap<String, Option<MarketQuote>> = HashMap::new();
+        let mut references: HashMap<(String, i64), Option<Decimal>> = HashMap::new();
+        let mut checked = 0;
+        let mut fired = 0;
+
+        for mut rule in rules {
+            let now = Utc::now();
+            let check = match rule.condition {
+                AlertCondition::OrderFilled => self.check_fills(&rule, now).await,
+                _ => self.check_market(&rule, trading_service, &mut quotes, &mut references, now).await,
+            };
+            let Some(check) = check else {
+                continue;
+            };
+            checked += 1;
+
+            rule.last_checked_at = Some(now);
+            rule.updated_at = now;
+            if check.value.is_some() {
+                rule.last_value = check.value;
+            }
+            if check.met && rule.armed {
+                fired += 1;
+                rule.triggered_at = Some(now);
+                rule.trigger_count += 1;
+                if !rule.repeat {
+                    rule.status = AlertStatus::Triggered;
+                    rule.armed = false;
+                } else if rule.condition.is_market() {
+                    // Fills are separate events, so only market rules wait for the condition to clear
+                    rule.armed = false;
+                }
+                for event in check.events {
+                    self.notify(&rule, event, now).await;
+                }
+            } else if !check.met && rule.repeat {
+                rule.armed = true;
+            }
+
+            if let Err(e) = self.alert_repository.update_state(&rule).await {
+                console_log!("ALERT SERVICE: Failed to record evaluation of {}: {}", rule.id, e);
+            }
+        }
+
+        console_log!("ALERT SERVICE: Checked {} alerts, {} fired", checked, fired);
+        Ok((checked, fired))
+    }
+
+    async fn find_alert(&self, user_id: &str, alert_id: &str) -> Result<Option<AlertRule>, String> {
+        let rule = self.alert_repository.find_rule(alert_id).await?;
+        Ok(rule.filter(|rule| rule.user_id == user_id))
+    }
+
+    /// Check a market rule against its instrument's quote. Returns None if
+    /// the quote or reference price couldn't be had, leaving the rule as it was.
+    async fn check_market(
+        &self,
+        rule: &AlertRule,
+        trading_service: &TradingService,
+        quotes: &mut HashMap<String, Option<MarketQuote>>,
+        references: &mut HashMap<(String, i64), Option<Decimal>>,
+        now: DateTime<Utc>,
+    ) -> Option<Check> {
+        let (Some(exchange), Some(base), Some(quote), Some(threshold)) =
+            (&rule.exchange, &rule.base, &rule.quote, rule.threshold) else {
+            return None;
+        };
+        let instrument = Instrument::new(base.clone(), quote.clone(), exchange.clone(), InstrumentKind::Spot);
+
+        if !quotes.contains_key(&instrument.id) {
+            let fetched = match trading_service.get_market_quote(exchange, base, quote).await {
+                Ok(quote) => Some(quote),
+                Err(e) => {
+                    console_log!("ALERT SERVICE: Failed to get quote for {}: {}", instrument.id, e.error);
+                    None
+                }
+            };
+            quotes.insert(instrument.id.clone(), fetched);
+        }
+        let market_quote = quotes.get(&instrument.id)?.as_ref()?;
+        let mid = market_quote.get_mid_price();
+        let label = format!("{} on {}", instrument.symbol, exchange);
+
+        let (value, met, message) = match rule.condition {
+            AlertCondition::PriceAbove => (mid, mid >= threshold, format!("{} is at {}, above {}", label, mid, threshold)),
+            AlertCondition::PriceBelow => (mid, mid <= threshold, format!("{} is at {}, below {}", label, mid, threshold)),
+            AlertCondition::SpreadAbove => {
+                let spread = market_quote.get_spread_percentage().round_dp(4);
+                (spread, spread >= threshold, format!("{} spread is {}%, above {}%", label, spread, threshold))
+            }
+            AlertCondition::PercentChange => {
+                let window = rule.window_minutes.unwrap_or(DEFAULT_WINDOW_MINUTES);
+                let key = (instrument.id.clone(), window);
+                if !references.contains_key(&key) {
+                    let reference = self.reference_price(&instrument, window, now).await;
+                    references.insert(key.clone(), reference);
+                }
+                let reference = (*references.get(&key)?)?;
+                let change = ((mid - reference) / reference * Decimal::ONE_HUNDRED).round_dp(4);
+                let direction = if change >= Decimal::ZERO { "up" } else { "down" };
+                (change, change.abs() >= threshold, format!("{} is {} {}% in {} minutes, from {} to {}",
+                    label, direction, change.abs(), window, reference, mid))
+            }
+            AlertCondition::OrderFilled => return None,
+        };
+
+        let data = json!({
+            "exchange": exchange,
+            "base": base,
+            "quote": quote,
+            "bid": market_quote.bid_price,
+            "ask": market_quote.ask_price,
+            "mid": mid,
+            "value": value,
+            "threshold": threshold,
+            "window_minutes": rule.window_minutes,
+        });
+        Some(Check {
+            value: Some(value),
+            met,
+            events: vec![AlertEvent { message, data }],
+        })
+    }
+
+    /// Opening price of the one-minute candle at the start of a percent change window
+    async fn reference_price(&self, instrument: &Instrument, window_minutes: i64, now: DateTime<Utc>) -> Option<Decimal> {
+        let interval = CandleInterval::OneMinute;
+        let start = interval.bucket_start(now - Duration::minutes(window_minutes));
+        match self.candle_service.load_candles(instrument, interval, start, start + interval.duration()).await {
+            Ok(loaded) => loaded.candles.first().map(|candle| candle.open).filter(|open| *open > Decimal::ZERO),
+            Err(e) => {
+                console_log!("ALERT SERVICE: Failed to load reference candle for {}: {}", instrument.id, e);
+                None
+            }
+        }
+    }
+
+    /// Check an order filled rule for the user's orders that filled since it last looked
+    async fn check_fills(&self, rule: &AlertRule, now: DateTime<Utc>) -> Option<Check> {
+        let since = rule.last_checked_at.unwrap_or(rule.created_at);
+        let orders = match self.order_repository.find_filled_orders(&rule.user_id, since).await {
+            Ok(orders) => orders,
+            Err(e) => {
+                console_log!("ALERT SERVICE: Failed to load filled orders for {}: {}", rule.user_id, e);
+                return None;
+            }
+        };
+
+        let events: Vec<AlertEvent> = orders.iter()
+            .filter(|order| order.updated_at <= now)
+            .filter(|order| fill_matches(rule, order))
+            .map(|order| AlertEvent {
+                message: format!("{} {} {} on {} filled at {}",
+                    order.side.as_str(), order.filled_quantity.normalize(), order.instrument.symbol,
+                    order.instrument.exchange, order.average_price.or(order.price).unwrap_or_default()),
+                data: json!({
+                    "order_id": order.id,
+                    "client_order_id": order.client_order_id,
+                    "exchange": order.instrument.exchange,
+                    "symbol": order.instrument.symbol,
+                    "side": order.side.as_str(),
+                    "order_type": order.order_type.as_str(),
+                    "quantity": order.quantity,
+                    "filled_quantity": order.filled_quantity,
+                    "average_price": order.average_price,
+                }),
+            })
+            .collect();
+        Some(Check {
+            value: None,
+            met: !events.is_empty(),
+            events,
+        })
+    }
+
+    /// Post a fired alert to the user's feed and webhook endpoints. Failures
+    /// are logged; the rule still counts as fired.
+    async fn notify(&self, rule: &AlertRule, event: AlertEvent, now: DateTime<Utc>) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let data = json!({
+            "notification_id": id,
+            "alert": {
+                "alert_id": rule.id,
+                "name": rule.name,
+                "condition": rule.condition.as_str(),
+                "description": rule.describe(),
+            },
+            "message": event.message,
+            "data": event.data,
+        });
+        let notification = Notification {
+            id: id.clone(),
+            user_id: rule.user_id.clone(),
+            alert_id: Some(rule.id.clone()),
+            title: rule.name.clone().unwrap_or_else(|| rule.describe()),
+            message: event.message,
+            data,
+            read_at: None,
+            created_at: now,
+        };
+        if let Err(e) = self.notification_repository.save_notification(&notification).await {
+            console_log!("ALERT SERVICE: Failed to save notification for {}: {}", rule.id, e);
+        }
+
+        self.webhook_service
+            .publish(&rule.user_id, WebhookEvent::AlertTriggered, notification.data)
+            .await;
+    }
+}
+
+/// Whether a filled order is on the exchange and assets an order filled rule narrows to
+fn fill_matches(rule: &AlertRule, order: &TradingOrder) -> bool {
+    let matches = |filter: &Option<String>, value: &str| filter.as_ref().is_none_or(|filter| filter.eq_ignore_ascii_case(value));
+    matches(&rule.exchange, &order.instrument.exchange)
+        && matches(&rule.base, &order.instrument.base_asset)
+        && matches(&rule.quote, &order.instrument.quote_asset)
+}
+
+pub fn convert_rule_to_dto(rule: &AlertRule) -> AlertDto {
+    AlertDto {
+        alert_id: rule.id.clone(),
+        name: rule.name.clone(),
+        condition: rule.condition.as_str().to_string(),
+        description: rule.describe(),
+        exchange: rule.exchange.clone(),
+        base: rule.base.clone(),
+        quote: rule.quote.clone(),
+        threshold: rule.threshold,
+        window_minutes: rule.window_minutes,
+        repeat: rule.repeat,
+        status: rule.status.as_str().to_string(),
+        armed: rule.armed,
+        last_value: rule.last_value,
+        last_checked_at: rule.last_checked_at,
+        triggered_at: rule.triggered_at,
+        trigger_count: rule.trigger_count,
+        created_at: rule.created_at,
+        updated_at: rule.updated_at,
+    }
+}
+
+pub fn convert_notification_to_dto(notification: &Notification) -> NotificationDto {
+    NotificationDto {
+        notification_id: notification.id.clone(),
+        alert_id: notification.alert_id.clone(),
+        title: notification.title.clone(),
+        message: notification.message.clone(),
+        data: notification.data.clone(),
+        read: notification.read_at.is_some(),
+        read_at: notification.read_at,
+        created_at: notification.created_at,
+    }
+}
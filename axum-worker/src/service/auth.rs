@@ -1,4 +1,10 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// How long a token is valid for
+const TOKEN_LIFETIME_SECONDS: usize = 86400;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -8,7 +14,6 @@ pub struct Claims {
 
 #[derive(Clone)]
 pub struct AuthenticationService {
-    #[allow(dead_code)]
     secret: String,
 }
 
@@ -19,16 +24,102 @@ impl AuthenticationService {
 
     pub fn generate_token_for(&self, username: String) -> Result<String, String> {
         // Use js_sys::Date for WASM compatibility
-        let now = js_sys::Date::now() / 1000.0; // Convert to seconds
-        let expiration = (now + 86400.0) as usize; // 24 hours
+        let now = (js_sys::Date::now() / 1000.0) as usize;
+        self.sign(&Claims { sub: username, exp: now + TOKEN_LIFETIME_SECONDS })
+    }
+
+    /// Verify a token issued by `generate_token_for` and return its subject
+    pub fn verify_token(&self, token: &str) -> Result<String, String> {
+        let now = (js_sys::Date::now() / 1000.0) as usize;
+        self.verify_at(token, now)
+    }
 
-        let claims = Claims {
-            sub: username,
-            exp: expiration,
-        };
+    /// HS256 JWT of the claims, signed with the secret
+    fn sign(&self, claims: &Claims) -> Result<String, String> {
+        let header = serde_json::json!({"alg": "HS256", "typ": "JWT"});
+        let header = serde_json::to_vec(&header).map_err(|e| e.to_string())?;
+        let payload = serde_json::to_vec(claims).map_err(|e| e.to_string())?;
+        let message = format!("{}.{}", URL_SAFE_NO_PAD.encode(header), URL_SAFE_NO_PAD.encode(payload));
 
-        // For now, create a simple token
-        // TODO: Implement proper JWT signing
-        Ok(format!("token_for_{}_exp_{}", claims.sub, claims.exp))
+        let signature = self.mac(&message).finalize().into_bytes();
+        Ok(format!("{}.{}", message, URL_SAFE_NO_PAD.encode(signature)))
     }
-}
\ No newline at end of file
+
+    fn verify_at(&self, token: &str, now: usize) -> Result<String, String> {
+        let (message, signature) = token
+            .rsplit_once('.')
+            .ok_or_else(|| "Invalid token format".to_string())?;
+        let (_, payload) = message
+            .split_once('.')
+            .ok_or_else(|| "Invalid token format".to_string())?;
+
+        // Checked before the claims are read, and in constant time
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| "Invalid token signature".to_string())?;
+        self.mac(message)
+            .verify_slice(&signature)
+            .map_err(|_| "Invalid token signature".to_string())?;
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| "Invalid token payload".to_string())?;
+        let claims: Claims = serde_json::from_slice(&payload)
+            .map_err(|_| "Invalid token claims".to_string())?;
+        if claims.exp < now {
+            return Err("Token expired".to_string());
+        }
+
+        Ok(claims.sub)
+    }
+
+    fn mac(&self, message: &str) -> Hmac<Sha256> {
+        // HMAC takes keys of any length, so this can't fail
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(message.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: usize = 1_760_000_000;
+
+    fn token(service: &AuthenticationService, sub: &str, exp: usize) -> String {
+        service.sign(&Claims { sub: sub.to_string(), exp }).unwrap()
+    }
+
+    #[test]
+    fn verifies_its_own_tokens() {
+        let service = AuthenticationService::new("secret".to_string());
+        let token = token(&service, "alice", NOW + 60);
+        assert_eq!(service.verify_at(&token, NOW), Ok("alice".to_string()));
+    }
+
+    #[test]
+    fn rejects_tokens_signed_with_another_secret() {
+        let service = AuthenticationService::new("secret".to_string());
+        let forged = token(&AuthenticationService::new("guess".to_string()), "alice", NOW + 60);
+        assert!(service.verify_at(&forged, NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_tokens_with_altered_claims() {
+        let service = AuthenticationService::new("secret".to_string());
+        let token = token(&service, "alice", NOW + 60);
+        let (header, rest) = token.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let payload = URL_SAFE_NO_PAD.encode(br#"{"sub":"admin","exp":1760000060}"#);
+        let altered = format!("{}.{}.{}", header, payload, signature);
+        assert!(service.verify_at(&altered, NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_expired_and_unsigned_tokens() {
+        let service = AuthenticationService::new("secret".to_string());
+        assert_eq!(service.verify_at(&token(&service, "alice", NOW - 1), NOW), Err("Token expired".to_string()));
+        assert!(service.verify_at("token_for_alice_exp_9999999999", NOW).is_err());
+    }
+}
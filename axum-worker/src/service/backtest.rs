@@ -0,0 +1,274 @@
+use worker::console_log;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+
+use crate::clients::trading::{Exchange, TradingClient};
+use crate::dto::backtest::{
+    BacktestEquityPointDto, BacktestFillDto, BacktestSummaryDto, BacktestTradeDto, RunBacktestRequest, RunBacktestResponse,
+};
+use crate::dto::market_data::InstrumentDto;
+use crate::entity::market_data::{CandleInterval, Instrument, InstrumentKind, MarketDataEvent};
+use crate::repo::market_data_archive::MarketDataArchive;
+use crate::service::backtest_engine::{run_backtest, BacktestConfig, BacktestReport, BacktestTrade};
+use crate::service::candle::CandleService;
+use crate::service::candle_aggregator::{bucket_count, fill_gaps};
+use crate::service::fill_model::FillModel;
+use crate::service::strategy::build_strategy;
+
+/// Candle interval used when a request doesn't give one
+const DEFAULT_INTERVAL: CandleInterval = CandleInterval::OneHour;
+
+/// Quote asset a backtest starts with when a request doesn't say
+const DEFAULT_INITIAL_CASH: i64 = 10_000;
+
+/// Slippage applied to market orders when a request doesn't say
+const DEFAULT_SLIPPAGE_BPS: i64 = 5;
+
+/// Most candles one backtest may replay
+const MAX_BACKTEST_CANDLES: i64 = 10_000;
+
+/// Longest span of recorded trades one backtest may replay, as for replays
+const MAX_TRADE_BACKTEST_HOURS: i64 = 24;
+
+/// Most recorded trades one backtest may replay
+const MAX_BACKTEST_TRADES: usize = 500_000;
+
+/// Where a backtest's market data comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BacktestSource {
+    /// Stored candles, backfilled from the exchange's klines
+    Candles,
+    /// Trades recorded from the live stream to the market data archive
+    Trades,
+}
+
+impl BacktestSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BacktestSource::Candles => "candles",
+            BacktestSource::Trades => "trades",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "candles" => Some(BacktestSource::Candles),
+            "trades" => Some(BacktestSource::Trades),
+            _ => None,
+        }
+    }
+}
+
+/// Runs strategies over stored market data.
+///
+/// Candles come from the candle store, backfilled from the exchange like
+/// the candles endpoint. Trades come from the recordings of the live stream
+/// in R2. Fills are simulated with the exchange's taker fee and a fixed
+/// slippage unless the request sets its own.
+#[derive(Clone)]
+pub struct BacktestService {
+    candle_service: CandleService,
+}
+
+impl BacktestService {
+    pub fn new(candle_service: CandleService) -> Self {
+        Self { candle_service }
+    }
+
+    /// Backtest a strategy. `archive` holds the recorded trades, and is None
+    /// when recording isn't configured.
+    pub async fn run_backtest(
+        &self,
+        request: RunBacktestRequest,
+        archive: Option<MarketDataArchive>,
+    ) -> Result<RunBacktestResponse, String> {
+        console_log!("BACKTEST SERVICE: Running {} on {}/{} on {}",
+            request.strategy, request.base, request.quote, request.exchange);
+
+        let exchange = Exchange::parse(&request.exchange)
+            .ok_or_else(|| format!("Unsupported exchange: {}", request.exchange))?;
+        let source = match &request.source {
+            Some(source) => BacktestSource::parse(source)
+                .ok_or_else(|| format!("Unsupported source: {} (expected candles or trades)", source))?,
+            None => BacktestSource::Candles,
+        };
+        let interval = match &request.interval {
+            Some(interval) => CandleInterval::parse(interval)
+                .ok_or_else(|| format!("Unsupported interval: {} (expected 1m, 5m, 15m, 1h, 4h or 1d)", interval))?,
+            None => DEFAULT_INTERVAL,
+        };
+        let mut strategy = build_strategy(&request.strategy, request.parameters.as_ref().unwrap_or(&serde_json::Value::Null))?;
+
+        let initial_cash = request.initial_cash.unwrap_or(Decimal::new(DEFAULT_INITIAL_CASH, 0));
+        if initial_cash <= Decimal::ZERO {
+            return Err("initial_cash must be positive".to_string());
+        }
+        let fee_rate = request.fee_rate.unwrap_or_else(|| TradingClient::new(exchange, None, None).taker_fee_rate());
+        if fee_rate < Decimal::ZERO || fee_rate >= Decimal::ONE {
+            return Err("fee_rate must be at least 0 and below 1".to_string());
+        }
+        let slippage_bps = request.slippage_bps.unwrap_or(Decimal::new(DEFAULT_SLIPPAGE_BPS, 0));
+        if slippage_bps < Decimal::ZERO || slippage_bps >= Decimal::new(10_000, 0) {
+            return Err("slippage_bps must be at least 0 and below 10000".to_string());
+        }
+
+        let end_time = request.end_time.unwrap_or_else(Utc::now).min(Utc::now());
+        if request.start_time >= end_time {
+            return Err("start_time must be before end_time and in the past".to_string());
+        }
+        let instrument = Instrument::new(request.base, request.quote, request.exchange.to_lowercase(), InstrumentKind::Spot);
+
+        let events = match source {
+            BacktestSource::Candles => self.load_candles(&instrument, interval, request.start_time, end_time).await?,
+            BacktestSource::Trades => {
+                let archive = archive.ok_or("Market data recording is not configured, so there are no trades to replay")?;
+                load_trades(&archive, &instrument, request.start_time, end_time).await?
+            }
+        };
+        if events.is_empty() {
+            return Err(format!("No {} stored for {} between {} and {}",
+                source.as_str(), instrument.id, request.start_time, end_time));
+        }
+
+        let config = BacktestConfig {
+            initial_cash,
+            fill_model: FillModel::new(fee_rate, slippage_bps),
+            interval,
+        };
+        let report = run_backtest(strategy.as_mut(), &events, &config);
+        console_log!("BACKTEST SERVICE: {} replayed {} events into {} fills, net PnL {}",
+            strategy.name(), report.events, report.fills.len(), report.net_pnl);
+
+        Ok(RunBacktestResponse {
+            instrument: InstrumentDto::from(&instrument),
+            strategy: strategy.name().to_string(),
+            parameters: strategy.parameters(),
+            source: source.as_str().to_string(),
+            interval: interval.as_str().to_string(),
+            start_time: request.start_time,
+            end_time,
+            events: report.events,
+            fee_rate,
+            slippage_bps,
+            summary: convert_report_to_summary(&report),
+            trades: report.trades.iter().map(convert_trade_to_dto).collect(),
+            fills: report.fills.iter()
+                .map(|fill| BacktestFillDto {
+                    timestamp: fill.timestamp,
+                    side: fill.side.as_str().to_string(),
+                    quantity: fill.quantity,
+                    price: fill.price.round_dp(8),
+                    fee: fill.fee.round_dp(8),
+                })
+                .collect(),
+            equity_curve: report.equity_curve.iter()
+                .map(|point| BacktestEquityPointDto {
+                    timestamp: point.timestamp,
+                    equity: point.value.round_dp(8),
+                    drawdown_percentage: (point.drawdown * Decimal::ONE_HUNDRED).round_dp(2),
+                })
+                .collect(),
+        })
+    }
+
+    /// Finished candles opening within `[start, end)`, with flat candles
+    /// filling buckets that had no trades. Fails if any bucket could be
+    /// neither loaded nor backfilled.
+    async fn load_candles(
+        &self,
+        instrument: &Instrument,
+        interval: CandleInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<MarketDataEvent>, String> {
+        let start = interval.bucket_start(start);
+        // The bucket in progress isn't replayed, since its candle isn't final
+        let end = interval.bucket_start(end);
+        let buckets = bucket_count(interval, start, end);
+        if buckets > MAX_BACKTEST_CANDLES {
+            return Err(format!("Requested range spans {} candles; a backtest may replay at most {}", buckets, MAX_BACKTEST_CANDLES));
+        }
+        if buckets <= 0 {
+            return Ok(Vec::new());
+        }
+
+        let loaded = self.candle_service.load_candles(instrument, interval, start, end).await?;
+        console_log!("BACKTEST SERVICE: Loaded {} candles for {} ({} backfilled)", loaded.candles.len(), instrument.id, loaded.backfilled);
+        // Running over made-up flat candles would give made-up results
+        if let Some(missing_from) = loaded.missing_from {
+            return Err(format!(
+                "Candles from {} on could not be loaded; backfill fetches a limited number per request, \
+                 so run the backtest again to load more, or narrow the range",
+                missing_from.to_rfc3339(),
+            ));
+        }
+        Ok(fill_gaps(&loaded.candles, start, end).into_iter().map(MarketDataEvent::Candle).collect())
+    }
+}
+
+/// Trades recorded for an instrument within `[start, end)`, in exchange time order
+async fn load_trades(
+    archive: &MarketDataArchive,
+    instrument: &Instrument,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<MarketDataEvent>, String> {
+    if end - start > Duration::hours(MAX_TRADE_BACKTEST_HOURS) {
+        return Err(format!("A backtest over trades may span at most {} hours", MAX_TRADE_BACKTEST_HOURS));
+    }
+
+    let mut trades = Vec::new();
+    for key in archive.list_chunks(&instrument.id, start, end).await? {
+        let events = match archive.load_chunk(&key).await {
+            Ok(events) => events,
+            // Skip a damaged chunk rather than failing the backtest
+            Err(e) => {
+                console_log!("BACKTEST SERVICE: Skipping chunk: {}", e);
+                continue;
+            }
+        };
+        trades.extend(events.iter()
+            .filter(|event| event.timestamp >= start && event.timestamp < end)
+            .filter_map(|event| match event.to_event() {
+                Some(MarketDataEvent::Trade(trade)) => Some(trade),
+                _ => None,
+            }));
+        if trades.len() > MAX_BACKTEST_TRADES {
+            return Err(format!("Requested range holds more than {} trades; narrow it", MAX_BACKTEST_TRADES));
+        }
+    }
+
+    trades.sort_by_key(|trade| trade.exchange_timestamp);
+    console_log!("BACKTEST SERVICE: Loaded {} recorded trades for {}", trades.len(), instrument.id);
+    Ok(trades.into_iter().map(MarketDataEvent::Trade).collect())
+}
+
+fn convert_report_to_summary(report: &BacktestReport) -> BacktestSummaryDto {
+    let closed = report.trades.iter().filter(|trade| trade.exit_time.is_some());
+    BacktestSummaryDto {
+        initial_equity: report.initial_equity,
+        final_equity: report.final_equity.round_dp(8),
+        net_pnl: report.net_pnl.round_dp(8),
+        return_percentage: (report.return_fraction * Decimal::ONE_HUNDRED).round_dp(4),
+        fees_paid: report.fees_paid.round_dp(8),
+        sharpe_ratio: report.sharpe_ratio.map(|ratio| ratio.round_dp(4)),
+        max_drawdown_percentage: (report.max_drawdown * Decimal::ONE_HUNDRED).round_dp(2),
+        win_rate_percentage: report.win_rate.map(|rate| (rate * Decimal::ONE_HUNDRED).round_dp(2)),
+        total_trades: report.trades.len() as u32,
+        winning_trades: closed.clone().filter(|trade| trade.pnl > Decimal::ZERO).count() as u32,
+        losing_trades: closed.filter(|trade| trade.pnl <= Decimal::ZERO).count() as u32,
+    }
+}
+
+fn convert_trade_to_dto(trade: &BacktestTrade) -> BacktestTradeDto {
+    BacktestTradeDto {
+        entry_time: trade.entry_time,
+        exit_time: trade.exit_time,
+        quantity: trade.quantity,
+        entry_price: trade.entry_price.round_dp(8),
+        exit_price: trade.exit_price.map(|price| price.round_dp(8)),
+        fees: trade.fees.round_dp(8),
+        pnl: trade.pnl.round_dp(8),
+        return_percentage: (trade.return_fraction * Decimal::ONE_HUNDRED).round_dp(4),
+    }
+}
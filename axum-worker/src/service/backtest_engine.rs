@@ -0,0 +1,466 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+use crate::entity::market_data::{Candle, CandleInterval, MarketDataEvent, OrderBook, Trade};
+use crate::entity::trading::OrderSide;
+use crate::service::candle_aggregator::CandleAggregator;
+use crate::service::equity_curve::EquityPoint;
+use crate::service::fill_model::FillModel;
+use crate::service::strategy::{OrderIntent, Strategy, StrategyFill};
+use crate::service::strategy_account::{SimulatedOrder, StrategyAccount};
+
+/// Seconds in a year, for annualizing; crypto markets trade every day
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+/// How a backtest is run
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    /// Quote asset the strategy starts with
+    pub initial_cash: Decimal,
+    pub fill_model: FillModel,
+    /// Interval equity is sampled at, and the candles built from trades for
+    /// `Strategy::on_candle`
+    pub interval: CandleInterval,
+}
+
+/// Round trip from a flat position back to flat
+#[derive(Debug, Clone)]
+pub struct BacktestTrade {
+    pub entry_time: DateTime<Utc>,
+    /// None while the position is still open
+    pub exit_time: Option<DateTime<Utc>>,
+    /// Base asset bought over the round trip
+    pub quantity: Decimal,
+    /// Average price of the buys
+    pub entry_price: Decimal,
+    /// Average price of the sells, if any
+    pub exit_price: Option<Decimal>,
+    pub fees: Decimal,
+    /// Net of fees; an open trade's remaining position is marked at the last price
+    pub pnl: Decimal,
+    /// `pnl` over the cost of the buys
+    pub return_fraction: Decimal,
+}
+
+/// Result of a backtest
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    pub initial_equity: Decimal,
+    pub final_equity: Decimal,
+    pub net_pnl: Decimal,
+    pub return_fraction: Decimal,
+    pub fees_paid: Decimal,
+    /// Annualized mean over standard deviation of the per-interval returns;
+    /// None with fewer than two returns or no variation
+    pub sharpe_ratio: Option<Decimal>,
+    pub max_drawdown: Decimal,
+    /// Fraction of closed trades with a positive PnL; None if none closed
+    pub win_rate: Option<Decimal>,
+    pub trades: Vec<BacktestTrade>,
+    pub fills: Vec<StrategyFill>,
+    pub equity_curve: Vec<EquityPoint>,
+    /// Market events replayed
+    pub events: u32,
+}
+
+/// Buys and sells since the position was last flat
+#[derive(Debug, Clone)]
+struct OpenTrade {
+    entry_time: DateTime<Utc>,
+    bought: Decimal,
+    cost: Decimal,
+    sold: Decimal,
+    proceeds: Decimal,
+    fees: Decimal,
+}
+
+/// Equity at the end of one sampling interval
+#[derive(Debug, Clone)]
+struct EquitySample {
+    bucket: DateTime<Utc>,
+    timestamp: DateTime<Utc>,
+    value: Decimal,
+}
+
+/// Replay `events`, ordered oldest first, through `strategy` and report how it did.
+///
+/// The strategy trades one instrument, long only, starting from cash. Its
+/// orders never fill on the event that produced them: market orders fill at
+/// the next event's price (a candle's open or a trade's price) plus slippage,
+/// and limit orders rest until a later candle's range or trade reaches them.
+/// Buys are cut down to what the cash covers and sells to the position held.
+/// Trades are also folded into candles of `config.interval` for the
+/// strategy's `on_candle`. `on_timer` isn't called.
+pub fn run_backtest(strategy: &mut dyn Strategy, events: &[MarketDataEvent], config: &BacktestConfig) -> BacktestReport {
+    let mut backtest = Backtest {
+        config: config.clone(),
+        account: StrategyAccount::new(config.initial_cash, config.fill_model),
+        fills: Vec::new(),
+        trades: Vec::new(),
+        open_trade: None,
+        samples: Vec::new(),
+        aggregator: None,
+    };
+
+    let mut replayed = 0;
+    for event in events {
+        match event {
+            MarketDataEvent::Candle(candle) => backtest.on_candle(strategy, candle),
+            MarketDataEvent::Trade(trade) => backtest.on_trade(strategy, trade),
+            MarketDataEvent::OrderBook(book) => backtest.on_book(strategy, book),
+        }
+        replayed += 1;
+    }
+
+    backtest.report(replayed)
+}
+
+struct Backtest {
+    config: BacktestConfig,
+    account: StrategyAccount,
+    fills: Vec<StrategyFill>,
+    trades: Vec<BacktestTrade>,
+    open_trade: Option<OpenTrade>,
+    samples: Vec<EquitySample>,
+    aggregator: Option<CandleAggregator>,
+}
+
+impl Backtest {
+    fn on_candle(&mut self, strategy: &mut dyn Strategy, candle: &Candle) {
+        self.start_at(candle.open_time, candle.open_time);
+        self.fill_market_orders(strategy, candle.open, candle.open_time);
+        self.fill_limit_orders(strategy, candle.low, candle.high, candle.open, candle.open_time);
+
+        self.account.last_price = Some(candle.close);
+        self.record_equity(candle.open_time, candle.close_time());
+        let intents = strategy.on_candle(&self.account.context(candle.close_time()), candle);
+        self.submit(intents);
+    }
+
+    fn on_trade(&mut self, strategy: &mut dyn Strategy, trade: &Trade) {
+        let traded_at = trade.exchange_timestamp;
+        let bucket = self.config.interval.bucket_start(traded_at);
+        self.start_at(bucket, traded_at);
+
+        // Candles built from earlier trades close before this trade is seen
+        let aggregator = self.aggregator.get_or_insert_with(|| {
+            CandleAggregator::new(trade.instrument.clone(), self.config.interval, Duration::zero())
+        });
+        let closed = aggregator.close_until(traded_at);
+        aggregator.push(trade);
+        for candle in closed {
+            let intents = strategy.on_candle(&self.account.context(candle.close_time()), &candle);
+            self.submit(intents);
+        }
+
+        self.fill_market_orders(strategy, trade.price, traded_at);
+        self.fill_limit_orders(strategy, trade.price, trade.price, trade.price, traded_at);
+
+        self.account.last_price = Some(trade.price);
+        self.record_equity(bucket, traded_at);
+        let intents = strategy.on_trade(&self.account.context(traded_at), trade);
+        self.submit(intents);
+    }
+
+    /// Books only inform the strategy; orders fill against candles and trades
+    fn on_book(&mut self, strategy: &mut dyn Strategy, book: &OrderBook) {
+        let intents = strategy.on_book(&self.account.context(book.exchange_timestamp), book);
+        self.submit(intents);
+    }
+
+    /// Record the starting equity just before the first event
+    fn start_at(&mut self, bucket: DateTime<Utc>, timestamp: DateTime<Utc>) {
+        if self.samples.is_empty() {
+            self.samples.push(EquitySample {
+                bucket: bucket - self.config.interval.duration(),
+                timestamp,
+                value: self.config.initial_cash,
+            });
+        }
+    }
+
+    fn submit(&mut self, intents: Vec<OrderIntent>) {
+        for intent in intents {
+            self.account.submit(intent);
+        }
+    }
+
+    fn fill_market_orders(&mut self, strategy: &mut dyn Strategy, price: Decimal, timestamp: DateTime<Utc>) {
+        for order in self.account.due_market_orders(price) {
+            self.execute(strategy, order, timestamp);
+        }
+    }
+
+    fn fill_limit_orders(
+        &mut self,
+        strategy: &mut dyn Strategy,
+        low: Decimal,
+        high: Decimal,
+        open: Decimal,
+        timestamp: DateTime<Utc>,
+    ) {
+        for order in self.account.due_limit_orders(low, high, open) {
+            self.execute(strategy, order, timestamp);
+        }
+    }
+
+    fn execute(&mut self, strategy: &mut dyn Strategy, order: SimulatedOrder, timestamp: DateTime<Utc>) {
+        let Some(fill) = self.account.execute(order, timestamp) else { return };
+
+        let notional = fill.quantity * fill.price;
+        let trade = self.open_trade.get_or_insert(OpenTrade {
+            entry_time: timestamp,
+            bought: Decimal::ZERO,
+            cost: Decimal::ZERO,
+            sold: Decimal::ZERO,
+            proceeds: Decimal::ZERO,
+            fees: Decimal::ZERO,
+        });
+        trade.fees += fill.fee;
+        match fill.side {
+            OrderSide::Buy => {
+                trade.bought += fill.quantity;
+                trade.cost += notional;
+            }
+            OrderSide::Sell => {
+                trade.sold += fill.quantity;
+                trade.proceeds += notional;
+            }
+        }
+        if self.account.position.is_zero() {
+            if let Some(trade) = self.open_trade.take() {
+                self.trades.push(finish_trade(trade, Some(timestamp), fill.price));
+            }
+        }
+
+        self.fills.push(fill.clone());
+        let intents = strategy.on_fill(&self.account.context(timestamp), &fill);
+        self.submit(intents);
+    }
+
+    /// Keep the latest equity of each sampling interval
+    fn record_equity(&mut self, bucket: DateTime<Utc>, timestamp: DateTime<Utc>) {
+        let sample = EquitySample { bucket, timestamp, value: self.account.equity() };
+        match self.samples.last_mut() {
+            Some(last) if last.bucket == bucket => *last = sample,
+            _ => self.samples.push(sample),
+        }
+    }
+
+    fn report(mut self, events: u32) -> BacktestReport {
+        if let (Some(trade), Some(price)) = (self.open_trade.take(), self.account.last_price) {
+            self.trades.push(finish_trade(trade, None, price));
+        }
+
+        let mut peak = Decimal::ZERO;
+        let mut max_drawdown = Decimal::ZERO;
+        let equity_curve: Vec<EquityPoint> = self.samples.iter()
+            .map(|sample| {
+                peak = peak.max(sample.value);
+                let drawdown = if peak > Decimal::ZERO { (peak - sample.value) / peak } else { Decimal::ZERO };
+                max_drawdown = max_drawdown.max(drawdown);
+                EquityPoint { timestamp: sample.timestamp, value: sample.value, drawdown }
+            })
+            .collect();
+
+        let closed: Vec<&BacktestTrade> = self.trades.iter().filter(|trade| trade.exit_time.is_some()).collect();
+        let win_rate = (!closed.is_empty()).then(|| {
+            let winners = closed.iter().filter(|trade| trade.pnl > Decimal::ZERO).count();
+            Decimal::from(winners) / Decimal::from(closed.len())
+        });
+
+        let initial_equity = self.config.initial_cash;
+        let final_equity = self.account.equity();
+        let net_pnl = final_equity - initial_equity;
+        BacktestReport {
+            initial_equity,
+            final_equity,
+            net_pnl,
+            return_fraction: if initial_equity > Decimal::ZERO { net_pnl / initial_equity } else { Decimal::ZERO },
+            fees_paid: self.fills.iter().map(|fill| fill.fee).sum(),
+            sharpe_ratio: sharpe_ratio(&self.samples, self.config.interval),
+            max_drawdown,
+            win_rate,
+            trades: self.trades,
+            fills: self.fills,
+            equity_curve,
+            events,
+        }
+    }
+}
+
+/// Close out a round trip, marking any remaining position at `mark_price`
+fn finish_trade(trade: OpenTrade, exit_time: Option<DateTime<Utc>>, mark_price: Decimal) -> BacktestTrade {
+    let remaining = trade.bought - trade.sold;
+    let pnl = trade.proceeds + remaining * mark_price - trade.cost - trade.fees;
+    BacktestTrade {
+        entry_time: trade.entry_time,
+        exit_time,
+        quantity: trade.bought,
+        entry_price: if trade.bought > Decimal::ZERO { trade.cost / trade.bought } else { Decimal::ZERO },
+        exit_price: (trade.sold > Decimal::ZERO).then(|| trade.proceeds / trade.sold),
+        fees: trade.fees,
+        pnl,
+        return_fraction: if trade.cost > Decimal::ZERO { pnl / trade.cost } else { Decimal::ZERO },
+    }
+}
+
+/// Sharpe ratio of the returns between consecutive samples, with a zero
+/// risk-free rate, annualized by the number of intervals in a year
+fn sharpe_ratio(samples: &[EquitySample], interval: CandleInterval) -> Option<Decimal> {
+    let returns: Vec<f64> = samples.windows(2)
+        .filter_map(|pair| {
+            let previous = pair[0].value.to_f64()?;
+            let current = pair[1].value.to_f64()?;
+            (previous > 0.0).then(|| current / previous - 1.0)
+        })
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let count = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / count;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (count - 1.0);
+    let deviation = variance.sqrt();
+    if deviation <= f64::EPSILON {
+        return None;
+    }
+
+    let periods_per_year = SECONDS_PER_YEAR / interval.seconds() as f64;
+    Decimal::from_f64(mean / deviation * periods_per_year.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+    use serde_json::{json, Value};
+
+    use crate::entity::market_data::{Instrument, InstrumentKind};
+    use crate::service::strategy::StrategyContext;
+
+    /// Returns the next scripted intents on each candle
+    struct Scripted {
+        script: Vec<Vec<OrderIntent>>,
+        candles_seen: usize,
+    }
+
+    impl Strategy for Scripted {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn parameters(&self) -> Value {
+            json!({})
+        }
+
+        fn on_candle(&mut self, _context: &StrategyContext, _candle: &Candle) -> Vec<OrderIntent> {
+            let intents = self.script.get(self.candles_seen).cloned().unwrap_or_default();
+            self.candles_seen += 1;
+            intents
+        }
+    }
+
+    fn hour(i: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::hours(i)
+    }
+
+    fn candle(i: i64, open: Decimal, low: Decimal, close: Decimal) -> MarketDataEvent {
+        let instrument = Instrument::new("BTC".to_string(), "USDT".to_string(), "binance".to_string(), InstrumentKind::Spot);
+        let mut candle = Candle::flat(instrument, CandleInterval::OneHour, hour(i), open, hour(i));
+        candle.low = low.min(close);
+        candle.high = open.max(close);
+        candle.close = close;
+        MarketDataEvent::Candle(candle)
+    }
+
+    fn config(fee_rate: Decimal) -> BacktestConfig {
+        BacktestConfig {
+            initial_cash: dec!(1000),
+            fill_model: FillModel::new(fee_rate, Decimal::ZERO),
+            interval: CandleInterval::OneHour,
+        }
+    }
+
+    fn buy(quantity: Decimal) -> OrderIntent {
+        OrderIntent::Market { side: OrderSide::Buy, quantity }
+    }
+
+    fn sell(quantity: Decimal) -> OrderIntent {
+        OrderIntent::Market { side: OrderSide::Sell, quantity }
+    }
+
+    #[test]
+    fn market_orders_fill_at_the_next_open() {
+        let mut strategy = Scripted { script: vec![vec![buy(dec!(1))], vec![], vec![sell(dec!(1))]], candles_seen: 0 };
+        let events = vec![
+            candle(0, dec!(100), dec!(100), dec!(100)),
+            candle(1, dec!(110), dec!(110), dec!(120)),
+            candle(2, dec!(120), dec!(120), dec!(130)),
+            candle(3, dec!(125), dec!(125), dec!(125)),
+        ];
+
+        let report = run_backtest(&mut strategy, &events, &config(Decimal::ZERO));
+        let prices: Vec<Decimal> = report.fills.iter().map(|fill| fill.price).collect();
+        assert_eq!(prices, vec![dec!(110), dec!(125)]);
+        assert_eq!(report.final_equity, dec!(1015));
+        assert_eq!(report.net_pnl, dec!(15));
+        assert_eq!(report.win_rate, Some(Decimal::ONE));
+        assert_eq!(report.trades.len(), 1);
+        assert_eq!(report.trades[0].pnl, dec!(15));
+        assert_eq!(report.events, 4);
+
+        // Equity peaked at 1020 before the sale at 125
+        assert_eq!(report.max_drawdown, dec!(5) / dec!(1020));
+        assert_eq!(report.equity_curve.first().map(|point| point.value), Some(dec!(1000)));
+    }
+
+    #[test]
+    fn buys_are_cut_to_the_cash_and_fees_are_charged() {
+        let mut strategy = Scripted { script: vec![vec![buy(dec!(100))]], candles_seen: 0 };
+        let events = vec![
+            candle(0, dec!(100), dec!(100), dec!(100)),
+            candle(1, dec!(100), dec!(100), dec!(90)),
+        ];
+
+        let report = run_backtest(&mut strategy, &events, &config(dec!(0.01)));
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].quantity, dec!(9.90099009));
+        assert!(report.fees_paid > Decimal::ZERO);
+
+        // The open trade is marked at the last close, fees included
+        let trade = &report.trades[0];
+        assert_eq!(trade.exit_time, None);
+        assert_eq!(trade.pnl, report.net_pnl);
+        assert_eq!(report.win_rate, None);
+    }
+
+    #[test]
+    fn limit_orders_rest_until_reached() {
+        let limit = OrderIntent::Limit { side: OrderSide::Buy, quantity: dec!(1), price: dec!(95) };
+        let mut strategy = Scripted { script: vec![vec![limit]], candles_seen: 0 };
+        let events = vec![
+            candle(0, dec!(100), dec!(94), dec!(100)),
+            candle(1, dec!(100), dec!(97), dec!(98)),
+            candle(2, dec!(96), dec!(93), dec!(94)),
+        ];
+
+        let report = run_backtest(&mut strategy, &events, &config(Decimal::ZERO));
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].price, dec!(95));
+        assert_eq!(report.fills[0].timestamp, hour(2));
+    }
+
+    #[test]
+    fn sharpe_needs_varying_returns() {
+        let sample = |i: i64, value: Decimal| EquitySample { bucket: hour(i), timestamp: hour(i), value };
+        let flat = vec![sample(0, dec!(100)), sample(1, dec!(100)), sample(2, dec!(100))];
+        assert_eq!(sharpe_ratio(&flat, CandleInterval::OneHour), None);
+
+        let rising = vec![sample(0, dec!(100)), sample(1, dec!(101)), sample(2, dec!(103))];
+        assert!(sharpe_ratio(&rising, CandleInterval::OneHour).is_some_and(|sharpe| sharpe > Decimal::ZERO));
+    }
+}
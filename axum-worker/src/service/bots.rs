@@ -0,0 +1,467 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::entity::market_data::{Candle, Trade};
+use crate::entity::trading::OrderSide;
+use crate::service::fill_model::QUANTITY_DECIMALS;
+use crate::service::strategy::{parse_state, OrderIntent, Strategy, StrategyContext, StrategyFill};
+
+/// Most levels a grid may have
+const MAX_GRID_LEVELS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DcaParameters {
+    /// Quote asset spent on each buy
+    pub amount: Decimal,
+    /// Time between buys, e.g. "4h", "1d" or "1w"
+    pub every: String,
+    /// Buys falling due while the price is above this are skipped
+    pub max_price: Option<Decimal>,
+    /// Stop after this many buys
+    pub max_buys: Option<u32>,
+    /// Lot step of the instrument each buy's quantity is rounded down to;
+    /// defaults to `QUANTITY_DECIMALS` places
+    pub step_size: Option<Decimal>,
+    /// Smallest quantity the exchange accepts; buys that round to less are skipped
+    pub min_quantity: Option<Decimal>,
+}
+
+impl Default for DcaParameters {
+    fn default() -> Self {
+        Self {
+            amount: Decimal::ZERO,
+            every: "1d".to_string(),
+            max_price: None,
+            max_buys: None,
+            step_size: None,
+            min_quantity: None,
+        }
+    }
+}
+
+/// Dollar-cost averaging: buys a fixed amount of the quote asset's worth at
+/// the market on a fixed schedule, starting with the first price seen
+#[derive(Debug, Clone)]
+pub struct Dca {
+    parameters: DcaParameters,
+    period: Duration,
+    state: DcaState,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DcaState {
+    next_buy_at: Option<DateTime<Utc>>,
+    /// Buys sent, including any a pause cancelled before they filled
+    buys: u32,
+    /// Buys skipped for being above the max price or below the minimum quantity
+    skipped: u32,
+}
+
+impl Dca {
+    pub fn new(parameters: DcaParameters) -> Result<Self, String> {
+        if parameters.amount <= Decimal::ZERO {
+            return Err("amount must be positive".to_string());
+        }
+        let period = parse_period(&parameters.every)
+            .ok_or_else(|| format!("Invalid every: {} (expected e.g. 30m, 4h, 1d or 1w)", parameters.every))?;
+        // The runtime's timer only fires once a minute
+        if period < Duration::minutes(1) {
+            return Err("every must be at least 1m".to_string());
+        }
+        if parameters.max_price.is_some_and(|price| price <= Decimal::ZERO) {
+            return Err("max_price must be positive".to_string());
+        }
+        if parameters.max_buys == Some(0) {
+            return Err("max_buys must be at least 1".to_string());
+        }
+        if parameters.step_size.is_some_and(|step| step <= Decimal::ZERO) {
+            return Err("step_size must be positive".to_string());
+        }
+        if parameters.min_quantity.is_some_and(|min| min < Decimal::ZERO) {
+            return Err("min_quantity must not be negative".to_string());
+        }
+        Ok(Self { parameters, period, state: DcaState::default() })
+    }
+
+    fn buy(&mut self, context: &StrategyContext, price: Decimal) -> Vec<OrderIntent> {
+        if price <= Decimal::ZERO
+            || self.parameters.max_buys.is_some_and(|max| self.state.buys >= max)
+            || self.state.next_buy_at.is_some_and(|next| context.timestamp < next)
+        {
+            return Vec::new();
+        }
+
+        // Keep to the schedule, unless buys were missed while paused or
+        // offline, in which case it restarts from now
+        let next = self.state.next_buy_at
+            .map(|next| next + self.period)
+            .filter(|next| *next > context.timestamp)
+            .unwrap_or(context.timestamp + self.period);
+        self.state.next_buy_at = Some(next);
+
+        if self.parameters.max_price.is_some_and(|max| price > max) {
+            self.state.skipped += 1;
+            return Vec::new();
+        }
+        let quantity = self.quantity(price);
+        if quantity <= Decimal::ZERO || self.parameters.min_quantity.is_some_and(|min| quantity < min) {
+            self.state.skipped += 1;
+            return Vec::new();
+        }
+        self.state.buys += 1;
+        vec![OrderIntent::Market { side: OrderSide::Buy, quantity }]
+    }
+
+    /// What the amount buys at `price`, rounded down to the lot step so the
+    /// exchange takes it as is and never spends more than the amount
+    fn quantity(&self, price: Decimal) -> Decimal {
+        let quantity = self.parameters.amount / price;
+        match self.parameters.step_size {
+            Some(step) => (quantity / step).floor() * step,
+            None => quantity.round_dp_with_strategy(QUANTITY_DECIMALS, RoundingStrategy::ToZero),
+        }
+    }
+}
+
+impl Strategy for Dca {
+    fn name(&self) -> &'static str {
+        "dca"
+    }
+
+    fn parameters(&self) -> Value {
+        serde_json::to_value(&self.parameters).unwrap_or_default()
+    }
+
+    fn on_candle(&mut self, context: &StrategyContext, candle: &Candle) -> Vec<OrderIntent> {
+        self.buy(context, candle.close)
+    }
+
+    fn on_trade(&mut self, context: &StrategyContext, trade: &Trade) -> Vec<OrderIntent> {
+        self.buy(context, trade.price)
+    }
+
+    fn on_timer(&mut self, context: &StrategyContext) -> Vec<OrderIntent> {
+        match context.last_price {
+            Some(price) => self.buy(context, price),
+            None => Vec::new(),
+        }
+    }
+
+    fn state(&self) -> Value {
+        serde_json::to_value(&self.state).unwrap_or_default()
+    }
+
+    fn restore_state(&mut self, state: &Value) -> Result<(), String> {
+        self.state = parse_state(self.name(), state)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GridParameters {
+    /// Price of the lowest level
+    pub lower: Decimal,
+    /// Price of the highest level
+    pub upper: Decimal,
+    /// Levels, evenly spaced from lower to upper
+    pub levels: usize,
+    /// Base asset bought or sold at each level
+    pub quantity: Decimal,
+}
+
+impl Default for GridParameters {
+    fn default() -> Self {
+        Self { lower: Decimal::ZERO, upper: Decimal::ZERO, levels: 10, quantity: Decimal::ZERO }
+    }
+}
+
+/// Grid trading: keeps a buy limit order resting at every level below the
+/// price and a sell at every level above it, leaving the level nearest the
+/// price empty. When a buy fills, a sell goes up one level above it; when a
+/// sell fills, a buy goes down one level below it, so each round trip earns
+/// the level spacing.
+///
+/// At the first price the grid buys, at the market, the base asset its sells
+/// need. Once that buy has finished the limit orders are placed, with sells
+/// at only as many levels as the position covers.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    parameters: GridParameters,
+    state: GridState,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct GridState {
+    /// Order wanted at each level, lowest level first
+    orders: Vec<Option<OrderSide>>,
+    /// What has filled of each level's order so far
+    filled: Vec<Decimal>,
+    /// Whether the opening buy was sent
+    started: bool,
+    /// Whether the limit orders were placed
+    placed: bool,
+    /// Sells filled, each closing a buy one level down or the opening buy
+    round_trips: u32,
+}
+
+impl Grid {
+    pub fn new(parameters: GridParameters) -> Result<Self, String> {
+        if parameters.lower <= Decimal::ZERO || parameters.upper <= parameters.lower {
+            return Err("lower must be positive and below upper".to_string());
+        }
+        if parameters.levels < 2 || parameters.levels > MAX_GRID_LEVELS {
+            return Err(format!("levels must be between 2 and {}", MAX_GRID_LEVELS));
+        }
+        if parameters.quantity <= Decimal::ZERO {
+            return Err("quantity must be positive".to_string());
+        }
+        Ok(Self {
+            state: GridState {
+                orders: vec![None; parameters.levels],
+                filled: vec![Decimal::ZERO; parameters.levels],
+                ..GridState::default()
+            },
+            parameters,
+        })
+    }
+
+    fn level_price(&self, level: usize) -> Decimal {
+        let step = (self.parameters.upper - self.parameters.lower) / Decimal::from(self.parameters.levels - 1);
+        self.parameters.lower + step * Decimal::from(level)
+    }
+
+    fn limit(&self, level: usize, side: OrderSide) -> OrderIntent {
+        OrderIntent::Limit {
+            side,
+            quantity: self.parameters.quantity - self.state.filled[level],
+            price: self.level_price(level),
+        }
+    }
+
+    fn on_price(&mut self, context: &StrategyContext, price: Decimal) -> Vec<OrderIntent> {
+        if !self.state.started {
+            let intents = self.start(price);
+            if !intents.is_empty() {
+                return intents;
+            }
+        }
+        self.place(context)
+    }
+
+    /// Lay out the grid around the first price, returning the opening buy
+    fn start(&mut self, price: Decimal) -> Vec<OrderIntent> {
+        self.state.started = true;
+        let nearest = (0..self.parameters.levels)
+            .min_by_key(|level| (self.level_price(*level) - price).abs())
+            .unwrap_or_default();
+        let mut sells = 0;
+        for (level, order) in self.state.orders.iter_mut().enumerate() {
+            *order = match level {
+                level if level < nearest => Some(OrderSide::Buy),
+                level if level > nearest => {
+                    sells += 1;
+                    Some(OrderSide::Sell)
+                }
+                _ => None,
+            };
+        }
+
+        if sells == 0 {
+            return Vec::new();
+        }
+        vec![OrderIntent::Market {
+            side: OrderSide::Buy,
+            quantity: self.parameters.quantity * Decimal::from(sells),
+        }]
+    }
+
+    /// Place the limit orders once the opening buy has finished
+    fn place(&mut self, context: &StrategyContext) -> Vec<OrderIntent> {
+        if !self.state.started || self.state.placed || context.open_orders > 0 {
+            return Vec::new();
+        }
+        self.state.placed = true;
+
+        // Sells the opening buy didn't cover are dropped, from the top down
+        let mut covered = (context.position / self.parameters.quantity).floor();
+        for order in self.state.orders.iter_mut() {
+            if matches!(order, Some(OrderSide::Sell)) {
+                if covered >= Decimal::ONE {
+                    covered -= Decimal::ONE;
+                } else {
+                    *order = None;
+                }
+            }
+        }
+        self.open_orders()
+    }
+
+    /// Level of the order a fill belongs to. Limit orders fill at their
+    /// price or better, so when the price jumps several levels at once every
+    /// order it passed fills at about the same price. Buys are matched from
+    /// the highest level down and sells from the lowest up, the order the
+    /// market reaches them in.
+    fn filled_level(&self, is_buy: bool, price: Decimal) -> Option<usize> {
+        let levels = (0..self.parameters.levels).filter(|level| match &self.state.orders[*level] {
+            Some(OrderSide::Buy) => is_buy,
+            Some(OrderSide::Sell) => !is_buy,
+            None => false,
+        });
+        let reached = if is_buy {
+            levels.clone().filter(|level| self.level_price(*level) >= price).max()
+        } else {
+            levels.clone().filter(|level| self.level_price(*level) <= price).min()
+        };
+        // Otherwise the fill was priced a little off the level, e.g. an average of partial fills
+        reached.or_else(|| levels.min_by_key(|level| (self.level_price(*level) - price).abs()))
+    }
+
+    fn open_orders(&self) -> Vec<OrderIntent> {
+        self.state.orders.iter().enumerate()
+            .filter_map(|(level, order)| order.clone().map(|side| self.limit(level, side)))
+            .collect()
+    }
+}
+
+impl Strategy for Grid {
+    fn name(&self) -> &'static str {
+        "grid"
+    }
+
+    fn parameters(&self) -> Value {
+        serde_json::to_value(&self.parameters).unwrap_or_default()
+    }
+
+    fn on_candle(&mut self, context: &StrategyContext, candle: &Candle) -> Vec<OrderIntent> {
+        self.on_price(context, candle.close)
+    }
+
+    fn on_trade(&mut self, context: &StrategyContext, trade: &Trade) -> Vec<OrderIntent> {
+        self.on_price(context, trade.price)
+    }
+
+    fn on_timer(&mut self, context: &StrategyContext) -> Vec<OrderIntent> {
+        match context.last_price {
+            Some(price) => self.on_price(context, price),
+            None => Vec::new(),
+        }
+    }
+
+    fn on_fill(&mut self, context: &StrategyContext, fill: &StrategyFill) -> Vec<OrderIntent> {
+        if !self.state.placed {
+            // The opening buy
+            return self.place(context);
+        }
+
+        let is_buy = matches!(fill.side, OrderSide::Buy);
+        let Some(level) = self.filled_level(is_buy, fill.price) else {
+            return Vec::new();
+        };
+
+        self.state.filled[level] += fill.quantity;
+        if self.state.filled[level] < self.parameters.quantity {
+            return Vec::new();
+        }
+        self.state.filled[level] = Decimal::ZERO;
+        self.state.orders[level] = None;
+
+        let (next, side) = if is_buy {
+            (level + 1, OrderSide::Sell)
+        } else {
+            self.state.round_trips += 1;
+            match level.checked_sub(1) {
+                Some(next) => (next, OrderSide::Buy),
+                None => return Vec::new(),
+            }
+        };
+        // The level is taken while an order there hasn't been matched to its fill yet
+        if next >= self.parameters.levels || self.state.orders[next].is_some() {
+            return Vec::new();
+        }
+        self.state.orders[next] = Some(side.clone());
+        self.state.filled[next] = Decimal::ZERO;
+        vec![self.limit(next, side)]
+    }
+
+    fn on_resume(&mut self, context: &StrategyContext) -> Vec<OrderIntent> {
+        if !self.state.placed {
+            return self.place(context);
+        }
+        self.open_orders()
+    }
+
+    fn state(&self) -> Value {
+        serde_json::to_value(&self.state).unwrap_or_default()
+    }
+
+    fn restore_state(&mut self, state: &Value) -> Result<(), String> {
+        self.state = parse_state(self.name(), state)?;
+        Ok(())
+    }
+}
+
+/// Parse a period like "30m", "4h", "1d" or "1w"
+fn parse_period(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let count: i64 = value[..value.len() - unit.len_utf8()].parse().ok().filter(|count| *count > 0)?;
+    match unit {
+        'm' => Some(Duration::minutes(count)),
+        'h' => Some(Duration::hours(count)),
+        'd' => Some(Duration::days(count)),
+        'w' => Some(Duration::weeks(count)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn context(minutes: i64) -> StrategyContext {
+        StrategyContext {
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap() + Duration::minutes(minutes),
+            cash: dec!(1000),
+            position: Decimal::ZERO,
+            last_price: None,
+            open_orders: 0,
+        }
+    }
+
+    fn dca(step_size: Option<Decimal>, min_quantity: Option<Decimal>) -> Dca {
+        Dca::new(DcaParameters { amount: dec!(100), every: "1h".to_string(), step_size, min_quantity, ..DcaParameters::default() }).unwrap()
+    }
+
+    fn bought(intents: &[OrderIntent]) -> Option<Decimal> {
+        match intents {
+            [OrderIntent::Market { side: OrderSide::Buy, quantity }] => Some(*quantity),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn dca_rounds_buys_down_to_the_step_size() {
+        let mut bot = dca(Some(dec!(0.001)), None);
+        assert_eq!(bought(&bot.buy(&context(0), dec!(70000))), Some(dec!(0.001)));
+
+        let mut bot = dca(None, None);
+        assert_eq!(bought(&bot.buy(&context(0), dec!(3))), Some(dec!(33.33333333)));
+    }
+
+    #[test]
+    fn dca_skips_buys_below_the_minimum_quantity_and_keeps_its_schedule() {
+        let mut bot = dca(Some(dec!(0.001)), Some(dec!(0.002)));
+        assert!(bot.buy(&context(0), dec!(70000)).is_empty());
+        assert_eq!((bot.state.buys, bot.state.skipped), (0, 1));
+
+        assert!(bot.buy(&context(30), dec!(40000)).is_empty());
+        assert_eq!(bought(&bot.buy(&context(60), dec!(40000))), Some(dec!(0.002)));
+        assert_eq!((bot.state.buys, bot.state.skipped), (1, 1));
+    }
+}
@@ -0,0 +1,470 @@
+use std::collections::BTreeMap;
+use worker::console_log;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+
+use crate::clients::exchange_governor::GovernorClient;
+use crate::clients::exchange_policy::ExchangePolicy;
+use crate::clients::trading::{Exchange, Kline, SimpleInstrument, TradingClient};
+use crate::dto::market_data::{
+    CandleDto, GetCandlesRequest, GetCandlesResponse, GetIndicatorsRequest, GetIndicatorsResponse, IndicatorRequest,
+    IndicatorSeriesDto, IndicatorValueDto, InstrumentDto,
+};
+use crate::entity::market_data::{Candle, CandleInterval, CandleSource, Instrument, InstrumentKind};
+use crate::repo::candle::CandleRepository;
+use crate::service::candle_aggregator::{bucket_count, fill_gaps, roll_up};
+use crate::service::indicators::{
+    AnyIndicator, Atr, BollingerBands, Ema, IndicatorValue, Macd, Rsi, Sma, Vwap, INDICATORS,
+};
+
+/// Candles returned when a request gives neither a start time nor a limit
+const DEFAULT_CANDLES_LIMIT: u32 = 100;
+
+/// Most candles a single request may span
+const MAX_CANDLES: i64 = 1000;
+
+/// Most indicators a single request may ask for
+const MAX_INDICATORS: usize = 10;
+
+/// Most kline requests one backfill may make, keeping under the worker's subrequest limit
+const MAX_BACKFILL_PAGES: u32 = 10;
+
+/// How far back each aggregation job run repairs and rolls up candles; the
+/// job runs every 15 minutes, so consecutive runs overlap
+const AGGREGATION_WINDOW_MINUTES: i64 = 30;
+
+/// An instrument's candles over a range, as loaded by `CandleService::load_candles`
+#[derive(Debug, Clone)]
+pub struct LoadedCandles {
+    /// Stored and backfilled candles, oldest first
+    pub candles: Vec<Candle>,
+    /// How many of the candles were fetched from the exchange
+    pub backfilled: u32,
+    /// Start of the first bucket that was missing and couldn't be backfilled,
+    /// because the backfill failed or stopped at its page limit. Candles from
+    /// here on may be missing and mustn't be filled in.
+    pub missing_from: Option<DateTime<Utc>>,
+}
+
+/// Serves OHLCV candles, backfilling from the exchanges' kline APIs.
+///
+/// One-minute candles are aggregated from the live trade stream by the
+/// `MarketStream` Durable Object. Whatever the stream missed is backfilled
+/// from klines when a client asks for it, and the aggregation job rolls the
+/// one-minute candles up into the larger intervals.
+#[derive(Clone)]
+pub struct CandleService {
+    candle_repository: CandleRepository,
+    governor: GovernorClient,
+    policy: ExchangePolicy,
+}
+
+impl CandleService {
+    pub fn new(candle_repository: CandleRepository, governor: GovernorClient, policy: ExchangePolicy) -> Self {
+        Self { candle_repository, governor, policy }
+    }
+
+    /// Get an instrument's candles, backfilling any the database is missing
+    pub async fn get_candles(&self, request: GetCandlesRequest) -> Result<GetCandlesResponse, String> {
+        console_log!("CANDLE SERVICE: Getting {} candles for {}/{} on {}",
+            request.interval, request.base, request.quote, request.exchange);
+
+        let interval = CandleInterval::parse(&request.interval)
+            .ok_or_else(|| format!("Unsupported interval: {} (expected 1m, 5m, 15m, 1h, 4h or 1d)", request.interval))?;
+        if Exchange::parse(&request.exchange).is_none() {
+            return Err(format!("Unsupported exchange: {}", request.exchange));
+        }
+        let instrument = Instrument::new(request.base, request.quote, request.exchange, InstrumentKind::Spot);
+
+        let (start, end) = candle_range(interval, request.start_time, request.end_time, request.limit)?;
+
+        let LoadedCandles { mut candles, backfilled, missing_from } = self.load_candles(&instrument, interval, start, end).await?;
+        if request.fill_gaps.unwrap_or(true) {
+            candles = fill_gaps(&candles, start, fill_end(interval, end, missing_from));
+        }
+
+        console_log!("CANDLE SERVICE: Returning {} candles for {} ({} backfilled)", candles.len(), instrument.id, backfilled);
+        Ok(GetCandlesResponse {
+            instrument: InstrumentDto::from(&instrument),
+            interval: interval.as_str().to_string(),
+            candles: candles.iter().map(CandleDto::from).collect(),
+            backfilled,
+        })
+    }
+
+    /// Compute technical indicators over an instrument's candles. Candles
+    /// before the requested range are loaded to warm the indicators up, so
+    /// the first values returned are those a longer series would give.
+    pub async fn get_indicators(&self, request: GetIndicatorsRequest) -> Result<GetIndicatorsResponse, String> {
+        console_log!("CANDLE SERVICE: Computing {} indicators over {} candles for {}/{} on {}",
+            request.indicators.len(), request.interval, request.base, request.quote, request.exchange);
+
+        if request.indicators.is_empty() {
+            return Err("At least one indicator is required".to_string());
+        }
+        if request.indicators.len() > MAX_INDICATORS {
+            return Err(format!("At most {} indicators per request", MAX_INDICATORS));
+        }
+        let mut indicators = request.indicators.iter()
+            .map(build_indicator)
+            .collect::<Result<Vec<_>, _>>()?;
+        let interval = CandleInterval::parse(&request.interval)
+            .ok_or_else(|| format!("Unsupported interval: {} (expected 1m, 5m, 15m, 1h, 4h or 1d)", request.interval))?;
+        if Exchange::parse(&request.exchange).is_none() {
+            return Err(format!("Unsupported exchange: {}", request.exchange));
+        }
+        let instrument = Instrument::new(request.base, request.quote, request.exchange, InstrumentKind::Spot);
+        let (start, end) = candle_range(interval, request.start_time, request.end_time, request.limit)?;
+        let history_start = indicators.iter()
+            .map(|indicator| indicator.history_start(interval, start))
+            .min()
+            .unwrap_or(start);
+
+        let LoadedCandles { candles, backfilled, missing_from } = self.load_candles(&instrument, interval, history_start, end).await?;
+        let candles = fill_gaps(&candles, history_start, fill_end(interval, end, missing_from));
+        // Every candle goes through the indicators, but only the requested range is returned
+        let first = candles.iter().position(|candle| candle.open_time >= start).unwrap_or(candles.len());
+        let series = indicators.iter_mut()
+            .map(|indicator| {
+                let values: Vec<Option<IndicatorValue>> = candles.iter().map(|candle| indicator.update(candle)).collect();
+                IndicatorSeriesDto {
+                    name: indicator.name().to_string(),
+                    label: indicator.label(),
+                    values: candles[first..].iter().zip(&values[first..])
+                        .map(|(candle, value)| convert_indicator_value(candle, value.as_ref()))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        console_log!("CANDLE SERVICE: Returning {} candles with indicators for {} ({} backfilled)",
+            candles.len() - first, instrument.id, backfilled);
+        Ok(GetIndicatorsResponse {
+            instrument: InstrumentDto::from(&instrument),
+            interval: interval.as_str().to_string(),
+            candles: candles[first..].iter().map(CandleDto::from).collect(),
+            indicators: series,
+            backfilled,
+        })
+    }
+
+    /// Load an instrument's stored candles opening within `[start, end)`,
+    /// oldest first, backfilling from the exchange every bucket that has
+    /// started but has no finished candle. If the backfill fails or stops
+    /// short, only what was stored or fetched is returned, and the first
+    /// bucket left missing is reported.
+    pub async fn load_candles(
+        &self,
+        instrument: &Instrument,
+        interval: CandleInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<LoadedCandles, String> {
+        let stored = self.candle_repository.find_candles(&instrument.id, interval, start, end).await?;
+        let mut candles: BTreeMap<DateTime<Utc>, Candle> = stored.into_iter()
+            .map(|candle| (candle.open_time, candle))
+            .collect();
+
+        // Backfill from the first bucket without a finished candle to the last
+        let now = Utc::now();
+        let missing: Vec<DateTime<Utc>> = (0..bucket_count(interval, start, end))
+            .map(|i| start + interval.duration() * i as i32)
+            .filter(|open_time| *open_time <= now)
+            .filter(|open_time| !candles.get(open_time).is_some_and(Candle::is_complete))
+            .collect();
+        let mut backfilled = 0;
+        let mut missing_from = None;
+        if let (Some(first), Some(last)) = (missing.first(), missing.last()) {
+            match self.backfill(instrument, interval, *first, *last + interval.duration()).await {
+                Ok((fetched, reached)) => {
+                    backfilled = fetched.len() as u32;
+                    candles.extend(fetched.into_iter().map(|candle| (candle.open_time, candle)));
+                    missing_from = missing.iter().find(|open_time| **open_time >= reached).copied();
+                }
+                // Serve what is stored rather than failing the request
+                Err(e) => {
+                    console_log!("CANDLE SERVICE: Backfill for {} failed: {}", instrument.id, e);
+                    missing_from = Some(*first);
+                }
+            }
+        }
+
+        Ok(LoadedCandles { candles: candles.into_values().collect(), backfilled, missing_from })
+    }
+
+    /// Keep tracked instruments' candles current: backfill recent one-minute
+    /// buckets the trade stream missed, then roll them up into every larger
+    /// interval whose bucket ended in the window. Returns the number of
+    /// candles saved.
+    pub async fn aggregate_candles(&self) -> Result<u32, String> {
+        let instruments = self.candle_repository.find_tracked_instruments().await?;
+        console_log!("CANDLE SERVICE: Aggregating candles for {} instruments", instruments.len());
+
+        let now = Utc::now();
+        let window_start = CandleInterval::OneMinute.bucket_start(now - Duration::minutes(AGGREGATION_WINDOW_MINUTES));
+        let window_end = CandleInterval::OneMinute.bucket_start(now);
+        let mut saved = 0;
+
+        for instrument in instruments {
+            let minutes = self.candle_repository
+                .find_candles(&instrument.id, CandleInterval::OneMinute, window_start, window_end)
+                .await?;
+            let complete = minutes.iter().filter(|candle| candle.is_complete()).count() as i64;
+            if complete < bucket_count(CandleInterval::OneMinute, window_start, window_end) {
+                match self.backfill(&instrument, CandleInterval::OneMinute, window_start, window_end).await {
+                    Ok((fetched, _)) => saved += fetched.len() as u32,
+                    Err(e) => console_log!("CANDLE SERVICE: Backfill for {} failed: {}", instrument.id, e),
+                }
+            }
+
+            for interval in CandleInterval::ALL.into_iter().skip(1) {
+                saved += self.roll_up_closed(&instrument, interval, window_start, now).await?;
+            }
+        }
+
+        Ok(saved)
+    }
+
+    /// Roll one-minute candles up into `interval` buckets that ended between
+    /// `since` and `now`. A bucket is only rolled up once every one of its
+    /// minutes is stored.
+    async fn roll_up_closed(
+        &self,
+        instrument: &Instrument,
+        interval: CandleInterval,
+        since: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<u32, String> {
+        let mut saved = 0;
+        let mut open_time = interval.bucket_start(since);
+
+        while open_time + interval.duration() <= now {
+            let close_time = open_time + interval.duration();
+            let minutes = self.candle_repository
+                .find_candles(&instrument.id, CandleInterval::OneMinute, open_time, close_time)
+                .await?;
+            let covered = minutes.len() as i64 == bucket_count(CandleInterval::OneMinute, open_time, close_time)
+                && minutes.iter().all(Candle::is_complete);
+            if covered {
+                let rolled = roll_up(&minutes, interval);
+                self.candle_repository.save_candles(&rolled, CandleSource::Trades).await?;
+                saved += rolled.len() as u32;
+            }
+            open_time = close_time;
+        }
+
+        Ok(saved)
+    }
+
+    /// Fetch and store klines for `[start, end)`. Buckets the exchange
+    /// reports no trades for are stored as flat candles so they aren't
+    /// fetched again. Returns the candles and where the fetch stopped,
+    /// which is before `end` if it ran out of pages; nothing from there on
+    /// is stored.
+    async fn backfill(
+        &self,
+        instrument: &Instrument,
+        interval: CandleInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(Vec<Candle>, DateTime<Utc>), String> {
+        console_log!("CANDLE SERVICE: Backfilling {} {} candles from {} to {}", instrument.id, interval.as_str(), start, end);
+
+        let exchange = Exchange::parse(&instrument.exchange)
+            .ok_or_else(|| format!("Unsupported exchange: {}", instrument.exchange))?;
+        // Klines are public, so no credentials are needed
+        let client = TradingClient::new(exchange, None, None)
+            .with_governor(self.governor.clone())
+            .with_policy(self.policy.clone());
+
+        let (fetched, reached) = if client.supports_kline_interval(interval) {
+            self.fetch_klines(&client, instrument, interval, start, end).await?
+        } else {
+            // e.g. Coinbase has no 4h klines; build them from hourly ones,
+            // keeping only the buckets whose every hour was fetched
+            let (hours, reached) = self.fetch_klines(&client, instrument, CandleInterval::OneHour, start, end).await?;
+            let reached = if reached < end { interval.bucket_start(reached) } else { end };
+            (roll_up(&fill_gaps(&hours, start, reached), interval), reached)
+        };
+        if reached < end {
+            console_log!("CANDLE SERVICE: Backfill of {} {} candles stopped at {} after {} pages, short of {}",
+                instrument.id, interval.as_str(), reached, MAX_BACKFILL_PAGES, end);
+        }
+
+        let candles = covered_candles(&fetched, start, reached, Utc::now());
+        self.candle_repository.save_candles(&candles, CandleSource::Klines).await?;
+        Ok((candles, reached))
+    }
+
+    /// Page through the exchange's klines for `[start, end)`, up to
+    /// `MAX_BACKFILL_PAGES` pages. Returns the klines and the time the last
+    /// page reached.
+    async fn fetch_klines(
+        &self,
+        client: &TradingClient,
+        instrument: &Instrument,
+        interval: CandleInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(Vec<Candle>, DateTime<Utc>), String> {
+        let simple = SimpleInstrument {
+            base: instrument.base.clone(),
+            quote: instrument.quote.clone(),
+        };
+        let page_span = interval.duration() * client.max_klines() as i32;
+        let mut candles = Vec::new();
+        let mut cursor = start;
+
+        for _ in 0..MAX_BACKFILL_PAGES {
+            if cursor >= end {
+                break;
+            }
+            let page_end = (cursor + page_span).min(end);
+            let klines = client.get_klines(&simple, interval, cursor, page_end).await?;
+            candles.extend(klines.into_iter().map(|kline| kline_to_candle(instrument, interval, kline)));
+            cursor = page_end;
+        }
+
+        Ok((candles, cursor))
+    }
+}
+
+/// Fetched candles with flat candles in the buckets that had no trades, up to
+/// the bucket in progress at `now`. Nothing at or after `reached`, where the
+/// fetch stopped, is filled in, since those buckets weren't fetched.
+fn covered_candles(fetched: &[Candle], start: DateTime<Utc>, reached: DateTime<Utc>, now: DateTime<Utc>) -> Vec<Candle> {
+    fill_gaps(fetched, start, reached).into_iter()
+        .filter(|candle| candle.open_time <= now)
+        .collect()
+}
+
+/// End of the range to fill with flat candles: the bucket in progress, or
+/// the first bucket that couldn't be loaded
+fn fill_end(interval: CandleInterval, end: DateTime<Utc>, missing_from: Option<DateTime<Utc>>) -> DateTime<Utc> {
+    let end = end.min(interval.bucket_start(Utc::now()) + interval.duration());
+    missing_from.map_or(end, |missing_from| end.min(missing_from))
+}
+
+/// Range of whole buckets `[start, end)` a candles request covers: from
+/// `start_time`, or `limit` candles back, to the bucket holding `end_time`
+fn candle_range(
+    interval: CandleInterval,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    limit: Option<u32>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    // Widen the range to whole buckets, including the one in progress
+    let end_time = end_time.unwrap_or_else(Utc::now);
+    let end = match interval.bucket_start(end_time) {
+        bucket if bucket == end_time => bucket,
+        bucket => bucket + interval.duration(),
+    };
+    let start = match start_time {
+        Some(start_time) => interval.bucket_start(start_time),
+        None => end - interval.duration() * limit.unwrap_or(DEFAULT_CANDLES_LIMIT).max(1) as i32,
+    };
+    if start >= end {
+        return Err("start_time must be before end_time".to_string());
+    }
+    let buckets = bucket_count(interval, start, end);
+    if buckets > MAX_CANDLES {
+        return Err(format!("Requested range spans {} candles; at most {} per request", buckets, MAX_CANDLES));
+    }
+    Ok((start, end))
+}
+
+/// Build an indicator from a request, with the usual defaults for settings left out
+fn build_indicator(request: &IndicatorRequest) -> Result<AnyIndicator, String> {
+    let indicator = match request.name.to_lowercase().as_str() {
+        "sma" => AnyIndicator::Sma(Sma::new(request.period.unwrap_or(20))?),
+        "ema" => AnyIndicator::Ema(Ema::new(request.period.unwrap_or(20))?),
+        "rsi" => AnyIndicator::Rsi(Rsi::new(request.period.unwrap_or(14))?),
+        "macd" => AnyIndicator::Macd(Box::new(Macd::new(
+            request.fast.unwrap_or(12),
+            request.slow.unwrap_or(26),
+            request.signal.unwrap_or(9),
+        )?)),
+        "bollinger" => AnyIndicator::BollingerBands(BollingerBands::new(
+            request.period.unwrap_or(20),
+            request.multiplier.unwrap_or(Decimal::TWO),
+        )?),
+        "atr" => AnyIndicator::Atr(Atr::new(request.period.unwrap_or(14))?),
+        "vwap" => AnyIndicator::Vwap(Vwap::new()),
+        _ => return Err(format!("Unknown indicator: {} (expected one of {})", request.name, INDICATORS.join(", "))),
+    };
+    Ok(indicator)
+}
+
+fn convert_indicator_value(candle: &Candle, value: Option<&IndicatorValue>) -> IndicatorValueDto {
+    IndicatorValueDto {
+        open_time: candle.open_time,
+        value: value.map(|value| value.value.round_dp(8)),
+        signal: value.and_then(|value| value.signal).map(|signal| signal.round_dp(8)),
+        histogram: value.and_then(|value| value.histogram).map(|histogram| histogram.round_dp(8)),
+        upper: value.and_then(|value| value.upper).map(|upper| upper.round_dp(8)),
+        lower: value.and_then(|value| value.lower).map(|lower| lower.round_dp(8)),
+    }
+}
+
+fn kline_to_candle(instrument: &Instrument, interval: CandleInterval, kline: Kline) -> Candle {
+    let now = Utc::now();
+    Candle {
+        instrument: instrument.clone(),
+        interval,
+        open_time: kline.open_time,
+        open: kline.open,
+        high: kline.high,
+        low: kline.low,
+        close: kline.close,
+        volume: kline.volume,
+        trade_count: kline.trade_count,
+        timestamp: now,
+        // Klines don't say when the last trade was
+        exchange_timestamp: (kline.open_time + interval.duration()).min(now),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn minute(i: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::minutes(i)
+    }
+
+    fn candle(i: i64, close: Decimal) -> Candle {
+        let instrument = Instrument::new("BTC".to_string(), "USDT".to_string(), "okx".to_string(), InstrumentKind::Spot);
+        Candle::flat(instrument, CandleInterval::OneMinute, minute(i), close, minute(i))
+    }
+
+    #[test]
+    fn backfill_fills_gaps_only_up_to_where_the_fetch_stopped() {
+        // Pages ran out at minute 5 of a range ending at minute 10
+        let fetched = vec![candle(0, dec!(100)), candle(2, dec!(101))];
+        let candles = covered_candles(&fetched, minute(0), minute(5), minute(60));
+
+        let open_times: Vec<DateTime<Utc>> = candles.iter().map(|candle| candle.open_time).collect();
+        assert_eq!(open_times, (0..5).map(minute).collect::<Vec<_>>());
+        assert_eq!(candles[1].close, dec!(100));
+        assert_eq!(candles[4].close, dec!(101));
+    }
+
+    #[test]
+    fn backfill_leaves_future_buckets_out() {
+        let fetched = vec![candle(0, dec!(100))];
+        let candles = covered_candles(&fetched, minute(0), minute(10), minute(3));
+        assert_eq!(candles.last().map(|candle| candle.open_time), Some(minute(3)));
+    }
+
+    #[test]
+    fn candle_range_rejects_ranges_over_the_limit() {
+        let end = minute(MAX_CANDLES + 1);
+        assert!(candle_range(CandleInterval::OneMinute, Some(minute(0)), Some(end), None).is_err());
+        assert_eq!(
+            candle_range(CandleInterval::OneMinute, Some(minute(0)), Some(minute(10)), None),
+            Ok((minute(0), minute(10))),
+        );
+    }
+}
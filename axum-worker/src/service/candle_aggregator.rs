@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::entity::market_data::{Candle, CandleInterval, Instrument, Trade};
+
+/// Candle of one bucket along with the trade times that decide its open and close
+#[derive(Debug, Clone)]
+struct CandleBuilder {
+    candle: Candle,
+    first_trade_at: DateTime<Utc>,
+}
+
+/// Folds a stream of trades into OHLCV candles of one interval.
+///
+/// Trades are bucketed by their exchange timestamp, so they may arrive out of
+/// order: a trade older than the bucket's first trade becomes its open, and
+/// one at or after the last trade becomes its close. A bucket stays open
+/// until `lateness` has passed after it ends. Once closed, its candle is
+/// final and any later trade for it is dropped as late.
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    instrument: Instrument,
+    interval: CandleInterval,
+    lateness: Duration,
+    buckets: BTreeMap<DateTime<Utc>, CandleBuilder>,
+    /// Buckets starting before this have been closed
+    closed_before: Option<DateTime<Utc>>,
+}
+
+impl CandleAggregator {
+    pub fn new(instrument: Instrument, interval: CandleInterval, lateness: Duration) -> Self {
+        Self {
+            instrument,
+            interval,
+            lateness,
+            buckets: BTreeMap::new(),
+            closed_before: None,
+        }
+    }
+
+    /// Add a trade to its bucket. Returns false if the bucket was already
+    /// closed and the trade was dropped.
+    pub fn push(&mut self, trade: &Trade) -> bool {
+        let traded_at = trade.exchange_timestamp;
+        let open_time = self.interval.bucket_start(traded_at);
+        if self.closed_before.is_some_and(|closed_before| open_time < closed_before) {
+            return false;
+        }
+
+        match self.buckets.get_mut(&open_time) {
+            Some(builder) => {
+                let candle = &mut builder.candle;
+                if traded_at < builder.first_trade_at {
+                    builder.first_trade_at = traded_at;
+                    candle.open = trade.price;
+                }
+                if traded_at >= candle.exchange_timestamp {
+                    candle.exchange_timestamp = traded_at;
+                    candle.close = trade.price;
+                }
+                candle.high = candle.high.max(trade.price);
+                candle.low = candle.low.min(trade.price);
+                candle.volume += trade.quantity;
+                candle.trade_count = Some(candle.trade_count.unwrap_or(0) + 1);
+            }
+            None => {
+                let mut candle = Candle::flat(self.instrument.clone(), self.interval, open_time, trade.price, traded_at);
+                candle.volume = trade.quantity;
+                candle.trade_count = Some(1);
+                self.buckets.insert(open_time, CandleBuilder { candle, first_trade_at: traded_at });
+            }
+        }
+        true
+    }
+
+    /// Close every bucket that ended at least `lateness` before `now`,
+    /// returning their candles oldest first
+    pub fn close_until(&mut self, now: DateTime<Utc>) -> Vec<Candle> {
+        let closed_before = self.interval.bucket_start(now - self.lateness);
+        if self.closed_before.is_some_and(|previous| previous >= closed_before) {
+            return Vec::new();
+        }
+        self.closed_before = Some(closed_before);
+
+        let open = self.buckets.split_off(&closed_before);
+        let closed = std::mem::replace(&mut self.buckets, open);
+        closed.into_values()
+            .map(|builder| Candle { timestamp: now, ..builder.candle })
+            .collect()
+    }
+}
+
+/// Fill empty buckets between `start` and `end` with flat candles.
+///
+/// `candles` must be of one interval and ordered oldest first. A bucket with
+/// no trades gets a candle at the previous close with zero volume. Buckets
+/// before the first candle are left empty since there is no price to carry.
+pub fn fill_gaps(candles: &[Candle], start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Candle> {
+    let Some(first) = candles.first() else { return Vec::new() };
+    let interval = first.interval;
+
+    let mut filled = Vec::with_capacity(candles.len());
+    let mut next = candles.iter().peekable();
+    let mut previous: Option<Candle> = None;
+    let mut open_time = interval.bucket_start(start);
+
+    while open_time < end {
+        // Skip anything not aligned to the bucket we're filling
+        while next.peek().is_some_and(|candle| candle.open_time < open_time) {
+            previous = next.next().cloned();
+        }
+
+        match next.peek() {
+            Some(candle) if candle.open_time == open_time => {
+                previous = next.next().cloned();
+                filled.extend(previous.clone());
+            }
+            _ => {
+                if let Some(previous) = &previous {
+                    filled.push(Candle::flat(
+                        previous.instrument.clone(),
+                        interval,
+                        open_time,
+                        previous.close,
+                        previous.exchange_timestamp,
+                    ));
+                }
+            }
+        }
+
+        open_time += interval.duration();
+    }
+
+    filled
+}
+
+/// Roll candles of a smaller interval up into `interval` buckets.
+///
+/// `candles` must be ordered oldest first. Each bucket's trade count is only
+/// known if every candle in it reports one. A rolled-up candle only counts as
+/// complete if all of its smaller buckets were present and complete, which
+/// callers check with `bucket_count`.
+pub fn roll_up(candles: &[Candle], interval: CandleInterval) -> Vec<Candle> {
+    let mut rolled: Vec<Candle> = Vec::new();
+
+    for candle in candles {
+        let open_time = interval.bucket_start(candle.open_time);
+        match rolled.last_mut() {
+            Some(bucket) if bucket.open_time == open_time => {
+                bucket.high = bucket.high.max(candle.high);
+                bucket.low = bucket.low.min(candle.low);
+                bucket.close = candle.close;
+                bucket.volume += candle.volume;
+                bucket.trade_count = bucket.trade_count.zip(candle.trade_count).map(|(a, b)| a + b);
+                bucket.timestamp = bucket.timestamp.max(candle.timestamp);
+                bucket.exchange_timestamp = candle.exchange_timestamp;
+            }
+            _ => rolled.push(Candle {
+                interval,
+                open_time,
+                ..candle.clone()
+            }),
+        }
+    }
+
+    rolled
+}
+
+/// Number of `interval` buckets in `[start, end)`
+pub fn bucket_count(interval: CandleInterval, start: DateTime<Utc>, end: DateTime<Utc>) -> i64 {
+    let span = end - interval.bucket_start(start);
+    (span.num_seconds() + interval.seconds() - 1).max(0) / interval.seconds()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use crate::entity::market_data::{InstrumentKind, TradeSide};
+
+    fn instrument() -> Instrument {
+        Instrument::new("BTC".to_string(), "USDT".to_string(), "binance".to_string(), InstrumentKind::Spot)
+    }
+
+    fn second(i: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::seconds(i)
+    }
+
+    fn trade(seconds: i64, price: Decimal, quantity: Decimal) -> Trade {
+        Trade::from_exchange(format!("trade-{}", seconds), instrument(), price, quantity, TradeSide::Buy, second(seconds))
+    }
+
+    fn aggregator() -> CandleAggregator {
+        CandleAggregator::new(instrument(), CandleInterval::OneMinute, Duration::seconds(2))
+    }
+
+    #[test]
+    fn out_of_order_trades_set_open_and_close_by_time() {
+        let mut aggregator = aggregator();
+        assert!(aggregator.push(&trade(30, dec!(102), dec!(1))));
+        assert!(aggregator.push(&trade(10, dec!(100), dec!(2))));
+        assert!(aggregator.push(&trade(50, dec!(99), dec!(1))));
+        assert!(aggregator.push(&trade(20, dec!(105), dec!(1))));
+
+        let candles = aggregator.close_until(second(62));
+        assert_eq!(candles.len(), 1);
+        let candle = &candles[0];
+        assert_eq!(candle.open_time, second(0));
+        assert_eq!((candle.open, candle.high, candle.low, candle.close), (dec!(100), dec!(105), dec!(99), dec!(99)));
+        assert_eq!(candle.volume, dec!(5));
+        assert_eq!(candle.trade_count, Some(4));
+        assert_eq!(candle.exchange_timestamp, second(50));
+    }
+
+    #[test]
+    fn buckets_wait_for_late_trades_then_drop_them() {
+        let mut aggregator = aggregator();
+        aggregator.push(&trade(30, dec!(100), dec!(1)));
+        aggregator.push(&trade(70, dec!(101), dec!(1)));
+
+        // Still within the lateness after the first minute
+        assert!(aggregator.close_until(second(61)).is_empty());
+        assert!(aggregator.push(&trade(59, dec!(103), dec!(1))));
+
+        let candles = aggregator.close_until(second(62));
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].close, dec!(103));
+
+        assert!(!aggregator.push(&trade(45, dec!(104), dec!(1))));
+        assert!(aggregator.close_until(second(62)).is_empty());
+        assert_eq!(aggregator.close_until(second(122))[0].open_time, second(60));
+    }
+
+    fn candle(minute: i64, close: Decimal) -> Candle {
+        Candle::flat(instrument(), CandleInterval::OneMinute, second(minute * 60), close, second(minute * 60))
+    }
+
+    #[test]
+    fn gaps_carry_the_previous_close() {
+        let candles = vec![candle(1, dec!(100)), candle(3, dec!(102))];
+        let filled = fill_gaps(&candles, second(0), second(5 * 60));
+
+        let opens: Vec<DateTime<Utc>> = filled.iter().map(|candle| candle.open_time).collect();
+        assert_eq!(opens, vec![second(60), second(120), second(180), second(240)]);
+        assert_eq!(filled[1].close, dec!(100));
+        assert_eq!(filled[1].volume, Decimal::ZERO);
+        assert_eq!(filled[3].close, dec!(102));
+    }
+
+    #[test]
+    fn roll_up_merges_smaller_buckets() {
+        let mut candles: Vec<Candle> = (0..10).map(|minute| candle(minute, Decimal::from(100 + minute))).collect();
+        for candle in &mut candles {
+            candle.volume = dec!(1);
+            candle.trade_count = Some(2);
+        }
+        candles[7].trade_count = None;
+
+        let rolled = roll_up(&candles, CandleInterval::FiveMinutes);
+        assert_eq!(rolled.len(), 2);
+        assert_eq!((rolled[0].open, rolled[0].close, rolled[0].high), (dec!(100), dec!(104), dec!(104)));
+        assert_eq!(rolled[0].volume, dec!(5));
+        assert_eq!(rolled[0].trade_count, Some(10));
+        assert_eq!(rolled[1].open_time, second(300));
+        assert_eq!(rolled[1].trade_count, None);
+    }
+
+    #[test]
+    fn bucket_count_rounds_partial_buckets_up() {
+        assert_eq!(bucket_count(CandleInterval::FiveMinutes, second(0), second(600)), 2);
+        assert_eq!(bucket_count(CandleInterval::FiveMinutes, second(60), second(601)), 3);
+        assert_eq!(bucket_count(CandleInterval::FiveMinutes, second(600), second(0)), 0);
+    }
+}
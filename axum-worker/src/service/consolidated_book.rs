@@ -0,0 +1,217 @@
+use rust_decimal::Decimal;
+
+use crate::entity::trading::{OrderSide, TradingOrderBook};
+
+/// One venue's order book and the taker fee paid to trade against it
+#[derive(Debug, Clone)]
+pub struct VenueBook {
+    pub book: TradingOrderBook,
+    pub taker_fee_rate: Decimal,
+}
+
+/// Price level of one venue within the consolidated book
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsolidatedLevel {
+    pub exchange: String,
+    pub price: Decimal,
+    /// Price after the taker fee: higher than `price` for asks, lower for bids
+    pub effective_price: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Order books of several venues merged into one, ranked by fee-adjusted price.
+///
+/// Bids are ordered by what a seller would actually receive and asks by what
+/// a buyer would actually pay, so the top of each side is the best venue to
+/// trade with even when fees differ. Levels at the same effective price keep
+/// the order the venues were given in.
+#[derive(Debug, Clone, Default)]
+pub struct ConsolidatedBook {
+    pub bids: Vec<ConsolidatedLevel>,
+    pub asks: Vec<ConsolidatedLevel>,
+}
+
+/// Expected result of a market order walking the book
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillEstimate {
+    pub requested_quantity: Decimal,
+    pub filled_quantity: Decimal,
+    /// Volume-weighted price of the levels consumed, before fees
+    pub average_price: Decimal,
+    /// Volume-weighted price including taker fees
+    pub effective_average_price: Decimal,
+    /// Fee-adjusted price of the first level consumed
+    pub best_effective_price: Decimal,
+    /// How much worse the effective average is than the best effective
+    /// price, in basis points; zero when the first level covers the order
+    pub slippage_bps: Decimal,
+    /// Quote asset spent on a buy or received from a sell, including fees
+    pub total_cost: Decimal,
+    /// Quantity taken from each venue, in the order first used
+    pub allocations: Vec<(String, Decimal)>,
+}
+
+impl ConsolidatedBook {
+    pub fn merge(venues: &[VenueBook]) -> Self {
+        let mut book = Self::default();
+        for venue in venues {
+            let exchange = &venue.book.instrument.exchange;
+            book.bids.extend(venue.book.bids.iter()
+                .filter(|level| level.quantity > Decimal::ZERO)
+                .map(|level| ConsolidatedLevel {
+                    exchange: exchange.clone(),
+                    price: level.price,
+                    effective_price: level.price * (Decimal::ONE - venue.taker_fee_rate),
+                    quantity: level.quantity,
+                }));
+            book.asks.extend(venue.book.asks.iter()
+                .filter(|level| level.quantity > Decimal::ZERO)
+                .map(|level| ConsolidatedLevel {
+                    exchange: exchange.clone(),
+                    price: level.price,
+                    effective_price: level.price * (Decimal::ONE + venue.taker_fee_rate),
+                    quantity: level.quantity,
+                }));
+        }
+        book.bids.sort_by_key(|level| std::cmp::Reverse(level.effective_price));
+        book.asks.sort_by_key(|level| level.effective_price);
+        book
+    }
+
+    pub fn best_bid(&self) -> Option<&ConsolidatedLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&ConsolidatedLevel> {
+        self.asks.first()
+    }
+
+    /// Levels a market order on `side` would consume: asks for a buy, bids for a sell
+    pub fn levels_for(&self, side: &OrderSide) -> &[ConsolidatedLevel] {
+        match side {
+            OrderSide::Buy => &self.asks,
+            OrderSide::Sell => &self.bids,
+        }
+    }
+
+    /// Walk the side of the book a market order would take, best level
+    /// first, until `quantity` is filled or the book runs out. Returns None
+    /// if the side is empty.
+    pub fn estimate_fill(&self, side: &OrderSide, quantity: Decimal) -> Option<FillEstimate> {
+        let levels = self.levels_for(side);
+        let best = levels.first()?;
+
+        let mut remaining = quantity;
+        let mut notional = Decimal::ZERO;
+        let mut effective_notional = Decimal::ZERO;
+        let mut allocations: Vec<(String, Decimal)> = Vec::new();
+        for level in levels {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let taken = remaining.min(level.quantity);
+            notional += taken * level.price;
+            effective_notional += taken * level.effective_price;
+            remaining -= taken;
+            match allocations.iter_mut().find(|(exchange, _)| *exchange == level.exchange) {
+                Some((_, allocated)) => *allocated += taken,
+                None => allocations.push((level.exchange.clone(), taken)),
+            }
+        }
+
+        let filled_quantity = quantity - remaining;
+        if filled_quantity <= Decimal::ZERO {
+            return None;
+        }
+        let effective_average_price = effective_notional / filled_quantity;
+        let slippage = match side {
+            OrderSide::Buy => effective_average_price - best.effective_price,
+            OrderSide::Sell => best.effective_price - effective_average_price,
+        };
+
+        Some(FillEstimate {
+            requested_quantity: quantity,
+            filled_quantity,
+            average_price: notional / filled_quantity,
+            effective_average_price,
+            best_effective_price: best.effective_price,
+            slippage_bps: if best.effective_price > Decimal::ZERO {
+                slippage / best.effective_price * Decimal::new(10_000, 0)
+            } else {
+                Decimal::ZERO
+            },
+            total_cost: effective_notional,
+            allocations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+
+    use crate::entity::trading::{InstrumentType, OrderBookLevel, TradingInstrument};
+
+    fn venue(exchange: &str, taker_fee_rate: Decimal, bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) -> VenueBook {
+        let levels = |levels: &[(Decimal, Decimal)]| levels.iter()
+            .map(|(price, quantity)| OrderBookLevel { price: *price, quantity: *quantity })
+            .collect();
+        VenueBook {
+            book: TradingOrderBook {
+                id: exchange.to_string(),
+                instrument: TradingInstrument::new(
+                    "BTCUSDT".to_string(), "BTC".to_string(), "USDT".to_string(), exchange.to_string(), InstrumentType::Spot,
+                ),
+                bids: levels(bids),
+                asks: levels(asks),
+                timestamp: Utc::now(),
+            },
+            taker_fee_rate,
+        }
+    }
+
+    /// Binance is cheaper on price but Kraken is cheaper after fees
+    fn book() -> ConsolidatedBook {
+        ConsolidatedBook::merge(&[
+            venue("binance", dec!(0.01), &[(dec!(99.5), dec!(1))], &[(dec!(100), dec!(5))]),
+            venue("kraken", dec!(0.001), &[(dec!(99), dec!(2)), (dec!(98), dec!(0))], &[(dec!(100.5), dec!(1))]),
+        ])
+    }
+
+    #[test]
+    fn levels_rank_by_price_after_fees() {
+        let book = book();
+        let best_ask = book.best_ask().unwrap();
+        assert_eq!((best_ask.exchange.as_str(), best_ask.effective_price), ("kraken", dec!(100.6005)));
+        let best_bid = book.best_bid().unwrap();
+        assert_eq!((best_bid.exchange.as_str(), best_bid.effective_price), ("kraken", dec!(98.901)));
+
+        // Empty levels are left out
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(book.bids[1].effective_price, dec!(98.505));
+    }
+
+    #[test]
+    fn fills_walk_across_venues() {
+        let estimate = book().estimate_fill(&OrderSide::Buy, dec!(2)).unwrap();
+        assert_eq!(estimate.filled_quantity, dec!(2));
+        assert_eq!(estimate.average_price, dec!(100.25));
+        assert_eq!(estimate.effective_average_price, dec!(100.80025));
+        assert_eq!(estimate.total_cost, dec!(201.6005));
+        assert_eq!(estimate.allocations, vec![("kraken".to_string(), dec!(1)), ("binance".to_string(), dec!(1))]);
+        assert!(estimate.slippage_bps > Decimal::ZERO);
+    }
+
+    #[test]
+    fn fills_stop_where_the_book_runs_out() {
+        let estimate = book().estimate_fill(&OrderSide::Sell, dec!(10)).unwrap();
+        assert_eq!(estimate.requested_quantity, dec!(10));
+        assert_eq!(estimate.filled_quantity, dec!(3));
+
+        let single = book().estimate_fill(&OrderSide::Sell, dec!(1)).unwrap();
+        assert_eq!(single.slippage_bps, Decimal::ZERO);
+        assert!(ConsolidatedBook::default().estimate_fill(&OrderSide::Buy, dec!(1)).is_none());
+    }
+}
@@ -0,0 +1,99 @@
+use rust_decimal::Decimal;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::entity::trading::PortfolioSnapshot;
+
+/// One point of an equity curve
+#[derive(Debug, Clone)]
+pub struct EquityPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: Decimal,
+    /// Fraction below the running peak, zero at a new high
+    pub drawdown: Decimal,
+}
+
+/// Return over one UTC day, measured between daily closing values
+#[derive(Debug, Clone)]
+pub struct DailyReturn {
+    pub date: NaiveDate,
+    pub close: Decimal,
+    pub return_fraction: Decimal,
+}
+
+/// Equity curve statistics derived from a series of snapshots
+#[derive(Debug, Clone, Default)]
+pub struct EquityCurve {
+    pub points: Vec<EquityPoint>,
+    pub daily_returns: Vec<DailyReturn>,
+    pub max_drawdown: Decimal,
+    pub time_weighted_return: Decimal,
+}
+
+/// Build an equity curve from snapshots ordered oldest first.
+///
+/// Returns are flow-adjusted: a snapshot's `net_flow_usd` is the money moved
+/// in or out since the previous snapshot, so the sub-period return is
+/// `(value - flow) / previous_value - 1`. The time-weighted return chains
+/// those sub-period returns, which removes the effect of deposit timing.
+/// Daily returns use the last snapshot of each UTC day as its close.
+pub fn build_equity_curve(snapshots: &[PortfolioSnapshot]) -> EquityCurve {
+    let mut curve = EquityCurve::default();
+    let mut peak = Decimal::ZERO;
+    let mut growth = Decimal::ONE;
+
+    for (index, snapshot) in snapshots.iter().enumerate() {
+        let value = snapshot.total_value_usd;
+        peak = peak.max(value);
+
+        let drawdown = if peak > Decimal::ZERO { (peak - value) / peak } else { Decimal::ZERO };
+        curve.max_drawdown = curve.max_drawdown.max(drawdown);
+
+        if index > 0 {
+            if let Some(period_return) = period_return(snapshots[index - 1].total_value_usd, value, snapshot.net_flow_usd) {
+                growth *= Decimal::ONE + period_return;
+            }
+        }
+
+        curve.points.push(EquityPoint {
+            timestamp: snapshot.taken_at,
+            value,
+            drawdown,
+        });
+    }
+
+    curve.time_weighted_return = growth - Decimal::ONE;
+    curve.daily_returns = daily_returns(snapshots);
+    curve
+}
+
+/// Closing value and net flow of each UTC day, then the return between consecutive closes
+fn daily_returns(snapshots: &[PortfolioSnapshot]) -> Vec<DailyReturn> {
+    let mut closes: Vec<(NaiveDate, Decimal, Decimal)> = Vec::new();
+    for snapshot in snapshots {
+        let date = snapshot.taken_at.date_naive();
+        match closes.last_mut() {
+            Some((last_date, close, flow)) if *last_date == date => {
+                *close = snapshot.total_value_usd;
+                *flow += snapshot.net_flow_usd;
+            }
+            _ => closes.push((date, snapshot.total_value_usd, snapshot.net_flow_usd)),
+        }
+    }
+
+    closes.windows(2)
+        .filter_map(|pair| {
+            let (_, previous_close, _) = pair[0];
+            let (date, close, flow) = pair[1];
+            period_return(previous_close, close, flow).map(|return_fraction| DailyReturn {
+                date,
+                close,
+                return_fraction,
+            })
+        })
+        .collect()
+}
+
+/// Flow-adjusted return between two values; undefined when starting from nothing
+fn period_return(previous: Decimal, current: Decimal, flow: Decimal) -> Option<Decimal> {
+    (previous > Decimal::ZERO).then(|| (current - flow) / previous - Decimal::ONE)
+}
@@ -0,0 +1,103 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::trading::OrderSide;
+
+/// Decimal places simulated order quantities are rounded to
+pub const QUANTITY_DECIMALS: u32 = 8;
+
+/// How simulated orders fill.
+///
+/// Every fill pays `fee_rate` of its notional. Market orders fill
+/// `slippage_bps` worse than the price they were sent at. Resting limit
+/// orders fill at their limit without slippage, or at a better price when the
+/// market opens through the limit.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FillModel {
+    pub fee_rate: Decimal,
+    pub slippage_bps: Decimal,
+}
+
+impl FillModel {
+    pub fn new(fee_rate: Decimal, slippage_bps: Decimal) -> Self {
+        Self { fee_rate, slippage_bps }
+    }
+
+    /// Price a market order fills at, given the market price when it arrives
+    pub fn market_price(&self, side: &OrderSide, reference_price: Decimal) -> Decimal {
+        let slippage = reference_price * self.slippage_bps / Decimal::new(10_000, 0);
+        match side {
+            OrderSide::Buy => reference_price + slippage,
+            OrderSide::Sell => reference_price - slippage,
+        }
+    }
+
+    /// Whether a resting limit order fills when the market trades between
+    /// `low` and `high`
+    pub fn limit_reached(&self, side: &OrderSide, limit_price: Decimal, low: Decimal, high: Decimal) -> bool {
+        match side {
+            OrderSide::Buy => low <= limit_price,
+            OrderSide::Sell => high >= limit_price,
+        }
+    }
+
+    /// Price a reached limit order fills at: its limit, or `open_price` when
+    /// the market opened on the better side of the limit
+    pub fn limit_price(&self, side: &OrderSide, limit_price: Decimal, open_price: Decimal) -> Decimal {
+        match side {
+            OrderSide::Buy => limit_price.min(open_price),
+            OrderSide::Sell => limit_price.max(open_price),
+        }
+    }
+
+    /// Fee paid in the quote asset on a fill
+    pub fn fee(&self, quantity: Decimal, price: Decimal) -> Decimal {
+        quantity * price * self.fee_rate
+    }
+
+    /// Largest quantity `cash` buys at `price` once the fee is added,
+    /// rounded down to `QUANTITY_DECIMALS` places
+    pub fn affordable_quantity(&self, cash: Decimal, price: Decimal) -> Decimal {
+        if price <= Decimal::ZERO || cash <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+        (cash / (price * (Decimal::ONE + self.fee_rate)))
+            .round_dp_with_strategy(QUANTITY_DECIMALS, RoundingStrategy::ToZero)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn market_orders_slip_against_the_taker() {
+        let model = FillModel::new(dec!(0.001), dec!(10));
+        assert_eq!(model.market_price(&OrderSide::Buy, dec!(100)), dec!(100.1));
+        assert_eq!(model.market_price(&OrderSide::Sell, dec!(100)), dec!(99.9));
+        assert_eq!(model.fee(dec!(2), dec!(100)), dec!(0.2));
+    }
+
+    #[test]
+    fn limits_fill_at_the_limit_or_a_better_open() {
+        let model = FillModel::new(Decimal::ZERO, dec!(10));
+        assert!(model.limit_reached(&OrderSide::Buy, dec!(100), dec!(99), dec!(105)));
+        assert!(!model.limit_reached(&OrderSide::Buy, dec!(100), dec!(101), dec!(105)));
+        assert!(model.limit_reached(&OrderSide::Sell, dec!(100), dec!(95), dec!(100)));
+
+        assert_eq!(model.limit_price(&OrderSide::Buy, dec!(100), dec!(102)), dec!(100));
+        assert_eq!(model.limit_price(&OrderSide::Buy, dec!(100), dec!(98)), dec!(98));
+        assert_eq!(model.limit_price(&OrderSide::Sell, dec!(100), dec!(103)), dec!(103));
+    }
+
+    #[test]
+    fn affordable_quantity_leaves_room_for_the_fee() {
+        let model = FillModel::new(dec!(0.001), Decimal::ZERO);
+        assert_eq!(model.affordable_quantity(dec!(1001), dec!(100)), dec!(10));
+
+        let free = FillModel::new(Decimal::ZERO, Decimal::ZERO);
+        assert_eq!(free.affordable_quantity(dec!(100), dec!(3)), dec!(33.33333333));
+        assert_eq!(free.affordable_quantity(dec!(100), Decimal::ZERO), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,561 @@
+use std::collections::VecDeque;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::{Decimal, MathematicalOps};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::market_data::{Candle, CandleInterval};
+
+/// Longest period any indicator accepts
+pub const MAX_PERIOD: usize = 200;
+
+/// Names of the indicators `AnyIndicator` covers
+pub const INDICATORS: [&str; 7] = ["sma", "ema", "rsi", "macd", "bollinger", "atr", "vwap"];
+
+/// A technical indicator over a candle series.
+///
+/// Indicators are streaming: each candle is folded in with `update`, oldest
+/// first, and the value as of that candle comes back, or None while the
+/// indicator is still warming up. `batch` runs a whole series through the
+/// same code, so batch and streaming values always agree. Every indicator
+/// serializes, so a strategy can save one with its state.
+pub trait Indicator {
+    type Output;
+
+    fn update(&mut self, candle: &Candle) -> Option<Self::Output>;
+
+    /// Candles needed before the first value
+    fn warm_up(&self) -> usize;
+
+    /// Values for each candle of a series, oldest first
+    fn batch(&mut self, candles: &[Candle]) -> Vec<Option<Self::Output>>
+    where
+        Self: Sized,
+    {
+        candles.iter().map(|candle| self.update(candle)).collect()
+    }
+}
+
+fn check_period(name: &str, period: usize) -> Result<(), String> {
+    if period == 0 || period > MAX_PERIOD {
+        return Err(format!("{} must be between 1 and {}", name, MAX_PERIOD));
+    }
+    Ok(())
+}
+
+/// Simple moving average of the closes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<Decimal>,
+    sum: Decimal,
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Result<Self, String> {
+        check_period("period", period)?;
+        Ok(Self { period, window: VecDeque::with_capacity(period + 1), sum: Decimal::ZERO })
+    }
+
+    /// Fold in the next value of any series, not just closes
+    pub fn next(&mut self, value: Decimal) -> Option<Decimal> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        (self.window.len() == self.period).then(|| self.sum / Decimal::from(self.period))
+    }
+}
+
+impl Indicator for Sma {
+    type Output = Decimal;
+
+    fn update(&mut self, candle: &Candle) -> Option<Decimal> {
+        self.next(candle.close)
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+}
+
+/// Exponential moving average of the closes, seeded with the simple average
+/// of the first `period` closes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ema {
+    period: usize,
+    /// Weight of each new value, 2 / (period + 1)
+    alpha: Decimal,
+    seed: Sma,
+    value: Option<Decimal>,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Result<Self, String> {
+        Ok(Self {
+            seed: Sma::new(period)?,
+            alpha: Decimal::TWO / Decimal::from(period + 1),
+            period,
+            value: None,
+        })
+    }
+
+    /// Fold in the next value of any series, not just closes
+    pub fn next(&mut self, value: Decimal) -> Option<Decimal> {
+        self.value = match self.value {
+            Some(previous) => Some(previous + self.alpha * (value - previous)),
+            None => self.seed.next(value),
+        };
+        self.value
+    }
+}
+
+impl Indicator for Ema {
+    type Output = Decimal;
+
+    fn update(&mut self, candle: &Candle) -> Option<Decimal> {
+        self.next(candle.close)
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+}
+
+/// Relative strength index of the closes, from 0 to 100, with Wilder's
+/// smoothing of the average gain and loss
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rsi {
+    period: usize,
+    previous_close: Option<Decimal>,
+    /// Gains and losses summed over the first `period` changes
+    seed_gain: Decimal,
+    seed_loss: Decimal,
+    changes: usize,
+    /// Smoothed average gain and loss, once seeded
+    averages: Option<(Decimal, Decimal)>,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Result<Self, String> {
+        check_period("period", period)?;
+        Ok(Self {
+            period,
+            previous_close: None,
+            seed_gain: Decimal::ZERO,
+            seed_loss: Decimal::ZERO,
+            changes: 0,
+            averages: None,
+        })
+    }
+}
+
+impl Indicator for Rsi {
+    type Output = Decimal;
+
+    fn update(&mut self, candle: &Candle) -> Option<Decimal> {
+        let previous = self.previous_close.replace(candle.close)?;
+        let change = candle.close - previous;
+        let gain = change.max(Decimal::ZERO);
+        let loss = (-change).max(Decimal::ZERO);
+        let period = Decimal::from(self.period);
+
+        let (average_gain, average_loss) = match self.averages {
+            Some((average_gain, average_loss)) => (
+                (average_gain * (period - Decimal::ONE) + gain) / period,
+                (average_loss * (period - Decimal::ONE) + loss) / period,
+            ),
+            None => {
+                self.seed_gain += gain;
+                self.seed_loss += loss;
+                self.changes += 1;
+                if self.changes < self.period {
+                    return None;
+                }
+                (self.seed_gain / period, self.seed_loss / period)
+            }
+        };
+        self.averages = Some((average_gain, average_loss));
+
+        Some(if average_loss.is_zero() {
+            // No losses: 100, or neutral if the price hasn't moved at all
+            if average_gain.is_zero() { Decimal::new(50, 0) } else { Decimal::ONE_HUNDRED }
+        } else {
+            Decimal::ONE_HUNDRED - Decimal::ONE_HUNDRED / (Decimal::ONE + average_gain / average_loss)
+        })
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period + 1
+    }
+}
+
+/// One MACD value
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MacdValue {
+    /// Fast EMA less slow EMA
+    pub macd: Decimal,
+    /// EMA of the MACD line
+    pub signal: Decimal,
+    /// MACD line less signal line
+    pub histogram: Decimal,
+}
+
+/// Moving average convergence divergence of the closes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Result<Self, String> {
+        check_period("fast", fast)?;
+        check_period("slow", slow)?;
+        check_period("signal", signal)?;
+        if fast >= slow {
+            return Err("fast must be below slow".to_string());
+        }
+        Ok(Self { fast: Ema::new(fast)?, slow: Ema::new(slow)?, signal: Ema::new(signal)? })
+    }
+}
+
+impl Indicator for Macd {
+    type Output = MacdValue;
+
+    fn update(&mut self, candle: &Candle) -> Option<MacdValue> {
+        let fast = self.fast.next(candle.close);
+        let slow = self.slow.next(candle.close);
+        let macd = fast? - slow?;
+        let signal = self.signal.next(macd)?;
+        Some(MacdValue { macd, signal, histogram: macd - signal })
+    }
+
+    fn warm_up(&self) -> usize {
+        self.slow.period + self.signal.period - 1
+    }
+}
+
+/// One set of Bollinger Bands
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BandsValue {
+    pub middle: Decimal,
+    pub upper: Decimal,
+    pub lower: Decimal,
+}
+
+/// Bollinger Bands: the simple moving average of the closes, with bands
+/// `multiplier` population standard deviations above and below it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BollingerBands {
+    period: usize,
+    multiplier: Decimal,
+    window: VecDeque<Decimal>,
+}
+
+impl BollingerBands {
+    pub fn new(period: usize, multiplier: Decimal) -> Result<Self, String> {
+        check_period("period", period)?;
+        if multiplier <= Decimal::ZERO {
+            return Err("multiplier must be positive".to_string());
+        }
+        Ok(Self { period, multiplier, window: VecDeque::with_capacity(period + 1) })
+    }
+}
+
+impl Indicator for BollingerBands {
+    type Output = BandsValue;
+
+    fn update(&mut self, candle: &Candle) -> Option<BandsValue> {
+        self.window.push_back(candle.close);
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if self.window.len() < self.period {
+            return None;
+        }
+
+        let count = Decimal::from(self.period);
+        let middle = self.window.iter().sum::<Decimal>() / count;
+        let variance = self.window.iter().map(|close| (close - middle) * (close - middle)).sum::<Decimal>() / count;
+        let width = variance.sqrt().unwrap_or_default() * self.multiplier;
+        Some(BandsValue { middle, upper: middle + width, lower: middle - width })
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+}
+
+/// Average true range, with Wilder's smoothing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Atr {
+    period: usize,
+    previous_close: Option<Decimal>,
+    seed: Sma,
+    value: Option<Decimal>,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Result<Self, String> {
+        Ok(Self { seed: Sma::new(period)?, period, previous_close: None, value: None })
+    }
+}
+
+impl Indicator for Atr {
+    type Output = Decimal;
+
+    fn update(&mut self, candle: &Candle) -> Option<Decimal> {
+        // The first candle has no previous close, so its range is all there is
+        let true_range = match self.previous_close.replace(candle.close) {
+            Some(previous) => (candle.high - candle.low)
+                .max((candle.high - previous).abs())
+                .max((candle.low - previous).abs()),
+            None => candle.high - candle.low,
+        };
+        let period = Decimal::from(self.period);
+        self.value = match self.value {
+            Some(previous) => Some((previous * (period - Decimal::ONE) + true_range) / period),
+            None => self.seed.next(true_range),
+        };
+        self.value
+    }
+
+    fn warm_up(&self) -> usize {
+        self.period
+    }
+}
+
+/// Volume-weighted average of the typical price, (high + low + close) / 3,
+/// since the start of the candle's UTC day
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Vwap {
+    day: Option<NaiveDate>,
+    price_volume: Decimal,
+    volume: Decimal,
+}
+
+impl Vwap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator for Vwap {
+    type Output = Decimal;
+
+    fn update(&mut self, candle: &Candle) -> Option<Decimal> {
+        let day = candle.open_time.date_naive();
+        if self.day != Some(day) {
+            *self = Self { day: Some(day), ..Self::default() };
+        }
+        let typical = (candle.high + candle.low + candle.close) / Decimal::from(3);
+        self.price_volume += typical * candle.volume;
+        self.volume += candle.volume;
+        (self.volume > Decimal::ZERO).then(|| self.price_volume / self.volume)
+    }
+
+    fn warm_up(&self) -> usize {
+        1
+    }
+}
+
+/// Value of any indicator. Single-valued indicators only fill in `value`;
+/// MACD puts its line there, and Bollinger Bands their middle band.
+#[derive(Debug, Clone, Default)]
+pub struct IndicatorValue {
+    pub value: Decimal,
+    pub signal: Option<Decimal>,
+    pub histogram: Option<Decimal>,
+    pub upper: Option<Decimal>,
+    pub lower: Option<Decimal>,
+}
+
+/// One of the indicators above, for callers that choose indicators at
+/// runtime, such as the indicators endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AnyIndicator {
+    Sma(Sma),
+    Ema(Ema),
+    Rsi(Rsi),
+    Macd(Box<Macd>),
+    BollingerBands(BollingerBands),
+    Atr(Atr),
+    Vwap(Vwap),
+}
+
+impl AnyIndicator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnyIndicator::Sma(_) => "sma",
+            AnyIndicator::Ema(_) => "ema",
+            AnyIndicator::Rsi(_) => "rsi",
+            AnyIndicator::Macd(_) => "macd",
+            AnyIndicator::BollingerBands(_) => "bollinger",
+            AnyIndicator::Atr(_) => "atr",
+            AnyIndicator::Vwap(_) => "vwap",
+        }
+    }
+
+    /// Name with settings, e.g. "sma(20)" or "macd(12,26,9)"
+    pub fn label(&self) -> String {
+        match self {
+            AnyIndicator::Sma(sma) => format!("sma({})", sma.period),
+            AnyIndicator::Ema(ema) => format!("ema({})", ema.period),
+            AnyIndicator::Rsi(rsi) => format!("rsi({})", rsi.period),
+            AnyIndicator::Macd(macd) => format!("macd({},{},{})", macd.fast.period, macd.slow.period, macd.signal.period),
+            AnyIndicator::BollingerBands(bands) => format!("bollinger({},{})", bands.period, bands.multiplier.normalize()),
+            AnyIndicator::Atr(atr) => format!("atr({})", atr.period),
+            AnyIndicator::Vwap(_) => "vwap".to_string(),
+        }
+    }
+
+    pub fn update(&mut self, candle: &Candle) -> Option<IndicatorValue> {
+        let single = |value: Decimal| IndicatorValue { value, ..IndicatorValue::default() };
+        match self {
+            AnyIndicator::Sma(sma) => sma.update(candle).map(single),
+            AnyIndicator::Ema(ema) => ema.update(candle).map(single),
+            AnyIndicator::Rsi(rsi) => rsi.update(candle).map(single),
+            AnyIndicator::Atr(atr) => atr.update(candle).map(single),
+            AnyIndicator::Vwap(vwap) => vwap.update(candle).map(single),
+            AnyIndicator::Macd(macd) => macd.update(candle).map(|value| IndicatorValue {
+                value: value.macd,
+                signal: Some(value.signal),
+                histogram: Some(value.histogram),
+                ..IndicatorValue::default()
+            }),
+            AnyIndicator::BollingerBands(bands) => bands.update(candle).map(|value| IndicatorValue {
+                value: value.middle,
+                upper: Some(value.upper),
+                lower: Some(value.lower),
+                ..IndicatorValue::default()
+            }),
+        }
+    }
+
+    /// Where the candle history must begin for the indicator to have a
+    /// settled value at the candle opening at `start`. Indicators smoothed
+    /// with an EMA never quite forget old candles, so they get three times
+    /// their warm-up; VWAP needs the whole UTC day.
+    pub fn history_start(&self, interval: CandleInterval, start: DateTime<Utc>) -> DateTime<Utc> {
+        let candles = match self {
+            AnyIndicator::Sma(sma) => sma.warm_up() - 1,
+            AnyIndicator::BollingerBands(bands) => bands.warm_up() - 1,
+            AnyIndicator::Ema(ema) => ema.warm_up() * 3,
+            AnyIndicator::Rsi(rsi) => rsi.warm_up() * 3,
+            AnyIndicator::Macd(macd) => macd.warm_up() * 3,
+            AnyIndicator::Atr(atr) => atr.warm_up() * 3,
+            AnyIndicator::Vwap(_) => return CandleInterval::OneDay.bucket_start(start),
+        };
+        start - interval.duration() * candles as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    use crate::entity::market_data::{Instrument, InstrumentKind};
+
+    fn hour(i: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::hours(i)
+    }
+
+    fn candle(i: i64, high: Decimal, low: Decimal, close: Decimal, volume: Decimal) -> Candle {
+        let instrument = Instrument::new("BTC".to_string(), "USDT".to_string(), "binance".to_string(), InstrumentKind::Spot);
+        let mut candle = Candle::flat(instrument, CandleInterval::OneHour, hour(i), close, hour(i));
+        candle.high = high;
+        candle.low = low;
+        candle.volume = volume;
+        candle
+    }
+
+    fn closes(values: &[Decimal]) -> Vec<Candle> {
+        values.iter().enumerate()
+            .map(|(i, close)| candle(i as i64, *close, *close, *close, Decimal::ONE))
+            .collect()
+    }
+
+    #[test]
+    fn sma_and_ema_warm_up_then_follow_the_closes() {
+        let candles = closes(&[dec!(1), dec!(2), dec!(3), dec!(4), dec!(5)]);
+        assert_eq!(Sma::new(3).unwrap().batch(&candles), vec![None, None, Some(dec!(2)), Some(dec!(3)), Some(dec!(4))]);
+        // Seeded at the simple average of 2, then half of each move
+        assert_eq!(Ema::new(3).unwrap().batch(&candles), vec![None, None, Some(dec!(2)), Some(dec!(3)), Some(dec!(4))]);
+
+        let mut ema = Ema::new(3).unwrap();
+        let values = ema.batch(&closes(&[dec!(1), dec!(2), dec!(3), dec!(7)]));
+        assert_eq!(values[3], Some(dec!(4.5)));
+    }
+
+    #[test]
+    fn rsi_uses_wilder_smoothing() {
+        let values = Rsi::new(2).unwrap().batch(&closes(&[dec!(10), dec!(12), dec!(11), dec!(13)]));
+        assert_eq!(values[..2], [None, None]);
+        // Average gain 1 and loss 0.5, then 1.5 and 0.25
+        assert_eq!(values[2].map(|value| value.round_dp(4)), Some(dec!(66.6667)));
+        assert_eq!(values[3].map(|value| value.round_dp(4)), Some(dec!(85.7143)));
+
+        assert_eq!(Rsi::new(2).unwrap().batch(&closes(&[dec!(1), dec!(2), dec!(3)]))[2], Some(dec!(100)));
+        assert_eq!(Rsi::new(2).unwrap().batch(&closes(&[dec!(1), dec!(1), dec!(1)]))[2], Some(dec!(50)));
+    }
+
+    #[test]
+    fn bollinger_bands_use_the_population_deviation() {
+        let values = BollingerBands::new(2, dec!(2)).unwrap().batch(&closes(&[dec!(1), dec!(3)]));
+        let bands = values[1].unwrap();
+        assert_eq!((bands.middle, bands.upper, bands.lower), (dec!(2), dec!(4), dec!(0)));
+    }
+
+    #[test]
+    fn atr_counts_gaps_from_the_previous_close() {
+        let candles = vec![
+            candle(0, dec!(10), dec!(8), dec!(9), Decimal::ONE),
+            candle(1, dec!(12), dec!(9), dec!(11), Decimal::ONE),
+            candle(2, dec!(11), dec!(10), dec!(10), Decimal::ONE),
+        ];
+        assert_eq!(Atr::new(2).unwrap().batch(&candles), vec![None, Some(dec!(2.5)), Some(dec!(1.75))]);
+    }
+
+    #[test]
+    fn vwap_restarts_each_utc_day() {
+        let candles = vec![
+            candle(22, dec!(12), dec!(9), dec!(9), dec!(1)),
+            candle(23, dec!(21), dec!(18), dec!(18), dec!(2)),
+            candle(24, dec!(30), dec!(30), dec!(30), dec!(1)),
+        ];
+        assert_eq!(Vwap::new().batch(&candles), vec![Some(dec!(10)), Some(dec!(16)), Some(dec!(30))]);
+    }
+
+    #[test]
+    fn macd_checks_its_periods() {
+        assert!(Macd::new(26, 12, 9).is_err());
+        assert!(Sma::new(0).is_err());
+        assert!(Sma::new(MAX_PERIOD + 1).is_err());
+
+        let macd = Macd::new(12, 26, 9).unwrap();
+        assert_eq!(macd.warm_up(), 34);
+        let mut any = AnyIndicator::Macd(Box::new(macd));
+        assert_eq!(any.label(), "macd(12,26,9)");
+
+        let candles = closes(&(1..=40).map(Decimal::from).collect::<Vec<_>>());
+        let values: Vec<Option<IndicatorValue>> = candles.iter().map(|candle| any.update(candle)).collect();
+        assert!(values[32].is_none());
+        let first = values[33].clone().unwrap();
+        assert_eq!(first.histogram, Some(first.value - first.signal.unwrap()));
+    }
+
+    #[test]
+    fn history_starts_early_enough_to_warm_up() {
+        let start = hour(48);
+        let sma = AnyIndicator::Sma(Sma::new(20).unwrap());
+        assert_eq!(sma.history_start(CandleInterval::OneHour, start), hour(29));
+        let ema = AnyIndicator::Ema(Ema::new(10).unwrap());
+        assert_eq!(ema.history_start(CandleInterval::OneHour, start), hour(18));
+        let vwap = AnyIndicator::Vwap(Vwap::new());
+        assert_eq!(vwap.history_start(CandleInterval::OneHour, hour(50)), hour(48));
+    }
+}
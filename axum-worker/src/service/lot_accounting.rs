@@ -0,0 +1,382 @@
+use std::collections::{HashMap, VecDeque};
+use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
+
+use crate::entity::trading::{CostBasisMethod, OrderSide, TradeExecution};
+use crate::service::pricing::USD_EQUIVALENTS;
+
+/// Quantity of an asset acquired in one execution, with its USD cost per unit
+#[derive(Debug, Clone)]
+pub struct Lot {
+    pub quantity: Decimal,
+    pub unit_cost: Decimal,
+    pub acquired_at: DateTime<Utc>,
+}
+
+/// USD conversion rates needed to book an execution
+#[derive(Debug, Clone, Copy)]
+pub struct ExecutionValuation {
+    /// USD value of one unit of the execution's quote asset
+    pub quote_usd_rate: Decimal,
+    /// USD value of the commission
+    pub fee_usd: Decimal,
+}
+
+/// Open lots and running totals for one asset
+#[derive(Debug, Clone, Default)]
+pub struct AssetPosition {
+    pub asset: String,
+    pub lots: VecDeque<Lot>,
+    pub realized_pnl: Decimal,
+    pub fees_usd: Decimal,
+    /// Commission paid, keyed by commission asset
+    pub fees: HashMap<String, Decimal>,
+    /// USD spent acquiring the asset over the whole history
+    pub total_cost: Decimal,
+    /// Quantity sold without a matching lot (held before our records start)
+    pub unmatched_quantity: Decimal,
+}
+
+impl AssetPosition {
+    pub fn quantity(&self) -> Decimal {
+        self.lots.iter().map(|lot| lot.quantity).sum()
+    }
+
+    /// USD cost of the open lots
+    pub fn cost_basis(&self) -> Decimal {
+        self.lots.iter().map(|lot| lot.quantity * lot.unit_cost).sum()
+    }
+
+    pub fn average_cost(&self) -> Option<Decimal> {
+        let quantity = self.quantity();
+        (quantity > Decimal::ZERO).then(|| self.cost_basis() / quantity)
+    }
+}
+
+/// Lot-accounting ledger that turns executions into realized and unrealized PnL.
+///
+/// Executions must be applied in the order they happened. Each asset is
+/// tracked in USD: buys open lots at their cost including fees, sells close
+/// lots and realize the difference between proceeds (net of fees) and the
+/// matched cost. FIFO closes the oldest lots first, LIFO the newest, and
+/// average cost closes every lot proportionally at the pooled unit cost.
+///
+/// On a cross pair such as ETHBTC the quote asset moves too: buying ETH
+/// disposes of BTC and selling it acquires BTC, both at the BTC price of the
+/// execution, so gains on the BTC spent are realized as well.
+#[derive(Debug, Clone)]
+pub struct LotLedger {
+    method: CostBasisMethod,
+    positions: HashMap<String, AssetPosition>,
+}
+
+impl LotLedger {
+    pub fn new(method: CostBasisMethod) -> Self {
+        Self {
+            method,
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn method(&self) -> CostBasisMethod {
+        self.method
+    }
+
+    /// Book an execution on its base asset, and on its quote asset unless that
+    /// is a dollar, returning the PnL it realized
+    pub fn apply(&mut self, execution: &TradeExecution, valuation: ExecutionValuation) -> Decimal {
+        let base = execution.instrument.base_asset.clone();
+        let quote = execution.instrument.quote_asset.clone();
+
+        let position = self.position_mut(&base);
+        *position.fees.entry(execution.commission_asset.clone()).or_default() += execution.commission;
+        position.fees_usd += valuation.fee_usd;
+
+        // A fee charged in the base asset changes the quantity moved rather than the USD amount
+        let fee_in_base = execution.commission_asset == base;
+        let base_fee = if fee_in_base { execution.commission } else { Decimal::ZERO };
+        let external_fee_usd = if fee_in_base { Decimal::ZERO } else { valuation.fee_usd };
+        let notional = execution.quantity * execution.price;
+        let notional_usd = notional * valuation.quote_usd_rate;
+
+        let mut realized = match execution.side {
+            OrderSide::Buy => {
+                self.acquire(&base, execution.quantity - base_fee, notional_usd + external_fee_usd, execution.executed_at);
+                Decimal::ZERO
+            }
+            OrderSide::Sell => self.dispose(&base, execution.quantity + base_fee, notional_usd - external_fee_usd),
+        };
+
+        if !USD_EQUIVALENTS.contains(&quote.as_str()) {
+            // The fee's USD value is already booked on the base asset
+            let quote_fee = if execution.commission_asset == quote { execution.commission } else { Decimal::ZERO };
+            match execution.side {
+                OrderSide::Buy => {
+                    let spent = notional + quote_fee;
+                    realized += self.dispose(&quote, spent, spent * valuation.quote_usd_rate);
+                }
+                OrderSide::Sell => {
+                    let received = notional - quote_fee;
+                    self.acquire(&quote, received, received * valuation.quote_usd_rate, execution.executed_at);
+                }
+            }
+        }
+        realized
+    }
+
+    fn position_mut(&mut self, asset: &str) -> &mut AssetPosition {
+        self.positions.entry(asset.to_string()).or_insert_with(|| AssetPosition {
+            asset: asset.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Open a lot of `quantity` costing `cost` USD in total
+    fn acquire(&mut self, asset: &str, quantity: Decimal, cost: Decimal, acquired_at: DateTime<Utc>) {
+        let position = self.position_mut(asset);
+        position.total_cost += cost;
+
+        if quantity > Decimal::ZERO {
+            position.lots.push_back(Lot {
+                quantity,
+                unit_cost: cost / quantity,
+                acquired_at,
+            });
+        }
+    }
+
+    /// Close lots for `quantity` sold for `proceeds` USD, returning the PnL realized
+    fn dispose(&mut self, asset: &str, quantity: Decimal, proceeds: Decimal) -> Decimal {
+        let method = self.method;
+        let position = self.position_mut(asset);
+        let (matched, matched_cost) = Self::close_lots(&mut position.lots, quantity, method);
+
+        position.unmatched_quantity += quantity - matched;
+        if matched <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        // Only the part of the sale backed by known lots has a cost basis
+        let realized = proceeds * matched / quantity - matched_cost;
+        position.realized_pnl += realized;
+        realized
+    }
+
+    pub fn position(&self, asset: &str) -> Option<&AssetPosition> {
+        self.positions.get(asset)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &AssetPosition> {
+        self.positions.values()
+    }
+
+    /// Unrealized PnL of an asset's open lots at a price, optionally only
+    /// counting lots acquired since a point in time
+    pub fn unrealized_pnl(&self, asset: &str, price: Decimal, since: Option<DateTime<Utc>>) -> Decimal {
+        let Some(position) = self.positions.get(asset) else { return Decimal::ZERO };
+        let average_cost = position.average_cost().unwrap_or(Decimal::ZERO);
+
+        position.lots.iter()
+            .filter(|lot| since.is_none_or(|since| lot.acquired_at >= since))
+            .map(|lot| {
+                let unit_cost = match self.method {
+                    CostBasisMethod::AverageCost => average_cost,
+                    _ => lot.unit_cost,
+                };
+                lot.quantity * (price - unit_cost)
+            })
+            .sum()
+    }
+
+    /// Remove up to `quantity` from the lots, returning the quantity matched
+    /// and its USD cost
+    fn close_lots(lots: &mut VecDeque<Lot>, quantity: Decimal, method: CostBasisMethod) -> (Decimal, Decimal) {
+        let held: Decimal = lots.iter().map(|lot| lot.quantity).sum();
+        if held <= Decimal::ZERO {
+            return (Decimal::ZERO, Decimal::ZERO);
+        }
+
+        if method == CostBasisMethod::AverageCost {
+            let matched = quantity.min(held);
+            let pooled_cost: Decimal = lots.iter().map(|lot| lot.quantity * lot.unit_cost).sum();
+            let remaining_fraction = (held - matched) / held;
+
+            for lot in lots.iter_mut() {
+                lot.quantity *= remaining_fraction;
+            }
+            lots.retain(|lot| lot.quantity > Decimal::ZERO);
+
+            return (matched, pooled_cost * matched / held);
+        }
+
+        let mut matched = Decimal::ZERO;
+        let mut matched_cost = Decimal::ZERO;
+        while matched < quantity {
+            let lot = match method {
+                CostBasisMethod::Lifo => lots.back_mut(),
+                _ => lots.front_mut(),
+            };
+            let Some(lot) = lot else { break };
+
+            let take = lot.quantity.min(quantity - matched);
+            matched += take;
+            matched_cost += take * lot.unit_cost;
+            lot.quantity -= take;
+
+            if lot.quantity <= Decimal::ZERO {
+                match method {
+                    CostBasisMethod::Lifo => lots.pop_back(),
+                    _ => lots.pop_front(),
+                };
+            }
+        }
+
+        (matched, matched_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    use crate::entity::trading::{InstrumentType, TradingInstrument};
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn execution(base: &str, quote: &str, side: OrderSide, quantity: Decimal, price: Decimal, minutes: i64) -> TradeExecution {
+        TradeExecution {
+            id: format!("execution-{}", minutes),
+            order_id: "order".to_string(),
+            user_id: "alice".to_string(),
+            exchange_trade_id: format!("trade-{}", minutes),
+            instrument: TradingInstrument::new(
+                format!("{}{}", base, quote), base.to_string(), quote.to_string(), "binance".to_string(), InstrumentType::Spot,
+            ),
+            side,
+            quantity,
+            price,
+            commission: Decimal::ZERO,
+            commission_asset: quote.to_string(),
+            is_maker: false,
+            executed_at: at(minutes),
+        }
+    }
+
+    fn at_par() -> ExecutionValuation {
+        ExecutionValuation { quote_usd_rate: Decimal::ONE, fee_usd: Decimal::ZERO }
+    }
+
+    /// Two buys at 100 and 200, then a sale of one unit at 300
+    fn realized_after_two_buys(method: CostBasisMethod) -> (LotLedger, Decimal) {
+        let mut ledger = LotLedger::new(method);
+        ledger.apply(&execution("BTC", "USD", OrderSide::Buy, dec!(1), dec!(100), 0), at_par());
+        ledger.apply(&execution("BTC", "USD", OrderSide::Buy, dec!(1), dec!(200), 10), at_par());
+        let realized = ledger.apply(&execution("BTC", "USD", OrderSide::Sell, dec!(1), dec!(300), 20), at_par());
+        (ledger, realized)
+    }
+
+    #[test]
+    fn methods_match_different_lots() {
+        let (fifo, realized) = realized_after_two_buys(CostBasisMethod::Fifo);
+        assert_eq!(realized, dec!(200));
+        assert_eq!(fifo.position("BTC").unwrap().cost_basis(), dec!(200));
+
+        let (lifo, realized) = realized_after_two_buys(CostBasisMethod::Lifo);
+        assert_eq!(realized, dec!(100));
+        assert_eq!(lifo.position("BTC").unwrap().cost_basis(), dec!(100));
+
+        let (average, realized) = realized_after_two_buys(CostBasisMethod::AverageCost);
+        assert_eq!(realized, dec!(150));
+        assert_eq!(average.position("BTC").unwrap().cost_basis(), dec!(150));
+    }
+
+    #[test]
+    fn unrealized_pnl_can_be_limited_to_recent_lots() {
+        let mut ledger = LotLedger::new(CostBasisMethod::Fifo);
+        ledger.apply(&execution("BTC", "USD", OrderSide::Buy, dec!(1), dec!(100), 0), at_par());
+        ledger.apply(&execution("BTC", "USD", OrderSide::Buy, dec!(1), dec!(200), 10), at_par());
+
+        assert_eq!(ledger.unrealized_pnl("BTC", dec!(250), None), dec!(200));
+        assert_eq!(ledger.unrealized_pnl("BTC", dec!(250), Some(at(5))), dec!(50));
+        assert_eq!(ledger.unrealized_pnl("ETH", dec!(250), None), Decimal::ZERO);
+    }
+
+    #[test]
+    fn base_asset_fees_reduce_the_quantity_received() {
+        let mut ledger = LotLedger::new(CostBasisMethod::Fifo);
+        let mut buy = execution("BTC", "USD", OrderSide::Buy, dec!(1), dec!(100), 0);
+        buy.commission = dec!(0.01);
+        buy.commission_asset = "BTC".to_string();
+        ledger.apply(&buy, ExecutionValuation { quote_usd_rate: Decimal::ONE, fee_usd: dec!(1) });
+
+        let position = ledger.position("BTC").unwrap();
+        assert_eq!(position.quantity(), dec!(0.99));
+        assert_eq!(position.cost_basis().round_dp(8), dec!(100));
+        assert_eq!(position.fees.get("BTC"), Some(&dec!(0.01)));
+        assert_eq!(position.fees_usd, dec!(1));
+    }
+
+    #[test]
+    fn sales_beyond_known_lots_are_unmatched() {
+        let mut ledger = LotLedger::new(CostBasisMethod::Fifo);
+        ledger.apply(&execution("BTC", "USD", OrderSide::Buy, dec!(1), dec!(100), 0), at_par());
+        let realized = ledger.apply(&execution("BTC", "USD", OrderSide::Sell, dec!(2), dec!(150), 10), at_par());
+
+        // Only the half of the proceeds backed by the known lot is realized
+        assert_eq!(realized, dec!(50));
+        let position = ledger.position("BTC").unwrap();
+        assert_eq!(position.unmatched_quantity, dec!(1));
+        assert!(position.lots.is_empty());
+    }
+
+    #[test]
+    fn non_usd_quotes_use_the_rate_of_each_execution() {
+        let mut ledger = LotLedger::new(CostBasisMethod::Fifo);
+        ledger.apply(
+            &execution("ETH", "BTC", OrderSide::Buy, dec!(1), dec!(0.05), 0),
+            ExecutionValuation { quote_usd_rate: dec!(60000), fee_usd: Decimal::ZERO },
+        );
+        assert_eq!(ledger.position("ETH").unwrap().total_cost, dec!(3000));
+
+        // ETH gained against BTC, but BTC fell, so nothing was made in USD
+        let realized = ledger.apply(
+            &execution("ETH", "BTC", OrderSide::Sell, dec!(1), dec!(0.06), 10),
+            ExecutionValuation { quote_usd_rate: dec!(50000), fee_usd: Decimal::ZERO },
+        );
+        assert_eq!(realized, Decimal::ZERO);
+    }
+
+    #[test]
+    fn cross_pairs_move_the_quote_asset_too() {
+        let mut ledger = LotLedger::new(CostBasisMethod::Fifo);
+        ledger.apply(&execution("BTC", "USD", OrderSide::Buy, dec!(1), dec!(40000), 0), at_par());
+
+        // Buying ETH spends BTC bought at 40000 while it is worth 60000
+        let realized = ledger.apply(
+            &execution("ETH", "BTC", OrderSide::Buy, dec!(1), dec!(0.05), 10),
+            ExecutionValuation { quote_usd_rate: dec!(60000), fee_usd: Decimal::ZERO },
+        );
+        assert_eq!(realized, dec!(1000));
+        assert_eq!(ledger.position("BTC").unwrap().quantity(), dec!(0.95));
+        assert_eq!(ledger.position("ETH").unwrap().cost_basis(), dec!(3000));
+
+        // Selling it brings BTC back at that moment's price
+        let realized = ledger.apply(
+            &execution("ETH", "BTC", OrderSide::Sell, dec!(1), dec!(0.06), 20),
+            ExecutionValuation { quote_usd_rate: dec!(50000), fee_usd: Decimal::ZERO },
+        );
+        assert_eq!(realized, Decimal::ZERO);
+        assert!(ledger.position("ETH").unwrap().lots.is_empty());
+        let btc = ledger.position("BTC").unwrap();
+        assert_eq!(btc.quantity(), dec!(1.01));
+        assert_eq!(btc.cost_basis(), dec!(41000));
+
+        let realized = ledger.apply(&execution("BTC", "USD", OrderSide::Sell, dec!(1.01), dec!(55000), 30), at_par());
+        assert_eq!(realized, dec!(14550));
+        assert_eq!(ledger.position("BTC").unwrap().unmatched_quantity, Decimal::ZERO);
+    }
+}
@@ -2,17 +2,21 @@ use std::collections::HashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
 use worker::console_log;
-use rust_decimal::Decimal;
-use chrono::Utc;
 
+use crate::clients::exchange_governor::GovernorClient;
+use crate::clients::exchange_policy::ExchangePolicy;
+use crate::clients::trading::{Exchange, Side, SimpleInstrument, TradingClient};
 use crate::entity::market_data::{
     Instrument, InstrumentKind, Trade, TradeSide
 };
 use crate::dto::market_data::{
     MarketDataSubscriptionRequest, MarketDataSubscriptionResponse,
-    GetInstrumentsResponse, InstrumentDto
+    GetInstrumentsResponse, InstrumentDto, GetTradesRequest, GetTradesResponse, TradeDto
 };
 
+/// Trades returned when a request has no limit
+const DEFAULT_TRADES_LIMIT: u32 = 100;
+
 /// Market data service inspired by barter-rs architecture but WASM-compatible
 #[derive(Clone)]
 pub struct MarketDataService {
@@ -22,6 +26,10 @@ pub struct MarketDataService {
     instruments: Vec<Instrument>,
     // Service status
     is_ready: bool,
+    // Counts public calls against the exchanges' rate limits
+    governor: GovernorClient,
+    // Timeouts, retries and circuit breaking of those calls
+    policy: ExchangePolicy,
 }
 
 /// Represents an active market data subscription
@@ -37,29 +45,27 @@ pub struct MarketDataSubscription {
 
 impl MarketDataService {
     pub fn new() -> Self {
-        let mut instruments = Vec::new();
-
         // Add some demo instruments for testing (inspired by barter-rs patterns)
-        instruments.push(Instrument::new(
-            "BTC".to_string(),
-            "USDT".to_string(),
-            "binance".to_string(),
-            InstrumentKind::Spot,
-        ));
-
-        instruments.push(Instrument::new(
-            "ETH".to_string(),
-            "USDT".to_string(),
-            "binance".to_string(),
-            InstrumentKind::Spot,
-        ));
-
-        instruments.push(Instrument::new(
-            "BTC".to_string(),
-            "USD".to_string(),
-            "coinbase".to_string(),
-            InstrumentKind::Spot,
-        ));
+        let instruments = vec![
+            Instrument::new(
+                "BTC".to_string(),
+                "USDT".to_string(),
+                "binance".to_string(),
+                InstrumentKind::Spot,
+            ),
+            Instrument::new(
+                "ETH".to_string(),
+                "USDT".to_string(),
+                "binance".to_string(),
+                InstrumentKind::Spot,
+            ),
+            Instrument::new(
+                "BTC".to_string(),
+                "USD".to_string(),
+                "coinbase".to_string(),
+                InstrumentKind::Spot,
+            ),
+        ];
 
         console_log!("MARKET DATA: Initialized service with {} instruments (barter-rs inspired)", instruments.len());
 
@@ -67,9 +73,23 @@ impl MarketDataService {
             subscriptions: Rc::new(RefCell::new(HashMap::new())),
             instruments,
             is_ready: true,
+            governor: GovernorClient::new(None),
+            policy: ExchangePolicy::new(None, None),
         }
     }
 
+    /// Count the service's exchange calls against the exchanges' rate limits
+    pub fn with_governor(mut self, governor: GovernorClient) -> Self {
+        self.governor = governor;
+        self
+    }
+
+    /// Apply timeouts, retries and circuit breaking to the service's exchange calls
+    pub fn with_policy(mut self, policy: ExchangePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
     /// Subscribe to market data for a specific instrument (barter-rs inspired)
     pub async fn subscribe(&self, request: MarketDataSubscriptionRequest) -> Result<MarketDataSubscriptionResponse, String> {
         console_log!("MARKET DATA: Subscribing to {} {}/{} on {} (barter-rs style)",
@@ -102,6 +122,12 @@ impl MarketDataService {
             success: true,
             message: "Subscription created successfully (barter-rs inspired)".to_string(),
             subscription_id: Some(subscription_id),
+            stream_url: Some(format!(
+                "/api/market-data/stream?exchange={}&base={}&quote={}",
+                request.exchange.to_lowercase(),
+                request.base.to_uppercase(),
+                request.quote.to_uppercase(),
+            )),
         })
     }
 
@@ -112,9 +138,9 @@ impl MarketDataService {
         let filtered_instruments: Vec<InstrumentDto> = self.instruments
             .iter()
             .filter(|instrument| {
-                exchange_filter.as_ref().map_or(true, |exchange| &instrument.exchange == exchange)
+                exchange_filter.as_ref().is_none_or(|exchange| &instrument.exchange == exchange)
             })
-            .map(|instrument| InstrumentDto::from(instrument))
+            .map(InstrumentDto::from)
             .collect();
 
         console_log!("MARKET DATA: Found {} instruments (following barter-rs patterns)", filtered_instruments.len());
@@ -124,39 +150,48 @@ impl MarketDataService {
         })
     }
 
-    /// Generate a sample trade for demo purposes (barter-rs inspired)
-    pub fn generate_sample_trade(&self, exchange: &str, base: &str, quote: &str) -> Result<Trade, String> {
-        console_log!("MARKET DATA: Generating sample trade for {}/{} on {} (barter-rs style)", base, quote, exchange);
+    /// Get the most recent public trades from the exchange, newest first
+    pub async fn get_recent_trades(&self, request: GetTradesRequest) -> Result<GetTradesResponse, String> {
+        console_log!("MARKET DATA: Getting recent trades for {}/{} on {}", request.base, request.quote, request.exchange);
 
+        let exchange = Exchange::parse(&request.exchange)
+            .ok_or_else(|| format!("Unsupported exchange: {}", request.exchange))?;
         let instrument = Instrument::new(
-            base.to_string(),
-            quote.to_string(),
-            exchange.to_string(),
+            request.base.clone(),
+            request.quote.clone(),
+            request.exchange.clone(),
             InstrumentKind::Spot,
         );
 
-        // Generate mock trade data (following barter-rs patterns)
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        Utc::now().timestamp_nanos_opt().unwrap_or(0).hash(&mut hasher);
-        let random_seed = hasher.finish();
-
-        let price = Decimal::new(50000 + ((random_seed % 1000) as i64), 0);
-        let quantity = Decimal::new(100 + ((random_seed % 50) as i64), 3);
-        let side = if random_seed % 2 == 0 { TradeSide::Buy } else { TradeSide::Sell };
-
-        let trade = Trade::new(
-            instrument,
-            price,
-            quantity,
-            side,
-            Utc::now(),
-        );
+        // Public trades need no credentials
+        let client = TradingClient::new(exchange, None, None)
+            .with_governor(self.governor.clone())
+            .with_policy(self.policy.clone());
+        let public_trades = client.get_recent_trades(
+            &SimpleInstrument { base: instrument.base.clone(), quote: instrument.quote.clone() },
+            request.limit.unwrap_or(DEFAULT_TRADES_LIMIT),
+        ).await?;
+
+        let trades: Vec<TradeDto> = public_trades.into_iter()
+            .map(|trade| {
+                let side = match trade.side {
+                    Side::Buy => TradeSide::Buy,
+                    Side::Sell => TradeSide::Sell,
+                };
+                let trade = Trade::from_exchange(
+                    trade.exchange_trade_id,
+                    instrument.clone(),
+                    trade.price,
+                    trade.quantity,
+                    side,
+                    trade.executed_at,
+                );
+                TradeDto::from(&trade)
+            })
+            .collect();
 
-        console_log!("MARKET DATA: Generated sample trade at price {} (barter-rs inspired)", price);
-        Ok(trade)
+        console_log!("MARKET DATA: Found {} recent trades for {}", trades.len(), instrument.id);
+        Ok(GetTradesResponse { trades })
     }
 
     /// Get active subscriptions count (barter-rs inspired)
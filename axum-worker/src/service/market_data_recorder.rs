@@ -0,0 +1,56 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::entity::market_data::{CandleInterval, RecordedChunk};
+
+/// Buffers one instrument's stream events into chunks for the archive.
+///
+/// A chunk is finished when it reaches `max_events`, when it has been open
+/// for `max_age`, or when an event from the next hour arrives, so no chunk
+/// spans two hours.
+#[derive(Debug, Clone)]
+pub struct MarketDataRecorder {
+    instrument_id: String,
+    max_events: usize,
+    max_age: Duration,
+    chunk: Option<RecordedChunk>,
+}
+
+impl MarketDataRecorder {
+    pub fn new(instrument_id: String, max_events: usize, max_age: Duration) -> Self {
+        Self {
+            instrument_id,
+            max_events: max_events.max(1),
+            max_age,
+            chunk: None,
+        }
+    }
+
+    /// Add an event's JSON, returning any chunks it finished
+    pub fn record(&mut self, line: String, timestamp: DateTime<Utc>) -> Vec<RecordedChunk> {
+        let mut finished = Vec::new();
+
+        let hour = CandleInterval::OneHour.bucket_start(timestamp);
+        if self.chunk.as_ref().is_some_and(|chunk| CandleInterval::OneHour.bucket_start(chunk.start) != hour) {
+            finished.extend(self.chunk.take());
+        }
+
+        let chunk = self.chunk.get_or_insert_with(|| RecordedChunk {
+            instrument_id: self.instrument_id.clone(),
+            start: timestamp,
+            end: timestamp,
+            lines: Vec::new(),
+        });
+        chunk.lines.push(line);
+        chunk.end = chunk.end.max(timestamp);
+
+        if chunk.lines.len() >= self.max_events || chunk.end - chunk.start >= self.max_age {
+            finished.extend(self.chunk.take());
+        }
+        finished
+    }
+
+    /// Finish the open chunk, if it has any events
+    pub fn flush(&mut self) -> Option<RecordedChunk> {
+        self.chunk.take()
+    }
+}
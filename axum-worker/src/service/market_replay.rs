@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::Stream;
+use worker::{console_log, Delay};
+
+use crate::dto::market_data::MarketDataEventResponse;
+use crate::entity::market_data::Instrument;
+use crate::repo::market_data_archive::MarketDataArchive;
+
+/// Longest wait between two replayed events, before the speed is applied,
+/// so quiet stretches of a recording don't stall the replay
+const MAX_REPLAY_GAP_SECONDS: i64 = 5;
+
+/// What to replay and how fast
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Multiple of real time; 0 sends events as fast as the client reads them
+    pub speed: f64,
+    pub channels: Vec<String>,
+}
+
+/// Replays recorded market data from the archive
+#[derive(Clone)]
+pub struct MarketReplayService {
+    archive: MarketDataArchive,
+}
+
+/// Position within a replay
+struct ReplayState {
+    archive: MarketDataArchive,
+    keys: VecDeque<String>,
+    events: VecDeque<MarketDataEventResponse>,
+    previous: Option<DateTime<Utc>>,
+    options: ReplayOptions,
+}
+
+impl MarketReplayService {
+    pub fn new(archive: MarketDataArchive) -> Self {
+        Self { archive }
+    }
+
+    /// Stream an instrument's recorded events between `options.start` and
+    /// `options.end` in their original order, spaced by the time between
+    /// them divided by `options.speed`. Returns None if nothing was
+    /// recorded in the range.
+    pub async fn replay(
+        &self,
+        instrument: &Instrument,
+        options: ReplayOptions,
+    ) -> Result<Option<impl Stream<Item = MarketDataEventResponse>>, String> {
+        let keys = self.archive.list_chunks(&instrument.id, options.start, options.end).await?;
+        if keys.is_empty() {
+            return Ok(None);
+        }
+        console_log!("MARKET REPLAY: Replaying {} chunks of {} at {}x", keys.len(), instrument.id, options.speed);
+
+        let state = ReplayState {
+            archive: self.archive.clone(),
+            keys: keys.into(),
+            events: VecDeque::new(),
+            previous: None,
+            options,
+        };
+        Ok(Some(futures::stream::unfold(state, |mut state| async move {
+            let event = state.next_event().await?;
+
+            if let Some(previous) = state.previous.filter(|_| state.options.speed > 0.0) {
+                let gap = (event.timestamp - previous).clamp(Duration::zero(), Duration::seconds(MAX_REPLAY_GAP_SECONDS));
+                if let Ok(gap) = gap.to_std() {
+                    Delay::from(gap.div_f64(state.options.speed)).await;
+                }
+            }
+            state.previous = Some(event.timestamp);
+            Some((event, state))
+        })))
+    }
+}
+
+impl ReplayState {
+    /// Next event in range on the requested channels, loading chunks as needed
+    async fn next_event(&mut self) -> Option<MarketDataEventResponse> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+
+            let key = self.keys.pop_front()?;
+            match self.archive.load_chunk(&key).await {
+                Ok(events) => self.events.extend(events.into_iter().filter(|event| {
+                    event.timestamp >= self.options.start
+                        && event.timestamp < self.options.end
+                        && self.options.channels.contains(&event.event_type)
+                })),
+                // Skip a damaged chunk rather than ending the replay
+                Err(e) => console_log!("MARKET REPLAY: Skipping chunk: {}", e),
+            }
+        }
+    }
+}
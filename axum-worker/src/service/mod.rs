@@ -1,4 +1,31 @@
+pub mod account_ledger;
+pub mod alert;
 pub mod auth;
+pub mod backtest;
+pub mod backtest_engine;
+pub mod bots;
+pub mod candle;
+pub mod candle_aggregator;
+pub mod consolidated_book;
+pub mod equity_curve;
+pub mod fill_model;
+pub mod indicators;
+pub mod lot_accounting;
 pub mod market_data;
+pub mod market_data_recorder;
+pub mod market_replay;
 pub mod trading;
-
+pub mod order_book;
+pub mod order_group;
+pub mod pricing;
+pub mod rate_limit;
+pub mod risk;
+pub mod scheduler;
+pub mod signal;
+pub mod snapshot;
+pub mod strategy;
+pub mod strategy_account;
+pub mod strategy_runtime;
+pub mod strategy_service;
+pub mod trigger_monitor;
+pub mod webhook;
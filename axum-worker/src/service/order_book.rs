@@ -0,0 +1,471 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::entity::market_data::{BookSnapshot, BookUpdate, Instrument, OrderBook, OrderBookLevel};
+
+/// Most updates held while waiting for a snapshot; older ones are dropped,
+/// which shows up as a gap once the snapshot arrives
+const MAX_PENDING_UPDATES: usize = 1000;
+
+/// Levels per side covered by OKX's order book checksum
+pub const CHECKSUM_DEPTH: usize = 25;
+
+/// Levels per side covered by Kraken's order book checksum
+pub const KRAKEN_CHECKSUM_DEPTH: usize = 10;
+
+/// How an exchange numbers its order book updates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceRule {
+    /// Each update covers the ids `first..=last` (Binance spot `U`/`u`). The
+    /// first update applied after a snapshot must cover the snapshot's id + 1,
+    /// and each later one must start right after the previous one ends.
+    UpdateRange,
+    /// Each update names the id of the update before it (Binance futures
+    /// `pu`, OKX `prevSeqId`). The first update after a snapshot follows the
+    /// `UpdateRange` rule.
+    PreviousId,
+    /// Ids go up by exactly one per update (Bybit `u`)
+    Contiguous,
+    /// Updates carry no ids and apply in the order they arrive (Kraken, and
+    /// Coinbase's ticker, whose every message is a new snapshot)
+    Unsequenced,
+}
+
+/// How an exchange computes the checksum it sends with the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumFormat {
+    /// OKX: the top 25 levels interleaved best first as
+    /// `bid_price:bid_size:ask_price:ask_size:...`, continuing with the
+    /// longer side once the shorter one runs out, written as sent
+    Interleaved,
+    /// Kraken: the top 10 asks, then the top 10 bids, each price and
+    /// quantity written at the pair's precision with the decimal point and
+    /// leading zeros removed, and no separators
+    Concatenated { price_precision: u32, quantity_precision: u32 },
+}
+
+/// Why the local book stopped matching the exchange's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookSyncError {
+    /// An update was missed between the last one applied and this one
+    Gap { expected: u64, received: u64 },
+    /// The book no longer matches the exchange's checksum
+    ChecksumMismatch { expected: i32, computed: i32 },
+}
+
+/// What applying an update did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    Applied,
+    /// The update is already covered by the book and was skipped
+    Stale,
+    /// No snapshot has arrived yet; the update is held until one does
+    Buffered,
+}
+
+impl std::fmt::Display for BookSyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookSyncError::Gap { expected, received } =>
+                write!(f, "sequence gap: expected update {}, received {}", expected, received),
+            BookSyncError::ChecksumMismatch { expected, computed } =>
+                write!(f, "checksum mismatch: exchange sent {}, book has {}", expected, computed),
+        }
+    }
+}
+
+/// Level 2 order book kept in sync from a snapshot plus incremental updates.
+///
+/// Updates that arrive before the snapshot are buffered and replayed onto it.
+/// Every update is checked against the exchange's sequence rule and, when the
+/// exchange sends one, its checksum. On a gap or checksum mismatch the book
+/// is cleared and the caller must resync it from a fresh snapshot.
+#[derive(Debug, Clone)]
+pub struct LocalOrderBook {
+    instrument: Instrument,
+    rule: SequenceRule,
+    checksum_format: ChecksumFormat,
+    /// Levels per side kept, for exchanges that don't remove levels falling
+    /// out of their subscription's depth
+    max_levels: Option<usize>,
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    /// Id of the last snapshot or update applied; None until synced
+    last_update_id: Option<u64>,
+    /// Whether an update has been applied since the snapshot
+    updated_since_snapshot: bool,
+    pending: Vec<BookUpdate>,
+    exchange_timestamp: DateTime<Utc>,
+}
+
+impl LocalOrderBook {
+    pub fn new(instrument: Instrument, rule: SequenceRule) -> Self {
+        Self {
+            instrument,
+            rule,
+            checksum_format: ChecksumFormat::Interleaved,
+            max_levels: None,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            last_update_id: None,
+            updated_since_snapshot: false,
+            pending: Vec::new(),
+            exchange_timestamp: Utc::now(),
+        }
+    }
+
+    /// Keep only the top `levels` of each side after every change
+    pub fn with_max_levels(mut self, levels: usize) -> Self {
+        self.max_levels = Some(levels);
+        self
+    }
+
+    /// Verify the exchange's checksums in this format instead of OKX's
+    pub fn with_checksum_format(mut self, format: ChecksumFormat) -> Self {
+        self.checksum_format = format;
+        self
+    }
+
+    /// Whether the book has a snapshot and hasn't lost sync since
+    pub fn is_synced(&self) -> bool {
+        self.last_update_id.is_some()
+    }
+
+    /// Id of the last snapshot or update applied
+    pub fn last_update_id(&self) -> Option<u64> {
+        self.last_update_id
+    }
+
+    /// Replace the book with a snapshot, then replay any buffered updates
+    /// newer than it
+    pub fn apply_snapshot(&mut self, snapshot: BookSnapshot) -> Result<(), BookSyncError> {
+        self.bids.clear();
+        self.asks.clear();
+        for level in &snapshot.bids {
+            apply_level(&mut self.bids, level);
+        }
+        for level in &snapshot.asks {
+            apply_level(&mut self.asks, level);
+        }
+        self.truncate();
+        self.last_update_id = Some(snapshot.last_update_id);
+        self.updated_since_snapshot = false;
+        self.exchange_timestamp = snapshot.exchange_timestamp;
+
+        if let Some(expected) = snapshot.checksum {
+            self.verify_checksum(expected)?;
+        }
+
+        for update in std::mem::take(&mut self.pending) {
+            self.apply_update(update)?;
+        }
+        Ok(())
+    }
+
+    /// Apply an incremental update, checking it follows the last one applied
+    pub fn apply_update(&mut self, update: BookUpdate) -> Result<ApplyOutcome, BookSyncError> {
+        let Some(last) = self.last_update_id else {
+            if self.pending.len() >= MAX_PENDING_UPDATES {
+                self.pending.remove(0);
+            }
+            self.pending.push(update);
+            return Ok(ApplyOutcome::Buffered);
+        };
+
+        if self.rule != SequenceRule::Unsequenced {
+            if update.last_update_id <= last {
+                return Ok(ApplyOutcome::Stale);
+            }
+            let gap = match self.rule {
+                SequenceRule::PreviousId if self.updated_since_snapshot => update.previous_update_id
+                    .filter(|previous| *previous != last)
+                    .map(|previous| BookSyncError::Gap { expected: last, received: previous }),
+                SequenceRule::Contiguous => (update.first_update_id != last + 1)
+                    .then_some(BookSyncError::Gap { expected: last + 1, received: update.first_update_id }),
+                _ => (update.first_update_id > last + 1)
+                    .then_some(BookSyncError::Gap { expected: last + 1, received: update.first_update_id }),
+            };
+            if let Some(gap) = gap {
+                self.reset();
+                return Err(gap);
+            }
+        }
+
+        for level in &update.bids {
+            apply_level(&mut self.bids, level);
+        }
+        for level in &update.asks {
+            apply_level(&mut self.asks, level);
+        }
+        self.truncate();
+        self.last_update_id = Some(match self.rule {
+            SequenceRule::Unsequenced => last + 1,
+            _ => update.last_update_id,
+        });
+        self.updated_since_snapshot = true;
+        self.exchange_timestamp = update.exchange_timestamp;
+
+        if let Some(expected) = update.checksum {
+            self.verify_checksum(expected)?;
+        }
+        Ok(ApplyOutcome::Applied)
+    }
+
+    /// Drop the book and any buffered updates, leaving it waiting for a snapshot
+    pub fn reset(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.last_update_id = None;
+        self.updated_since_snapshot = false;
+        self.pending.clear();
+    }
+
+    pub fn best_bid(&self) -> Option<OrderBookLevel> {
+        self.bids.iter().next_back().map(|(price, quantity)| OrderBookLevel { price: *price, quantity: *quantity })
+    }
+
+    pub fn best_ask(&self) -> Option<OrderBookLevel> {
+        self.asks.iter().next().map(|(price, quantity)| OrderBookLevel { price: *price, quantity: *quantity })
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.best_ask()?.price + self.best_bid()?.price) / Decimal::TWO)
+    }
+
+    /// Top `levels` of each side, bids highest first and asks lowest first
+    pub fn depth(&self, levels: usize) -> OrderBook {
+        OrderBook::new(
+            self.instrument.clone(),
+            self.top_bids(levels),
+            self.top_asks(levels),
+            self.exchange_timestamp,
+        )
+    }
+
+    /// CRC32 of the top `levels` of each side, as a signed 32-bit integer.
+    ///
+    /// Follows OKX's checksum: the levels are interleaved best first as
+    /// `bid_price:bid_size:ask_price:ask_size:...`, continuing with the longer
+    /// side once the shorter one runs out. Prices and sizes are written as the
+    /// exchange sent them, so two books with the same levels match.
+    pub fn checksum(&self, levels: usize) -> i32 {
+        let bids = self.top_bids(levels);
+        let asks = self.top_asks(levels);
+        let mut fields = Vec::with_capacity((bids.len() + asks.len()) * 2);
+        for i in 0..levels {
+            for level in [bids.get(i), asks.get(i)].into_iter().flatten() {
+                fields.push(level.price.to_string());
+                fields.push(level.quantity.to_string());
+            }
+        }
+        crc32fast::hash(fields.join(":").as_bytes()) as i32
+    }
+
+    /// CRC32 of Kraken's checksum string over the top `levels` of each side,
+    /// asks first, as the bits of Kraken's unsigned checksum
+    pub fn kraken_checksum(&self, levels: usize, price_precision: u32, quantity_precision: u32) -> i32 {
+        let mut text = String::new();
+        for level in self.top_asks(levels).iter().chain(self.top_bids(levels).iter()) {
+            text.push_str(&kraken_field(level.price, price_precision));
+            text.push_str(&kraken_field(level.quantity, quantity_precision));
+        }
+        crc32fast::hash(text.as_bytes()) as i32
+    }
+
+    fn verify_checksum(&mut self, expected: i32) -> Result<(), BookSyncError> {
+        let computed = match self.checksum_format {
+            ChecksumFormat::Interleaved => self.checksum(CHECKSUM_DEPTH),
+            ChecksumFormat::Concatenated { price_precision, quantity_precision } =>
+                self.kraken_checksum(KRAKEN_CHECKSUM_DEPTH, price_precision, quantity_precision),
+        };
+        if computed != expected {
+            self.reset();
+            return Err(BookSyncError::ChecksumMismatch { expected, computed });
+        }
+        Ok(())
+    }
+
+    fn truncate(&mut self) {
+        let Some(levels) = self.max_levels else { return };
+        while self.bids.len() > levels {
+            self.bids.pop_first();
+        }
+        while self.asks.len() > levels {
+            self.asks.pop_last();
+        }
+    }
+
+    fn top_bids(&self, levels: usize) -> Vec<OrderBookLevel> {
+        self.bids.iter().rev().take(levels)
+            .map(|(price, quantity)| OrderBookLevel { price: *price, quantity: *quantity })
+            .collect()
+    }
+
+    fn top_asks(&self, levels: usize) -> Vec<OrderBookLevel> {
+        self.asks.iter().take(levels)
+            .map(|(price, quantity)| OrderBookLevel { price: *price, quantity: *quantity })
+            .collect()
+    }
+}
+
+/// A price or quantity at a fixed number of decimals, without the decimal
+/// point or leading zeros, as Kraken's checksum writes it
+fn kraken_field(value: Decimal, precision: u32) -> String {
+    let mut value = value.round_dp(precision);
+    value.rescale(precision);
+    value.to_string().replace('.', "").trim_start_matches('0').to_string()
+}
+
+/// Set a level's quantity, removing it when the quantity is zero
+fn apply_level(side: &mut BTreeMap<Decimal, Decimal>, level: &OrderBookLevel) {
+    if level.quantity.is_zero() {
+        side.remove(&level.price);
+    } else {
+        side.insert(level.price, level.quantity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::entity::market_data::InstrumentKind;
+
+    fn book(rule: SequenceRule) -> LocalOrderBook {
+        let instrument = Instrument::new("BTC".to_string(), "USDT".to_string(), "binance".to_string(), InstrumentKind::Spot);
+        LocalOrderBook::new(instrument, rule)
+    }
+
+    fn level(price: Decimal, quantity: Decimal) -> OrderBookLevel {
+        OrderBookLevel { price, quantity }
+    }
+
+    /// Price and quantity of a level, for comparing
+    fn pair(level: OrderBookLevel) -> (Decimal, Decimal) {
+        (level.price, level.quantity)
+    }
+
+    fn snapshot(last_update_id: u64) -> BookSnapshot {
+        BookSnapshot {
+            last_update_id,
+            bids: vec![level(dec!(100), dec!(1)), level(dec!(99), dec!(2))],
+            asks: vec![level(dec!(101), dec!(1)), level(dec!(102), dec!(2))],
+            checksum: None,
+            exchange_timestamp: Utc::now(),
+        }
+    }
+
+    fn update(first_update_id: u64, last_update_id: u64, bids: Vec<OrderBookLevel>) -> BookUpdate {
+        BookUpdate {
+            first_update_id,
+            last_update_id,
+            previous_update_id: None,
+            bids,
+            asks: Vec::new(),
+            checksum: None,
+            exchange_timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn updates_before_the_snapshot_are_replayed_onto_it() {
+        let mut book = book(SequenceRule::UpdateRange);
+        assert_eq!(book.apply_update(update(8, 9, vec![level(dec!(98), dec!(1))])), Ok(ApplyOutcome::Buffered));
+        assert_eq!(book.apply_update(update(10, 12, vec![level(dec!(100), dec!(0))])), Ok(ApplyOutcome::Buffered));
+
+        book.apply_snapshot(snapshot(10)).unwrap();
+        assert!(book.is_synced());
+        assert_eq!(book.last_update_id(), Some(12));
+        // The update covered by the snapshot was skipped; the other removed the best bid
+        assert_eq!(book.best_bid().map(pair), Some((dec!(99), dec!(2))));
+        assert_eq!(book.spread(), Some(dec!(2)));
+    }
+
+    #[test]
+    fn a_gap_clears_the_book_until_the_next_snapshot() {
+        let mut book = book(SequenceRule::UpdateRange);
+        book.apply_snapshot(snapshot(10)).unwrap();
+        assert_eq!(book.apply_update(update(11, 11, Vec::new())), Ok(ApplyOutcome::Applied));
+        assert_eq!(book.apply_update(update(5, 9, Vec::new())), Ok(ApplyOutcome::Stale));
+
+        assert_eq!(book.apply_update(update(13, 14, Vec::new())), Err(BookSyncError::Gap { expected: 12, received: 13 }));
+        assert!(!book.is_synced());
+        assert!(book.best_bid().is_none());
+
+        // Resync: updates wait for the fresh snapshot, then apply on top of it
+        assert_eq!(book.apply_update(update(15, 15, vec![level(dec!(100.5), dec!(1))])), Ok(ApplyOutcome::Buffered));
+        book.apply_snapshot(snapshot(14)).unwrap();
+        assert_eq!(book.last_update_id(), Some(15));
+        assert_eq!(book.best_bid().map(pair), Some((dec!(100.5), dec!(1))));
+    }
+
+    #[test]
+    fn previous_id_must_match_the_last_update() {
+        let mut book = book(SequenceRule::PreviousId);
+        book.apply_snapshot(snapshot(10)).unwrap();
+
+        let mut first = update(11, 15, Vec::new());
+        first.previous_update_id = Some(10);
+        assert_eq!(book.apply_update(first), Ok(ApplyOutcome::Applied));
+
+        let mut next = update(17, 20, Vec::new());
+        next.previous_update_id = Some(16);
+        assert_eq!(book.apply_update(next), Err(BookSyncError::Gap { expected: 15, received: 16 }));
+    }
+
+    #[test]
+    fn contiguous_ids_must_go_up_by_one() {
+        let mut book = book(SequenceRule::Contiguous);
+        book.apply_snapshot(snapshot(10)).unwrap();
+        assert_eq!(book.apply_update(update(11, 11, Vec::new())), Ok(ApplyOutcome::Applied));
+        assert_eq!(book.apply_update(update(13, 13, Vec::new())), Err(BookSyncError::Gap { expected: 12, received: 13 }));
+    }
+
+    #[test]
+    fn max_levels_drops_the_worst_prices() {
+        let mut book = book(SequenceRule::Unsequenced).with_max_levels(1);
+        book.apply_snapshot(snapshot(0)).unwrap();
+        let top = book.depth(10);
+        assert_eq!(top.bids.into_iter().map(pair).collect::<Vec<_>>(), vec![(dec!(100), dec!(1))]);
+        assert_eq!(top.asks.into_iter().map(pair).collect::<Vec<_>>(), vec![(dec!(101), dec!(1))]);
+    }
+
+    #[test]
+    fn checksum_mismatch_clears_the_book() {
+        let mut book = book(SequenceRule::Unsequenced);
+        let mut first = snapshot(0);
+        first.checksum = Some(crc32fast::hash(b"100:1:101:1:99:2:102:2") as i32);
+        assert_eq!(book.apply_snapshot(first), Ok(()));
+
+        let mut second = snapshot(0);
+        second.checksum = Some(1);
+        assert!(matches!(book.apply_snapshot(second), Err(BookSyncError::ChecksumMismatch { expected: 1, .. })));
+        assert!(!book.is_synced());
+    }
+
+    #[test]
+    fn kraken_checksum_lists_asks_then_bids_at_the_pair_precision() {
+        let mut book = book(SequenceRule::Unsequenced);
+        book.apply_snapshot(BookSnapshot {
+            last_update_id: 0,
+            bids: vec![level(dec!(0.05), dec!(1.5))],
+            asks: vec![level(dec!(0.05005), dec!(0.000005))],
+            checksum: None,
+            exchange_timestamp: Utc::now(),
+        }).unwrap();
+
+        let expected = crc32fast::hash(b"50055005000150000000") as i32;
+        assert_eq!(book.kraken_checksum(KRAKEN_CHECKSUM_DEPTH, 5, 8), expected);
+
+        let mut book = book.with_checksum_format(ChecksumFormat::Concatenated { price_precision: 5, quantity_precision: 8 });
+        let mut update = update(0, 0, Vec::new());
+        update.checksum = Some(expected);
+        assert_eq!(book.apply_update(update), Ok(ApplyOutcome::Applied));
+    }
+}
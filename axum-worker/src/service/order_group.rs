@@ -0,0 +1,205 @@
+use rust_decimal::Decimal;
+
+use crate::entity::trading::{OrderGroup, OrderGroupStatus, OrderGroupType, OrderStatus, TradingOrder};
+
+/// Action needed to keep the legs of an order group consistent
+#[derive(Debug, Clone, PartialEq)]
+pub enum LegAction {
+    /// Cancel a working or held leg
+    Cancel { order_id: String },
+    /// Cancel a working leg and replace it with a copy for the given quantity
+    Replace { order_id: String, quantity: Decimal },
+    /// Submit a bracket exit leg that was waiting for its entry order to fill
+    Activate { order_id: String, quantity: Decimal },
+}
+
+/// Work out which legs of a group must be cancelled, resized or activated.
+///
+/// The rule is the same for OCO and bracket groups: the exit legs together may
+/// only ever close the position that exists. For an OCO group that is the
+/// group quantity; for a bracket it is whatever the entry order has filled so
+/// far. Each working exit leg is sized to the part of that position not yet
+/// closed by fills on any exit leg, and once nothing is left the remaining legs
+/// (and any unfilled part of a bracket entry) are cancelled.
+pub fn plan_leg_actions(group: &OrderGroup, parent: Option<&TradingOrder>, legs: &[TradingOrder]) -> Vec<LegAction> {
+    if !group.is_active() {
+        return Vec::new();
+    }
+
+    let position = match (&group.group_type, parent) {
+        (OrderGroupType::Bracket, Some(parent)) => {
+            // An entry that died unfilled takes its exit legs with it
+            if parent.is_terminal() && parent.filled_quantity <= Decimal::ZERO {
+                return cancel_all(legs);
+            }
+            parent.filled_quantity
+        }
+        _ => group.quantity,
+    };
+
+    let closed: Decimal = legs.iter().map(|leg| leg.filled_quantity).sum();
+    let remaining = (position - closed).max(Decimal::ZERO);
+
+    if remaining <= Decimal::ZERO {
+        if closed <= Decimal::ZERO {
+            // Bracket entry has not filled yet
+            return Vec::new();
+        }
+
+        // The position is closed: cancel what is left, including any unfilled
+        // remainder of the entry so it cannot reopen a position without exits
+        let mut actions = cancel_all(legs);
+        if let Some(parent) = parent.filter(|parent| !parent.is_terminal()) {
+            actions.push(LegAction::Cancel { order_id: parent.id.clone() });
+        }
+        return actions;
+    }
+
+    legs.iter()
+        .filter_map(|leg| match leg.status {
+            OrderStatus::AwaitingParent => Some(LegAction::Activate {
+                order_id: leg.id.clone(),
+                quantity: remaining,
+            }),
+            _ if leg.is_active() && leg.remaining_quantity() != remaining => Some(LegAction::Replace {
+                order_id: leg.id.clone(),
+                quantity: remaining,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Derive the group status from the state of its orders
+pub fn resolve_group_status(parent: Option<&TradingOrder>, legs: &[TradingOrder]) -> OrderGroupStatus {
+    let parent_done = parent.is_none_or(|parent| parent.is_terminal());
+    let legs_done = legs.iter().all(|leg| leg.is_terminal());
+
+    if !(parent_done && legs_done) {
+        return OrderGroupStatus::Active;
+    }
+
+    let any_fill = parent.is_some_and(|parent| parent.filled_quantity > Decimal::ZERO)
+        || legs.iter().any(|leg| leg.filled_quantity > Decimal::ZERO);
+
+    if any_fill { OrderGroupStatus::Completed } else { OrderGroupStatus::Cancelled }
+}
+
+fn cancel_all(legs: &[TradingOrder]) -> Vec<LegAction> {
+    legs.iter()
+        .filter(|leg| !leg.is_terminal())
+        .map(|leg| LegAction::Cancel { order_id: leg.id.clone() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::entity::trading::{InstrumentType, OrderSide, OrderType, TradingInstrument};
+
+    fn group(group_type: OrderGroupType, quantity: Decimal) -> OrderGroup {
+        OrderGroup::new("alice".to_string(), "binance".to_string(), "BTCUSDT".to_string(), group_type, quantity)
+    }
+
+    fn order(id: &str, order_type: OrderType, quantity: Decimal, status: OrderStatus, filled: Decimal) -> TradingOrder {
+        let instrument = TradingInstrument::new(
+            "BTCUSDT".to_string(), "BTC".to_string(), "USDT".to_string(), "binance".to_string(), InstrumentType::Spot,
+        );
+        let mut order = TradingOrder::new("alice".to_string(), instrument, OrderSide::Sell, order_type, quantity, None);
+        order.id = id.to_string();
+        order.status = status;
+        order.filled_quantity = filled;
+        order
+    }
+
+    fn exit_legs(status: OrderStatus, quantity: Decimal) -> Vec<TradingOrder> {
+        vec![
+            order("take-profit", OrderType::Limit, quantity, status.clone(), Decimal::ZERO),
+            order("stop-loss", OrderType::StopLoss, quantity, status, Decimal::ZERO),
+        ]
+    }
+
+    #[test]
+    fn oco_fill_cancels_the_sibling() {
+        let mut legs = exit_legs(OrderStatus::New, dec!(1));
+        legs[0].status = OrderStatus::Filled;
+        legs[0].filled_quantity = dec!(1);
+
+        let actions = plan_leg_actions(&group(OrderGroupType::Oco, dec!(1)), None, &legs);
+        assert_eq!(actions, vec![LegAction::Cancel { order_id: "stop-loss".to_string() }]);
+        assert_eq!(resolve_group_status(None, &legs), OrderGroupStatus::Active);
+    }
+
+    #[test]
+    fn oco_partial_fill_shrinks_the_sibling() {
+        let mut legs = exit_legs(OrderStatus::New, dec!(1));
+        legs[0].status = OrderStatus::PartiallyFilled;
+        legs[0].filled_quantity = dec!(0.4);
+
+        let actions = plan_leg_actions(&group(OrderGroupType::Oco, dec!(1)), None, &legs);
+        assert_eq!(actions, vec![LegAction::Replace { order_id: "stop-loss".to_string(), quantity: dec!(0.6) }]);
+    }
+
+    #[test]
+    fn bracket_entry_fills_activate_the_exits() {
+        let parent = order("entry", OrderType::Limit, dec!(1), OrderStatus::PartiallyFilled, dec!(0.5));
+        let legs = exit_legs(OrderStatus::AwaitingParent, dec!(1));
+
+        let actions = plan_leg_actions(&group(OrderGroupType::Bracket, dec!(1)), Some(&parent), &legs);
+        assert_eq!(actions, vec![
+            LegAction::Activate { order_id: "take-profit".to_string(), quantity: dec!(0.5) },
+            LegAction::Activate { order_id: "stop-loss".to_string(), quantity: dec!(0.5) },
+        ]);
+    }
+
+    #[test]
+    fn bracket_waits_for_its_entry() {
+        let parent = order("entry", OrderType::Limit, dec!(1), OrderStatus::New, Decimal::ZERO);
+        let legs = exit_legs(OrderStatus::AwaitingParent, dec!(1));
+
+        assert!(plan_leg_actions(&group(OrderGroupType::Bracket, dec!(1)), Some(&parent), &legs).is_empty());
+    }
+
+    #[test]
+    fn unfilled_bracket_entry_cancels_the_exits() {
+        let parent = order("entry", OrderType::Limit, dec!(1), OrderStatus::Cancelled, Decimal::ZERO);
+        let legs = exit_legs(OrderStatus::AwaitingParent, dec!(1));
+
+        let actions = plan_leg_actions(&group(OrderGroupType::Bracket, dec!(1)), Some(&parent), &legs);
+        assert_eq!(actions, vec![
+            LegAction::Cancel { order_id: "take-profit".to_string() },
+            LegAction::Cancel { order_id: "stop-loss".to_string() },
+        ]);
+        let cancelled = exit_legs(OrderStatus::Cancelled, dec!(1));
+        assert_eq!(resolve_group_status(Some(&parent), &cancelled), OrderGroupStatus::Cancelled);
+    }
+
+    #[test]
+    fn closed_bracket_cancels_the_rest_of_the_entry() {
+        let parent = order("entry", OrderType::Limit, dec!(1), OrderStatus::PartiallyFilled, dec!(0.5));
+        let mut legs = exit_legs(OrderStatus::New, dec!(0.5));
+        legs[1].status = OrderStatus::Filled;
+        legs[1].filled_quantity = dec!(0.5);
+
+        let actions = plan_leg_actions(&group(OrderGroupType::Bracket, dec!(1)), Some(&parent), &legs);
+        assert_eq!(actions, vec![
+            LegAction::Cancel { order_id: "take-profit".to_string() },
+            LegAction::Cancel { order_id: "entry".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn finished_groups_resolve_by_whether_anything_filled() {
+        let mut legs = exit_legs(OrderStatus::Cancelled, dec!(1));
+        legs[0].status = OrderStatus::Filled;
+        legs[0].filled_quantity = dec!(1);
+
+        assert_eq!(resolve_group_status(None, &legs), OrderGroupStatus::Completed);
+
+        let mut completed = group(OrderGroupType::Oco, dec!(1));
+        completed.status = OrderGroupStatus::Completed;
+        assert!(plan_leg_actions(&completed, None, &legs).is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use serde::{Deserialize, Serialize};
+use worker::{console_log, Cache, Response};
+use rust_decimal::Decimal;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::clients::trading::{Quote, SimpleInstrument, TradingClient};
+use crate::entity::market_data::CandleInterval;
+
+/// How long a resolved (or unresolvable) price is reused before quoting again
+const PRICE_TTL_SECONDS: i64 = 30;
+
+/// Host of the synthetic URLs prices are stored under in the edge cache
+const PRICE_CACHE_HOST: &str = "https://pricing.cache";
+
+/// Assets valued at exactly one dollar
+pub(crate) const USD_EQUIVALENTS: [&str; 3] = ["USD", "USDT", "USDC"];
+
+/// Quote assets tried for a direct price, in order of preference
+const DIRECT_QUOTES: [&str; 3] = ["USD", "USDT", "USDC"];
+
+/// Assets used to triangulate a price when no direct pair exists
+const BRIDGE_ASSETS: [&str; 2] = ["BTC", "ETH"];
+
+/// USD price of an asset and the pairs it was derived from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetPrice {
+    pub asset: String,
+    pub usd_price: Decimal,
+    pub path: Vec<String>,
+    pub priced_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPrice {
+    price: Option<AssetPrice>,
+    cached_at: DateTime<Utc>,
+}
+
+/// Values assets in USD from exchange quotes.
+///
+/// Each asset is priced through the first pair that quotes: a direct USD,
+/// USDT or USDC pair, otherwise a BTC or ETH pair multiplied by that bridge
+/// asset's own direct price. Stablecoins are taken at par. Results, including
+/// assets that could not be priced, are cached per exchange for
+/// `PRICE_TTL_SECONDS` in the colo's edge cache, so they outlive the request
+/// (and the service instance) that resolved them. The in-memory map only
+/// saves repeat cache reads within one instance.
+#[derive(Clone)]
+pub struct PricingService {
+    // Prices keyed by "exchange:ASSET" (with interior mutability for WASM),
+    // in front of the edge cache
+    cache: Rc<RefCell<HashMap<String, CachedPrice>>>,
+    // Historical prices keyed by "exchange:ASSET:minute"; these never go stale
+    history: Rc<RefCell<HashMap<String, Option<Decimal>>>>,
+}
+
+impl PricingService {
+    pub fn new() -> Self {
+        Self {
+            cache: Rc::new(RefCell::new(HashMap::new())),
+            history: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// USD price of a single asset on the client's exchange
+    pub async fn price_usd(&self, client: &TradingClient, asset: &str) -> Option<AssetPrice> {
+        let asset = asset.to_uppercase();
+
+        if USD_EQUIVALENTS.contains(&asset.as_str()) {
+            return Some(AssetPrice {
+                asset,
+                usd_price: Decimal::ONE,
+                path: Vec::new(),
+                priced_at: Utc::now(),
+            });
+        }
+
+        let key = Self::key(client, &asset);
+        if let Some(cached) = self.cached(&key).await {
+            return cached;
+        }
+
+        let price = match self.direct_price(client, &asset).await {
+            Some(price) => Some(price),
+            None => self.triangulated_price(client, &asset).await,
+        };
+
+        match &price {
+            Some(price) => console_log!("PRICING SERVICE: {} = {} USD via {}", asset, price.usd_price, price.path.join(" x ")),
+            None => console_log!("PRICING SERVICE: No quote path to USD for {} on {:?}", asset, client.exchange),
+        }
+
+        self.store(key, price.clone()).await;
+        price
+    }
+
+    /// USD prices for a set of assets; assets that cannot be priced are omitted
+    pub async fn price_assets(&self, client: &TradingClient, assets: &[String]) -> HashMap<String, AssetPrice> {
+        let mut prices = HashMap::new();
+        for asset in assets {
+            if let Some(price) = self.price_usd(client, asset).await {
+                prices.insert(asset.clone(), price);
+            }
+        }
+        prices
+    }
+
+    /// USD price of an asset at a past time, from the close of the finest
+    /// kline covering it on a direct USD, USDT or USDC pair. Returns `None`
+    /// when the exchange has no such history, so callers can fall back to
+    /// the current price.
+    pub async fn price_usd_at(&self, client: &TradingClient, asset: &str, at: DateTime<Utc>) -> Option<Decimal> {
+        let asset = asset.to_uppercase();
+
+        if USD_EQUIVALENTS.contains(&asset.as_str()) {
+            return Some(Decimal::ONE);
+        }
+
+        let key = format!("{}:{}", Self::key(client, &asset), at.timestamp() / 60);
+        if let Some(cached) = self.history.try_borrow().ok().and_then(|history| history.get(&key).copied()) {
+            return cached;
+        }
+
+        let mut price = None;
+        for quote_asset in DIRECT_QUOTES {
+            price = self.kline_close(client, &asset, quote_asset, at).await;
+            if price.is_some() {
+                break;
+            }
+        }
+
+        if price.is_none() {
+            console_log!("PRICING SERVICE: No USD history for {} at {} on {:?}", asset, at, client.exchange);
+        }
+
+        if let Ok(mut history) = self.history.try_borrow_mut() {
+            history.insert(key, price);
+        }
+        price
+    }
+
+    /// Close of the finest supported kline that opened at or before `at`
+    async fn kline_close(&self, client: &TradingClient, base: &str, quote: &str, at: DateTime<Utc>) -> Option<Decimal> {
+        let interval = CandleInterval::ALL.into_iter()
+            .find(|interval| client.supports_kline_interval(*interval))?;
+        let instrument = SimpleInstrument {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        };
+
+        let start = at - interval.duration();
+        let end = at + Duration::seconds(1);
+        match client.get_klines(&instrument, interval, start, end).await {
+            Ok(klines) => klines.into_iter()
+                .rev()
+                .find(|kline| kline.open_time <= at && kline.close > Decimal::ZERO)
+                .map(|kline| kline.close),
+            Err(e) => {
+                console_log!("PRICING SERVICE: No klines for {}/{}: {}", base, quote, e);
+                None
+            }
+        }
+    }
+
+    /// Price from a pair quoted directly in a USD-equivalent asset
+    async fn direct_price(&self, client: &TradingClient, asset: &str) -> Option<AssetPrice> {
+        for quote_asset in DIRECT_QUOTES {
+            if let Some(mid) = self.quote_mid(client, asset, quote_asset).await {
+                return Some(AssetPrice {
+                    asset: asset.to_string(),
+                    usd_price: mid,
+                    path: vec![format!("{}/{}", asset, quote_asset)],
+                    priced_at: Utc::now(),
+                });
+            }
+        }
+        None
+    }
+
+    /// Price from a pair quoted in a bridge asset, times the bridge's USD price
+    async fn triangulated_price(&self, client: &TradingClient, asset: &str) -> Option<AssetPrice> {
+        for bridge in BRIDGE_ASSETS {
+            if asset == bridge {
+                continue;
+            }
+
+            let Some(cross) = self.quote_mid(client, asset, bridge).await else { continue };
+
+            let bridge_key = Self::key(client, bridge);
+            let bridge_price = match self.cached(&bridge_key).await {
+                Some(cached) => cached,
+                None => {
+                    let price = self.direct_price(client, bridge).await;
+                    self.store(bridge_key, price.clone()).await;
+                    price
+                }
+            };
+
+            if let Some(bridge_price) = bridge_price {
+                let mut path = vec![format!("{}/{}", asset, bridge)];
+                path.extend(bridge_price.path);
+                return Some(AssetPrice {
+                    asset: asset.to_string(),
+                    usd_price: cross * bridge_price.usd_price,
+                    path,
+                    priced_at: Utc::now(),
+                });
+            }
+        }
+        None
+    }
+
+    /// Mid price of a pair, if the exchange quotes it
+    async fn quote_mid(&self, client: &TradingClient, base: &str, quote: &str) -> Option<Decimal> {
+        let instrument = SimpleInstrument {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        };
+
+        match client.get_quote(&instrument).await {
+            Ok(quote) => Self::mid(&quote),
+            Err(e) => {
+                console_log!("PRICING SERVICE: No quote for {}/{}: {}", base, quote, e);
+                None
+            }
+        }
+    }
+
+    /// Mid of bid and ask, falling back to whichever side is present
+    fn mid(quote: &Quote) -> Option<Decimal> {
+        match (quote.bid > Decimal::ZERO, quote.ask > Decimal::ZERO) {
+            (true, true) => Some((quote.bid + quote.ask) / Decimal::new(2, 0)),
+            (true, false) => Some(quote.bid),
+            (false, true) => Some(quote.ask),
+            (false, false) => None,
+        }
+    }
+
+    /// Cached price for a key, if still fresh, from memory or else the edge
+    /// cache. The outer option is the cache hit; the inner one is whether the
+    /// asset could be priced.
+    async fn cached(&self, key: &str) -> Option<Option<AssetPrice>> {
+        let local = self.cache.try_borrow().ok()
+            .and_then(|cache| cache.get(key).cloned())
+            .filter(|entry| Self::is_fresh(entry, Utc::now()));
+        if let Some(entry) = local {
+            return Some(entry.price);
+        }
+
+        let mut response = match Cache::default().get(Self::cache_url(key), false).await {
+            Ok(Some(response)) => response,
+            Ok(None) => return None,
+            Err(e) => {
+                console_log!("PRICING SERVICE: Cache read failed for {}: {}", key, e);
+                return None;
+            }
+        };
+        let entry: CachedPrice = response.json().await.ok()?;
+        if !Self::is_fresh(&entry, Utc::now()) {
+            return None;
+        }
+
+        if let Ok(mut cache) = self.cache.try_borrow_mut() {
+            cache.insert(key.to_string(), entry.clone());
+        }
+        Some(entry.price)
+    }
+
+    async fn store(&self, key: String, price: Option<AssetPrice>) {
+        let entry = CachedPrice {
+            price,
+            cached_at: Utc::now(),
+        };
+
+        let stored = match Response::from_json(&entry) {
+            Ok(mut response) => match response.headers_mut().set("Cache-Control", &format!("max-age={}", PRICE_TTL_SECONDS)) {
+                Ok(()) => Cache::default().put(Self::cache_url(&key), response).await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = stored {
+            console_log!("PRICING SERVICE: Cache write failed for {}: {}", key, e);
+        }
+
+        if let Ok(mut cache) = self.cache.try_borrow_mut() {
+            cache.insert(key, entry);
+        }
+    }
+
+    /// Whether an entry is young enough to reuse; the edge cache's own
+    /// expiry is only a ceiling, so the age is checked here as well
+    fn is_fresh(entry: &CachedPrice, now: DateTime<Utc>) -> bool {
+        now - entry.cached_at <= Duration::seconds(PRICE_TTL_SECONDS)
+    }
+
+    fn cache_url(key: &str) -> String {
+        format!("{}/{}", PRICE_CACHE_HOST, key.replace(':', "/"))
+    }
+
+    fn key(client: &TradingClient, asset: &str) -> String {
+        format!("{:?}:{}", client.exchange, asset)
+    }
+}
+
+impl Default for PricingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_expire_after_the_ttl() {
+        let now = Utc::now();
+        let entry = |age: i64| CachedPrice {
+            price: None,
+            cached_at: now - Duration::seconds(age),
+        };
+
+        assert!(PricingService::is_fresh(&entry(0), now));
+        assert!(PricingService::is_fresh(&entry(PRICE_TTL_SECONDS), now));
+        assert!(!PricingService::is_fresh(&entry(PRICE_TTL_SECONDS + 1), now));
+    }
+
+    #[test]
+    fn cache_urls_are_one_path_segment_per_key_part() {
+        assert_eq!(PricingService::cache_url("Binance:BTC"), "https://pricing.cache/Binance/BTC");
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasm_bindgen::JsValue;
+use worker::{console_log, Method, ObjectNamespace, Request, RequestInit};
+
+/// Variable holding JSON overrides of the default limits
+pub const RATE_LIMITS_VAR: &str = "RATE_LIMITS";
+
+/// Routes that share a rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteGroup {
+    Login,
+    /// Registration
+    Auth,
+    /// Placing orders and cancelling order groups
+    Orders,
+    /// Inbound trading signals, limited per hook
+    Signals,
+    Trading,
+    MarketData,
+    Admin,
+    /// Every other API route
+    Default,
+}
+
+/// Who a request is counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrincipalKind {
+    /// A signed-in user
+    User,
+    /// An anonymous client, by IP address
+    Ip,
+    /// A signal hook, by its token
+    Token,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub kind: PrincipalKind,
+    pub id: String,
+}
+
+/// A token bucket: up to `capacity` requests in a burst, refilled at
+/// `per_minute` requests a minute
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub per_minute: u32,
+}
+
+/// How many failed logins lock an account, and for how long
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockoutPolicy {
+    pub max_failures: u32,
+    /// Failures older than this are forgotten
+    pub window_seconds: u32,
+    pub lockout_seconds: u32,
+}
+
+/// Overrides of the default limits, read from the `RATE_LIMITS` variable
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// By route group and principal kind, e.g. `{"orders": {"user": {...}}}`
+    pub groups: HashMap<RouteGroup, HashMap<PrincipalKind, RateLimit>>,
+    /// For particular principals, e.g. `{"user:alice": {"orders": {...}}}`
+    pub principals: HashMap<String, HashMap<RouteGroup, RateLimit>>,
+    pub login_lockout: Option<LockoutPolicy>,
+}
+
+/// Outcome of taking a token from a bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until a request would be allowed; 0 if this one was
+    pub retry_after_seconds: u32,
+    /// Seconds until the bucket is full again
+    pub reset_seconds: u32,
+}
+
+/// Token bucket state, kept by the rate limiter object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at_ms: i64,
+}
+
+/// Recent failed logins of one account
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoginFailures {
+    failed_at_ms: Vec<i64>,
+    locked_until_ms: Option<i64>,
+}
+
+/// Whether an account is locked, as reported by the rate limiter object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockoutStatus {
+    pub locked: bool,
+    pub retry_after_seconds: u32,
+}
+
+/// Request to the rate limiter object to record a failed login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginFailureRequest {
+    pub policy: LockoutPolicy,
+}
+
+impl RouteGroup {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteGroup::Login => "login",
+            RouteGroup::Auth => "auth",
+            RouteGroup::Orders => "orders",
+            RouteGroup::Signals => "signals",
+            RouteGroup::Trading => "trading",
+            RouteGroup::MarketData => "market_data",
+            RouteGroup::Admin => "admin",
+            RouteGroup::Default => "default",
+        }
+    }
+
+    /// Group of an API path; other paths aren't limited
+    pub fn for_path(path: &str) -> Option<Self> {
+        let group = match path {
+            "/api/auth/login" => RouteGroup::Login,
+            _ if path.starts_with("/api/auth/") => RouteGroup::Auth,
+            "/api/trading/order/status" => RouteGroup::Trading,
+            "/api/trading/order" | "/api/trading/order-group/cancel" => RouteGroup::Orders,
+            _ if path.starts_with("/api/trading/order/") => RouteGroup::Orders,
+            _ if path.starts_with("/api/signals/hook/") => RouteGroup::Signals,
+            _ if path.starts_with("/api/trading/") => RouteGroup::Trading,
+            _ if path.starts_with("/api/market-data/") => RouteGroup::MarketData,
+            _ if path.starts_with("/api/admin/") => RouteGroup::Admin,
+            _ if path.starts_with("/api/") => RouteGroup::Default,
+            _ => return None,
+        };
+        Some(group)
+    }
+
+    /// Built-in limit for a kind of principal
+    fn default_limit(&self, kind: PrincipalKind) -> RateLimit {
+        let (capacity, per_minute) = match (self, kind) {
+            // Anyone can try to log in, so the limit is per IP whatever the token
+            (RouteGroup::Login | RouteGroup::Auth, _) => (5, 5),
+            (RouteGroup::Orders, PrincipalKind::User) => (10, 60),
+            // Signed-in orders also count against their IP, so leave room for a few accounts
+            (RouteGroup::Orders, _) => (20, 120),
+            (RouteGroup::Signals, _) => (10, 30),
+            (RouteGroup::MarketData, PrincipalKind::User) => (60, 600),
+            (RouteGroup::MarketData, _) => (30, 300),
+            (RouteGroup::Admin, PrincipalKind::User) => (20, 60),
+            (RouteGroup::Admin, _) => (10, 30),
+            (RouteGroup::Trading | RouteGroup::Default, PrincipalKind::User) => (60, 300),
+            (RouteGroup::Trading | RouteGroup::Default, _) => (30, 120),
+        };
+        RateLimit { capacity, per_minute }
+    }
+}
+
+impl PrincipalKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrincipalKind::User => "user",
+            PrincipalKind::Ip => "ip",
+            PrincipalKind::Token => "token",
+        }
+    }
+}
+
+impl Principal {
+    /// Name of the principal in config and limiter keys, e.g. "user:alice"
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.kind.as_str(), self.id)
+    }
+}
+
+impl RateLimit {
+    fn validate(&self) -> Result<(), String> {
+        if self.capacity == 0 || self.per_minute == 0 {
+            return Err("capacity and per_minute must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Tokens refilled per millisecond
+    fn refill_rate(&self) -> f64 {
+        self.per_minute as f64 / 60_000.0
+    }
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            window_seconds: 15 * 60,
+            lockout_seconds: 15 * 60,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Parse and check overrides; each limit must allow at least one request
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let config: RateLimitConfig = serde_json::from_str(json).map_err(|e| format!("Invalid {}: {}", RATE_LIMITS_VAR, e))?;
+        let limits = config.groups.values().flat_map(|limits| limits.values())
+            .chain(config.principals.values().flat_map(|limits| limits.values()));
+        for limit in limits {
+            limit.validate()?;
+        }
+        if config.login_lockout.is_some_and(|policy| policy.max_failures == 0) {
+            return Err("login_lockout.max_failures must be at least 1".to_string());
+        }
+        Ok(config)
+    }
+
+    /// Limit for a principal on a route group: its own override, else the
+    /// group's override for its kind, else the default
+    pub fn limit_for(&self, group: RouteGroup, principal: &Principal) -> RateLimit {
+        self.principals.get(&principal.key())
+            .and_then(|limits| limits.get(&group))
+            .or_else(|| self.groups.get(&group).and_then(|limits| limits.get(&principal.kind)))
+            .copied()
+            .unwrap_or_else(|| group.default_limit(principal.kind))
+    }
+}
+
+impl TokenBucket {
+    pub fn full(limit: RateLimit, now_ms: i64) -> Self {
+        Self {
+            tokens: limit.capacity as f64,
+            updated_at_ms: now_ms,
+        }
+    }
+
+    /// Refill for the time since the last request, then take a token if one
+    /// is left
+    pub fn take(&mut self, limit: RateLimit, now_ms: i64) -> RateLimitDecision {
+        let capacity = limit.capacity as f64;
+        let rate = limit.refill_rate();
+        let elapsed = (now_ms - self.updated_at_ms).max(0) as f64;
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.updated_at_ms = now_ms;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+        let seconds_until = |tokens: f64| (tokens.max(0.0) / rate / 1000.0).ceil() as u32;
+
+        RateLimitDecision {
+            allowed,
+            limit: limit.capacity,
+            remaining: self.tokens.floor() as u32,
+            retry_after_seconds: if allowed { 0 } else { seconds_until(1.0 - self.tokens).max(1) },
+            reset_seconds: seconds_until(capacity - self.tokens),
+        }
+    }
+}
+
+impl LoginFailures {
+    pub fn status(&self, now_ms: i64) -> LockoutStatus {
+        match self.locked_until_ms {
+            Some(until) if until > now_ms => LockoutStatus {
+                locked: true,
+                retry_after_seconds: ((until - now_ms) as f64 / 1000.0).ceil() as u32,
+            },
+            _ => LockoutStatus { locked: false, retry_after_seconds: 0 },
+        }
+    }
+
+    /// Count a failed login, locking the account once the policy's number of
+    /// failures fall within its window
+    pub fn record_failure(&mut self, policy: LockoutPolicy, now_ms: i64) -> LockoutStatus {
+        let window_start = now_ms - policy.window_seconds as i64 * 1000;
+        self.failed_at_ms.retain(|failed_at| *failed_at > window_start);
+        self.failed_at_ms.push(now_ms);
+
+        if self.failed_at_ms.len() >= policy.max_failures as usize {
+            self.locked_until_ms = Some(now_ms + policy.lockout_seconds as i64 * 1000);
+            self.failed_at_ms.clear();
+        }
+        self.status(now_ms)
+    }
+}
+
+/// Rate limits and login lockouts, enforced by `RateLimiter` Durable Objects.
+///
+/// Each principal gets a token bucket per route group, held by the object
+/// named after the pair, so limits hold across every worker instance.
+/// Without the binding nothing is limited. Requests are let through when
+/// the limiter can't be reached, so an outage of it doesn't take the API
+/// down too.
+#[derive(Clone)]
+pub struct RateLimitService {
+    namespace: Option<ObjectNamespace>,
+    config: RateLimitConfig,
+}
+
+impl RateLimitService {
+    pub fn new(namespace: Option<ObjectNamespace>, config_json: Option<String>) -> Self {
+        let config = match config_json.as_deref().map(RateLimitConfig::parse) {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                console_log!("RATE LIMIT: Using default limits, {}", e);
+                RateLimitConfig::default()
+            }
+            None => RateLimitConfig::default(),
+        };
+        Self { namespace, config }
+    }
+
+    /// Route group and principals a request counts against, if its path is
+    /// limited. Signal hooks are counted per token, since alerting services
+    /// post every user's signals from the same few addresses; logins and
+    /// registrations per IP; everything else per user when signed in. Orders
+    /// are also counted per IP when signed in, so one address can't place
+    /// orders faster by spreading them over many accounts.
+    pub fn classify(&self, req: &Request, user_id: Option<&str>) -> Option<(RouteGroup, Vec<Principal>)> {
+        let path = req.path();
+        let group = RouteGroup::for_path(&path)?;
+        let ip = || {
+            let ip = req.headers().get("CF-Connecting-IP").ok().flatten().unwrap_or_else(|| "unknown".to_string());
+            Principal { kind: PrincipalKind::Ip, id: ip }
+        };
+
+        let principals = match (group, user_id) {
+            (RouteGroup::Signals, _) => {
+                let token = path.trim_start_matches("/api/signals/hook/");
+                // The token is a credential, so only its hash is used as a name
+                vec![Principal { kind: PrincipalKind::Token, id: format!("{:x}", Sha256::digest(token.as_bytes())) }]
+            }
+            (RouteGroup::Login | RouteGroup::Auth, _) | (_, None) => vec![ip()],
+            (RouteGroup::Orders, Some(user_id)) => vec![
+                Principal { kind: PrincipalKind::User, id: user_id.to_string() },
+                ip(),
+            ],
+            (_, Some(user_id)) => vec![Principal { kind: PrincipalKind::User, id: user_id.to_string() }],
+        };
+        Some((group, principals))
+    }
+
+    /// Take a token from each principal's bucket. The decision reported is
+    /// the first refusal, else the bucket with the fewest tokens left. None
+    /// means the request wasn't checked.
+    pub async fn check_all(&self, group: RouteGroup, principals: &[Principal]) -> Option<RateLimitDecision> {
+        let mut tightest: Option<RateLimitDecision> = None;
+        for principal in principals {
+            let Some(decision) = self.check(group, principal).await else { continue };
+            if !decision.allowed {
+                return Some(decision);
+            }
+            if tightest.as_ref().is_none_or(|tightest| decision.remaining < tightest.remaining) {
+                tightest = Some(decision);
+            }
+        }
+        tightest
+    }
+
+    /// Take a token for a request. None means the request wasn't checked.
+    pub async fn check(&self, group: RouteGroup, principal: &Principal) -> Option<RateLimitDecision> {
+        let limit = self.config.limit_for(group, principal);
+        let name = format!("{}:{}", group.as_str(), principal.key());
+        let decision: RateLimitDecision = self.call(&name, Method::Post, "/take", Some(&limit)).await?;
+        if !decision.allowed {
+            console_log!("RATE LIMIT: {} over its {} limit, retry in {}s", principal.key(), group.as_str(), decision.retry_after_seconds);
+        }
+        Some(decision)
+    }
+
+    /// Seconds until a locked account may try to log in again
+    pub async fn login_locked(&self, username: &str) -> Option<u32> {
+        let status: LockoutStatus = self.call(&lockout_name(username), Method::Get, "/lockout", None::<&()>).await?;
+        status.locked.then_some(status.retry_after_seconds)
+    }
+
+    /// Count a failed login. Returns how long the account is now locked for,
+    /// if this failure locked it.
+    pub async fn record_login_failure(&self, username: &str) -> Option<u32> {
+        let request = LoginFailureRequest { policy: self.config.login_lockout.unwrap_or_default() };
+        let status: LockoutStatus = self.call(&lockout_name(username), Method::Post, "/failure", Some(&request)).await?;
+        if status.locked {
+            console_log!("RATE LIMIT: Locked logins of {} for {}s after repeated failures", username, status.retry_after_seconds);
+        }
+        status.locked.then_some(status.retry_after_seconds)
+    }
+
+    /// Forget an account's failed logins after it signs in
+    pub async fn record_login_success(&self, username: &str) {
+        let _: Option<LockoutStatus> = self.call(&lockout_name(username), Method::Post, "/success", None::<&()>).await;
+    }
+
+    async fn call<B: Serialize, T: for<'de> Deserialize<'de>>(&self, name: &str, method: Method, path: &str, body: Option<&B>) -> Option<T> {
+        let namespace = self.namespace.as_ref()?;
+        let result = async {
+            let body = body.map(serde_json::to_string).transpose().map_err(|e| worker::Error::RustError(e.to_string()))?;
+            let mut init = RequestInit::new();
+            init.with_method(method).with_body(body.map(|body| JsValue::from_str(&body)));
+            let request = Request::new_with_init(&format!("https://rate-limiter{}", path), &init)?;
+
+            let stub = namespace.id_from_name(name)?.get_stub()?;
+            let mut response = stub.fetch_with_request(request).await?;
+            response.json::<T>().await
+        }.await;
+
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                console_log!("RATE LIMIT: Rate limiter unavailable for {}, letting the request through: {}", name, e);
+                None
+            }
+        }
+    }
+}
+
+/// Name of the object counting an account's failed logins
+fn lockout_name(username: &str) -> String {
+    format!("lockout:{}", username.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit { capacity: 2, per_minute: 60 };
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills() {
+        let mut bucket = TokenBucket::full(LIMIT, 0);
+        assert!(bucket.take(LIMIT, 0).allowed);
+        let second = bucket.take(LIMIT, 0);
+        assert!(second.allowed);
+        assert_eq!((second.remaining, second.reset_seconds), (0, 2));
+
+        let refused = bucket.take(LIMIT, 500);
+        assert!(!refused.allowed);
+        assert_eq!(refused.retry_after_seconds, 1);
+
+        // One token a second
+        assert!(bucket.take(LIMIT, 1_000).allowed);
+        assert!(!bucket.take(LIMIT, 1_000).allowed);
+        let refilled = bucket.take(LIMIT, 60_000);
+        assert!(refilled.allowed);
+        assert_eq!(refilled.remaining, 1);
+    }
+
+    #[test]
+    fn failures_within_the_window_lock_the_account() {
+        let policy = LockoutPolicy { max_failures: 3, window_seconds: 60, lockout_seconds: 300 };
+        let mut failures = LoginFailures::default();
+
+        assert!(!failures.record_failure(policy, 0).locked);
+        // The first failure falls out of the window
+        assert!(!failures.record_failure(policy, 61_000).locked);
+        assert!(!failures.record_failure(policy, 62_000).locked);
+        let locked = failures.record_failure(policy, 63_000);
+        assert!(locked.locked);
+        assert_eq!(locked.retry_after_seconds, 300);
+
+        assert_eq!(failures.status(63_000 + 299_500).retry_after_seconds, 1);
+        assert!(!failures.status(63_000 + 300_000).locked);
+    }
+
+    #[test]
+    fn config_overrides_principals_then_groups() {
+        let config = RateLimitConfig::parse(r#"{
+            "groups": {"orders": {"user": {"capacity": 3, "per_minute": 6}}},
+            "principals": {"user:alice": {"orders": {"capacity": 50, "per_minute": 100}}}
+        }"#).unwrap();
+        let user = |id: &str| Principal { kind: PrincipalKind::User, id: id.to_string() };
+        let ip = Principal { kind: PrincipalKind::Ip, id: "203.0.113.7".to_string() };
+
+        assert_eq!(config.limit_for(RouteGroup::Orders, &user("alice")), RateLimit { capacity: 50, per_minute: 100 });
+        assert_eq!(config.limit_for(RouteGroup::Orders, &user("bob")), RateLimit { capacity: 3, per_minute: 6 });
+        assert_eq!(config.limit_for(RouteGroup::Orders, &ip), RouteGroup::Orders.default_limit(PrincipalKind::Ip));
+        assert_eq!(config.limit_for(RouteGroup::Trading, &user("alice")), RouteGroup::Trading.default_limit(PrincipalKind::User));
+
+        assert!(RateLimitConfig::parse(r#"{"groups": {"orders": {"user": {"capacity": 0, "per_minute": 6}}}}"#).is_err());
+        assert!(RateLimitConfig::parse(r#"{"login_lockout": {"max_failures": 0}}"#).is_err());
+        assert!(RateLimitConfig::parse(r#"{"unknown": 1}"#).is_err());
+    }
+
+    #[test]
+    fn paths_fall_into_groups() {
+        assert_eq!(RouteGroup::for_path("/api/auth/login"), Some(RouteGroup::Login));
+        assert_eq!(RouteGroup::for_path("/api/auth/register"), Some(RouteGroup::Auth));
+        assert_eq!(RouteGroup::for_path("/api/trading/order"), Some(RouteGroup::Orders));
+        assert_eq!(RouteGroup::for_path("/api/trading/order/cancel"), Some(RouteGroup::Orders));
+        assert_eq!(RouteGroup::for_path("/api/trading/order/status"), Some(RouteGroup::Trading));
+        assert_eq!(RouteGroup::for_path("/api/trading/orders"), Some(RouteGroup::Trading));
+        assert_eq!(RouteGroup::for_path("/api/signals/hook/abc"), Some(RouteGroup::Signals));
+        assert_eq!(RouteGroup::for_path("/api/admin/transfers"), Some(RouteGroup::Admin));
+        assert_eq!(RouteGroup::for_path("/api/strategies"), Some(RouteGroup::Default));
+        assert_eq!(RouteGroup::for_path("/health"), None);
+    }
+}
@@ -0,0 +1,105 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::trading::OrderSide;
+use crate::service::strategy::{OrderIntent, StrategyContext};
+
+/// Limits a running strategy's orders must stay within. Unset limits aren't
+/// checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskLimits {
+    /// Largest notional of a single order, in the quote asset
+    pub max_order_notional: Option<Decimal>,
+    /// Largest position the strategy may build up, in the base asset
+    pub max_position: Option<Decimal>,
+    /// Most limit orders working at once
+    pub max_open_orders: Option<usize>,
+    /// Fraction of the starting equity the strategy may lose before it is halted
+    pub max_drawdown: Option<Decimal>,
+}
+
+/// Pre-trade checks on a strategy's orders, and the loss limit that halts it.
+///
+/// Orders that reduce the position are only held to the notional limit, so a
+/// strategy can always get out.
+#[derive(Debug, Clone)]
+pub struct RiskEngine {
+    limits: RiskLimits,
+}
+
+impl RiskEngine {
+    pub fn new(limits: RiskLimits) -> Result<Self, String> {
+        if limits.max_order_notional.is_some_and(|limit| limit <= Decimal::ZERO) {
+            return Err("max_order_notional must be positive".to_string());
+        }
+        if limits.max_position.is_some_and(|limit| limit <= Decimal::ZERO) {
+            return Err("max_position must be positive".to_string());
+        }
+        if limits.max_open_orders == Some(0) {
+            return Err("max_open_orders must be at least 1".to_string());
+        }
+        if limits.max_drawdown.is_some_and(|limit| limit <= Decimal::ZERO || limit > Decimal::ONE) {
+            return Err("max_drawdown must be above 0 and at most 1".to_string());
+        }
+        Ok(Self { limits })
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Check an order against the limits before it is sent
+    pub fn check(&self, intent: &OrderIntent, context: &StrategyContext) -> Result<(), String> {
+        let (side, quantity, price) = match intent {
+            OrderIntent::CancelAll => return Ok(()),
+            OrderIntent::Market { side, quantity } => {
+                let price = context.last_price.ok_or("No market price yet to value a market order")?;
+                (side, *quantity, price)
+            }
+            OrderIntent::Limit { side, quantity, price } => {
+                if *price <= Decimal::ZERO {
+                    return Err(format!("Limit price must be positive, got {}", price));
+                }
+                if let Some(limit) = self.limits.max_open_orders {
+                    if context.open_orders >= limit {
+                        return Err(format!("{} orders already open, the limit is {}", context.open_orders, limit));
+                    }
+                }
+                (side, *quantity, *price)
+            }
+        };
+        if quantity <= Decimal::ZERO {
+            return Err(format!("Order quantity must be positive, got {}", quantity));
+        }
+
+        let notional = quantity * price;
+        if let Some(limit) = self.limits.max_order_notional {
+            if notional > limit {
+                return Err(format!("Order notional {} is above the limit of {}", notional.round_dp(8), limit));
+            }
+        }
+
+        if let (OrderSide::Buy, Some(limit)) = (side, self.limits.max_position) {
+            if context.position + quantity > limit {
+                return Err(format!("Position would reach {}, above the limit of {}", context.position + quantity, limit));
+            }
+        }
+        Ok(())
+    }
+
+    /// Why the strategy must stop, once its equity has fallen more than the
+    /// drawdown limit below where it started
+    pub fn breach(&self, initial_equity: Decimal, equity: Decimal) -> Option<String> {
+        let limit = self.limits.max_drawdown?;
+        if initial_equity <= Decimal::ZERO {
+            return None;
+        }
+        let drawdown = (initial_equity - equity) / initial_equity;
+        (drawdown > limit).then(|| format!(
+            "Lost {}% of the starting equity, above the limit of {}%",
+            (drawdown * Decimal::ONE_HUNDRED).round_dp(2),
+            (limit * Decimal::ONE_HUNDRED).round_dp(2),
+        ))
+    }
+}
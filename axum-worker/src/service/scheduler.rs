@@ -0,0 +1,179 @@
+use worker::console_log;
+
+use crate::dto::trading::GetInstrumentsRequest;
+use crate::entity::job::{JobRun, JobRunStatus, JobTrigger, ScheduledJob};
+use crate::repo::job::JobRepository;
+use crate::service::alert::AlertService;
+use crate::service::candle::CandleService;
+use crate::service::snapshot::SnapshotService;
+use crate::service::trading::TradingService;
+
+/// Result of a job that ran to completion
+enum JobOutcome {
+    Completed(String),
+    /// The job had nothing to do
+    Skipped(String),
+}
+
+/// Registry and runner for the worker's scheduled jobs.
+///
+/// Each job has its own cron expression. A cron event runs every job whose
+/// expression matches the one that fired. Every run is recorded in the run
+/// history, and a per-job lock in the database keeps a run from starting
+/// while a previous one is still going.
+#[derive(Clone)]
+pub struct SchedulerService {
+    job_repository: JobRepository,
+    trading_service: TradingService,
+    snapshot_service: SnapshotService,
+    candle_service: CandleService,
+    alert_service: AlertService,
+}
+
+impl SchedulerService {
+    pub fn new(
+        job_repository: JobRepository,
+        trading_service: TradingService,
+        snapshot_service: SnapshotService,
+        candle_service: CandleService,
+        alert_service: AlertService,
+    ) -> Self {
+        Self {
+            job_repository,
+            trading_service,
+            snapshot_service,
+            candle_service,
+            alert_service,
+        }
+    }
+
+    /// Run the jobs scheduled on a cron expression
+    pub async fn run_scheduled(&self, cron: &str) -> Vec<JobRun> {
+        let jobs: Vec<ScheduledJob> = ScheduledJob::ALL.into_iter().filter(|job| job.cron() == cron).collect();
+        console_log!("SCHEDULER: Cron {} matched {} jobs", cron, jobs.len());
+
+        let mut runs = Vec::new();
+        for job in jobs {
+            runs.push(self.run_job(job, JobTrigger::Cron, None).await);
+        }
+        runs
+    }
+
+    /// Run one job now unless another run holds its lock
+    pub async fn run_job(&self, job: ScheduledJob, trigger: JobTrigger, triggered_by: Option<String>) -> JobRun {
+        let mut run = JobRun::new(job, trigger, triggered_by);
+        console_log!("SCHEDULER: Starting {} run {} ({})", job.as_str(), run.id, trigger.as_str());
+
+        match self.job_repository.acquire_lock(&run).await {
+            Ok(true) => {}
+            Ok(false) => {
+                run.finish(JobRunStatus::Skipped, "Previous run still holds the lock".to_string());
+                self.record(&run).await;
+                return run;
+            }
+            Err(e) => {
+                run.finish(JobRunStatus::Failed, format!("Failed to acquire lock: {}", e));
+                self.record(&run).await;
+                return run;
+            }
+        }
+        self.record(&run).await;
+
+        match self.execute(job).await {
+            Ok(JobOutcome::Completed(message)) => run.finish(JobRunStatus::Succeeded, message),
+            Ok(JobOutcome::Skipped(message)) => run.finish(JobRunStatus::Skipped, message),
+            Err(e) => run.finish(JobRunStatus::Failed, e),
+        }
+        console_log!("SCHEDULER: {} run {} finished as {}: {}",
+            job.as_str(), run.id, run.status.as_str(), run.message.as_deref().unwrap_or_default());
+
+        self.record(&run).await;
+        if let Err(e) = self.job_repository.release_lock(&run).await {
+            console_log!("SCHEDULER: Failed to release lock on {}: {}", job.as_str(), e);
+        }
+        run
+    }
+
+    /// Latest run of each job
+    pub async fn latest_runs(&self) -> Result<Vec<JobRun>, String> {
+        self.job_repository.find_latest_runs().await
+    }
+
+    /// Run history, newest first
+    pub async fn run_history(&self, job: Option<ScheduledJob>, limit: u32) -> Result<Vec<JobRun>, String> {
+        self.job_repository.find_runs(job, limit).await
+    }
+
+    async fn execute(&self, job: ScheduledJob) -> Result<JobOutcome, String> {
+        match job {
+            ScheduledJob::InstrumentCatalogRefresh => self.refresh_instrument_catalog().await,
+            ScheduledJob::OrderReconciliation => {
+                let (checked, settled) = self.trading_service.reconcile_open_orders().await?;
+                Ok(JobOutcome::Completed(format!("Checked {} open orders, settled {} unsent", checked, settled)))
+            }
+            ScheduledJob::PortfolioSnapshots => {
+                let saved = self.snapshot_service.record_snapshots(&self.trading_service).await?;
+                Ok(JobOutcome::Completed(format!("Saved {} snapshots", saved)))
+            }
+            ScheduledJob::CandleAggregation => {
+                let saved = self.candle_service.aggregate_candles().await?;
+                Ok(JobOutcome::Completed(format!("Saved {} candles", saved)))
+            }
+            ScheduledJob::AlertEvaluation => {
+                let (checked, fired) = self.alert_service.evaluate_alerts(&self.trading_service).await?;
+                Ok(JobOutcome::Completed(format!("Checked {} alerts, {} fired", checked, fired)))
+            }
+            ScheduledJob::TriggerEvaluation => {
+                match self.trading_service.evaluate_triggers().await? {
+                    (0, _) => Ok(JobOutcome::Skipped("No pending trigger orders".to_string())),
+                    (checked, fired) => Ok(JobOutcome::Completed(format!("Checked {} trigger orders, {} fired", checked, fired))),
+                }
+            }
+            ScheduledJob::LedgerReconciliation => self.reconcile_ledgers().await,
+        }
+    }
+
+    /// Report each exchange's drift in the run message. Drift doesn't fail
+    /// the run; only failing to reconcile every exchange does.
+    async fn reconcile_ledgers(&self) -> Result<JobOutcome, String> {
+        let results = self.trading_service.reconcile_ledgers().await;
+        let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+        let reports: Vec<String> = results.into_iter()
+            .map(|(exchange, result)| match result {
+                Ok(drifts) if drifts.is_empty() => format!("{} balanced", exchange),
+                Ok(drifts) => format!("{} drifted on {}", exchange, drifts.iter()
+                    .map(|drift| format!("{} {:+} (exchange {}, ledgers {})",
+                        drift.asset, drift.drift(), drift.exchange_total, drift.ledger_total))
+                    .collect::<Vec<_>>()
+                    .join(", ")),
+                Err(e) => format!("{} failed: {}", exchange, e),
+            })
+            .collect();
+
+        if !reports.is_empty() && failed == reports.len() {
+            return Err(reports.join("; "));
+        }
+        Ok(JobOutcome::Completed(reports.join("; ")))
+    }
+
+    async fn refresh_instrument_catalog(&self) -> Result<JobOutcome, String> {
+        let mut counts = Vec::new();
+        for exchange in self.trading_service.exchange_names() {
+            let request = GetInstrumentsRequest {
+                exchange: exchange.clone(),
+                instrument_type: None,
+                status: None,
+            };
+            let response = self.trading_service.get_instruments(request).await
+                .map_err(|e| format!("{}: {}", exchange, e.error))?;
+            counts.push(format!("{} {}", exchange, response.instruments.len()));
+        }
+        Ok(JobOutcome::Completed(format!("Instruments loaded: {}", counts.join(", "))))
+    }
+
+    async fn record(&self, run: &JobRun) {
+        if let Err(e) = self.job_repository.save_run(run).await {
+            console_log!("SCHEDULER: Failed to record {} run {}: {}", run.job.as_str(), run.id, e);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use worker::console_log;
+use chrono::Utc;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde_json::Value;
+
+use crate::clients::trading::Exchange;
+use crate::dto::signal::{
+    ConfigureSignalHookRequest, GetSignalsRequest, GetSignalsResponse, SignalHookDto, SignalRiskLimitsDto,
+    TradingSignalDto, TradingSignalRequest,
+};
+use crate::dto::trading::{GetBalancesRequest, PlaceOrderRequest, PlaceOrderResponse};
+use crate::entity::signal::{SignalHook, SignalStatus, TradingSignal};
+use crate::entity::trading::{OrderSide, OrderType};
+use crate::repo::order::OrderRepository;
+use crate::repo::signal::SignalRepository;
+use crate::service::risk::{RiskEngine, RiskLimits};
+use crate::service::strategy::{OrderIntent, StrategyContext};
+use crate::service::trading::TradingService;
+
+/// Path signals are posted to, followed by the hook's token
+pub const SIGNAL_HOOK_PATH: &str = "/api/signals/hook";
+
+/// Decimal places a percent of balance quantity is rounded down to
+const QUANTITY_DECIMALS: u32 = 8;
+
+/// Signals returned when a request doesn't give a limit
+const DEFAULT_SIGNALS_LIMIT: u32 = 50;
+
+/// Most signals a single request may return
+const MAX_SIGNALS_LIMIT: u32 = 200;
+
+/// Why a signal didn't place an order, and how to record it
+type SignalError = (SignalStatus, String);
+
+/// Trading signals from external alerting tools, such as TradingView alerts.
+///
+/// Each user can set up one hook: a URL with a secret token that stands in
+/// for their login, since alerting tools can't sign in. A signal names the
+/// symbol, side, order type and either a quantity or a percentage of the
+/// free balance. It is checked against the balances and the hook's risk
+/// limits before being placed as an order, and every signal is logged with
+/// the order it led to, or why it didn't.
+#[derive(Clone)]
+pub struct SignalService {
+    signal_repository: SignalRepository,
+    order_repository: OrderRepository,
+    trading_service: TradingService,
+}
+
+impl SignalService {
+    pub fn new(signal_repository: SignalRepository, order_repository: OrderRepository, trading_service: TradingService) -> Self {
+        Self {
+            signal_repository,
+            order_repository,
+            trading_service,
+        }
+    }
+
+    pub async fn get_hook(&self, user_id: &str) -> Result<Option<SignalHookDto>, String> {
+        let hook = self.signal_repository.find_user_hook(user_id).await?;
+        Ok(hook.as_ref().map(convert_hook_to_dto))
+    }
+
+    /// Set up the user's hook, or change its limits, switch it on or off, or
+    /// give it a new token
+    pub async fn configure_hook(&self, user_id: &str, request: ConfigureSignalHookRequest) -> Result<SignalHookDto, String> {
+        console_log!("SIGNAL SERVICE: Configuring signal hook for {}", user_id);
+
+        let risk_limits = match request.risk_limits {
+            Some(limits) => {
+                let limits = convert_limits_from_dto(limits);
+                RiskEngine::new(limits.clone())?;
+                Some(serde_json::to_value(limits).map_err(|e| format!("Failed to store risk limits: {}", e))?)
+            }
+            None => None,
+        };
+
+        let now = Utc::now();
+        let hook = match self.signal_repository.find_user_hook(user_id).await? {
+            Some(mut hook) => {
+                if let Some(risk_limits) = risk_limits {
+                    hook.risk_limits = risk_limits;
+                }
+                if let Some(enabled) = request.enabled {
+                    hook.is_active = enabled;
+                }
+                if request.rotate_token.unwrap_or(false) {
+                    hook.token = generate_token();
+                }
+                hook.updated_at = now;
+                self.signal_repository.update_hook(&hook).await?;
+                hook
+            }
+            None => {
+                let hook = SignalHook {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    user_id: user_id.to_string(),
+                    token: generate_token(),
+                    risk_limits: risk_limits.unwrap_or_else(|| serde_json::json!({})),
+                    is_active: request.enabled.unwrap_or(true),
+                    created_at: now,
+                    updated_at: now,
+                };
+                self.signal_repository.save_hook(&hook).await?;
+                hook
+            }
+        };
+
+        Ok(convert_hook_to_dto(&hook))
+    }
+
+    /// The user's signal log, newest first
+    pub async fn get_signals(&self, user_id: &str, request: GetSignalsRequest) -> Result<GetSignalsResponse, String> {
+        let limit = request.limit.unwrap_or(DEFAULT_SIGNALS_LIMIT).clamp(1, MAX_SIGNALS_LIMIT);
+        let signals = self.signal_repository.find_user_signals(user_id, limit as usize).await?;
+        Ok(GetSignalsResponse {
+            signals: signals.iter().map(convert_signal_to_dto).collect(),
+        })
+    }
+
+    /// Act on a signal posted to a hook and log it. Returns None if no hook
+    /// has the token.
+    pub async fn receive_signal(&self, token: &str, body: &str) -> Result<Option<TradingSignalDto>, String> {
+        let Some(hook) = self.signal_repository.find_hook_by_token(token).await? else {
+            return Ok(None);
+        };
+        console_log!("SIGNAL SERVICE: Received signal on hook {} of {}", hook.id, hook.user_id);
+
+        let mut signal = TradingSignal {
+            id: uuid::Uuid::new_v4().to_string(),
+            hook_id: hook.id.clone(),
+            user_id: hook.user_id.clone(),
+            payload: body.to_string(),
+            status: SignalStatus::Rejected,
+            error: None,
+            exchange: None,
+            symbol: None,
+            side: None,
+            order_type: None,
+            quantity: None,
+            order_id: None,
+            order_status: None,
+            received_at: Utc::now(),
+        };
+
+        match self.execute_signal(&hook, body, &mut signal).await {
+            Ok(order) => {
+                console_log!("SIGNAL SERVICE: Signal {} placed order {}", signal.id, order.order_id);
+                signal.status = SignalStatus::Placed;
+                signal.order_id = Some(order.order_id);
+                signal.order_status = Some(order.status);
+            }
+            Err((status, error)) => {
+                console_log!("SIGNAL SERVICE: Signal {} {}: {}", signal.id, status.as_str(), error);
+                signal.status = status;
+                signal.error = Some(error);
+            }
+        }
+
+        if let Err(e) = self.signal_repository.save_signal(&signal).await {
+            console_log!("SIGNAL SERVICE: Failed to log signal {}: {}", signal.id, e);
+        }
+        Ok(Some(convert_signal_to_dto(&signal)))
+    }
+
+    /// Map a signal to an order, check it, and place it. What was worked out
+    /// along the way is filled into the signal's log entry.
+    async fn execute_signal(&self, hook: &SignalHook, body: &str, signal: &mut TradingSignal) -> Result<PlaceOrderResponse, SignalError> {
+        let rejected = |error: String| (SignalStatus::Rejected, error);
+        let failed = |error: String| (SignalStatus::Failed, error);
+
+        if !hook.is_active {
+            return Err(rejected("Signal hook is disabled".to_string()));
+        }
+        let request: TradingSignalRequest = serde_json::from_str(body)
+            .map_err(|e| rejected(format!("Invalid signal: {}", e)))?;
+
+        // TradingView's {{exchange}}:{{ticker}} form names the exchange in the symbol
+        let (symbol_exchange, ticker) = match request.symbol.split_once(':') {
+            Some((exchange, ticker)) => (Some(exchange.to_string()), ticker),
+            None => (None, request.symbol.as_str()),
+        };
+        let exchange = request.exchange.clone()
+            .or(symbol_exchange)
+            .map(|exchange| exchange.to_lowercase())
+            .ok_or_else(|| rejected("exchange is required unless the symbol names it".to_string()))?;
+        signal.exchange = Some(exchange.clone());
+        if Exchange::parse(&exchange).is_none() {
+            return Err(rejected(format!("Unsupported exchange: {}", exchange)));
+        }
+        let symbol = ticker.replace(['/', '-'], "").to_uppercase();
+        signal.symbol = Some(symbol.clone());
+        let (base, quote) = self.trading_service.parse_symbol(&symbol).map_err(|e| rejected(e.error))?;
+
+        let side = OrderSide::parse(&request.side)
+            .ok_or_else(|| rejected(format!("Invalid side: {} (expected BUY or SELL)", request.side)))?;
+        signal.side = Some(side.as_str().to_string());
+        let order_type = match &request.order_type {
+            Some(order_type) => OrderType::parse(order_type)
+                .filter(|order_type| matches!(order_type, OrderType::Market | OrderType::Limit))
+                .ok_or_else(|| rejected(format!("Unsupported order type: {} (expected MARKET or LIMIT)", order_type)))?,
+            None => OrderType::Market,
+        };
+        signal.order_type = Some(order_type.as_str().to_string());
+        let limit_price = match order_type {
+            OrderType::Limit => match request.price {
+                Some(price) if price > Decimal::ZERO => Some(price),
+                _ => return Err(rejected("LIMIT signals require a positive price".to_string())),
+            },
+            _ => None,
+        };
+
+        let market = self.trading_service.get_market_quote(&exchange, &base, &quote).await
+            .map_err(|e| failed(format!("Failed to get a price: {}", e.error)))?;
+        let price = limit_price.unwrap_or(match side {
+            OrderSide::Buy => market.ask_price,
+            OrderSide::Sell => market.bid_price,
+        });
+        if price <= Decimal::ZERO {
+            return Err(failed(format!("No price for {} on {}", symbol, exchange)));
+        }
+
+        let balances = self.trading_service.get_balances(GetBalancesRequest { exchange: exchange.clone() }).await
+            .map_err(|e| failed(format!("Failed to get balances: {}", e.error)))?;
+        let balance = |asset: &str| balances.balances.iter()
+            .find(|balance| balance.asset.eq_ignore_ascii_case(asset))
+            .map_or((Decimal::ZERO, Decimal::ZERO), |balance| (balance.free, balance.total));
+        let (base_free, base_total) = balance(&base);
+        let (quote_free, _) = balance(&quote);
+
+        let quantity = match (request.quantity, request.percent_of_balance) {
+            (Some(quantity), None) => quantity,
+            (None, Some(percent)) => {
+                if percent <= Decimal::ZERO || percent > Decimal::ONE_HUNDRED {
+                    return Err(rejected("percent_of_balance must be above 0 and at most 100".to_string()));
+                }
+                let fraction = percent / Decimal::ONE_HUNDRED;
+                let quantity = match side {
+                    OrderSide::Buy => quote_free * fraction / price,
+                    OrderSide::Sell => base_free * fraction,
+                };
+                quantity.round_dp_with_strategy(QUANTITY_DECIMALS, RoundingStrategy::ToZero)
+            }
+            _ => return Err(rejected("Give exactly one of quantity or percent_of_balance".to_string())),
+        };
+        signal.quantity = Some(quantity);
+        if quantity <= Decimal::ZERO {
+            return Err(rejected(format!("Order quantity must be positive, got {}", quantity)));
+        }
+
+        match side {
+            OrderSide::Buy if quantity * price > quote_free => {
+                return Err(rejected(format!(
+                    "Order needs {} {}, only {} is free",
+                    (quantity * price).round_dp(8), quote, quote_free,
+                )));
+            }
+            OrderSide::Sell if quantity > base_free => {
+                return Err(rejected(format!("Order sells {} {}, only {} is free", quantity, base, base_free)));
+            }
+            _ => {}
+        }
+
+        let limits: RiskLimits = serde_json::from_value(hook.risk_limits.clone())
+            .map_err(|e| failed(format!("Stored risk limits are invalid: {}", e)))?;
+        let open_orders = self.order_repository.find_open_orders(&hook.user_id, &exchange).await
+            .map_err(|e| failed(format!("Failed to load open orders: {}", e)))?;
+        let context = StrategyContext {
+            timestamp: Utc::now(),
+            cash: quote_free,
+            position: base_total,
+            last_price: Some(price),
+            open_orders: open_orders.len(),
+        };
+        let intent = match limit_price {
+            Some(price) => OrderIntent::Limit { side: side.clone(), quantity, price },
+            None => OrderIntent::Market { side: side.clone(), quantity },
+        };
+        RiskEngine::new(limits).and_then(|engine| engine.check(&intent, &context)).map_err(rejected)?;
+
+        let order_request = PlaceOrderRequest {
+            exchange,
+            symbol,
+            side: side.as_str().to_string(),
+            order_type: order_type.as_str().to_string(),
+            quantity,
+            price: limit_price,
+            time_in_force: None,
+            stop_price: None,
+            trigger_by: None,
+            client_order_id: request.signal_id,
+        };
+        self.trading_service.place_order(&hook.user_id, order_request).await
+            .map_err(|e| failed(format!("Failed to place order: {}", e.error)))
+    }
+}
+
+/// New random token for a user's signal hook
+fn generate_token() -> String {
+    format!("sig_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+fn convert_limits_from_dto(limits: SignalRiskLimitsDto) -> RiskLimits {
+    RiskLimits {
+        max_order_notional: limits.max_order_notional,
+        max_position: limits.max_position,
+        max_open_orders: limits.max_open_orders,
+        max_drawdown: None,
+    }
+}
+
+fn convert_limits_to_dto(limits: &RiskLimits) -> SignalRiskLimitsDto {
+    SignalRiskLimitsDto {
+        max_order_notional: limits.max_order_notional,
+        max_position: limits.max_position,
+        max_open_orders: limits.max_open_orders,
+    }
+}
+
+pub fn convert_hook_to_dto(hook: &SignalHook) -> SignalHookDto {
+    let limits: RiskLimits = serde_json::from_value(hook.risk_limits.clone()).unwrap_or_default();
+    SignalHookDto {
+        hook_id: hook.id.clone(),
+        token: hook.token.clone(),
+        path: format!("{}/{}", SIGNAL_HOOK_PATH, hook.token),
+        risk_limits: convert_limits_to_dto(&limits),
+        enabled: hook.is_active,
+        created_at: hook.created_at,
+        updated_at: hook.updated_at,
+    }
+}
+
+pub fn convert_signal_to_dto(signal: &TradingSignal) -> TradingSignalDto {
+    TradingSignalDto {
+        signal_id: signal.id.clone(),
+        status: signal.status.as_str().to_string(),
+        error: signal.error.clone(),
+        payload: serde_json::from_str(&signal.payload).unwrap_or_else(|_| Value::String(signal.payload.clone())),
+        exchange: signal.exchange.clone(),
+        symbol: signal.symbol.clone(),
+        side: signal.side.clone(),
+        order_type: signal.order_type.clone(),
+        quantity: signal.quantity,
+        order_id: signal.order_id.clone(),
+        order_status: signal.order_status.clone(),
+        received_at: signal.received_at,
+    }
+}
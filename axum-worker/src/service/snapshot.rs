@@ -0,0 +1,164 @@
+use worker::console_log;
+use rust_decimal::Decimal;
+use chrono::{Duration, Utc};
+
+use crate::dto::trading::{
+    DailyReturnDto, EquityPointDto, GetEquityCurveRequest, GetEquityCurveResponse, GetPortfolioRequest,
+    TradingErrorResponse,
+};
+use crate::entity::trading::PortfolioSnapshot;
+use crate::repo::order::OrderRepository;
+use crate::repo::snapshot::SnapshotRepository;
+use crate::repo::transfer::TransferRepository;
+use crate::service::equity_curve::build_equity_curve;
+use crate::service::trading::TradingService;
+
+/// Default equity-curve window when no start time is given
+const DEFAULT_HISTORY_DAYS: i64 = 30;
+
+/// Records portfolio snapshots and turns them into equity curves
+#[derive(Clone)]
+pub struct SnapshotService {
+    snapshot_repository: SnapshotRepository,
+    order_repository: OrderRepository,
+    transfer_repository: TransferRepository,
+}
+
+impl SnapshotService {
+    pub fn new(snapshot_repository: SnapshotRepository, order_repository: OrderRepository, transfer_repository: TransferRepository) -> Self {
+        Self {
+            snapshot_repository,
+            order_repository,
+            transfer_repository,
+        }
+    }
+
+    /// Snapshot each user's own portfolio on every exchange they have an
+    /// account on, plus an aggregate across them. Each snapshot carries the
+    /// deposits and withdrawals recorded since the exchange's previous one,
+    /// so returns can leave them out. Exchanges that fail to value are
+    /// skipped and left out of the aggregate. Returns the number of snapshots saved.
+    pub async fn record_snapshots(&self, trading_service: &TradingService) -> Result<u32, String> {
+        let mut users = self.order_repository.find_trading_users().await?;
+        users.extend(self.transfer_repository.find_transfer_users().await?);
+        users.sort();
+        users.dedup();
+        console_log!("SNAPSHOT SERVICE: Recording snapshots for {} users", users.len());
+
+        let taken_at = Utc::now();
+        let mut saved = 0;
+        for user_id in &users {
+            let exchanges = match trading_service.account_exchanges(user_id).await {
+                Ok(exchanges) => exchanges,
+                Err(e) => {
+                    console_log!("SNAPSHOT SERVICE: Failed to find the accounts of {}: {}", user_id, e);
+                    continue;
+                }
+            };
+            let mut aggregate = PortfolioSnapshot::new(
+                user_id.clone(),
+                PortfolioSnapshot::AGGREGATE_EXCHANGE.to_string(),
+                taken_at,
+            );
+            let mut valued = 0;
+
+            for exchange in &exchanges {
+                let request = GetPortfolioRequest {
+                    exchange: exchange.clone(),
+                    cost_basis_method: None,
+                    session_start: None,
+                };
+                let portfolio = match trading_service.get_portfolio(user_id, request).await {
+                    Ok(portfolio) => portfolio,
+                    Err(e) => {
+                        console_log!("SNAPSHOT SERVICE: Failed to value {} for {}: {}", exchange, user_id, e.error);
+                        continue;
+                    }
+                };
+
+                let mut snapshot = PortfolioSnapshot::new(user_id.clone(), exchange.clone(), taken_at);
+                snapshot.total_value_usd = portfolio.total_value_usd;
+                snapshot.realized_pnl_usd = portfolio.asset_pnl.iter().map(|asset| asset.realized_pnl_usd).sum();
+                snapshot.unrealized_pnl_usd = portfolio.asset_pnl.iter().map(|asset| asset.unrealized_pnl_usd).sum();
+                snapshot.net_flow_usd = match self.net_flow_usd(trading_service, user_id, exchange).await {
+                    Ok(flow) => flow,
+                    Err(e) => {
+                        console_log!("SNAPSHOT SERVICE: Failed to value {} transfers for {}: {}", exchange, user_id, e);
+                        continue;
+                    }
+                };
+
+                match self.snapshot_repository.save_snapshot(&snapshot).await {
+                    Ok(()) => saved += 1,
+                    Err(e) => console_log!("SNAPSHOT SERVICE: Failed to save {} snapshot for {}: {}", exchange, user_id, e),
+                }
+                aggregate.accumulate(&snapshot);
+                valued += 1;
+            }
+
+            if valued > 0 {
+                match self.snapshot_repository.save_snapshot(&aggregate).await {
+                    Ok(()) => saved += 1,
+                    Err(e) => console_log!("SNAPSHOT SERVICE: Failed to save aggregate snapshot for {}: {}", user_id, e),
+                }
+            }
+        }
+
+        console_log!("SNAPSHOT SERVICE: Saved {} snapshots", saved);
+        Ok(saved)
+    }
+
+    /// Deposits minus withdrawals recorded since the user's previous snapshot of the exchange
+    async fn net_flow_usd(&self, trading_service: &TradingService, user_id: &str, exchange: &str) -> Result<Decimal, String> {
+        let since = self.snapshot_repository.find_latest_taken_at(user_id, exchange).await?;
+        trading_service.net_flow_usd(user_id, exchange, since).await
+    }
+
+    /// Equity curve for a user on one exchange, or across all exchanges
+    pub async fn get_equity_curve(&self, user_id: &str, request: GetEquityCurveRequest) -> Result<GetEquityCurveResponse, TradingErrorResponse> {
+        let exchange = request.exchange
+            .map(|exchange| exchange.to_lowercase())
+            .unwrap_or_else(|| PortfolioSnapshot::AGGREGATE_EXCHANGE.to_string());
+        let end_time = request.end_time.unwrap_or_else(Utc::now);
+        let start_time = request.start_time.unwrap_or(end_time - Duration::days(DEFAULT_HISTORY_DAYS));
+        if start_time >= end_time {
+            return Err(TradingErrorResponse::with_code("start_time must be before end_time".to_string(), "INVALID_TIME_RANGE".to_string()));
+        }
+
+        console_log!("SNAPSHOT SERVICE: Building {} equity curve for {}", exchange, user_id);
+
+        let snapshots = self.snapshot_repository.find_snapshots(user_id, &exchange, start_time, end_time).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load portfolio snapshots: {}", e)))?;
+        let curve = build_equity_curve(&snapshots);
+
+        let points = snapshots.iter().zip(&curve.points)
+            .map(|(snapshot, point)| EquityPointDto {
+                timestamp: point.timestamp,
+                total_value_usd: point.value,
+                realized_pnl_usd: snapshot.realized_pnl_usd,
+                unrealized_pnl_usd: snapshot.unrealized_pnl_usd,
+                net_flow_usd: snapshot.net_flow_usd,
+                drawdown_percentage: (point.drawdown * Decimal::ONE_HUNDRED).round_dp(2),
+            })
+            .collect();
+        let daily_returns = curve.daily_returns.iter()
+            .map(|daily| DailyReturnDto {
+                date: daily.date.format("%Y-%m-%d").to_string(),
+                close_value_usd: daily.close,
+                return_percentage: (daily.return_fraction * Decimal::ONE_HUNDRED).round_dp(4),
+            })
+            .collect();
+
+        Ok(GetEquityCurveResponse {
+            exchange,
+            start_time,
+            end_time,
+            points,
+            daily_returns,
+            start_value_usd: snapshots.first().map(|s| s.total_value_usd).unwrap_or(Decimal::ZERO),
+            end_value_usd: snapshots.last().map(|s| s.total_value_usd).unwrap_or(Decimal::ZERO),
+            max_drawdown_percentage: (curve.max_drawdown * Decimal::ONE_HUNDRED).round_dp(2),
+            time_weighted_return_percentage: (curve.time_weighted_return * Decimal::ONE_HUNDRED).round_dp(4),
+        })
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::VecDeque;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::entity::market_data::{Candle, OrderBook, Trade};
+use crate::entity::trading::OrderSide;
+use crate::service::bots::{Dca, Grid};
+
+/// Names accepted by `build_strategy`
+pub const STRATEGIES: [&str; 4] = ["buy_and_hold", "sma_crossover", "dca", "grid"];
+
+/// Order a strategy asks for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OrderIntent {
+    /// Fill at the next price the market offers
+    Market { side: OrderSide, quantity: Decimal },
+    /// Rest until the market reaches `price`
+    Limit { side: OrderSide, quantity: Decimal, price: Decimal },
+    /// Cancel every resting limit order
+    CancelAll,
+}
+
+/// Fill of one of the strategy's orders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyFill {
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    /// Paid in the quote asset
+    pub fee: Decimal,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The strategy's account when an event is delivered
+#[derive(Debug, Clone)]
+pub struct StrategyContext {
+    pub timestamp: DateTime<Utc>,
+    /// Quote asset available
+    pub cash: Decimal,
+    /// Base asset held
+    pub position: Decimal,
+    pub last_price: Option<Decimal>,
+    /// Orders not filled or cancelled yet, market orders included
+    pub open_orders: usize,
+}
+
+/// A trading strategy driven by market events.
+///
+/// Each hook sees the account as it stands and returns the orders the
+/// strategy wants. Hooks it doesn't need can be left out. The same strategy
+/// runs in backtests and in the strategy runtime, live or on paper.
+pub trait Strategy {
+    fn name(&self) -> &'static str;
+
+    /// Settings the strategy runs with, including defaults it filled in
+    fn parameters(&self) -> Value;
+
+    fn on_candle(&mut self, _context: &StrategyContext, _candle: &Candle) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    fn on_trade(&mut self, _context: &StrategyContext, _trade: &Trade) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    fn on_book(&mut self, _context: &StrategyContext, _book: &OrderBook) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    fn on_fill(&mut self, _context: &StrategyContext, _fill: &StrategyFill) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    /// Called about once a minute by the runtime; backtests don't call it
+    fn on_timer(&mut self, _context: &StrategyContext) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    /// Called when the runtime resumes a paused strategy. Pausing cancelled
+    /// every open order, so this is where a strategy puts back the ones it
+    /// still wants.
+    fn on_resume(&mut self, _context: &StrategyContext) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    /// What the strategy has learned from the events so far, saved so a
+    /// running strategy resumes where it left off
+    fn state(&self) -> Value {
+        Value::Null
+    }
+
+    /// Resume from what `state` returned
+    fn restore_state(&mut self, _state: &Value) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Build a built-in strategy from its name and JSON parameters. Missing
+/// parameters take their defaults; unknown ones are rejected.
+pub fn build_strategy(name: &str, parameters: &Value) -> Result<Box<dyn Strategy>, String> {
+    match name.to_lowercase().as_str() {
+        "buy_and_hold" => Ok(Box::new(BuyAndHold::new(parse_parameters(name, parameters)?)?)),
+        "sma_crossover" => Ok(Box::new(SmaCrossover::new(parse_parameters(name, parameters)?)?)),
+        "dca" => Ok(Box::new(Dca::new(parse_parameters(name, parameters)?)?)),
+        "grid" => Ok(Box::new(Grid::new(parse_parameters(name, parameters)?)?)),
+        _ => Err(format!("Unknown strategy: {} (expected one of {})", name, STRATEGIES.join(", "))),
+    }
+}
+
+/// Restore state saved by a strategy's `state`
+pub(crate) fn parse_state<T: for<'de> Deserialize<'de>>(name: &str, state: &Value) -> Result<T, String> {
+    serde_json::from_value(state.clone()).map_err(|e| format!("Invalid saved state for {}: {}", name, e))
+}
+
+pub(crate) fn parse_parameters<T: for<'de> Deserialize<'de>>(name: &str, parameters: &Value) -> Result<T, String> {
+    let parameters = match parameters {
+        Value::Null => Value::Object(Default::default()),
+        other => other.clone(),
+    };
+    serde_json::from_value(parameters).map_err(|e| format!("Invalid parameters for {}: {}", name, e))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuyAndHoldParameters {
+    /// Fraction of the starting cash to buy with
+    pub allocation: Decimal,
+}
+
+impl Default for BuyAndHoldParameters {
+    fn default() -> Self {
+        Self { allocation: Decimal::ONE }
+    }
+}
+
+/// Buys once at the first price seen and holds to the end; the baseline
+/// other strategies are measured against
+#[derive(Debug, Clone)]
+pub struct BuyAndHold {
+    parameters: BuyAndHoldParameters,
+    state: BuyAndHoldState,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BuyAndHoldState {
+    bought: bool,
+}
+
+impl BuyAndHold {
+    pub fn new(parameters: BuyAndHoldParameters) -> Result<Self, String> {
+        if parameters.allocation <= Decimal::ZERO || parameters.allocation > Decimal::ONE {
+            return Err("allocation must be above 0 and at most 1".to_string());
+        }
+        Ok(Self { parameters, state: BuyAndHoldState::default() })
+    }
+
+    fn buy(&mut self, context: &StrategyContext, price: Decimal) -> Vec<OrderIntent> {
+        if self.state.bought || price <= Decimal::ZERO {
+            return Vec::new();
+        }
+        self.state.bought = true;
+        vec![OrderIntent::Market {
+            side: OrderSide::Buy,
+            quantity: context.cash * self.parameters.allocation / price,
+        }]
+    }
+}
+
+impl Strategy for BuyAndHold {
+    fn name(&self) -> &'static str {
+        "buy_and_hold"
+    }
+
+    fn parameters(&self) -> Value {
+        serde_json::to_value(&self.parameters).unwrap_or_default()
+    }
+
+    fn on_candle(&mut self, context: &StrategyContext, candle: &Candle) -> Vec<OrderIntent> {
+        self.buy(context, candle.close)
+    }
+
+    fn on_trade(&mut self, context: &StrategyContext, trade: &Trade) -> Vec<OrderIntent> {
+        self.buy(context, trade.price)
+    }
+
+    fn state(&self) -> Value {
+        serde_json::to_value(&self.state).unwrap_or_default()
+    }
+
+    fn restore_state(&mut self, state: &Value) -> Result<(), String> {
+        self.state = parse_state(self.name(), state)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmaCrossoverParameters {
+    /// Candles in the fast moving average
+    pub fast: usize,
+    /// Candles in the slow moving average
+    pub slow: usize,
+    /// Fraction of the cash to buy with on each entry
+    pub allocation: Decimal,
+}
+
+impl Default for SmaCrossoverParameters {
+    fn default() -> Self {
+        Self { fast: 10, slow: 30, allocation: Decimal::ONE }
+    }
+}
+
+/// Goes long when the fast simple moving average of candle closes crosses
+/// above the slow one, and sells everything when it crosses back below
+#[derive(Debug, Clone)]
+pub struct SmaCrossover {
+    parameters: SmaCrossoverParameters,
+    state: SmaCrossoverState,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SmaCrossoverState {
+    /// Closes of the last `slow` candles, oldest first
+    closes: VecDeque<Decimal>,
+    /// Whether the fast average was above the slow one at the previous candle
+    fast_above: Option<bool>,
+}
+
+impl SmaCrossover {
+    pub fn new(parameters: SmaCrossoverParameters) -> Result<Self, String> {
+        if parameters.fast == 0 || parameters.fast >= parameters.slow {
+            return Err("fast must be at least 1 and below slow".to_string());
+        }
+        if parameters.allocation <= Decimal::ZERO || parameters.allocation > Decimal::ONE {
+            return Err("allocation must be above 0 and at most 1".to_string());
+        }
+        Ok(Self {
+            state: SmaCrossoverState {
+                closes: VecDeque::with_capacity(parameters.slow),
+                fast_above: None,
+            },
+            parameters,
+        })
+    }
+
+    fn average(&self, period: usize) -> Decimal {
+        let sum: Decimal = self.state.closes.iter().rev().take(period).sum();
+        sum / Decimal::from(period)
+    }
+}
+
+impl Strategy for SmaCrossover {
+    fn name(&self) -> &'static str {
+        "sma_crossover"
+    }
+
+    fn parameters(&self) -> Value {
+        serde_json::to_value(&self.parameters).unwrap_or_default()
+    }
+
+    fn on_candle(&mut self, context: &StrategyContext, candle: &Candle) -> Vec<OrderIntent> {
+        let closes = &mut self.state.closes;
+        while closes.len() >= self.parameters.slow {
+            closes.pop_front();
+        }
+        closes.push_back(candle.close);
+        if closes.len() < self.parameters.slow {
+            return Vec::new();
+        }
+
+        let fast_above = self.average(self.parameters.fast) > self.average(self.parameters.slow);
+        let crossed = self.state.fast_above.is_some_and(|previous| previous != fast_above);
+        self.state.fast_above = Some(fast_above);
+        if !crossed {
+            return Vec::new();
+        }
+
+        if fast_above && context.position.is_zero() && candle.close > Decimal::ZERO {
+            vec![OrderIntent::Market {
+                side: OrderSide::Buy,
+                quantity: context.cash * self.parameters.allocation / candle.close,
+            }]
+        } else if !fast_above && context.position > Decimal::ZERO {
+            vec![OrderIntent::Market { side: OrderSide::Sell, quantity: context.position }]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn state(&self) -> Value {
+        serde_json::to_value(&self.state).unwrap_or_default()
+    }
+
+    fn restore_state(&mut self, state: &Value) -> Result<(), String> {
+        self.state = parse_state(self.name(), state)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::trading::OrderSide;
+use crate::service::fill_model::{FillModel, QUANTITY_DECIMALS};
+use crate::service::strategy::{OrderIntent, StrategyContext, StrategyFill};
+
+/// Simulated order, resting or due to fill
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedOrder {
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    /// Limit price while resting; the fill price once due
+    pub price: Decimal,
+}
+
+/// Cash, position and simulated orders of one strategy trading one
+/// instrument, long only.
+///
+/// Backtests and paper trading share it, so a strategy fills the same way in
+/// both. The caller decides when orders fill: `due_market_orders` and
+/// `due_limit_orders` hand back what the latest price fills, and `execute`
+/// books each fill. Live trading only books the fills the exchange reports
+/// through `apply_fill`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyAccount {
+    pub fill_model: FillModel,
+    /// Quote asset available
+    pub cash: Decimal,
+    /// Base asset held
+    pub position: Decimal,
+    pub last_price: Option<Decimal>,
+    /// Market orders waiting for the next price
+    pending: Vec<(OrderSide, Decimal)>,
+    /// Limit orders waiting for the market to reach their price
+    resting: Vec<SimulatedOrder>,
+}
+
+impl StrategyAccount {
+    pub fn new(cash: Decimal, fill_model: FillModel) -> Self {
+        Self {
+            fill_model,
+            cash,
+            position: Decimal::ZERO,
+            last_price: None,
+            pending: Vec::new(),
+            resting: Vec::new(),
+        }
+    }
+
+    pub fn context(&self, timestamp: DateTime<Utc>) -> StrategyContext {
+        StrategyContext {
+            timestamp,
+            cash: self.cash,
+            position: self.position,
+            last_price: self.last_price,
+            open_orders: self.pending.len() + self.resting.len(),
+        }
+    }
+
+    /// Cash plus the position marked at the last price
+    pub fn equity(&self) -> Decimal {
+        self.cash + self.position * self.last_price.unwrap_or_default()
+    }
+
+    pub fn resting_orders(&self) -> &[SimulatedOrder] {
+        &self.resting
+    }
+
+    /// Queue a simulated order; orders without a quantity or price are ignored
+    pub fn submit(&mut self, intent: OrderIntent) {
+        match intent {
+            OrderIntent::Market { side, quantity } if quantity > Decimal::ZERO => self.pending.push((side, quantity)),
+            OrderIntent::Limit { side, quantity, price } if quantity > Decimal::ZERO && price > Decimal::ZERO =>
+                self.resting.push(SimulatedOrder { side, quantity, price }),
+            OrderIntent::CancelAll => self.resting.clear(),
+            _ => {}
+        }
+    }
+
+    /// Drop every queued market order and resting limit order
+    pub fn cancel_all(&mut self) {
+        self.pending.clear();
+        self.resting.clear();
+    }
+
+    /// Take the queued market orders, priced to fill at `price` plus slippage
+    pub fn due_market_orders(&mut self, price: Decimal) -> Vec<SimulatedOrder> {
+        let model = self.fill_model;
+        std::mem::take(&mut self.pending).into_iter()
+            .map(|(side, quantity)| SimulatedOrder { price: model.market_price(&side, price), side, quantity })
+            .collect()
+    }
+
+    /// Take the resting limit orders reached by a market trading between
+    /// `low` and `high`, priced by where it opened
+    pub fn due_limit_orders(&mut self, low: Decimal, high: Decimal, open: Decimal) -> Vec<SimulatedOrder> {
+        let model = self.fill_model;
+        let (reached, waiting): (Vec<SimulatedOrder>, Vec<SimulatedOrder>) = std::mem::take(&mut self.resting)
+            .into_iter()
+            .partition(|order| model.limit_reached(&order.side, order.price, low, high));
+        self.resting = waiting;
+
+        reached.into_iter()
+            .map(|order| SimulatedOrder { price: model.limit_price(&order.side, order.price, open), ..order })
+            .collect()
+    }
+
+    /// Book a simulated fill. Buys are cut down to what the cash covers and
+    /// sells to the position held; None when nothing is left to fill.
+    pub fn execute(&mut self, order: SimulatedOrder, timestamp: DateTime<Utc>) -> Option<StrategyFill> {
+        let available = match order.side {
+            OrderSide::Buy => self.fill_model.affordable_quantity(self.cash, order.price),
+            OrderSide::Sell => self.position,
+        };
+        let quantity = order.quantity.min(available).round_dp_with_strategy(QUANTITY_DECIMALS, RoundingStrategy::ToZero);
+        if quantity <= Decimal::ZERO {
+            return None;
+        }
+
+        let fill = StrategyFill {
+            fee: self.fill_model.fee(quantity, order.price),
+            side: order.side,
+            quantity,
+            price: order.price,
+            timestamp,
+        };
+        self.apply_fill(&fill);
+        Some(fill)
+    }
+
+    /// Move cash and position by a fill
+    pub fn apply_fill(&mut self, fill: &StrategyFill) {
+        let notional = fill.quantity * fill.price;
+        match fill.side {
+            OrderSide::Buy => {
+                self.cash -= notional + fill.fee;
+                self.position += fill.quantity;
+            }
+            OrderSide::Sell => {
+                self.cash += notional - fill.fee;
+                self.position -= fill.quantity;
+            }
+        }
+    }
+}
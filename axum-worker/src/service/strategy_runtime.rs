@@ -0,0 +1,537 @@
+use std::collections::VecDeque;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dto::trading::{OrderDto, PlaceOrderRequest};
+use crate::entity::market_data::{Candle, CandleInterval, Instrument, MarketDataEvent, OrderBook, Trade};
+use crate::entity::strategy::{StrategyMode, StrategyStatus};
+use crate::entity::trading::{OrderSide, OrderStatus};
+use crate::service::candle_aggregator::CandleAggregator;
+use crate::service::fill_model::{FillModel, QUANTITY_DECIMALS};
+use crate::service::risk::{RiskEngine, RiskLimits};
+use crate::service::strategy::{build_strategy, OrderIntent, Strategy, StrategyContext, StrategyFill};
+use crate::service::strategy_account::{SimulatedOrder, StrategyAccount};
+
+/// How long a candle built from the live trades waits for late trades
+const CANDLE_LATENESS_SECONDS: i64 = 2;
+
+/// Fills kept for the status report
+const RECENT_FILLS: usize = 50;
+
+/// Rejected orders kept for the status report
+const RECENT_REJECTIONS: usize = 20;
+
+/// How a strategy instance runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyConfig {
+    pub strategy_id: String,
+    pub user_id: String,
+    pub strategy: String,
+    pub parameters: Value,
+    pub instrument: Instrument,
+    pub mode: StrategyMode,
+    /// Interval of the candles built from trades for `Strategy::on_candle`
+    pub interval: CandleInterval,
+    /// Quote asset the strategy may trade with; in live mode a budget carved
+    /// out of the exchange balance
+    pub initial_cash: Decimal,
+    /// Simulates paper fills, and prices the fees of live fills
+    pub fill_model: FillModel,
+    pub risk_limits: RiskLimits,
+}
+
+/// Live order placed by the strategy that hasn't finished yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingOrder {
+    pub order_id: String,
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    /// None for a market order
+    pub price: Option<Decimal>,
+    /// Filled so far, and what it cost in the quote asset
+    pub filled_quantity: Decimal,
+    pub filled_notional: Decimal,
+}
+
+/// Order the risk engine or the exchange turned down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedOrder {
+    pub intent: OrderIntent,
+    pub reason: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// What the runtime needs done on the exchange in live mode
+#[derive(Debug, Clone)]
+pub enum RuntimeAction {
+    /// Place an order for the intent, reporting back through `order_placed`
+    /// or `order_failed`
+    Place { intent: OrderIntent, request: Box<PlaceOrderRequest> },
+    /// Cancel every working order
+    CancelAll,
+}
+
+/// Running totals of a strategy's fills for its performance summary. The
+/// position is costed at its average price, fees included.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StrategyPerformance {
+    pub buys: u64,
+    pub sells: u64,
+    pub bought_quantity: Decimal,
+    pub bought_notional: Decimal,
+    pub sold_quantity: Decimal,
+    pub sold_notional: Decimal,
+    /// Base asset bought and not sold yet, and what it cost
+    pub held_quantity: Decimal,
+    pub cost_basis: Decimal,
+    /// Proceeds of sales, net of fees, less the cost of what was sold
+    pub realized_pnl: Decimal,
+}
+
+impl StrategyPerformance {
+    fn record(&mut self, fill: &StrategyFill) {
+        let notional = fill.quantity * fill.price;
+        match fill.side {
+            OrderSide::Buy => {
+                self.buys += 1;
+                self.bought_quantity += fill.quantity;
+                self.bought_notional += notional;
+                self.held_quantity += fill.quantity;
+                self.cost_basis += notional + fill.fee;
+            }
+            OrderSide::Sell => {
+                self.sells += 1;
+                self.sold_quantity += fill.quantity;
+                self.sold_notional += notional;
+                let closed = fill.quantity.min(self.held_quantity);
+                let cost = match self.average_cost() {
+                    Some(average) => average * closed,
+                    None => Decimal::ZERO,
+                };
+                self.realized_pnl += notional - fill.fee - cost;
+                self.held_quantity -= closed;
+                self.cost_basis -= cost;
+            }
+        }
+    }
+
+    /// Average cost of the base asset held, fees included
+    pub fn average_cost(&self) -> Option<Decimal> {
+        (self.held_quantity > Decimal::ZERO).then(|| self.cost_basis / self.held_quantity)
+    }
+
+    /// What the base asset held would gain if sold at `price`, before fees
+    pub fn unrealized_pnl(&self, price: Decimal) -> Decimal {
+        self.held_quantity * price - self.cost_basis
+    }
+}
+
+/// Everything a strategy instance needs to resume, saved by its Durable Object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeState {
+    pub config: StrategyConfig,
+    pub status: StrategyStatus,
+    pub status_reason: Option<String>,
+    pub account: StrategyAccount,
+    /// What `Strategy::state` returned when this was saved
+    pub strategy_state: Value,
+    pub working_orders: Vec<WorkingOrder>,
+    pub recent_fills: VecDeque<StrategyFill>,
+    pub recent_rejections: VecDeque<RejectedOrder>,
+    pub events: u64,
+    pub orders_sent: u64,
+    pub orders_rejected: u64,
+    pub fills: u64,
+    pub fees_paid: Decimal,
+    #[serde(default)]
+    pub performance: StrategyPerformance,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Runs a strategy against the live market.
+///
+/// Market data events are passed to the strategy's hooks, with trades also
+/// folded into candles of the configured interval. Every order the strategy
+/// asks for is checked by the risk engine first. In paper mode approved
+/// orders fill in the simulated account exactly as in a backtest; in live
+/// mode they come back as `RuntimeAction`s for the caller to send, and the
+/// account only moves when the exchange reports fills through `sync_order`.
+///
+/// When the equity breaches the loss limit the strategy is halted: working
+/// orders are cancelled and nothing more is sent, but any position is left
+/// as it is. A paused strategy is the same except that it can resume: it
+/// sees no market data meanwhile, but is still told of fills of orders that
+/// were working when it paused.
+pub struct StrategyRuntime {
+    state: RuntimeState,
+    strategy: Box<dyn Strategy>,
+    risk: RiskEngine,
+    aggregator: CandleAggregator,
+}
+
+impl StrategyRuntime {
+    pub fn start(config: StrategyConfig, now: DateTime<Utc>) -> Result<Self, String> {
+        if config.initial_cash <= Decimal::ZERO {
+            return Err("initial_cash must be positive".to_string());
+        }
+        let state = RuntimeState {
+            status: StrategyStatus::Running,
+            status_reason: None,
+            account: StrategyAccount::new(config.initial_cash, config.fill_model),
+            strategy_state: Value::Null,
+            working_orders: Vec::new(),
+            recent_fills: VecDeque::new(),
+            recent_rejections: VecDeque::new(),
+            events: 0,
+            orders_sent: 0,
+            orders_rejected: 0,
+            fills: 0,
+            fees_paid: Decimal::ZERO,
+            performance: StrategyPerformance::default(),
+            started_at: now,
+            updated_at: now,
+            config,
+        };
+        Self::build(state)
+    }
+
+    /// Resume from a saved state. Candles still being built when it was
+    /// saved are lost.
+    pub fn restore(state: RuntimeState) -> Result<Self, String> {
+        let saved = state.strategy_state.clone();
+        let mut runtime = Self::build(state)?;
+        if !saved.is_null() {
+            runtime.strategy.restore_state(&saved)?;
+        }
+        Ok(runtime)
+    }
+
+    fn build(state: RuntimeState) -> Result<Self, String> {
+        let config = &state.config;
+        Ok(Self {
+            strategy: build_strategy(&config.strategy, &config.parameters)?,
+            risk: RiskEngine::new(config.risk_limits.clone())?,
+            aggregator: CandleAggregator::new(
+                config.instrument.clone(),
+                config.interval,
+                Duration::seconds(CANDLE_LATENESS_SECONDS),
+            ),
+            state,
+        })
+    }
+
+    pub fn config(&self) -> &StrategyConfig {
+        &self.state.config
+    }
+
+    pub fn state(&self) -> &RuntimeState {
+        &self.state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.status == StrategyStatus::Running
+    }
+
+    /// State to save, including what the strategy has learned
+    pub fn snapshot(&self) -> RuntimeState {
+        let mut state = self.state.clone();
+        state.strategy_state = self.strategy.state();
+        state
+    }
+
+    pub fn working_order_ids(&self) -> Vec<String> {
+        self.state.working_orders.iter().map(|order| order.order_id.clone()).collect()
+    }
+
+    /// Stop for good: simulated orders are dropped, and working live orders
+    /// come back to be cancelled
+    pub fn stop(&mut self, status: StrategyStatus, reason: Option<String>, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        if !self.state.status.is_active() {
+            return Vec::new();
+        }
+        self.state.status = status;
+        self.state.status_reason = reason;
+        self.state.updated_at = now;
+        self.cancel_orders()
+    }
+
+    /// Stop trading until `resume`, cancelling open orders the same way as `stop`
+    pub fn pause(&mut self, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        if !self.is_running() {
+            return Vec::new();
+        }
+        self.state.status = StrategyStatus::Paused;
+        self.state.updated_at = now;
+        self.cancel_orders()
+    }
+
+    /// Resume a paused strategy, placing the orders it wants back
+    pub fn resume(&mut self, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        if self.state.status != StrategyStatus::Paused {
+            return Vec::new();
+        }
+        self.state.status = StrategyStatus::Running;
+        self.state.updated_at = now;
+        let intents = self.strategy.on_resume(&self.context(now));
+        self.route(intents, now)
+    }
+
+    fn cancel_orders(&mut self) -> Vec<RuntimeAction> {
+        self.state.account.cancel_all();
+        match self.state.config.mode {
+            StrategyMode::Live if !self.state.working_orders.is_empty() => vec![RuntimeAction::CancelAll],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn on_event(&mut self, event: &MarketDataEvent, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        if !self.is_running() {
+            return Vec::new();
+        }
+        self.state.events += 1;
+        self.state.updated_at = now;
+        match event {
+            MarketDataEvent::Trade(trade) => self.on_trade(trade),
+            MarketDataEvent::OrderBook(book) => self.on_book(book),
+            // Candles are built from the trades at the strategy's own interval
+            MarketDataEvent::Candle(_) => Vec::new(),
+        }
+    }
+
+    /// Close candles whose interval has ended and call the strategy's timer
+    pub fn on_timer(&mut self, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        if !self.is_running() {
+            return Vec::new();
+        }
+        self.state.updated_at = now;
+        let mut actions = self.close_candles(now);
+        let intents = self.strategy.on_timer(&self.context(now));
+        actions.extend(self.route(intents, now));
+        actions
+    }
+
+    fn on_trade(&mut self, trade: &Trade) -> Vec<RuntimeAction> {
+        let traded_at = trade.exchange_timestamp;
+        // Candles built from earlier trades close before this trade is seen
+        let mut actions = self.close_candles(traded_at);
+        self.aggregator.push(trade);
+
+        if self.state.config.mode == StrategyMode::Paper {
+            for order in self.state.account.due_market_orders(trade.price) {
+                actions.extend(self.execute_paper(order, traded_at));
+            }
+            for order in self.state.account.due_limit_orders(trade.price, trade.price, trade.price) {
+                actions.extend(self.execute_paper(order, traded_at));
+            }
+        }
+
+        self.state.account.last_price = Some(trade.price);
+        actions.extend(self.check_loss_limit(traded_at));
+        if !self.is_running() {
+            return actions;
+        }
+        let intents = self.strategy.on_trade(&self.context(traded_at), trade);
+        actions.extend(self.route(intents, traded_at));
+        actions
+    }
+
+    fn on_book(&mut self, book: &OrderBook) -> Vec<RuntimeAction> {
+        let intents = self.strategy.on_book(&self.context(book.exchange_timestamp), book);
+        self.route(intents, book.exchange_timestamp)
+    }
+
+    fn close_candles(&mut self, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        let mut actions = Vec::new();
+        for candle in self.aggregator.close_until(now) {
+            actions.extend(self.on_candle(&candle));
+        }
+        actions
+    }
+
+    fn on_candle(&mut self, candle: &Candle) -> Vec<RuntimeAction> {
+        if !self.is_running() {
+            return Vec::new();
+        }
+        let intents = self.strategy.on_candle(&self.context(candle.close_time()), candle);
+        self.route(intents, candle.close_time())
+    }
+
+    /// Fold a live order's latest state into the account, passing any new
+    /// fill to the strategy. Fees are estimated with the fill model, since
+    /// the exchange may charge them in either asset.
+    pub fn sync_order(&mut self, order: &OrderDto, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        let Some(index) = self.state.working_orders.iter().position(|working| working.order_id == order.order_id) else {
+            return Vec::new();
+        };
+
+        let mut actions = Vec::new();
+        let working = &mut self.state.working_orders[index];
+        let quantity = order.filled_quantity - working.filled_quantity;
+        if quantity > Decimal::ZERO {
+            let filled_notional = order.average_price
+                .map(|price| price * order.filled_quantity)
+                .unwrap_or(working.filled_notional);
+            let price = (filled_notional - working.filled_notional) / quantity;
+            working.filled_quantity = order.filled_quantity;
+            working.filled_notional = filled_notional;
+
+            let fill = StrategyFill {
+                side: working.side.clone(),
+                quantity,
+                price,
+                fee: self.state.account.fill_model.fee(quantity, price),
+                timestamp: now,
+            };
+            self.state.account.apply_fill(&fill);
+            actions.extend(self.record_fill(fill));
+        }
+
+        let finished = OrderStatus::parse(&order.status).is_some_and(|status| matches!(status,
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Rejected | OrderStatus::Expired));
+        if finished {
+            self.state.working_orders.retain(|working| working.order_id != order.order_id);
+        }
+        self.state.updated_at = now;
+        actions
+    }
+
+    /// Track a live order the exchange accepted
+    pub fn order_placed(&mut self, order_id: String, side: OrderSide, quantity: Decimal, price: Option<Decimal>) {
+        self.state.working_orders.push(WorkingOrder {
+            order_id,
+            side,
+            quantity,
+            price,
+            filled_quantity: Decimal::ZERO,
+            filled_notional: Decimal::ZERO,
+        });
+    }
+
+    /// Record a live order the trading service refused
+    pub fn order_failed(&mut self, intent: OrderIntent, reason: String, now: DateTime<Utc>) {
+        self.reject(intent, reason, now);
+    }
+
+    fn execute_paper(&mut self, order: SimulatedOrder, timestamp: DateTime<Utc>) -> Vec<RuntimeAction> {
+        match self.state.account.execute(order, timestamp) {
+            Some(fill) => self.record_fill(fill),
+            None => Vec::new(),
+        }
+    }
+
+    fn record_fill(&mut self, fill: StrategyFill) -> Vec<RuntimeAction> {
+        self.state.fills += 1;
+        self.state.fees_paid += fill.fee;
+        self.state.performance.record(&fill);
+        self.state.recent_fills.push_back(fill.clone());
+        if self.state.recent_fills.len() > RECENT_FILLS {
+            self.state.recent_fills.pop_front();
+        }
+
+        let mut actions = self.check_loss_limit(fill.timestamp);
+        // A paused strategy keeps track of its fills, but orders it asks for
+        // meanwhile are dropped by `route`
+        if self.state.status.is_active() {
+            let intents = self.strategy.on_fill(&self.context(fill.timestamp), &fill);
+            actions.extend(self.route(intents, fill.timestamp));
+        }
+        actions
+    }
+
+    /// Halt the strategy once it has lost more than the risk limits allow
+    fn check_loss_limit(&mut self, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        match self.risk.breach(self.state.config.initial_cash, self.state.account.equity()) {
+            Some(reason) => self.stop(StrategyStatus::Halted, Some(reason), now),
+            None => Vec::new(),
+        }
+    }
+
+    fn context(&self, timestamp: DateTime<Utc>) -> StrategyContext {
+        let mut context = self.state.account.context(timestamp);
+        if self.state.config.mode == StrategyMode::Live {
+            context.open_orders = self.state.working_orders.len();
+        }
+        context
+    }
+
+    /// Check the strategy's orders with the risk engine, then fill approved
+    /// ones on paper or hand them back to be sent
+    fn route(&mut self, intents: Vec<OrderIntent>, now: DateTime<Utc>) -> Vec<RuntimeAction> {
+        let mut actions = Vec::new();
+        for intent in intents {
+            if !self.is_running() {
+                break;
+            }
+            if let Err(reason) = self.risk.check(&intent, &self.context(now)) {
+                self.reject(intent, reason, now);
+                continue;
+            }
+
+            match self.state.config.mode {
+                StrategyMode::Paper => self.state.account.submit(intent.clone()),
+                StrategyMode::Live => match self.live_action(&intent) {
+                    Ok(action) => actions.push(action),
+                    Err(reason) => {
+                        self.reject(intent, reason, now);
+                        continue;
+                    }
+                },
+            }
+            if !matches!(intent, OrderIntent::CancelAll) {
+                self.state.orders_sent += 1;
+            }
+        }
+        actions
+    }
+
+    /// Exchange order for an approved intent. Like simulated fills, buys are
+    /// cut down to what the cash covers at the last price and sells to the
+    /// position held.
+    fn live_action(&self, intent: &OrderIntent) -> Result<RuntimeAction, String> {
+        let (side, quantity, order_type, price) = match intent {
+            OrderIntent::CancelAll => return Ok(RuntimeAction::CancelAll),
+            OrderIntent::Market { side, quantity } => (side, *quantity, "MARKET", None),
+            OrderIntent::Limit { side, quantity, price } => (side, *quantity, "LIMIT", Some(*price)),
+        };
+        let account = &self.state.account;
+        let available = match side {
+            OrderSide::Buy => price.or(account.last_price)
+                .map(|price| account.fill_model.affordable_quantity(account.cash, account.fill_model.market_price(side, price)))
+                .unwrap_or_default(),
+            OrderSide::Sell => account.position,
+        };
+        let quantity = quantity.min(available).round_dp_with_strategy(QUANTITY_DECIMALS, RoundingStrategy::ToZero);
+        if quantity <= Decimal::ZERO {
+            return Err(match side {
+                OrderSide::Buy => "Not enough cash for the order".to_string(),
+                OrderSide::Sell => "No position to sell".to_string(),
+            });
+        }
+
+        let config = &self.state.config;
+        Ok(RuntimeAction::Place { intent: intent.clone(), request: Box::new(PlaceOrderRequest {
+            exchange: config.instrument.exchange.clone(),
+            symbol: format!("{}{}", config.instrument.base, config.instrument.quote),
+            side: side.as_str().to_string(),
+            order_type: order_type.to_string(),
+            quantity,
+            price,
+            time_in_force: price.map(|_| "GTC".to_string()),
+            stop_price: None,
+            trigger_by: None,
+            // Makes a retried placement idempotent
+            client_order_id: Some(format!("{}:{}", config.strategy_id, self.state.orders_sent)),
+        }) })
+    }
+
+    fn reject(&mut self, intent: OrderIntent, reason: String, now: DateTime<Utc>) {
+        self.state.orders_rejected += 1;
+        self.state.recent_rejections.push_back(RejectedOrder { intent, reason, timestamp: now });
+        if self.state.recent_rejections.len() > RECENT_REJECTIONS {
+            self.state.recent_rejections.pop_front();
+        }
+    }
+}
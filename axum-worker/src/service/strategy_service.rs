@@ -0,0 +1,264 @@
+use worker::console_log;
+use chrono::Utc;
+use rust_decimal::Decimal;
+
+use crate::clients::trading::{Exchange, TradingClient};
+use crate::dto::market_data::InstrumentDto;
+use crate::dto::strategy::{
+    ListStrategiesResponse, RejectedOrderDto, RiskLimitsDto, StartStrategyRequest, StrategyAccountDto, StrategyFillDto,
+    StrategyInstanceDto, StrategyOrderDto, StrategyPerformanceDto, StrategyStatusResponse,
+};
+use crate::entity::market_data::{CandleInterval, Instrument, InstrumentKind};
+use crate::entity::strategy::{StrategyInstance, StrategyMode, StrategyStatus};
+use crate::repo::strategy::StrategyRepository;
+use crate::service::fill_model::FillModel;
+use crate::service::risk::{RiskEngine, RiskLimits};
+use crate::service::strategy::{build_strategy, OrderIntent};
+use crate::service::strategy_runtime::{RuntimeState, StrategyConfig};
+
+/// Candle interval used when a request doesn't give one
+const DEFAULT_INTERVAL: CandleInterval = CandleInterval::OneHour;
+
+/// Quote asset a paper strategy starts with when a request doesn't say
+const DEFAULT_PAPER_CASH: i64 = 10_000;
+
+/// Slippage applied to paper market orders when a request doesn't say
+const DEFAULT_SLIPPAGE_BPS: i64 = 5;
+
+/// Keeps the record of the strategies users run. The strategies themselves
+/// run in `StrategyRunner` Durable Objects, one per instance.
+#[derive(Clone)]
+pub struct StrategyService {
+    strategy_repository: StrategyRepository,
+}
+
+impl StrategyService {
+    pub fn new(strategy_repository: StrategyRepository) -> Self {
+        Self { strategy_repository }
+    }
+
+    /// Validate a start request and record the new instance, returning the
+    /// config its runner starts from
+    pub async fn create_instance(&self, user_id: &str, request: StartStrategyRequest) -> Result<(StrategyInstance, StrategyConfig), String> {
+        console_log!("STRATEGY SERVICE: Starting {} on {}/{} on {} for {}",
+            request.strategy, request.base, request.quote, request.exchange, user_id);
+
+        let exchange = Exchange::parse(&request.exchange)
+            .ok_or_else(|| format!("Unsupported exchange: {}", request.exchange))?;
+        let mode = match &request.mode {
+            Some(mode) => StrategyMode::parse(mode)
+                .ok_or_else(|| format!("Unsupported mode: {} (expected paper or live)", mode))?,
+            None => StrategyMode::Paper,
+        };
+        let interval = match &request.interval {
+            Some(interval) => CandleInterval::parse(interval)
+                .ok_or_else(|| format!("Unsupported interval: {} (expected 1m, 5m, 15m, 1h, 4h or 1d)", interval))?,
+            None => DEFAULT_INTERVAL,
+        };
+        // Fills in the parameter defaults, and rejects bad parameters before anything runs
+        let strategy = build_strategy(&request.strategy, request.parameters.as_ref().unwrap_or(&serde_json::Value::Null))?;
+
+        let initial_cash = match (request.initial_cash, mode) {
+            (Some(cash), _) => cash,
+            (None, StrategyMode::Paper) => Decimal::new(DEFAULT_PAPER_CASH, 0),
+            (None, StrategyMode::Live) => return Err("initial_cash is required in live mode".to_string()),
+        };
+        if initial_cash <= Decimal::ZERO {
+            return Err("initial_cash must be positive".to_string());
+        }
+        let fee_rate = request.fee_rate.unwrap_or_else(|| TradingClient::new(exchange, None, None).taker_fee_rate());
+        if fee_rate < Decimal::ZERO || fee_rate >= Decimal::ONE {
+            return Err("fee_rate must be at least 0 and below 1".to_string());
+        }
+        let slippage_bps = request.slippage_bps.unwrap_or(Decimal::new(DEFAULT_SLIPPAGE_BPS, 0));
+        if slippage_bps < Decimal::ZERO || slippage_bps >= Decimal::new(10_000, 0) {
+            return Err("slippage_bps must be at least 0 and below 10000".to_string());
+        }
+        let risk_limits = convert_limits_from_dto(request.risk_limits.unwrap_or_default());
+        RiskEngine::new(risk_limits.clone())?;
+
+        let now = Utc::now();
+        let instrument = Instrument::new(request.base, request.quote, request.exchange.to_lowercase(), InstrumentKind::Spot);
+        let instance = StrategyInstance {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            strategy: strategy.name().to_string(),
+            parameters: strategy.parameters(),
+            exchange: instrument.exchange.clone(),
+            base: instrument.base.clone(),
+            quote: instrument.quote.clone(),
+            mode,
+            status: StrategyStatus::Running,
+            status_reason: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.strategy_repository.save_instance(&instance).await?;
+
+        let config = StrategyConfig {
+            strategy_id: instance.id.clone(),
+            user_id: user_id.to_string(),
+            strategy: instance.strategy.clone(),
+            parameters: instance.parameters.clone(),
+            instrument,
+            mode,
+            interval,
+            initial_cash,
+            fill_model: FillModel::new(fee_rate, slippage_bps),
+            risk_limits,
+        };
+        Ok((instance, config))
+    }
+
+    /// One of the user's strategy instances, or None if they have no such instance
+    pub async fn find_instance(&self, user_id: &str, strategy_id: &str) -> Result<Option<StrategyInstance>, String> {
+        let instance = self.strategy_repository.find_instance(strategy_id).await?;
+        Ok(instance.filter(|instance| instance.user_id == user_id))
+    }
+
+    pub async fn list_instances(&self, user_id: &str) -> Result<ListStrategiesResponse, String> {
+        let instances = self.strategy_repository.find_user_instances(user_id).await?;
+        console_log!("STRATEGY SERVICE: Found {} strategies for {}", instances.len(), user_id);
+        Ok(ListStrategiesResponse {
+            strategies: instances.iter().map(convert_instance_to_dto).collect(),
+        })
+    }
+
+    /// Mark an instance as no longer running, e.g. when its runner failed to start
+    pub async fn update_status(&self, strategy_id: &str, status: StrategyStatus, reason: Option<&str>) -> Result<(), String> {
+        self.strategy_repository.update_status(strategy_id, status, reason).await
+    }
+}
+
+fn convert_limits_from_dto(limits: RiskLimitsDto) -> RiskLimits {
+    RiskLimits {
+        max_order_notional: limits.max_order_notional,
+        max_position: limits.max_position,
+        max_open_orders: limits.max_open_orders,
+        max_drawdown: limits.max_drawdown_percentage.map(|percentage| percentage / Decimal::ONE_HUNDRED),
+    }
+}
+
+fn convert_limits_to_dto(limits: &RiskLimits) -> RiskLimitsDto {
+    RiskLimitsDto {
+        max_order_notional: limits.max_order_notional,
+        max_position: limits.max_position,
+        max_open_orders: limits.max_open_orders,
+        max_drawdown_percentage: limits.max_drawdown.map(|fraction| (fraction * Decimal::ONE_HUNDRED).round_dp(2)),
+    }
+}
+
+pub fn convert_instance_to_dto(instance: &StrategyInstance) -> StrategyInstanceDto {
+    let instrument = Instrument::new(instance.base.clone(), instance.quote.clone(), instance.exchange.clone(), InstrumentKind::Spot);
+    StrategyInstanceDto {
+        strategy_id: instance.id.clone(),
+        strategy: instance.strategy.clone(),
+        parameters: instance.parameters.clone(),
+        instrument: InstrumentDto::from(&instrument),
+        mode: instance.mode.as_str().to_string(),
+        status: instance.status.as_str().to_string(),
+        status_reason: instance.status_reason.clone(),
+        created_at: instance.created_at,
+        updated_at: instance.updated_at,
+    }
+}
+
+/// Report a runner's state
+pub fn convert_state_to_status(state: &RuntimeState) -> StrategyStatusResponse {
+    let config = &state.config;
+    let account = &state.account;
+    let equity = account.equity();
+    let pnl = equity - config.initial_cash;
+    let performance = &state.performance;
+    let average = |notional: Decimal, quantity: Decimal| (quantity > Decimal::ZERO).then(|| (notional / quantity).round_dp(8));
+
+    let open_orders = match config.mode {
+        StrategyMode::Paper => account.resting_orders().iter()
+            .map(|order| StrategyOrderDto {
+                order_id: None,
+                side: order.side.as_str().to_string(),
+                quantity: order.quantity,
+                filled_quantity: Decimal::ZERO,
+                price: Some(order.price),
+            })
+            .collect(),
+        StrategyMode::Live => state.working_orders.iter()
+            .map(|order| StrategyOrderDto {
+                order_id: Some(order.order_id.clone()),
+                side: order.side.as_str().to_string(),
+                quantity: order.quantity,
+                filled_quantity: order.filled_quantity,
+                price: order.price,
+            })
+            .collect(),
+    };
+
+    StrategyStatusResponse {
+        strategy_id: config.strategy_id.clone(),
+        strategy: config.strategy.clone(),
+        parameters: config.parameters.clone(),
+        instrument: InstrumentDto::from(&config.instrument),
+        mode: config.mode.as_str().to_string(),
+        status: state.status.as_str().to_string(),
+        status_reason: state.status_reason.clone(),
+        interval: config.interval.as_str().to_string(),
+        fee_rate: config.fill_model.fee_rate,
+        slippage_bps: config.fill_model.slippage_bps,
+        risk_limits: convert_limits_to_dto(&config.risk_limits),
+        account: StrategyAccountDto {
+            initial_cash: config.initial_cash,
+            cash: account.cash.round_dp(8),
+            position: account.position,
+            last_price: account.last_price,
+            equity: equity.round_dp(8),
+            pnl: pnl.round_dp(8),
+            return_percentage: (pnl / config.initial_cash * Decimal::ONE_HUNDRED).round_dp(4),
+        },
+        performance: StrategyPerformanceDto {
+            buys: performance.buys,
+            sells: performance.sells,
+            bought_quantity: performance.bought_quantity,
+            average_buy_price: average(performance.bought_notional, performance.bought_quantity),
+            sold_quantity: performance.sold_quantity,
+            average_sell_price: average(performance.sold_notional, performance.sold_quantity),
+            average_entry_price: performance.average_cost().map(|price| price.round_dp(8)),
+            realized_pnl: performance.realized_pnl.round_dp(8),
+            unrealized_pnl: account.last_price.map(|price| performance.unrealized_pnl(price).round_dp(8)),
+        },
+        strategy_state: state.strategy_state.clone(),
+        open_orders,
+        events: state.events,
+        orders_sent: state.orders_sent,
+        orders_rejected: state.orders_rejected,
+        fills: state.fills,
+        fees_paid: state.fees_paid.round_dp(8),
+        recent_fills: state.recent_fills.iter()
+            .map(|fill| StrategyFillDto {
+                timestamp: fill.timestamp,
+                side: fill.side.as_str().to_string(),
+                quantity: fill.quantity,
+                price: fill.price.round_dp(8),
+                fee: fill.fee.round_dp(8),
+            })
+            .collect(),
+        recent_rejections: state.recent_rejections.iter()
+            .map(|rejected| {
+                let (order_type, side, quantity, price) = match &rejected.intent {
+                    OrderIntent::Market { side, quantity } => ("MARKET", Some(side), Some(*quantity), None),
+                    OrderIntent::Limit { side, quantity, price } => ("LIMIT", Some(side), Some(*quantity), Some(*price)),
+                    OrderIntent::CancelAll => ("CANCEL_ALL", None, None, None),
+                };
+                RejectedOrderDto {
+                    timestamp: rejected.timestamp,
+                    order_type: order_type.to_string(),
+                    side: side.map(|side| side.as_str().to_string()),
+                    quantity,
+                    price,
+                    reason: rejected.reason.clone(),
+                }
+            })
+            .collect(),
+        started_at: state.started_at,
+        updated_at: state.updated_at,
+    }
+}
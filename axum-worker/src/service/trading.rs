@@ -1,32 +1,96 @@
-use worker::console_log;
-use std::collections::HashMap;
+use worker::{console_log, ObjectNamespace};
+use std::collections::{BTreeMap, HashMap};
 use rust_decimal::Decimal;
-use chrono::Utc;
+use chrono::{DateTime, NaiveTime, Utc};
+use futures::future::join_all;
+use serde_json::json;
 
 
 
-use crate::clients::trading::{TradingClient, OrderRequest, OrderType, Quote, OrderBook, Balance, SimpleInstrument, Exchange, Side};
+use crate::clients::exchange_governor::{GovernorClient, LimitUsage};
+use crate::clients::exchange_policy::{CircuitState, CircuitStatus, ExchangePolicy};
+use crate::clients::trading::{TradingClient, OrderRequest, OrderStatusReport, OrderType, TriggerBy, Quote, OrderBook, Balance, SimpleInstrument, Exchange, Side};
 use crate::dto::trading::{
     GetQuoteRequest, GetQuoteResponse, GetOrderBookRequest, GetOrderBookResponse,
-    PlaceOrderRequest, PlaceOrderResponse, GetBalancesRequest, GetBalancesResponse,
+    PlaceOrderRequest, PlaceOrderResponse, CancelOrderRequest, CancelOrderResponse, GetBalancesRequest, GetBalancesResponse,
     GetInstrumentsRequest, GetInstrumentsResponse, GetTradingStatusRequest, GetTradingStatusResponse,
-    OrderBookLevelDto, BalanceDto, InstrumentDto, TradingErrorResponse
+    PlaceOcoOrderRequest, PlaceBracketOrderRequest,
+    OrderGroupRequest, OrderGroupResponse, OrderDto, GetPortfolioRequest, GetPortfolioResponse, HoldingDto,
+    AssetPnlDto, SessionPnlDto, GetAggregatePortfolioRequest, GetAggregatePortfolioResponse, AggregateBalanceDto,
+    ExchangeBalanceDto, ExchangeErrorDto, GetConsolidatedBookRequest, GetConsolidatedBookResponse, ConsolidatedLevelDto,
+    GetBestBidOfferRequest, GetBestBidOfferResponse, VenueBidOfferDto, GetFillQuoteRequest, GetFillQuoteResponse,
+    VenueFillDto, FillEstimateDto, FillAllocationDto, RecordTransferRequest, GetTransfersRequest, GetTransfersResponse, TransferDto,
+    OrderBookLevelDto, BalanceDto, InstrumentDto, RateLimitDto, ExchangeHealthDto, TradingHealthResponse, TradingErrorResponse
 };
+use crate::entity::trading::{
+    AccountTransfer, TransferKind, CostBasisMethod, InstrumentType, MarketQuote, OrderBookLevel as EntityOrderBookLevel, OrderGroup, Portfolio, PortfolioSnapshot, TradeExecution, TradingSession, OrderGroupStatus, OrderGroupType, OrderSide, OrderStatus, OrderType as EntityOrderType,
+    TradingInstrument, TradingOrder, TradingOrderBook, TriggerPriceType,
+};
+use crate::entity::webhook::WebhookEvent;
+use crate::repo::order::OrderRepository;
+use crate::repo::trade::TradeRepository;
+use crate::repo::transfer::TransferRepository;
+use crate::service::account_ledger::{ledger_balances, ledger_drift, net_flows, LedgerDrift};
+use crate::service::consolidated_book::{ConsolidatedBook, ConsolidatedLevel, FillEstimate, VenueBook};
+use crate::service::lot_accounting::{ExecutionValuation, LotLedger};
+use crate::service::order_group::{plan_leg_actions, resolve_group_status, LegAction};
+use crate::service::pricing::{AssetPrice, PricingService};
+use crate::service::trigger_monitor::{needs_mark_price, PriceUpdate, TriggerMonitor};
+use crate::service::webhook::WebhookService;
+
+
+/// Number of holdings listed in a portfolio summary
+const TOP_HOLDINGS_LIMIT: usize = 10;
+
+/// Levels fetched from each exchange for a consolidated book
+const DEFAULT_CONSOLIDATED_DEPTH: u32 = 20;
 
+/// Levels fetched from each exchange when estimating a fill, enough for
+/// orders well beyond the top of the book
+const DEFAULT_FILL_QUOTE_DEPTH: u32 = 100;
 
 /// Trading service that orchestrates trading operations using barter-rs
 #[derive(Clone)]
 pub struct TradingService {
     clients: HashMap<String, TradingClient>,
     supported_exchanges: Vec<Exchange>,
+    order_repository: OrderRepository,
+    trade_repository: TradeRepository,
+    transfer_repository: TransferRepository,
+    trigger_monitor: TriggerMonitor,
+    pricing_service: PricingService,
+    webhook_service: WebhookService,
 }
 
+/// Lot-accounting results for a portfolio summary
+struct PnlSummary {
+    method: CostBasisMethod,
+    assets: Vec<AssetPnlDto>,
+    session: SessionPnlDto,
+}
+
+/// A user's balances on one exchange and the USD prices to value them at
+type ValuedBalances = (Vec<Balance>, HashMap<String, AssetPrice>);
+
 impl TradingService {
     /// Create a new trading service instance
-    pub fn new() -> Self {
+    pub fn new(
+        order_repository: OrderRepository,
+        trade_repository: TradeRepository,
+        transfer_repository: TransferRepository,
+        webhook_service: WebhookService,
+        governor: GovernorClient,
+        policy: ExchangePolicy,
+    ) -> Self {
         console_log!("TRADING SERVICE: Initializing trading service with barter-rs integration");
         
         let mut service = Self {
+            trigger_monitor: TriggerMonitor::new(order_repository.clone()),
+            order_repository,
+            trade_repository,
+            transfer_repository,
+            pricing_service: PricingService::new(),
+            webhook_service,
             clients: HashMap::new(),
             supported_exchanges: vec![
                 Exchange::Binance,
@@ -36,118 +100,1958 @@ impl TradingService {
                 Exchange::Bybit,
             ],
         };
-        
-        // Initialize clients for supported exchanges
-        service.initialize_clients();
-        
-        service
+        
+        // Initialize clients for supported exchanges
+        service.initialize_clients(governor, policy);
+        
+        service
+    }
+
+    /// Have `MarketStream` objects fire the trigger monitor's orders as
+    /// trades arrive, instead of only the scheduled evaluation
+    pub fn with_market_streams(mut self, namespace: Option<ObjectNamespace>) -> Self {
+        self.trigger_monitor = self.trigger_monitor.with_market_streams(namespace);
+        self
+    }
+
+    /// Initialize trading clients for supported exchanges
+    fn initialize_clients(&mut self, governor: GovernorClient, policy: ExchangePolicy) {
+        console_log!("TRADING SERVICE: Initializing trading clients for supported exchanges");
+        
+        for exchange in &self.supported_exchanges {
+            match TradingClient::from_env(*exchange) {
+                Ok(client) => {
+                    let exchange_name = format!("{:?}", exchange).to_lowercase();
+                    self.clients.insert(exchange_name, client.with_governor(governor.clone()).with_policy(policy.clone()));
+                    console_log!("TRADING SERVICE: Successfully initialized client for {:?}", exchange);
+                }
+                Err(e) => {
+                    console_log!("TRADING SERVICE: Failed to initialize client for {:?}: {}", exchange, e);
+                }
+            }
+        }
+        
+        console_log!("TRADING SERVICE: Initialized {} trading clients", self.clients.len());
+    }
+
+    /// Names of the exchanges with an initialized client
+    pub fn exchange_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.clients.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Get market quote for an instrument
+    pub async fn get_quote(&self, request: GetQuoteRequest) -> Result<GetQuoteResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting quote for {} on {}", request.symbol, request.exchange);
+        
+        let client = self.get_client(&request.exchange)?;
+        let instrument = self.parse_instrument(&request.symbol, &request.exchange)?;
+        
+        match client.get_quote(&instrument).await {
+            Ok(quote) => {
+                console_log!("TRADING SERVICE: Successfully retrieved quote for {}", request.symbol);
+                Ok(self.convert_quote_to_response(quote, &request.exchange))
+            }
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to get quote: {}", e);
+                Err(TradingErrorResponse::new(format!("Failed to get quote: {}", e)))
+            }
+        }
+    }
+
+    /// Get the current quote of an instrument as a market quote entity
+    pub async fn get_market_quote(&self, exchange: &str, base: &str, quote: &str) -> Result<MarketQuote, TradingErrorResponse> {
+        let client = self.get_client(exchange)?;
+        let instrument = SimpleInstrument {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        };
+
+        let quote = client.get_quote(&instrument).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to get quote: {}", e)))?;
+        Ok(self.convert_quote_to_entity(quote, exchange))
+    }
+
+    /// Get order book data for an instrument
+    pub async fn get_order_book(&self, request: GetOrderBookRequest) -> Result<GetOrderBookResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting order book for {} on {}", request.symbol, request.exchange);
+        
+        let client = self.get_client(&request.exchange)?;
+        let instrument = self.parse_instrument(&request.symbol, &request.exchange)?;
+        let depth = request.depth.unwrap_or(20);
+        
+        match client.get_order_book(&instrument, depth).await {
+            Ok(order_book) => {
+                console_log!("TRADING SERVICE: Successfully retrieved order book for {}", request.symbol);
+                Ok(self.convert_order_book_to_response(order_book, &request.exchange))
+            }
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to get order book: {}", e);
+                Err(TradingErrorResponse::new(format!("Failed to get order book: {}", e)))
+            }
+        }
+    }
+
+    /// Merge an instrument's order books from several exchanges, ranked by
+    /// fee-adjusted price
+    pub async fn get_consolidated_book(&self, request: GetConsolidatedBookRequest) -> Result<GetConsolidatedBookResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting consolidated order book for {}", request.symbol);
+
+        let depth = request.depth.unwrap_or(DEFAULT_CONSOLIDATED_DEPTH);
+        let (venues, exchange_errors) = self.fetch_venue_books(&request.symbol, request.exchanges, depth, request.fee_rates).await?;
+        let book = ConsolidatedBook::merge(&venues);
+
+        Ok(GetConsolidatedBookResponse {
+            symbol: request.symbol,
+            exchanges: venues.iter().map(|venue| venue.book.instrument.exchange.clone()).collect(),
+            bids: book.bids.iter().map(|level| self.convert_consolidated_level_to_dto(level)).collect(),
+            asks: book.asks.iter().map(|level| self.convert_consolidated_level_to_dto(level)).collect(),
+            exchange_errors,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Get the best fee-adjusted bid and offer across exchanges, along with
+    /// each exchange's top of book
+    pub async fn get_best_bid_offer(&self, request: GetBestBidOfferRequest) -> Result<GetBestBidOfferResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting best bid and offer for {}", request.symbol);
+
+        // Fee-adjusted ranking can't change the order within one exchange, so its top level is enough
+        let (venues, exchange_errors) = self.fetch_venue_books(&request.symbol, request.exchanges, 1, request.fee_rates).await?;
+
+        let venue_quotes = venues.iter()
+            .map(|venue| {
+                let book = ConsolidatedBook::merge(std::slice::from_ref(venue));
+                VenueBidOfferDto {
+                    exchange: venue.book.instrument.exchange.clone(),
+                    taker_fee_rate: venue.taker_fee_rate,
+                    bid: book.best_bid().map(|level| self.convert_consolidated_level_to_dto(level)),
+                    ask: book.best_ask().map(|level| self.convert_consolidated_level_to_dto(level)),
+                }
+            })
+            .collect();
+
+        let book = ConsolidatedBook::merge(&venues);
+        let spread = book.best_ask().zip(book.best_bid())
+            .map(|(ask, bid)| ask.effective_price - bid.effective_price);
+
+        Ok(GetBestBidOfferResponse {
+            symbol: request.symbol,
+            best_bid: book.best_bid().map(|level| self.convert_consolidated_level_to_dto(level)),
+            best_ask: book.best_ask().map(|level| self.convert_consolidated_level_to_dto(level)),
+            spread,
+            venues: venue_quotes,
+            exchange_errors,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Estimate filling a market order of `quantity` on each exchange, and
+    /// split across all of them through the consolidated book
+    pub async fn get_fill_quote(&self, request: GetFillQuoteRequest) -> Result<GetFillQuoteResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Quoting a {} {} fill for {}", request.side, request.quantity, request.symbol);
+
+        let side = OrderSide::parse(&request.side).ok_or_else(|| TradingErrorResponse::with_code(
+            format!("Invalid side: {} (expected BUY or SELL)", request.side),
+            "INVALID_SIDE".to_string(),
+        ))?;
+        if request.quantity <= Decimal::ZERO {
+            return Err(TradingErrorResponse::with_code(
+                "quantity must be positive".to_string(),
+                "INVALID_QUANTITY".to_string(),
+            ));
+        }
+
+        let depth = request.depth.unwrap_or(DEFAULT_FILL_QUOTE_DEPTH);
+        let (venues, mut exchange_errors) = self.fetch_venue_books(&request.symbol, request.exchanges, depth, request.fee_rates).await?;
+
+        let mut venue_fills = Vec::new();
+        for venue in &venues {
+            let exchange = venue.book.instrument.exchange.clone();
+            match ConsolidatedBook::merge(std::slice::from_ref(venue)).estimate_fill(&side, request.quantity) {
+                Some(estimate) => venue_fills.push(VenueFillDto {
+                    exchange,
+                    taker_fee_rate: venue.taker_fee_rate,
+                    estimate: self.convert_fill_estimate_to_dto(estimate),
+                }),
+                None => exchange_errors.push(ExchangeErrorDto {
+                    exchange,
+                    error: format!("Order book has no {}", if matches!(side, OrderSide::Buy) { "asks" } else { "bids" }),
+                }),
+            }
+        }
+
+        // Full fills first, then the best price for the side
+        venue_fills.sort_by(|a, b| {
+            let by_price = a.estimate.effective_average_price.cmp(&b.estimate.effective_average_price);
+            b.estimate.fully_filled.cmp(&a.estimate.fully_filled).then(match side {
+                OrderSide::Buy => by_price,
+                OrderSide::Sell => by_price.reverse(),
+            })
+        });
+        let best_exchange = venue_fills.first()
+            .filter(|fill| fill.estimate.fully_filled)
+            .map(|fill| fill.exchange.clone());
+
+        let routed = ConsolidatedBook::merge(&venues).estimate_fill(&side, request.quantity)
+            .map(|estimate| self.convert_fill_estimate_to_dto(estimate));
+
+        Ok(GetFillQuoteResponse {
+            symbol: request.symbol,
+            side: side.as_str().to_string(),
+            quantity: request.quantity,
+            best_exchange,
+            venues: venue_fills,
+            routed,
+            exchange_errors,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Fetch an instrument's order book from each requested exchange along
+    /// with its taker fee. Exchanges that fail are reported rather than
+    /// failing the request, unless all of them do.
+    async fn fetch_venue_books(
+        &self,
+        symbol: &str,
+        exchanges: Option<Vec<String>>,
+        depth: u32,
+        fee_rates: Option<HashMap<String, Decimal>>,
+    ) -> Result<(Vec<VenueBook>, Vec<ExchangeErrorDto>), TradingErrorResponse> {
+        let (base, quote) = self.parse_symbol(symbol)?;
+        let instrument = SimpleInstrument { base, quote };
+
+        let fee_rates: HashMap<String, Decimal> = fee_rates.unwrap_or_default().into_iter()
+            .map(|(exchange, rate)| (exchange.to_lowercase(), rate))
+            .collect();
+        if let Some((exchange, rate)) = fee_rates.iter().find(|(_, rate)| **rate < Decimal::ZERO || **rate >= Decimal::ONE) {
+            return Err(TradingErrorResponse::with_code(
+                format!("Fee rate for {} must be at least 0 and below 1, got {}", exchange, rate),
+                "INVALID_FEE_RATE".to_string(),
+            ));
+        }
+
+        let exchanges = self.requested_exchanges(exchanges);
+        let results = join_all(exchanges.iter().map(|exchange| async {
+            let client = self.get_client(exchange)?;
+            let order_book = client.get_order_book(&instrument, depth).await
+                .map_err(|e| TradingErrorResponse::new(format!("Failed to get order book: {}", e)))?;
+            Ok::<_, TradingErrorResponse>((client.taker_fee_rate(), order_book))
+        })).await;
+
+        let mut venues = Vec::new();
+        let mut exchange_errors = Vec::new();
+        for (exchange, result) in exchanges.into_iter().zip(results) {
+            match result {
+                Ok((taker_fee_rate, order_book)) => venues.push(VenueBook {
+                    taker_fee_rate: fee_rates.get(&exchange).copied().unwrap_or(taker_fee_rate),
+                    book: self.convert_order_book_to_entity(order_book, symbol, &exchange),
+                }),
+                Err(e) => {
+                    console_log!("TRADING SERVICE: Leaving {} out of consolidated book: {}", exchange, e.error);
+                    exchange_errors.push(ExchangeErrorDto { exchange, error: e.error });
+                }
+            }
+        }
+
+        if venues.is_empty() {
+            return Err(Self::all_exchanges_failed("Failed to get order books from every exchange", &exchange_errors));
+        }
+        Ok((venues, exchange_errors))
+    }
+
+    /// Place a trading order.
+    ///
+    /// Stop-loss and take-profit orders go to the exchange as native trigger orders
+    /// when it supports the requested trigger; otherwise they are held by the
+    /// server-side trigger monitor until market data crosses the stop price.
+    ///
+    /// Orders carrying a client order id are idempotent: a retry with the same id
+    /// returns the original order instead of placing a second one. Every order is
+    /// stored before it is sent and submitted under its own id as the exchange
+    /// client order id, so an order whose placement failed without a definite
+    /// answer can be looked up on the exchange afterwards.
+    pub async fn place_order(&self, user_id: &str, request: PlaceOrderRequest) -> Result<PlaceOrderResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Placing {} order for {} {} on {}", 
+            request.side, request.quantity, request.symbol, request.exchange);
+        
+        let mut order = self.build_trading_order(user_id, &request)?;
+
+        if let Some(client_order_id) = order.client_order_id.clone() {
+            if let Some(existing) = self.find_client_order(user_id, &client_order_id).await? {
+                return self.replay_order(existing, &order).await;
+            }
+        }
+
+        if self.needs_server_trigger(&order)? {
+            let (order, trigger_mode) = self.submit_order(order).await?;
+            self.record_order(&order, trigger_mode, false).await?;
+            return Ok(self.convert_order_to_response(&order, trigger_mode));
+        }
+
+        // Record the attempt first so a lost response can be reconciled later
+        order.status = OrderStatus::PendingSubmit;
+        if let Err(e) = self.order_repository.save_order(&order).await {
+            console_log!("TRADING SERVICE: Failed to record order {} before submission: {}", order.id, e);
+
+            // A concurrent retry with the same client order id got there first
+            if let Some(client_order_id) = order.client_order_id.clone() {
+                if let Some(existing) = self.find_client_order(user_id, &client_order_id).await? {
+                    return self.replay_order(existing, &order).await;
+                }
+            }
+            return Err(TradingErrorResponse::new(format!("Failed to record order: {}", e)));
+        }
+
+        self.submit_recorded_order(order).await
+    }
+
+    /// Place an OCO pair: a take-profit limit and a stop-loss on the same side.
+    ///
+    /// Both legs work at once; the group keeps their combined size within the
+    /// group quantity, so a fill on one leg shrinks or cancels the other. See
+    /// `open_exit_legs` for how the pair is held without locking the size twice.
+    pub async fn place_oco_order(&self, user_id: &str, request: PlaceOcoOrderRequest) -> Result<OrderGroupResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Placing OCO {} {} {} on {} (TP: {}, SL: {})",
+            request.side, request.quantity, request.symbol, request.exchange,
+            request.take_profit_price, request.stop_loss_price);
+
+        self.get_client(&request.exchange)?;
+        let side = OrderSide::parse(&request.side)
+            .ok_or_else(|| TradingErrorResponse::with_code(format!("Invalid order side: {}", request.side), "INVALID_SIDE".to_string()))?;
+        let trigger_by = self.parse_trigger_by(request.trigger_by.as_deref())?;
+        self.validate_group_quantity(request.quantity)?;
+        self.validate_exit_prices(&side, request.take_profit_price, request.stop_loss_price, None)?;
+        self.parse_symbol(&request.symbol)?;
+
+        let group = OrderGroup::new(
+            user_id.to_string(),
+            request.exchange.clone(),
+            request.symbol.clone(),
+            OrderGroupType::Oco,
+            request.quantity,
+        );
+        self.order_repository.save_group(&group).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to create order group: {}", e)))?;
+
+        let exit_legs = self.build_exit_legs(
+            &group,
+            side,
+            request.take_profit_price,
+            request.stop_loss_price,
+            request.stop_limit_price,
+            trigger_by,
+        )?;
+
+        let (legs, mut failed) = self.open_exit_legs(exit_legs, false).await;
+        if let Some((_, e)) = failed.pop() {
+            console_log!("TRADING SERVICE: OCO leg rejected, unwinding group {}: {}", group.id, e.error);
+            self.abandon_group(group, legs).await;
+            return Err(e);
+        }
+
+        Ok(self.convert_group_to_response(&group, None, &legs))
+    }
+
+    /// Place an entry order with an attached take-profit and stop-loss.
+    ///
+    /// The exit legs are held server-side until the entry fills and are then
+    /// submitted as an OCO pair sized to the filled quantity.
+    pub async fn place_bracket_order(&self, user_id: &str, request: PlaceBracketOrderRequest) -> Result<OrderGroupResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Placing bracket {} {} {} on {} (TP: {}, SL: {})",
+            request.side, request.quantity, request.symbol, request.exchange,
+            request.take_profit_price, request.stop_loss_price);
+
+        self.get_client(&request.exchange)?;
+        let entry_side = OrderSide::parse(&request.side)
+            .ok_or_else(|| TradingErrorResponse::with_code(format!("Invalid order side: {}", request.side), "INVALID_SIDE".to_string()))?;
+        let entry_type = match EntityOrderType::parse(&request.order_type) {
+            Some(order_type) if !order_type.is_trigger_order() => order_type,
+            _ => return Err(TradingErrorResponse::with_code(
+                format!("Invalid bracket entry order type: {}", request.order_type),
+                "INVALID_ORDER_TYPE".to_string(),
+            )),
+        };
+        if entry_type.requires_limit_price() && request.price.is_none() {
+            return Err(TradingErrorResponse::with_code(
+                format!("{} orders require a price", entry_type.as_str()),
+                "MISSING_PRICE".to_string(),
+            ));
+        }
+        let trigger_by = self.parse_trigger_by(request.trigger_by.as_deref())?;
+        self.validate_group_quantity(request.quantity)?;
+
+        let exit_side = entry_side.opposite();
+        self.validate_exit_prices(&exit_side, request.take_profit_price, request.stop_loss_price, request.price)?;
+
+        let instrument = self.build_instrument(&request.exchange, &request.symbol)?;
+        let mut group = OrderGroup::new(
+            user_id.to_string(),
+            request.exchange.clone(),
+            request.symbol.clone(),
+            OrderGroupType::Bracket,
+            request.quantity,
+        );
+
+        let mut entry = TradingOrder::new(
+            user_id.to_string(),
+            instrument,
+            entry_side,
+            entry_type,
+            request.quantity,
+            request.price,
+        );
+        entry.group_id = Some(group.id.clone());
+        group.parent_order_id = Some(entry.id.clone());
+
+        self.order_repository.save_group(&group).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to create order group: {}", e)))?;
+
+        let mut legs = Vec::new();
+        let exit_legs = self.build_exit_legs(
+            &group,
+            exit_side,
+            request.take_profit_price,
+            request.stop_loss_price,
+            request.stop_limit_price,
+            trigger_by,
+        )?;
+        for mut leg in exit_legs {
+            leg.parent_order_id = Some(entry.id.clone());
+            leg.status = OrderStatus::AwaitingParent;
+
+            if let Err(e) = self.order_repository.save_order(&leg).await {
+                console_log!("TRADING SERVICE: Failed to persist bracket leg {}: {}", leg.id, e);
+                self.abandon_group(group, legs).await;
+                return Err(TradingErrorResponse::new(format!("Failed to persist bracket leg: {}", e)));
+            }
+            legs.push(leg);
+        }
+
+        match self.submit_order(entry).await {
+            Ok((entry, trigger_mode)) => self.record_order(&entry, trigger_mode, false).await?,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Bracket entry rejected, unwinding group {}: {}", group.id, e.error);
+                self.abandon_group(group, legs).await;
+                return Err(e);
+            }
+        }
+
+        // A market entry may already have filled, in which case the exits go live now
+        self.sync_group(group).await
+    }
+
+    /// Get an order group, bringing its legs in line with the latest fills
+    pub async fn get_order_group(&self, user_id: &str, request: OrderGroupRequest) -> Result<OrderGroupResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting order group {}", request.group_id);
+
+        let group = self.find_user_group(user_id, &request.group_id).await?;
+        self.sync_group(group).await
+    }
+
+    /// Cancel every open order in a group
+    pub async fn cancel_order_group(&self, user_id: &str, request: OrderGroupRequest) -> Result<OrderGroupResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Cancelling order group {}", request.group_id);
+
+        let mut group = self.find_user_group(user_id, &request.group_id).await?;
+        let (mut parent, mut legs) = self.load_group_orders(&group).await?;
+
+        if let Some(parent) = parent.as_mut().filter(|parent| !parent.is_terminal()) {
+            self.cancel_group_order(parent).await;
+        }
+        for leg in legs.iter_mut().filter(|leg| !leg.is_terminal()) {
+            self.cancel_group_order(leg).await;
+        }
+
+        let still_open = parent.iter().chain(legs.iter()).any(|order| !order.is_terminal());
+        if group.is_active() && !still_open {
+            group.status = OrderGroupStatus::Cancelled;
+            group.updated_at = Utc::now();
+            if let Err(e) = self.order_repository.update_group(&group).await {
+                console_log!("TRADING SERVICE: Failed to persist group {}: {}", group.id, e);
+            }
+        }
+
+        Ok(self.convert_group_to_response(&group, parent.as_ref(), &legs))
+    }
+
+    /// Get one of a user's orders, refreshed from the exchange while it is working
+    pub async fn get_order(&self, user_id: &str, order_id: &str) -> Result<OrderDto, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting order {}", order_id);
+
+        let mut order = self.find_user_order(user_id, order_id).await?;
+        if self.refresh_order(&mut order).await {
+            if let Some(group_id) = &order.group_id {
+                self.sync_group_by_id(group_id).await;
+            }
+        }
+        Ok(self.convert_order_to_dto(&order))
+    }
+
+    /// Cancel one of a user's open orders, on the exchange or in the trigger monitor
+    pub async fn cancel_order(&self, user_id: &str, request: CancelOrderRequest) -> Result<CancelOrderResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Cancelling order {}", request.order_id);
+
+        let mut order = self.find_user_order(user_id, &request.order_id).await?;
+        if order.is_terminal() {
+            return Err(TradingErrorResponse::with_code(
+                format!("Order {} is already {}", order.id, order.status.as_str()),
+                "ORDER_NOT_OPEN".to_string(),
+            ));
+        }
+        if !self.cancel_group_order(&mut order).await {
+            return Err(TradingErrorResponse::new(format!("Exchange refused to cancel order {}", order.id)));
+        }
+
+        Ok(CancelOrderResponse {
+            order_id: order.id,
+            status: order.status.as_str().to_string(),
+            cancelled_at: order.updated_at,
+        })
+    }
+
+    /// Check every server-side trigger order against its instrument's latest
+    /// trade price (and mark price, where an order triggers on it) and submit
+    /// those whose trigger fired. Returns the orders checked and fired.
+    ///
+    /// Market streams fire orders as trades arrive; this is the backstop for
+    /// the streams that aren't running and for mark-price orders.
+    pub async fn evaluate_triggers(&self) -> Result<(usize, usize), String> {
+        let pending = self.trigger_monitor.pending_by_instrument().await?;
+
+        let (mut checked, mut fired) = (0, 0);
+        for orders in pending.into_values() {
+            let Some(first) = orders.first() else { continue };
+            let exchange = first.instrument.exchange.clone();
+            let symbol = first.instrument.symbol.clone();
+            let instrument = SimpleInstrument {
+                base: first.instrument.base_asset.clone(),
+                quote: first.instrument.quote_asset.clone(),
+            };
+            let client = match self.get_client(&exchange) {
+                Ok(client) => client,
+                Err(e) => {
+                    console_log!("TRADING SERVICE: Skipping trigger orders on {}: {}", exchange, e.error);
+                    continue;
+                }
+            };
+
+            let last_price = match client.get_recent_trades(&instrument, 1).await {
+                Ok(trades) => match trades.last() {
+                    Some(trade) => trade.price,
+                    None => continue,
+                },
+                Err(e) => {
+                    console_log!("TRADING SERVICE: No last price for {} on {}, skipping its triggers: {}", symbol, exchange, e);
+                    continue;
+                }
+            };
+            let mark_price = if needs_mark_price(&orders) {
+                match client.get_mark_price(&instrument).await {
+                    Ok(price) => Some(price),
+                    Err(e) => {
+                        console_log!("TRADING SERVICE: Mark price unavailable, skipping mark-price triggers: {}", e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            // Restarts the market stream's watch if its object was evicted
+            self.trigger_monitor.watch(&first.instrument).await;
+
+            checked += orders.len();
+            let update = PriceUpdate { exchange, symbol, last_price, mark_price };
+            fired += self.process_price_update(update, orders).await?.len();
+        }
+        Ok((checked, fired))
+    }
+
+    /// Pending trigger orders of one instrument
+    pub async fn pending_triggers_for(&self, exchange: &str, base_asset: &str, quote_asset: &str) -> Result<Vec<TradingOrder>, String> {
+        self.trigger_monitor.pending_for(exchange, base_asset, quote_asset).await
+    }
+
+    /// Check a price update against an instrument's pending trigger orders and
+    /// submit any whose trigger fired
+    pub async fn process_price_update(&self, update: PriceUpdate, pending: Vec<TradingOrder>) -> Result<Vec<PlaceOrderResponse>, String> {
+        let triggered = self.trigger_monitor.evaluate(&update, pending).await
+            .map_err(|e| format!("Failed to evaluate trigger orders: {}", e))?;
+
+        let mut responses = Vec::new();
+        let mut group_ids: Vec<String> = Vec::new();
+        for mut order in triggered {
+            if let Some(group_id) = order.group_id.clone().filter(|id| !group_ids.contains(id)) {
+                group_ids.push(group_id);
+            }
+            if self.prepare_group_exit(&mut order).await {
+                responses.push(self.submit_triggered_order(order).await);
+            }
+        }
+
+        // A fired leg changes what its siblings may still close
+        for group_id in group_ids {
+            self.sync_group_by_id(&group_id).await;
+        }
+
+        Ok(responses)
+    }
+
+    /// Make room on the exchange for a fired group leg: cancel the siblings
+    /// resting there, which lock the same balance, and size the leg to what
+    /// is left of the position. Returns false when it must not be submitted:
+    /// it goes back to waiting for the next price when a sibling couldn't be
+    /// cancelled, and is cancelled when the position is already closed.
+    async fn prepare_group_exit(&self, order: &mut TradingOrder) -> bool {
+        let Some(group_id) = order.group_id.clone() else { return true };
+        let group = match self.order_repository.find_group(&group_id).await {
+            Ok(Some(group)) => group,
+            Ok(None) => return true,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to load group {} of triggered order {}: {}", group_id, order.id, e);
+                self.rearm_trigger(order).await;
+                return false;
+            }
+        };
+        let (parent, mut legs) = match self.load_group_orders(&group).await {
+            Ok(orders) => orders,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to load group {} of triggered order {}: {}", group_id, order.id, e.error);
+                self.rearm_trigger(order).await;
+                return false;
+            }
+        };
+
+        for sibling in legs.iter_mut().filter(|leg| leg.id != order.id) {
+            self.refresh_order(sibling).await;
+            let resting = sibling.exchange_order_id.is_some()
+                && matches!(sibling.status, OrderStatus::New | OrderStatus::PartiallyFilled);
+            if resting && !self.cancel_group_order(sibling).await {
+                console_log!("TRADING SERVICE: Order {} still rests on the exchange, triggered order {} waits for the next price",
+                    sibling.id, order.id);
+                self.rearm_trigger(order).await;
+                return false;
+            }
+        }
+
+        let position = match (&group.group_type, &parent) {
+            (OrderGroupType::Bracket, Some(parent)) => parent.filled_quantity,
+            _ => group.quantity,
+        };
+        let closed: Decimal = legs.iter().filter(|leg| leg.id != order.id).map(|leg| leg.filled_quantity).sum();
+        let remaining = position - closed;
+        if remaining <= Decimal::ZERO {
+            console_log!("TRADING SERVICE: Group {} is already closed, cancelling triggered order {}", group.id, order.id);
+            order.status = OrderStatus::Cancelled;
+            order.updated_at = Utc::now();
+            if let Err(e) = self.order_repository.update_order(order).await {
+                console_log!("TRADING SERVICE: Failed to persist cancelled order {}: {}", order.id, e);
+            }
+            self.publish_order_event(WebhookEvent::OrderCancelled, order).await;
+            return false;
+        }
+        order.quantity = order.quantity.min(remaining);
+        true
+    }
+
+    /// Put a fired trigger order back to wait for its trigger again
+    async fn rearm_trigger(&self, order: &mut TradingOrder) {
+        match self.trigger_monitor.rearm(&order.id).await {
+            Ok(true) => order.status = OrderStatus::PendingTrigger,
+            Ok(false) => console_log!("TRADING SERVICE: Triggered order {} changed before it could be rearmed", order.id),
+            Err(e) => console_log!("TRADING SERVICE: Failed to rearm triggered order {}: {}", order.id, e),
+        }
+    }
+
+    /// Submit a fired trigger order to the exchange as a market or limit order
+    async fn submit_triggered_order(&self, mut order: TradingOrder) -> PlaceOrderResponse {
+        let order_type = if order.order_type.requires_limit_price() { OrderType::Limit } else { OrderType::Market };
+        let side = match order.side {
+            OrderSide::Buy => Side::Buy,
+            OrderSide::Sell => Side::Sell,
+        };
+
+        let result = match self.get_client(&order.instrument.exchange) {
+            Ok(client) => {
+                let order_request = OrderRequest {
+                    instrument: SimpleInstrument {
+                        base: order.instrument.base_asset.clone(),
+                        quote: order.instrument.quote_asset.clone(),
+                    },
+                    side,
+                    quantity: order.quantity,
+                    price: if order.order_type.requires_limit_price() { order.price } else { None },
+                    order_type,
+                    stop_price: None,
+                    trigger_by: TriggerBy::LastPrice,
+                    client_order_id: Some(order.id.clone()),
+                };
+                client.place_order(&order_request).await
+            }
+            Err(e) => Err(e.error),
+        };
+
+        match result {
+            Ok(order_id) => {
+                console_log!("TRADING SERVICE: Triggered order {} submitted with exchange ID: {}", order.id, order_id);
+                order.exchange_order_id = Some(order_id);
+                order.status = OrderStatus::New;
+            }
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to submit triggered order {}: {}", order.id, e);
+                order.status = OrderStatus::Rejected;
+            }
+        }
+        order.updated_at = Utc::now();
+
+        if let Err(e) = self.order_repository.update_order(&order).await {
+            console_log!("TRADING SERVICE: Failed to persist triggered order {}: {}", order.id, e);
+        }
+        if matches!(order.status, OrderStatus::Rejected) {
+            self.publish_order_event(WebhookEvent::OrderRejected, &order).await;
+        }
+
+        self.convert_order_to_response(&order, "SERVER")
+    }
+
+    /// Send an order already stored as PendingSubmit and record the outcome
+    async fn submit_recorded_order(&self, order: TradingOrder) -> Result<PlaceOrderResponse, TradingErrorResponse> {
+        match self.submit_order(order.clone()).await {
+            Ok((order, trigger_mode)) => {
+                self.record_order(&order, trigger_mode, true).await?;
+                Ok(self.convert_order_to_response(&order, trigger_mode))
+            }
+            Err(e) => self.recover_order(order, e).await,
+        }
+    }
+
+    /// Work out what happened to an order whose submission failed.
+    ///
+    /// The error may have hidden a successful placement (a timeout, a dropped
+    /// connection), so the exchange is asked for the order by client order id
+    /// before the failure is reported.
+    async fn recover_order(&self, mut order: TradingOrder, error: TradingErrorResponse) -> Result<PlaceOrderResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Recovering state of order {} after failed submission", order.id);
+
+        let client = self.get_client(&order.instrument.exchange)?;
+        let instrument = SimpleInstrument {
+            base: order.instrument.base_asset.clone(),
+            quote: order.instrument.quote_asset.clone(),
+        };
+
+        match client.get_order_by_client_id(&instrument, &order.id).await {
+            Ok(Some(report)) => self.adopt_exchange_order(order, report).await,
+            Ok(None) => {
+                console_log!("TRADING SERVICE: Order {} not found on exchange", order.id);
+                self.reject_unsent_order(&mut order).await;
+                Err(error)
+            }
+            Err(lookup_error) => {
+                // The order may be live; a retry or reconciliation settles it
+                console_log!("TRADING SERVICE: State of order {} is unknown: {}", order.id, lookup_error);
+                self.release_order(&mut order).await;
+                Err(self.order_state_unknown(&order, &error.error, Some(lookup_error)))
+            }
+        }
+    }
+
+    /// Reject an order the exchange has confirmed it never received
+    async fn reject_unsent_order(&self, order: &mut TradingOrder) {
+        order.status = OrderStatus::Rejected;
+        order.updated_at = Utc::now();
+        if let Err(e) = self.order_repository.update_order(order).await {
+            console_log!("TRADING SERVICE: Failed to persist rejected order {}: {}", order.id, e);
+        }
+        self.publish_order_event(WebhookEvent::OrderRejected, order).await;
+    }
+
+    /// Settle a PendingSubmit order retried with its client order id. The
+    /// order is only sent again once it has been claimed and the exchange
+    /// says it has no order with that id.
+    async fn resolve_pending_order(&self, mut order: TradingOrder) -> Result<PlaceOrderResponse, TradingErrorResponse> {
+        match self.claim_unsettled_order(&mut order).await? {
+            Some(report) => self.adopt_exchange_order(order, report).await,
+            None => {
+                console_log!("TRADING SERVICE: Order {} is not on the exchange, sending it again", order.id);
+                self.submit_recorded_order(order).await
+            }
+        }
+    }
+
+    /// Claim an order whose submission never got a definite answer and ask
+    /// the exchange for it by client order id.
+    ///
+    /// An attempt to send the order may still be in flight until the exchange
+    /// call deadline has passed since the order last changed, and the exchange
+    /// only reports the order once that attempt lands, so until then the order
+    /// is left alone. It is then moved from PendingSubmit to Submitting in one
+    /// conditional update, so only one caller acts on it; a Submitting order is
+    /// one whose claimant never recorded an outcome, and its claim is given back
+    /// first. The claim is also given back if the exchange can't be asked.
+    async fn claim_unsettled_order(&self, order: &mut TradingOrder) -> Result<Option<OrderStatusReport>, TradingErrorResponse> {
+        let client = self.get_client(&order.instrument.exchange)?;
+        let deadline = chrono::Duration::from_std(client.submit_deadline()).unwrap_or(chrono::Duration::MAX);
+        if Utc::now() - order.updated_at < deadline {
+            return Err(self.order_state_unknown(order, "an earlier attempt to send it may still be in flight", None));
+        }
+
+        let claim_error = |e: String| TradingErrorResponse::new(format!("Failed to claim order {}: {}", order.id, e));
+        if matches!(order.status, OrderStatus::Submitting) {
+            let released = self.order_repository
+                .transition_status(&order.id, &OrderStatus::Submitting, &OrderStatus::PendingSubmit).await
+                .map_err(claim_error)?;
+            if !released {
+                return Err(self.order_state_unknown(order, "another request is settling it", None));
+            }
+        }
+        let claimed = self.order_repository
+            .transition_status(&order.id, &OrderStatus::PendingSubmit, &OrderStatus::Submitting).await
+            .map_err(claim_error)?;
+        if !claimed {
+            return Err(self.order_state_unknown(order, "another request is settling it", None));
+        }
+        order.status = OrderStatus::Submitting;
+
+        let instrument = SimpleInstrument {
+            base: order.instrument.base_asset.clone(),
+            quote: order.instrument.quote_asset.clone(),
+        };
+        match client.get_order_by_client_id(&instrument, &order.id).await {
+            Ok(report) => Ok(report),
+            Err(lookup_error) => {
+                console_log!("TRADING SERVICE: State of order {} is still unknown: {}", order.id, lookup_error);
+                self.release_order(order).await;
+                Err(self.order_state_unknown(order, "the exchange could not be asked about it", Some(lookup_error)))
+            }
+        }
+    }
+
+    /// Record what the exchange says about an order it holds
+    async fn adopt_exchange_order(&self, mut order: TradingOrder, report: OrderStatusReport) -> Result<PlaceOrderResponse, TradingErrorResponse> {
+        let trigger_mode = self.apply_exchange_report(&mut order, report).await?;
+        Ok(self.convert_order_to_response(&order, trigger_mode))
+    }
+
+    /// Bring an order the exchange holds in line with its report, returning
+    /// the order's trigger mode
+    async fn apply_exchange_report(&self, order: &mut TradingOrder, report: OrderStatusReport) -> Result<&'static str, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Order {} reached the exchange as {}", order.id, report.exchange_order_id);
+        order.exchange_order_id = Some(report.exchange_order_id);
+        order.status = OrderStatus::parse(&report.status).unwrap_or(OrderStatus::New);
+        order.filled_quantity = report.filled_quantity;
+        order.average_price = report.average_price;
+        order.updated_at = Utc::now();
+        if order.is_filled() {
+            order.executed_at = Some(order.updated_at);
+        }
+
+        let trigger_mode = if order.order_type.is_trigger_order() { "NATIVE" } else { "NONE" };
+        self.record_order(order, trigger_mode, true).await?;
+        if order.filled_quantity > Decimal::ZERO {
+            self.record_fills(order).await;
+        }
+        self.publish_order_changes(None, order).await;
+        Ok(trigger_mode)
+    }
+
+    /// Put an order whose state is unknown back as PendingSubmit, for a
+    /// retry or reconciliation to settle
+    async fn release_order(&self, order: &mut TradingOrder) {
+        order.status = OrderStatus::PendingSubmit;
+        order.updated_at = Utc::now();
+        if let Err(e) = self.order_repository.update_order(order).await {
+            console_log!("TRADING SERVICE: Failed to persist pending order {}: {}", order.id, e);
+        }
+    }
+
+    /// Error for an order that may or may not be on the exchange, with how long
+    /// to wait before it can be settled: until its last attempt has timed out
+    fn order_state_unknown(&self, order: &TradingOrder, reason: &str, lookup_error: Option<String>) -> TradingErrorResponse {
+        let next_step = if order.client_order_id.is_some() {
+            "retry with the same client_order_id"
+        } else {
+            "look the order up again shortly"
+        };
+        let mut unknown = TradingErrorResponse::with_code(
+            format!("State of order {} is unknown, {}: {}", order.id, next_step, reason),
+            "ORDER_STATE_UNKNOWN".to_string(),
+        );
+        unknown.details = lookup_error;
+
+        let deadline = self.get_client(&order.instrument.exchange)
+            .map(|client| client.submit_deadline())
+            .unwrap_or_default();
+        let age = (Utc::now() - order.updated_at).to_std().unwrap_or_default();
+        unknown.retry_after_seconds = Some(deadline.saturating_sub(age).as_secs().max(1));
+        unknown
+    }
+
+    /// Answer a retried request from the order it already created
+    async fn replay_order(&self, existing: TradingOrder, requested: &TradingOrder) -> Result<PlaceOrderResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Client order id {:?} already used by order {}", existing.client_order_id, existing.id);
+
+        let same_order = existing.instrument.exchange == requested.instrument.exchange
+            && existing.instrument.symbol == requested.instrument.symbol
+            && existing.side.as_str() == requested.side.as_str()
+            && existing.order_type.as_str() == requested.order_type.as_str()
+            && existing.quantity == requested.quantity
+            && existing.price == requested.price
+            && existing.stop_price == requested.stop_price;
+        if !same_order {
+            return Err(TradingErrorResponse::with_code(
+                "client_order_id was already used for a different order".to_string(),
+                "CLIENT_ORDER_ID_REUSED".to_string(),
+            ));
+        }
+
+        match existing.status {
+            // The first attempt never got a definite answer: find out, or send it again
+            OrderStatus::PendingSubmit | OrderStatus::Submitting => self.resolve_pending_order(existing).await,
+            _ => {
+                let trigger_mode = match (&existing.status, &existing.exchange_order_id) {
+                    _ if !existing.order_type.is_trigger_order() => "NONE",
+                    (OrderStatus::PendingTrigger, _) | (_, None) => "SERVER",
+                    _ => "NATIVE",
+                };
+                Ok(self.convert_order_to_response(&existing, trigger_mode))
+            }
+        }
+    }
+
+    /// Look up a user's order by client order id
+    async fn find_client_order(&self, user_id: &str, client_order_id: &str) -> Result<Option<TradingOrder>, TradingErrorResponse> {
+        self.order_repository.find_order_by_client_id(user_id, client_order_id).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to look up client order id: {}", e)))
+    }
+
+    /// Whether the order must be held by the trigger monitor instead of the exchange
+    fn needs_server_trigger(&self, order: &TradingOrder) -> Result<bool, TradingErrorResponse> {
+        if !order.order_type.is_trigger_order() {
+            return Ok(false);
+        }
+
+        let client = self.get_client(&order.instrument.exchange)?;
+        let order_request = self.convert_order_to_request(order);
+        Ok(!client.supports_native_trigger(&order_request.order_type, order_request.trigger_by))
+    }
+
+    /// Send an order to the exchange, or to the trigger monitor when the
+    /// exchange cannot hold its trigger natively. Returns the updated order
+    /// and its trigger mode; persisting it is left to the caller.
+    async fn submit_order(&self, mut order: TradingOrder) -> Result<(TradingOrder, &'static str), TradingErrorResponse> {
+        let client = self.get_client(&order.instrument.exchange)?;
+        let order_request = self.convert_order_to_request(&order);
+
+        if self.needs_server_trigger(&order)? {
+            console_log!("TRADING SERVICE: {:?} cannot hold {} orders triggered by {}, using server-side trigger monitor",
+                client.exchange, order.order_type.as_str(), order.trigger_by.as_str());
+            return self.hold_for_trigger(order);
+        }
+
+        match client.place_order(&order_request).await {
+            Ok(order_id) => {
+                console_log!("TRADING SERVICE: Successfully placed order with ID: {}", order_id);
+                order.exchange_order_id = Some(order_id);
+                order.status = OrderStatus::New;
+                order.updated_at = Utc::now();
+
+                let trigger_mode = if order.order_type.is_trigger_order() { "NATIVE" } else { "NONE" };
+                Ok((order, trigger_mode))
+            }
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to place order: {}", e);
+                Err(TradingErrorResponse::new(format!("Failed to place order: {}", e)))
+            }
+        }
+    }
+
+    /// Hand a trigger order to the trigger monitor instead of the exchange
+    fn hold_for_trigger(&self, order: TradingOrder) -> Result<(TradingOrder, &'static str), TradingErrorResponse> {
+        match self.trigger_monitor.register(order) {
+            Ok(order) => Ok((order, "SERVER")),
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to register trigger order: {}", e);
+                Err(TradingErrorResponse::new(format!("Failed to register trigger order: {}", e)))
+            }
+        }
+    }
+
+    /// Open a group's take-profit and stop-loss legs, which exit the same
+    /// position. Both resting on the exchange at full size would lock it
+    /// twice, so the pair goes as one native OCO where the exchange offers
+    /// it; otherwise only the take-profit rests there and the stop is held by
+    /// the trigger monitor, which cancels the take-profit when the stop fires.
+    /// Returns the legs opened and those that failed, with their error.
+    async fn open_exit_legs(&self, legs: Vec<TradingOrder>, existing: bool) -> (Vec<TradingOrder>, Vec<(TradingOrder, TradingErrorResponse)>) {
+        let (stops, limits): (Vec<TradingOrder>, Vec<TradingOrder>) = legs.into_iter()
+            .partition(|leg| leg.order_type.is_trigger_order());
+
+        if let ([take_profit], [stop_loss]) = (limits.as_slice(), stops.as_slice()) {
+            let stop_request = self.convert_order_to_request(stop_loss);
+            let native = self.get_client(&stop_loss.instrument.exchange)
+                .is_ok_and(|client| client.supports_native_oco(stop_request.trigger_by));
+            if native {
+                return self.place_native_oco(take_profit.clone(), stop_loss.clone(), existing).await;
+            }
+        }
+
+        let (mut opened, mut failed) = (Vec::new(), Vec::new());
+        for leg in limits.into_iter().chain(stops) {
+            let submitted = if leg.order_type.is_trigger_order() {
+                self.hold_for_trigger(leg.clone())
+            } else {
+                self.submit_order(leg.clone()).await
+            };
+            let recorded = match submitted {
+                Ok((submitted, trigger_mode)) => self.record_order(&submitted, trigger_mode, existing).await.map(|_| submitted),
+                Err(e) => Err(e),
+            };
+            match recorded {
+                Ok(leg) => opened.push(leg),
+                Err(e) => failed.push((leg, e)),
+            }
+        }
+        (opened, failed)
+    }
+
+    /// Place a take-profit and stop-loss as one exchange-held OCO pair
+    async fn place_native_oco(
+        &self,
+        mut take_profit: TradingOrder,
+        mut stop_loss: TradingOrder,
+        existing: bool,
+    ) -> (Vec<TradingOrder>, Vec<(TradingOrder, TradingErrorResponse)>) {
+        let result = match self.get_client(&take_profit.instrument.exchange) {
+            Ok(client) => client.place_oco_order(
+                &self.convert_order_to_request(&take_profit),
+                &self.convert_order_to_request(&stop_loss),
+            ).await.map_err(|e| TradingErrorResponse::new(format!("Failed to place OCO: {}", e))),
+            Err(e) => Err(e),
+        };
+
+        let (take_profit_id, stop_loss_id) = match result {
+            Ok(ids) => ids,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to place OCO: {}", e.error);
+                return (Vec::new(), vec![(take_profit, e.clone()), (stop_loss, e)]);
+            }
+        };
+        console_log!("TRADING SERVICE: Placed OCO with exchange IDs {} and {}", take_profit_id, stop_loss_id);
+
+        for (leg, exchange_order_id, trigger_mode) in [(&mut take_profit, take_profit_id, "NONE"), (&mut stop_loss, stop_loss_id, "NATIVE")] {
+            leg.exchange_order_id = Some(exchange_order_id);
+            leg.status = OrderStatus::New;
+            leg.updated_at = Utc::now();
+            // Only orders held by the trigger monitor fail to record
+            let _ = self.record_order(leg, trigger_mode, existing).await;
+        }
+        (vec![take_profit, stop_loss], Vec::new())
+    }
+
+    /// Persist a submitted order. Orders held by the trigger monitor only exist
+    /// in the database, so failing to store one withdraws it and is an error.
+    async fn record_order(&self, order: &TradingOrder, trigger_mode: &str, existing: bool) -> Result<(), TradingErrorResponse> {
+        let result = if existing {
+            self.order_repository.update_order(order).await
+        } else {
+            self.order_repository.save_order(order).await
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(e) if trigger_mode == "SERVER" => {
+                console_log!("TRADING SERVICE: Failed to persist trigger order {}: {}", order.id, e);
+                return Err(TradingErrorResponse::new(format!("Failed to register trigger order: {}", e)));
+            }
+            Err(e) => console_log!("TRADING SERVICE: Failed to persist order {}: {}", order.id, e),
+        }
+
+        if trigger_mode == "SERVER" {
+            self.trigger_monitor.watch(&order.instrument).await;
+        }
+        self.publish_order_event(WebhookEvent::OrderCreated, order).await;
+        Ok(())
+    }
+
+    /// Load a group and check it belongs to the user
+    async fn find_user_group(&self, user_id: &str, group_id: &str) -> Result<OrderGroup, TradingErrorResponse> {
+        let group = self.order_repository.find_group(group_id).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load order group: {}", e)))?;
+
+        match group {
+            Some(group) if group.user_id == user_id => Ok(group),
+            _ => Err(TradingErrorResponse::with_code(
+                format!("Order group not found: {}", group_id),
+                "GROUP_NOT_FOUND".to_string(),
+            )),
+        }
+    }
+
+    async fn find_user_order(&self, user_id: &str, order_id: &str) -> Result<TradingOrder, TradingErrorResponse> {
+        let order = self.order_repository.find_order(order_id).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load order: {}", e)))?;
+
+        match order {
+            Some(order) if order.user_id == user_id => Ok(order),
+            _ => Err(TradingErrorResponse::with_code(
+                format!("Order not found: {}", order_id),
+                "ORDER_NOT_FOUND".to_string(),
+            )),
+        }
+    }
+
+    /// Load a group's orders, split into the bracket entry (if any) and the exit legs
+    async fn load_group_orders(&self, group: &OrderGroup) -> Result<(Option<TradingOrder>, Vec<TradingOrder>), TradingErrorResponse> {
+        let orders = self.order_repository.find_orders_by_group(&group.id).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load group orders: {}", e)))?;
+
+        let (parents, legs): (Vec<TradingOrder>, Vec<TradingOrder>) = orders
+            .into_iter()
+            .partition(|order| group.parent_order_id.as_deref() == Some(order.id.as_str()));
+
+        Ok((parents.into_iter().next(), legs))
+    }
+
+    /// Sync a group by id, logging rather than returning failures
+    async fn sync_group_by_id(&self, group_id: &str) {
+        match self.order_repository.find_group(group_id).await {
+            Ok(Some(group)) => {
+                if let Err(e) = self.sync_group(group).await {
+                    console_log!("TRADING SERVICE: Failed to sync group {}: {}", group_id, e.error);
+                }
+            }
+            Ok(None) => console_log!("TRADING SERVICE: Group {} not found", group_id),
+            Err(e) => console_log!("TRADING SERVICE: Failed to load group {}: {}", group_id, e),
+        }
+    }
+
+    /// Refresh fills from the exchange and apply the leg actions they imply
+    async fn sync_group(&self, mut group: OrderGroup) -> Result<OrderGroupResponse, TradingErrorResponse> {
+        let (mut parent, mut legs) = self.load_group_orders(&group).await?;
+
+        if let Some(parent) = parent.as_mut() {
+            self.refresh_order(parent).await;
+        }
+        for leg in legs.iter_mut() {
+            self.refresh_order(leg).await;
+        }
+
+        let mut activating = Vec::new();
+        for action in plan_leg_actions(&group, parent.as_ref(), &legs) {
+            console_log!("TRADING SERVICE: Group {} action: {:?}", group.id, action);
+
+            match action {
+                LegAction::Cancel { order_id } => {
+                    let order = parent.iter_mut()
+                        .chain(legs.iter_mut())
+                        .find(|order| order.id == order_id);
+                    if let Some(order) = order {
+                        self.cancel_group_order(order).await;
+                    }
+                }
+                LegAction::Replace { order_id, quantity } => {
+                    let Some(index) = legs.iter().position(|leg| leg.id == order_id) else { continue };
+                    if !self.cancel_group_order(&mut legs[index]).await {
+                        continue;
+                    }
+
+                    // The cancelled leg keeps its fills; the replacement covers what is left
+                    let mut replacement = TradingOrder::new(
+                        legs[index].user_id.clone(),
+                        legs[index].instrument.clone(),
+                        legs[index].side.clone(),
+                        legs[index].order_type.clone(),
+                        quantity,
+                        legs[index].price,
+                    );
+                    replacement.stop_price = legs[index].stop_price;
+                    replacement.trigger_by = legs[index].trigger_by;
+                    replacement.group_id = legs[index].group_id.clone();
+                    replacement.parent_order_id = legs[index].parent_order_id.clone();
+
+                    // A stop resting beside its sibling would lock the same balance twice
+                    let submitted = if replacement.order_type.is_trigger_order() {
+                        self.hold_for_trigger(replacement)
+                    } else {
+                        self.submit_order(replacement).await
+                    };
+                    match submitted {
+                        Ok((replacement, trigger_mode)) => {
+                            if self.record_order(&replacement, trigger_mode, false).await.is_ok() {
+                                legs.push(replacement);
+                            }
+                        }
+                        Err(e) => console_log!("TRADING SERVICE: Failed to resize leg {}: {}", order_id, e.error),
+                    }
+                }
+                // Opened together below, as one pair
+                LegAction::Activate { order_id, quantity } => {
+                    let Some(leg) = legs.iter_mut().find(|leg| leg.id == order_id) else { continue };
+                    leg.quantity = quantity;
+                    activating.push(leg.clone());
+                }
+            }
+        }
+
+        if !activating.is_empty() {
+            let (opened, failed) = self.open_exit_legs(activating, true).await;
+            for submitted in opened {
+                if let Some(leg) = legs.iter_mut().find(|leg| leg.id == submitted.id) {
+                    *leg = submitted;
+                }
+            }
+            for (mut leg, e) in failed {
+                console_log!("TRADING SERVICE: Failed to activate leg {}: {}", leg.id, e.error);
+                leg.status = OrderStatus::Rejected;
+                leg.updated_at = Utc::now();
+                if let Err(e) = self.order_repository.update_order(&leg).await {
+                    console_log!("TRADING SERVICE: Failed to persist leg {}: {}", leg.id, e);
+                }
+                self.publish_order_event(WebhookEvent::OrderRejected, &leg).await;
+                if let Some(slot) = legs.iter_mut().find(|slot| slot.id == leg.id) {
+                    *slot = leg;
+                }
+            }
+        }
+
+        let status = resolve_group_status(parent.as_ref(), &legs);
+        if group.is_active() && status != group.status {
+            console_log!("TRADING SERVICE: Group {} is now {}", group.id, status.as_str());
+            group.status = status;
+            group.updated_at = Utc::now();
+            if let Err(e) = self.order_repository.update_group(&group).await {
+                console_log!("TRADING SERVICE: Failed to persist group {}: {}", group.id, e);
+            }
+        }
+
+        Ok(self.convert_group_to_response(&group, parent.as_ref(), &legs))
+    }
+
+    /// Pull the latest status and fills for an order working on the exchange,
+    /// returning whether either changed
+    async fn refresh_order(&self, order: &mut TradingOrder) -> bool {
+        let exchange_order_id = match &order.exchange_order_id {
+            Some(id) if order.is_active() => id.clone(),
+            _ => return false,
+        };
+        let Ok(client) = self.get_client(&order.instrument.exchange) else { return false };
+
+        let instrument = SimpleInstrument {
+            base: order.instrument.base_asset.clone(),
+            quote: order.instrument.quote_asset.clone(),
+        };
+        let report = match client.get_order_status(&instrument, &exchange_order_id).await {
+            Ok(report) => report,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to refresh order {}: {}", order.id, e);
+                return false;
+            }
+        };
+
+        let status = OrderStatus::parse(&report.status).unwrap_or_else(|| order.status.clone());
+        if status.as_str() == order.status.as_str() && report.filled_quantity == order.filled_quantity {
+            return false;
+        }
+        let new_fills = report.filled_quantity > order.filled_quantity;
+        let previous = order.clone();
+
+        order.status = status;
+        order.filled_quantity = report.filled_quantity;
+        order.average_price = report.average_price.or(order.average_price);
+        order.updated_at = Utc::now();
+        if order.is_filled() {
+            order.executed_at = Some(order.updated_at);
+        }
+
+        if let Err(e) = self.order_repository.update_order(order).await {
+            console_log!("TRADING SERVICE: Failed to persist order {}: {}", order.id, e);
+        }
+
+        if new_fills {
+            self.record_fills(order).await;
+        }
+        self.publish_order_changes(Some(&previous), order).await;
+        true
+    }
+
+    /// Tell the user's webhooks about an order
+    async fn publish_order_event(&self, event: WebhookEvent, order: &TradingOrder) {
+        let data = json!({
+            "exchange": order.instrument.exchange,
+            "order": self.convert_order_to_dto(order),
+        });
+        self.webhook_service.publish(&order.user_id, event, data).await;
+    }
+
+    /// Tell the user's webhooks what changed since `previous`, the order as it
+    /// was before the exchange's latest report: the final status it reached,
+    /// and the balance change made by new fills
+    async fn publish_order_changes(&self, previous: Option<&TradingOrder>, order: &TradingOrder) {
+        let status_changed = previous.is_none_or(|previous| previous.status.as_str() != order.status.as_str());
+        let event = match order.status {
+            OrderStatus::Filled => Some(WebhookEvent::OrderFilled),
+            OrderStatus::Cancelled | OrderStatus::Expired => Some(WebhookEvent::OrderCancelled),
+            OrderStatus::Rejected => Some(WebhookEvent::OrderRejected),
+            _ => None,
+        };
+        if let Some(event) = event.filter(|_| status_changed) {
+            self.publish_order_event(event, order).await;
+        }
+
+        let previous_filled = previous.map_or(Decimal::ZERO, |previous| previous.filled_quantity);
+        if order.filled_quantity <= previous_filled {
+            return;
+        }
+
+        // Quote moved by the new fills, before fees: what has now filled at the
+        // average price, less what had filled before
+        let notional = |order: &TradingOrder| order.average_price.map(|price| price * order.filled_quantity);
+        let quote_quantity = match previous {
+            Some(previous) if previous.filled_quantity > Decimal::ZERO => notional(order)
+                .zip(notional(previous))
+                .map(|(now, before)| now - before),
+            _ => notional(order),
+        };
+        let base_quantity = order.filled_quantity - previous_filled;
+        let (base_delta, quote_delta) = match order.side {
+            OrderSide::Buy => (base_quantity, quote_quantity.map(|quantity| -quantity)),
+            OrderSide::Sell => (-base_quantity, quote_quantity),
+        };
+
+        let data = json!({
+            "exchange": order.instrument.exchange,
+            "order_id": order.id,
+            "changes": [
+                { "asset": order.instrument.base_asset, "delta": base_delta },
+                { "asset": order.instrument.quote_asset, "delta": quote_delta },
+            ],
+        });
+        self.webhook_service.publish(&order.user_id, WebhookEvent::BalanceChanged, data).await;
+    }
+
+    /// Store the exchange's fills for an order as trade executions
+    async fn record_fills(&self, order: &TradingOrder) {
+        let Some(exchange_order_id) = &order.exchange_order_id else { return };
+        let Ok(client) = self.get_client(&order.instrument.exchange) else { return };
+
+        let instrument = SimpleInstrument {
+            base: order.instrument.base_asset.clone(),
+            quote: order.instrument.quote_asset.clone(),
+        };
+        let fills = match client.get_order_fills(&instrument, exchange_order_id).await {
+            Ok(fills) => fills,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to fetch fills for order {}: {}", order.id, e);
+                return;
+            }
+        };
+
+        for fill in fills {
+            let execution = TradeExecution {
+                id: uuid::Uuid::new_v4().to_string(),
+                order_id: order.id.clone(),
+                user_id: order.user_id.clone(),
+                exchange_trade_id: fill.exchange_trade_id,
+                instrument: order.instrument.clone(),
+                side: order.side.clone(),
+                quantity: fill.quantity,
+                price: fill.price,
+                commission: fill.commission,
+                // Venues that do not name the fee asset charge it in the quote asset
+                commission_asset: if fill.commission_asset.is_empty() {
+                    order.instrument.quote_asset.clone()
+                } else {
+                    fill.commission_asset
+                },
+                is_maker: fill.is_maker,
+                executed_at: fill.executed_at,
+            };
+
+            if let Err(e) = self.trade_repository.save_execution(&execution).await {
+                console_log!("TRADING SERVICE: Failed to persist execution {}: {}", execution.exchange_trade_id, e);
+            }
+        }
+    }
+
+    /// Bring a user's working orders up to date so their fills are recorded,
+    /// returning how many orders were checked
+    async fn sync_user_fills(&self, user_id: &str, exchange: &str) -> usize {
+        let orders = match self.order_repository.find_open_orders(user_id, exchange).await {
+            Ok(orders) => orders,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to load open orders for {}: {}", user_id, e);
+                return 0;
+            }
+        };
+
+        let count = orders.len();
+        let mut group_ids: Vec<String> = Vec::new();
+        for mut order in orders {
+            if self.refresh_order(&mut order).await {
+                if let Some(group_id) = order.group_id.filter(|id| !group_ids.contains(id)) {
+                    group_ids.push(group_id);
+                }
+            }
+        }
+
+        // A leg that filled or was cancelled on the exchange changes what its
+        // siblings may still close, and a filled bracket entry activates its exits
+        for group_id in group_ids {
+            self.sync_group_by_id(&group_id).await;
+        }
+        count
+    }
+
+    /// Refresh every user's working orders on every exchange against the
+    /// exchange's view, recording any fills, and settle orders whose
+    /// submission never got a definite answer. Returns the number of working
+    /// orders checked and of unsent orders settled.
+    pub async fn reconcile_open_orders(&self) -> Result<(usize, usize), String> {
+        let users = self.order_repository.find_trading_users().await?;
+        console_log!("TRADING SERVICE: Reconciling open orders for {} users", users.len());
+
+        let mut checked = 0;
+        for user_id in &users {
+            for exchange in self.exchange_names() {
+                checked += self.sync_user_fills(user_id, &exchange).await;
+            }
+        }
+
+        let mut settled = 0;
+        for order in self.order_repository.find_unsettled_orders().await? {
+            if self.settle_unsent_order(order).await {
+                settled += 1;
+            }
+        }
+        Ok((checked, settled))
+    }
+
+    /// Settle an order left PendingSubmit by a failed or abandoned submission:
+    /// adopt it if the exchange has it, or reject it if the exchange confirms
+    /// it doesn't. Orders that may still be in flight, or that the exchange
+    /// can't be asked about, are left for a later run. Returns whether the
+    /// order was settled.
+    async fn settle_unsent_order(&self, mut order: TradingOrder) -> bool {
+        let report = match self.claim_unsettled_order(&mut order).await {
+            Ok(report) => report,
+            Err(e) => {
+                console_log!("TRADING SERVICE: Leaving order {} unsettled: {}", order.id, e.error);
+                return false;
+            }
+        };
+
+        let group_id = order.group_id.clone();
+        match report {
+            Some(report) => {
+                if self.adopt_exchange_order(order, report).await.is_err() {
+                    return false;
+                }
+            }
+            None => {
+                console_log!("TRADING SERVICE: Order {} never reached the exchange", order.id);
+                self.reject_unsent_order(&mut order).await;
+            }
+        }
+
+        // A fired leg changes what its siblings may still close
+        if let Some(group_id) = group_id {
+            self.sync_group_by_id(&group_id).await;
+        }
+        true
+    }
+
+    /// Cancel an order wherever it is held. Returns false if the exchange
+    /// refused, in which case the order is left as it was.
+    ///
+    /// An order sent without a definite answer may be live on the exchange
+    /// under its client order id, so it is looked up there first: found, it is
+    /// cancelled on the exchange like any other; it is only cancelled locally
+    /// once the exchange confirms it doesn't have it.
+    async fn cancel_group_order(&self, order: &mut TradingOrder) -> bool {
+        if matches!(order.status, OrderStatus::PendingSubmit | OrderStatus::Submitting) && order.exchange_order_id.is_none() {
+            match self.claim_unsettled_order(order).await {
+                Ok(Some(report)) => {
+                    if let Err(e) = self.apply_exchange_report(order, report).await {
+                        console_log!("TRADING SERVICE: Failed to record order {} found on the exchange: {}", order.id, e.error);
+                        return false;
+                    }
+                    if order.is_terminal() {
+                        console_log!("TRADING SERVICE: Order {} was already {} on the exchange", order.id, order.status.as_str());
+                        return false;
+                    }
+                }
+                Ok(None) => console_log!("TRADING SERVICE: Order {} never reached the exchange", order.id),
+                Err(e) => {
+                    console_log!("TRADING SERVICE: Can't cancel order {} yet: {}", order.id, e.error);
+                    return false;
+                }
+            }
+        }
+
+        match (&order.status, &order.exchange_order_id) {
+            // Only cancelled if it hasn't fired meanwhile
+            (OrderStatus::PendingTrigger, _) => match self.trigger_monitor.withdraw(&order.id).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    console_log!("TRADING SERVICE: Trigger order {} fired before it could be cancelled", order.id);
+                    return false;
+                }
+                Err(e) => {
+                    console_log!("TRADING SERVICE: Failed to withdraw trigger order {}: {}", order.id, e);
+                    return false;
+                }
+            },
+            (OrderStatus::New | OrderStatus::PartiallyFilled, Some(exchange_order_id)) => {
+                let result = match self.get_client(&order.instrument.exchange) {
+                    Ok(client) => {
+                        let instrument = SimpleInstrument {
+                            base: order.instrument.base_asset.clone(),
+                            quote: order.instrument.quote_asset.clone(),
+                        };
+                        client.cancel_order(&instrument, exchange_order_id).await
+                    }
+                    Err(e) => Err(e.error),
+                };
+
+                if let Err(e) = result {
+                    console_log!("TRADING SERVICE: Failed to cancel order {}: {}", order.id, e);
+                    return false;
+                }
+            }
+            _ => {}
+        }
+
+        order.status = OrderStatus::Cancelled;
+        order.updated_at = Utc::now();
+        if let Err(e) = self.order_repository.update_order(order).await {
+            console_log!("TRADING SERVICE: Failed to persist cancelled order {}: {}", order.id, e);
+        }
+        self.publish_order_event(WebhookEvent::OrderCancelled, order).await;
+        true
+    }
+
+    /// Cancel whatever was already placed for a group that failed to open
+    async fn abandon_group(&self, mut group: OrderGroup, mut orders: Vec<TradingOrder>) {
+        for order in orders.iter_mut() {
+            self.cancel_group_order(order).await;
+        }
+
+        group.status = OrderGroupStatus::Cancelled;
+        group.updated_at = Utc::now();
+        if let Err(e) = self.order_repository.update_group(&group).await {
+            console_log!("TRADING SERVICE: Failed to persist group {}: {}", group.id, e);
+        }
+    }
+
+    /// Get account balances
+    pub async fn get_balances(&self, request: GetBalancesRequest) -> Result<GetBalancesResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting balances for {}", request.exchange);
+        
+        let client = self.get_client(&request.exchange)?;
+        
+        match client.get_balances().await {
+            Ok(balances) => {
+                console_log!("TRADING SERVICE: Successfully retrieved {} balances", balances.len());
+                let prices = self.price_balances(client, &balances).await;
+                Ok(self.convert_balances_to_response(balances, &prices, &request.exchange))
+            }
+            Err(e) => {
+                console_log!("TRADING SERVICE: Failed to get balances: {}", e);
+                Err(TradingErrorResponse::new(format!("Failed to get balances: {}", e)))
+            }
+        }
+    }
+
+    /// Get a portfolio summary of the user's own balances valued in USD, and
+    /// their realized and unrealized PnL from lot accounting over their executions
+    pub async fn get_portfolio(&self, user_id: &str, request: GetPortfolioRequest) -> Result<GetPortfolioResponse, TradingErrorResponse> {
+        console_log!("TRADING SERVICE: Getting portfolio for {} on {}", user_id, request.exchange);
+
+        let client = self.get_client(&request.exchange)?;
+        let method = match request.cost_basis_method.as_deref() {
+            None => CostBasisMethod::default(),
+            Some(value) => CostBasisMethod::parse(value)
+                .ok_or_else(|| TradingErrorResponse::with_code(
+                    format!("Invalid cost_basis_method: {}", value),
+                    "INVALID_COST_BASIS_METHOD".to_string(),
+                ))?,
+        };
+        let session_start = request.session_start
+            .unwrap_or_else(|| Utc::now().date_naive().and_time(NaiveTime::MIN).and_utc());
+
+        self.sync_user_fills(user_id, &request.exchange).await;
+        let executions = self.trade_repository.find_executions(user_id, &request.exchange).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load trade executions: {}", e)))?;
+        let balances = self.account_balances(user_id, &request.exchange, &executions).await?;
+        let prices = self.price_balances(client, &balances).await;
+
+        let mut portfolio = Portfolio::new(user_id.to_string(), request.exchange.clone());
+        for balance in &balances {
+            let usd_value = prices.get(&balance.asset)
+                .map(|price| (balance.total * price.usd_price).round_dp(2))
+                .unwrap_or(Decimal::ZERO);
+            portfolio.update_balance(balance.asset.clone(), balance.free, balance.locked, usd_value);
+        }
+
+        let unpriced_assets: Vec<String> = balances.iter()
+            .filter(|balance| !balance.total.is_zero() && !prices.contains_key(&balance.asset))
+            .map(|balance| balance.asset.clone())
+            .collect();
+        if !unpriced_assets.is_empty() {
+            console_log!("TRADING SERVICE: No USD price for {:?}, valued at zero", unpriced_assets);
+        }
+
+        let session = TradingSession::new(user_id.to_string(), request.exchange.clone(), session_start);
+        let pnl = self.summarize_pnl(client, &executions, method, session).await;
+
+        Ok(self.convert_portfolio_to_response(portfolio, unpriced_assets, pnl))
+    }
+
+    /// The user's own balances on an exchange, from their transfers and the
+    /// given executions. Balances are all free: funds held by open orders
+    /// aren't set aside.
+    async fn account_balances(&self, user_id: &str, exchange: &str, executions: &[TradeExecution]) -> Result<Vec<Balance>, TradingErrorResponse> {
+        let transfers = self.transfer_repository.find_transfers(user_id, Some(exchange)).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load account transfers: {}", e)))?;
+
+        Ok(ledger_balances(&transfers, executions)
+            .into_iter()
+            .map(|(asset, total)| Balance { asset, free: total, locked: Decimal::ZERO, total })
+            .collect())
+    }
+
+    /// Compare each exchange account's balances with the sum of every user's
+    /// ledger there, since users' balances are only ever rebuilt from their
+    /// own records. Returns the drift found on each exchange, or why that
+    /// exchange couldn't be reconciled.
+    pub async fn reconcile_ledgers(&self) -> Vec<(String, Result<Vec<LedgerDrift>, String>)> {
+        let mut results = Vec::new();
+        for exchange in self.exchange_names() {
+            let result = self.reconcile_ledger(&exchange).await;
+            match &result {
+                Ok(drifts) => {
+                    for drift in drifts {
+                        console_log!("TRADING SERVICE: Ledger drift on {}: {} holds {} but users' ledgers add up to {}",
+                            exchange, drift.asset, drift.exchange_total, drift.ledger_total);
+                    }
+                }
+                Err(e) => console_log!("TRADING SERVICE: Failed to reconcile ledger on {}: {}", exchange, e),
+            }
+            results.push((exchange, result));
+        }
+        results
+    }
+
+    async fn reconcile_ledger(&self, exchange: &str) -> Result<Vec<LedgerDrift>, String> {
+        let client = self.get_client(exchange).map_err(|e| e.error)?;
+        let balances = client.get_balances().await
+            .map_err(|e| format!("Failed to get balances: {}", e))?;
+        let transfers = self.transfer_repository.find_exchange_transfers(exchange).await?;
+        let executions = self.trade_repository.find_exchange_executions(exchange).await?;
+
+        let mut exchange_totals: BTreeMap<String, Decimal> = BTreeMap::new();
+        for balance in balances {
+            *exchange_totals.entry(client.normalize_asset(&balance.asset)).or_default() += balance.total;
+        }
+        exchange_totals.retain(|_, total| !total.is_zero());
+
+        Ok(ledger_drift(&exchange_totals, &ledger_balances(&transfers, &executions)))
+    }
+
+    /// Exchanges the user has an account on: those with a recorded transfer
+    /// or an execution, and a client to value them with
+    pub async fn account_exchanges(&self, user_id: &str) -> Result<Vec<String>, String> {
+        let transfers = self.transfer_repository.find_transfers(user_id, None).await?;
+        let mut exchanges = self.trade_repository.find_user_exchanges(user_id).await?;
+        exchanges.extend(transfers.into_iter().map(|transfer| transfer.exchange));
+        exchanges.retain(|exchange| self.clients.contains_key(exchange));
+        exchanges.sort();
+        exchanges.dedup();
+        Ok(exchanges)
+    }
+
+    /// USD value, at current prices, of the deposits minus withdrawals the
+    /// user had recorded on an exchange after `since`
+    pub async fn net_flow_usd(&self, user_id: &str, exchange: &str, since: Option<DateTime<Utc>>) -> Result<Decimal, String> {
+        let transfers = self.transfer_repository.find_transfers(user_id, Some(exchange)).await?;
+        let flows = net_flows(&transfers, since);
+        if flows.is_empty() {
+            return Ok(Decimal::ZERO);
+        }
+
+        let client = self.get_client(exchange).map_err(|e| e.error)?;
+        let assets: Vec<String> = flows.keys().cloned().collect();
+        let prices = self.pricing_service.price_assets(client, &assets).await;
+        let mut total = Decimal::ZERO;
+        for (asset, flow) in flows {
+            match prices.get(&asset) {
+                Some(price) => total += flow * price.usd_price,
+                None => console_log!("TRADING SERVICE: No USD price for {} transfer of {}, left out of its flow", asset, user_id),
+            }
+        }
+        Ok(total.round_dp(2))
+    }
+
+    /// Record a deposit or withdrawal against a user's account, moving their
+    /// balances from now on
+    pub async fn record_transfer(&self, request: RecordTransferRequest) -> Result<TransferDto, TradingErrorResponse> {
+        let exchange = request.exchange.to_lowercase();
+        let client = self.get_client(&exchange)?;
+        let kind = TransferKind::parse(&request.kind)
+            .ok_or_else(|| TradingErrorResponse::with_code(
+                format!("Invalid kind: {} (expected DEPOSIT or WITHDRAWAL)", request.kind),
+                "INVALID_TRANSFER_KIND".to_string(),
+            ))?;
+        let asset = client.normalize_asset(&request.asset);
+        if asset.is_empty() {
+            return Err(TradingErrorResponse::with_code("asset is required".to_string(), "MISSING_ASSET".to_string()));
+        }
+        if request.amount <= Decimal::ZERO {
+            return Err(TradingErrorResponse::with_code("amount must be positive".to_string(), "INVALID_AMOUNT".to_string()));
+        }
+
+        let now = Utc::now();
+        let transfer = AccountTransfer {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: request.user_id,
+            exchange,
+            asset,
+            kind,
+            amount: request.amount,
+            note: request.note,
+            occurred_at: request.occurred_at.unwrap_or(now),
+            created_at: now,
+        };
+        console_log!("TRADING SERVICE: Recording {} of {} {} for {} on {}",
+            transfer.kind.as_str(), transfer.amount, transfer.asset, transfer.user_id, transfer.exchange);
+        self.transfer_repository.save_transfer(&transfer).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to record transfer: {}", e)))?;
+
+        Ok(self.convert_transfer_to_dto(transfer))
+    }
+
+    /// The user's deposits and withdrawals, oldest first
+    pub async fn get_transfers(&self, user_id: &str, request: GetTransfersRequest) -> Result<GetTransfersResponse, TradingErrorResponse> {
+        let exchange = request.exchange.map(|exchange| exchange.to_lowercase());
+        let transfers = self.transfer_repository.find_transfers(user_id, exchange.as_deref()).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load account transfers: {}", e)))?;
+
+        Ok(GetTransfersResponse {
+            transfers: transfers.into_iter().map(|transfer| self.convert_transfer_to_dto(transfer)).collect(),
+        })
+    }
+
+    /// Get one portfolio combining the user's own balances on several exchanges.
+    ///
+    /// Exchanges default to those the user has an account on, and are valued
+    /// concurrently, each pricing its own balances. Exchanges that fail are
+    /// reported in `exchange_errors` and left out of the totals; the request
+    /// only fails when none of them respond.
+    pub async fn get_aggregate_portfolio(&self, user_id: &str, request: GetAggregatePortfolioRequest) -> Result<GetAggregatePortfolioResponse, TradingErrorResponse> {
+        let exchanges = match request.exchanges {
+            Some(exchanges) if !exchanges.is_empty() => self.requested_exchanges(Some(exchanges)),
+            _ => self.account_exchanges(user_id).await
+                .map_err(|e| TradingErrorResponse::new(format!("Failed to find the user's exchanges: {}", e)))?,
+        };
+        console_log!("TRADING SERVICE: Getting aggregate portfolio for {} across {:?}", user_id, exchanges);
+
+        let results = join_all(exchanges.iter().map(|exchange| self.value_account_balances(user_id, exchange))).await;
+        let valued: Vec<_> = exchanges.into_iter().zip(results).collect();
+        for (exchange, result) in &valued {
+            if let Err(e) = result {
+                console_log!("TRADING SERVICE: Leaving {} out of aggregate portfolio: {}", exchange, e.error);
+            }
+        }
+
+        Self::aggregate_portfolio(user_id, valued)
     }
 
-    /// Initialize trading clients for supported exchanges
-    fn initialize_clients(&mut self) {
-        console_log!("TRADING SERVICE: Initializing trading clients for supported exchanges");
-        
-        for exchange in &self.supported_exchanges {
-            match TradingClient::from_env(*exchange) {
-                Ok(client) => {
-                    let exchange_name = format!("{:?}", exchange).to_lowercase();
-                    self.clients.insert(exchange_name, client);
-                    console_log!("TRADING SERVICE: Successfully initialized client for {:?}", exchange);
-                }
+    /// Combine the balances valued on each exchange into one portfolio,
+    /// listing the exchanges that failed in `exchange_errors`. Fails only
+    /// when every exchange did.
+    fn aggregate_portfolio(
+        user_id: &str,
+        valued: Vec<(String, Result<ValuedBalances, TradingErrorResponse>)>,
+    ) -> Result<GetAggregatePortfolioResponse, TradingErrorResponse> {
+        let mut portfolio = Portfolio::new(user_id.to_string(), PortfolioSnapshot::AGGREGATE_EXCHANGE.to_string());
+        let mut breakdown: HashMap<String, Vec<ExchangeBalanceDto>> = HashMap::new();
+        let mut unpriced_assets: Vec<String> = Vec::new();
+        let mut succeeded = Vec::new();
+        let mut exchange_errors = Vec::new();
+
+        for (exchange, result) in valued {
+            let (balances, prices) = match result {
+                Ok(valued) => valued,
                 Err(e) => {
-                    console_log!("TRADING SERVICE: Failed to initialize client for {:?}: {}", exchange, e);
+                    exchange_errors.push(ExchangeErrorDto { exchange, error: e.error });
+                    continue;
                 }
+            };
+
+            for balance in balances {
+                let usd_value = match prices.get(&balance.asset) {
+                    Some(price) => (balance.total * price.usd_price).round_dp(2),
+                    None => {
+                        if !balance.total.is_zero() && !unpriced_assets.contains(&balance.asset) {
+                            unpriced_assets.push(balance.asset.clone());
+                        }
+                        Decimal::ZERO
+                    }
+                };
+
+                breakdown.entry(balance.asset.clone()).or_default().push(ExchangeBalanceDto {
+                    exchange: exchange.clone(),
+                    total: balance.total,
+                    usd_value,
+                });
+                portfolio.add_balance(balance.asset, balance.free, balance.locked, usd_value);
             }
+            succeeded.push(exchange);
         }
-        
-        console_log!("TRADING SERVICE: Initialized {} trading clients", self.clients.len());
-    }
 
-    /// Get market quote for an instrument
-    pub async fn get_quote(&self, request: GetQuoteRequest) -> Result<GetQuoteResponse, TradingErrorResponse> {
-        console_log!("TRADING SERVICE: Getting quote for {} on {}", request.symbol, request.exchange);
-        
-        let client = self.get_client(&request.exchange)?;
-        let instrument = self.parse_instrument(&request.symbol, &request.exchange)?;
-        
-        match client.get_quote(&instrument).await {
-            Ok(quote) => {
-                console_log!("TRADING SERVICE: Successfully retrieved quote for {}", request.symbol);
-                Ok(self.convert_quote_to_response(quote, &request.exchange))
-            }
-            Err(e) => {
-                console_log!("TRADING SERVICE: Failed to get quote: {}", e);
-                Err(TradingErrorResponse::new(format!("Failed to get quote: {}", e)))
-            }
+        if succeeded.is_empty() && !exchange_errors.is_empty() {
+            return Err(Self::all_exchanges_failed("Failed to get balances from every exchange", &exchange_errors));
         }
+
+        Ok(Self::convert_aggregate_portfolio_to_response(portfolio, breakdown, succeeded, exchange_errors, unpriced_assets))
     }
 
-    /// Get order book data for an instrument
-    pub async fn get_order_book(&self, request: GetOrderBookRequest) -> Result<GetOrderBookResponse, TradingErrorResponse> {
-        console_log!("TRADING SERVICE: Getting order book for {} on {}", request.symbol, request.exchange);
-        
-        let client = self.get_client(&request.exchange)?;
-        let instrument = self.parse_instrument(&request.symbol, &request.exchange)?;
-        let depth = request.depth.unwrap_or(20);
-        
-        match client.get_order_book(&instrument, depth).await {
-            Ok(order_book) => {
-                console_log!("TRADING SERVICE: Successfully retrieved order book for {}", request.symbol);
-                Ok(self.convert_order_book_to_response(order_book, &request.exchange))
+    /// The user's own balances on one exchange, and their USD prices
+    async fn value_account_balances(&self, user_id: &str, exchange: &str) -> Result<ValuedBalances, TradingErrorResponse> {
+        let client = self.get_client(exchange)?;
+        self.sync_user_fills(user_id, exchange).await;
+        let executions = self.trade_repository.find_executions(user_id, exchange).await
+            .map_err(|e| TradingErrorResponse::new(format!("Failed to load trade executions: {}", e)))?;
+        let balances = self.account_balances(user_id, exchange, &executions).await?;
+
+        let prices = self.price_balances(client, &balances).await;
+        Ok((balances, prices))
+    }
+
+    /// Replay executions through the lot ledger, converting each at the USD rates
+    /// when it executed, and value the open positions at current prices
+    async fn summarize_pnl(
+        &self,
+        client: &TradingClient,
+        executions: &[TradeExecution],
+        method: CostBasisMethod,
+        mut session: TradingSession,
+    ) -> PnlSummary {
+        let mut assets: Vec<String> = Vec::new();
+        for execution in executions {
+            for asset in [&execution.instrument.base_asset, &execution.instrument.quote_asset, &execution.commission_asset] {
+                if !assets.contains(asset) {
+                    assets.push(asset.clone());
+                }
             }
-            Err(e) => {
-                console_log!("TRADING SERVICE: Failed to get order book: {}", e);
-                Err(TradingErrorResponse::new(format!("Failed to get order book: {}", e)))
+        }
+        let prices = self.pricing_service.price_assets(client, &assets).await;
+
+        let mut ledger = LotLedger::new(method);
+        let mut session_fees_usd = Decimal::ZERO;
+        for execution in executions {
+            let Some(quote_usd_rate) = self.usd_rate_at(client, &execution.instrument.quote_asset, execution, &prices).await else {
+                console_log!("TRADING SERVICE: Skipping execution {}: no USD price for {}",
+                    execution.exchange_trade_id, execution.instrument.quote_asset);
+                continue;
+            };
+
+            let fee_rate = if execution.commission_asset == execution.instrument.quote_asset {
+                quote_usd_rate
+            } else if execution.commission_asset == execution.instrument.base_asset {
+                execution.price * quote_usd_rate
+            } else {
+                self.usd_rate_at(client, &execution.commission_asset, execution, &prices).await.unwrap_or(Decimal::ZERO)
+            };
+            let valuation = ExecutionValuation {
+                quote_usd_rate,
+                fee_usd: execution.commission * fee_rate,
+            };
+
+            let realized = ledger.apply(execution, valuation);
+            if session.covers(execution.executed_at) {
+                session.total_trades += 1;
+                session.total_volume += execution.quantity * execution.price * quote_usd_rate;
+                session.realized_pnl += realized;
+                session_fees_usd += valuation.fee_usd;
             }
         }
+
+        let mut asset_pnl: Vec<AssetPnlDto> = ledger.positions()
+            .map(|position| {
+                let price = prices.get(&position.asset).map(|price| price.usd_price);
+                let quantity = position.quantity();
+                let unrealized = price
+                    .map(|price| ledger.unrealized_pnl(&position.asset, price, None))
+                    .unwrap_or(Decimal::ZERO);
+                let total_pnl = position.realized_pnl + unrealized;
+
+                if let Some(price) = price {
+                    session.unrealized_pnl += ledger.unrealized_pnl(&position.asset, price, Some(session.started_at));
+                }
+
+                AssetPnlDto {
+                    asset: position.asset.clone(),
+                    quantity,
+                    average_cost_usd: position.average_cost().map(|cost| cost.round_dp(8)),
+                    cost_basis_usd: position.cost_basis().round_dp(2),
+                    total_cost_usd: position.total_cost.round_dp(2),
+                    market_value_usd: price.map(|price| (quantity * price).round_dp(2)).unwrap_or(Decimal::ZERO),
+                    realized_pnl_usd: position.realized_pnl.round_dp(2),
+                    unrealized_pnl_usd: unrealized.round_dp(2),
+                    pnl_percentage: Self::percentage(total_pnl, position.total_cost),
+                    fees_usd: position.fees_usd.round_dp(2),
+                    fees: position.fees.clone(),
+                    unmatched_quantity: position.unmatched_quantity,
+                }
+            })
+            .collect();
+        asset_pnl.sort_by(|a, b| a.asset.cmp(&b.asset));
+
+        PnlSummary {
+            method: ledger.method(),
+            assets: asset_pnl,
+            session: SessionPnlDto {
+                session_id: session.id,
+                started_at: session.started_at,
+                total_trades: session.total_trades,
+                total_volume_usd: session.total_volume.round_dp(2),
+                realized_pnl_usd: session.realized_pnl.round_dp(2),
+                unrealized_pnl_usd: session.unrealized_pnl.round_dp(2),
+                fees_usd: session_fees_usd.round_dp(2),
+            },
+        }
     }
 
-    /// Place a trading order
-    pub async fn place_order(&self, request: PlaceOrderRequest) -> Result<PlaceOrderResponse, TradingErrorResponse> {
-        console_log!("TRADING SERVICE: Placing {} order for {} {} on {}", 
-            request.side, request.quantity, request.symbol, request.exchange);
-        
-        let client = self.get_client(&request.exchange)?;
-        let order_request = self.convert_place_order_request(request)?;
-        
-        match client.place_order(&order_request).await {
-            Ok(order_id) => {
-                console_log!("TRADING SERVICE: Successfully placed order with ID: {}", order_id);
-                Ok(PlaceOrderResponse {
-                    order_id: uuid::Uuid::new_v4().to_string(),
-                    exchange_order_id: order_id,
-                    symbol: order_request.instrument.base.clone() + &order_request.instrument.quote,
-                    side: format!("{:?}", order_request.side),
-                    order_type: format!("{:?}", order_request.order_type),
-                    status: "NEW".to_string(),
-                    quantity: order_request.quantity,
-                    price: order_request.price,
-                    filled_quantity: Decimal::ZERO,
-                    created_at: Utc::now(),
-                })
-            }
-            Err(e) => {
-                console_log!("TRADING SERVICE: Failed to place order: {}", e);
-                Err(TradingErrorResponse::new(format!("Failed to place order: {}", e)))
-            }
+    /// USD rate of an asset when an execution happened, falling back to the
+    /// current price when the exchange has no history for it
+    async fn usd_rate_at(
+        &self,
+        client: &TradingClient,
+        asset: &str,
+        execution: &TradeExecution,
+        prices: &HashMap<String, AssetPrice>,
+    ) -> Option<Decimal> {
+        if let Some(rate) = self.pricing_service.price_usd_at(client, asset, execution.executed_at).await {
+            return Some(rate);
+        }
+
+        let current = prices.get(asset).map(|price| price.usd_price);
+        if current.is_some() {
+            console_log!("TRADING SERVICE: Execution {} valued at the current {} price; no rate at {}",
+                execution.exchange_trade_id, asset, execution.executed_at);
         }
+        current
     }
 
-    /// Get account balances
-    pub async fn get_balances(&self, request: GetBalancesRequest) -> Result<GetBalancesResponse, TradingErrorResponse> {
-        console_log!("TRADING SERVICE: Getting balances for {}", request.exchange);
-        
-        let client = self.get_client(&request.exchange)?;
-        
-        match client.get_balances().await {
-            Ok(balances) => {
-                console_log!("TRADING SERVICE: Successfully retrieved {} balances", balances.len());
-                Ok(self.convert_balances_to_response(balances, &request.exchange))
-            }
-            Err(e) => {
-                console_log!("TRADING SERVICE: Failed to get balances: {}", e);
-                Err(TradingErrorResponse::new(format!("Failed to get balances: {}", e)))
-            }
+    /// Percentage of a base amount, rounded for display; zero when there is no base
+    fn percentage(amount: Decimal, base: Decimal) -> Decimal {
+        if base > Decimal::ZERO {
+            (amount / base * Decimal::ONE_HUNDRED).round_dp(2)
+        } else {
+            Decimal::ZERO
         }
     }
 
@@ -178,6 +2082,10 @@ impl TradingService {
         let connected = true; // In production, this would test the connection
         
         console_log!("TRADING SERVICE: Trading status - Connected: {}, API Key Valid: {}", connected, api_key_valid);
+
+        let rate_limits = client.rate_limit_usage().await.into_iter()
+            .map(convert_limit_usage_to_dto)
+            .collect();
         
         Ok(GetTradingStatusResponse {
             exchange: request.exchange,
@@ -185,15 +2093,71 @@ impl TradingService {
             connected,
             api_key_valid,
             permissions: vec!["SPOT".to_string(), "MARGIN".to_string()],
-            rate_limits: vec![], // Would be populated from exchange info
+            rate_limits,
             server_time: Utc::now(),
         })
     }
 
+    /// Health of each exchange, from the state of its circuit breaker.
+    ///
+    /// "unhealthy" when every exchange's circuit is open, "degraded" when any
+    /// is open or half-open, "healthy" otherwise.
+    pub async fn get_health(&self) -> TradingHealthResponse {
+        let names = self.exchange_names();
+        let statuses = join_all(names.iter().map(|name| self.clients[name].circuit_status())).await;
+
+        let exchanges: Vec<ExchangeHealthDto> = names.into_iter().zip(statuses)
+            .map(|(exchange, status)| convert_circuit_status_to_dto(exchange, status))
+            .collect();
+        let open = exchanges.iter().filter(|exchange| exchange.circuit_state == CircuitState::Open.as_str()).count();
+        let healthy = exchanges.iter().filter(|exchange| exchange.status == "healthy").count();
+        let status = if !exchanges.is_empty() && open == exchanges.len() {
+            "unhealthy"
+        } else if healthy < exchanges.len() {
+            "degraded"
+        } else {
+            "healthy"
+        };
+
+        TradingHealthResponse {
+            status: status.to_string(),
+            service: "trading".to_string(),
+            barter_integration: "active".to_string(),
+            supported_exchanges: exchanges.iter().map(|exchange| exchange.exchange.clone()).collect(),
+            exchanges,
+            timestamp: Utc::now(),
+            version: "1.0.0".to_string(),
+        }
+    }
+
+    /// Exchanges named in a multi-exchange request, defaulting to every configured one
+    fn requested_exchanges(&self, exchanges: Option<Vec<String>>) -> Vec<String> {
+        let mut exchanges: Vec<String> = match exchanges {
+            Some(exchanges) if !exchanges.is_empty() => exchanges.iter().map(|exchange| exchange.to_lowercase()).collect(),
+            _ => self.exchange_names(),
+        };
+        exchanges.sort();
+        exchanges.dedup();
+        exchanges
+    }
+
+    /// Error for a multi-exchange request where no exchange succeeded
+    fn all_exchanges_failed(message: &str, exchange_errors: &[ExchangeErrorDto]) -> TradingErrorResponse {
+        let mut error = TradingErrorResponse::with_code(message.to_string(), "ALL_EXCHANGES_FAILED".to_string());
+        error.details = Some(exchange_errors.iter()
+            .map(|e| format!("{}: {}", e.exchange, e.error))
+            .collect::<Vec<_>>()
+            .join("; "));
+        error
+    }
+
     /// Get trading client for a specific exchange
     fn get_client(&self, exchange: &str) -> Result<&TradingClient, TradingErrorResponse> {
         self.clients.get(exchange)
-            .ok_or_else(|| TradingErrorResponse::new(format!("Unsupported exchange: {}", exchange)))
+            .ok_or_else(|| TradingErrorResponse::with_code(
+                format!("Unsupported exchange: {}", exchange),
+                "UNSUPPORTED_EXCHANGE".to_string(),
+            ))
     }
 
     /// Parse instrument symbol into SimpleInstrument
@@ -208,7 +2172,7 @@ impl TradingService {
     }
 
     /// Parse trading symbol into base and quote assets
-    fn parse_symbol(&self, symbol: &str) -> Result<(String, String), TradingErrorResponse> {
+    pub fn parse_symbol(&self, symbol: &str) -> Result<(String, String), TradingErrorResponse> {
         // Common quote currencies to try
         let quote_currencies = ["USDT", "USDC", "BTC", "ETH", "BNB", "USD", "EUR"];
         
@@ -221,7 +2185,7 @@ impl TradingService {
             }
         }
         
-        Err(TradingErrorResponse::new(format!("Unable to parse symbol: {}", symbol)))
+        Err(TradingErrorResponse::with_code(format!("Unable to parse symbol: {}", symbol), "INVALID_SYMBOL".to_string()))
     }
 
     /// Convert barter-rs Quote to response DTO
@@ -271,42 +2235,348 @@ impl TradingService {
         }
     }
 
-    /// Convert place order request to barter-rs OrderRequest
-    fn convert_place_order_request(&self, request: PlaceOrderRequest) -> Result<OrderRequest, TradingErrorResponse> {
-        let instrument = self.parse_instrument(&request.symbol, &request.exchange)?;
-        
-        let side = match request.side.to_uppercase().as_str() {
-            "BUY" => Side::Buy,
-            "SELL" => Side::Sell,
-            _ => return Err(TradingErrorResponse::new(format!("Invalid order side: {}", request.side))),
+    /// Convert barter-rs Quote to a market quote entity
+    fn convert_quote_to_entity(&self, quote: Quote, exchange: &str) -> MarketQuote {
+        let instrument = TradingInstrument::new(
+            format!("{}{}", quote.instrument.base, quote.instrument.quote),
+            quote.instrument.base,
+            quote.instrument.quote,
+            exchange.to_string(),
+            InstrumentType::Spot,
+        );
+        let mut market_quote = MarketQuote::new(instrument, quote.bid, quote.ask);
+        market_quote.timestamp = quote.timestamp;
+        market_quote
+    }
+
+    /// Convert barter-rs OrderBook to an order book entity
+    fn convert_order_book_to_entity(&self, order_book: OrderBook, symbol: &str, exchange: &str) -> TradingOrderBook {
+        let instrument = TradingInstrument::new(
+            symbol.to_string(),
+            order_book.instrument.base,
+            order_book.instrument.quote,
+            exchange.to_string(),
+            InstrumentType::Spot,
+        );
+        let levels = |levels: Vec<crate::clients::trading::OrderBookLevel>| levels.into_iter()
+            .map(|level| EntityOrderBookLevel { price: level.price, quantity: level.quantity })
+            .collect();
+        TradingOrderBook::new(instrument, levels(order_book.bids), levels(order_book.asks), order_book.timestamp)
+    }
+
+    fn convert_consolidated_level_to_dto(&self, level: &ConsolidatedLevel) -> ConsolidatedLevelDto {
+        ConsolidatedLevelDto {
+            exchange: level.exchange.clone(),
+            price: level.price,
+            effective_price: level.effective_price.round_dp(8),
+            quantity: level.quantity,
+        }
+    }
+
+    fn convert_fill_estimate_to_dto(&self, estimate: FillEstimate) -> FillEstimateDto {
+        FillEstimateDto {
+            filled_quantity: estimate.filled_quantity,
+            fully_filled: estimate.filled_quantity >= estimate.requested_quantity,
+            average_price: estimate.average_price.round_dp(8),
+            effective_average_price: estimate.effective_average_price.round_dp(8),
+            best_price: estimate.best_effective_price.round_dp(8),
+            slippage_bps: estimate.slippage_bps.round_dp(2),
+            total_cost: estimate.total_cost.round_dp(8),
+            allocations: estimate.allocations.into_iter()
+                .map(|(exchange, quantity)| FillAllocationDto { exchange, quantity })
+                .collect(),
+        }
+    }
+
+    /// Convert an order entity to a barter-rs OrderRequest
+    fn convert_order_to_request(&self, order: &TradingOrder) -> OrderRequest {
+        let side = match order.side {
+            OrderSide::Buy => Side::Buy,
+            OrderSide::Sell => Side::Sell,
         };
-        
-        let order_type = match request.order_type.to_uppercase().as_str() {
-            "MARKET" => OrderType::Market,
-            "LIMIT" => OrderType::Limit,
-            "STOP_LOSS" => OrderType::StopLoss,
-            "TAKE_PROFIT" => OrderType::TakeProfit,
-            _ => return Err(TradingErrorResponse::new(format!("Invalid order type: {}", request.order_type))),
+
+        let order_type = match order.order_type {
+            EntityOrderType::Market => OrderType::Market,
+            EntityOrderType::Limit => OrderType::Limit,
+            EntityOrderType::StopLoss => OrderType::StopLoss,
+            EntityOrderType::StopLossLimit => OrderType::StopLossLimit,
+            EntityOrderType::TakeProfit => OrderType::TakeProfit,
+            EntityOrderType::TakeProfitLimit => OrderType::TakeProfitLimit,
         };
-        
-        Ok(OrderRequest {
-            instrument,
+
+        let trigger_by = match order.trigger_by {
+            TriggerPriceType::LastPrice => TriggerBy::LastPrice,
+            TriggerPriceType::MarkPrice => TriggerBy::MarkPrice,
+        };
+
+        OrderRequest {
+            instrument: SimpleInstrument {
+                base: order.instrument.base_asset.clone(),
+                quote: order.instrument.quote_asset.clone(),
+            },
             side,
-            quantity: request.quantity,
-            price: request.price,
+            quantity: order.quantity,
+            price: order.price,
             order_type,
-        })
+            stop_price: order.stop_price,
+            trigger_by,
+            client_order_id: Some(order.id.clone()),
+        }
+    }
+
+    /// Build and validate the order entity for a place order request
+    fn build_trading_order(&self, user_id: &str, request: &PlaceOrderRequest) -> Result<TradingOrder, TradingErrorResponse> {
+        let instrument = self.build_instrument(&request.exchange, &request.symbol)?;
+
+        let side = OrderSide::parse(&request.side)
+            .ok_or_else(|| TradingErrorResponse::with_code(format!("Invalid order side: {}", request.side), "INVALID_SIDE".to_string()))?;
+        let order_type = EntityOrderType::parse(&request.order_type)
+            .ok_or_else(|| TradingErrorResponse::with_code(
+                format!("Invalid order type: {}", request.order_type),
+                "INVALID_ORDER_TYPE".to_string(),
+            ))?;
+
+        if order_type.requires_limit_price() && request.price.is_none() {
+            return Err(TradingErrorResponse::with_code(
+                format!("{} orders require a price", order_type.as_str()),
+                "MISSING_PRICE".to_string(),
+            ));
+        }
+
+        let mut order = TradingOrder::new(
+            user_id.to_string(),
+            instrument,
+            side,
+            order_type.clone(),
+            request.quantity,
+            request.price,
+        );
+        order.client_order_id = self.validate_client_order_id(request.client_order_id.as_deref())?;
+
+        if !order_type.is_trigger_order() {
+            return Ok(order);
+        }
+
+        let stop_price = request.stop_price.ok_or_else(|| TradingErrorResponse::with_code(
+            format!("{} orders require a stop_price", order_type.as_str()),
+            "MISSING_STOP_PRICE".to_string(),
+        ))?;
+        if stop_price <= Decimal::ZERO {
+            return Err(TradingErrorResponse::with_code(
+                "stop_price must be positive".to_string(),
+                "INVALID_STOP_PRICE".to_string(),
+            ));
+        }
+
+        let trigger_by = self.parse_trigger_by(request.trigger_by.as_deref())?;
+        Ok(order.with_trigger(stop_price, trigger_by))
+    }
+
+    /// Client order ids are opaque to us but must be short, printable keys
+    fn validate_client_order_id(&self, client_order_id: Option<&str>) -> Result<Option<String>, TradingErrorResponse> {
+        let Some(client_order_id) = client_order_id else { return Ok(None) };
+
+        let valid = !client_order_id.is_empty()
+            && client_order_id.len() <= 64
+            && client_order_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+        if !valid {
+            return Err(TradingErrorResponse::with_code(
+                "client_order_id must be 1-64 characters of letters, digits, '-', '_', '.' or ':'".to_string(),
+                "INVALID_CLIENT_ORDER_ID".to_string(),
+            ));
+        }
+
+        Ok(Some(client_order_id.to_string()))
+    }
+
+    /// Build the instrument entity for an exchange symbol
+    fn build_instrument(&self, exchange: &str, symbol: &str) -> Result<TradingInstrument, TradingErrorResponse> {
+        let (base, quote) = self.parse_symbol(symbol)?;
+
+        Ok(TradingInstrument::new(
+            symbol.to_string(),
+            base,
+            quote,
+            exchange.to_string(),
+            InstrumentType::Spot,
+        ))
+    }
+
+    /// Build the take-profit and stop-loss legs of a group
+    fn build_exit_legs(
+        &self,
+        group: &OrderGroup,
+        side: OrderSide,
+        take_profit_price: Decimal,
+        stop_loss_price: Decimal,
+        stop_limit_price: Option<Decimal>,
+        trigger_by: TriggerPriceType,
+    ) -> Result<Vec<TradingOrder>, TradingErrorResponse> {
+        let instrument = self.build_instrument(&group.exchange, &group.symbol)?;
+
+        let mut take_profit = TradingOrder::new(
+            group.user_id.clone(),
+            instrument.clone(),
+            side.clone(),
+            EntityOrderType::Limit,
+            group.quantity,
+            Some(take_profit_price),
+        );
+        take_profit.group_id = Some(group.id.clone());
+
+        let stop_type = if stop_limit_price.is_some() { EntityOrderType::StopLossLimit } else { EntityOrderType::StopLoss };
+        let mut stop_loss = TradingOrder::new(
+            group.user_id.clone(),
+            instrument,
+            side,
+            stop_type,
+            group.quantity,
+            stop_limit_price,
+        ).with_trigger(stop_loss_price, trigger_by);
+        stop_loss.group_id = Some(group.id.clone());
+
+        Ok(vec![take_profit, stop_loss])
+    }
+
+    /// Group orders need a positive size to split between their legs
+    fn validate_group_quantity(&self, quantity: Decimal) -> Result<(), TradingErrorResponse> {
+        if quantity <= Decimal::ZERO {
+            return Err(TradingErrorResponse::with_code(
+                "quantity must be positive".to_string(),
+                "INVALID_QUANTITY".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the take-profit sits on the profitable side of the stop-loss (and
+    /// of the entry price, when there is one) for the given exit side
+    fn validate_exit_prices(
+        &self,
+        exit_side: &OrderSide,
+        take_profit_price: Decimal,
+        stop_loss_price: Decimal,
+        entry_price: Option<Decimal>,
+    ) -> Result<(), TradingErrorResponse> {
+        if take_profit_price <= Decimal::ZERO || stop_loss_price <= Decimal::ZERO {
+            return Err(TradingErrorResponse::with_code(
+                "take_profit_price and stop_loss_price must be positive".to_string(),
+                "INVALID_EXIT_PRICES".to_string(),
+            ));
+        }
+
+        // Selling closes a long: profit above, stop below. Buying closes a short.
+        let (low, high) = match exit_side {
+            OrderSide::Sell => (stop_loss_price, take_profit_price),
+            OrderSide::Buy => (take_profit_price, stop_loss_price),
+        };
+        let ordered = match entry_price {
+            Some(entry) => low < entry && entry < high,
+            None => low < high,
+        };
+
+        if !ordered {
+            return Err(TradingErrorResponse::with_code(
+                format!("{} exits need stop_loss_price {} take_profit_price{}",
+                    exit_side.as_str(),
+                    if matches!(exit_side, OrderSide::Sell) { "<" } else { ">" },
+                    if entry_price.is_some() { ", with the entry price between them" } else { "" }),
+                "INVALID_EXIT_PRICES".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parse the trigger price source, defaulting to last price
+    fn parse_trigger_by(&self, trigger_by: Option<&str>) -> Result<TriggerPriceType, TradingErrorResponse> {
+        match trigger_by {
+            None => Ok(TriggerPriceType::LastPrice),
+            Some(value) => TriggerPriceType::parse(value)
+                .ok_or_else(|| TradingErrorResponse::with_code(format!("Invalid trigger_by: {}", value), "INVALID_TRIGGER_BY".to_string())),
+        }
+    }
+
+    /// Convert an order entity to a place order response
+    fn convert_order_to_response(&self, order: &TradingOrder, trigger_mode: &str) -> PlaceOrderResponse {
+        PlaceOrderResponse {
+            order_id: order.id.clone(),
+            client_order_id: order.client_order_id.clone(),
+            exchange_order_id: order.exchange_order_id.clone().unwrap_or_default(),
+            symbol: order.instrument.symbol.clone(),
+            side: order.side.as_str().to_string(),
+            order_type: order.order_type.as_str().to_string(),
+            status: order.status.as_str().to_string(),
+            quantity: order.quantity,
+            price: order.price,
+            stop_price: order.stop_price,
+            trigger_by: order.stop_price.map(|_| order.trigger_by.as_str().to_string()),
+            trigger_mode: trigger_mode.to_string(),
+            filled_quantity: order.filled_quantity,
+            created_at: order.created_at,
+        }
+    }
+
+    /// Convert an order entity to an order DTO
+    fn convert_order_to_dto(&self, order: &TradingOrder) -> OrderDto {
+        OrderDto {
+            order_id: order.id.clone(),
+            client_order_id: order.client_order_id.clone(),
+            exchange_order_id: order.exchange_order_id.clone(),
+            symbol: order.instrument.symbol.clone(),
+            side: order.side.as_str().to_string(),
+            order_type: order.order_type.as_str().to_string(),
+            status: order.status.as_str().to_string(),
+            quantity: order.quantity,
+            price: order.price,
+            stop_price: order.stop_price,
+            filled_quantity: order.filled_quantity,
+            average_price: order.average_price,
+            commission: order.commission,
+            commission_asset: order.commission_asset.clone(),
+            group_id: order.group_id.clone(),
+            parent_order_id: order.parent_order_id.clone(),
+            created_at: order.created_at,
+            updated_at: order.updated_at,
+        }
+    }
+
+    /// Convert an order group and its orders to a response DTO
+    fn convert_group_to_response(&self, group: &OrderGroup, parent: Option<&TradingOrder>, legs: &[TradingOrder]) -> OrderGroupResponse {
+        OrderGroupResponse {
+            group_id: group.id.clone(),
+            group_type: group.group_type.as_str().to_string(),
+            status: group.status.as_str().to_string(),
+            exchange: group.exchange.clone(),
+            symbol: group.symbol.clone(),
+            quantity: group.quantity,
+            parent_order: parent.map(|order| self.convert_order_to_dto(order)),
+            legs: legs.iter().map(|leg| self.convert_order_to_dto(leg)).collect(),
+            created_at: group.created_at,
+            updated_at: group.updated_at,
+        }
+    }
+
+    /// Look up USD prices for every non-zero balance
+    async fn price_balances(&self, client: &TradingClient, balances: &[Balance]) -> HashMap<String, AssetPrice> {
+        let assets: Vec<String> = balances.iter()
+            .filter(|balance| !balance.total.is_zero())
+            .map(|balance| balance.asset.clone())
+            .collect();
+
+        self.pricing_service.price_assets(client, &assets).await
     }
 
     /// Convert barter-rs balances to response DTO
-    fn convert_balances_to_response(&self, balances: Vec<Balance>, exchange: &str) -> GetBalancesResponse {
+    fn convert_balances_to_response(&self, balances: Vec<Balance>, prices: &HashMap<String, AssetPrice>, exchange: &str) -> GetBalancesResponse {
         let balance_dtos: Vec<BalanceDto> = balances.into_iter()
             .map(|balance| BalanceDto {
+                usd_value: prices.get(&balance.asset)
+                    .map(|price| (balance.total * price.usd_price).round_dp(2))
+                    .unwrap_or(Decimal::ZERO),
                 asset: balance.asset,
                 free: balance.free,
                 locked: balance.locked,
                 total: balance.total,
-                usd_value: Decimal::ZERO, // Would be calculated using current prices
             })
             .collect();
 
@@ -320,6 +2590,107 @@ impl TradingService {
         }
     }
 
+    /// Convert an aggregated portfolio to response DTO, ranking balances by USD value
+    fn convert_aggregate_portfolio_to_response(
+        portfolio: Portfolio,
+        mut breakdown: HashMap<String, Vec<ExchangeBalanceDto>>,
+        exchanges: Vec<String>,
+        exchange_errors: Vec<ExchangeErrorDto>,
+        unpriced_assets: Vec<String>,
+    ) -> GetAggregatePortfolioResponse {
+        let total_value_usd = portfolio.total_value_usd;
+
+        let mut balances: Vec<AggregateBalanceDto> = portfolio.balances.into_iter()
+            .map(|balance| AggregateBalanceDto {
+                percentage: Self::percentage(balance.usd_value, total_value_usd),
+                exchanges: breakdown.remove(&balance.asset).unwrap_or_default(),
+                asset: balance.asset,
+                free: balance.free,
+                locked: balance.locked,
+                total: balance.total,
+                usd_value: balance.usd_value,
+            })
+            .collect();
+        balances.sort_by_key(|balance| std::cmp::Reverse(balance.usd_value));
+
+        GetAggregatePortfolioResponse {
+            exchanges,
+            total_value_usd,
+            balances,
+            exchange_errors,
+            unpriced_assets,
+            updated_at: portfolio.updated_at,
+        }
+    }
+
+    /// Convert an account transfer to its DTO
+    fn convert_transfer_to_dto(&self, transfer: AccountTransfer) -> TransferDto {
+        TransferDto {
+            transfer_id: transfer.id,
+            user_id: transfer.user_id,
+            exchange: transfer.exchange,
+            asset: transfer.asset,
+            kind: transfer.kind.as_str().to_string(),
+            amount: transfer.amount,
+            note: transfer.note,
+            occurred_at: transfer.occurred_at,
+            created_at: transfer.created_at,
+        }
+    }
+
+    /// Convert a valued portfolio to response DTO, ranking holdings by USD value
+    fn convert_portfolio_to_response(&self, portfolio: Portfolio, unpriced_assets: Vec<String>, pnl: PnlSummary) -> GetPortfolioResponse {
+        let total_value_usd = portfolio.total_value_usd;
+
+        let mut top_holdings: Vec<HoldingDto> = portfolio.balances.iter()
+            .filter(|balance| balance.usd_value > Decimal::ZERO)
+            .map(|balance| {
+                let asset_pnl = pnl.assets.iter().find(|asset| asset.asset == balance.asset);
+                HoldingDto {
+                    asset: balance.asset.clone(),
+                    quantity: balance.total,
+                    usd_value: balance.usd_value,
+                    percentage: Self::percentage(balance.usd_value, total_value_usd),
+                    pnl_usd: asset_pnl
+                        .map(|asset| asset.realized_pnl_usd + asset.unrealized_pnl_usd)
+                        .unwrap_or(Decimal::ZERO),
+                    pnl_percentage: asset_pnl.map(|asset| asset.pnl_percentage).unwrap_or(Decimal::ZERO),
+                }
+            })
+            .collect();
+        top_holdings.sort_by_key(|holding| std::cmp::Reverse(holding.usd_value));
+        top_holdings.truncate(TOP_HOLDINGS_LIMIT);
+
+        let balances = portfolio.balances.into_iter()
+            .map(|balance| BalanceDto {
+                asset: balance.asset,
+                free: balance.free,
+                locked: balance.locked,
+                total: balance.total,
+                usd_value: balance.usd_value,
+            })
+            .collect();
+
+        let total_pnl_usd: Decimal = pnl.assets.iter()
+            .map(|asset| asset.realized_pnl_usd + asset.unrealized_pnl_usd)
+            .sum();
+        let total_invested: Decimal = pnl.assets.iter().map(|asset| asset.total_cost_usd).sum();
+
+        GetPortfolioResponse {
+            exchange: portfolio.exchange,
+            total_value_usd,
+            total_pnl_usd,
+            total_pnl_percentage: Self::percentage(total_pnl_usd, total_invested),
+            cost_basis_method: pnl.method.as_str().to_string(),
+            balances,
+            top_holdings,
+            asset_pnl: pnl.assets,
+            session: pnl.session,
+            unpriced_assets,
+            updated_at: portfolio.updated_at,
+        }
+    }
+
     /// Get popular trading instruments for an exchange
     fn get_popular_instruments(&self, exchange: &str) -> Vec<InstrumentDto> {
         console_log!("TRADING SERVICE: Getting popular instruments for {}", exchange);
@@ -375,8 +2746,101 @@ impl TradingService {
     }
 }
 
-impl Default for TradingService {
-    fn default() -> Self {
-        Self::new()
+/// Convert an exchange limit and its usage in the current window to a DTO
+pub fn convert_limit_usage_to_dto(usage: LimitUsage) -> RateLimitDto {
+    RateLimitDto {
+        rate_limit_type: usage.limit.rate_limit_type.as_str().to_string(),
+        interval: usage.limit.interval.as_str().to_string(),
+        interval_num: usage.limit.interval_num,
+        limit: usage.limit.limit,
+        count: usage.count,
+    }
+}
+
+/// Convert an exchange's circuit breaker state to a health DTO. Exchanges
+/// whose breaker isn't tracked are reported healthy with an "UNKNOWN" circuit.
+pub fn convert_circuit_status_to_dto(exchange: String, status: Option<CircuitStatus>) -> ExchangeHealthDto {
+    let Some(status) = status else {
+        return ExchangeHealthDto {
+            exchange,
+            status: "healthy".to_string(),
+            circuit_state: "UNKNOWN".to_string(),
+            consecutive_failures: 0,
+            last_failure: None,
+            opened_at: None,
+            retry_after_seconds: None,
+        };
+    };
+    let health = match status.state {
+        CircuitState::Closed => "healthy",
+        CircuitState::HalfOpen => "recovering",
+        CircuitState::Open => "unavailable",
+    };
+    ExchangeHealthDto {
+        exchange,
+        status: health.to_string(),
+        circuit_state: status.state.as_str().to_string(),
+        consecutive_failures: status.consecutive_failures,
+        last_failure: status.last_failure,
+        opened_at: status.opened_at,
+        retry_after_seconds: (status.state == CircuitState::Open).then_some(status.retry_after_seconds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn balance(asset: &str, total: Decimal) -> Balance {
+        Balance { asset: asset.to_string(), free: total, locked: Decimal::ZERO, total }
+    }
+
+    fn price(asset: &str, usd_price: Decimal) -> (String, AssetPrice) {
+        (asset.to_string(), AssetPrice {
+            asset: asset.to_string(),
+            usd_price,
+            path: vec![format!("{}/USDT", asset)],
+            priced_at: Utc::now(),
+        })
+    }
+
+    #[test]
+    fn aggregate_portfolio_keeps_the_exchanges_that_answered() {
+        let valued = vec![
+            ("binance".to_string(), Ok((
+                vec![balance("BTC", dec!(0.5)), balance("USDT", dec!(1000))],
+                HashMap::from([price("BTC", dec!(60000)), price("USDT", dec!(1))]),
+            ))),
+            ("kraken".to_string(), Err(TradingErrorResponse::new("Failed to get balances: timeout".to_string()))),
+            ("coinbase".to_string(), Ok((
+                vec![balance("BTC", dec!(0.25)), balance("DOGE", dec!(100))],
+                HashMap::from([price("BTC", dec!(60000))]),
+            ))),
+        ];
+
+        let response = TradingService::aggregate_portfolio("alice", valued).unwrap();
+        assert_eq!(response.exchanges, vec!["binance", "coinbase"]);
+        assert_eq!(response.exchange_errors.len(), 1);
+        assert_eq!(response.exchange_errors[0].exchange, "kraken");
+        assert_eq!(response.exchange_errors[0].error, "Failed to get balances: timeout");
+        assert_eq!(response.total_value_usd, dec!(46000));
+        assert_eq!(response.unpriced_assets, vec!["DOGE"]);
+
+        let btc = &response.balances[0];
+        assert_eq!((btc.asset.as_str(), btc.total, btc.usd_value), ("BTC", dec!(0.75), dec!(45000)));
+        assert_eq!(btc.exchanges.iter().map(|e| e.exchange.as_str()).collect::<Vec<_>>(), vec!["binance", "coinbase"]);
+    }
+
+    #[test]
+    fn aggregate_portfolio_fails_when_no_exchange_answers() {
+        let valued = vec![
+            ("binance".to_string(), Err(TradingErrorResponse::new("down".to_string()))),
+            ("kraken".to_string(), Err(TradingErrorResponse::new("timeout".to_string()))),
+        ];
+
+        let error = TradingService::aggregate_portfolio("alice", valued).unwrap_err();
+        assert_eq!(error.error_code.as_deref(), Some("ALL_EXCHANGES_FAILED"));
+        assert_eq!(error.details.as_deref(), Some("binance: down; kraken: timeout"));
     }
 }
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use worker::{console_log, ObjectNamespace, Url};
+use rust_decimal::Decimal;
+
+use crate::entity::market_data::{Instrument, InstrumentKind};
+use crate::entity::trading::{OrderStatus, TradingInstrument, TradingOrder, TriggerPriceType};
+use crate::repo::order::OrderRepository;
+
+/// Latest reference prices for an instrument, fed in from market data
+#[derive(Debug, Clone)]
+pub struct PriceUpdate {
+    pub exchange: String,
+    pub symbol: String,
+    pub last_price: Decimal,
+    pub mark_price: Option<Decimal>,
+}
+
+/// Server-side monitor for stop-loss and take-profit orders the exchange
+/// cannot hold natively.
+///
+/// Pending orders live only in the database and are read afresh for every
+/// evaluation, so a cancellation made by another isolate is always seen.
+/// An order whose trigger price has been crossed is claimed by moving it from
+/// PendingTrigger to PendingSubmit in a single conditional update; only the
+/// evaluation whose update changed the row hands it back to be submitted.
+///
+/// Prices come from the instrument's `MarketStream` object, which checks its
+/// pending orders against every trade. `watch` asks that object to start;
+/// the scheduled evaluation with REST prices is the backstop for objects
+/// that are evicted or unbound and for mark-price orders, which the spot
+/// trade feed cannot fire.
+#[derive(Clone)]
+pub struct TriggerMonitor {
+    order_repository: OrderRepository,
+    market_streams: Option<ObjectNamespace>,
+}
+
+impl TriggerMonitor {
+    pub fn new(order_repository: OrderRepository) -> Self {
+        Self { order_repository, market_streams: None }
+    }
+
+    /// Have the instrument's `MarketStream` objects watch pending orders
+    pub fn with_market_streams(mut self, namespace: Option<ObjectNamespace>) -> Self {
+        self.market_streams = namespace;
+        self
+    }
+
+    /// Start monitoring a trigger order.
+    ///
+    /// The order is marked PendingTrigger; persisting it is left to the caller,
+    /// which knows whether the row is new or already exists.
+    pub fn register(&self, mut order: TradingOrder) -> Result<TradingOrder, String> {
+        console_log!("TRIGGER MONITOR: Registering {} order {} at stop {:?} ({})",
+            order.order_type.as_str(), order.id, order.stop_price, order.trigger_by.as_str());
+
+        if order.stop_price.is_none() {
+            return Err("Trigger orders require a stop price".to_string());
+        }
+
+        order.status = OrderStatus::PendingTrigger;
+        Ok(order)
+    }
+
+    /// Every pending order, grouped by "exchange:symbol"
+    pub async fn pending_by_instrument(&self) -> Result<BTreeMap<String, Vec<TradingOrder>>, String> {
+        let orders = self.order_repository.find_pending_triggers().await?;
+        console_log!("TRIGGER MONITOR: Loaded {} pending trigger order(s)", orders.len());
+
+        let mut pending: BTreeMap<String, Vec<TradingOrder>> = BTreeMap::new();
+        for order in orders {
+            pending
+                .entry(Self::key(&order.instrument.exchange, &order.instrument.symbol))
+                .or_default()
+                .push(order);
+        }
+        Ok(pending)
+    }
+
+    /// The pending orders of one instrument
+    pub async fn pending_for(&self, exchange: &str, base_asset: &str, quote_asset: &str) -> Result<Vec<TradingOrder>, String> {
+        self.order_repository.find_pending_triggers_for(exchange, base_asset, quote_asset).await
+    }
+
+    /// Ask the instrument's `MarketStream` object to load its pending orders
+    /// and keep its feed open while any are left. Failures are only logged:
+    /// the scheduled evaluation still covers the orders.
+    pub async fn watch(&self, instrument: &TradingInstrument) {
+        let Some(namespace) = &self.market_streams else { return };
+        let stream = Instrument::new(
+            instrument.base_asset.clone(),
+            instrument.quote_asset.clone(),
+            instrument.exchange.clone(),
+            InstrumentKind::Spot,
+        );
+
+        let result = async {
+            let mut url = Url::parse("https://market-stream/watch")?;
+            url.query_pairs_mut()
+                .append_pair("exchange", &stream.exchange)
+                .append_pair("base", &stream.base)
+                .append_pair("quote", &stream.quote);
+            let stub = namespace.id_from_name(&stream.id)?.get_stub()?;
+            let mut response = stub.fetch_with_str(url.as_str()).await?;
+            if response.status_code() == 200 {
+                Ok(())
+            } else {
+                Err(worker::Error::from(response.text().await.unwrap_or_default()))
+            }
+        }.await;
+
+        if let Err(e) = result {
+            console_log!("TRIGGER MONITOR: Market stream of {} is not watching its orders: {}", stream.id, e);
+        }
+    }
+
+    /// Check a price update against an instrument's pending orders.
+    ///
+    /// Returns the orders whose trigger fired and that this call claimed; they
+    /// are stored as PendingSubmit and must be submitted by the caller.
+    pub async fn evaluate(&self, update: &PriceUpdate, pending: Vec<TradingOrder>) -> Result<Vec<TradingOrder>, String> {
+        let mut claimed = Vec::new();
+        for mut order in triggered_orders(pending, update) {
+            if self.order_repository
+                .transition_status(&order.id, &OrderStatus::PendingTrigger, &OrderStatus::PendingSubmit)
+                .await?
+            {
+                order.status = OrderStatus::PendingSubmit;
+                claimed.push(order);
+            } else {
+                console_log!("TRIGGER MONITOR: Order {} was cancelled or fired elsewhere", order.id);
+            }
+        }
+
+        if !claimed.is_empty() {
+            console_log!("TRIGGER MONITOR: {} order(s) triggered for {} (last: {}, mark: {:?})",
+                claimed.len(), Self::key(&update.exchange, &update.symbol), update.last_price, update.mark_price);
+        }
+
+        Ok(claimed)
+    }
+
+    /// Put an order claimed by `evaluate` back to wait for its trigger, when it
+    /// can't be submitted yet. Returns whether this call moved it.
+    pub async fn rearm(&self, order_id: &str) -> Result<bool, String> {
+        self.order_repository
+            .transition_status(order_id, &OrderStatus::PendingSubmit, &OrderStatus::PendingTrigger)
+            .await
+    }
+
+    /// Stop monitoring an order by cancelling it, unless it has already fired.
+    /// Returns whether this call cancelled it.
+    pub async fn withdraw(&self, order_id: &str) -> Result<bool, String> {
+        let withdrawn = self.order_repository
+            .transition_status(order_id, &OrderStatus::PendingTrigger, &OrderStatus::Cancelled)
+            .await?;
+
+        if withdrawn {
+            console_log!("TRIGGER MONITOR: Withdrew order {}", order_id);
+        }
+        Ok(withdrawn)
+    }
+
+    fn key(exchange: &str, symbol: &str) -> String {
+        format!("{}:{}", exchange.to_lowercase(), symbol.to_uppercase())
+    }
+}
+
+/// Whether any of the orders triggers on mark price
+pub fn needs_mark_price(orders: &[TradingOrder]) -> bool {
+    orders.iter().any(|order| order.trigger_by == TriggerPriceType::MarkPrice)
+}
+
+/// Whether the update crosses the trigger price of any of the orders
+pub fn any_triggered(orders: &[TradingOrder], update: &PriceUpdate) -> bool {
+    orders.iter().any(|order| fires_on(order, update))
+}
+
+/// The orders whose trigger price the update has crossed. Mark-price orders
+/// are left waiting when the update has no mark price.
+fn triggered_orders(orders: Vec<TradingOrder>, update: &PriceUpdate) -> Vec<TradingOrder> {
+    orders
+        .into_iter()
+        .filter(|order| fires_on(order, update))
+        .collect()
+}
+
+fn fires_on(order: &TradingOrder, update: &PriceUpdate) -> bool {
+    let reference_price = match order.trigger_by {
+        TriggerPriceType::LastPrice => Some(update.last_price),
+        TriggerPriceType::MarkPrice => update.mark_price,
+    };
+    reference_price.is_some_and(|price| order.is_triggered_by(price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    use crate::entity::trading::{InstrumentType, OrderSide, OrderType, TradingInstrument};
+
+    fn stop(side: OrderSide, order_type: OrderType, stop_price: Decimal, trigger_by: TriggerPriceType) -> TradingOrder {
+        let instrument = TradingInstrument::new(
+            "BTCUSDT".to_string(), "BTC".to_string(), "USDT".to_string(), "kraken".to_string(), InstrumentType::Spot,
+        );
+        let mut order = TradingOrder::new("user".to_string(), instrument, side, order_type, dec!(1), None);
+        order.stop_price = Some(stop_price);
+        order.trigger_by = trigger_by;
+        order
+    }
+
+    fn update(last_price: Decimal, mark_price: Option<Decimal>) -> PriceUpdate {
+        PriceUpdate { exchange: "kraken".to_string(), symbol: "BTCUSDT".to_string(), last_price, mark_price }
+    }
+
+    #[test]
+    fn fires_stops_and_take_profits_on_the_right_side() {
+        let orders = vec![
+            stop(OrderSide::Sell, OrderType::StopLoss, dec!(100), TriggerPriceType::LastPrice),
+            stop(OrderSide::Sell, OrderType::TakeProfit, dec!(120), TriggerPriceType::LastPrice),
+            stop(OrderSide::Buy, OrderType::StopLoss, dec!(110), TriggerPriceType::LastPrice),
+        ];
+        let ids: Vec<String> = orders.iter().map(|order| order.id.clone()).collect();
+
+        let fired = triggered_orders(orders, &update(dec!(99), None));
+        assert_eq!(fired.iter().map(|order| &order.id).collect::<Vec<_>>(), vec![&ids[0]]);
+    }
+
+    #[test]
+    fn mark_price_orders_wait_for_a_mark_price() {
+        let orders = vec![stop(OrderSide::Sell, OrderType::StopLoss, dec!(100), TriggerPriceType::MarkPrice)];
+        assert!(needs_mark_price(&orders));
+        assert!(!any_triggered(&orders, &update(dec!(90), None)));
+        assert!(triggered_orders(orders.clone(), &update(dec!(90), None)).is_empty());
+        assert!(triggered_orders(orders.clone(), &update(dec!(90), Some(dec!(101)))).is_empty());
+        assert_eq!(triggered_orders(orders, &update(dec!(101), Some(dec!(99)))).len(), 1);
+    }
+}
@@ -0,0 +1,354 @@
+use worker::{console_log, Queue};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::clients::webhook::{send_webhook, validate_webhook_url};
+use crate::dto::webhook::{
+    CreateWebhookRequest, GetDeliveriesRequest, GetDeliveriesResponse, ListWebhooksResponse, WebhookDeliveryDto,
+    WebhookEndpointDto,
+};
+use crate::entity::webhook::{DeliveryStatus, WebhookDelivery, WebhookEndpoint, WebhookEvent};
+use crate::repo::webhook::WebhookRepository;
+use crate::util::signing::generate_secret;
+
+/// Name of the queue binding deliveries are sent through
+pub const WEBHOOK_QUEUE_BINDING: &str = "WEBHOOK_DELIVERIES";
+
+/// Event name of deliveries sent by the test endpoint
+pub const TEST_EVENT: &str = "webhook.test";
+
+/// Attempts made at a delivery before it is marked failed
+const MAX_ATTEMPTS: i64 = 8;
+
+/// Wait before the first retry, doubled for each one after
+const BASE_RETRY_DELAY_SECONDS: u32 = 30;
+
+/// Longest wait between two attempts
+const MAX_RETRY_DELAY_SECONDS: u32 = 60 * 60;
+
+/// Most endpoints one user may register
+const MAX_ENDPOINTS_PER_USER: usize = 10;
+
+/// Deliveries returned when a request doesn't give a limit
+const DEFAULT_DELIVERIES_LIMIT: u32 = 50;
+
+/// Most deliveries a single request may return
+const MAX_DELIVERIES_LIMIT: u32 = 200;
+
+/// Message put on the delivery queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDeliveryMessage {
+    pub delivery_id: String,
+}
+
+/// What the queue consumer should do with a message after an attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryOutcome {
+    /// Delivered, given up on, or nothing left to do
+    Done,
+    /// Try again after this many seconds
+    RetryAfter(u32),
+}
+
+/// Users' webhook endpoints and the deliveries of events to them.
+///
+/// Order, balance and alert events become one delivery per subscribed endpoint,
+/// recorded in the delivery log and sent through the delivery queue. The
+/// queue consumer POSTs each one signed with the endpoint's secret, and
+/// retries failures with exponential backoff until one succeeds or the
+/// attempts run out. Without a queue binding, events are sent once inline.
+#[derive(Clone)]
+pub struct WebhookService {
+    webhook_repository: WebhookRepository,
+    queue: Option<Queue>,
+}
+
+impl WebhookService {
+    pub fn new(webhook_repository: WebhookRepository, queue: Option<Queue>) -> Self {
+        Self { webhook_repository, queue }
+    }
+
+    /// Validate and record a new endpoint, with a fresh signing secret
+    pub async fn create_endpoint(&self, user_id: &str, request: CreateWebhookRequest) -> Result<WebhookEndpointDto, String> {
+        console_log!("WEBHOOK SERVICE: Creating endpoint {} for {}", request.url, user_id);
+
+        validate_webhook_url(&request.url)?;
+        let events = match &request.events {
+            Some(names) if names.is_empty() => return Err("events must name at least one event".to_string()),
+            Some(names) => {
+                let mut events = Vec::new();
+                for name in names {
+                    let event = WebhookEvent::parse(name).ok_or_else(|| format!(
+                        "Unsupported event: {} (expected order.created, order.filled, order.cancelled, order.rejected, balance.changed or alert.triggered)",
+                        name,
+                    ))?;
+                    if !events.contains(&event) {
+                        events.push(event);
+                    }
+                }
+                events
+            }
+            None => WebhookEvent::ALL.to_vec(),
+        };
+
+        let existing = self.webhook_repository.find_user_endpoints(user_id).await?;
+        if existing.len() >= MAX_ENDPOINTS_PER_USER {
+            return Err(format!("At most {} webhooks may be registered", MAX_ENDPOINTS_PER_USER));
+        }
+
+        let now = Utc::now();
+        let endpoint = WebhookEndpoint {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            url: request.url,
+            secret: generate_secret(),
+            events,
+            description: request.description,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        };
+        self.webhook_repository.save_endpoint(&endpoint).await?;
+
+        Ok(convert_endpoint_to_dto(&endpoint))
+    }
+
+    pub async fn list_endpoints(&self, user_id: &str) -> Result<ListWebhooksResponse, String> {
+        let endpoints = self.webhook_repository.find_user_endpoints(user_id).await?;
+        Ok(ListWebhooksResponse {
+            webhooks: endpoints.iter().map(convert_endpoint_to_dto).collect(),
+        })
+    }
+
+    /// Delete one of the user's endpoints. Deliveries still queued for it
+    /// are marked failed when they come up.
+    pub async fn delete_endpoint(&self, user_id: &str, webhook_id: &str) -> Result<bool, String> {
+        if self.find_endpoint(user_id, webhook_id).await?.is_none() {
+            return Ok(false);
+        }
+        self.webhook_repository.delete_endpoint(webhook_id).await?;
+        Ok(true)
+    }
+
+    /// Send a test event to one of the user's endpoints right away, once,
+    /// and report how it went
+    pub async fn send_test(&self, user_id: &str, webhook_id: &str) -> Result<Option<WebhookDeliveryDto>, String> {
+        let Some(endpoint) = self.find_endpoint(user_id, webhook_id).await? else {
+            return Ok(None);
+        };
+        console_log!("WEBHOOK SERVICE: Sending test event to {}", endpoint.id);
+
+        let data = json!({
+            "webhook_id": endpoint.id,
+            "message": "Test event sent from the webhook settings",
+        });
+        let mut delivery = new_delivery(&endpoint, TEST_EVENT, data, Utc::now());
+        self.webhook_repository.save_delivery(&delivery).await?;
+        self.attempt(&endpoint, &mut delivery, false).await?;
+
+        Ok(Some(convert_delivery_to_dto(&delivery)))
+    }
+
+    /// The user's delivery log, newest first
+    pub async fn get_deliveries(&self, user_id: &str, request: GetDeliveriesRequest) -> Result<GetDeliveriesResponse, String> {
+        if let Some(webhook_id) = &request.webhook_id {
+            if self.find_endpoint(user_id, webhook_id).await?.is_none() {
+                return Err(format!("Webhook not found: {}", webhook_id));
+            }
+        }
+        let limit = request.limit.unwrap_or(DEFAULT_DELIVERIES_LIMIT).clamp(1, MAX_DELIVERIES_LIMIT);
+        let deliveries = self.webhook_repository
+            .find_user_deliveries(user_id, request.webhook_id.as_deref(), limit as usize)
+            .await?;
+
+        Ok(GetDeliveriesResponse {
+            deliveries: deliveries.iter().map(convert_delivery_to_dto).collect(),
+        })
+    }
+
+    /// Send an event to each of the user's endpoints subscribed to it.
+    ///
+    /// Failures are logged rather than returned: the order, fill or alert
+    /// that caused the event has already happened.
+    pub async fn publish(&self, user_id: &str, event: WebhookEvent, data: Value) {
+        let endpoints = match self.webhook_repository.find_user_endpoints(user_id).await {
+            Ok(endpoints) => endpoints,
+            Err(e) => {
+                console_log!("WEBHOOK SERVICE: Failed to load endpoints of {} for {}: {}", user_id, event.as_str(), e);
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        for endpoint in endpoints.iter().filter(|endpoint| endpoint.subscribes_to(event)) {
+            let mut delivery = new_delivery(endpoint, event.as_str(), data.clone(), now);
+            if let Err(e) = self.webhook_repository.save_delivery(&delivery).await {
+                console_log!("WEBHOOK SERVICE: Failed to record {} delivery to {}: {}", event.as_str(), endpoint.id, e);
+                continue;
+            }
+
+            let queued = match &self.queue {
+                Some(queue) => match queue.send(WebhookDeliveryMessage { delivery_id: delivery.id.clone() }).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        console_log!("WEBHOOK SERVICE: Failed to queue delivery {}: {}", delivery.id, e);
+                        false
+                    }
+                },
+                None => false,
+            };
+            if !queued {
+                if let Err(e) = self.attempt(endpoint, &mut delivery, false).await {
+                    console_log!("WEBHOOK SERVICE: Failed to record delivery {}: {}", delivery.id, e);
+                }
+            }
+        }
+    }
+
+    /// Make the next attempt at a queued delivery
+    pub async fn process_delivery(&self, delivery_id: &str) -> Result<DeliveryOutcome, String> {
+        let Some(mut delivery) = self.webhook_repository.find_delivery(delivery_id).await? else {
+            console_log!("WEBHOOK SERVICE: Delivery {} no longer exists", delivery_id);
+            return Ok(DeliveryOutcome::Done);
+        };
+        if delivery.status.is_final() {
+            return Ok(DeliveryOutcome::Done);
+        }
+
+        let endpoint = self.webhook_repository.find_endpoint(&delivery.endpoint_id).await?;
+        let Some(endpoint) = endpoint.filter(|endpoint| endpoint.is_active) else {
+            let now = Utc::now();
+            delivery.status = DeliveryStatus::Failed;
+            delivery.error = Some("Webhook was deleted or disabled".to_string());
+            delivery.next_attempt_at = None;
+            delivery.updated_at = now;
+            self.webhook_repository.update_delivery(&delivery).await?;
+            return Ok(DeliveryOutcome::Done);
+        };
+
+        self.attempt(&endpoint, &mut delivery, true).await
+    }
+
+    async fn find_endpoint(&self, user_id: &str, webhook_id: &str) -> Result<Option<WebhookEndpoint>, String> {
+        let endpoint = self.webhook_repository.find_endpoint(webhook_id).await?;
+        Ok(endpoint.filter(|endpoint| endpoint.user_id == user_id))
+    }
+
+    /// POST a delivery to its endpoint and record the outcome. A failed
+    /// attempt is scheduled for a retry if `retry` is set and attempts
+    /// remain, and marked failed otherwise.
+    async fn attempt(&self, endpoint: &WebhookEndpoint, delivery: &mut WebhookDelivery, retry: bool) -> Result<DeliveryOutcome, String> {
+        let result = send_webhook(&endpoint.url, &endpoint.secret, &delivery.id, &delivery.event, &delivery.payload).await;
+
+        let now = Utc::now();
+        delivery.attempts += 1;
+        delivery.updated_at = now;
+        let error = match result {
+            Ok(status) => {
+                delivery.response_status = Some(status as i64);
+                if (200..300).contains(&status) {
+                    None
+                } else {
+                    Some(format!("Endpoint answered {}", status))
+                }
+            }
+            Err(e) => {
+                delivery.response_status = None;
+                Some(e)
+            }
+        };
+
+        let outcome = match error {
+            None => {
+                delivery.status = DeliveryStatus::Succeeded;
+                delivery.error = None;
+                delivery.next_attempt_at = None;
+                delivery.delivered_at = Some(now);
+                DeliveryOutcome::Done
+            }
+            Some(error) if retry && delivery.attempts < MAX_ATTEMPTS => {
+                let delay = retry_delay_seconds(delivery.attempts);
+                console_log!("WEBHOOK SERVICE: Delivery {} failed ({}), retrying in {}s", delivery.id, error, delay);
+                delivery.status = DeliveryStatus::Retrying;
+                delivery.error = Some(error);
+                delivery.next_attempt_at = Some(now + Duration::seconds(delay as i64));
+                DeliveryOutcome::RetryAfter(delay)
+            }
+            Some(error) => {
+                console_log!("WEBHOOK SERVICE: Delivery {} failed after {} attempts: {}", delivery.id, delivery.attempts, error);
+                delivery.status = DeliveryStatus::Failed;
+                delivery.error = Some(error);
+                delivery.next_attempt_at = None;
+                DeliveryOutcome::Done
+            }
+        };
+        self.webhook_repository.update_delivery(delivery).await?;
+
+        Ok(outcome)
+    }
+}
+
+/// Wait after the given number of failed attempts: 30s, 1m, 2m, 4m and so
+/// on, capped at an hour
+fn retry_delay_seconds(attempts: i64) -> u32 {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    BASE_RETRY_DELAY_SECONDS.saturating_mul(1 << exponent).min(MAX_RETRY_DELAY_SECONDS)
+}
+
+/// A pending delivery of an event to an endpoint. The payload's id is the
+/// delivery's, so receivers can drop retries they've already handled.
+fn new_delivery(endpoint: &WebhookEndpoint, event: &str, data: Value, now: DateTime<Utc>) -> WebhookDelivery {
+    let id = uuid::Uuid::new_v4().to_string();
+    let payload = json!({
+        "id": id,
+        "event": event,
+        "created_at": now,
+        "data": data,
+    });
+    WebhookDelivery {
+        id,
+        endpoint_id: endpoint.id.clone(),
+        user_id: endpoint.user_id.clone(),
+        event: event.to_string(),
+        payload: payload.to_string(),
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        response_status: None,
+        error: None,
+        next_attempt_at: None,
+        delivered_at: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+pub fn convert_endpoint_to_dto(endpoint: &WebhookEndpoint) -> WebhookEndpointDto {
+    WebhookEndpointDto {
+        webhook_id: endpoint.id.clone(),
+        url: endpoint.url.clone(),
+        secret: endpoint.secret.clone(),
+        events: endpoint.events.iter().map(|event| event.as_str().to_string()).collect(),
+        description: endpoint.description.clone(),
+        is_active: endpoint.is_active,
+        created_at: endpoint.created_at,
+        updated_at: endpoint.updated_at,
+    }
+}
+
+pub fn convert_delivery_to_dto(delivery: &WebhookDelivery) -> WebhookDeliveryDto {
+    WebhookDeliveryDto {
+        delivery_id: delivery.id.clone(),
+        webhook_id: delivery.endpoint_id.clone(),
+        event: delivery.event.clone(),
+        payload: serde_json::from_str(&delivery.payload).unwrap_or(Value::Null),
+        status: delivery.status.as_str().to_string(),
+        attempts: delivery.attempts,
+        response_status: delivery.response_status,
+        error: delivery.error.clone(),
+        next_attempt_at: delivery.next_attempt_at,
+        delivered_at: delivery.delivered_at,
+        created_at: delivery.created_at,
+        updated_at: delivery.updated_at,
+    }
+}
@@ -1,8 +1,32 @@
 use crate::repo::user::UserRepository;
+use crate::repo::order::OrderRepository;
+use crate::repo::trade::TradeRepository;
+use crate::repo::transfer::TransferRepository;
+use crate::repo::snapshot::SnapshotRepository;
+use crate::repo::job::JobRepository;
+use crate::repo::candle::CandleRepository;
+use crate::repo::strategy::StrategyRepository;
+use crate::repo::alert::AlertRepository;
+use crate::repo::notification::NotificationRepository;
+use crate::repo::webhook::WebhookRepository;
+use crate::repo::signal::SignalRepository;
+use crate::service::alert::AlertService;
 use crate::service::auth::AuthenticationService;
+use crate::service::backtest::BacktestService;
+use crate::service::candle::CandleService;
 use crate::service::market_data::MarketDataService;
+use crate::service::rate_limit::{RateLimitService, RATE_LIMITS_VAR};
 use crate::service::trading::TradingService;
-use worker::console_log;
+use crate::service::snapshot::SnapshotService;
+use crate::service::scheduler::SchedulerService;
+use crate::service::signal::SignalService;
+use crate::service::strategy_service::StrategyService;
+use crate::service::webhook::{WebhookService, WEBHOOK_QUEUE_BINDING};
+use crate::clients::exchange_governor::{GovernorClient, EXCHANGE_GOVERNOR_BINDING};
+use crate::clients::exchange_policy::{ExchangePolicy, CIRCUIT_BREAKER_BINDING, EXCHANGE_CALL_POLICY_VAR};
+use crate::durable::market_stream::MARKET_STREAM_BINDING;
+use crate::durable::rate_limiter::RATE_LIMITER_BINDING;
+use worker::{console_log, Env};
 
 /// Application state following rusty-worker pattern
 #[derive(Clone)]
@@ -11,18 +35,85 @@ pub struct AppState {
     pub auth_service: AuthenticationService,
     pub market_data_service: MarketDataService,
     pub trading_service: TradingService,
+    pub snapshot_service: SnapshotService,
+    pub candle_service: CandleService,
+    pub backtest_service: BacktestService,
+    pub strategy_service: StrategyService,
+    pub alert_service: AlertService,
+    pub webhook_service: WebhookService,
+    pub signal_service: SignalService,
+    pub rate_limit_service: RateLimitService,
+    pub scheduler_service: SchedulerService,
 }
 
 /// Initialize the application state with LIVE Neon database integration
-pub async fn init_app_state(database_url: String, jwt_secret: String) -> Result<AppState, String> {
+/// from the worker's secrets, bindings and vars. Without a webhook queue,
+/// webhook events are delivered inline, once. The governor and policy apply
+/// to every exchange call the services make.
+pub async fn init_app_state(env: &Env) -> Result<AppState, String> {
+    let jwt_secret = env.secret("JWT_SECRET").map_err(|e| format!("Missing JWT_SECRET: {}", e))?.to_string();
+    let database_url = env.secret("DB_CONNECTION_STRING")
+        .map_err(|e| format!("Missing DB_CONNECTION_STRING: {}", e))?
+        .to_string();
+    let webhook_queue = env.queue(WEBHOOK_QUEUE_BINDING).ok();
+    let governor = GovernorClient::new(env.durable_object(EXCHANGE_GOVERNOR_BINDING).ok());
+    let exchange_policy = ExchangePolicy::new(
+        env.durable_object(CIRCUIT_BREAKER_BINDING).ok(),
+        env.var(EXCHANGE_CALL_POLICY_VAR).ok().map(|var| var.to_string()),
+    );
+
     console_log!("Initializing application state with LIVE Neon database connection");
     console_log!("Database URL: {}", &database_url[..50]); // Show first 50 chars for verification
 
     // Create user repository with LIVE Neon database connection
-    let user_repository = UserRepository::new(database_url);
+    let user_repository = UserRepository::new(database_url.clone());
+    let order_repository = OrderRepository::new(database_url.clone());
+    let trade_repository = TradeRepository::new(database_url.clone());
+    let transfer_repository = TransferRepository::new(database_url.clone());
+    let snapshot_repository = SnapshotRepository::new(database_url.clone());
+    let job_repository = JobRepository::new(database_url.clone());
+    let candle_repository = CandleRepository::new(database_url.clone());
+    let strategy_repository = StrategyRepository::new(database_url.clone());
+    let alert_repository = AlertRepository::new(database_url.clone());
+    let notification_repository = NotificationRepository::new(database_url.clone());
+    let webhook_repository = WebhookRepository::new(database_url.clone());
+    let signal_repository = SignalRepository::new(database_url);
     let auth_service = AuthenticationService::new(jwt_secret);
-    let market_data_service = MarketDataService::new();
-    let trading_service = TradingService::new();
+    let market_data_service = MarketDataService::new()
+        .with_governor(governor.clone())
+        .with_policy(exchange_policy.clone());
+    let snapshot_service = SnapshotService::new(snapshot_repository, order_repository.clone(), transfer_repository.clone());
+    let candle_service = CandleService::new(candle_repository, governor.clone(), exchange_policy.clone());
+    let backtest_service = BacktestService::new(candle_service.clone());
+    let strategy_service = StrategyService::new(strategy_repository);
+    let webhook_service = WebhookService::new(webhook_repository, webhook_queue);
+    let alert_service = AlertService::new(
+        alert_repository,
+        notification_repository,
+        order_repository.clone(),
+        candle_service.clone(),
+        webhook_service.clone(),
+    );
+    let trading_service = TradingService::new(
+        order_repository.clone(),
+        trade_repository,
+        transfer_repository,
+        webhook_service.clone(),
+        governor,
+        exchange_policy,
+    ).with_market_streams(env.durable_object(MARKET_STREAM_BINDING).ok());
+    let signal_service = SignalService::new(signal_repository, order_repository, trading_service.clone());
+    let rate_limit_service = RateLimitService::new(
+        env.durable_object(RATE_LIMITER_BINDING).ok(),
+        env.var(RATE_LIMITS_VAR).ok().map(|var| var.to_string()),
+    );
+    let scheduler_service = SchedulerService::new(
+        job_repository,
+        trading_service.clone(),
+        snapshot_service.clone(),
+        candle_service.clone(),
+        alert_service.clone(),
+    );
 
     console_log!("Application state initialized successfully with LIVE Neon database, market data service, and trading service");
     Ok(AppState {
@@ -30,5 +121,31 @@ pub async fn init_app_state(database_url: String, jwt_secret: String) -> Result<
         auth_service,
         market_data_service,
         trading_service,
+        snapshot_service,
+        candle_service,
+        backtest_service,
+        strategy_service,
+        alert_service,
+        webhook_service,
+        signal_service,
+        rate_limit_service,
+        scheduler_service,
     })
 }
+
+/// Trading service for a Durable Object, on the same bindings and vars as
+/// the worker's own. Its trigger orders are left to the caller to hand to
+/// market streams.
+pub fn init_trading_service(env: &Env, database_url: String) -> TradingService {
+    TradingService::new(
+        OrderRepository::new(database_url.clone()),
+        TradeRepository::new(database_url.clone()),
+        TransferRepository::new(database_url.clone()),
+        WebhookService::new(WebhookRepository::new(database_url), env.queue(WEBHOOK_QUEUE_BINDING).ok()),
+        GovernorClient::new(env.durable_object(EXCHANGE_GOVERNOR_BINDING).ok()),
+        ExchangePolicy::new(
+            env.durable_object(CIRCUIT_BREAKER_BINDING).ok(),
+            env.var(EXCHANGE_CALL_POLICY_VAR).ok().map(|var| var.to_string()),
+        ),
+    )
+}
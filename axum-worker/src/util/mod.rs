@@ -1,2 +1,4 @@
 // Utility functions and helpers
 pub mod neon_client;
+pub mod sql;
+pub mod signing;
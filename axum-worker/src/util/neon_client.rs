@@ -109,6 +109,21 @@ impl NeonClient {
         }
     }
 
+    /// Execute a query and return its result rows
+    pub async fn query_rows(&self, sql: &str) -> Result<Vec<Value>, String> {
+        let result = self.execute_sql(sql).await?;
+
+        Ok(result.get("rows")
+            .and_then(|r| r.as_array())
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Escape a value for use inside a single-quoted SQL string literal
+    pub fn escape(value: &str) -> String {
+        value.replace('\'', "''")
+    }
+
     /// Build API endpoint from connection string
     #[allow(dead_code)]
     fn build_api_endpoint(&self) -> Result<String, String> {
@@ -154,7 +169,7 @@ impl NeonClient {
     /// Find a user by username in the LIVE Neon database
     pub async fn find_user_by_username(&self, username: &str) -> Result<Option<Value>, String> {
         let sql = format!(
-            "SELECT id, username, email, password_hash, created_at FROM users WHERE username = '{}' LIMIT 1",
+            "SELECT id, username, email, password_hash, role, created_at FROM users WHERE username = '{}' LIMIT 1",
             username
         );
 
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::util::neon_client::NeonClient;
+
+/// Read a NUMERIC column, which Neon returns as a string
+pub fn row_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(s) => Decimal::from_str_exact(s).ok(),
+        Value::Number(n) => Decimal::from_str_exact(&n.to_string()).ok(),
+        _ => None,
+    }
+}
+
+/// Read a TIMESTAMPTZ column
+pub fn row_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    value.as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Render an optional string as a quoted SQL literal or NULL
+pub fn sql_optional_text(value: Option<&str>) -> String {
+    value.map_or("NULL".to_string(), |v| format!("'{}'", NeonClient::escape(v)))
+}
+
+/// Render an optional decimal as a SQL literal or NULL
+pub fn sql_optional_decimal(value: Option<Decimal>) -> String {
+    value.map_or("NULL".to_string(), |v| v.to_string())
+}
+
+/// Render an optional timestamp as a quoted SQL literal or NULL
+pub fn sql_optional_timestamp(value: Option<DateTime<Utc>>) -> String {
+    value.map_or("NULL".to_string(), |v| format!("'{}'", v.to_rfc3339()))
+}
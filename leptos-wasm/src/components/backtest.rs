@@ -0,0 +1,348 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use serde::{Deserialize, Serialize};
+
+/// Width and height of the equity chart's SVG view box
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 160.0;
+
+#[derive(Debug, Clone, Serialize)]
+struct RunBacktestRequest {
+    exchange: String,
+    base: String,
+    quote: String,
+    strategy: String,
+    parameters: serde_json::Value,
+    source: String,
+    interval: String,
+    start_time: String,
+    end_time: String,
+    initial_cash: String,
+    slippage_bps: String,
+}
+
+// Decimal fields arrive as strings, so they are shown as sent
+#[derive(Debug, Clone, Deserialize)]
+pub struct BacktestReport {
+    pub strategy: String,
+    pub interval: String,
+    pub events: u32,
+    pub fee_rate: String,
+    pub slippage_bps: String,
+    pub summary: BacktestSummary,
+    pub trades: Vec<BacktestTrade>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BacktestSummary {
+    pub initial_equity: String,
+    pub final_equity: String,
+    pub net_pnl: String,
+    pub return_percentage: String,
+    pub fees_paid: String,
+    pub sharpe_ratio: Option<String>,
+    pub max_drawdown_percentage: String,
+    pub win_rate_percentage: Option<String>,
+    pub total_trades: u32,
+    pub winning_trades: u32,
+    pub losing_trades: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BacktestTrade {
+    pub entry_time: String,
+    pub exit_time: Option<String>,
+    pub quantity: String,
+    pub entry_price: String,
+    pub exit_price: Option<String>,
+    pub fees: String,
+    pub pnl: String,
+    pub return_percentage: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EquityPoint {
+    pub equity: String,
+}
+
+#[component]
+pub fn BacktestDashboard() -> impl IntoView {
+    let (exchange, set_exchange) = signal("binance".to_string());
+    let (base, set_base) = signal("BTC".to_string());
+    let (quote, set_quote) = signal("USDT".to_string());
+    let (strategy, set_strategy) = signal("sma_crossover".to_string());
+    let (fast, set_fast) = signal("10".to_string());
+    let (slow, set_slow) = signal("30".to_string());
+    let (interval, set_interval) = signal("1h".to_string());
+    let (start_date, set_start_date) = signal(String::new());
+    let (end_date, set_end_date) = signal(String::new());
+    let (initial_cash, set_initial_cash) = signal("10000".to_string());
+    let (slippage_bps, set_slippage_bps) = signal("5".to_string());
+    let (report, set_report) = signal(None::<BacktestReport>);
+    let (loading, set_loading) = signal(false);
+    let (error, set_error) = signal(None::<String>);
+
+    let on_submit = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+
+        if start_date.get().is_empty() || end_date.get().is_empty() {
+            set_error.set(Some("Please choose a start and end date".to_string()));
+            return;
+        }
+        let parameters = if strategy.get() == "sma_crossover" {
+            let (Ok(fast), Ok(slow)) = (fast.get().parse::<u32>(), slow.get().parse::<u32>()) else {
+                set_error.set(Some("Moving average lengths must be whole numbers".to_string()));
+                return;
+            };
+            serde_json::json!({ "fast": fast, "slow": slow })
+        } else {
+            serde_json::json!({})
+        };
+
+        let request = RunBacktestRequest {
+            exchange: exchange.get(),
+            base: base.get(),
+            quote: quote.get(),
+            strategy: strategy.get(),
+            parameters,
+            source: "candles".to_string(),
+            interval: interval.get(),
+            start_time: format!("{}T00:00:00Z", start_date.get()),
+            end_time: format!("{}T00:00:00Z", end_date.get()),
+            initial_cash: initial_cash.get(),
+            slippage_bps: slippage_bps.get(),
+        };
+
+        set_loading.set(true);
+        set_error.set(None);
+        spawn_local(async move {
+            match run_backtest(&request).await {
+                Ok(result) => set_report.set(Some(result)),
+                Err(e) => {
+                    set_report.set(None);
+                    set_error.set(Some(e));
+                }
+            }
+            set_loading.set(false);
+        });
+    };
+
+    let input_class = "mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md text-gray-900 focus:outline-none focus:ring-indigo-500 focus:border-indigo-500 sm:text-sm";
+    let label_class = "block text-sm font-medium text-gray-700";
+
+    view! {
+        <div class="bg-white shadow rounded-lg p-6">
+            <h2 class="text-2xl font-bold text-gray-900 mb-6">"Backtest"</h2>
+
+            <form class="grid grid-cols-2 md:grid-cols-4 gap-4 mb-6" on:submit=on_submit>
+                <div>
+                    <label class=label_class>"Exchange"</label>
+                    <select class=input_class on:change=move |ev| set_exchange.set(event_target_value(&ev))>
+                        <option value="binance" selected=true>"Binance"</option>
+                        <option value="coinbase">"Coinbase"</option>
+                        <option value="kraken">"Kraken"</option>
+                        <option value="okx">"OKX"</option>
+                        <option value="bybit">"Bybit"</option>
+                    </select>
+                </div>
+                <div>
+                    <label class=label_class>"Base"</label>
+                    <input type="text" class=input_class prop:value=base
+                        on:input=move |ev| set_base.set(event_target_value(&ev)) />
+                </div>
+                <div>
+                    <label class=label_class>"Quote"</label>
+                    <input type="text" class=input_class prop:value=quote
+                        on:input=move |ev| set_quote.set(event_target_value(&ev)) />
+                </div>
+                <div>
+                    <label class=label_class>"Interval"</label>
+                    <select class=input_class on:change=move |ev| set_interval.set(event_target_value(&ev))>
+                        <option value="1m">"1m"</option>
+                        <option value="5m">"5m"</option>
+                        <option value="15m">"15m"</option>
+                        <option value="1h" selected=true>"1h"</option>
+                        <option value="4h">"4h"</option>
+                        <option value="1d">"1d"</option>
+                    </select>
+                </div>
+                <div>
+                    <label class=label_class>"Strategy"</label>
+                    <select class=input_class on:change=move |ev| set_strategy.set(event_target_value(&ev))>
+                        <option value="sma_crossover" selected=true>"SMA crossover"</option>
+                        <option value="buy_and_hold">"Buy and hold"</option>
+                    </select>
+                </div>
+                <div>
+                    <label class=label_class>"Fast SMA"</label>
+                    <input type="number" min="1" class=input_class prop:value=fast
+                        prop:disabled=move || strategy.get() != "sma_crossover"
+                        on:input=move |ev| set_fast.set(event_target_value(&ev)) />
+                </div>
+                <div>
+                    <label class=label_class>"Slow SMA"</label>
+                    <input type="number" min="2" class=input_class prop:value=slow
+                        prop:disabled=move || strategy.get() != "sma_crossover"
+                        on:input=move |ev| set_slow.set(event_target_value(&ev)) />
+                </div>
+                <div>
+                    <label class=label_class>"Initial cash"</label>
+                    <input type="number" min="0" class=input_class prop:value=initial_cash
+                        on:input=move |ev| set_initial_cash.set(event_target_value(&ev)) />
+                </div>
+                <div>
+                    <label class=label_class>"Start date"</label>
+                    <input type="date" class=input_class prop:value=start_date
+                        on:input=move |ev| set_start_date.set(event_target_value(&ev)) />
+                </div>
+                <div>
+                    <label class=label_class>"End date"</label>
+                    <input type="date" class=input_class prop:value=end_date
+                        on:input=move |ev| set_end_date.set(event_target_value(&ev)) />
+                </div>
+                <div>
+                    <label class=label_class>"Slippage (bps)"</label>
+                    <input type="number" min="0" class=input_class prop:value=slippage_bps
+                        on:input=move |ev| set_slippage_bps.set(event_target_value(&ev)) />
+                </div>
+                <div class="flex items-end">
+                    <button
+                        type="submit"
+                        class="w-full bg-indigo-600 text-white text-sm py-2 px-3 rounded hover:bg-indigo-700 transition-colors disabled:opacity-50"
+                        disabled=move || loading.get()
+                    >
+                        {move || if loading.get() { "Running..." } else { "Run Backtest" }}
+                    </button>
+                </div>
+            </form>
+
+            {move || error.get().map(|error_msg| view! {
+                <div class="bg-red-50 border border-red-200 rounded-md p-3 mb-6">
+                    <p class="text-red-800 text-sm">{error_msg}</p>
+                </div>
+            })}
+
+            {move || report.get().map(|report| view! { <BacktestReportView report=report /> })}
+        </div>
+    }
+}
+
+#[component]
+fn BacktestReportView(report: BacktestReport) -> impl IntoView {
+    let summary = report.summary.clone();
+    let stats = vec![
+        ("Final equity", summary.final_equity),
+        ("Net PnL", summary.net_pnl),
+        ("Return", format!("{}%", summary.return_percentage)),
+        ("Sharpe ratio", summary.sharpe_ratio.unwrap_or_else(|| "-".to_string())),
+        ("Max drawdown", format!("{}%", summary.max_drawdown_percentage)),
+        ("Win rate", summary.win_rate_percentage.map(|rate| format!("{}%", rate)).unwrap_or_else(|| "-".to_string())),
+        ("Trades", format!("{} ({} won, {} lost)", summary.total_trades, summary.winning_trades, summary.losing_trades)),
+        ("Fees paid", summary.fees_paid),
+    ];
+
+    view! {
+        <div>
+            <p class="text-sm text-gray-600 mb-4">
+                {format!(
+                    "{} over {} {} candles from {} starting equity, fee rate {}, slippage {} bps",
+                    report.strategy, report.events, report.interval, summary.initial_equity,
+                    report.fee_rate, report.slippage_bps,
+                )}
+            </p>
+
+            <div class="grid grid-cols-2 md:grid-cols-4 gap-4 mb-6">
+                {stats.into_iter().map(|(label, value)| view! {
+                    <div class="border border-gray-200 rounded-lg p-4">
+                        <p class="text-xs text-gray-500">{label}</p>
+                        <p class="text-lg font-semibold text-gray-900">{value}</p>
+                    </div>
+                }).collect_view()}
+            </div>
+
+            <h3 class="text-lg font-semibold text-gray-800 mb-3">"Equity"</h3>
+            <svg
+                class="w-full h-40 mb-6 bg-gray-50 rounded"
+                viewBox=format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)
+                preserveAspectRatio="none"
+            >
+                <polyline
+                    fill="none"
+                    stroke="#4f46e5"
+                    stroke-width="2"
+                    vector-effect="non-scaling-stroke"
+                    points=equity_polyline(&report.equity_curve)
+                />
+            </svg>
+
+            <h3 class="text-lg font-semibold text-gray-800 mb-3">"Trades"</h3>
+            <div class="overflow-x-auto">
+                <table class="min-w-full text-sm">
+                    <thead>
+                        <tr class="text-left text-gray-500 border-b">
+                            <th class="py-2 pr-4">"Entry"</th>
+                            <th class="py-2 pr-4">"Exit"</th>
+                            <th class="py-2 pr-4">"Quantity"</th>
+                            <th class="py-2 pr-4">"Entry price"</th>
+                            <th class="py-2 pr-4">"Exit price"</th>
+                            <th class="py-2 pr-4">"Fees"</th>
+                            <th class="py-2 pr-4">"PnL"</th>
+                            <th class="py-2 pr-4">"Return"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {report.trades.into_iter().map(|trade| {
+                            let pnl_class = if trade.pnl.starts_with('-') { "py-2 pr-4 text-red-700" } else { "py-2 pr-4 text-green-700" };
+                            view! {
+                                <tr class="border-b border-gray-100">
+                                    <td class="py-2 pr-4">{trade.entry_time}</td>
+                                    <td class="py-2 pr-4">{trade.exit_time.unwrap_or_else(|| "Open".to_string())}</td>
+                                    <td class="py-2 pr-4">{trade.quantity}</td>
+                                    <td class="py-2 pr-4">{trade.entry_price}</td>
+                                    <td class="py-2 pr-4">{trade.exit_price.unwrap_or_else(|| "-".to_string())}</td>
+                                    <td class="py-2 pr-4">{trade.fees}</td>
+                                    <td class=pnl_class>{trade.pnl}</td>
+                                    <td class=pnl_class>{format!("{}%", trade.return_percentage)}</td>
+                                </tr>
+                            }
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+/// SVG polyline points for the equity curve, scaled to fill the chart
+fn equity_polyline(points: &[EquityPoint]) -> String {
+    let values: Vec<f64> = points.iter().filter_map(|point| point.equity.parse().ok()).collect();
+    let (min, max) = values.iter().fold((f64::MAX, f64::MIN), |(min, max), value| (min.min(*value), max.max(*value)));
+    let range = if max > min { max - min } else { 1.0 };
+    let step = if values.len() > 1 { CHART_WIDTH / (values.len() - 1) as f64 } else { 0.0 };
+
+    values.iter().enumerate()
+        .map(|(i, value)| format!("{:.1},{:.1}", i as f64 * step, CHART_HEIGHT - (value - min) / range * CHART_HEIGHT))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// API functions
+async fn run_backtest(request: &RunBacktestRequest) -> Result<BacktestReport, String> {
+    let response = gloo_net::http::Request::post("/api/backtest")
+        .json(request)
+        .map_err(|e| format!("Failed to serialize request: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if response.ok() {
+        response
+            .json::<BacktestReport>()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    } else {
+        let message = response.text().await.unwrap_or_default();
+        Err(if message.is_empty() { format!("HTTP error: {}", response.status()) } else { message })
+    }
+}
@@ -2,3 +2,4 @@ pub mod counter_btn;
 pub mod login;
 pub mod navbar;
 pub mod market_data;
+pub mod backtest;
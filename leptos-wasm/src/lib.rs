@@ -11,6 +11,7 @@ mod pages;
 use crate::pages::home::Home;
 use crate::pages::not_found::NotFound;
 use crate::components::navbar::Navbar;
+use crate::components::backtest::BacktestDashboard;
 use crate::components::login::{LoginForm, RegisterForm};
 use crate::auth::{provide_auth_context, AuthContext, AuthState};
 
@@ -23,6 +24,9 @@ fn Dashboard() -> impl IntoView {
             <div class="bg-white shadow-lg rounded-lg p-6">
                 <p class="text-gray-700">"Welcome to your dashboard! This is a protected page that requires authentication."</p>
             </div>
+            <div class="mt-6">
+                <BacktestDashboard />
+            </div>
         </div>
     }
 }